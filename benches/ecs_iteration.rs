@@ -0,0 +1,75 @@
+//! Times the movers scene over a million entities, next to the same update
+//! on a `HashMap` per component.
+//!
+//! Run with `cargo bench --bench ecs_iteration`, optionally followed by
+//! `-- <entities>`.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use nyanko_engine::benchmark::{BenchMover, BenchScene, Benchmark};
+use nyanko_engine::ecs::{Entity, Schedule, World};
+use nyanko_engine::scene::Transform;
+
+const FRAMES: u32 = 120;
+const WARMUP: u32 = 10;
+const STEP: f32 = 1.0 / 60.0;
+/// Half the width of the box movers wrap around in.
+const BOX: f32 = 20.0;
+
+fn main() {
+    let entities = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.replace('_', "").parse().ok())
+        .unwrap_or(1_000_000);
+
+    let scene = BenchScene::new().with_movers(entities);
+    let mut world = World::new();
+    let mut schedule = Schedule::new();
+    scene.spawn(&mut world);
+    scene.add_systems(&mut schedule);
+
+    // The same components keyed by entity, moved the same way.
+    let movers: HashMap<Entity, BenchMover> = world
+        .query::<BenchMover>()
+        .iter()
+        .map(|(entity, mover)| (entity, *mover))
+        .collect();
+    let mut transforms: HashMap<Entity, Transform> = world
+        .query::<Transform>()
+        .iter()
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+
+    let report = Benchmark::new("movers", FRAMES)
+        .with_warmup(WARMUP)
+        .with_step(STEP)
+        .run_headless(&mut world, &mut schedule);
+    let summary = report.summary();
+    println!(
+        "sparse sets: {} entities, {:.2} ms average, {:.2} ms median, {:.2} ms p95",
+        report.entities, summary.average_ms, summary.median_ms, summary.p95_ms
+    );
+
+    let mut total_ms = 0.0;
+    for frame in 0..WARMUP + FRAMES {
+        let start = Instant::now();
+        for (entity, mover) in &movers {
+            if let Some(transform) = transforms.get_mut(entity) {
+                let position = &mut transform.translation;
+                *position += mover.velocity * STEP;
+                for axis in 0..3 {
+                    position[axis] = (position[axis] + BOX).rem_euclid(BOX * 2.0) - BOX;
+                }
+            }
+        }
+        if frame >= WARMUP {
+            total_ms += start.elapsed().as_secs_f32() * 1000.0;
+        }
+    }
+    println!(
+        "hash maps:   {} entities, {:.2} ms average",
+        movers.len(),
+        total_ms / FRAMES as f32
+    );
+}
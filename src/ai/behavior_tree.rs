@@ -0,0 +1,694 @@
+use std::any::Any;
+use std::collections::HashMap;
+
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+
+/// The result of ticking a behavior.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Status {
+    Success,
+    Failure,
+    /// Not finished yet; the behavior continues on the next tick.
+    Running,
+}
+
+/// # Blackboard
+///
+/// Named values shared by every node of a behavior tree, such as the current
+/// target or the last known player position.
+///
+/// ## Example
+/// ```ignore
+/// blackboard.set("target", player);
+/// if let Some(target) = blackboard.get::<Entity>("target") {
+///     // ...
+/// }
+/// ```
+#[derive(Default)]
+pub struct Blackboard {
+    values: HashMap<String, Box<dyn Any + Send + Sync>>,
+}
+
+impl Blackboard {
+    /// Creates an empty blackboard.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a value, replacing any previous value under the same key.
+    pub fn set<T: Any + Send + Sync>(&mut self, key: &str, value: T) {
+        self.values.insert(key.to_string(), Box::new(value));
+    }
+
+    /// Returns a value if it exists and has type `T`.
+    pub fn get<T: Any>(&self, key: &str) -> Option<&T> {
+        self.values.get(key)?.downcast_ref()
+    }
+
+    /// Returns a mutable value if it exists and has type `T`.
+    pub fn get_mut<T: Any>(&mut self, key: &str) -> Option<&mut T> {
+        self.values.get_mut(key)?.downcast_mut()
+    }
+
+    /// Removes a value, returning it if it had type `T`.
+    pub fn remove<T: Any>(&mut self, key: &str) -> Option<T> {
+        self.values
+            .remove(key)?
+            .downcast()
+            .ok()
+            .map(|value: Box<T>| *value)
+    }
+
+    /// Checks if a key has a value.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Removes every value.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// Everything a behavior can see while it ticks.
+pub struct BehaviorContext<'a> {
+    pub world: &'a World,
+    /// The entity the tree belongs to.
+    pub entity: Entity,
+    pub blackboard: &'a mut Blackboard,
+    pub delta_time: f32,
+}
+
+/// # Behavior
+///
+/// A node of a behavior tree. Implement it for custom leaves or decorators the
+/// builder doesn't cover.
+pub trait Behavior: Send + Sync {
+    /// Name shown when debugging a tree.
+    fn name(&self) -> &str;
+
+    /// Advances the behavior by one tick.
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status;
+
+    /// Forgets any progress, e.g. when a parent is interrupted.
+    fn reset(&mut self) {}
+}
+
+/// Runs children in order until one fails. Resumes at a running child.
+pub struct Sequence {
+    children: Vec<Box<dyn Behavior>>,
+    current: usize,
+}
+
+impl Behavior for Sequence {
+    fn name(&self) -> &str {
+        "Sequence"
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        while let Some(child) = self.children.get_mut(self.current) {
+            match child.tick(context) {
+                Status::Success => self.current += 1,
+                Status::Running => return Status::Running,
+                Status::Failure => {
+                    self.reset();
+                    return Status::Failure;
+                }
+            }
+        }
+        self.reset();
+        Status::Success
+    }
+
+    fn reset(&mut self) {
+        for child in &mut self.children {
+            child.reset();
+        }
+        self.current = 0;
+    }
+}
+
+/// Runs children in order until one succeeds. Resumes at a running child.
+pub struct Selector {
+    children: Vec<Box<dyn Behavior>>,
+    current: usize,
+}
+
+impl Behavior for Selector {
+    fn name(&self) -> &str {
+        "Selector"
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        while let Some(child) = self.children.get_mut(self.current) {
+            match child.tick(context) {
+                Status::Failure => self.current += 1,
+                Status::Running => return Status::Running,
+                Status::Success => {
+                    self.reset();
+                    return Status::Success;
+                }
+            }
+        }
+        self.reset();
+        Status::Failure
+    }
+
+    fn reset(&mut self) {
+        for child in &mut self.children {
+            child.reset();
+        }
+        self.current = 0;
+    }
+}
+
+/// Ticks every unfinished child each tick. Succeeds once `required` children
+/// succeed and fails once that is no longer possible.
+pub struct Parallel {
+    children: Vec<Box<dyn Behavior>>,
+    results: Vec<Option<Status>>,
+    required: usize,
+}
+
+impl Behavior for Parallel {
+    fn name(&self) -> &str {
+        "Parallel"
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        self.results.resize(self.children.len(), None);
+        for (child, result) in self.children.iter_mut().zip(&mut self.results) {
+            if result.is_none() {
+                match child.tick(context) {
+                    Status::Running => {}
+                    status => *result = Some(status),
+                }
+            }
+        }
+
+        let successes = self
+            .results
+            .iter()
+            .filter(|r| **r == Some(Status::Success))
+            .count();
+        let failures = self
+            .results
+            .iter()
+            .filter(|r| **r == Some(Status::Failure))
+            .count();
+        let status = if successes >= self.required {
+            Status::Success
+        } else if self.children.len() - failures < self.required {
+            Status::Failure
+        } else {
+            return Status::Running;
+        };
+        self.reset();
+        status
+    }
+
+    fn reset(&mut self) {
+        for child in &mut self.children {
+            child.reset();
+        }
+        self.results.clear();
+    }
+}
+
+/// What a [`Decorator`] does with its child.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecoratorKind {
+    /// Swaps success and failure.
+    Inverter,
+    /// Reports success whenever the child finishes.
+    AlwaysSucceed,
+    /// Reports failure whenever the child finishes.
+    AlwaysFail,
+    /// Runs the child again after it succeeds, `None` times meaning forever.
+    /// Fails as soon as the child fails.
+    Repeat(Option<u32>),
+    /// Runs the child again after it fails, up to this many attempts.
+    Retry(u32),
+    /// Fails without ticking the child until this many seconds passed since it
+    /// last finished.
+    Cooldown(f32),
+    /// Fails if the child is still running after this many seconds.
+    Timeout(f32),
+}
+
+/// Modifies the result or timing of a single child.
+pub struct Decorator {
+    kind: DecoratorKind,
+    child: Box<dyn Behavior>,
+    count: u32,
+    timer: f32,
+    cooling_down: bool,
+}
+
+impl Behavior for Decorator {
+    fn name(&self) -> &str {
+        match self.kind {
+            DecoratorKind::Inverter => "Inverter",
+            DecoratorKind::AlwaysSucceed => "AlwaysSucceed",
+            DecoratorKind::AlwaysFail => "AlwaysFail",
+            DecoratorKind::Repeat(_) => "Repeat",
+            DecoratorKind::Retry(_) => "Retry",
+            DecoratorKind::Cooldown(_) => "Cooldown",
+            DecoratorKind::Timeout(_) => "Timeout",
+        }
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        match self.kind {
+            DecoratorKind::Inverter => match self.child.tick(context) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            DecoratorKind::AlwaysSucceed => match self.child.tick(context) {
+                Status::Running => Status::Running,
+                _ => Status::Success,
+            },
+            DecoratorKind::AlwaysFail => match self.child.tick(context) {
+                Status::Running => Status::Running,
+                _ => Status::Failure,
+            },
+            DecoratorKind::Repeat(times) => match self.child.tick(context) {
+                Status::Success => {
+                    self.count += 1;
+                    self.child.reset();
+                    if times.is_some_and(|times| self.count >= times) {
+                        self.count = 0;
+                        Status::Success
+                    } else {
+                        Status::Running
+                    }
+                }
+                Status::Failure => {
+                    self.count = 0;
+                    Status::Failure
+                }
+                Status::Running => Status::Running,
+            },
+            DecoratorKind::Retry(attempts) => match self.child.tick(context) {
+                Status::Failure => {
+                    self.count += 1;
+                    self.child.reset();
+                    if self.count >= attempts {
+                        self.count = 0;
+                        Status::Failure
+                    } else {
+                        Status::Running
+                    }
+                }
+                Status::Success => {
+                    self.count = 0;
+                    Status::Success
+                }
+                Status::Running => Status::Running,
+            },
+            DecoratorKind::Cooldown(seconds) => {
+                if self.cooling_down {
+                    self.timer += context.delta_time;
+                    if self.timer < seconds {
+                        return Status::Failure;
+                    }
+                    self.cooling_down = false;
+                }
+                let status = self.child.tick(context);
+                if status != Status::Running {
+                    self.cooling_down = true;
+                    self.timer = 0.0;
+                }
+                status
+            }
+            DecoratorKind::Timeout(seconds) => {
+                self.timer += context.delta_time;
+                let status = self.child.tick(context);
+                if status != Status::Running {
+                    self.timer = 0.0;
+                    status
+                } else if self.timer >= seconds {
+                    self.child.reset();
+                    self.timer = 0.0;
+                    Status::Failure
+                } else {
+                    Status::Running
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+        self.count = 0;
+        if !matches!(self.kind, DecoratorKind::Cooldown(_)) {
+            self.timer = 0.0;
+        }
+    }
+}
+
+type ActionFn = Box<dyn FnMut(&mut BehaviorContext) -> Status + Send + Sync>;
+type ConditionFn = Box<dyn Fn(&BehaviorContext) -> bool + Send + Sync>;
+
+/// A leaf that runs a closure.
+pub struct Action {
+    name: String,
+    action: ActionFn,
+}
+
+impl Behavior for Action {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        (self.action)(context)
+    }
+}
+
+/// A leaf that succeeds when a check passes and fails otherwise.
+pub struct Condition {
+    name: String,
+    condition: ConditionFn,
+}
+
+impl Behavior for Condition {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        if (self.condition)(context) {
+            Status::Success
+        } else {
+            Status::Failure
+        }
+    }
+}
+
+/// A leaf that keeps running for a number of seconds, then succeeds.
+pub struct Wait {
+    seconds: f32,
+    elapsed: f32,
+}
+
+impl Behavior for Wait {
+    fn name(&self) -> &str {
+        "Wait"
+    }
+
+    fn tick(&mut self, context: &mut BehaviorContext) -> Status {
+        self.elapsed += context.delta_time;
+        if self.elapsed >= self.seconds {
+            self.elapsed = 0.0;
+            Status::Success
+        } else {
+            Status::Running
+        }
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+/// A node waiting for its children inside the builder.
+enum Frame {
+    Sequence(Vec<Box<dyn Behavior>>),
+    Selector(Vec<Box<dyn Behavior>>),
+    Parallel(Vec<Box<dyn Behavior>>, usize),
+    Decorator(DecoratorKind),
+}
+
+/// # Behavior Tree Builder
+///
+/// Composes a tree declaratively. Composites (`sequence`, `selector`,
+/// `parallel`) collect the nodes added after them until the matching `end`;
+/// decorators wrap the next single node.
+///
+/// ## Example
+/// ```ignore
+/// let tree = BehaviorTree::builder()
+///     .selector()
+///         .sequence()
+///             .condition("sees player", |ctx| ctx.blackboard.contains("target"))
+///             .cooldown(1.5)
+///             .action("attack", attack)
+///         .end()
+///         .sequence()
+///             .action("pick patrol point", pick_patrol_point)
+///             .timeout(10.0)
+///             .action("walk to patrol point", walk)
+///             .wait(2.0)
+///         .end()
+///     .end()
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct BehaviorTreeBuilder {
+    stack: Vec<Frame>,
+    root: Option<Box<dyn Behavior>>,
+}
+
+impl BehaviorTreeBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a finished node to the innermost open composite, closing any
+    /// decorators waiting for it.
+    fn push(mut self, mut node: Box<dyn Behavior>) -> Self {
+        loop {
+            match self.stack.last_mut() {
+                Some(Frame::Sequence(children))
+                | Some(Frame::Selector(children))
+                | Some(Frame::Parallel(children, _)) => {
+                    children.push(node);
+                    return self;
+                }
+                Some(Frame::Decorator(kind)) => {
+                    let kind = *kind;
+                    self.stack.pop();
+                    node = Box::new(Decorator {
+                        kind,
+                        child: node,
+                        count: 0,
+                        timer: 0.0,
+                        cooling_down: false,
+                    });
+                }
+                None => {
+                    assert!(self.root.is_none(), "Behavior tree already has a root node");
+                    self.root = Some(node);
+                    return self;
+                }
+            }
+        }
+    }
+
+    /// Opens a sequence.
+    pub fn sequence(mut self) -> Self {
+        self.stack.push(Frame::Sequence(Vec::new()));
+        self
+    }
+
+    /// Opens a selector.
+    pub fn selector(mut self) -> Self {
+        self.stack.push(Frame::Selector(Vec::new()));
+        self
+    }
+
+    /// Opens a parallel node that succeeds once `required` children succeed.
+    pub fn parallel(mut self, required: usize) -> Self {
+        self.stack.push(Frame::Parallel(Vec::new(), required));
+        self
+    }
+
+    /// Closes the innermost composite.
+    pub fn end(mut self) -> Self {
+        let node: Box<dyn Behavior> = match self.stack.pop() {
+            Some(Frame::Sequence(children)) => Box::new(Sequence {
+                children,
+                current: 0,
+            }),
+            Some(Frame::Selector(children)) => Box::new(Selector {
+                children,
+                current: 0,
+            }),
+            Some(Frame::Parallel(children, required)) => Box::new(Parallel {
+                children,
+                results: Vec::new(),
+                required,
+            }),
+            Some(Frame::Decorator(_)) => panic!("Behavior tree decorator closed without a child"),
+            None => panic!("Behavior tree `end` without an open composite"),
+        };
+        self.push(node)
+    }
+
+    /// Wraps the next node in a decorator.
+    pub fn decorate(mut self, kind: DecoratorKind) -> Self {
+        self.stack.push(Frame::Decorator(kind));
+        self
+    }
+
+    /// Swaps success and failure of the next node.
+    pub fn inverter(self) -> Self {
+        self.decorate(DecoratorKind::Inverter)
+    }
+
+    /// Makes the next node always succeed once finished.
+    pub fn always_succeed(self) -> Self {
+        self.decorate(DecoratorKind::AlwaysSucceed)
+    }
+
+    /// Repeats the next node `times` times, or forever for `None`.
+    pub fn repeat(self, times: Option<u32>) -> Self {
+        self.decorate(DecoratorKind::Repeat(times))
+    }
+
+    /// Retries the next node until it succeeds, up to `attempts` times.
+    pub fn retry(self, attempts: u32) -> Self {
+        self.decorate(DecoratorKind::Retry(attempts))
+    }
+
+    /// Stops the next node from running again until `seconds` passed.
+    pub fn cooldown(self, seconds: f32) -> Self {
+        self.decorate(DecoratorKind::Cooldown(seconds))
+    }
+
+    /// Fails the next node if it runs longer than `seconds`.
+    pub fn timeout(self, seconds: f32) -> Self {
+        self.decorate(DecoratorKind::Timeout(seconds))
+    }
+
+    /// Adds an action leaf.
+    pub fn action<F>(self, name: &str, action: F) -> Self
+    where
+        F: FnMut(&mut BehaviorContext) -> Status + Send + Sync + 'static,
+    {
+        self.push(Box::new(Action {
+            name: name.to_string(),
+            action: Box::new(action),
+        }))
+    }
+
+    /// Adds a condition leaf.
+    pub fn condition<F>(self, name: &str, condition: F) -> Self
+    where
+        F: Fn(&BehaviorContext) -> bool + Send + Sync + 'static,
+    {
+        self.push(Box::new(Condition {
+            name: name.to_string(),
+            condition: Box::new(condition),
+        }))
+    }
+
+    /// Adds a leaf that waits for a number of seconds.
+    pub fn wait(self, seconds: f32) -> Self {
+        self.push(Box::new(Wait {
+            seconds,
+            elapsed: 0.0,
+        }))
+    }
+
+    /// Adds a custom node.
+    pub fn node(self, node: impl Behavior + 'static) -> Self {
+        self.push(Box::new(node))
+    }
+
+    /// Finishes the tree. Panics if a composite is still open or nothing was added.
+    pub fn build(self) -> BehaviorTree {
+        assert!(
+            self.stack.is_empty(),
+            "Behavior tree has {} unclosed node(s)",
+            self.stack.len()
+        );
+        BehaviorTree::new(self.root.expect("Behavior tree has no nodes"))
+    }
+}
+
+/// # Behavior Tree
+///
+/// A component holding an NPC's behavior tree and blackboard. The tree is
+/// ticked once per update by [`behavior_tree_system`] and starts over after its
+/// root finishes.
+///
+/// Actions get the whole `World` and may lock any component storage except
+/// `BehaviorTree` itself, which is locked while trees tick.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(guard, guard_tree());
+/// schedule.add_system(behavior_tree_system(
+///     Access::new().write::<Transform>().read::<Health>(),
+/// ));
+/// ```
+pub struct BehaviorTree {
+    root: Box<dyn Behavior>,
+    pub blackboard: Blackboard,
+    status: Option<Status>,
+}
+
+impl BehaviorTree {
+    /// Creates a tree from its root node.
+    pub fn new(root: Box<dyn Behavior>) -> Self {
+        Self {
+            root,
+            blackboard: Blackboard::new(),
+            status: None,
+        }
+    }
+
+    /// Starts building a tree.
+    pub fn builder() -> BehaviorTreeBuilder {
+        BehaviorTreeBuilder::new()
+    }
+
+    /// Returns the status of the last tick.
+    pub fn status(&self) -> Option<Status> {
+        self.status
+    }
+
+    /// Ticks the tree for an entity.
+    pub fn tick(&mut self, world: &World, entity: Entity, delta_time: f32) -> Status {
+        let mut context = BehaviorContext {
+            world,
+            entity,
+            blackboard: &mut self.blackboard,
+            delta_time,
+        };
+        let status = self.root.tick(&mut context);
+        if status != Status::Running {
+            self.root.reset();
+        }
+        self.status = Some(status);
+        status
+    }
+
+    /// Interrupts the tree so the next tick starts from the root.
+    pub fn reset(&mut self) {
+        self.root.reset();
+        self.status = None;
+    }
+}
+
+/// Returns a system that ticks every [`BehaviorTree`]. `access` should declare
+/// the components the trees' actions read and write.
+pub fn behavior_tree_system(access: Access) -> impl System {
+    FunctionSystem::new(
+        "behavior_trees",
+        access.write::<BehaviorTree>(),
+        tick_behavior_trees,
+    )
+}
+
+/// Ticks every behavior tree once.
+pub fn tick_behavior_trees(world: &World) {
+    let delta_time = world.time().delta();
+    for (entity, tree) in world.write::<BehaviorTree>().iter_mut() {
+        tree.tick(world, *entity, delta_time);
+    }
+}
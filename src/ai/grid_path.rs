@@ -0,0 +1,716 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f32::consts::SQRT_2;
+
+use crate::math::*;
+
+/// A cell coordinate on a [`NavGrid`].
+pub type GridPoint = (i32, i32);
+
+/// Which diagonal steps paths may take.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiagonalMovement {
+    /// Only horizontal and vertical steps.
+    Never,
+    /// Diagonal steps when both cells beside the step are walkable, so paths
+    /// never cut the corner of a wall.
+    #[default]
+    IfNoObstacles,
+}
+
+/// An open-set entry for the grid searches.
+struct Candidate {
+    cost: f32,
+    index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Search bookkeeping shared by A* and jump point search.
+struct Search {
+    costs: Vec<f32>,
+    came_from: Vec<usize>,
+    closed: Vec<bool>,
+    open: BinaryHeap<Candidate>,
+}
+
+impl Search {
+    fn new(cells: usize, start: usize, estimate: f32) -> Self {
+        let mut costs = vec![f32::INFINITY; cells];
+        costs[start] = 0.0;
+        Self {
+            costs,
+            came_from: vec![usize::MAX; cells],
+            closed: vec![false; cells],
+            open: BinaryHeap::from([Candidate {
+                cost: estimate,
+                index: start,
+            }]),
+        }
+    }
+
+    /// Records a cheaper route to `index`, if it is one.
+    fn relax(&mut self, from: usize, index: usize, cost: f32, estimate: f32) {
+        if !self.closed[index] && cost < self.costs[index] {
+            self.costs[index] = cost;
+            self.came_from[index] = from;
+            self.open.push(Candidate {
+                cost: cost + estimate,
+                index,
+            });
+        }
+    }
+
+    /// Pops the cheapest unvisited cell.
+    fn next(&mut self) -> Option<usize> {
+        while let Some(Candidate { index, .. }) = self.open.pop() {
+            if !self.closed[index] {
+                self.closed[index] = true;
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    /// Walks back from `goal` to the start.
+    fn path(&self, goal: usize) -> Vec<usize> {
+        let mut path = vec![goal];
+        let mut current = goal;
+        while self.came_from[current] != usize::MAX {
+            current = self.came_from[current];
+            path.push(current);
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// # Nav Grid
+///
+/// A walkability grid for 2D and tilemap games. Each cell has a movement cost
+/// (`1.0` for plain ground, higher for mud or water) or is blocked. Paths can be
+/// found with A*, which respects costs, or with jump point search, which skips
+/// across open areas and is much faster on large uniform maps.
+///
+/// Cells can change at any time for doors, destructible walls or moving
+/// obstacles; [`NavGrid::revision`] changes with them so cached paths know
+/// when to re-check themselves with [`NavGrid::is_path_clear`].
+///
+/// ## Example
+/// ```ignore
+/// let mut grid = NavGrid::from_tiles(map.width, map.height, &map.collision, |tile| *tile != 0)
+///     .with_cell_size(32.0);
+///
+/// grid.set_blocked(door.x, door.y, true);
+/// if let Some(path) = grid.find_world_path(enemy_position, player_position) {
+///     enemy.follow(path);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavGrid {
+    width: i32,
+    height: i32,
+    costs: Vec<f32>,
+    revision: u64,
+    pub diagonal: DiagonalMovement,
+    /// World position of the top-left corner of cell `(0, 0)`.
+    pub origin: Vec2,
+    /// World size of a cell.
+    pub cell_size: f32,
+}
+
+impl NavGrid {
+    /// Creates a grid where every cell is walkable with cost `1.0`.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width as i32,
+            height: height as i32,
+            costs: vec![1.0; (width * height) as usize],
+            revision: 0,
+            diagonal: DiagonalMovement::default(),
+            origin: vec2(0.0, 0.0),
+            cell_size: 1.0,
+        }
+    }
+
+    /// Creates a grid from a tilemap collision layer in row-major order, blocking
+    /// every tile for which `is_solid` returns `true`.
+    pub fn from_tiles<T>(
+        width: u32,
+        height: u32,
+        tiles: &[T],
+        is_solid: impl Fn(&T) -> bool,
+    ) -> Self {
+        let mut grid = Self::new(width, height);
+        for (cost, tile) in grid.costs.iter_mut().zip(tiles) {
+            if is_solid(tile) {
+                *cost = f32::INFINITY;
+            }
+        }
+        grid
+    }
+
+    /// Sets which diagonal steps paths may take.
+    pub fn with_diagonal(mut self, diagonal: DiagonalMovement) -> Self {
+        self.diagonal = diagonal;
+        self
+    }
+
+    /// Sets the world size of a cell.
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Sets the world position of the grid's top-left corner.
+    pub fn with_origin(mut self, origin: Vec2) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Returns the width in cells.
+    pub fn width(&self) -> u32 {
+        self.width as u32
+    }
+
+    /// Returns the height in cells.
+    pub fn height(&self) -> u32 {
+        self.height as u32
+    }
+
+    /// Returns a counter that changes whenever a cell changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Checks if a cell is inside the grid.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    fn point(&self, index: usize) -> GridPoint {
+        (index as i32 % self.width, index as i32 / self.width)
+    }
+
+    /// Checks if a cell is inside the grid and not blocked.
+    pub fn is_walkable(&self, x: i32, y: i32) -> bool {
+        self.contains(x, y) && self.costs[self.index(x, y)].is_finite()
+    }
+
+    /// Returns the cost of entering a cell, or `None` if it is blocked or outside the grid.
+    pub fn cost(&self, x: i32, y: i32) -> Option<f32> {
+        self.is_walkable(x, y).then(|| self.costs[self.index(x, y)])
+    }
+
+    /// Sets the cost of entering a cell. Costs below `1.0` are raised to `1.0`
+    /// so the search heuristic stays exact; `f32::INFINITY` blocks the cell.
+    pub fn set_cost(&mut self, x: i32, y: i32, cost: f32) {
+        if self.contains(x, y) {
+            let index = self.index(x, y);
+            self.costs[index] = cost.max(1.0);
+            self.revision += 1;
+        }
+    }
+
+    /// Blocks or unblocks a cell. Unblocked cells get cost `1.0`.
+    pub fn set_blocked(&mut self, x: i32, y: i32, blocked: bool) {
+        self.set_cost(x, y, if blocked { f32::INFINITY } else { 1.0 });
+    }
+
+    /// Sets the cost of every cell in a rectangle, clipped to the grid.
+    pub fn fill(&mut self, x: i32, y: i32, width: u32, height: u32, cost: f32) {
+        for cy in y.max(0)..(y + height as i32).min(self.height) {
+            for cx in x.max(0)..(x + width as i32).min(self.width) {
+                let index = self.index(cx, cy);
+                self.costs[index] = cost.max(1.0);
+            }
+        }
+        self.revision += 1;
+    }
+
+    /// Returns the cell containing a world position.
+    pub fn world_to_cell(&self, position: Vec2) -> GridPoint {
+        let local = (position - self.origin) / self.cell_size;
+        (local.x.floor() as i32, local.y.floor() as i32)
+    }
+
+    /// Returns the world position of a cell's center.
+    pub fn cell_to_world(&self, (x, y): GridPoint) -> Vec2 {
+        self.origin + vec2(x as f32 + 0.5, y as f32 + 0.5) * self.cell_size
+    }
+
+    /// Estimates the cost between two cells.
+    fn heuristic(&self, (ax, ay): GridPoint, (bx, by): GridPoint) -> f32 {
+        let (dx, dy) = ((ax - bx).abs() as f32, (ay - by).abs() as f32);
+        match self.diagonal {
+            DiagonalMovement::Never => dx + dy,
+            DiagonalMovement::IfNoObstacles => dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy),
+        }
+    }
+
+    /// Calls `visit` for every cell reachable in one step, with the step length.
+    fn for_each_neighbor(&self, (x, y): GridPoint, mut visit: impl FnMut(GridPoint, f32)) {
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            if self.is_walkable(x + dx, y + dy) {
+                visit((x + dx, y + dy), 1.0);
+            }
+        }
+        if self.diagonal == DiagonalMovement::Never {
+            return;
+        }
+        for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            if self.is_walkable(x + dx, y + dy)
+                && self.is_walkable(x + dx, y)
+                && self.is_walkable(x, y + dy)
+            {
+                visit((x + dx, y + dy), SQRT_2);
+            }
+        }
+    }
+
+    /// Finds the cheapest path between two cells with A*, respecting cell
+    /// costs. Returns every cell along the way, including both ends.
+    pub fn find_path(&self, start: GridPoint, goal: GridPoint) -> Option<Vec<GridPoint>> {
+        if !self.is_walkable(start.0, start.1) || !self.is_walkable(goal.0, goal.1) {
+            return None;
+        }
+
+        let goal_index = self.index(goal.0, goal.1);
+        let mut search = Search::new(
+            self.costs.len(),
+            self.index(start.0, start.1),
+            self.heuristic(start, goal),
+        );
+        while let Some(index) = search.next() {
+            if index == goal_index {
+                return Some(
+                    search
+                        .path(goal_index)
+                        .into_iter()
+                        .map(|i| self.point(i))
+                        .collect(),
+                );
+            }
+            let point = self.point(index);
+            let cost = search.costs[index];
+            self.for_each_neighbor(point, |next, step| {
+                let next_index = self.index(next.0, next.1);
+                let next_cost = cost + step * self.costs[next_index];
+                search.relax(index, next_index, next_cost, self.heuristic(next, goal));
+            });
+        }
+        None
+    }
+
+    /// Finds the shortest path between two cells with jump point search, which
+    /// expands far fewer cells than A* on open maps. Cell costs are ignored;
+    /// every walkable cell counts as plain ground. Falls back to A* when
+    /// diagonal movement is disabled.
+    ///
+    /// Returns only the turning points; use [`NavGrid::expand_path`] for every cell.
+    pub fn find_path_jps(&self, start: GridPoint, goal: GridPoint) -> Option<Vec<GridPoint>> {
+        if self.diagonal == DiagonalMovement::Never {
+            return self.find_path(start, goal).map(|path| simplify_path(&path));
+        }
+        if !self.is_walkable(start.0, start.1) || !self.is_walkable(goal.0, goal.1) {
+            return None;
+        }
+
+        let start_index = self.index(start.0, start.1);
+        let goal_index = self.index(goal.0, goal.1);
+        let mut search = Search::new(self.costs.len(), start_index, self.heuristic(start, goal));
+        let mut successors = Vec::new();
+        while let Some(index) = search.next() {
+            if index == goal_index {
+                return Some(
+                    search
+                        .path(goal_index)
+                        .into_iter()
+                        .map(|i| self.point(i))
+                        .collect(),
+                );
+            }
+
+            let point = self.point(index);
+            let parent = search.came_from[index];
+            let parent = (parent != usize::MAX).then(|| self.point(parent));
+            successors.clear();
+            self.pruned_neighbors(point, parent, &mut successors);
+            for &neighbor in &successors {
+                let direction = (
+                    (neighbor.0 - point.0).signum(),
+                    (neighbor.1 - point.1).signum(),
+                );
+                let Some(jump_point) = self.jump(neighbor, direction, goal) else {
+                    continue;
+                };
+                let jump_index = self.index(jump_point.0, jump_point.1);
+                let cost = search.costs[index] + self.heuristic(point, jump_point);
+                search.relax(index, jump_index, cost, self.heuristic(jump_point, goal));
+            }
+        }
+        None
+    }
+
+    /// Collects the neighbors worth exploring when arriving at `point` from `parent`.
+    fn pruned_neighbors(
+        &self,
+        (x, y): GridPoint,
+        parent: Option<GridPoint>,
+        out: &mut Vec<GridPoint>,
+    ) {
+        let Some((px, py)) = parent else {
+            self.for_each_neighbor((x, y), |next, _| out.push(next));
+            return;
+        };
+
+        let (dx, dy) = ((x - px).signum(), (y - py).signum());
+        let walkable = |x, y| self.is_walkable(x, y);
+        if dx != 0 && dy != 0 {
+            if walkable(x, y + dy) {
+                out.push((x, y + dy));
+            }
+            if walkable(x + dx, y) {
+                out.push((x + dx, y));
+            }
+            if walkable(x, y + dy) && walkable(x + dx, y) && walkable(x + dx, y + dy) {
+                out.push((x + dx, y + dy));
+            }
+        } else if dx != 0 {
+            let (ahead, up, down) = (walkable(x + dx, y), walkable(x, y - 1), walkable(x, y + 1));
+            if ahead {
+                out.push((x + dx, y));
+                if up && walkable(x + dx, y - 1) {
+                    out.push((x + dx, y - 1));
+                }
+                if down && walkable(x + dx, y + 1) {
+                    out.push((x + dx, y + 1));
+                }
+            }
+            if up {
+                out.push((x, y - 1));
+            }
+            if down {
+                out.push((x, y + 1));
+            }
+        } else {
+            let (ahead, left, right) =
+                (walkable(x, y + dy), walkable(x - 1, y), walkable(x + 1, y));
+            if ahead {
+                out.push((x, y + dy));
+                if left && walkable(x - 1, y + dy) {
+                    out.push((x - 1, y + dy));
+                }
+                if right && walkable(x + 1, y + dy) {
+                    out.push((x + 1, y + dy));
+                }
+            }
+            if left {
+                out.push((x - 1, y));
+            }
+            if right {
+                out.push((x + 1, y));
+            }
+        }
+    }
+
+    /// Moves from `point` in `direction` until reaching the goal, a cell with a
+    /// forced neighbor, or a wall.
+    fn jump(
+        &self,
+        (mut x, mut y): GridPoint,
+        (dx, dy): GridPoint,
+        goal: GridPoint,
+    ) -> Option<GridPoint> {
+        loop {
+            if !self.is_walkable(x, y) {
+                return None;
+            }
+            if (x, y) == goal {
+                return Some((x, y));
+            }
+
+            if dx != 0 && dy != 0 {
+                if self.jump((x + dx, y), (dx, 0), goal).is_some()
+                    || self.jump((x, y + dy), (0, dy), goal).is_some()
+                {
+                    return Some((x, y));
+                }
+            } else if dx != 0 {
+                if (self.is_walkable(x, y - 1) && !self.is_walkable(x - dx, y - 1))
+                    || (self.is_walkable(x, y + 1) && !self.is_walkable(x - dx, y + 1))
+                {
+                    return Some((x, y));
+                }
+            } else if (self.is_walkable(x - 1, y) && !self.is_walkable(x - 1, y - dy))
+                || (self.is_walkable(x + 1, y) && !self.is_walkable(x + 1, y - dy))
+            {
+                return Some((x, y));
+            }
+
+            if !(self.is_walkable(x + dx, y) && self.is_walkable(x, y + dy)) {
+                return None;
+            }
+            x += dx;
+            y += dy;
+        }
+    }
+
+    /// Fills in every cell between consecutive turning points of a path made of
+    /// straight or diagonal runs, such as one from [`NavGrid::find_path_jps`].
+    pub fn expand_path(&self, path: &[GridPoint]) -> Vec<GridPoint> {
+        let mut cells: Vec<GridPoint> = path.first().copied().into_iter().collect();
+        for pair in path.windows(2) {
+            let (mut x, mut y) = pair[0];
+            let (dx, dy) = ((pair[1].0 - x).signum(), (pair[1].1 - y).signum());
+            while (x, y) != pair[1] {
+                if x != pair[1].0 {
+                    x += dx;
+                }
+                if y != pair[1].1 {
+                    y += dy;
+                }
+                cells.push((x, y));
+            }
+        }
+        cells
+    }
+
+    /// Checks if an agent can walk in a straight line between two cell centers
+    /// without entering a blocked cell or squeezing between two diagonal walls.
+    pub fn line_of_sight(&self, from: GridPoint, to: GridPoint) -> bool {
+        let (mut x, mut y) = from;
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let (step_x, step_y) = (dx.signum(), dy.signum());
+        let (nx, ny) = (dx.abs(), dy.abs());
+        if !self.is_walkable(x, y) {
+            return false;
+        }
+
+        // Walks the cells the segment crosses in order, comparing how far along
+        // the segment the next vertical and horizontal cell borders are.
+        let (mut ix, mut iy) = (0, 0);
+        while ix < nx || iy < ny {
+            let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+            match decision.cmp(&0) {
+                Ordering::Equal => {
+                    if !self.is_walkable(x + step_x, y) || !self.is_walkable(x, y + step_y) {
+                        return false;
+                    }
+                    x += step_x;
+                    y += step_y;
+                    ix += 1;
+                    iy += 1;
+                }
+                Ordering::Less => {
+                    x += step_x;
+                    ix += 1;
+                }
+                Ordering::Greater => {
+                    y += step_y;
+                    iy += 1;
+                }
+            }
+            if !self.is_walkable(x, y) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Removes every corner that can be skipped by walking in a straight line,
+    /// turning staircase-shaped grid paths into natural-looking ones.
+    pub fn smooth_path(&self, path: &[GridPoint]) -> Vec<GridPoint> {
+        let Some(&first) = path.first() else {
+            return Vec::new();
+        };
+        let mut smoothed = vec![first];
+        let mut anchor = 0;
+        while anchor + 1 < path.len() {
+            let mut next = anchor + 1;
+            for candidate in (anchor + 2..path.len()).rev() {
+                if self.line_of_sight(path[anchor], path[candidate]) {
+                    next = candidate;
+                    break;
+                }
+            }
+            smoothed.push(path[next]);
+            anchor = next;
+        }
+        smoothed
+    }
+
+    /// Checks if every segment of a path is still walkable, e.g. after cells changed.
+    pub fn is_path_clear(&self, path: &[GridPoint]) -> bool {
+        match path {
+            [single] => self.is_walkable(single.0, single.1),
+            _ => path
+                .windows(2)
+                .all(|pair| self.line_of_sight(pair[0], pair[1])),
+        }
+    }
+
+    /// Finds a smoothed path between two world positions with jump point
+    /// search and returns it as world-space waypoints, ending exactly at `goal`.
+    pub fn find_world_path(&self, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        let path = self.find_path_jps(self.world_to_cell(start), self.world_to_cell(goal))?;
+        let path = self.smooth_path(&self.expand_path(&path));
+        let mut points: Vec<Vec2> = path
+            .iter()
+            .skip(1)
+            .map(|cell| self.cell_to_world(*cell))
+            .collect();
+        points.pop();
+        points.insert(0, start);
+        points.push(goal);
+        Some(points)
+    }
+}
+
+/// Keeps only the cells where a path changes direction.
+fn simplify_path(path: &[GridPoint]) -> Vec<GridPoint> {
+    let mut simplified: Vec<GridPoint> = path.first().copied().into_iter().collect();
+    for window in path.windows(3) {
+        let first = (window[1].0 - window[0].0, window[1].1 - window[0].1);
+        let second = (window[2].0 - window[1].0, window[2].1 - window[1].1);
+        if first != second {
+            simplified.push(window[1]);
+        }
+    }
+    if path.len() > 1 {
+        simplified.push(path[path.len() - 1]);
+    }
+    simplified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the length of a path of single steps.
+    fn length(path: &[GridPoint]) -> f32 {
+        path.windows(2)
+            .map(|pair| {
+                let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+                assert!(dx.abs() <= 1 && dy.abs() <= 1, "{:?} is not a step", pair);
+                if dx != 0 && dy != 0 {
+                    SQRT_2
+                } else {
+                    1.0
+                }
+            })
+            .sum()
+    }
+
+    /// Checks that a path of single steps only enters walkable cells and
+    /// never cuts the corner of a wall.
+    fn assert_walkable(grid: &NavGrid, path: &[GridPoint]) {
+        for pair in path.windows(2) {
+            let ((x, y), (nx, ny)) = (pair[0], pair[1]);
+            assert!(grid.is_walkable(nx, ny), "{:?} is blocked", (nx, ny));
+            if x != nx && y != ny {
+                assert!(grid.is_walkable(nx, y) && grid.is_walkable(x, ny));
+            }
+        }
+    }
+
+    /// A grid with a wall down the middle, open at the bottom.
+    fn walled_grid() -> NavGrid {
+        let mut grid = NavGrid::new(10, 10);
+        grid.fill(5, 0, 1, 8, f32::INFINITY);
+        grid
+    }
+
+    #[test]
+    fn a_star_goes_around_walls() {
+        let grid = walled_grid();
+        let path = grid.find_path((0, 0), (9, 0)).unwrap();
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(9, 0)));
+        assert!(path.contains(&(5, 8)) || path.contains(&(5, 9)));
+        assert_walkable(&grid, &path);
+        // Out to (4, 8), two straight steps through the gap, back to (9, 0).
+        assert!((length(&path) - (7.0 * SQRT_2 + 11.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn a_star_avoids_expensive_cells() {
+        let mut grid = NavGrid::new(5, 3).with_diagonal(DiagonalMovement::Never);
+        grid.fill(1, 1, 3, 1, 10.0);
+        let path = grid.find_path((0, 1), (4, 1)).unwrap();
+        assert_eq!(path.len(), 7);
+        assert!(path[1..path.len() - 1].iter().all(|&(_, y)| y != 1));
+    }
+
+    #[test]
+    fn unreachable_goals_have_no_path() {
+        let mut grid = walled_grid();
+        grid.fill(5, 8, 1, 2, f32::INFINITY);
+        assert_eq!(grid.find_path((0, 0), (9, 0)), None);
+        assert_eq!(grid.find_path_jps((0, 0), (9, 0)), None);
+        assert_eq!(grid.find_path((0, 0), (5, 0)), None);
+    }
+
+    #[test]
+    fn jump_point_search_matches_a_star() {
+        let mut rng = Rng::new(3);
+        let mut grid = NavGrid::new(24, 24);
+        for _ in 0..140 {
+            grid.set_blocked(rng.range(0..24), rng.range(0..24), true);
+        }
+        let mut compared = 0;
+        for _ in 0..200 {
+            let start = (rng.range(0..24), rng.range(0..24));
+            let goal = (rng.range(0..24), rng.range(0..24));
+            let a_star = grid.find_path(start, goal);
+            let jps = grid.find_path_jps(start, goal);
+            assert_eq!(a_star.is_some(), jps.is_some(), "{:?} to {:?}", start, goal);
+            if let (Some(a_star), Some(jps)) = (a_star, jps) {
+                let expanded = grid.expand_path(&jps);
+                assert_eq!(expanded.first(), Some(&start));
+                assert_eq!(expanded.last(), Some(&goal));
+                assert_walkable(&grid, &expanded);
+                assert!(
+                    (length(&a_star) - length(&expanded)).abs() < 1e-3,
+                    "{:?} to {:?}",
+                    start,
+                    goal
+                );
+                compared += 1;
+            }
+        }
+        assert!(compared > 100);
+    }
+
+    #[test]
+    fn jump_point_search_without_diagonals_keeps_turning_points() {
+        let grid = walled_grid().with_diagonal(DiagonalMovement::Never);
+        let path = grid.find_path_jps((0, 0), (9, 0)).unwrap();
+        let expanded = grid.expand_path(&path);
+        assert_walkable(&grid, &expanded);
+        assert_eq!(length(&expanded), 8.0 + 9.0 + 8.0);
+        assert!(path.len() < expanded.len());
+    }
+}
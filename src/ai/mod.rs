@@ -0,0 +1,13 @@
+pub mod behavior_tree;
+pub mod grid_path;
+pub mod navmesh;
+pub mod navmesh_builder;
+pub mod steering;
+
+pub use behavior_tree::{behavior_tree_system, BehaviorTree, Blackboard, Status};
+pub use grid_path::{DiagonalMovement, GridPoint, NavGrid};
+pub use navmesh::{NavAgent, NavMesh};
+pub use navmesh_builder::{NavMeshBuilder, NavMeshSettings};
+pub use steering::{
+    steering_movement_system, steering_system, SteeringAgent, SteeringBehavior, SteeringObstacle,
+};
@@ -0,0 +1,572 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::ecs::World;
+use crate::math::*;
+use crate::scene::Transform;
+
+/// A connection from one polygon to a neighbor through a shared edge segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NavLink {
+    pub neighbor: usize,
+    /// Endpoints of the shared segment, in no particular order.
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+/// A convex walkable polygon of a [`NavMesh`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavPolygon {
+    /// Corners, counter-clockwise seen from above.
+    pub vertices: Vec<Vec3>,
+    pub links: Vec<NavLink>,
+    pub center: Vec3,
+    pub bounds: Aabb,
+}
+
+impl NavPolygon {
+    /// Creates a polygon without links.
+    pub fn new(vertices: Vec<Vec3>) -> Self {
+        let center = vertices.iter().fold(vec3(0.0, 0.0, 0.0), |sum, v| sum + *v)
+            / vertices.len().max(1) as f32;
+        let bounds = Aabb::from_points(&vertices).unwrap_or(Aabb::new(center, center));
+        Self {
+            vertices,
+            links: Vec::new(),
+            center,
+            bounds,
+        }
+    }
+
+    /// Checks if a point lies inside the polygon seen from above.
+    pub fn contains_xz(&self, x: f32, z: f32) -> bool {
+        if x < self.bounds.min.x
+            || x > self.bounds.max.x
+            || z < self.bounds.min.z
+            || z > self.bounds.max.z
+        {
+            return false;
+        }
+        let count = self.vertices.len();
+        let mut sign = 0.0f32;
+        for i in 0..count {
+            let a = self.vertices[i];
+            let b = self.vertices[(i + 1) % count];
+            let cross = (b.x - a.x) * (z - a.z) - (b.z - a.z) * (x - a.x);
+            if cross.abs() <= 1e-6 {
+                continue;
+            }
+            if sign == 0.0 {
+                sign = cross.signum();
+            } else if cross.signum() != sign {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the surface height at a point inside the polygon, interpolating
+    /// over a triangle fan.
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let a = self.vertices[0];
+        for pair in self.vertices[1..].windows(2) {
+            let (b, c) = (pair[0], pair[1]);
+            let denominator = (b.z - c.z) * (a.x - c.x) + (c.x - b.x) * (a.z - c.z);
+            if denominator.abs() < 1e-9 {
+                continue;
+            }
+            let u = ((b.z - c.z) * (x - c.x) + (c.x - b.x) * (z - c.z)) / denominator;
+            let v = ((c.z - a.z) * (x - c.x) + (a.x - c.x) * (z - c.z)) / denominator;
+            let w = 1.0 - u - v;
+            if u >= -1e-4 && v >= -1e-4 && w >= -1e-4 {
+                return a.y * u + b.y * v + c.y * w;
+            }
+        }
+        self.center.y
+    }
+
+    /// Returns the closest point on or inside the polygon to `point`, seen from above.
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        if self.contains_xz(point.x, point.z) {
+            return vec3(point.x, self.height_at(point.x, point.z), point.z);
+        }
+        let count = self.vertices.len();
+        let mut best = self.vertices[0];
+        let mut best_distance = f32::MAX;
+        for i in 0..count {
+            let candidate =
+                closest_on_segment_xz(point, self.vertices[i], self.vertices[(i + 1) % count]);
+            let distance = distance2_xz(point, candidate);
+            if distance < best_distance {
+                best = candidate;
+                best_distance = distance;
+            }
+        }
+        best
+    }
+}
+
+fn distance2_xz(a: Vec3, b: Vec3) -> f32 {
+    let (dx, dz) = (a.x - b.x, a.z - b.z);
+    dx * dx + dz * dz
+}
+
+fn closest_on_segment_xz(point: Vec3, a: Vec3, b: Vec3) -> Vec3 {
+    let (dx, dz) = (b.x - a.x, b.z - a.z);
+    let length2 = dx * dx + dz * dz;
+    if length2 <= 1e-12 {
+        return a;
+    }
+    let t = (((point.x - a.x) * dx + (point.z - a.z) * dz) / length2).clamp(0.0, 1.0);
+    a + (b - a) * t
+}
+
+/// Twice the signed area of a triangle seen from above.
+fn triangle_area2(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    (c.x - a.x) * (b.z - a.z) - (b.x - a.x) * (c.z - a.z)
+}
+
+/// An open-set entry for the corridor search.
+struct Candidate {
+    cost: f32,
+    polygon: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// # Navigation Mesh
+///
+/// The walkable surface of a level as convex polygons connected through shared
+/// edges. Path queries search the polygon graph with A* and then pull the
+/// string through the shared edges (the funnel algorithm), giving the shortest
+/// path within the corridor rather than a zig-zag between polygon centers.
+///
+/// Build one with a [`NavMeshBuilder`](super::NavMeshBuilder) from level
+/// geometry, or import polygons made in an editor with [`NavMesh::from_polygons`].
+///
+/// ## Example
+/// ```ignore
+/// let navmesh = NavMeshBuilder::new(NavMeshSettings::default())
+///     .add_mesh(&level.positions, &level.indices, &Mat4::identity())
+///     .build();
+///
+/// if let Some(path) = navmesh.find_path(enemy_position, player_position) {
+///     debug_lines.polyline(&path, LineStyle::new(Color::GREEN, 2.0), false);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavMesh {
+    polygons: Vec<NavPolygon>,
+}
+
+impl NavMesh {
+    /// Creates a navmesh from linked polygons.
+    pub fn new(polygons: Vec<NavPolygon>) -> Self {
+        Self { polygons }
+    }
+
+    /// Creates a navmesh from indexed convex polygons, linking polygons that
+    /// share an edge (the same two vertex indices).
+    pub fn from_polygons(vertices: &[Vec3], polygons: &[Vec<u32>]) -> Self {
+        let mut result: Vec<NavPolygon> = polygons
+            .iter()
+            .map(|indices| NavPolygon::new(indices.iter().map(|i| vertices[*i as usize]).collect()))
+            .collect();
+
+        let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+        for (index, indices) in polygons.iter().enumerate() {
+            for i in 0..indices.len() {
+                let (a, b) = (indices[i], indices[(i + 1) % indices.len()]);
+                let key = (a.min(b), a.max(b));
+                match edges.get(&key) {
+                    Some(&other) if other != index => {
+                        let (start, end) = (vertices[a as usize], vertices[b as usize]);
+                        result[index].links.push(NavLink {
+                            neighbor: other,
+                            start,
+                            end,
+                        });
+                        result[other].links.push(NavLink {
+                            neighbor: index,
+                            start,
+                            end,
+                        });
+                    }
+                    _ => {
+                        edges.insert(key, index);
+                    }
+                }
+            }
+        }
+        Self::new(result)
+    }
+
+    /// Returns the polygons.
+    pub fn polygons(&self) -> &[NavPolygon] {
+        &self.polygons
+    }
+
+    /// Checks if the navmesh has no polygons.
+    pub fn is_empty(&self) -> bool {
+        self.polygons.is_empty()
+    }
+
+    /// Returns the polygon under or over a point whose surface is closest in
+    /// height, within `max_vertical_distance`.
+    pub fn find_polygon(&self, point: Vec3, max_vertical_distance: f32) -> Option<usize> {
+        self.polygons
+            .iter()
+            .enumerate()
+            .filter(|(_, polygon)| polygon.contains_xz(point.x, point.z))
+            .map(|(index, polygon)| (index, (polygon.height_at(point.x, point.z) - point.y).abs()))
+            .filter(|(_, distance)| *distance <= max_vertical_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the closest point on the navmesh and its polygon.
+    pub fn closest_point(&self, point: Vec3) -> Option<(usize, Vec3)> {
+        self.polygons
+            .iter()
+            .enumerate()
+            .map(|(index, polygon)| (index, polygon.closest_point(point)))
+            .min_by(|a, b| {
+                (a.1 - point)
+                    .magnitude2()
+                    .total_cmp(&(b.1 - point).magnitude2())
+            })
+    }
+
+    /// Finds the polygons a path from `start` to `end` passes through.
+    pub fn find_corridor(&self, start: usize, end: usize) -> Option<Vec<usize>> {
+        let goal = self.polygons.get(end)?.center;
+        let mut costs: HashMap<usize, f32> = HashMap::from([(start, 0.0)]);
+        let mut came_from: HashMap<usize, usize> = HashMap::new();
+        let mut open = BinaryHeap::from([Candidate {
+            cost: (self.polygons.get(start)?.center - goal).magnitude(),
+            polygon: start,
+        }]);
+
+        while let Some(Candidate { polygon, .. }) = open.pop() {
+            if polygon == end {
+                let mut corridor = vec![end];
+                let mut current = end;
+                while let Some(&previous) = came_from.get(&current) {
+                    corridor.push(previous);
+                    current = previous;
+                }
+                corridor.reverse();
+                return Some(corridor);
+            }
+
+            let cost = costs[&polygon];
+            for link in &self.polygons[polygon].links {
+                let portal = (link.start + link.end) * 0.5;
+                let step = (portal - self.polygons[polygon].center).magnitude()
+                    + (self.polygons[link.neighbor].center - portal).magnitude();
+                let next_cost = cost + step;
+                if costs
+                    .get(&link.neighbor)
+                    .is_none_or(|known| next_cost < *known)
+                {
+                    costs.insert(link.neighbor, next_cost);
+                    came_from.insert(link.neighbor, polygon);
+                    open.push(Candidate {
+                        cost: next_cost + (self.polygons[link.neighbor].center - goal).magnitude(),
+                        polygon: link.neighbor,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds the shortest path between two points, snapping both onto the
+    /// navmesh first. Returns the corner points, starting at `start`.
+    pub fn find_path(&self, start: Vec3, end: Vec3) -> Option<Vec<Vec3>> {
+        let (start_polygon, start) = self.closest_point(start)?;
+        let (end_polygon, end) = self.closest_point(end)?;
+        let corridor = self.find_corridor(start_polygon, end_polygon)?;
+        Some(self.string_pull(&corridor, start, end))
+    }
+
+    /// Straightens a path through a corridor with the simple stupid funnel algorithm.
+    fn string_pull(&self, corridor: &[usize], start: Vec3, end: Vec3) -> Vec<Vec3> {
+        let mut portals = vec![(start, start)];
+        for pair in corridor.windows(2) {
+            let from = &self.polygons[pair[0]];
+            let Some(link) = from.links.iter().find(|link| link.neighbor == pair[1]) else {
+                continue;
+            };
+            if triangle_area2(from.center, link.start, link.end) > 0.0 {
+                portals.push((link.start, link.end));
+            } else {
+                portals.push((link.end, link.start));
+            }
+        }
+        portals.push((end, end));
+
+        let mut path = vec![start];
+        let (mut apex, mut left, mut right) = (start, start, start);
+        let (mut left_index, mut right_index) = (0, 0);
+        let mut i = 1;
+        while i < portals.len() {
+            let (portal_left, portal_right) = portals[i];
+
+            if triangle_area2(apex, right, portal_right) <= 0.0 {
+                if apex == right || triangle_area2(apex, left, portal_right) > 0.0 {
+                    right = portal_right;
+                    right_index = i;
+                } else {
+                    path.push(left);
+                    apex = left;
+                    let apex_index = left_index;
+                    (left, right) = (apex, apex);
+                    (left_index, right_index) = (apex_index, apex_index);
+                    i = apex_index + 1;
+                    continue;
+                }
+            }
+
+            if triangle_area2(apex, left, portal_left) >= 0.0 {
+                if apex == left || triangle_area2(apex, right, portal_left) < 0.0 {
+                    left = portal_left;
+                    left_index = i;
+                } else {
+                    path.push(right);
+                    apex = right;
+                    let apex_index = right_index;
+                    (left, right) = (apex, apex);
+                    (left_index, right_index) = (apex_index, apex_index);
+                    i = apex_index + 1;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+
+        if path.last() != Some(&end) {
+            path.push(end);
+        }
+        path
+    }
+}
+
+/// # Nav Agent
+///
+/// A component that walks an entity along navmesh paths. Set a destination,
+/// then move it every frame with [`NavAgent::update`] or for every entity at
+/// once with [`update_nav_agents`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavAgent {
+    /// Movement speed in units per second.
+    pub speed: f32,
+    /// How close to a corner counts as reaching it.
+    pub arrival_distance: f32,
+    /// Turn the entity to face its movement direction.
+    pub face_movement: bool,
+    path: Vec<Vec3>,
+    next_corner: usize,
+}
+
+impl NavAgent {
+    /// Creates an idle agent.
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            arrival_distance: 0.05,
+            face_movement: true,
+            path: Vec::new(),
+            next_corner: 0,
+        }
+    }
+
+    /// Plans a path from `position` to `destination`. Returns `false`, and
+    /// stops, if the destination can't be reached.
+    pub fn set_destination(
+        &mut self,
+        navmesh: &NavMesh,
+        position: Vec3,
+        destination: Vec3,
+    ) -> bool {
+        match navmesh.find_path(position, destination) {
+            Some(path) => {
+                self.path = path;
+                self.next_corner = 1;
+                true
+            }
+            None => {
+                self.stop();
+                false
+            }
+        }
+    }
+
+    /// Clears the path.
+    pub fn stop(&mut self) {
+        self.path.clear();
+        self.next_corner = 0;
+    }
+
+    /// Checks if the agent still has corners to reach.
+    pub fn is_moving(&self) -> bool {
+        self.next_corner < self.path.len()
+    }
+
+    /// Returns the planned path.
+    pub fn path(&self) -> &[Vec3] {
+        &self.path
+    }
+
+    /// Returns the final point of the path.
+    pub fn destination(&self) -> Option<Vec3> {
+        self.path.last().copied()
+    }
+
+    /// Moves a position along the path, returning the direction moved in.
+    pub fn update(&mut self, position: &mut Vec3, delta_time: f32) -> Option<Vec3> {
+        let mut budget = self.speed * delta_time;
+        let mut direction = None;
+        while budget > 0.0 && self.is_moving() {
+            let target = self.path[self.next_corner];
+            let offset = target - *position;
+            let distance = offset.magnitude();
+            if distance <= self.arrival_distance.max(budget) {
+                *position = target;
+                budget -= distance;
+                self.next_corner += 1;
+                if distance > 1e-6 {
+                    direction = Some(offset / distance);
+                }
+            } else {
+                let step = offset / distance;
+                *position += step * budget;
+                direction = Some(step);
+                budget = 0.0;
+            }
+        }
+        direction
+    }
+}
+
+impl Default for NavAgent {
+    fn default() -> Self {
+        Self::new(3.5)
+    }
+}
+
+/// Moves every entity with a [`NavAgent`] and a [`Transform`] along its path.
+pub fn update_nav_agents(world: &World, delta_time: f32) {
+    if world.try_read::<NavAgent>().is_none() || world.try_read::<Transform>().is_none() {
+        return;
+    }
+    let mut agents = world.write::<NavAgent>();
+    let mut transforms = world.write::<Transform>();
+    for (entity, agent) in agents.iter_mut() {
+        let Some(transform) = transforms.get_mut(*entity) else {
+            continue;
+        };
+        let face_movement = agent.face_movement;
+        if let Some(direction) = agent.update(&mut transform.translation, delta_time) {
+            let flat = vec3(direction.x, 0.0, direction.z);
+            if face_movement && flat.magnitude2() > 1e-6 {
+                transform.rotation = quat::look_rotation(flat, Vec3::unit_y());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit squares in an L: one at the origin, one to its right and one
+    /// above that.
+    fn l_shaped() -> NavMesh {
+        let vertices: Vec<Vec3> = (0..9)
+            .map(|i| vec3((i % 3) as f32, 0.0, (i / 3) as f32))
+            .collect();
+        NavMesh::from_polygons(
+            &vertices,
+            &[vec![0, 1, 4, 3], vec![1, 2, 5, 4], vec![4, 5, 8, 7]],
+        )
+    }
+
+    fn assert_path(path: &[Vec3], expected: &[Vec3]) {
+        assert_eq!(path.len(), expected.len(), "{:?}", path);
+        for (point, expected) in path.iter().zip(expected) {
+            assert!((*point - *expected).magnitude() < 1e-4, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn shared_edges_link_polygons() {
+        let navmesh = l_shaped();
+        let neighbors = |polygon: usize| -> Vec<usize> {
+            navmesh.polygons()[polygon]
+                .links
+                .iter()
+                .map(|link| link.neighbor)
+                .collect()
+        };
+        assert_eq!(neighbors(0), vec![1]);
+        assert_eq!(neighbors(1), vec![0, 2]);
+        assert_eq!(neighbors(2), vec![1]);
+        assert_eq!(navmesh.find_corridor(0, 2), Some(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn funnel_bends_around_the_inner_corner() {
+        let navmesh = l_shaped();
+        let start = vec3(0.5, 0.0, 0.5);
+        let end = vec3(1.5, 0.0, 1.5);
+        let path = navmesh.find_path(start, end).unwrap();
+        assert_path(&path, &[start, vec3(1.0, 0.0, 1.0), end]);
+    }
+
+    #[test]
+    fn funnel_goes_straight_when_nothing_is_in_the_way() {
+        let navmesh = l_shaped();
+        let start = vec3(0.2, 0.0, 0.4);
+        let end = vec3(1.8, 0.0, 0.6);
+        assert_path(&navmesh.find_path(start, end).unwrap(), &[start, end]);
+
+        let start = vec3(1.2, 0.0, 0.1);
+        let end = vec3(1.7, 0.0, 1.9);
+        assert_path(&navmesh.find_path(start, end).unwrap(), &[start, end]);
+    }
+
+    #[test]
+    fn points_off_the_mesh_are_snapped_onto_it() {
+        let navmesh = l_shaped();
+        let path = navmesh
+            .find_path(vec3(-1.0, 0.0, 0.5), vec3(1.5, 0.0, 3.0))
+            .unwrap();
+        assert_path(
+            &path,
+            &[
+                vec3(0.0, 0.0, 0.5),
+                vec3(1.0, 0.0, 1.0),
+                vec3(1.5, 0.0, 2.0),
+            ],
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use std::collections::VecDeque;
+
+use super::navmesh::{NavLink, NavMesh, NavPolygon};
+use crate::math::*;
+
+/// Agent and voxel settings used when baking a [`NavMesh`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NavMeshSettings {
+    /// Horizontal size of a heightfield cell. Smaller cells follow geometry
+    /// more closely but take longer to bake.
+    pub cell_size: f32,
+    /// Distance kept from walls and ledges.
+    pub agent_radius: f32,
+    /// Free space needed above a surface to stand on it.
+    pub agent_height: f32,
+    /// Highest step the agent can walk up.
+    pub max_climb: f32,
+    /// Steepest walkable slope.
+    pub max_slope: Deg<f32>,
+}
+
+impl Default for NavMeshSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.25,
+            agent_radius: 0.4,
+            agent_height: 1.8,
+            max_climb: 0.4,
+            max_slope: Deg(45.0),
+        }
+    }
+}
+
+/// A walkable surface inside a heightfield column.
+#[derive(Clone, Copy, Debug)]
+struct Cell {
+    x: usize,
+    z: usize,
+    height: f32,
+    /// Linked cells in the -X, +X, -Z and +Z directions.
+    neighbors: [Option<usize>; 4],
+}
+
+const NEG_X: usize = 0;
+const POS_X: usize = 1;
+const NEG_Z: usize = 2;
+const POS_Z: usize = 3;
+
+/// Walkable surface heights and obstructing height spans per heightfield column.
+type Heightfield = (Vec<Vec<f32>>, Vec<Vec<(f32, f32)>>);
+
+/// Merged rectangle of cells, inclusive.
+#[derive(Clone, Copy, Debug)]
+struct CellRect {
+    x0: usize,
+    z0: usize,
+    x1: usize,
+    z1: usize,
+}
+
+/// # Nav Mesh Builder
+///
+/// Bakes a [`NavMesh`] from level triangles. Geometry is rasterized into a
+/// column heightfield, surfaces that are too steep or too low to stand under
+/// are dropped, the walkable area is shrunk by the agent radius, and the
+/// remaining cells are merged into rectangles that become the navmesh polygons.
+///
+/// ## Example
+/// ```ignore
+/// let mut builder = NavMeshBuilder::new(NavMeshSettings {
+///     agent_radius: 0.3,
+///     ..Default::default()
+/// });
+/// builder.add_mesh(&floor.positions, &floor.indices, &floor_transform.matrix());
+/// builder.add_mesh(&crates.positions, &crates.indices, &crates_transform.matrix());
+/// let navmesh = builder.build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct NavMeshBuilder {
+    settings: NavMeshSettings,
+    triangles: Vec<[Vec3; 3]>,
+}
+
+impl NavMeshBuilder {
+    /// Creates a builder without geometry.
+    pub fn new(settings: NavMeshSettings) -> Self {
+        Self {
+            settings,
+            triangles: Vec::new(),
+        }
+    }
+
+    /// Returns the bake settings.
+    pub fn settings(&self) -> &NavMeshSettings {
+        &self.settings
+    }
+
+    /// Adds indexed triangles, transformed into world space. Front faces are
+    /// counter-clockwise.
+    pub fn add_mesh(&mut self, positions: &[Vec3], indices: &[u32], transform: &Mat4) -> &mut Self {
+        let world = |index: u32| (*transform * positions[index as usize].extend(1.0)).truncate();
+        for triangle in indices.chunks_exact(3) {
+            self.triangles
+                .push([world(triangle[0]), world(triangle[1]), world(triangle[2])]);
+        }
+        self
+    }
+
+    /// Adds a single world-space triangle.
+    pub fn add_triangle(&mut self, a: Vec3, b: Vec3, c: Vec3) -> &mut Self {
+        self.triangles.push([a, b, c]);
+        self
+    }
+
+    /// Bakes the navmesh.
+    pub fn build(&self) -> NavMesh {
+        let points: Vec<Vec3> = self.triangles.iter().flatten().copied().collect();
+        let Some(bounds) = Aabb::from_points(&points) else {
+            return NavMesh::default();
+        };
+
+        let cell_size = self.settings.cell_size.max(1e-3);
+        let width = ((bounds.max.x - bounds.min.x) / cell_size).ceil() as usize + 1;
+        let depth = ((bounds.max.z - bounds.min.z) / cell_size).ceil() as usize + 1;
+        let origin = vec2(bounds.min.x, bounds.min.z);
+
+        let (surfaces, obstacles) = self.rasterize(origin, width, depth, cell_size);
+        let mut cells = self.filter_surfaces(&surfaces, &obstacles, width);
+        self.link_cells(&mut cells, width, depth);
+        let cells = self.erode(cells);
+        let (rects, owners) = merge_rects(&cells, self.settings.max_climb);
+        let polygons = build_polygons(&cells, &rects, &owners, origin, cell_size);
+
+        log::info!(
+            "Baked navmesh: {} polygons from {} cells",
+            polygons.len(),
+            cells.len()
+        );
+        NavMesh::new(polygons)
+    }
+
+    /// Collects walkable surface heights and obstructing height spans per column.
+    fn rasterize(&self, origin: Vec2, width: usize, depth: usize, cell_size: f32) -> Heightfield {
+        let mut surfaces = vec![Vec::new(); width * depth];
+        let mut obstacles = vec![Vec::new(); width * depth];
+        let min_normal_y = self.settings.max_slope.0.to_radians().cos();
+        let reach = cell_size * std::f32::consts::FRAC_1_SQRT_2;
+
+        for &[a, b, c] in &self.triangles {
+            let normal = (b - a).cross(c - a);
+            if normal.magnitude2() <= 1e-12 {
+                continue;
+            }
+            let walkable = normal.normalize().y >= min_normal_y;
+            let low = a.y.min(b.y).min(c.y);
+            let high = a.y.max(b.y).max(c.y);
+
+            let cell = |value: f32, base: f32, limit: usize| {
+                (((value - base) / cell_size).floor().max(0.0) as usize).min(limit - 1)
+            };
+            let (x0, x1) = (
+                cell(a.x.min(b.x).min(c.x) - reach, origin.x, width),
+                cell(a.x.max(b.x).max(c.x) + reach, origin.x, width),
+            );
+            let (z0, z1) = (
+                cell(a.z.min(b.z).min(c.z) - reach, origin.y, depth),
+                cell(a.z.max(b.z).max(c.z) + reach, origin.y, depth),
+            );
+
+            for z in z0..=z1 {
+                for x in x0..=x1 {
+                    let px = origin.x + (x as f32 + 0.5) * cell_size;
+                    let pz = origin.y + (z as f32 + 0.5) * cell_size;
+                    let column = z * width + x;
+                    match barycentric_height(a, b, c, px, pz) {
+                        Some(height) if walkable => {
+                            surfaces[column].push(height);
+                            obstacles[column].push((height, height));
+                        }
+                        Some(_) => obstacles[column].push((low, high)),
+                        None if !walkable
+                            && distance_to_triangle_edges(a, b, c, px, pz) <= reach =>
+                        {
+                            obstacles[column].push((low, high));
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+        (surfaces, obstacles)
+    }
+
+    /// Keeps surfaces with enough headroom, merging near-duplicate heights
+    /// from triangles that share an edge.
+    fn filter_surfaces(
+        &self,
+        surfaces: &[Vec<f32>],
+        obstacles: &[Vec<(f32, f32)>],
+        width: usize,
+    ) -> Vec<Cell> {
+        let mut cells = Vec::new();
+        for (column, heights) in surfaces.iter().enumerate() {
+            let mut heights = heights.clone();
+            heights.sort_by(f32::total_cmp);
+            heights.dedup_by(|a, b| (*a - *b).abs() <= 0.01);
+
+            for height in heights {
+                let blocked = obstacles[column].iter().any(|&(low, high)| {
+                    high > height + self.settings.max_climb.max(0.01)
+                        && low < height + self.settings.agent_height
+                });
+                if !blocked {
+                    cells.push(Cell {
+                        x: column % width,
+                        z: column / width,
+                        height,
+                        neighbors: [None; 4],
+                    });
+                }
+            }
+        }
+        cells
+    }
+
+    /// Links cells to the closest surface in each neighboring column within climbing range.
+    fn link_cells(&self, cells: &mut [Cell], width: usize, depth: usize) {
+        let mut columns: Vec<Vec<usize>> = vec![Vec::new(); width * depth];
+        for (index, cell) in cells.iter().enumerate() {
+            columns[cell.z * width + cell.x].push(index);
+        }
+
+        for index in 0..cells.len() {
+            let Cell { x, z, height, .. } = cells[index];
+            let offsets = [
+                (x.checked_sub(1), Some(z)),
+                ((x + 1 < width).then_some(x + 1), Some(z)),
+                (Some(x), z.checked_sub(1)),
+                (Some(x), (z + 1 < depth).then_some(z + 1)),
+            ];
+            for (direction, offset) in offsets.into_iter().enumerate() {
+                let (Some(nx), Some(nz)) = offset else {
+                    continue;
+                };
+                cells[index].neighbors[direction] = columns[nz * width + nx]
+                    .iter()
+                    .map(|&other| (other, (cells[other].height - height).abs()))
+                    .filter(|(_, difference)| *difference <= self.settings.max_climb)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(other, _)| other);
+            }
+        }
+    }
+
+    /// Removes cells closer to an edge than the agent radius.
+    fn erode(&self, cells: Vec<Cell>) -> Vec<Cell> {
+        let mut distance = vec![u32::MAX; cells.len()];
+        let mut queue = VecDeque::new();
+        for (index, cell) in cells.iter().enumerate() {
+            if cell.neighbors.iter().any(Option::is_none) {
+                distance[index] = 1;
+                queue.push_back(index);
+            }
+        }
+        while let Some(index) = queue.pop_front() {
+            for neighbor in cells[index].neighbors.into_iter().flatten() {
+                if distance[neighbor] == u32::MAX {
+                    distance[neighbor] = distance[index] + 1;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let cell_size = self.settings.cell_size.max(1e-3);
+        let keep: Vec<bool> = distance
+            .iter()
+            .map(|&d| (d as f32 - 0.5) * cell_size >= self.settings.agent_radius)
+            .collect();
+
+        let mut remap = vec![None; cells.len()];
+        let mut kept = Vec::new();
+        for (index, cell) in cells.iter().enumerate() {
+            if keep[index] {
+                remap[index] = Some(kept.len());
+                kept.push(*cell);
+            }
+        }
+        for cell in &mut kept {
+            for neighbor in &mut cell.neighbors {
+                *neighbor = neighbor.and_then(|index| remap[index]);
+            }
+        }
+        kept
+    }
+}
+
+/// Returns the height of a triangle at a point, if the point lies inside it seen from above.
+fn barycentric_height(a: Vec3, b: Vec3, c: Vec3, x: f32, z: f32) -> Option<f32> {
+    let denominator = (b.z - c.z) * (a.x - c.x) + (c.x - b.x) * (a.z - c.z);
+    if denominator.abs() < 1e-9 {
+        return None;
+    }
+    let u = ((b.z - c.z) * (x - c.x) + (c.x - b.x) * (z - c.z)) / denominator;
+    let v = ((c.z - a.z) * (x - c.x) + (a.x - c.x) * (z - c.z)) / denominator;
+    let w = 1.0 - u - v;
+    (u >= 0.0 && v >= 0.0 && w >= 0.0).then_some(a.y * u + b.y * v + c.y * w)
+}
+
+/// Returns the distance from a point to the closest triangle edge, seen from above.
+fn distance_to_triangle_edges(a: Vec3, b: Vec3, c: Vec3, x: f32, z: f32) -> f32 {
+    let point = vec2(x, z);
+    [(a, b), (b, c), (c, a)]
+        .into_iter()
+        .map(|(start, end)| {
+            let (start, end) = (vec2(start.x, start.z), vec2(end.x, end.z));
+            let edge = end - start;
+            let t = if edge.magnitude2() > 1e-12 {
+                ((point - start).dot(edge) / edge.magnitude2()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (start + edge * t - point).magnitude()
+        })
+        .fold(f32::MAX, f32::min)
+}
+
+/// Greedily merges linked cells into rectangles whose heights stay within
+/// `tolerance` of their first cell. Returns the rectangles and each cell's rectangle.
+fn merge_rects(cells: &[Cell], tolerance: f32) -> (Vec<CellRect>, Vec<usize>) {
+    let mut order: Vec<usize> = (0..cells.len()).collect();
+    order.sort_by_key(|&index| (cells[index].z, cells[index].x));
+
+    let mut owners = vec![usize::MAX; cells.len()];
+    let mut rects = Vec::new();
+    let fits = |index: usize, base: f32, owners: &[usize]| {
+        owners[index] == usize::MAX && (cells[index].height - base).abs() <= tolerance
+    };
+
+    for &start in &order {
+        if owners[start] != usize::MAX {
+            continue;
+        }
+        let base = cells[start].height;
+
+        let mut row = vec![start];
+        while let Some(next) = cells[*row.last().unwrap()].neighbors[POS_X] {
+            if !fits(next, base, &owners) {
+                break;
+            }
+            row.push(next);
+        }
+
+        let id = rects.len();
+        for &index in &row {
+            owners[index] = id;
+        }
+        let mut rows = 1;
+        let mut current = row;
+        loop {
+            let next: Option<Vec<usize>> = current
+                .iter()
+                .map(|&index| cells[index].neighbors[POS_Z].filter(|&n| fits(n, base, &owners)))
+                .collect();
+            let Some(next) = next else {
+                break;
+            };
+            let contiguous = next
+                .windows(2)
+                .all(|pair| cells[pair[0]].neighbors[POS_X] == Some(pair[1]));
+            if !contiguous {
+                break;
+            }
+            for &index in &next {
+                owners[index] = id;
+            }
+            rows += 1;
+            current = next;
+        }
+
+        let first = &cells[start];
+        rects.push(CellRect {
+            x0: first.x,
+            z0: first.z,
+            x1: first.x + current.len() - 1,
+            z1: first.z + rows - 1,
+        });
+    }
+    (rects, owners)
+}
+
+/// Turns merged rectangles into polygons and links them along shared edges.
+fn build_polygons(
+    cells: &[Cell],
+    rects: &[CellRect],
+    owners: &[usize],
+    origin: Vec2,
+    cell_size: f32,
+) -> Vec<NavPolygon> {
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); rects.len()];
+    for (index, owner) in owners.iter().enumerate() {
+        members[*owner].push(index);
+    }
+
+    let mut polygons = Vec::with_capacity(rects.len());
+    for (id, rect) in rects.iter().enumerate() {
+        let at = |x: usize, z: usize| {
+            members[id]
+                .iter()
+                .copied()
+                .find(|&index| cells[index].x == x && cells[index].z == z)
+                .expect("Merged rectangle is missing a cell")
+        };
+        let corner = |x: usize, z: usize, cell: usize| {
+            vec3(
+                origin.x + x as f32 * cell_size,
+                cells[cell].height,
+                origin.y + z as f32 * cell_size,
+            )
+        };
+
+        let mut polygon = NavPolygon::new(vec![
+            corner(rect.x0, rect.z0, at(rect.x0, rect.z0)),
+            corner(rect.x0, rect.z1 + 1, at(rect.x0, rect.z1)),
+            corner(rect.x1 + 1, rect.z1 + 1, at(rect.x1, rect.z1)),
+            corner(rect.x1 + 1, rect.z0, at(rect.x1, rect.z0)),
+        ]);
+
+        let sides = [
+            (
+                NEG_X,
+                (rect.z0..=rect.z1)
+                    .map(|z| at(rect.x0, z))
+                    .collect::<Vec<_>>(),
+            ),
+            (POS_X, (rect.z0..=rect.z1).map(|z| at(rect.x1, z)).collect()),
+            (NEG_Z, (rect.x0..=rect.x1).map(|x| at(x, rect.z0)).collect()),
+            (POS_Z, (rect.x0..=rect.x1).map(|x| at(x, rect.z1)).collect()),
+        ];
+        for (direction, edge) in sides {
+            let mut run: Option<(usize, usize, usize)> = None;
+            for &index in edge.iter().chain([&usize::MAX]) {
+                let neighbor = (index != usize::MAX)
+                    .then(|| cells[index].neighbors[direction])
+                    .flatten()
+                    .filter(|&n| owners[n] != id);
+                let next_owner = neighbor.map(|n| owners[n]);
+
+                if let Some((owner, first, last)) = run {
+                    if next_owner == Some(owner) {
+                        run = Some((owner, first, index));
+                        continue;
+                    }
+                    polygon.links.push(portal(
+                        cells, owner, first, last, direction, origin, cell_size,
+                    ));
+                    run = None;
+                }
+                if let (Some(owner), false) = (next_owner, index == usize::MAX) {
+                    run = Some((owner, index, index));
+                }
+            }
+        }
+        polygons.push(polygon);
+    }
+    polygons
+}
+
+/// Builds the link through the outer edge of a run of boundary cells.
+fn portal(
+    cells: &[Cell],
+    neighbor: usize,
+    first: usize,
+    last: usize,
+    direction: usize,
+    origin: Vec2,
+    cell_size: f32,
+) -> NavLink {
+    let edge_height = |index: usize| {
+        let across =
+            cells[index].neighbors[direction].map_or(cells[index].height, |n| cells[n].height);
+        (cells[index].height + across) * 0.5
+    };
+    let (a, b) = (&cells[first], &cells[last]);
+    let (start, end) = match direction {
+        NEG_X | POS_X => {
+            let x = a.x + usize::from(direction == POS_X);
+            ((x, a.z), (x, b.z + 1))
+        }
+        _ => {
+            let z = a.z + usize::from(direction == POS_Z);
+            ((a.x, z), (b.x + 1, z))
+        }
+    };
+    let point = |(x, z): (usize, usize), height: f32| {
+        vec3(
+            origin.x + x as f32 * cell_size,
+            height,
+            origin.y + z as f32 * cell_size,
+        )
+    };
+    NavLink {
+        neighbor,
+        start: point(start, edge_height(first)),
+        end: point(end, edge_height(last)),
+    }
+}
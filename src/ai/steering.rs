@@ -0,0 +1,518 @@
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+use crate::math::*;
+use crate::scene::Transform;
+
+/// Returns `vector` shortened to at most `max_length`.
+fn truncate(vector: Vec3, max_length: f32) -> Vec3 {
+    let length2 = vector.magnitude2();
+    if length2 > max_length * max_length && length2 > 0.0 {
+        vector * (max_length / length2.sqrt())
+    } else {
+        vector
+    }
+}
+
+/// Steers towards `target` at full speed.
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let offset = target - position;
+    if offset.magnitude2() <= 1e-12 {
+        return -velocity;
+    }
+    offset.normalize() * max_speed - velocity
+}
+
+/// Steers away from `target` while it is closer than `panic_distance`.
+pub fn flee(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    max_speed: f32,
+    panic_distance: f32,
+) -> Vec3 {
+    let offset = position - target;
+    let distance2 = offset.magnitude2();
+    if distance2 > panic_distance * panic_distance || distance2 <= 1e-12 {
+        return Vec3::zero();
+    }
+    offset.normalize() * max_speed - velocity
+}
+
+/// Steers towards `target`, slowing down inside `slowing_radius` to stop on it.
+pub fn arrive(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    max_speed: f32,
+    slowing_radius: f32,
+) -> Vec3 {
+    let offset = target - position;
+    let distance = offset.magnitude();
+    if distance <= 1e-4 {
+        return -velocity;
+    }
+    let speed = max_speed * (distance / slowing_radius.max(1e-4)).min(1.0);
+    offset * (speed / distance) - velocity
+}
+
+/// Steers towards where a moving target will be, assuming it keeps its velocity.
+pub fn pursue(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    max_speed: f32,
+) -> Vec3 {
+    let look_ahead = (target - position).magnitude() / max_speed.max(1e-4);
+    seek(
+        position,
+        velocity,
+        target + target_velocity * look_ahead,
+        max_speed,
+    )
+}
+
+/// Steers away from where a moving target will be.
+pub fn evade(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    max_speed: f32,
+    panic_distance: f32,
+) -> Vec3 {
+    let look_ahead = (target - position).magnitude() / max_speed.max(1e-4);
+    flee(
+        position,
+        velocity,
+        target + target_velocity * look_ahead,
+        max_speed,
+        panic_distance,
+    )
+}
+
+/// A spherical obstacle for [`avoid_obstacles`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphereObstacle {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+/// Steers sideways away from the nearest obstacle in front of the agent,
+/// looking `look_ahead` units along its velocity.
+pub fn avoid_obstacles(
+    position: Vec3,
+    velocity: Vec3,
+    radius: f32,
+    look_ahead: f32,
+    max_speed: f32,
+    obstacles: &[SphereObstacle],
+) -> Vec3 {
+    let speed = velocity.magnitude();
+    if speed <= 1e-4 {
+        return Vec3::zero();
+    }
+    let forward = velocity / speed;
+    let reach = look_ahead * (speed / max_speed.max(1e-4)).max(0.25);
+
+    let threat = obstacles
+        .iter()
+        .filter_map(|obstacle| {
+            let offset = obstacle.center - position;
+            let along = offset.dot(forward);
+            let combined = obstacle.radius + radius;
+            if along < -combined || along > reach + combined {
+                return None;
+            }
+            let lateral = offset - forward * along;
+            (lateral.magnitude2() < combined * combined).then_some((along, lateral, combined))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+    let Some((along, lateral, combined)) = threat else {
+        return Vec3::zero();
+    };
+    let away = if lateral.magnitude2() > 1e-8 {
+        -lateral.normalize()
+    } else {
+        let side = forward.cross(Vec3::unit_y());
+        if side.magnitude2() > 1e-8 {
+            side.normalize()
+        } else {
+            Vec3::unit_x()
+        }
+    };
+    let urgency = 1.0 - (along.max(0.0) / (reach + combined)).min(1.0);
+    away * max_speed * (0.5 + urgency)
+}
+
+/// A nearby agent considered by the flocking behaviors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Neighbor {
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+/// Steers away from neighbors closer than `radius`, harder the closer they are.
+pub fn separation(position: Vec3, neighbors: &[Neighbor], radius: f32) -> Vec3 {
+    neighbors
+        .iter()
+        .filter_map(|neighbor| {
+            let offset = position - neighbor.position;
+            let distance = offset.magnitude();
+            (distance > 1e-5 && distance < radius)
+                .then(|| offset / distance * (1.0 - distance / radius))
+        })
+        .fold(Vec3::zero(), |sum, push| sum + push)
+}
+
+/// Steers towards the average heading of neighbors within `radius`.
+pub fn alignment(position: Vec3, velocity: Vec3, neighbors: &[Neighbor], radius: f32) -> Vec3 {
+    let (sum, count) = neighbors
+        .iter()
+        .filter(|neighbor| (neighbor.position - position).magnitude2() < radius * radius)
+        .fold((Vec3::zero(), 0), |(sum, count), neighbor| {
+            (sum + neighbor.velocity, count + 1)
+        });
+    if count == 0 {
+        return Vec3::zero();
+    }
+    sum / count as f32 - velocity
+}
+
+/// Steers towards the center of neighbors within `radius`.
+pub fn cohesion(
+    position: Vec3,
+    velocity: Vec3,
+    neighbors: &[Neighbor],
+    radius: f32,
+    max_speed: f32,
+) -> Vec3 {
+    let (sum, count) = neighbors
+        .iter()
+        .filter(|neighbor| (neighbor.position - position).magnitude2() < radius * radius)
+        .fold((Vec3::zero(), 0), |(sum, count), neighbor| {
+            (sum + neighbor.position, count + 1)
+        });
+    if count == 0 {
+        return Vec3::zero();
+    }
+    seek(position, velocity, sum / count as f32, max_speed)
+}
+
+/// One behavior of a [`SteeringAgent`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SteeringBehavior {
+    /// Head towards a point.
+    Seek(Vec3),
+    /// Run from a point while closer than `panic_distance`.
+    Flee { target: Vec3, panic_distance: f32 },
+    /// Head towards a point and stop on it.
+    Arrive { target: Vec3, slowing_radius: f32 },
+    /// Chase another entity, aiming where it is going.
+    Pursue(Entity),
+    /// Run from another entity, avoiding where it is going.
+    Evade { target: Entity, panic_distance: f32 },
+    /// Meander by nudging a point on a circle ahead of the agent.
+    Wander {
+        distance: f32,
+        radius: f32,
+        jitter: f32,
+    },
+    /// Steer around entities with a [`SteeringObstacle`].
+    AvoidObstacles { look_ahead: f32 },
+    /// Keep apart from other agents.
+    Separation { radius: f32 },
+    /// Match the heading of other agents.
+    Alignment { radius: f32 },
+    /// Stay close to other agents.
+    Cohesion { radius: f32 },
+}
+
+/// A component marking an entity as a spherical obstacle for steering agents.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SteeringObstacle {
+    pub radius: f32,
+}
+
+/// # Steering Agent
+///
+/// A component that blends weighted steering behaviors into a desired velocity.
+/// The [`steering_system`] fills in `desired_velocity` every update; move the
+/// entity with it from a physics body, or let [`steering_movement_system`]
+/// apply it to the `Transform` directly.
+///
+/// ## Example
+/// ```ignore
+/// let boid = SteeringAgent::new(4.0, 10.0)
+///     .with_behavior(SteeringBehavior::Separation { radius: 1.5 }, 2.0)
+///     .with_behavior(SteeringBehavior::Alignment { radius: 4.0 }, 1.0)
+///     .with_behavior(SteeringBehavior::Cohesion { radius: 4.0 }, 1.0)
+///     .with_behavior(SteeringBehavior::AvoidObstacles { look_ahead: 3.0 }, 3.0);
+/// world.insert(entity, boid);
+///
+/// schedule.add_system(steering_system());
+/// schedule.add_system(steering_movement_system());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SteeringAgent {
+    pub max_speed: f32,
+    /// Largest change in velocity per second.
+    pub max_force: f32,
+    /// Radius used for obstacle avoidance.
+    pub radius: f32,
+    /// Keep movement on the XZ plane.
+    pub planar: bool,
+    pub behaviors: Vec<(SteeringBehavior, f32)>,
+    /// The current velocity. Written by [`steering_movement_system`], or by
+    /// whatever moves the entity.
+    pub velocity: Vec3,
+    /// The velocity the behaviors ask for.
+    pub desired_velocity: Vec3,
+    wander_target: Vec3,
+}
+
+impl SteeringAgent {
+    /// Creates an agent without behaviors.
+    pub fn new(max_speed: f32, max_force: f32) -> Self {
+        Self {
+            max_speed,
+            max_force,
+            radius: 0.5,
+            planar: true,
+            behaviors: Vec::new(),
+            velocity: Vec3::zero(),
+            desired_velocity: Vec3::zero(),
+            wander_target: -Vec3::unit_z(),
+        }
+    }
+
+    /// Adds a behavior with a weight.
+    pub fn with_behavior(mut self, behavior: SteeringBehavior, weight: f32) -> Self {
+        self.behaviors.push((behavior, weight));
+        self
+    }
+
+    /// Sets the radius used for obstacle avoidance.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Allows movement on every axis, for flying or swimming agents.
+    pub fn with_3d_movement(mut self) -> Self {
+        self.planar = false;
+        self
+    }
+
+    /// Replaces all behaviors with a single one.
+    pub fn set_behavior(&mut self, behavior: SteeringBehavior) {
+        self.behaviors.clear();
+        self.behaviors.push((behavior, 1.0));
+    }
+
+    /// Returns the agent's wander force and advances its wander target.
+    fn wander(&mut self, distance: f32, radius: f32, jitter: f32, rng: &mut Rng) -> Vec3 {
+        let mut nudge = rng.in_sphere() * jitter;
+        if self.planar {
+            nudge.y = 0.0;
+        }
+        let target = self.wander_target + nudge;
+        self.wander_target = if target.magnitude2() > 1e-8 {
+            target.normalize()
+        } else {
+            Vec3::unit_x()
+        };
+
+        let forward = if self.velocity.magnitude2() > 1e-8 {
+            self.velocity.normalize()
+        } else {
+            self.wander_target
+        };
+        (forward * distance + self.wander_target * radius).normalize_to(self.max_speed)
+            - self.velocity
+    }
+}
+
+impl Default for SteeringAgent {
+    fn default() -> Self {
+        Self::new(3.0, 8.0)
+    }
+}
+
+/// Returns a system that updates the `desired_velocity` of every
+/// [`SteeringAgent`] with a `Transform`.
+pub fn steering_system() -> impl System {
+    FunctionSystem::new(
+        "steering",
+        Access::new()
+            .write::<SteeringAgent>()
+            .read::<SteeringObstacle>()
+            .read::<Transform>(),
+        update_steering,
+    )
+}
+
+/// Returns a system that moves every [`SteeringAgent`] by its desired velocity
+/// and turns it to face where it is going.
+pub fn steering_movement_system() -> impl System {
+    FunctionSystem::new(
+        "steering_movement",
+        Access::new().write::<SteeringAgent>().write::<Transform>(),
+        apply_steering,
+    )
+}
+
+/// Computes desired velocities for every steering agent.
+pub fn update_steering(world: &World) {
+    let delta_time = world.time().delta();
+    let transforms = world.read::<Transform>();
+    let mut agents = world.write::<SteeringAgent>();
+
+    let snapshot: Vec<(Entity, Neighbor)> = agents
+        .iter()
+        .filter_map(|(entity, agent)| {
+            let position = transforms.get(*entity)?.translation;
+            Some((
+                *entity,
+                Neighbor {
+                    position,
+                    velocity: agent.velocity,
+                },
+            ))
+        })
+        .collect();
+    let obstacles: Vec<SphereObstacle> = world
+        .try_read::<SteeringObstacle>()
+        .map(|obstacles| {
+            obstacles
+                .iter()
+                .filter_map(|(entity, obstacle)| {
+                    Some(SphereObstacle {
+                        center: transforms.get(*entity)?.translation,
+                        radius: obstacle.radius,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let lookup = |entity: Entity| {
+        snapshot
+            .iter()
+            .find(|(other, _)| *other == entity)
+            .map(|(_, neighbor)| *neighbor)
+            .or_else(|| {
+                transforms.get(entity).map(|transform| Neighbor {
+                    position: transform.translation,
+                    velocity: Vec3::zero(),
+                })
+            })
+    };
+
+    let mut neighbors = Vec::new();
+    world.random().with_stream("steering", |rng| {
+        for (entity, agent) in agents.iter_mut() {
+            let Some(transform) = transforms.get(*entity) else {
+                continue;
+            };
+            let position = transform.translation;
+            neighbors.clear();
+            neighbors.extend(
+                snapshot
+                    .iter()
+                    .filter(|(other, _)| other != entity)
+                    .map(|(_, neighbor)| *neighbor),
+            );
+
+            let (velocity, max_speed) = (agent.velocity, agent.max_speed);
+            let mut force = Vec3::zero();
+            for (behavior, weight) in agent.behaviors.clone() {
+                let behavior_force = match behavior {
+                    SteeringBehavior::Seek(target) => seek(position, velocity, target, max_speed),
+                    SteeringBehavior::Flee {
+                        target,
+                        panic_distance,
+                    } => flee(position, velocity, target, max_speed, panic_distance),
+                    SteeringBehavior::Arrive {
+                        target,
+                        slowing_radius,
+                    } => arrive(position, velocity, target, max_speed, slowing_radius),
+                    SteeringBehavior::Pursue(target) => {
+                        lookup(target).map_or(Vec3::zero(), |target| {
+                            pursue(
+                                position,
+                                velocity,
+                                target.position,
+                                target.velocity,
+                                max_speed,
+                            )
+                        })
+                    }
+                    SteeringBehavior::Evade {
+                        target,
+                        panic_distance,
+                    } => lookup(target).map_or(Vec3::zero(), |target| {
+                        evade(
+                            position,
+                            velocity,
+                            target.position,
+                            target.velocity,
+                            max_speed,
+                            panic_distance,
+                        )
+                    }),
+                    SteeringBehavior::Wander {
+                        distance,
+                        radius,
+                        jitter,
+                    } => agent.wander(distance, radius, jitter, rng),
+                    SteeringBehavior::AvoidObstacles { look_ahead } => avoid_obstacles(
+                        position,
+                        velocity,
+                        agent.radius,
+                        look_ahead,
+                        max_speed,
+                        &obstacles,
+                    ),
+                    SteeringBehavior::Separation { radius } => {
+                        separation(position, &neighbors, radius) * max_speed
+                    }
+                    SteeringBehavior::Alignment { radius } => {
+                        alignment(position, velocity, &neighbors, radius)
+                    }
+                    SteeringBehavior::Cohesion { radius } => {
+                        cohesion(position, velocity, &neighbors, radius, max_speed)
+                    }
+                };
+                force += behavior_force * weight;
+            }
+
+            if agent.planar {
+                force.y = 0.0;
+            }
+            let force = truncate(force, agent.max_force);
+            agent.desired_velocity = truncate(velocity + force * delta_time, max_speed);
+        }
+    });
+}
+
+/// Moves every steering agent by its desired velocity.
+pub fn apply_steering(world: &World) {
+    let delta_time = world.time().delta();
+    let mut transforms = world.write::<Transform>();
+    for (entity, agent) in world.write::<SteeringAgent>().iter_mut() {
+        let Some(transform) = transforms.get_mut(*entity) else {
+            continue;
+        };
+        agent.velocity = agent.desired_velocity;
+        transform.translation += agent.velocity * delta_time;
+
+        let heading = if agent.planar {
+            vec3(agent.velocity.x, 0.0, agent.velocity.z)
+        } else {
+            agent.velocity
+        };
+        if heading.magnitude2() > 1e-6 && heading.cross(Vec3::unit_y()).magnitude2() > 1e-6 {
+            transform.rotation = quat::look_rotation(heading, Vec3::unit_y());
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::alloc::{self, Layout};
+use std::cell::{Cell, RefCell};
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+use std::slice;
+
+/// Alignment of every chunk; values needing more are aligned within it.
+const CHUNK_ALIGN: usize = 16;
+
+/// One block of memory handed out front to back.
+struct Chunk {
+    data: NonNull<u8>,
+    size: usize,
+}
+
+impl Chunk {
+    fn new(size: usize) -> Self {
+        let layout =
+            Layout::from_size_align(size.max(1), CHUNK_ALIGN).expect("Frame arena chunk too large");
+        // Safety: the layout has a non-zero size.
+        let data = unsafe { alloc::alloc(layout) };
+        let data = NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Self {
+            data,
+            size: layout.size(),
+        }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        // Safety: allocated in `Chunk::new` with this size and alignment.
+        unsafe {
+            alloc::dealloc(
+                self.data.as_ptr(),
+                Layout::from_size_align_unchecked(self.size, CHUNK_ALIGN),
+            );
+        }
+    }
+}
+
+/// # Frame Arena
+///
+/// A bump allocator for data that lives for one frame, such as sorted draw
+/// lists or scratch buffers. Allocating only moves a cursor, and
+/// [`FrameArena::reset`] frees everything at once while keeping the memory
+/// for the next frame, so a steady frame allocates nothing from the heap.
+///
+/// Only `Copy` values can be stored, since nothing is dropped on reset.
+/// When a frame needs more than one chunk, reset merges them into one big
+/// enough for the next frame.
+///
+/// ## Example
+/// ```ignore
+/// let mut arena = FrameArena::new();
+///
+/// // every frame
+/// arena.reset();
+/// let visible = arena.alloc_from_iter(sprites.iter().filter(|sprite| view.contains(sprite)));
+/// visible.sort_by_key(|sprite| sprite.layer);
+/// ```
+pub struct FrameArena {
+    /// The chunk allocated from last is the current one.
+    chunks: RefCell<Vec<Chunk>>,
+    /// Bytes handed out of the current chunk.
+    offset: Cell<usize>,
+    /// Bytes handed out since the last reset, padding included.
+    used: Cell<usize>,
+    chunk_size: usize,
+}
+
+#[allow(clippy::mut_from_ref)]
+impl FrameArena {
+    /// Creates an arena that allocates its first 64 KiB chunk when used.
+    pub fn new() -> Self {
+        Self::with_capacity(64 * 1024)
+    }
+
+    /// Creates an arena that allocates a first chunk of `bytes` when used.
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            chunks: RefCell::new(Vec::new()),
+            offset: Cell::new(0),
+            used: Cell::new(0),
+            chunk_size: bytes.max(CHUNK_ALIGN),
+        }
+    }
+
+    /// Moves a value into the arena.
+    pub fn alloc<T: Copy>(&self, value: T) -> &mut T {
+        let slot = &mut self.alloc_uninit::<T>(1)[0];
+        slot.write(value)
+    }
+
+    /// Copies a slice into the arena.
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        let slots = self.alloc_uninit::<T>(values.len());
+        for (slot, value) in slots.iter_mut().zip(values) {
+            slot.write(*value);
+        }
+        // Safety: every slot was just written.
+        unsafe { assume_init(slots) }
+    }
+
+    /// Allocates a slice of `len` copies of a value.
+    pub fn alloc_slice_fill<T: Copy>(&self, len: usize, value: T) -> &mut [T] {
+        let slots = self.alloc_uninit::<T>(len);
+        for slot in slots.iter_mut() {
+            slot.write(value);
+        }
+        // Safety: every slot was just written.
+        unsafe { assume_init(slots) }
+    }
+
+    /// Collects an iterator into the arena. Iterators that don't know their
+    /// length up front may leave unused space behind as the slice grows.
+    pub fn alloc_from_iter<T: Copy, I: IntoIterator<Item = T>>(&self, iter: I) -> &mut [T] {
+        let iter = iter.into_iter();
+        let mut slots = self.alloc_uninit::<T>(iter.size_hint().0);
+        let mut len = 0;
+        for value in iter {
+            if len == slots.len() {
+                let grown = self.alloc_uninit::<T>((len * 2).max(8));
+                grown[..len].copy_from_slice(&slots[..len]);
+                slots = grown;
+            }
+            slots[len].write(value);
+            len += 1;
+        }
+        // Safety: the first `len` slots were written.
+        unsafe { assume_init(&mut slots[..len]) }
+    }
+
+    /// Copies a string into the arena.
+    pub fn alloc_str(&self, text: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(text.as_bytes());
+        // Safety: the bytes were copied from a `str`.
+        unsafe { std::str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// Returns the bytes allocated since the last reset.
+    pub fn used_bytes(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Returns the bytes held in chunks.
+    pub fn capacity(&self) -> usize {
+        self.chunks.borrow().iter().map(|chunk| chunk.size).sum()
+    }
+
+    /// Frees everything allocated, keeping the memory for reuse.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+        if chunks.len() > 1 {
+            let size = chunks.iter().map(|chunk| chunk.size).sum();
+            chunks.clear();
+            chunks.push(Chunk::new(size));
+        }
+        self.offset.set(0);
+        self.used.set(0);
+    }
+
+    fn alloc_uninit<T>(&self, len: usize) -> &mut [MaybeUninit<T>] {
+        let layout = Layout::array::<T>(len).expect("Frame arena allocation too large");
+        if layout.size() == 0 {
+            // Safety: zero-sized slices need only an aligned pointer.
+            return unsafe { slice::from_raw_parts_mut(NonNull::dangling().as_ptr(), len) };
+        }
+        let data = self.alloc_layout(layout);
+        // Safety: the memory is fresh, sized and aligned for `len` values,
+        // and never handed out again until reset, which needs `&mut self`.
+        unsafe { slice::from_raw_parts_mut(data.as_ptr() as *mut MaybeUninit<T>, len) }
+    }
+
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        let mut chunks = self.chunks.borrow_mut();
+        if let Some(chunk) = chunks.last() {
+            if let Some(data) = self.bump(chunk, layout) {
+                return data;
+            }
+        }
+        let size = chunks
+            .last()
+            .map_or(self.chunk_size, |chunk| chunk.size * 2)
+            .max(layout.size() + layout.align());
+        chunks.push(Chunk::new(size));
+        self.offset.set(0);
+        self.bump(chunks.last().unwrap(), layout)
+            .expect("New frame arena chunk fits the allocation")
+    }
+
+    fn bump(&self, chunk: &Chunk, layout: Layout) -> Option<NonNull<u8>> {
+        let base = chunk.data.as_ptr() as usize;
+        let start = (base + self.offset.get()).next_multiple_of(layout.align()) - base;
+        let end = start.checked_add(layout.size())?;
+        if end > chunk.size {
+            return None;
+        }
+        self.used.set(self.used.get() + end - self.offset.get());
+        self.offset.set(end);
+        // Safety: `start` is within the chunk.
+        Some(unsafe { NonNull::new_unchecked(chunk.data.as_ptr().add(start)) })
+    }
+}
+
+impl Default for FrameArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Safety: the arena owns its chunks; handing it to another thread moves them
+// along, and `&self` allocation is single-threaded through the cells.
+unsafe impl Send for FrameArena {}
+
+/// Safety: every slot must be initialized.
+unsafe fn assume_init<T>(slots: &mut [MaybeUninit<T>]) -> &mut [T] {
+    &mut *(slots as *mut [MaybeUninit<T>] as *mut [T])
+}
@@ -0,0 +1,251 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::custom_errors::Errors;
+
+/// A microphone or other input a [`CaptureBackend`] can record from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CaptureDeviceInfo {
+    /// Identifies the device to [`CaptureBackend::open`].
+    pub id: String,
+    /// Name to show players.
+    pub name: String,
+    pub is_default: bool,
+    pub max_channels: usize,
+    pub sample_rates: Vec<u32>,
+}
+
+/// The format to record in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CaptureConfig {
+    pub channels: usize,
+    pub sample_rate: u32,
+    /// Seconds of audio the ring buffer holds before new samples are dropped.
+    pub buffer_seconds: u32,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            channels: 1,
+            sample_rate: 48_000,
+            buffer_seconds: 1,
+        }
+    }
+}
+
+/// # Capture Backend
+///
+/// Platform access to recording devices. A backend lists devices and, when one
+/// is opened, pushes its samples into a [`CaptureProducer`] from its own
+/// audio thread until the returned stream is dropped. The engine's `capture`
+/// feature provides [`CpalBackend`](super::cpal_backend::CpalBackend).
+pub trait CaptureBackend {
+    /// Returns the devices that can record.
+    fn devices(&self) -> Result<Vec<CaptureDeviceInfo>, Errors>;
+
+    /// Starts recording from a device, or the default one if `device` is
+    /// `None`.
+    fn open(
+        &mut self,
+        device: Option<&str>,
+        config: CaptureConfig,
+        producer: CaptureProducer,
+    ) -> Result<Box<dyn CaptureStream>, Errors>;
+}
+
+/// A running recording. Dropping it stops the device.
+pub trait CaptureStream: Send {
+    /// Returns the device being recorded.
+    fn device(&self) -> &CaptureDeviceInfo;
+
+    /// Pauses or resumes recording without closing the device.
+    fn set_paused(&mut self, paused: bool);
+}
+
+struct Ring {
+    samples: Box<[AtomicU32]>,
+    // Total samples ever written and read; their difference is what's buffered.
+    written: AtomicUsize,
+    read: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+/// Creates a ring buffer holding `capacity` samples, split into the end the
+/// audio thread writes and the end the game reads.
+pub fn capture_ring(capacity: usize) -> (CaptureProducer, CaptureConsumer) {
+    let ring = Arc::new(Ring {
+        samples: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+        written: AtomicUsize::new(0),
+        read: AtomicUsize::new(0),
+        dropped: AtomicU64::new(0),
+    });
+    (
+        CaptureProducer { ring: ring.clone() },
+        CaptureConsumer { ring },
+    )
+}
+
+/// The writing end of a capture ring buffer, used from the audio thread. It
+/// never blocks or allocates.
+pub struct CaptureProducer {
+    ring: Arc<Ring>,
+}
+
+impl CaptureProducer {
+    /// Appends samples and returns how many fit. Samples that don't fit are
+    /// dropped and counted, since the reader has fallen behind.
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let ring = &self.ring;
+        let capacity = ring.samples.len();
+        let written = ring.written.load(Ordering::Relaxed);
+        let read = ring.read.load(Ordering::Acquire);
+        let count = samples.len().min(capacity - (written - read));
+        for (offset, sample) in samples[..count].iter().enumerate() {
+            ring.samples[(written + offset) % capacity].store(sample.to_bits(), Ordering::Relaxed);
+        }
+        ring.written.store(written + count, Ordering::Release);
+        if count < samples.len() {
+            ring.dropped
+                .fetch_add((samples.len() - count) as u64, Ordering::Relaxed);
+        }
+        count
+    }
+}
+
+/// # Capture Consumer
+///
+/// The reading end of a capture ring buffer. Voice chat reads every sample in
+/// order with [`read`](Self::read); visuals that only care about the sound
+/// right now look at the newest samples with [`latest`](Self::latest) and
+/// throw away the rest.
+///
+/// ## Example
+/// ```ignore
+/// let mut microphone = Microphone::open(&mut backend, None, CaptureConfig::default())?;
+///
+/// // each frame:
+/// let mut wave = [0.0; 512];
+/// microphone.consumer().latest(&mut wave);
+/// bars.set_height(microphone.consumer().rms(512) * 10.0);
+/// microphone.consumer().clear();
+/// ```
+pub struct CaptureConsumer {
+    ring: Arc<Ring>,
+}
+
+impl CaptureConsumer {
+    /// Returns the number of samples waiting to be read.
+    pub fn available(&self) -> usize {
+        let written = self.ring.written.load(Ordering::Acquire);
+        written - self.ring.read.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of samples dropped because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.ring.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Moves the oldest waiting samples into `output` and returns how many
+    /// were read.
+    pub fn read(&mut self, output: &mut [f32]) -> usize {
+        let ring = &self.ring;
+        let capacity = ring.samples.len();
+        let read = ring.read.load(Ordering::Relaxed);
+        let count = output.len().min(self.available());
+        for (offset, sample) in output[..count].iter_mut().enumerate() {
+            *sample =
+                f32::from_bits(ring.samples[(read + offset) % capacity].load(Ordering::Relaxed));
+        }
+        ring.read.store(read + count, Ordering::Release);
+        count
+    }
+
+    /// Copies the newest waiting samples into the end of `output`, without
+    /// reading them, and returns how many were copied.
+    pub fn latest(&self, output: &mut [f32]) -> usize {
+        let ring = &self.ring;
+        let capacity = ring.samples.len();
+        let available = self.available();
+        let count = output.len().min(available);
+        let start = ring.read.load(Ordering::Relaxed) + available - count;
+        let skip = output.len() - count;
+        for (offset, sample) in output[skip..].iter_mut().enumerate() {
+            *sample =
+                f32::from_bits(ring.samples[(start + offset) % capacity].load(Ordering::Relaxed));
+        }
+        count
+    }
+
+    /// Returns the loudness of the newest `samples` samples as a root mean
+    /// square, from 0 for silence to about 0.7 for a full-scale sine.
+    pub fn rms(&self, samples: usize) -> f32 {
+        let mut buffer = vec![0.0; samples];
+        let count = self.latest(&mut buffer);
+        if count == 0 {
+            return 0.0;
+        }
+        let sum: f32 = buffer[samples - count..]
+            .iter()
+            .map(|sample| sample * sample)
+            .sum();
+        (sum / count as f32).sqrt()
+    }
+
+    /// Throws away every waiting sample.
+    pub fn clear(&mut self) {
+        let written = self.ring.written.load(Ordering::Acquire);
+        self.ring.read.store(written, Ordering::Release);
+    }
+}
+
+/// # Microphone
+///
+/// A recording from a capture device and the ring buffer its samples arrive
+/// in. Recording stops when the microphone is dropped.
+pub struct Microphone {
+    stream: Box<dyn CaptureStream>,
+    consumer: CaptureConsumer,
+    config: CaptureConfig,
+}
+
+impl Microphone {
+    /// Starts recording from a device, or the default one if `device` is
+    /// `None`.
+    pub fn open(
+        backend: &mut dyn CaptureBackend,
+        device: Option<&str>,
+        config: CaptureConfig,
+    ) -> Result<Self, Errors> {
+        let capacity =
+            config.channels * config.sample_rate as usize * config.buffer_seconds as usize;
+        let (producer, consumer) = capture_ring(capacity);
+        let stream = backend.open(device, config, producer)?;
+        log::info!("Recording from '{}'", stream.device().name);
+        Ok(Self {
+            stream,
+            consumer,
+            config,
+        })
+    }
+
+    /// Returns the device being recorded.
+    pub fn device(&self) -> &CaptureDeviceInfo {
+        self.stream.device()
+    }
+
+    /// Returns the recording format.
+    pub fn config(&self) -> CaptureConfig {
+        self.config
+    }
+
+    /// Returns the ring buffer the samples arrive in.
+    pub fn consumer(&mut self) -> &mut CaptureConsumer {
+        &mut self.consumer
+    }
+
+    /// Pauses or resumes recording.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.stream.set_paused(paused);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::ecs::{Access, FunctionSystem, System, World};
+
+/// How far the conductor's clock may drift from the track before it jumps
+/// instead of catching up smoothly, in seconds.
+const RESYNC_THRESHOLD: f32 = 0.1;
+
+/// How much of the drift from the track is corrected each second.
+const DRIFT_CORRECTION: f32 = 4.0;
+
+/// One beat of the music.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Beat {
+    /// Beats since the first beat of the track, counting from 0.
+    pub index: i64,
+    /// Bars since the first beat, counting from 0.
+    pub bar: i64,
+    /// Beat within its bar, counting from 0.
+    pub beat_in_bar: u32,
+}
+
+impl Beat {
+    /// Checks if this is the first beat of a bar.
+    pub fn is_downbeat(&self) -> bool {
+        self.beat_in_bar == 0
+    }
+}
+
+/// Sent by the [`conductor_system`] for every beat a [`Conductor`] passes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BeatEvent {
+    pub beat: Beat,
+}
+
+/// # Conductor
+///
+/// Keeps time with a playing track: where it is in beats and bars, and which
+/// beats just passed. The conductor runs its own clock from the frame time so
+/// beats land smoothly, and follows the track's reported position, which audio
+/// streams only update a block at a time, by gently correcting its drift.
+/// Large jumps, such as seeks or loops, resync at once without firing the
+/// skipped beats.
+///
+/// ## Example
+/// ```ignore
+/// let mut conductor = Conductor::new(128.0).with_offset(0.35);
+/// world.insert(music, conductor);
+///
+/// // each frame:
+/// world.get_mut::<Conductor>(music).unwrap().report_position(handle.position());
+///
+/// for event in world.events::<BeatEvent>().iter() {
+///     if event.beat.is_downbeat() {
+///         lights.flash();
+///     }
+/// }
+///
+/// // judging a rhythm-game hit:
+/// let error = conductor.offset_from_nearest_beat(conductor.position());
+/// let perfect = error.abs() < 0.05;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conductor {
+    bpm: f32,
+    beats_per_bar: u32,
+    offset: f32,
+    position: f32,
+    reported: Option<f32>,
+    playing: bool,
+    last_beat: Option<i64>,
+    passed: Vec<Beat>,
+}
+
+impl Conductor {
+    /// Creates a conductor for a track in 4/4 at the given tempo.
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            bpm: bpm.max(1.0),
+            beats_per_bar: 4,
+            offset: 0.0,
+            position: 0.0,
+            reported: None,
+            playing: true,
+            last_beat: None,
+            passed: Vec::new(),
+        }
+    }
+
+    /// Sets the number of beats in a bar.
+    pub fn with_beats_per_bar(mut self, beats_per_bar: u32) -> Self {
+        self.beats_per_bar = beats_per_bar.max(1);
+        self
+    }
+
+    /// Sets the time of the first beat in the track, in seconds.
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Returns the tempo in beats per minute.
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    /// Changes the tempo, keeping the current beat where it is.
+    pub fn set_bpm(&mut self, bpm: f32) {
+        let beat = self.beat();
+        self.bpm = bpm.max(1.0);
+        self.offset = self.position - beat * self.seconds_per_beat();
+    }
+
+    /// Returns the number of beats in a bar.
+    pub fn beats_per_bar(&self) -> u32 {
+        self.beats_per_bar
+    }
+
+    /// Returns the length of a beat in seconds.
+    pub fn seconds_per_beat(&self) -> f32 {
+        60.0 / self.bpm
+    }
+
+    /// Returns the position in the track in seconds.
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Checks if the clock is running.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Starts or stops the clock, to match the track being paused.
+    pub fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+    }
+
+    /// Jumps to a position in seconds without passing the beats in between.
+    pub fn seek(&mut self, position: f32) {
+        self.position = position;
+        self.reported = None;
+        self.last_beat = Some(self.beat().floor() as i64);
+    }
+
+    /// Tells the conductor where the track actually is, in seconds, for the
+    /// next update to follow.
+    pub fn report_position(&mut self, position: f32) {
+        self.reported = Some(position);
+    }
+
+    /// Returns the position in beats since the first beat, with the fraction
+    /// of the current beat. Negative before the first beat.
+    pub fn beat(&self) -> f32 {
+        self.beat_at_time(self.position)
+    }
+
+    /// Returns how far through the current beat the track is, from 0 to 1.
+    pub fn beat_phase(&self) -> f32 {
+        self.beat().rem_euclid(1.0)
+    }
+
+    /// Returns the position in bars since the first beat, with the fraction of
+    /// the current bar.
+    pub fn bar(&self) -> f32 {
+        self.beat() / self.beats_per_bar as f32
+    }
+
+    /// Returns the beat the track is in.
+    pub fn current_beat(&self) -> Beat {
+        self.beat_at(self.beat().floor() as i64)
+    }
+
+    /// Returns the time of a beat in seconds.
+    pub fn time_of_beat(&self, index: i64) -> f32 {
+        self.offset + index as f32 * self.seconds_per_beat()
+    }
+
+    /// Returns how far a time is from the nearest beat in seconds, negative
+    /// when early, for judging hits.
+    pub fn offset_from_nearest_beat(&self, time: f32) -> f32 {
+        let beat = self.beat_at_time(time).round() as i64;
+        time - self.time_of_beat(beat)
+    }
+
+    /// Returns the beats passed by the last update.
+    pub fn passed_beats(&self) -> &[Beat] {
+        &self.passed
+    }
+
+    /// Advances the clock by `delta_time` seconds, follows the reported
+    /// position, and returns the beats passed.
+    pub fn update(&mut self, delta_time: f32) -> &[Beat] {
+        self.passed.clear();
+        let previous = self.position;
+        if self.playing {
+            self.position += delta_time;
+        }
+        if let Some(reported) = self.reported.take() {
+            let drift = reported - self.position;
+            if drift.abs() > RESYNC_THRESHOLD {
+                self.seek(reported);
+                return &self.passed;
+            }
+            self.position += drift * (DRIFT_CORRECTION * delta_time).min(1.0);
+        }
+
+        // Before the first update, a beat exactly at the starting position
+        // still counts as passed.
+        let last = self
+            .last_beat
+            .unwrap_or_else(|| self.beat_at_time(previous).ceil() as i64 - 1);
+        let current = self.beat().floor() as i64;
+        for index in last + 1..=current {
+            self.passed.push(self.beat_at(index));
+        }
+        // Drift correction can pull the clock back over a beat; remembering the
+        // highest beat reached keeps it from firing twice.
+        self.last_beat = Some(last.max(current));
+        &self.passed
+    }
+
+    fn beat_at_time(&self, time: f32) -> f32 {
+        (time - self.offset) / self.seconds_per_beat()
+    }
+
+    fn beat_at(&self, index: i64) -> Beat {
+        let beats_per_bar = self.beats_per_bar as i64;
+        Beat {
+            index,
+            bar: index.div_euclid(beats_per_bar),
+            beat_in_bar: index.rem_euclid(beats_per_bar) as u32,
+        }
+    }
+}
+
+/// Returns a system that advances every [`Conductor`] by the frame time and
+/// sends a [`BeatEvent`] for each beat passed.
+pub fn conductor_system() -> impl System {
+    FunctionSystem::new(
+        "conductor",
+        Access::new().write::<Conductor>().event::<BeatEvent>(),
+        update_conductors,
+    )
+}
+
+/// Advances conductors and sends their beats.
+pub fn update_conductors(world: &World) {
+    let delta_time = world.time().delta();
+    let mut conductors = world.write::<Conductor>();
+    let events = world.events::<BeatEvent>();
+    for (_, conductor) in conductors.iter_mut() {
+        for beat in conductor.update(delta_time) {
+            events.send(BeatEvent { beat: *beat });
+        }
+    }
+}
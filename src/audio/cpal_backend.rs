@@ -0,0 +1,267 @@
+//! A [`CaptureBackend`] on `cpal`, behind the engine's `capture` feature. It
+//! records through ALSA on Linux, WASAPI on Windows and CoreAudio on macOS.
+
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use super::capture::{
+    CaptureBackend, CaptureConfig, CaptureDeviceInfo, CaptureProducer, CaptureStream,
+};
+use crate::custom_errors::Errors;
+
+/// Rates listed in [`CaptureDeviceInfo::sample_rates`] when a device supports
+/// them; devices report ranges rather than single rates.
+const COMMON_SAMPLE_RATES: [u32; 6] = [8_000, 16_000, 22_050, 44_100, 48_000, 96_000];
+
+/// Samples converted at a time in the audio callback.
+const CALLBACK_CHUNK: usize = 1_024;
+
+/// # Cpal Backend
+///
+/// Records from the system's input devices through `cpal`. Devices are
+/// identified by name, as `cpal` has no stable ids.
+///
+/// ## Example
+/// ```ignore
+/// let mut backend = CpalBackend::new();
+/// for device in backend.devices()? {
+///     log::info!("{}{}", device.name, if device.is_default { " (default)" } else { "" });
+/// }
+/// let microphone = Microphone::open(&mut backend, None, CaptureConfig::default())?;
+/// ```
+pub struct CpalBackend {
+    host: cpal::Host,
+}
+
+impl CpalBackend {
+    /// Creates a backend on the platform's default audio host.
+    pub fn new() -> Self {
+        Self {
+            host: cpal::default_host(),
+        }
+    }
+
+    fn default_name(&self) -> Option<String> {
+        self.host
+            .default_input_device()
+            .and_then(|device| device.name().ok())
+    }
+}
+
+impl Default for CpalBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn device_info(device: &cpal::Device, default_name: Option<&str>) -> Option<CaptureDeviceInfo> {
+    let name = device.name().ok()?;
+    let configs: Vec<_> = device.supported_input_configs().ok()?.collect();
+    let sample_rates = COMMON_SAMPLE_RATES
+        .into_iter()
+        .filter(|&rate| {
+            configs
+                .iter()
+                .any(|range| range.min_sample_rate().0 <= rate && rate <= range.max_sample_rate().0)
+        })
+        .collect();
+    Some(CaptureDeviceInfo {
+        id: name.clone(),
+        is_default: default_name == Some(name.as_str()),
+        name,
+        max_channels: configs
+            .iter()
+            .map(|range| range.channels() as usize)
+            .max()
+            .unwrap_or(0),
+        sample_rates,
+    })
+}
+
+/// Builds an input stream that converts samples to `f32` and pushes them into
+/// the ring buffer, without allocating on the audio thread.
+fn build_stream<T: cpal::SizedSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut producer: CaptureProducer,
+    convert: fn(T) -> f32,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    let mut scratch = vec![0.0f32; CALLBACK_CHUNK];
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            for chunk in data.chunks(CALLBACK_CHUNK) {
+                for (out, &sample) in scratch.iter_mut().zip(chunk) {
+                    *out = convert(sample);
+                }
+                producer.push(&scratch[..chunk.len()]);
+            }
+        },
+        |error| log::warn!("Recording error: {}", error),
+        None,
+    )
+}
+
+impl CaptureBackend for CpalBackend {
+    fn devices(&self) -> Result<Vec<CaptureDeviceInfo>, Errors> {
+        let default_name = self.default_name();
+        let devices = self
+            .host
+            .input_devices()
+            .map_err(|error| Errors::Capture(error.to_string()))?;
+        Ok(devices
+            .filter_map(|device| device_info(&device, default_name.as_deref()))
+            .collect())
+    }
+
+    fn open(
+        &mut self,
+        device: Option<&str>,
+        config: CaptureConfig,
+        producer: CaptureProducer,
+    ) -> Result<Box<dyn CaptureStream>, Errors> {
+        let default_name = self.default_name();
+        let wanted = device;
+        let device = match wanted {
+            None => self.host.default_input_device(),
+            Some(id) => self
+                .host
+                .input_devices()
+                .map_err(|error| Errors::Capture(error.to_string()))?
+                .find(|device| device.name().ok().as_deref() == Some(id)),
+        }
+        .ok_or_else(|| {
+            Errors::Capture(match wanted {
+                Some(id) => format!("no input device '{}'", id),
+                None => "no default input device".to_string(),
+            })
+        })?;
+        let info = device_info(&device, default_name.as_deref())
+            .ok_or_else(|| Errors::Capture("the input device went away".to_string()))?;
+
+        let rate = config.sample_rate;
+        let format = device
+            .supported_input_configs()
+            .map_err(|error| Errors::Capture(error.to_string()))?
+            .filter(|range| {
+                range.channels() as usize == config.channels
+                    && range.min_sample_rate().0 <= rate
+                    && rate <= range.max_sample_rate().0
+            })
+            .map(|range| range.sample_format())
+            .min_by_key(|format| match format {
+                cpal::SampleFormat::F32 => 0,
+                cpal::SampleFormat::I16 => 1,
+                cpal::SampleFormat::U16 => 2,
+                _ => 3,
+            })
+            .ok_or_else(|| {
+                Errors::Capture(format!(
+                    "'{}' can't record {} channels at {} Hz",
+                    info.name, config.channels, rate
+                ))
+            })?;
+        let stream_config = cpal::StreamConfig {
+            channels: config.channels as cpal::ChannelCount,
+            sample_rate: cpal::SampleRate(rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        // cpal streams can't move between threads, so each lives on a thread
+        // of its own that the returned handle talks to.
+        let (started_sender, started) = mpsc::sync_channel(1);
+        let (commands, receiver) = mpsc::channel::<bool>();
+        let thread = thread::Builder::new()
+            .name("nyanko-capture".to_string())
+            .spawn(move || {
+                let stream = match format {
+                    cpal::SampleFormat::F32 => {
+                        build_stream(&device, &stream_config, producer, |sample: f32| sample)
+                    }
+                    cpal::SampleFormat::I16 => {
+                        build_stream(&device, &stream_config, producer, |sample: i16| {
+                            sample as f32 / 32_768.0
+                        })
+                    }
+                    cpal::SampleFormat::U16 => {
+                        build_stream(&device, &stream_config, producer, |sample: u16| {
+                            (sample as f32 - 32_768.0) / 32_768.0
+                        })
+                    }
+                    other => {
+                        let _ = started_sender.send(Err(format!("unsupported format {:?}", other)));
+                        return;
+                    }
+                };
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(error) => {
+                        let _ = started_sender.send(Err(error.to_string()));
+                        return;
+                    }
+                };
+                if let Err(error) = stream.play() {
+                    let _ = started_sender.send(Err(error.to_string()));
+                    return;
+                }
+                let _ = started_sender.send(Ok(()));
+
+                // Runs until the handle is dropped and the channel closes.
+                for paused in receiver {
+                    let result = if paused {
+                        stream.pause().map_err(|error| error.to_string())
+                    } else {
+                        stream.play().map_err(|error| error.to_string())
+                    };
+                    if let Err(error) = result {
+                        log::warn!("Failed to pause or resume recording: {}", error);
+                    }
+                }
+            })
+            .map_err(|error| Errors::Capture(error.to_string()))?;
+
+        let started = started
+            .recv()
+            .unwrap_or_else(|_| Err("the recording thread stopped".to_string()));
+        if let Err(message) = started {
+            let _ = thread.join();
+            return Err(Errors::Capture(format!("{}: {}", info.name, message)));
+        }
+        Ok(Box::new(CpalStream {
+            device: info,
+            commands: Some(commands),
+            thread: Some(thread),
+        }))
+    }
+}
+
+/// A recording running on its own thread.
+struct CpalStream {
+    device: CaptureDeviceInfo,
+    commands: Option<Sender<bool>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureStream for CpalStream {
+    fn device(&self) -> &CaptureDeviceInfo {
+        &self.device
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(paused);
+        }
+    }
+}
+
+impl Drop for CpalStream {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread, which drops the stream.
+        self.commands = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Compressed formats for [`AudioStream`](super::stream::AudioStream), each
+//! behind a cargo feature: `ogg` for Ogg Vorbis through `lewton` and `mp3`
+//! through `minimp3`.
+
+#[cfg(any(feature = "ogg", feature = "mp3"))]
+use std::fs::File;
+#[cfg(any(feature = "ogg", feature = "mp3"))]
+use std::io::BufReader;
+
+#[cfg(any(feature = "ogg", feature = "mp3"))]
+use super::stream::StreamDecoder;
+#[cfg(any(feature = "ogg", feature = "mp3", test))]
+use crate::custom_errors::Errors;
+
+/// Samples of the last decoded packet not handed out yet.
+#[cfg(any(feature = "ogg", feature = "mp3", test))]
+#[derive(Debug, Default)]
+struct Pending {
+    samples: Vec<i16>,
+    read: usize,
+}
+
+#[cfg(any(feature = "ogg", feature = "mp3", test))]
+impl Pending {
+    /// Replaces the samples with a new packet.
+    fn set(&mut self, samples: Vec<i16>) {
+        self.read = 0;
+        self.samples = samples;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.read >= self.samples.len()
+    }
+
+    /// Copies as many samples as fit into `output` and returns how many.
+    fn take_into(&mut self, output: &mut [f32]) -> usize {
+        let samples = &self.samples[self.read..];
+        let count = samples.len().min(output.len());
+        for (out, &sample) in output.iter_mut().zip(&samples[..count]) {
+            *out = sample as f32 / 32_768.0;
+        }
+        self.read += count;
+        count
+    }
+}
+
+/// Fills whole frames of `output` from `pending`, calling `next` for more
+/// packets until it returns `false`.
+#[cfg(any(feature = "ogg", feature = "mp3", test))]
+fn fill(
+    pending: &mut Pending,
+    output: &mut [f32],
+    channels: usize,
+    mut next: impl FnMut(&mut Pending) -> Result<bool, Errors>,
+) -> Result<usize, Errors> {
+    let wanted = output.len() / channels * channels;
+    let mut written = 0;
+    while written < wanted {
+        if pending.is_empty() {
+            if !next(pending)? {
+                break;
+            }
+            continue;
+        }
+        written += pending.take_into(&mut output[written..wanted]);
+    }
+    Ok(written)
+}
+
+/// Returns the granule position of the last Ogg page in a file, which for
+/// Vorbis is the length in frames.
+#[cfg(feature = "ogg")]
+fn ogg_length(file: &mut File) -> Option<u64> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    // A page is at most 65 kB, so the last one starts within the tail.
+    const TAIL: u64 = 65_536 + 282;
+    let size = file.seek(SeekFrom::End(0)).ok()?;
+    file.seek(SeekFrom::Start(size.saturating_sub(TAIL))).ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    file.seek(SeekFrom::Start(0)).ok()?;
+
+    let start = tail.windows(4).rposition(|bytes| bytes == b"OggS")?;
+    let granule = tail.get(start + 6..start + 14)?;
+    Some(u64::from_le_bytes(granule.try_into().ok()?))
+}
+
+/// Decodes Ogg Vorbis files. Seeking lands on the start of the Ogg page
+/// holding the frame, a few thousand frames early at most.
+#[cfg(feature = "ogg")]
+pub struct OggDecoder {
+    path: String,
+    reader: lewton::inside_ogg::OggStreamReader<BufReader<File>>,
+    channels: usize,
+    sample_rate: u32,
+    length: Option<u64>,
+    pending: Pending,
+}
+
+#[cfg(feature = "ogg")]
+impl OggDecoder {
+    /// Opens an Ogg Vorbis file.
+    pub fn open(path: &str) -> Result<Self, Errors> {
+        let error = |message: String| Errors::InvalidAudio(format!("{}: {}", path, message));
+        let mut file = File::open(path).map_err(|io| error(io.to_string()))?;
+        let length = ogg_length(&mut file);
+        let reader = lewton::inside_ogg::OggStreamReader::new(BufReader::new(file))
+            .map_err(|vorbis| error(vorbis.to_string()))?;
+        let channels = reader.ident_hdr.audio_channels as usize;
+        if channels == 0 {
+            return Err(error("no channels".to_string()));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            reader,
+            channels,
+            length,
+            pending: Pending::default(),
+        })
+    }
+
+    fn error(&self, message: String) -> Errors {
+        Errors::InvalidAudio(format!("{}: {}", self.path, message))
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl StreamDecoder for OggDecoder {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    fn decode(&mut self, output: &mut [f32]) -> Result<usize, Errors> {
+        let Self {
+            path,
+            reader,
+            pending,
+            channels,
+            ..
+        } = self;
+        fill(pending, output, *channels, |pending| {
+            match reader.read_dec_packet_itl() {
+                Ok(Some(samples)) => {
+                    pending.set(samples);
+                    Ok(true)
+                }
+                Ok(None) => Ok(false),
+                Err(vorbis) => Err(Errors::InvalidAudio(format!("{}: {}", path, vorbis))),
+            }
+        })
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<(), Errors> {
+        self.reader
+            .seek_absgp_pg(frame)
+            .map_err(|vorbis| self.error(vorbis.to_string()))?;
+        self.pending = Pending::default();
+        Ok(())
+    }
+}
+
+/// Decodes MP3 files. The length isn't known up front, and seeking decodes
+/// from the start of the file, or from the current position when seeking
+/// forward, up to the frame.
+#[cfg(feature = "mp3")]
+pub struct Mp3Decoder {
+    path: String,
+    decoder: minimp3::Decoder<BufReader<File>>,
+    channels: usize,
+    sample_rate: u32,
+    /// Frame the next decoded sample belongs to.
+    frame: u64,
+    pending: Pending,
+}
+
+#[cfg(feature = "mp3")]
+impl Mp3Decoder {
+    /// Opens an MP3 file.
+    pub fn open(path: &str) -> Result<Self, Errors> {
+        let mut decoder = Self::reader(path)?;
+        let mut pending = Pending::default();
+        let first = next_mp3_frame(path, &mut decoder)?
+            .ok_or_else(|| Errors::InvalidAudio(format!("{}: no MP3 frames", path)))?;
+        let (channels, sample_rate) = (first.channels, first.sample_rate.max(0) as u32);
+        if channels == 0 {
+            return Err(Errors::InvalidAudio(format!("{}: no channels", path)));
+        }
+        pending.set(first.data);
+        Ok(Self {
+            path: path.to_string(),
+            decoder,
+            channels,
+            sample_rate,
+            frame: 0,
+            pending,
+        })
+    }
+
+    fn reader(path: &str) -> Result<minimp3::Decoder<BufReader<File>>, Errors> {
+        let file =
+            File::open(path).map_err(|io| Errors::InvalidAudio(format!("{}: {}", path, io)))?;
+        Ok(minimp3::Decoder::new(BufReader::new(file)))
+    }
+}
+
+/// Decodes the next MP3 frame, skipping data that isn't audio such as ID3
+/// tags. Returns `None` at the end of the file.
+#[cfg(feature = "mp3")]
+fn next_mp3_frame(
+    path: &str,
+    decoder: &mut minimp3::Decoder<BufReader<File>>,
+) -> Result<Option<minimp3::Frame>, Errors> {
+    loop {
+        match decoder.next_frame() {
+            Ok(frame) => return Ok(Some(frame)),
+            Err(minimp3::Error::SkippedData) => continue,
+            Err(minimp3::Error::Eof | minimp3::Error::InsufficientData) => return Ok(None),
+            Err(minimp3::Error::Io(io)) => {
+                return Err(Errors::InvalidAudio(format!("{}: {}", path, io)))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl StreamDecoder for Mp3Decoder {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn length(&self) -> Option<u64> {
+        None
+    }
+
+    fn decode(&mut self, output: &mut [f32]) -> Result<usize, Errors> {
+        let Self {
+            path,
+            decoder,
+            pending,
+            channels,
+            ..
+        } = self;
+        let written = fill(pending, output, *channels, |pending| {
+            Ok(match next_mp3_frame(path, decoder)? {
+                Some(frame) => {
+                    pending.set(frame.data);
+                    true
+                }
+                None => false,
+            })
+        })?;
+        self.frame += (written / self.channels) as u64;
+        Ok(written)
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<(), Errors> {
+        if frame < self.frame {
+            self.decoder = Self::reader(&self.path)?;
+            self.pending = Pending::default();
+            self.frame = 0;
+        }
+        // Decode and drop everything before the frame.
+        while self.frame < frame {
+            if self.pending.is_empty() {
+                match next_mp3_frame(&self.path, &mut self.decoder)? {
+                    Some(decoded) => self.pending.set(decoded.data),
+                    None => break,
+                }
+            }
+            let left = (self.pending.samples.len() - self.pending.read) / self.channels;
+            let skip = (left as u64).min(frame - self.frame);
+            self.pending.read += skip as usize * self.channels;
+            self.frame += skip;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_samples_fill_whole_frames_across_packets() {
+        let mut packets = vec![vec![16_384i16; 3], vec![-16_384; 4]].into_iter();
+        let mut pending = Pending::default();
+        pending.set(vec![32_767, 32_767]);
+
+        let mut output = [9.0f32; 7];
+        let written = fill(&mut pending, &mut output, 2, |pending| {
+            Ok(match packets.next() {
+                Some(samples) => {
+                    pending.set(samples);
+                    true
+                }
+                None => false,
+            })
+        })
+        .unwrap();
+
+        assert_eq!(written, 6);
+        assert!((output[0] - 32_767.0 / 32_768.0).abs() < 1e-6);
+        assert_eq!(&output[2..5], &[0.5, 0.5, 0.5]);
+        assert_eq!(output[5], -0.5);
+        assert_eq!(output[6], 9.0);
+        assert_eq!(pending.samples.len() - pending.read, 3);
+    }
+}
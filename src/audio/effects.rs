@@ -0,0 +1,684 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+/// Cutoff frequency that makes sound muffled, as if heard underwater.
+pub const UNDERWATER_CUTOFF: f32 = 600.0;
+
+/// Highest cutoff a [`Filter`] reaches, above which it changes nothing audible.
+const MAX_CUTOFF: f32 = 20_000.0;
+
+/// Seconds a [`Filter`] takes to glide to a new cutoff, so sweeps don't click.
+const CUTOFF_GLIDE: f32 = 0.05;
+
+/// Converts decibels to a linear gain.
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Converts a linear gain to decibels.
+pub fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(1e-6).log10()
+}
+
+#[doc(hidden)]
+pub trait AsAnyMut {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAnyMut for T {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// # Audio Effect
+///
+/// Processes blocks of interleaved samples in place. Effects keep their own
+/// state, such as filter history or delay lines, between blocks, and are
+/// created for a fixed sample rate.
+pub trait AudioEffect: AsAnyMut + Send {
+    /// Processes interleaved samples with `channels` samples per frame.
+    fn process(&mut self, samples: &mut [f32], channels: usize);
+
+    /// Clears the effect's state, for example after seeking.
+    fn reset(&mut self) {}
+}
+
+/// The response of a [`Filter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FilterKind {
+    /// Keeps frequencies below the cutoff, for occlusion and underwater.
+    LowPass,
+    /// Keeps frequencies above the cutoff, for radios and telephones.
+    HighPass,
+    /// Keeps frequencies around the cutoff.
+    BandPass,
+}
+
+/// # Filter
+///
+/// A biquad filter whose cutoff can be changed while playing, for example to
+/// muffle sounds behind walls. Cutoff changes glide over a few milliseconds.
+///
+/// ## Example
+/// ```ignore
+/// let mut muffle = Filter::low_pass(48_000, 20_000.0);
+/// muffle.set_cutoff(Filter::occlusion_cutoff(occlusion));
+/// muffle.process(&mut samples, 2);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    kind: FilterKind,
+    sample_rate: f32,
+    cutoff: f32,
+    target_cutoff: f32,
+    q: f32,
+    coefficients: [f32; 5],
+    // x1, x2, y1, y2 per channel.
+    history: Vec<[f32; 4]>,
+}
+
+impl Filter {
+    /// Creates a filter.
+    pub fn new(kind: FilterKind, sample_rate: u32, cutoff: f32) -> Self {
+        let mut filter = Self {
+            kind,
+            sample_rate: sample_rate as f32,
+            cutoff,
+            target_cutoff: cutoff,
+            q: std::f32::consts::FRAC_1_SQRT_2,
+            coefficients: [0.0; 5],
+            history: Vec::new(),
+        };
+        filter.update_coefficients();
+        filter
+    }
+
+    /// Creates a low-pass filter.
+    pub fn low_pass(sample_rate: u32, cutoff: f32) -> Self {
+        Self::new(FilterKind::LowPass, sample_rate, cutoff)
+    }
+
+    /// Creates a high-pass filter.
+    pub fn high_pass(sample_rate: u32, cutoff: f32) -> Self {
+        Self::new(FilterKind::HighPass, sample_rate, cutoff)
+    }
+
+    /// Creates a band-pass filter.
+    pub fn band_pass(sample_rate: u32, cutoff: f32) -> Self {
+        Self::new(FilterKind::BandPass, sample_rate, cutoff)
+    }
+
+    /// Sets the resonance. The default, about 0.707, has no peak.
+    pub fn with_q(mut self, q: f32) -> Self {
+        self.q = q.max(0.05);
+        self.update_coefficients();
+        self
+    }
+
+    /// Returns the filter's response.
+    pub fn kind(&self) -> FilterKind {
+        self.kind
+    }
+
+    /// Returns the cutoff the filter is gliding to, in hertz.
+    pub fn target_cutoff(&self) -> f32 {
+        self.target_cutoff
+    }
+
+    /// Sets the cutoff in hertz.
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.target_cutoff = cutoff;
+    }
+
+    /// Returns the low-pass cutoff for an occlusion amount from 0, fully
+    /// audible, to 1, behind a thick wall.
+    pub fn occlusion_cutoff(occlusion: f32) -> f32 {
+        // Interpolated in octaves so the muffling sounds even.
+        let occlusion = occlusion.clamp(0.0, 1.0);
+        MAX_CUTOFF * (400.0 / MAX_CUTOFF).powf(occlusion)
+    }
+
+    fn update_coefficients(&mut self) {
+        let nyquist = self.sample_rate * 0.5;
+        let cutoff = self.cutoff.clamp(10.0, nyquist * 0.99);
+        let omega = 2.0 * PI * cutoff / self.sample_rate;
+        let (sin, cos) = omega.sin_cos();
+        let alpha = sin / (2.0 * self.q);
+        let (b0, b1, b2) = match self.kind {
+            FilterKind::LowPass => ((1.0 - cos) * 0.5, 1.0 - cos, (1.0 - cos) * 0.5),
+            FilterKind::HighPass => ((1.0 + cos) * 0.5, -(1.0 + cos), (1.0 + cos) * 0.5),
+            FilterKind::BandPass => (alpha, 0.0, -alpha),
+        };
+        let a0 = 1.0 + alpha;
+        self.coefficients = [
+            b0 / a0,
+            b1 / a0,
+            b2 / a0,
+            -2.0 * cos / a0,
+            (1.0 - alpha) / a0,
+        ];
+    }
+}
+
+impl AudioEffect for Filter {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+        if self.cutoff != self.target_cutoff {
+            let frames = (samples.len() / channels) as f32;
+            let step = (frames / (self.sample_rate * CUTOFF_GLIDE)).min(1.0);
+            self.cutoff += (self.target_cutoff - self.cutoff) * step;
+            if (self.cutoff - self.target_cutoff).abs() < 1.0 {
+                self.cutoff = self.target_cutoff;
+            }
+            self.update_coefficients();
+        }
+        self.history.resize(channels, [0.0; 4]);
+
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        for frame in samples.chunks_exact_mut(channels) {
+            for (sample, history) in frame.iter_mut().zip(&mut self.history) {
+                let [x1, x2, y1, y2] = *history;
+                let x = *sample;
+                let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+                *history = [x, x1, y, y1];
+                *sample = y;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.cutoff = self.target_cutoff;
+        self.update_coefficients();
+    }
+}
+
+/// How a [`Reverb`] sounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReverbSettings {
+    /// Size of the space from 0 to 1; larger rooms ring longer.
+    pub room_size: f32,
+    /// How quickly high frequencies die out, from 0 to 1.
+    pub damping: f32,
+    /// Level of the reverberated sound.
+    pub wet: f32,
+    /// Level of the original sound.
+    pub dry: f32,
+    /// Stereo spread of the reverb from 0, mono, to 1.
+    pub width: f32,
+}
+
+impl ReverbSettings {
+    /// No reverb at all.
+    pub fn off() -> Self {
+        Self {
+            room_size: 0.0,
+            damping: 0.5,
+            wet: 0.0,
+            dry: 1.0,
+            width: 1.0,
+        }
+    }
+
+    /// A small furnished room.
+    pub fn small_room() -> Self {
+        Self {
+            room_size: 0.35,
+            damping: 0.7,
+            wet: 0.2,
+            dry: 1.0,
+            width: 0.8,
+        }
+    }
+
+    /// A large hall.
+    pub fn hall() -> Self {
+        Self {
+            room_size: 0.8,
+            damping: 0.4,
+            wet: 0.35,
+            dry: 0.9,
+            width: 1.0,
+        }
+    }
+
+    /// A cave with long, bright echoes.
+    pub fn cave() -> Self {
+        Self {
+            room_size: 0.95,
+            damping: 0.15,
+            wet: 0.45,
+            dry: 0.85,
+            width: 1.0,
+        }
+    }
+
+    /// Interpolates between two settings, for crossing from one space to another.
+    pub fn lerp(&self, other: &ReverbSettings, t: f32) -> ReverbSettings {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        ReverbSettings {
+            room_size: mix(self.room_size, other.room_size),
+            damping: mix(self.damping, other.damping),
+            wet: mix(self.wet, other.wet),
+            dry: mix(self.dry, other.dry),
+            width: mix(self.width, other.width),
+        }
+    }
+}
+
+impl Default for ReverbSettings {
+    fn default() -> Self {
+        Self::off()
+    }
+}
+
+// Freeverb's tunings, in samples at 44.1 kHz.
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+const STEREO_SPREAD: usize = 23;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filtered: f32,
+}
+
+impl Comb {
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filtered = output * (1.0 - damping) + self.filtered * damping;
+        self.buffer[self.index] = input + self.filtered * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ReverbChannel {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl ReverbChannel {
+    fn new(sample_rate: u32, spread: usize) -> Self {
+        let scale = sample_rate as f32 / 44_100.0;
+        let length = |tuning: usize| (((tuning + spread) as f32 * scale) as usize).max(1);
+        Self {
+            combs: COMB_TUNINGS
+                .iter()
+                .map(|&tuning| Comb {
+                    buffer: vec![0.0; length(tuning)],
+                    index: 0,
+                    filtered: 0.0,
+                })
+                .collect(),
+            allpasses: ALLPASS_TUNINGS
+                .iter()
+                .map(|&tuning| Allpass {
+                    buffer: vec![0.0; length(tuning)],
+                    index: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let mut output = 0.0;
+        for comb in &mut self.combs {
+            output += comb.process(input, feedback, damping);
+        }
+        for allpass in &mut self.allpasses {
+            output = allpass.process(output);
+        }
+        output
+    }
+
+    fn clear(&mut self) {
+        for comb in &mut self.combs {
+            comb.buffer.fill(0.0);
+            comb.filtered = 0.0;
+        }
+        for allpass in &mut self.allpasses {
+            allpass.buffer.fill(0.0);
+        }
+    }
+}
+
+/// # Reverb
+///
+/// A Freeverb-style room reverb. Mono input gets a mono reverb; for two or
+/// more channels the first two are treated as left and right and any others
+/// pass through untouched. Settings can change while playing, which is how
+/// [`ReverbZone`](super::ReverbZone)s blend as the listener moves.
+///
+/// ## Example
+/// ```ignore
+/// let mut reverb = Reverb::new(48_000, ReverbSettings::hall());
+/// reverb.set_settings(listener.reverb);
+/// reverb.process(&mut samples, 2);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reverb {
+    settings: ReverbSettings,
+    left: ReverbChannel,
+    right: ReverbChannel,
+}
+
+impl Reverb {
+    /// Creates a reverb.
+    pub fn new(sample_rate: u32, settings: ReverbSettings) -> Self {
+        Self {
+            settings,
+            left: ReverbChannel::new(sample_rate, 0),
+            right: ReverbChannel::new(sample_rate, STEREO_SPREAD),
+        }
+    }
+
+    /// Returns the current settings.
+    pub fn settings(&self) -> &ReverbSettings {
+        &self.settings
+    }
+
+    /// Sets how the reverb sounds.
+    pub fn set_settings(&mut self, settings: ReverbSettings) {
+        self.settings = settings;
+    }
+}
+
+impl AudioEffect for Reverb {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+        let settings = self.settings;
+        let feedback = settings.room_size.clamp(0.0, 1.0) * 0.28 + 0.7;
+        let damping = settings.damping.clamp(0.0, 1.0) * 0.4;
+        // Freeverb's fixed input gain keeps the eight summed combs in range.
+        let input_gain = 0.015;
+        let wet = settings.wet * 3.0;
+        let wet_same = wet * (settings.width * 0.5 + 0.5);
+        let wet_cross = wet * (0.5 - settings.width * 0.5);
+
+        for frame in samples.chunks_exact_mut(channels) {
+            if channels == 1 {
+                let input = frame[0];
+                let output = self.left.process(input * input_gain, feedback, damping);
+                frame[0] = output * wet + input * settings.dry;
+                continue;
+            }
+            let (left, right) = (frame[0], frame[1]);
+            let input = (left + right) * input_gain;
+            let out_left = self.left.process(input, feedback, damping);
+            let out_right = self.right.process(input, feedback, damping);
+            frame[0] = out_left * wet_same + out_right * wet_cross + left * settings.dry;
+            frame[1] = out_right * wet_same + out_left * wet_cross + right * settings.dry;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.left.clear();
+        self.right.clear();
+    }
+}
+
+/// # Pitch Shift
+///
+/// Changes pitch without changing speed, using two crossfaded read heads over
+/// a short delay line. Small shifts sound natural; large ones get a slight
+/// flutter. For changing pitch and speed together, play the source faster
+/// instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PitchShift {
+    ratio: f32,
+    window: usize,
+    phase: f32,
+    write: usize,
+    buffers: Vec<Vec<f32>>,
+}
+
+impl PitchShift {
+    /// Creates a pitch shift by the given number of semitones.
+    pub fn new(sample_rate: u32, semitones: f32) -> Self {
+        // About 40 ms: long enough for low voices, short enough to avoid echoes.
+        let window = ((sample_rate as f32 * 0.04) as usize).max(16);
+        let mut shift = Self {
+            ratio: 1.0,
+            window,
+            phase: 0.0,
+            write: 0,
+            buffers: Vec::new(),
+        };
+        shift.set_semitones(semitones);
+        shift
+    }
+
+    /// Returns the shift in semitones.
+    pub fn semitones(&self) -> f32 {
+        12.0 * self.ratio.log2()
+    }
+
+    /// Sets the shift in semitones.
+    pub fn set_semitones(&mut self, semitones: f32) {
+        self.ratio = 2f32.powf(semitones / 12.0);
+    }
+
+    fn read(buffer: &[f32], write: usize, delay: f32) -> f32 {
+        let length = buffer.len();
+        let position = (write + length) as f32 - delay;
+        let index = position.floor();
+        let fraction = position - index;
+        let a = buffer[index as usize % length];
+        let b = buffer[(index as usize + 1) % length];
+        a + (b - a) * fraction
+    }
+}
+
+impl AudioEffect for PitchShift {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if channels == 0 || self.ratio == 1.0 {
+            return;
+        }
+        let length = self.window + 2;
+        self.buffers.resize_with(channels, || vec![0.0; length]);
+        let window = self.window as f32;
+        let step = (1.0 - self.ratio) / window;
+
+        for frame in samples.chunks_exact_mut(channels) {
+            let phase_b = (self.phase + 0.5) % 1.0;
+            // Each head fades in and out over its sweep, so the jump back to
+            // the start of the window is silent.
+            let gain_a = 1.0 - (2.0 * self.phase - 1.0).abs();
+            let gain_b = 1.0 - (2.0 * phase_b - 1.0).abs();
+            for (sample, buffer) in frame.iter_mut().zip(&mut self.buffers) {
+                buffer[self.write] = *sample;
+                let a = Self::read(buffer, self.write, self.phase * window);
+                let b = Self::read(buffer, self.write, phase_b * window);
+                *sample = a * gain_a + b * gain_b;
+            }
+            self.write = (self.write + 1) % length;
+            self.phase = (self.phase + step).rem_euclid(1.0);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffers.clear();
+        self.phase = 0.0;
+        self.write = 0;
+    }
+}
+
+/// # Limiter
+///
+/// Keeps peaks under a ceiling by turning the whole signal down as they arrive
+/// and letting it recover slowly, so loud moments don't clip. Belongs last on
+/// the master bus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limiter {
+    ceiling: f32,
+    release: f32,
+    sample_rate: f32,
+    gain: f32,
+}
+
+impl Limiter {
+    /// Creates a limiter with a ceiling in decibels, usually just under 0.
+    pub fn new(sample_rate: u32, ceiling_db: f32) -> Self {
+        Self {
+            ceiling: db_to_gain(ceiling_db),
+            release: 0.2,
+            sample_rate: sample_rate as f32,
+            gain: 1.0,
+        }
+    }
+
+    /// Sets how many seconds the gain takes to recover after a peak.
+    pub fn with_release(mut self, release: f32) -> Self {
+        self.release = release.max(0.001);
+        self
+    }
+
+    /// Sets the ceiling in decibels.
+    pub fn set_ceiling(&mut self, ceiling_db: f32) {
+        self.ceiling = db_to_gain(ceiling_db);
+    }
+
+    /// Returns how far the signal is currently turned down, in decibels.
+    pub fn gain_reduction_db(&self) -> f32 {
+        -gain_to_db(self.gain)
+    }
+}
+
+impl AudioEffect for Limiter {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+        let recovery = 1.0 - (-1.0 / (self.release * self.sample_rate)).exp();
+        for frame in samples.chunks_exact_mut(channels) {
+            let peak = frame
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            let target = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+            if target < self.gain {
+                self.gain = target;
+            } else {
+                self.gain += (target - self.gain) * recovery;
+            }
+            for sample in frame.iter_mut() {
+                *sample = (*sample * self.gain).clamp(-self.ceiling, self.ceiling);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
+
+/// # Effect Chain
+///
+/// Effects applied one after another, each of which can be bypassed. Effects
+/// are looked up by the index [`push`](Self::push) returns to change their
+/// parameters while playing.
+///
+/// ## Example
+/// ```ignore
+/// let mut chain = EffectChain::new();
+/// let muffle = chain.push(Filter::low_pass(48_000, 20_000.0));
+/// chain.push(Reverb::new(48_000, ReverbSettings::small_room()));
+///
+/// if let Some(filter) = chain.get_mut::<Filter>(muffle) {
+///     filter.set_cutoff(UNDERWATER_CUTOFF);
+/// }
+/// chain.process(&mut samples, 2);
+/// ```
+#[derive(Default)]
+pub struct EffectChain {
+    effects: Vec<(Box<dyn AudioEffect>, bool)>,
+}
+
+impl EffectChain {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an effect at the end of the chain and returns its index.
+    pub fn push(&mut self, effect: impl AudioEffect + 'static) -> usize {
+        self.effects.push((Box::new(effect), false));
+        self.effects.len() - 1
+    }
+
+    /// Returns the number of effects.
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// Checks if the chain has no effects.
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Returns an effect by index, if it is a `T`.
+    pub fn get_mut<T: AudioEffect + 'static>(&mut self, index: usize) -> Option<&mut T> {
+        self.effects
+            .get_mut(index)
+            .and_then(|(effect, _)| effect.as_mut().as_any_mut().downcast_mut())
+    }
+
+    /// Sets whether an effect is skipped.
+    pub fn set_bypassed(&mut self, index: usize, bypassed: bool) {
+        if let Some((effect, current)) = self.effects.get_mut(index) {
+            if bypassed && !*current {
+                effect.reset();
+            }
+            *current = bypassed;
+        }
+    }
+
+    /// Checks if an effect is skipped.
+    pub fn is_bypassed(&self, index: usize) -> bool {
+        self.effects
+            .get(index)
+            .is_some_and(|(_, bypassed)| *bypassed)
+    }
+
+    /// Runs interleaved samples through every effect that isn't bypassed.
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) {
+        for (effect, bypassed) in &mut self.effects {
+            if !*bypassed {
+                effect.process(samples, channels);
+            }
+        }
+    }
+
+    /// Clears the state of every effect.
+    pub fn reset(&mut self) {
+        for (effect, _) in &mut self.effects {
+            effect.reset();
+        }
+    }
+}
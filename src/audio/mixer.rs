@@ -0,0 +1,585 @@
+use super::effects::{db_to_gain, EffectChain, Limiter};
+use super::source::{AudioSource, Resampler};
+use crate::cvars::{CVar, CVars};
+
+/// The mixer's output is always interleaved stereo.
+pub const MIXER_CHANNELS: usize = 2;
+
+/// Identifies a bus in a [`Mixer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BusId(usize);
+
+impl BusId {
+    /// The bus everything ends up in.
+    pub const MASTER: BusId = BusId(0);
+    pub const MUSIC: BusId = BusId(1);
+    /// Sound effects.
+    pub const SFX: BusId = BusId(2);
+    /// Dialogue and barks.
+    pub const VOICE: BusId = BusId(3);
+}
+
+/// Identifies a playing source in a [`Mixer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SourceId(u64);
+
+/// # Ducking
+///
+/// Side-chain ducking: while the `trigger` bus is louder than `threshold`, the
+/// `target` bus is turned down by `amount_db`, for example music under
+/// dialogue.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    pub trigger: BusId,
+    pub target: BusId,
+    /// How far the target is turned down, in decibels.
+    pub amount_db: f32,
+    /// Peak level of the trigger, as a linear gain, that starts the ducking.
+    pub threshold: f32,
+    /// Seconds to duck down.
+    pub attack: f32,
+    /// Seconds to come back up after the trigger goes quiet.
+    pub release: f32,
+}
+
+impl Ducking {
+    /// Creates ducking of 10 dB that comes in fast and recovers over half a second.
+    pub fn new(trigger: BusId, target: BusId) -> Self {
+        Self {
+            trigger,
+            target,
+            amount_db: 10.0,
+            threshold: 0.02,
+            attack: 0.05,
+            release: 0.5,
+        }
+    }
+
+    /// Sets how far the target is turned down, in decibels.
+    pub fn with_amount_db(mut self, amount_db: f32) -> Self {
+        self.amount_db = amount_db;
+        self
+    }
+
+    /// Sets the attack and release times in seconds.
+    pub fn with_timing(mut self, attack: f32, release: f32) -> Self {
+        self.attack = attack;
+        self.release = release;
+        self
+    }
+}
+
+struct Bus {
+    name: String,
+    parent: Option<BusId>,
+    volume: f32,
+    muted: bool,
+    effects: EffectChain,
+    buffer: Vec<f32>,
+    // Gain applied at the end of the last block, ramped from to avoid clicks.
+    gain: f32,
+    level: f32,
+    cvar_volume: Option<f64>,
+    cvar_muted: Option<bool>,
+}
+
+struct PlayingSource {
+    id: SourceId,
+    source: Box<dyn AudioSource>,
+    bus: BusId,
+    volume: f32,
+    paused: bool,
+    fade: Option<Fade>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Fade {
+    target: f32,
+    // Volume change per second.
+    speed: f32,
+    stop: bool,
+}
+
+/// # Mixer
+///
+/// Mixes playing sources through a tree of buses into interleaved stereo.
+/// Every bus has a volume, a mute switch and an [`EffectChain`], and sums into
+/// its parent; the master bus starts with a limiter. The Music, SFX and Voice
+/// buses exist from the start, and more can be added under any bus. Buses
+/// with [`Ducking`] turn down other buses while they play.
+///
+/// The mixer doesn't talk to an audio device itself: whatever owns the output
+/// stream calls [`mix`](Self::mix) for each block it needs.
+///
+/// The player's volumes are saved as [`CVars`]: `audio.<bus>.volume` and
+/// `audio.<bus>.muted` for every bus, registered by
+/// [`register_cvars`](Self::register_cvars).
+///
+/// ## Example
+/// ```ignore
+/// let mut mixer = Mixer::new(48_000);
+/// mixer.add_ducking(Ducking::new(BusId::VOICE, BusId::MUSIC));
+/// mixer.register_cvars(&mut cvars);
+/// cvars.load("settings.cfg")?;
+///
+/// mixer.play(theme.play().looping(), BusId::MUSIC);
+/// let line = mixer.play(greeting.play(), BusId::VOICE);
+///
+/// // every frame:
+/// mixer.apply_cvars(&cvars);
+/// // on the audio thread:
+/// mixer.mix(&mut output);
+///
+/// // from the options menu:
+/// cvars.set("audio.music.volume", 0.5)?;
+/// cvars.save("settings.cfg")?;
+/// ```
+pub struct Mixer {
+    sample_rate: u32,
+    buses: Vec<Bus>,
+    sources: Vec<PlayingSource>,
+    duckings: Vec<(Ducking, f32)>,
+    next_source: u64,
+    scratch: Vec<f32>,
+}
+
+impl Mixer {
+    /// Creates a mixer with the master, Music, SFX and Voice buses.
+    pub fn new(sample_rate: u32) -> Self {
+        let mut mixer = Self {
+            sample_rate,
+            buses: Vec::new(),
+            sources: Vec::new(),
+            duckings: Vec::new(),
+            next_source: 0,
+            scratch: Vec::new(),
+        };
+        mixer.push_bus("master", None);
+        mixer.push_bus("music", Some(BusId::MASTER));
+        mixer.push_bus("sfx", Some(BusId::MASTER));
+        mixer.push_bus("voice", Some(BusId::MASTER));
+        mixer.buses[0].effects.push(Limiter::new(sample_rate, -1.0));
+        mixer
+    }
+
+    fn push_bus(&mut self, name: &str, parent: Option<BusId>) -> BusId {
+        self.buses.push(Bus {
+            name: name.to_string(),
+            parent,
+            volume: 1.0,
+            muted: false,
+            effects: EffectChain::new(),
+            buffer: Vec::new(),
+            gain: 1.0,
+            level: 0.0,
+            cvar_volume: None,
+            cvar_muted: None,
+        });
+        BusId(self.buses.len() - 1)
+    }
+
+    /// Returns the output sample rate.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Adds a bus that sums into `parent` and returns its id. Names are
+    /// lowercase by convention and must be unique.
+    pub fn add_bus(&mut self, name: &str, parent: BusId) -> BusId {
+        if let Some(existing) = self.bus(name) {
+            log::warn!("Audio bus '{}' already exists", name);
+            return existing;
+        }
+        self.push_bus(name, Some(parent))
+    }
+
+    /// Returns a bus by name.
+    pub fn bus(&self, name: &str) -> Option<BusId> {
+        self.buses
+            .iter()
+            .position(|bus| bus.name == name)
+            .map(BusId)
+    }
+
+    /// Returns the name of a bus.
+    pub fn bus_name(&self, bus: BusId) -> &str {
+        &self.buses[bus.0].name
+    }
+
+    /// Returns the volume of a bus.
+    pub fn volume(&self, bus: BusId) -> f32 {
+        self.buses[bus.0].volume
+    }
+
+    /// Sets the volume of a bus, where 1 leaves it unchanged.
+    pub fn set_volume(&mut self, bus: BusId, volume: f32) {
+        self.buses[bus.0].volume = volume.max(0.0);
+    }
+
+    /// Checks if a bus is muted.
+    pub fn is_muted(&self, bus: BusId) -> bool {
+        self.buses[bus.0].muted
+    }
+
+    /// Mutes or unmutes a bus.
+    pub fn set_muted(&mut self, bus: BusId, muted: bool) {
+        self.buses[bus.0].muted = muted;
+    }
+
+    /// Returns the effects of a bus.
+    pub fn effects_mut(&mut self, bus: BusId) -> &mut EffectChain {
+        &mut self.buses[bus.0].effects
+    }
+
+    /// Returns the peak level of a bus in the last block, for meters.
+    pub fn level(&self, bus: BusId) -> f32 {
+        self.buses[bus.0].level
+    }
+
+    /// Adds side-chain ducking between two buses.
+    pub fn add_ducking(&mut self, ducking: Ducking) {
+        self.duckings.push((ducking, 1.0));
+    }
+
+    /// Starts playing a source on a bus. Sources should have one or two
+    /// channels; ones at another sample rate go through a [`Resampler`].
+    pub fn play(&mut self, source: impl AudioSource + 'static, bus: BusId) -> SourceId {
+        let source: Box<dyn AudioSource> = if source.sample_rate() != self.sample_rate {
+            Box::new(Resampler::new(source, self.sample_rate))
+        } else {
+            Box::new(source)
+        };
+        let id = SourceId(self.next_source);
+        self.next_source += 1;
+        self.sources.push(PlayingSource {
+            id,
+            source,
+            bus,
+            volume: 1.0,
+            paused: false,
+            fade: None,
+        });
+        id
+    }
+
+    /// Starts playing a source silently and fades it in to full volume.
+    pub fn play_faded(
+        &mut self,
+        source: impl AudioSource + 'static,
+        bus: BusId,
+        seconds: f32,
+    ) -> SourceId {
+        let id = self.play(source, bus);
+        self.set_source_volume(id, 0.0);
+        self.fade(id, 1.0, seconds);
+        id
+    }
+
+    /// Changes the volume of a source gradually.
+    pub fn fade(&mut self, id: SourceId, volume: f32, seconds: f32) {
+        self.start_fade(id, volume, seconds, false);
+    }
+
+    /// Fades a source out and stops it.
+    pub fn fade_out(&mut self, id: SourceId, seconds: f32) {
+        self.start_fade(id, 0.0, seconds, true);
+    }
+
+    /// Fades a source out while fading a new one in on `bus`, for changing
+    /// music tracks. Returns the new source's id.
+    pub fn crossfade(
+        &mut self,
+        from: SourceId,
+        to: impl AudioSource + 'static,
+        bus: BusId,
+        seconds: f32,
+    ) -> SourceId {
+        self.fade_out(from, seconds);
+        self.play_faded(to, bus, seconds)
+    }
+
+    fn start_fade(&mut self, id: SourceId, volume: f32, seconds: f32, stop: bool) {
+        if let Some(playing) = self.source_mut(id) {
+            let target = volume.max(0.0);
+            playing.fade = Some(Fade {
+                target,
+                speed: (target - playing.volume).abs() / seconds.max(0.001),
+                stop,
+            });
+        }
+    }
+
+    /// Stops a source.
+    pub fn stop(&mut self, id: SourceId) {
+        self.sources.retain(|playing| playing.id != id);
+    }
+
+    /// Checks if a source is still playing or paused.
+    pub fn is_playing(&self, id: SourceId) -> bool {
+        self.sources.iter().any(|playing| playing.id == id)
+    }
+
+    /// Pauses or resumes a source.
+    pub fn set_paused(&mut self, id: SourceId, paused: bool) {
+        if let Some(playing) = self.source_mut(id) {
+            playing.paused = paused;
+        }
+    }
+
+    /// Sets the volume of a source, cancelling any fade.
+    pub fn set_source_volume(&mut self, id: SourceId, volume: f32) {
+        if let Some(playing) = self.source_mut(id) {
+            playing.volume = volume.max(0.0);
+            playing.fade = None;
+        }
+    }
+
+    /// Moves a source to another bus.
+    pub fn route(&mut self, id: SourceId, bus: BusId) {
+        if let Some(playing) = self.source_mut(id) {
+            playing.bus = bus;
+        }
+    }
+
+    fn source_mut(&mut self, id: SourceId) -> Option<&mut PlayingSource> {
+        self.sources.iter_mut().find(|playing| playing.id == id)
+    }
+
+    /// Mixes the next block of interleaved stereo samples into `output`,
+    /// replacing its contents.
+    pub fn mix(&mut self, output: &mut [f32]) {
+        let frames = output.len() / MIXER_CHANNELS;
+        let length = frames * MIXER_CHANNELS;
+        for bus in &mut self.buses {
+            bus.buffer.clear();
+            bus.buffer.resize(length, 0.0);
+        }
+
+        let block_seconds = frames as f32 / self.sample_rate as f32;
+        self.sources.retain_mut(|playing| {
+            if playing.paused {
+                return true;
+            }
+            let start_volume = playing.volume;
+            let mut faded_out = false;
+            if let Some(fade) = playing.fade {
+                let step = fade.speed * block_seconds;
+                playing.volume = if playing.volume < fade.target {
+                    (playing.volume + step).min(fade.target)
+                } else {
+                    (playing.volume - step).max(fade.target)
+                };
+                if playing.volume == fade.target {
+                    playing.fade = None;
+                    faded_out = fade.stop;
+                }
+            }
+
+            let channels = playing.source.channels().max(1);
+            self.scratch.clear();
+            self.scratch.resize(frames * channels, 0.0);
+            let read = playing.source.read(&mut self.scratch);
+            let buffer = &mut self.buses[playing.bus.0].buffer;
+            for (index, (frame, input)) in buffer
+                .chunks_exact_mut(MIXER_CHANNELS)
+                .zip(self.scratch[..read].chunks_exact(channels))
+                .enumerate()
+            {
+                let t = (index + 1) as f32 / frames as f32;
+                let volume = start_volume + (playing.volume - start_volume) * t;
+                let (left, right) = if channels == 1 {
+                    (input[0], input[0])
+                } else {
+                    (input[0], input[1])
+                };
+                frame[0] += left * volume;
+                frame[1] += right * volume;
+            }
+            read == self.scratch.len() && !faded_out
+        });
+
+        // Children always come after their parents, so walking backwards sums
+        // every bus into its parent after the bus itself is complete.
+        for index in (0..self.buses.len()).rev() {
+            let mut target_gain = if self.buses[index].muted {
+                0.0
+            } else {
+                self.buses[index].volume
+            };
+            for (ducking, gain) in &mut self.duckings {
+                if ducking.target.0 != index {
+                    continue;
+                }
+                // The trigger's level is from this block if it was mixed
+                // already, or from the last one otherwise.
+                let triggered = self.buses[ducking.trigger.0].level > ducking.threshold;
+                let (goal, time) = if triggered {
+                    (db_to_gain(-ducking.amount_db), ducking.attack)
+                } else {
+                    (1.0, ducking.release)
+                };
+                let step = (block_seconds / time.max(0.001)).min(1.0);
+                *gain += (goal - *gain) * step;
+                target_gain *= *gain;
+            }
+
+            let bus = &mut self.buses[index];
+            bus.effects.process(&mut bus.buffer, MIXER_CHANNELS);
+            let start_gain = bus.gain;
+            let mut level = 0.0f32;
+            for (frame_index, frame) in bus.buffer.chunks_exact_mut(MIXER_CHANNELS).enumerate() {
+                let t = (frame_index + 1) as f32 / frames as f32;
+                let gain = start_gain + (target_gain - start_gain) * t;
+                for sample in frame {
+                    *sample *= gain;
+                    level = level.max(sample.abs());
+                }
+            }
+            bus.gain = target_gain;
+            bus.level = level;
+
+            if let Some(parent) = bus.parent {
+                let buffer = std::mem::take(&mut bus.buffer);
+                for (sum, sample) in self.buses[parent.0].buffer.iter_mut().zip(&buffer) {
+                    *sum += sample;
+                }
+                self.buses[index].buffer = buffer;
+            }
+        }
+
+        output[..length].copy_from_slice(&self.buses[0].buffer);
+        output[length..].fill(0.0);
+    }
+
+    /// Registers `audio.<bus>.volume` and `audio.<bus>.muted` for every bus.
+    /// Call again after adding buses.
+    pub fn register_cvars(&self, cvars: &mut CVars) {
+        for bus in &self.buses {
+            let volume = format!("audio.{}.volume", bus.name);
+            if !cvars.contains(&volume) {
+                cvars.register(
+                    CVar::new(&volume, 1.0, &format!("Volume of the {} bus", bus.name))
+                        .with_range(0.0, 4.0),
+                );
+            }
+            let muted = format!("audio.{}.muted", bus.name);
+            if !cvars.contains(&muted) {
+                cvars.register(CVar::new(
+                    &muted,
+                    false,
+                    &format!("Silences the {} bus", bus.name),
+                ));
+            }
+        }
+    }
+
+    /// Applies the bus volume and mute variables when they change, so values
+    /// set in code stay until the variables are set again. Call once per frame.
+    pub fn apply_cvars(&mut self, cvars: &CVars) {
+        for bus in &mut self.buses {
+            if let Some(volume) = cvars.float(&format!("audio.{}.volume", bus.name)) {
+                if bus.cvar_volume != Some(volume) {
+                    bus.cvar_volume = Some(volume);
+                    bus.volume = (volume as f32).max(0.0);
+                }
+            }
+            if let Some(muted) = cvars.bool(&format!("audio.{}.muted", bus.name)) {
+                if bus.cvar_muted != Some(muted) {
+                    bus.cvar_muted = Some(muted);
+                    bus.muted = muted;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::source::Sound;
+
+    const RATE: u32 = 1_000;
+
+    /// A steady mono signal long enough for every test.
+    fn tone(level: f32) -> Sound {
+        Sound::new(vec![level; RATE as usize * 10], 1, RATE)
+    }
+
+    /// Mixes `frames` frames and returns the left channel.
+    fn mix_left(mixer: &mut Mixer, frames: usize) -> Vec<f32> {
+        let mut output = vec![0.0; frames * MIXER_CHANNELS];
+        mixer.mix(&mut output);
+        output.iter().step_by(MIXER_CHANNELS).copied().collect()
+    }
+
+    #[test]
+    fn fades_ramp_over_their_duration() {
+        let mut mixer = Mixer::new(RATE);
+        let id = mixer.play_faded(tone(0.5).play(), BusId::SFX, 0.1);
+
+        let first = mix_left(&mut mixer, 50);
+        assert!(first[0] > 0.0 && first[0] < 0.01);
+        assert!((first[49] - 0.25).abs() < 1e-4);
+        assert!(first.windows(2).all(|pair| pair[1] >= pair[0]));
+        let second = mix_left(&mut mixer, 50);
+        assert!((second[49] - 0.5).abs() < 1e-4);
+
+        mixer.fade_out(id, 0.1);
+        mix_left(&mut mixer, 100);
+        assert!(!mixer.is_playing(id));
+        assert!(mix_left(&mut mixer, 10).iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    fn ducking_turns_the_target_down_while_the_trigger_plays() {
+        let mut mixer = Mixer::new(RATE);
+        mixer.add_ducking(Ducking::new(BusId::VOICE, BusId::MUSIC).with_timing(0.01, 0.01));
+        mixer.play(tone(0.5).play(), BusId::MUSIC);
+        assert!((mix_left(&mut mixer, 100)[99] - 0.5).abs() < 1e-4);
+
+        let line = mixer.play(tone(0.1).play(), BusId::VOICE);
+        mix_left(&mut mixer, 100);
+        let ducked = mix_left(&mut mixer, 100)[99];
+        let expected = 0.5 * db_to_gain(-10.0) + 0.1;
+        assert!(
+            (ducked - expected).abs() < 1e-3,
+            "{} != {}",
+            ducked,
+            expected
+        );
+
+        mixer.stop(line);
+        mix_left(&mut mixer, 100);
+        assert!((mix_left(&mut mixer, 100)[99] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sources_at_other_rates_keep_their_length() {
+        let mut mixer = Mixer::new(RATE * 2);
+        let id = mixer.play(Sound::new(vec![0.5; 100], 1, RATE).play(), BusId::SFX);
+        mix_left(&mut mixer, 199);
+        assert!(mixer.is_playing(id));
+        mix_left(&mut mixer, 2);
+        assert!(!mixer.is_playing(id));
+    }
+
+    #[test]
+    fn bus_volumes_follow_their_cvars() {
+        let mut mixer = Mixer::new(RATE);
+        let mut cvars = CVars::new();
+        mixer.register_cvars(&mut cvars);
+        mixer.apply_cvars(&cvars);
+        assert_eq!(mixer.volume(BusId::MUSIC), 1.0);
+
+        mixer.set_volume(BusId::MUSIC, 0.8);
+        mixer.apply_cvars(&cvars);
+        assert_eq!(mixer.volume(BusId::MUSIC), 0.8);
+
+        cvars.set("audio.music.volume", 0.25).unwrap();
+        cvars.set("audio.sfx.muted", true).unwrap();
+        mixer.apply_cvars(&cvars);
+        assert_eq!(mixer.volume(BusId::MUSIC), 0.25);
+        assert!(mixer.is_muted(BusId::SFX));
+        assert!(cvars.to_text().contains("audio.music.volume = 0.25"));
+    }
+}
@@ -0,0 +1,30 @@
+pub mod capture;
+pub mod conductor;
+#[cfg(feature = "capture")]
+pub mod cpal_backend;
+pub mod decoders;
+pub mod effects;
+pub mod mixer;
+pub mod source;
+pub mod stream;
+pub mod zones;
+
+pub use capture::{
+    capture_ring, CaptureBackend, CaptureConfig, CaptureConsumer, CaptureDeviceInfo,
+    CaptureProducer, CaptureStream, Microphone,
+};
+pub use conductor::{conductor_system, Beat, BeatEvent, Conductor};
+#[cfg(feature = "capture")]
+pub use cpal_backend::CpalBackend;
+#[cfg(feature = "mp3")]
+pub use decoders::Mp3Decoder;
+#[cfg(feature = "ogg")]
+pub use decoders::OggDecoder;
+pub use effects::{
+    db_to_gain, gain_to_db, AudioEffect, EffectChain, Filter, FilterKind, Limiter, PitchShift,
+    Reverb, ReverbSettings, UNDERWATER_CUTOFF,
+};
+pub use mixer::{BusId, Ducking, Mixer, SourceId, MIXER_CHANNELS};
+pub use source::{AudioSource, Resampler, Sound, SoundInstance};
+pub use stream::{AudioStream, StreamDecoder, StreamHandle, WavDecoder};
+pub use zones::{reverb_zone_system, AudioListener, ReverbZone};
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use crate::memory::{MemoryCategory, Tracked};
+
+/// # Audio Source
+///
+/// Anything the mixer can play: a sound in memory, a stream, or a generator.
+/// Samples are interleaved `f32`s between -1 and 1.
+pub trait AudioSource: Send {
+    /// Returns the number of interleaved channels.
+    fn channels(&self) -> usize;
+
+    /// Returns the samples per second of each channel.
+    fn sample_rate(&self) -> u32;
+
+    /// Fills `output` with the next samples and returns how many were
+    /// written. Writing fewer than asked for means the source has finished.
+    fn read(&mut self, output: &mut [f32]) -> usize;
+}
+
+/// # Sound
+///
+/// Decoded samples held in memory, cheap to clone and to play many times at
+/// once. Suited to short effects; long music belongs in a stream. The
+/// samples count towards [`MemoryCategory::Audio`] until the last clone is
+/// dropped.
+///
+/// ## Example
+/// ```ignore
+/// let click = Sound::new(samples, 1, 48_000);
+/// mixer.play(click.play(), BusId::SFX);
+/// mixer.play(click.play().looping(), BusId::SFX);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sound {
+    samples: Arc<Tracked<Box<[f32]>>>,
+    channels: usize,
+    sample_rate: u32,
+}
+
+impl Sound {
+    /// Creates a sound from interleaved samples.
+    pub fn new(samples: Vec<f32>, channels: usize, sample_rate: u32) -> Self {
+        let bytes = samples.len() * std::mem::size_of::<f32>();
+        Self {
+            samples: Arc::new(Tracked::new(
+                samples.into_boxed_slice(),
+                MemoryCategory::Audio,
+                bytes,
+            )),
+            channels: channels.max(1),
+            sample_rate,
+        }
+    }
+
+    /// Returns the interleaved samples.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Returns the number of interleaved channels.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Returns the samples per second of each channel.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the length in seconds.
+    pub fn duration(&self) -> f32 {
+        (self.samples.len() / self.channels) as f32 / self.sample_rate as f32
+    }
+
+    /// Creates a source that plays the sound once.
+    pub fn play(&self) -> SoundInstance {
+        SoundInstance {
+            sound: self.clone(),
+            position: 0,
+            looping: false,
+        }
+    }
+}
+
+/// One playback of a [`Sound`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundInstance {
+    sound: Sound,
+    position: usize,
+    looping: bool,
+}
+
+impl SoundInstance {
+    /// Makes the playback repeat until stopped.
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+}
+
+impl AudioSource for SoundInstance {
+    fn channels(&self) -> usize {
+        self.sound.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate
+    }
+
+    fn read(&mut self, output: &mut [f32]) -> usize {
+        let samples = &self.sound.samples;
+        let mut written = 0;
+        while written < output.len() {
+            if self.position >= samples.len() {
+                if !self.looping || samples.is_empty() {
+                    break;
+                }
+                self.position = 0;
+            }
+            let count = (output.len() - written).min(samples.len() - self.position);
+            output[written..written + count]
+                .copy_from_slice(&samples[self.position..self.position + count]);
+            written += count;
+            self.position += count;
+        }
+        written
+    }
+}
+
+/// Input frames read from the wrapped source at a time.
+const RESAMPLER_CHUNK: usize = 256;
+
+/// # Resampler
+///
+/// Plays a source at another sample rate by interpolating linearly between
+/// its frames, so a 44.1 kHz file plays at the right pitch on a 48 kHz
+/// mixer. [`Mixer::play`](super::mixer::Mixer::play) wraps sources in one
+/// when their rate differs.
+///
+/// ## Example
+/// ```ignore
+/// let source = Resampler::new(sound.play(), 48_000);
+/// assert_eq!(source.sample_rate(), 48_000);
+/// ```
+pub struct Resampler<S> {
+    source: S,
+    sample_rate: u32,
+    channels: usize,
+    source_rate: u32,
+    // Frame in `input` before the playback position, and how far towards the
+    // next one it is, in units of 1 / `sample_rate`.
+    frame: usize,
+    phase: u32,
+    input: Vec<f32>,
+    finished: bool,
+}
+
+impl<S: AudioSource> Resampler<S> {
+    /// Wraps a source so it plays at `sample_rate`.
+    pub fn new(source: S, sample_rate: u32) -> Self {
+        Self {
+            channels: source.channels().max(1),
+            source_rate: source.sample_rate(),
+            source,
+            sample_rate: sample_rate.max(1),
+            frame: 0,
+            phase: 0,
+            input: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Drops the frames before the playback position and reads the next chunk
+    /// after the rest.
+    fn refill(&mut self) {
+        let channels = self.channels;
+        let consumed = (self.frame * channels).min(self.input.len());
+        self.input.drain(..consumed);
+        self.frame -= consumed / channels;
+
+        let start = self.input.len();
+        let wanted = RESAMPLER_CHUNK * channels;
+        self.input.resize(start + wanted, 0.0);
+        let read = self.source.read(&mut self.input[start..]);
+        self.input.truncate(start + read / channels * channels);
+        if read < wanted {
+            self.finished = true;
+        }
+    }
+}
+
+impl<S: AudioSource> AudioSource for Resampler<S> {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, output: &mut [f32]) -> usize {
+        let channels = self.channels;
+        let mut written = 0;
+        while written + channels <= output.len() {
+            let frame = self.frame;
+            let frames = self.input.len() / channels;
+            if frame + 1 >= frames && !self.finished {
+                self.refill();
+                continue;
+            }
+            if frame >= frames {
+                break;
+            }
+            let next = (frame + 1).min(frames - 1);
+            let t = self.phase as f32 / self.sample_rate as f32;
+            for channel in 0..channels {
+                let a = self.input[frame * channels + channel];
+                let b = self.input[next * channels + channel];
+                output[written + channel] = a + (b - a) * t;
+            }
+            written += channels;
+
+            // Integer steps, so long playback doesn't drift.
+            let phase = self.phase as u64 + self.source_rate as u64;
+            self.frame += (phase / self.sample_rate as u64) as usize;
+            self.phase = (phase % self.sample_rate as u64) as u32;
+        }
+        written
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resampling_interpolates_between_frames() {
+        let sound = Sound::new(vec![0.0, 1.0, 0.0, -1.0], 1, 24_000);
+        let mut source = Resampler::new(sound.play(), 48_000);
+        assert_eq!(source.sample_rate(), 48_000);
+
+        let mut output = [9.0f32; 10];
+        assert_eq!(source.read(&mut output), 8);
+        assert_eq!(&output[..8], &[0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -1.0]);
+        assert_eq!(source.read(&mut output), 0);
+    }
+
+    #[test]
+    fn resampling_keeps_pitch_across_reads() {
+        // 441 stereo frames of a ramp make 480 frames at 48 kHz.
+        let samples: Vec<f32> = (0..441).flat_map(|i| [i as f32, -(i as f32)]).collect();
+        let sound = Sound::new(samples, 2, 44_100);
+        let mut source = Resampler::new(sound.play(), 48_000);
+
+        let mut output = Vec::new();
+        let mut block = [0.0f32; 64];
+        loop {
+            let read = source.read(&mut block);
+            output.extend_from_slice(&block[..read]);
+            if read < block.len() {
+                break;
+            }
+        }
+        assert_eq!(output.len() / 2, 480);
+        // The last frame holds the source's last frame instead of extrapolating.
+        for (index, frame) in output.chunks_exact(2).enumerate().take(479) {
+            let expected = (index as f64 * 44_100.0 / 48_000.0) as f32;
+            assert!((frame[0] - expected).abs() < 1e-3, "frame {}", index);
+            assert_eq!(frame[1], -frame[0]);
+        }
+    }
+}
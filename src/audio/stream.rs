@@ -0,0 +1,610 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use super::source::AudioSource;
+use crate::custom_errors::Errors;
+
+/// Frames decoded per chunk sent to the audio thread.
+const CHUNK_FRAMES: usize = 4096;
+
+/// Chunks decoded ahead of playback; about 0.7 seconds at 48 kHz.
+const CHUNKS_AHEAD: usize = 8;
+
+/// # Stream Decoder
+///
+/// Decodes a compressed or uncompressed audio file a piece at a time. Formats
+/// are added by implementing this trait and opening streams with
+/// [`AudioStream::from_decoder`]. WAV is built in; Ogg Vorbis and MP3 come
+/// with the `ogg` and `mp3` features, see [`decoders`](super::decoders).
+pub trait StreamDecoder: Send {
+    /// Returns the number of interleaved channels.
+    fn channels(&self) -> usize;
+
+    /// Returns the samples per second of each channel.
+    fn sample_rate(&self) -> u32;
+
+    /// Returns the length in frames, if known.
+    fn length(&self) -> Option<u64>;
+
+    /// Decodes the next samples into `output` and returns how many were
+    /// written, always whole frames. Returns 0 at the end of the file.
+    fn decode(&mut self, output: &mut [f32]) -> Result<usize, Errors>;
+
+    /// Moves to a frame.
+    fn seek(&mut self, frame: u64) -> Result<(), Errors>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WavFormat {
+    Int(u16),
+    Float,
+}
+
+/// Decodes WAV files with 8, 16, 24 or 32-bit integer or 32-bit float samples.
+pub struct WavDecoder {
+    reader: BufReader<File>,
+    format: WavFormat,
+    channels: usize,
+    sample_rate: u32,
+    data_start: u64,
+    frames: u64,
+    frame: u64,
+    bytes: Vec<u8>,
+}
+
+impl WavDecoder {
+    /// Opens a WAV file.
+    pub fn open(path: &str) -> Result<Self, Errors> {
+        let error = |message: String| Errors::InvalidAudio(format!("{}: {}", path, message));
+        let file = File::open(path).map_err(|io| error(io.to_string()))?;
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0u8; 12];
+        reader
+            .read_exact(&mut header)
+            .map_err(|io| error(io.to_string()))?;
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return Err(error("not a WAV file".to_string()));
+        }
+
+        let mut format = None;
+        loop {
+            let mut chunk = [0u8; 8];
+            reader
+                .read_exact(&mut chunk)
+                .map_err(|_| error("missing data chunk".to_string()))?;
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+            match &chunk[0..4] {
+                b"fmt " => {
+                    let mut fmt = vec![0u8; size as usize];
+                    reader
+                        .read_exact(&mut fmt)
+                        .map_err(|io| error(io.to_string()))?;
+                    if fmt.len() < 16 {
+                        return Err(error("truncated format chunk".to_string()));
+                    }
+                    let u16_at = |at: usize| u16::from_le_bytes([fmt[at], fmt[at + 1]]);
+                    let mut tag = u16_at(0);
+                    // WAVE_FORMAT_EXTENSIBLE keeps the real tag in its sub-format.
+                    if tag == 0xFFFE && fmt.len() >= 26 {
+                        tag = u16_at(24);
+                    }
+                    let channels = u16_at(2) as usize;
+                    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    let bits = u16_at(14);
+                    let sample_format = match (tag, bits) {
+                        (1, 8 | 16 | 24 | 32) => WavFormat::Int(bits),
+                        (3, 32) => WavFormat::Float,
+                        _ => {
+                            return Err(error(format!(
+                                "unsupported sample format {} with {} bits",
+                                tag, bits
+                            )))
+                        }
+                    };
+                    if channels == 0 {
+                        return Err(error("no channels".to_string()));
+                    }
+                    format = Some((sample_format, channels, sample_rate));
+                    if size % 2 == 1 {
+                        reader
+                            .seek(SeekFrom::Current(1))
+                            .map_err(|io| error(io.to_string()))?;
+                    }
+                }
+                b"data" => {
+                    let (format, channels, sample_rate) =
+                        format.ok_or_else(|| error("data before format chunk".to_string()))?;
+                    let data_start = reader
+                        .stream_position()
+                        .map_err(|io| error(io.to_string()))?;
+                    let frame_bytes = (sample_bytes(format) * channels) as u64;
+                    return Ok(Self {
+                        reader,
+                        format,
+                        channels,
+                        sample_rate,
+                        data_start,
+                        frames: size / frame_bytes,
+                        frame: 0,
+                        bytes: Vec::new(),
+                    });
+                }
+                _ => {
+                    // Chunks are padded to an even size.
+                    reader
+                        .seek(SeekFrom::Current((size + size % 2) as i64))
+                        .map_err(|io| error(io.to_string()))?;
+                }
+            }
+        }
+    }
+}
+
+fn sample_bytes(format: WavFormat) -> usize {
+    match format {
+        WavFormat::Int(bits) => bits as usize / 8,
+        WavFormat::Float => 4,
+    }
+}
+
+impl StreamDecoder for WavDecoder {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn length(&self) -> Option<u64> {
+        Some(self.frames)
+    }
+
+    fn decode(&mut self, output: &mut [f32]) -> Result<usize, Errors> {
+        let frames = ((output.len() / self.channels) as u64).min(self.frames - self.frame) as usize;
+        let samples = frames * self.channels;
+        let size = sample_bytes(self.format);
+        self.bytes.resize(samples * size, 0);
+        self.reader
+            .read_exact(&mut self.bytes)
+            .map_err(|io| Errors::InvalidAudio(io.to_string()))?;
+
+        for (sample, bytes) in output.iter_mut().zip(self.bytes.chunks_exact(size)) {
+            *sample = match self.format {
+                WavFormat::Int(8) => (bytes[0] as f32 - 128.0) / 128.0,
+                WavFormat::Int(16) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32_768.0,
+                WavFormat::Int(24) => {
+                    // Shifted into the top of an i32 so the sign extends.
+                    let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]);
+                    value as f32 / 2_147_483_648.0
+                }
+                WavFormat::Int(_) => {
+                    i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                        / 2_147_483_648.0
+                }
+                WavFormat::Float => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            };
+        }
+        self.frame += frames as u64;
+        Ok(samples)
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<(), Errors> {
+        let frame = frame.min(self.frames);
+        let offset = self.data_start + frame * (sample_bytes(self.format) * self.channels) as u64;
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|io| Errors::InvalidAudio(io.to_string()))?;
+        self.frame = frame;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum StreamCommand {
+    Seek(u64),
+}
+
+enum StreamMessage {
+    Chunk {
+        generation: u64,
+        start: u64,
+        samples: Vec<f32>,
+    },
+    End {
+        generation: u64,
+    },
+}
+
+/// # Audio Stream
+///
+/// An [`AudioSource`] that decodes a long file on a background thread a
+/// little ahead of playback, instead of holding the whole track in memory.
+/// Looping streams jump back to their loop start without a gap, so a track can
+/// have an intro that plays once. Seeking and the playback position go
+/// through a [`StreamHandle`], since the mixer owns the stream while it plays.
+///
+/// If the disk can't keep up, the stream plays silence until it catches up.
+///
+/// ## Example
+/// ```ignore
+/// let stream = AudioStream::open("music/forest.wav")?.looping_from(12.5);
+/// let handle = stream.handle();
+/// let id = mixer.play(stream, BusId::MUSIC);
+///
+/// handle.seek(60.0);
+/// println!("{:.1}s", handle.position());
+///
+/// // later, change tracks over two seconds:
+/// mixer.crossfade(id, AudioStream::open("music/battle.wav")?.looping(), BusId::MUSIC, 2.0);
+/// ```
+pub struct AudioStream {
+    channels: usize,
+    sample_rate: u32,
+    decoder: Option<Box<dyn StreamDecoder>>,
+    loop_start: Option<u64>,
+    commands: Sender<StreamCommand>,
+    command_receiver: Option<Receiver<StreamCommand>>,
+    messages: Option<Receiver<StreamMessage>>,
+    position: Arc<AtomicU64>,
+    seeks: Arc<AtomicU64>,
+    chunk_generation: u64,
+    chunk: Vec<f32>,
+    chunk_read: usize,
+    finished: bool,
+}
+
+impl AudioStream {
+    /// Opens a file for streaming, choosing the decoder by its extension.
+    pub fn open(path: &str) -> Result<Self, Errors> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "wav" | "wave" => Ok(Self::from_decoder(WavDecoder::open(path)?)),
+            #[cfg(feature = "ogg")]
+            "ogg" | "oga" => Ok(Self::from_decoder(super::decoders::OggDecoder::open(path)?)),
+            #[cfg(feature = "mp3")]
+            "mp3" => Ok(Self::from_decoder(super::decoders::Mp3Decoder::open(path)?)),
+            #[cfg(not(feature = "ogg"))]
+            "ogg" | "oga" => Err(Errors::InvalidAudio(format!(
+                "{}: Ogg Vorbis streams need the engine's `ogg` feature",
+                path
+            ))),
+            #[cfg(not(feature = "mp3"))]
+            "mp3" => Err(Errors::InvalidAudio(format!(
+                "{}: MP3 streams need the engine's `mp3` feature",
+                path
+            ))),
+            _ => Err(Errors::InvalidAudio(format!(
+                "{}: no stream decoder for '.{}' files",
+                path, extension
+            ))),
+        }
+    }
+
+    /// Creates a stream from any decoder.
+    pub fn from_decoder(decoder: impl StreamDecoder + 'static) -> Self {
+        let (commands, command_receiver) = mpsc::channel();
+        Self {
+            channels: decoder.channels().max(1),
+            sample_rate: decoder.sample_rate(),
+            decoder: Some(Box::new(decoder)),
+            loop_start: None,
+            commands,
+            command_receiver: Some(command_receiver),
+            messages: None,
+            position: Arc::new(AtomicU64::new(0)),
+            seeks: Arc::new(AtomicU64::new(0)),
+            chunk_generation: 0,
+            chunk: Vec::new(),
+            chunk_read: 0,
+            finished: false,
+        }
+    }
+
+    /// Makes the stream loop from the start.
+    pub fn looping(self) -> Self {
+        self.looping_from(0.0)
+    }
+
+    /// Makes the stream loop back to `seconds` after reaching the end, so the
+    /// part before it plays only once.
+    pub fn looping_from(mut self, seconds: f32) -> Self {
+        self.loop_start = Some((seconds.max(0.0) * self.sample_rate as f32) as u64);
+        self
+    }
+
+    /// Returns a handle for seeking and reading the position while the stream
+    /// plays.
+    pub fn handle(&self) -> StreamHandle {
+        StreamHandle {
+            commands: self.commands.clone(),
+            position: self.position.clone(),
+            seeks: self.seeks.clone(),
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Starts the decoding thread on the first read.
+    fn start(&mut self) {
+        let (Some(mut decoder), Some(commands)) =
+            (self.decoder.take(), self.command_receiver.take())
+        else {
+            return;
+        };
+        let (sender, messages) = mpsc::sync_channel(CHUNKS_AHEAD);
+        let loop_start = self.loop_start;
+        let spawned = thread::Builder::new()
+            .name("nyanko-audio-stream".to_string())
+            .spawn(move || decode_stream(decoder.as_mut(), loop_start, commands, sender));
+        match spawned {
+            Ok(_) => self.messages = Some(messages),
+            Err(error) => {
+                log::error!("Failed to start audio stream thread: {}", error);
+                self.finished = true;
+            }
+        }
+    }
+}
+
+fn decode_stream(
+    decoder: &mut dyn StreamDecoder,
+    loop_start: Option<u64>,
+    commands: Receiver<StreamCommand>,
+    sender: SyncSender<StreamMessage>,
+) {
+    let channels = decoder.channels().max(1);
+    let mut generation = 0;
+    let mut frame = 0u64;
+    let mut ended = false;
+    loop {
+        // Once the end is sent there is nothing to do until a seek arrives.
+        let command = if ended {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            }
+        } else {
+            match commands.try_recv() {
+                Ok(command) => Some(command),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        };
+        if let Some(StreamCommand::Seek(target)) = command {
+            if let Err(error) = decoder.seek(target) {
+                log::error!("Audio stream seek failed: {}", error);
+            }
+            generation += 1;
+            frame = target;
+            ended = false;
+        }
+        if ended {
+            continue;
+        }
+
+        let mut samples = vec![0.0; CHUNK_FRAMES * channels];
+        let mut written = match decoder.decode(&mut samples) {
+            Ok(written) => written,
+            Err(error) => {
+                log::error!("Audio stream decoding failed: {}", error);
+                0
+            }
+        };
+        let start = frame;
+        if written == 0 {
+            if let Some(loop_start) = loop_start {
+                if decoder.seek(loop_start).is_ok() && start != loop_start {
+                    frame = loop_start;
+                    continue;
+                }
+            }
+            ended = true;
+            if sender.send(StreamMessage::End { generation }).is_err() {
+                return;
+            }
+            continue;
+        }
+        written -= written % channels;
+        samples.truncate(written);
+        frame += (written / channels) as u64;
+        let message = StreamMessage::Chunk {
+            generation,
+            start,
+            samples,
+        };
+        if sender.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+impl AudioSource for AudioStream {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, output: &mut [f32]) -> usize {
+        if self.finished {
+            return 0;
+        }
+        self.start();
+        let Some(messages) = &self.messages else {
+            return 0;
+        };
+
+        // Every seek starts a new generation of chunks; anything older is
+        // dropped, including the rest of the current chunk.
+        let generation = self.seeks.load(Ordering::Acquire);
+        if self.chunk_generation < generation {
+            self.chunk.clear();
+            self.chunk_read = 0;
+        }
+
+        let mut written = 0;
+        while written < output.len() {
+            if self.chunk_read < self.chunk.len() {
+                let count = (output.len() - written).min(self.chunk.len() - self.chunk_read);
+                output[written..written + count]
+                    .copy_from_slice(&self.chunk[self.chunk_read..self.chunk_read + count]);
+                written += count;
+                self.chunk_read += count;
+                self.position
+                    .fetch_add((count / self.channels) as u64, Ordering::Relaxed);
+                continue;
+            }
+            match messages.try_recv() {
+                Ok(StreamMessage::Chunk {
+                    generation: chunk_generation,
+                    start,
+                    samples,
+                }) => {
+                    if chunk_generation < generation {
+                        continue;
+                    }
+                    self.chunk_generation = chunk_generation;
+                    self.position.store(start, Ordering::Relaxed);
+                    self.chunk = samples;
+                    self.chunk_read = 0;
+                }
+                Ok(StreamMessage::End {
+                    generation: end_generation,
+                }) => {
+                    if end_generation >= generation {
+                        self.finished = true;
+                        return written;
+                    }
+                }
+                Err(TryRecvError::Empty) => {
+                    output[written..].fill(0.0);
+                    return output.len();
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    return written;
+                }
+            }
+        }
+        written
+    }
+}
+
+/// Controls an [`AudioStream`] from outside the mixer.
+#[derive(Clone, Debug)]
+pub struct StreamHandle {
+    commands: Sender<StreamCommand>,
+    position: Arc<AtomicU64>,
+    seeks: Arc<AtomicU64>,
+    sample_rate: u32,
+}
+
+impl StreamHandle {
+    /// Jumps to a time in seconds. Playback continues from there once the
+    /// decoder has caught up, usually within a few milliseconds.
+    pub fn seek(&self, seconds: f32) {
+        let frame = (seconds.max(0.0) * self.sample_rate as f32) as u64;
+        if self.commands.send(StreamCommand::Seek(frame)).is_ok() {
+            self.seeks.fetch_add(1, Ordering::Release);
+            self.position.store(frame, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the playback position in seconds.
+    pub fn position(&self) -> f32 {
+        self.position.load(Ordering::Relaxed) as f32 / self.sample_rate as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a PCM WAV file with an extra chunk before the data, and returns
+    /// its path.
+    fn write_wav(name: &str, channels: u16, bits: u16, data: &[u8]) -> String {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&channels.to_le_bytes());
+        fmt.extend_from_slice(&48_000u32.to_le_bytes());
+        let block = channels * bits / 8;
+        fmt.extend_from_slice(&(48_000 * block as u32).to_le_bytes());
+        fmt.extend_from_slice(&block.to_le_bytes());
+        fmt.extend_from_slice(&bits.to_le_bytes());
+
+        let mut chunks = Vec::new();
+        for (id, body) in [(b"fmt ", &fmt[..]), (b"LIST", &[7u8][..]), (b"data", data)] {
+            chunks.extend_from_slice(id);
+            chunks.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            chunks.extend_from_slice(body);
+            if body.len() % 2 == 1 && id != b"data" {
+                chunks.push(0);
+            }
+        }
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend_from_slice(&chunks);
+
+        let path =
+            std::env::temp_dir().join(format!("nyanko-stream-{}-{}.wav", name, std::process::id()));
+        std::fs::write(&path, file).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn wav_frames_decode_and_seek() {
+        let samples: [i16; 6] = [0, 16_384, -16_384, 32_767, -32_768, 8_192];
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let path = write_wav("pcm16", 2, 16, &data);
+        let mut decoder = WavDecoder::open(&path).unwrap();
+        assert_eq!(decoder.channels(), 2);
+        assert_eq!(decoder.sample_rate(), 48_000);
+        assert_eq!(decoder.length(), Some(3));
+
+        let mut output = [9.0f32; 5];
+        assert_eq!(decoder.decode(&mut output).unwrap(), 4);
+        assert_eq!(&output[..4], &[0.0, 0.5, -0.5, 32_767.0 / 32_768.0]);
+        assert_eq!(output[4], 9.0);
+
+        decoder.seek(2).unwrap();
+        let mut output = [0.0f32; 4];
+        assert_eq!(decoder.decode(&mut output).unwrap(), 2);
+        assert_eq!(&output[..2], &[-1.0, 0.25]);
+        assert_eq!(decoder.decode(&mut output).unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wav_24_bit_samples_keep_their_sign() {
+        let data = [0x00, 0x00, 0xC0, 0x00, 0x00, 0x40];
+        let path = write_wav("pcm24", 1, 24, &data);
+        let mut decoder = WavDecoder::open(&path).unwrap();
+
+        let mut output = [0.0f32; 2];
+        assert_eq!(decoder.decode(&mut output).unwrap(), 2);
+        assert_eq!(output, [-0.5, 0.5]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn files_that_are_not_wav_are_rejected() {
+        let path = std::env::temp_dir().join(format!("nyanko-stream-{}.txt", std::process::id()));
+        std::fs::write(&path, b"definitely not audio").unwrap();
+        let result = WavDecoder::open(path.to_str().unwrap());
+        assert!(matches!(result, Err(Errors::InvalidAudio(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+}
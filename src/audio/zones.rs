@@ -0,0 +1,183 @@
+use super::effects::ReverbSettings;
+use crate::ecs::{Access, FunctionSystem, System, World};
+use crate::gameplay::TriggerShape;
+use crate::math::*;
+use crate::scene::Transform;
+
+/// Cutoff reported to a listener outside every filtering zone, in hertz.
+const OPEN_CUTOFF: f32 = 20_000.0;
+
+/// # Reverb Zone
+///
+/// A component for a volume with its own acoustics, such as a cave, a hall or
+/// a body of water, centered on its entity's translation. A listener inside
+/// the zone hears its reverb fully; within `fade` units outside it the reverb
+/// blends with its surroundings. Zones can also muffle everything with a
+/// low-pass cutoff, for underwater volumes.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(cave, Transform::from_translation(vec3(0.0, -10.0, 0.0)));
+/// world.insert(cave, ReverbZone::new(TriggerShape::Sphere { radius: 30.0 }, ReverbSettings::cave()));
+///
+/// world.insert(lake, ReverbZone::new(lake_shape, ReverbSettings::small_room())
+///     .with_cutoff(UNDERWATER_CUTOFF)
+///     .with_priority(1));
+///
+/// // each audio update:
+/// let listener = world.read::<AudioListener>().get(player).copied().unwrap_or_default();
+/// reverb.set_settings(listener.reverb);
+/// muffle.set_cutoff(listener.cutoff);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReverbZone {
+    pub shape: TriggerShape,
+    pub settings: ReverbSettings,
+    /// Distance outside the shape over which the zone fades out.
+    pub fade: f32,
+    /// Low-pass cutoff in hertz applied inside the zone.
+    pub cutoff: Option<f32>,
+    /// Zones with higher priority win where zones overlap.
+    pub priority: i32,
+}
+
+impl ReverbZone {
+    /// Creates a zone with a fade of 5 units.
+    pub fn new(shape: TriggerShape, settings: ReverbSettings) -> Self {
+        Self {
+            shape,
+            settings,
+            fade: 5.0,
+            cutoff: None,
+            priority: 0,
+        }
+    }
+
+    /// Sets the fade distance.
+    pub fn with_fade(mut self, fade: f32) -> Self {
+        self.fade = fade;
+        self
+    }
+
+    /// Sets the low-pass cutoff inside the zone.
+    pub fn with_cutoff(mut self, cutoff: f32) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+
+    /// Sets the priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns how strongly the zone applies at a point, from 0 to 1.
+    pub fn weight(&self, transform: &Transform, point: Vec3) -> f32 {
+        let distance = self.distance(transform, point);
+        if distance <= 0.0 {
+            1.0
+        } else if self.fade <= 0.0 {
+            0.0
+        } else {
+            (1.0 - distance / self.fade).max(0.0)
+        }
+    }
+
+    fn distance(&self, transform: &Transform, point: Vec3) -> f32 {
+        let offset = point - transform.translation;
+        match self.shape {
+            TriggerShape::Sphere { radius } => offset.magnitude() - radius,
+            TriggerShape::Box { half_extents } => {
+                let local = transform.rotation.invert() * offset;
+                let outside = vec3(
+                    (local.x.abs() - half_extents.x).max(0.0),
+                    (local.y.abs() - half_extents.y).max(0.0),
+                    (local.z.abs() - half_extents.z).max(0.0),
+                );
+                outside.magnitude()
+            }
+        }
+    }
+}
+
+/// # Audio Listener
+///
+/// A component for the entity whose position the world is heard from, usually
+/// the camera or player. The [`reverb_zone_system`] fills in the acoustics at
+/// its position for the game to hand to its [`Reverb`](super::Reverb) and
+/// low-pass [`Filter`](super::Filter).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AudioListener {
+    /// Reverb blended from the zones around the listener.
+    pub reverb: ReverbSettings,
+    /// Low-pass cutoff in hertz from the zones around the listener.
+    pub cutoff: f32,
+}
+
+impl AudioListener {
+    /// Creates a listener with no reverb and no filtering.
+    pub fn new() -> Self {
+        Self {
+            reverb: ReverbSettings::off(),
+            cutoff: OPEN_CUTOFF,
+        }
+    }
+}
+
+impl Default for AudioListener {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns a system that blends the [`ReverbZone`]s around every
+/// [`AudioListener`].
+pub fn reverb_zone_system() -> impl System {
+    FunctionSystem::new(
+        "reverb_zones",
+        Access::new()
+            .read::<ReverbZone>()
+            .read::<Transform>()
+            .write::<AudioListener>(),
+        update_reverb_zones,
+    )
+}
+
+/// Updates each listener's reverb and cutoff from the zones it is in or near.
+pub fn update_reverb_zones(world: &World) {
+    let zones = world.read::<ReverbZone>();
+    let transforms = world.read::<Transform>();
+    let mut listeners = world.write::<AudioListener>();
+
+    let mut placed: Vec<(&ReverbZone, &Transform)> = zones
+        .iter()
+        .filter_map(|(entity, zone)| Some((zone, transforms.get(*entity)?)))
+        .collect();
+    placed.sort_by_key(|(zone, _)| zone.priority);
+
+    for (entity, listener) in listeners.iter_mut() {
+        let Some(position) = transforms
+            .get(*entity)
+            .map(|transform| transform.translation)
+        else {
+            continue;
+        };
+        let mut reverb = ReverbSettings::off();
+        let mut cutoff = OPEN_CUTOFF;
+        // Later, higher priority zones blend over earlier ones.
+        for (zone, transform) in &placed {
+            let weight = zone.weight(transform, position);
+            if weight <= 0.0 {
+                continue;
+            }
+            reverb = reverb.lerp(&zone.settings, weight);
+            if let Some(zone_cutoff) = zone.cutoff {
+                // Blended in octaves, like the filter's own sweeps.
+                let blended = OPEN_CUTOFF * (zone_cutoff / OPEN_CUTOFF).powf(weight);
+                cutoff = cutoff.min(blended);
+            }
+        }
+        listener.reverb = reverb;
+        listener.cutoff = cutoff;
+    }
+}
@@ -0,0 +1,727 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::time::Instant;
+
+use crate::custom_errors::Errors;
+use crate::ecs::{Access, FunctionSystem, Schedule, System, World};
+use crate::graphics::light::PointLight;
+use crate::graphics::sprite_batch::SpriteBatch;
+use crate::math::*;
+use crate::scene::Transform;
+use crate::telemetry::{json_number, json_string};
+
+/// Size of the screen area bench sprites move in, in pixels.
+pub const BENCH_SCREEN: Vec2 = Vec2::new(1280.0, 720.0);
+
+/// Half the width of the box bench bodies fall in, in world units.
+const BODY_BOX: f32 = 20.0;
+
+const GRAVITY: f32 = -9.81;
+
+/// A sprite of a synthetic scene, bouncing around [`BENCH_SCREEN`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchSprite {
+    pub size: Vec2,
+    pub color: Color,
+    pub velocity: Vec2,
+}
+
+/// A point light of a synthetic scene, circling a center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchLight {
+    pub center: Vec3,
+    pub radius: f32,
+    /// Radians per second.
+    pub speed: f32,
+    pub angle: f32,
+}
+
+/// A sphere of a synthetic scene, falling in a box and pushed out of the
+/// others it overlaps. It stands in for a physics body; there is no real
+/// collision response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchBody {
+    pub velocity: Vec3,
+    pub radius: f32,
+}
+
+/// An entity of a synthetic scene that only drifts, for measuring how fast
+/// systems iterate over components.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchMover {
+    pub velocity: Vec3,
+}
+
+/// # Bench Scene
+///
+/// A synthetic scene of many sprites, lights, stand-in physics bodies and
+/// movers, for measuring how the engine scales. The same seed always builds
+/// the same scene. The engine has no physics solver, so the bodies load the
+/// frame with a physics-like workload: integration, bounds and a broad phase
+/// with overlap separation, but no impulses, friction or rotation.
+///
+/// ## Example
+/// ```ignore
+/// let scene = BenchScene::new().with_sprites(10_000).with_lights(256).with_bodies(2_000);
+/// scene.spawn(&mut world);
+/// scene.add_systems(&mut schedule);
+///
+/// // raw iteration over a million entities
+/// let scene = BenchScene::new().with_movers(1_000_000);
+/// scene.spawn(&mut world);
+/// scene.add_systems(&mut schedule);
+/// let report = Benchmark::new("movers_1m", 120).with_warmup(10).run_headless(&mut world, &mut schedule);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BenchScene {
+    pub sprites: usize,
+    pub lights: usize,
+    pub bodies: usize,
+    pub movers: usize,
+    pub seed: u64,
+}
+
+impl BenchScene {
+    /// Creates an empty scene.
+    pub fn new() -> Self {
+        Self {
+            sprites: 0,
+            lights: 0,
+            bodies: 0,
+            movers: 0,
+            seed: 1,
+        }
+    }
+
+    /// Sets the number of sprites.
+    pub fn with_sprites(mut self, sprites: usize) -> Self {
+        self.sprites = sprites;
+        self
+    }
+
+    /// Sets the number of point lights.
+    pub fn with_lights(mut self, lights: usize) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    /// Sets the number of stand-in physics bodies.
+    pub fn with_bodies(mut self, bodies: usize) -> Self {
+        self.bodies = bodies;
+        self
+    }
+
+    /// Sets the number of movers.
+    pub fn with_movers(mut self, movers: usize) -> Self {
+        self.movers = movers;
+        self
+    }
+
+    /// Sets the seed placing everything.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the number of entities the scene spawns.
+    pub fn entity_count(&self) -> usize {
+        self.sprites + self.lights + self.bodies + self.movers
+    }
+
+    /// Spawns the scene's entities.
+    pub fn spawn(&self, world: &mut World) {
+        let mut rng = Rng::new(self.seed);
+        for _ in 0..self.sprites {
+            let entity = world.spawn();
+            let position = vec3(
+                rng.range(0.0..BENCH_SCREEN.x),
+                rng.range(0.0..BENCH_SCREEN.y),
+                0.0,
+            );
+            world.insert(entity, Transform::from_translation(position));
+            world.insert(
+                entity,
+                BenchSprite {
+                    size: vec2(rng.range(4.0..24.0), rng.range(4.0..24.0)),
+                    color: Color::rgb(rng.f32(), rng.f32(), rng.f32()),
+                    velocity: rng.unit_vector_2d() * rng.range(40.0..200.0),
+                },
+            );
+        }
+        for _ in 0..self.lights {
+            let entity = world.spawn();
+            let light = BenchLight {
+                center: vec3(
+                    rng.range(-BODY_BOX..BODY_BOX),
+                    rng.range(1.0..6.0),
+                    rng.range(-BODY_BOX..BODY_BOX),
+                ),
+                radius: rng.range(1.0..5.0),
+                speed: rng.range(-2.0..2.0),
+                angle: rng.angle(),
+            };
+            world.insert(entity, Transform::from_translation(light.center));
+            world.insert(entity, light);
+            world.insert(
+                entity,
+                PointLight::new(rng.range(3.0..8.0)).with_color(Color::rgb(
+                    rng.f32(),
+                    rng.f32(),
+                    rng.f32(),
+                )),
+            );
+        }
+        for _ in 0..self.bodies {
+            let entity = world.spawn();
+            let position = vec3(
+                rng.range(-BODY_BOX..BODY_BOX),
+                rng.range(1.0..BODY_BOX * 2.0),
+                rng.range(-BODY_BOX..BODY_BOX),
+            );
+            world.insert(entity, Transform::from_translation(position));
+            world.insert(
+                entity,
+                BenchBody {
+                    velocity: rng.in_sphere() * 2.0,
+                    radius: rng.range(0.2..0.6),
+                },
+            );
+        }
+        for _ in 0..self.movers {
+            let entity = world.spawn();
+            let position = vec3(
+                rng.range(-BODY_BOX..BODY_BOX),
+                rng.range(-BODY_BOX..BODY_BOX),
+                rng.range(-BODY_BOX..BODY_BOX),
+            );
+            world.insert(entity, Transform::from_translation(position));
+            world.insert(
+                entity,
+                BenchMover {
+                    velocity: rng.in_sphere(),
+                },
+            );
+        }
+    }
+
+    /// Adds the systems that move the scene.
+    pub fn add_systems(&self, schedule: &mut Schedule) {
+        schedule.add_system(bench_sprite_system());
+        schedule.add_system(bench_light_system());
+        schedule.add_system(bench_body_system());
+        schedule.add_system(bench_mover_system());
+    }
+}
+
+impl Default for BenchScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns a system that bounces [`BenchSprite`]s around the screen.
+pub fn bench_sprite_system() -> impl System {
+    FunctionSystem::new(
+        "bench_sprites",
+        Access::new().read::<BenchSprite>().write::<Transform>(),
+        move_bench_sprites,
+    )
+}
+
+/// Moves sprites and bounces them off the screen edges.
+pub fn move_bench_sprites(world: &World) {
+    let delta_time = world.time().delta();
+    let sprites = world.read::<BenchSprite>();
+    let mut transforms = world.write::<Transform>();
+    for (entity, sprite) in sprites.iter() {
+        let Some(transform) = transforms.get_mut(*entity) else {
+            continue;
+        };
+        let position = &mut transform.translation;
+        position.x += sprite.velocity.x * delta_time;
+        position.y += sprite.velocity.y * delta_time;
+        // Reflect about the edges instead of storing a flipped velocity, so
+        // the system only needs to read sprites.
+        position.x = position.x.rem_euclid(BENCH_SCREEN.x * 2.0);
+        position.y = position.y.rem_euclid(BENCH_SCREEN.y * 2.0);
+    }
+}
+
+/// Draws [`BenchSprite`]s on a batch begun over [`BENCH_SCREEN`].
+pub fn draw_bench_sprites(world: &World, batch: &mut SpriteBatch) {
+    let sprites = world.read::<BenchSprite>();
+    let transforms = world.read::<Transform>();
+    for (entity, sprite) in sprites.iter() {
+        if let Some(transform) = transforms.get(*entity) {
+            let position = transform.translation;
+            let fold = |value: f32, size: f32| size - (value - size).abs();
+            let center = vec2(
+                fold(position.x, BENCH_SCREEN.x),
+                fold(position.y, BENCH_SCREEN.y),
+            );
+            batch.fill_rect(Rect::from_center(center, sprite.size), sprite.color);
+        }
+    }
+}
+
+/// Returns a system that circles [`BenchLight`]s around their centers.
+pub fn bench_light_system() -> impl System {
+    FunctionSystem::new(
+        "bench_lights",
+        Access::new().write::<BenchLight>().write::<Transform>(),
+        move_bench_lights,
+    )
+}
+
+/// Moves lights along their circles.
+pub fn move_bench_lights(world: &World) {
+    let delta_time = world.time().delta();
+    let mut lights = world.write::<BenchLight>();
+    let mut transforms = world.write::<Transform>();
+    for (entity, light) in lights.iter_mut() {
+        light.angle += light.speed * delta_time;
+        if let Some(transform) = transforms.get_mut(*entity) {
+            transform.translation =
+                light.center + vec3(light.angle.cos(), 0.0, light.angle.sin()) * light.radius;
+        }
+    }
+}
+
+/// Returns a system that simulates [`BenchBody`]s.
+pub fn bench_body_system() -> impl System {
+    FunctionSystem::new(
+        "bench_bodies",
+        Access::new().write::<BenchBody>().write::<Transform>(),
+        simulate_bench_bodies,
+    )
+}
+
+/// Applies gravity, bounces bodies off the box and pushes overlapping bodies
+/// apart, finding neighbours with a uniform grid. This is a stand-in
+/// workload, not a rigid body solver.
+pub fn simulate_bench_bodies(world: &World) {
+    let delta_time = world.time().delta();
+    let mut bodies = world.write::<BenchBody>();
+    let mut transforms = world.write::<Transform>();
+
+    let mut spheres = Vec::with_capacity(bodies.len());
+    for (entity, body) in bodies.iter_mut() {
+        let Some(transform) = transforms.get_mut(*entity) else {
+            continue;
+        };
+        body.velocity.y += GRAVITY * delta_time;
+        let position = &mut transform.translation;
+        *position += body.velocity * delta_time;
+        for (axis, min, max) in [
+            (0, -BODY_BOX, BODY_BOX),
+            (1, 0.0, f32::INFINITY),
+            (2, -BODY_BOX, BODY_BOX),
+        ] {
+            if position[axis] - body.radius < min {
+                position[axis] = min + body.radius;
+                body.velocity[axis] = body.velocity[axis].abs() * 0.6;
+            } else if position[axis] + body.radius > max {
+                position[axis] = max - body.radius;
+                body.velocity[axis] = -body.velocity[axis].abs() * 0.6;
+            }
+        }
+        spheres.push((*entity, *position, body.radius));
+    }
+
+    let cell_size = 1.2;
+    let cell = |position: Vec3| {
+        (
+            (position.x / cell_size).floor() as i32,
+            (position.y / cell_size).floor() as i32,
+            (position.z / cell_size).floor() as i32,
+        )
+    };
+    let mut grid: HashMap<(i32, i32, i32), Vec<usize>> = HashMap::new();
+    for (index, (_, position, _)) in spheres.iter().enumerate() {
+        grid.entry(cell(*position)).or_default().push(index);
+    }
+
+    let mut pushes = vec![Vec3::zero(); spheres.len()];
+    for (index, (_, position, radius)) in spheres.iter().enumerate() {
+        let (x, y, z) = cell(*position);
+        for neighbour in (-1..=1).flat_map(|dx| {
+            (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (x + dx, y + dy, z + dz)))
+        }) {
+            for &other in grid.get(&neighbour).into_iter().flatten() {
+                if other <= index {
+                    continue;
+                }
+                let (_, other_position, other_radius) = spheres[other];
+                let offset = other_position - *position;
+                let distance = offset.magnitude();
+                let overlap = radius + other_radius - distance;
+                if overlap > 0.0 && distance > f32::EPSILON {
+                    let push = offset / distance * (overlap * 0.5);
+                    pushes[index] -= push;
+                    pushes[other] += push;
+                }
+            }
+        }
+    }
+    for ((entity, _, _), push) in spheres.iter().zip(pushes) {
+        if let Some(transform) = transforms.get_mut(*entity) {
+            transform.translation += push;
+        }
+    }
+}
+
+/// Returns a system that moves [`BenchMover`]s.
+pub fn bench_mover_system() -> impl System {
+    FunctionSystem::new(
+        "bench_movers",
+        Access::new().read::<BenchMover>().write::<Transform>(),
+        move_bench_movers,
+    )
+}
+
+/// Moves movers along their velocities, wrapping around the box.
+pub fn move_bench_movers(world: &World) {
+    let delta_time = world.time().delta();
+    let movers = world.read::<BenchMover>();
+    let mut transforms = world.write::<Transform>();
+    let (entities, movers) = movers.as_slices();
+    for (entity, mover) in entities.iter().zip(movers) {
+        if let Some(transform) = transforms.get_mut(*entity) {
+            let position = &mut transform.translation;
+            *position += mover.velocity * delta_time;
+            for axis in 0..3 {
+                position[axis] = (position[axis] + BODY_BOX).rem_euclid(BODY_BOX * 2.0) - BODY_BOX;
+            }
+        }
+    }
+}
+
+/// How long one benchmark frame took, in milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTiming {
+    /// Time spent running the schedule.
+    pub update_ms: f32,
+    /// Time spent in the render callback.
+    pub render_ms: f32,
+}
+
+impl FrameTiming {
+    /// Returns the whole frame's time.
+    pub fn total_ms(&self) -> f32 {
+        self.update_ms + self.render_ms
+    }
+}
+
+/// Frame time statistics of a [`BenchReport`], in milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BenchSummary {
+    pub frames: usize,
+    pub average_ms: f32,
+    pub median_ms: f32,
+    pub p95_ms: f32,
+    pub p99_ms: f32,
+    pub worst_ms: f32,
+    pub average_update_ms: f32,
+    pub average_render_ms: f32,
+}
+
+impl BenchSummary {
+    /// Returns the frames per second matching the average frame time.
+    pub fn average_fps(&self) -> f32 {
+        if self.average_ms > 0.0 {
+            1000.0 / self.average_ms
+        } else {
+            0.0
+        }
+    }
+}
+
+/// How a report compares to a baseline, as fractions: 0.1 is 10% slower.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BenchComparison {
+    pub average_change: f32,
+    pub p95_change: f32,
+}
+
+impl BenchComparison {
+    /// Checks if the average or 95th percentile frame time got slower by more
+    /// than `tolerance`, e.g. 0.05 for 5%.
+    pub fn regressed(&self, tolerance: f32) -> bool {
+        self.average_change > tolerance || self.p95_change > tolerance
+    }
+}
+
+/// # Bench Report
+///
+/// The timings of a benchmark run, frame by frame. Reports are saved as CSV,
+/// one row per frame, which can be loaded back as a baseline, or as JSON with
+/// a summary for dashboards.
+///
+/// ## Example
+/// ```ignore
+/// let report = benchmark.run_headless(&mut world, &mut schedule);
+/// report.save_json("bench/sprites.json")?;
+///
+/// let baseline = BenchReport::load_csv("bench/sprites-baseline.csv")?;
+/// let comparison = report.compare(&baseline);
+/// if comparison.regressed(0.05) {
+///     log::error!("sprites got {:.1}% slower", comparison.average_change * 100.0);
+///     std::process::exit(1);
+/// }
+/// report.save_csv("bench/sprites-baseline.csv")?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BenchReport {
+    pub name: String,
+    /// Entities in the world when the run started.
+    pub entities: usize,
+    pub frames: Vec<FrameTiming>,
+}
+
+impl BenchReport {
+    /// Returns statistics of the frame times.
+    pub fn summary(&self) -> BenchSummary {
+        if self.frames.is_empty() {
+            return BenchSummary::default();
+        }
+        let count = self.frames.len();
+        let mut totals: Vec<f32> = self.frames.iter().map(FrameTiming::total_ms).collect();
+        totals.sort_by(f32::total_cmp);
+        let percentile = |fraction: f32| totals[((count - 1) as f32 * fraction).round() as usize];
+        let average = |value: fn(&FrameTiming) -> f32| {
+            self.frames.iter().map(value).sum::<f32>() / count as f32
+        };
+        BenchSummary {
+            frames: count,
+            average_ms: average(FrameTiming::total_ms),
+            median_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            worst_ms: totals[count - 1],
+            average_update_ms: average(|frame| frame.update_ms),
+            average_render_ms: average(|frame| frame.render_ms),
+        }
+    }
+
+    /// Compares against a baseline run of the same benchmark.
+    pub fn compare(&self, baseline: &BenchReport) -> BenchComparison {
+        let (current, baseline) = (self.summary(), baseline.summary());
+        let change = |current: f32, baseline: f32| {
+            if baseline > 0.0 {
+                current / baseline - 1.0
+            } else {
+                0.0
+            }
+        };
+        BenchComparison {
+            average_change: change(current.average_ms, baseline.average_ms),
+            p95_change: change(current.p95_ms, baseline.p95_ms),
+        }
+    }
+
+    /// Serializes the frames as CSV, after a comment line naming the run.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!(
+            "# {} with {} entities\nframe,update_ms,render_ms,total_ms\n",
+            self.name, self.entities
+        );
+        for (index, frame) in self.frames.iter().enumerate() {
+            let _ = writeln!(
+                csv,
+                "{},{:.4},{:.4},{:.4}",
+                index,
+                frame.update_ms,
+                frame.render_ms,
+                frame.total_ms()
+            );
+        }
+        csv
+    }
+
+    /// Parses frames written by [`BenchReport::to_csv`].
+    pub fn from_csv(text: &str) -> Result<Self, String> {
+        let mut report = BenchReport::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some((name, entities)) = comment.trim().rsplit_once(" with ") {
+                    report.name = name.to_string();
+                    report.entities = entities.trim_end_matches(" entities").parse().unwrap_or(0);
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with("frame") {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').collect();
+            let field = |index: usize| {
+                fields
+                    .get(index)
+                    .and_then(|field| field.trim().parse::<f32>().ok())
+                    .ok_or_else(|| format!("line {}: expected frame timings", number + 1))
+            };
+            report.frames.push(FrameTiming {
+                update_ms: field(1)?,
+                render_ms: field(2)?,
+            });
+        }
+        Ok(report)
+    }
+
+    /// Serializes the summary and frames as JSON.
+    pub fn to_json(&self) -> String {
+        let summary = self.summary();
+        let number = |value: f32| json_number(value as f64);
+        let mut json = format!(
+            "{{\"name\":{},\"entities\":{},\"summary\":{{\"frames\":{},\"average_ms\":{},\"median_ms\":{},\"p95_ms\":{},\"p99_ms\":{},\"worst_ms\":{},\"average_update_ms\":{},\"average_render_ms\":{},\"average_fps\":{}}},\"frames\":[",
+            json_string(&self.name),
+            self.entities,
+            summary.frames,
+            number(summary.average_ms),
+            number(summary.median_ms),
+            number(summary.p95_ms),
+            number(summary.p99_ms),
+            number(summary.worst_ms),
+            number(summary.average_update_ms),
+            number(summary.average_render_ms),
+            number(summary.average_fps()),
+        );
+        for (index, frame) in self.frames.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"update_ms\":{},\"render_ms\":{}}}",
+                number(frame.update_ms),
+                number(frame.render_ms)
+            );
+        }
+        json.push_str("]}\n");
+        json
+    }
+
+    /// Writes the report as CSV.
+    pub fn save_csv(&self, path: &str) -> Result<(), Errors> {
+        fs::write(path, self.to_csv())
+            .map_err(|error| Errors::Benchmark(format!("{}: {}", path, error)))
+    }
+
+    /// Reads a report written by [`BenchReport::save_csv`].
+    pub fn load_csv(path: &str) -> Result<Self, Errors> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| Errors::Benchmark(format!("{}: {}", path, error)))?;
+        Self::from_csv(&text).map_err(|message| Errors::Benchmark(format!("{}: {}", path, message)))
+    }
+
+    /// Writes the report as JSON.
+    pub fn save_json(&self, path: &str) -> Result<(), Errors> {
+        fs::write(path, self.to_json())
+            .map_err(|error| Errors::Benchmark(format!("{}: {}", path, error)))
+    }
+}
+
+/// # Benchmark
+///
+/// Runs a world's schedule for a fixed number of frames and times each one.
+/// Every frame advances the clock by the same step, so runs simulate exactly
+/// the same thing and only the timings differ. Headless runs time only the
+/// simulation; windowed runs pass a callback that renders and presents,
+/// which is timed separately.
+///
+/// ## Example
+/// ```ignore
+/// let scene = BenchScene::new().with_sprites(20_000);
+/// scene.spawn(&mut world);
+/// scene.add_systems(&mut schedule);
+///
+/// let benchmark = Benchmark::new("sprites_20k", 600).with_warmup(60);
+/// let report = benchmark.run(&mut world, &mut schedule, |world| {
+///     batch.begin((1280, 720));
+///     draw_bench_sprites(world, &mut batch);
+///     batch.end();
+///     window.present();
+///     window.poll_events();
+/// });
+/// println!("{:.2} ms average", report.summary().average_ms);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Benchmark {
+    pub name: String,
+    pub frames: u32,
+    /// Frames run before timing starts, to fill caches and settle the scene.
+    pub warmup: u32,
+    /// Seconds each frame advances the clock.
+    pub step: f32,
+}
+
+impl Benchmark {
+    /// Creates a benchmark timing `frames` frames of 1/60 s, without warmup.
+    pub fn new(name: &str, frames: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            frames,
+            warmup: 0,
+            step: 1.0 / 60.0,
+        }
+    }
+
+    /// Sets the number of untimed frames run first.
+    pub fn with_warmup(mut self, warmup: u32) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Sets the seconds each frame advances the clock.
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Runs the schedule without rendering.
+    pub fn run_headless(&self, world: &mut World, schedule: &mut Schedule) -> BenchReport {
+        self.run(world, schedule, |_| {})
+    }
+
+    /// Runs the schedule and then `render` each frame.
+    pub fn run<F>(&self, world: &mut World, schedule: &mut Schedule, mut render: F) -> BenchReport
+    where
+        F: FnMut(&mut World),
+    {
+        let mut report = BenchReport {
+            name: self.name.clone(),
+            entities: world.entity_count(),
+            frames: Vec::with_capacity(self.frames as usize),
+        };
+        log::info!(
+            "Benchmark '{}': {} frames with {} entities",
+            self.name,
+            self.frames,
+            report.entities
+        );
+        for frame in 0..self.warmup + self.frames {
+            world.time_mut().advance(self.step);
+            let start = Instant::now();
+            schedule.run(world);
+            let updated = Instant::now();
+            render(world);
+            let timing = FrameTiming {
+                update_ms: (updated - start).as_secs_f32() * 1000.0,
+                render_ms: updated.elapsed().as_secs_f32() * 1000.0,
+            };
+            if frame >= self.warmup {
+                report.frames.push(timing);
+            }
+        }
+        let summary = report.summary();
+        log::info!(
+            "Benchmark '{}': {:.2} ms average, {:.2} ms p95, {:.2} ms worst",
+            self.name,
+            summary.average_ms,
+            summary.p95_ms,
+            summary.worst_ms
+        );
+        report
+    }
+}
@@ -0,0 +1,324 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::custom_errors::Errors;
+use crate::{jobs, logger};
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext::new());
+static OPTIONS: Mutex<Option<CrashOptions>> = Mutex::new(None);
+static LAST_FRAME: AtomicU64 = AtomicU64::new(0);
+static LAST_DELTA: AtomicU32 = AtomicU32::new(0);
+static AVERAGE_DELTA: AtomicU32 = AtomicU32::new(0);
+
+struct CrashContext {
+    gpu: Option<String>,
+    entries: Vec<(String, String)>,
+}
+
+impl CrashContext {
+    const fn new() -> Self {
+        Self {
+            gpu: None,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// How crashes are reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrashOptions {
+    /// Name of the game, shown in the report and the message box.
+    pub app_name: String,
+    /// Folder reports are written to, created if needed.
+    pub directory: PathBuf,
+    /// Whether to tell the player with a message box, for release builds
+    /// without a console.
+    pub message_box: bool,
+}
+
+impl CrashOptions {
+    /// Creates options writing reports to a `crashes` folder next to the
+    /// working directory, without a message box.
+    pub fn new(app_name: &str) -> Self {
+        Self {
+            app_name: app_name.to_string(),
+            directory: PathBuf::from("crashes"),
+            message_box: false,
+        }
+    }
+
+    /// Sets the folder reports are written to.
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = directory.into();
+        self
+    }
+
+    /// Sets whether the player is told with a message box.
+    pub fn with_message_box(mut self, message_box: bool) -> Self {
+        self.message_box = message_box;
+        self
+    }
+}
+
+/// Installs a panic hook that writes a diagnostic report before the usual
+/// panic message: the panic and where it happened, a backtrace, the engine
+/// version, the GPU, the last frame's timing, context set with
+/// [`set_context`] and the most recent log lines kept by
+/// [`logger::init`](crate::logger::init).
+///
+/// ## Example
+/// ```ignore
+/// logger::init();
+/// crash::install(CrashOptions::new("Nyanko Quest").with_message_box(!cfg!(debug_assertions)));
+///
+/// crash::set_context("level", "forest_2");
+/// ```
+pub fn install(options: CrashOptions) {
+    *OPTIONS.lock().unwrap_or_else(PoisonError::into_inner) = Some(options);
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // A job's panic is handed to whoever waits for it, which reports it
+        // through `report_resumed` if nothing catches it there.
+        if !jobs::catching_panic() {
+            report(&panic_message(info));
+        }
+        previous(info);
+    }));
+}
+
+/// Reports a job's panic re-raised on the thread that waited for the job,
+/// unless that thread catches it in turn.
+pub(crate) fn report_resumed(payload: &(dyn Any + Send)) {
+    if !jobs::catching_panic() {
+        report(&format!("{} (raised in a job)", payload_message(payload)));
+    }
+}
+
+fn report(reason: &str) {
+    let options = OPTIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    let Some(options) = options else {
+        return;
+    };
+    let report = build_report(&options.app_name, reason);
+    match write_report(&options.directory, &report) {
+        Ok(path) => {
+            log::error!("Crash report written to {}", path.display());
+            if options.message_box {
+                show_message_box(
+                    &options.app_name,
+                    &format!(
+                        "{} has crashed.\n\nA report was saved to:\n{}",
+                        options.app_name,
+                        path.display()
+                    ),
+                );
+            }
+        }
+        Err(error) => log::error!("{}", error),
+    }
+}
+
+/// Records the GPU for crash reports. Called by
+/// [`Window::init_gl`](crate::graphics::window::Window::init_gl).
+pub fn set_gpu_info(description: &str) {
+    with_context(|context| context.gpu = Some(description.to_string()));
+}
+
+/// Adds or replaces a line of context in crash reports, such as the current
+/// level or game mode.
+pub fn set_context(key: &str, value: &str) {
+    with_context(
+        |context| match context.entries.iter_mut().find(|(name, _)| name == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => context.entries.push((key.to_string(), value.to_string())),
+        },
+    );
+}
+
+/// Records a frame's number and real duration in seconds for crash reports.
+/// Called by [`Time::advance`](crate::time::Time::advance).
+pub fn record_frame(frame: u64, delta_time: f32) {
+    LAST_FRAME.store(frame, Ordering::Relaxed);
+    LAST_DELTA.store(delta_time.to_bits(), Ordering::Relaxed);
+    let average = f32::from_bits(AVERAGE_DELTA.load(Ordering::Relaxed));
+    let average = if average == 0.0 {
+        delta_time
+    } else {
+        average + (delta_time - average) * 0.05
+    };
+    AVERAGE_DELTA.store(average.to_bits(), Ordering::Relaxed);
+}
+
+fn with_context(update: impl FnOnce(&mut CrashContext)) {
+    match CONTEXT.lock() {
+        Ok(mut context) => update(&mut context),
+        Err(poisoned) => update(&mut poisoned.into_inner()),
+    }
+}
+
+/// Writes a diagnostic report without crashing, for example from a debug
+/// key, and returns its path.
+pub fn write_diagnostics(
+    directory: &Path,
+    app_name: &str,
+    reason: &str,
+) -> Result<PathBuf, Errors> {
+    write_report(directory, &build_report(app_name, reason))
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let message = payload_message(info.payload());
+    match info.location() {
+        Some(location) => format!("{} at {}", message, location),
+        None => message,
+    }
+}
+
+fn build_report(app_name: &str, reason: &str) -> String {
+    let mut report = String::new();
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let _ = writeln!(report, "{} crash report", app_name);
+    let _ = writeln!(
+        report,
+        "Engine: nyanko_engine {}",
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(report, "Time: {} (Unix seconds)", seconds);
+    let _ = writeln!(
+        report,
+        "System: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(
+        report,
+        "Thread: {}",
+        thread::current().name().unwrap_or("unnamed")
+    );
+    let _ = writeln!(report, "Reason: {}", reason);
+
+    // The panicking thread may hold the lock; a report without context beats
+    // a deadlock.
+    match CONTEXT.try_lock() {
+        Ok(context) => {
+            let _ = writeln!(
+                report,
+                "GPU: {}",
+                context.gpu.as_deref().unwrap_or("not initialized")
+            );
+            for (key, value) in &context.entries {
+                let _ = writeln!(report, "{}: {}", key, value);
+            }
+        }
+        Err(_) => report.push_str("GPU: unavailable\n"),
+    }
+
+    let frame = LAST_FRAME.load(Ordering::Relaxed);
+    let delta = f32::from_bits(LAST_DELTA.load(Ordering::Relaxed));
+    let average = f32::from_bits(AVERAGE_DELTA.load(Ordering::Relaxed));
+    if average > 0.0 {
+        let _ = writeln!(
+            report,
+            "Last frame: #{} took {:.2} ms, averaging {:.1} FPS",
+            frame,
+            delta * 1000.0,
+            1.0 / average
+        );
+    } else {
+        report.push_str("Last frame: none\n");
+    }
+
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+    report.push_str("\nRecent log:\n");
+    for line in logger::recent_lines() {
+        report.push_str(&line);
+        report.push('\n');
+    }
+    report
+}
+
+fn write_report(directory: &Path, report: &str) -> Result<PathBuf, Errors> {
+    let error =
+        |message: String| Errors::CrashReport(format!("{}: {}", directory.display(), message));
+    fs::create_dir_all(directory).map_err(|io| error(io.to_string()))?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let path = directory.join(format!("crash-{}.txt", millis));
+    fs::write(&path, report).map_err(|io| error(io.to_string()))?;
+    Ok(path)
+}
+
+#[cfg(target_os = "windows")]
+fn show_message_box(title: &str, message: &str) {
+    #[link(name = "user32")]
+    extern "system" {
+        fn MessageBoxW(window: *mut u8, text: *const u16, caption: *const u16, kind: u32) -> i32;
+    }
+    const MB_ICONERROR: u32 = 0x10;
+    let wide = |text: &str| text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let (text, caption) = (wide(message), wide(title));
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_ICONERROR,
+        );
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn show_message_box(title: &str, message: &str) {
+    let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let script = format!(
+        "display alert \"{}\" message \"{}\" as critical",
+        quote(title),
+        quote(message)
+    );
+    let _ = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .status();
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn show_message_box(title: &str, message: &str) {
+    // Whichever dialog tool the desktop has; without one the report is still
+    // written and logged.
+    let tools: [(&str, Vec<&str>); 3] = [
+        (
+            "zenity",
+            vec!["--error", "--title", title, "--text", message],
+        ),
+        ("kdialog", vec!["--title", title, "--error", message]),
+        ("xmessage", vec!["-center", message]),
+    ];
+    for (tool, args) in tools {
+        if std::process::Command::new(tool).args(args).status().is_ok() {
+            return;
+        }
+    }
+}
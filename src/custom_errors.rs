@@ -4,4 +4,61 @@ use thiserror::Error;
 pub enum Errors {
     #[error("This is a testing error for the nyanko engine.")]
     TestError,
-}
\ No newline at end of file
+
+    #[error("Failed to load font: {0}")]
+    InvalidFont(String),
+
+    #[error("Failed to load string table: {0}")]
+    InvalidStringTable(String),
+
+    #[error("Recording failed: {0}")]
+    Capture(String),
+
+    #[error("Replay file error: {0}")]
+    InvalidReplay(String),
+
+    #[error("Failed to load item definitions: {0}")]
+    InvalidItemDatabase(String),
+
+    #[error("Failed to load dialogue: {0}")]
+    InvalidDialogue(String),
+
+    #[error("Failed to load image: {0}")]
+    InvalidImage(String),
+
+    #[error("Unsupported by the graphics backend: {0}")]
+    UnsupportedByBackend(String),
+
+    #[error("Failed to load audio: {0}")]
+    InvalidAudio(String),
+
+    #[error("Telemetry error: {0}")]
+    Telemetry(String),
+
+    #[error("Failed to write crash report: {0}")]
+    CrashReport(String),
+
+    #[error("Console variable error: {0}")]
+    InvalidCVar(String),
+
+    #[error("Failed to read or write a file: {0}")]
+    Io(String),
+
+    #[error("Hot reload failed: {0}")]
+    HotReload(String),
+
+    #[error("Benchmark report error: {0}")]
+    Benchmark(String),
+
+    #[error("Golden image mismatch: {0}")]
+    GoldenImage(String),
+
+    #[error("Simulation is not deterministic: {0}")]
+    Nondeterministic(String),
+
+    #[error("Shader preprocessing failed: {0}")]
+    ShaderPreprocess(String),
+
+    #[error("Shader validation failed: {0}")]
+    ShaderValidation(String),
+}
@@ -0,0 +1,605 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+
+use crate::custom_errors::Errors;
+
+/// Parses a bool the way players type one: `1`/`0`, `true`/`false`,
+/// `on`/`off` or `yes`/`no`.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// The value of a [`CVar`]. A variable keeps the type of its default.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CVarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl CVarValue {
+    /// Returns the name of the value's type, for messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            CVarValue::Bool(_) => "bool",
+            CVarValue::Int(_) => "int",
+            CVarValue::Float(_) => "float",
+            CVarValue::Text(_) => "string",
+        }
+    }
+
+    /// Returns the value if it's a bool.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            CVarValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's an int.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            CVarValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a number, converting ints.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            CVarValue::Int(value) => Some(*value as f64),
+            CVarValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's text.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            CVarValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Parses text as a value of the same type as this one, see
+    /// [`parse_bool`] for bools. Text may be quoted as written by
+    /// [`CVars::to_text`].
+    pub fn parse_like(&self, text: &str) -> Option<CVarValue> {
+        let text = text.trim();
+        match self {
+            CVarValue::Bool(_) => parse_bool(text).map(CVarValue::Bool),
+            CVarValue::Int(_) => text.parse().ok().map(CVarValue::Int),
+            CVarValue::Float(_) => text.parse().ok().map(CVarValue::Float),
+            CVarValue::Text(_) => Some(CVarValue::Text(
+                unquote(text).unwrap_or_else(|| text.to_string()),
+            )),
+        }
+    }
+
+    /// Converts a value to the type of this one, where that loses nothing:
+    /// ints become floats and 0 or 1 become bools.
+    fn coerce(&self, value: CVarValue) -> Option<CVarValue> {
+        match (self, value) {
+            (CVarValue::Bool(_), CVarValue::Bool(value)) => Some(CVarValue::Bool(value)),
+            (CVarValue::Bool(_), CVarValue::Int(value)) if value == 0 || value == 1 => {
+                Some(CVarValue::Bool(value == 1))
+            }
+            (CVarValue::Int(_), CVarValue::Int(value)) => Some(CVarValue::Int(value)),
+            (CVarValue::Float(_), CVarValue::Int(value)) => Some(CVarValue::Float(value as f64)),
+            (CVarValue::Float(_), CVarValue::Float(value)) => Some(CVarValue::Float(value)),
+            (CVarValue::Text(_), CVarValue::Text(value)) => Some(CVarValue::Text(value)),
+            _ => None,
+        }
+    }
+}
+
+/// Quotes text for a config file, escaping quotes, backslashes and line breaks.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Reverses [`quote`], or returns `None` if the text isn't quoted.
+fn unquote(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('r') => text.push('\r'),
+            Some('t') => text.push('\t'),
+            Some(escaped @ ('"' | '\\')) => text.push(escaped),
+            Some(other) => {
+                text.push('\\');
+                text.push(other);
+            }
+            None => text.push('\\'),
+        }
+    }
+    Some(text)
+}
+
+impl fmt::Display for CVarValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CVarValue::Bool(value) => write!(f, "{}", if *value { 1 } else { 0 }),
+            CVarValue::Int(value) => write!(f, "{}", value),
+            CVarValue::Float(value) => write!(f, "{}", value),
+            CVarValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<bool> for CVarValue {
+    fn from(value: bool) -> Self {
+        CVarValue::Bool(value)
+    }
+}
+
+impl From<i32> for CVarValue {
+    fn from(value: i32) -> Self {
+        CVarValue::Int(value as i64)
+    }
+}
+
+impl From<i64> for CVarValue {
+    fn from(value: i64) -> Self {
+        CVarValue::Int(value)
+    }
+}
+
+impl From<u32> for CVarValue {
+    fn from(value: u32) -> Self {
+        CVarValue::Int(value as i64)
+    }
+}
+
+impl From<f32> for CVarValue {
+    fn from(value: f32) -> Self {
+        CVarValue::Float(value as f64)
+    }
+}
+
+impl From<f64> for CVarValue {
+    fn from(value: f64) -> Self {
+        CVarValue::Float(value)
+    }
+}
+
+impl From<&str> for CVarValue {
+    fn from(value: &str) -> Self {
+        CVarValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for CVarValue {
+    fn from(value: String) -> Self {
+        CVarValue::Text(value)
+    }
+}
+
+type ChangeCallback = Box<dyn FnMut(&CVarValue) + Send + Sync>;
+
+/// # CVar
+///
+/// A named, typed setting such as `r.vsync` or `time.scale`, with a default,
+/// help text and, for numbers, an allowed range. Register it with
+/// [`CVars::register`].
+///
+/// ## Example
+/// ```ignore
+/// cvars.register(CVar::new("r.shadow_resolution", 2048, "Shadow map size in pixels").with_range(256.0, 8192.0));
+/// cvars.register(CVar::new("game.difficulty", "normal", "easy, normal or hard"));
+/// ```
+pub struct CVar {
+    name: String,
+    help: String,
+    default: CVarValue,
+    value: CVarValue,
+    range: Option<(f64, f64)>,
+    callbacks: Vec<ChangeCallback>,
+}
+
+impl CVar {
+    /// Creates a variable whose type is the type of its default.
+    pub fn new(name: &str, default: impl Into<CVarValue>, help: &str) -> Self {
+        let default = default.into();
+        Self {
+            name: name.to_string(),
+            help: help.to_string(),
+            value: default.clone(),
+            default,
+            range: None,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Limits a number variable to a range; values outside it are clamped.
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.range = Some((min.min(max), max.max(min)));
+        self.value = self.clamp(self.value.clone());
+        self
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the help text.
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> &CVarValue {
+        &self.value
+    }
+
+    /// Returns the default value.
+    pub fn default_value(&self) -> &CVarValue {
+        &self.default
+    }
+
+    /// Returns the allowed range of a number variable.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.range
+    }
+
+    /// Checks if the variable has its default value.
+    pub fn is_default(&self) -> bool {
+        self.value == self.default
+    }
+
+    fn clamp(&self, value: CVarValue) -> CVarValue {
+        match (self.range, value) {
+            (Some((min, max)), CVarValue::Int(value)) => {
+                CVarValue::Int(value.clamp(min.ceil() as i64, max.floor() as i64))
+            }
+            (Some((min, max)), CVarValue::Float(value)) => CVarValue::Float(value.clamp(min, max)),
+            (_, value) => value,
+        }
+    }
+}
+
+impl fmt::Debug for CVar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CVar")
+            .field("name", &self.name)
+            .field("value", &self.value)
+            .field("default", &self.default)
+            .field("range", &self.range)
+            .finish()
+    }
+}
+
+/// # CVars
+///
+/// The registry of console variables: engine and game settings that can be
+/// changed from code, the developer console and config files. Each variable
+/// keeps the type of its default, numbers stay within their range, and
+/// callbacks run whenever a value actually changes.
+///
+/// Config files hold `name = value` lines, and lines starting with `#` are
+/// comments. Text values are saved in quotes with `\"`, `\\` and `\n`
+/// escapes, though unquoted text loads too. Values
+/// for names not registered yet are kept and applied when they are, so the
+/// config can be loaded before every system has registered its variables.
+///
+/// The engine's own variables come from [`register_engine_cvars`] and are
+/// applied by [`Window::apply_cvars`](crate::graphics::window::Window::apply_cvars),
+/// [`Renderer::apply_cvars`](crate::graphics::renderer::Renderer::apply_cvars)
+/// and [`Time::apply_cvars`](crate::time::Time::apply_cvars). The audio bus
+/// volumes come from [`Mixer::register_cvars`](crate::audio::Mixer::register_cvars).
+///
+/// ## Example
+/// ```ignore
+/// let mut cvars = CVars::new();
+/// register_engine_cvars(&mut cvars);
+/// cvars.register(CVar::new("game.fov", 70.0, "Field of view in degrees").with_range(50.0, 110.0));
+/// cvars.on_change("game.fov", |value| log::info!("FOV is now {}", value))?;
+/// cvars.load("settings.cfg")?;
+///
+/// cvars.set("game.fov", 90.0)?;
+/// cvars.set_from_str("r.vsync", "off")?;
+/// let fov = cvars.float("game.fov").unwrap_or(70.0);
+///
+/// cvars.save("settings.cfg")?;
+/// ```
+#[derive(Debug, Default)]
+pub struct CVars {
+    vars: BTreeMap<String, CVar>,
+    pending: HashMap<String, String>,
+}
+
+impl CVars {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a variable. Replacing one with the same name keeps its callbacks,
+    /// and a value loaded for it earlier is applied.
+    pub fn register(&mut self, mut cvar: CVar) {
+        if let Some(previous) = self.vars.remove(&cvar.name) {
+            log::warn!("Console variable '{}' registered twice", cvar.name);
+            cvar.callbacks = previous.callbacks;
+        }
+        let name = cvar.name.clone();
+        self.vars.insert(name.clone(), cvar);
+        if let Some(text) = self.pending.remove(&name) {
+            if let Err(error) = self.set_from_str(&name, &text) {
+                log::warn!("{}", error);
+            }
+        }
+    }
+
+    /// Checks if a variable is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+
+    /// Returns a variable.
+    pub fn get(&self, name: &str) -> Option<&CVar> {
+        self.vars.get(name)
+    }
+
+    /// Returns every variable, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &CVar> {
+        self.vars.values()
+    }
+
+    /// Returns a variable's value.
+    pub fn value(&self, name: &str) -> Option<&CVarValue> {
+        self.get(name).map(CVar::value)
+    }
+
+    /// Returns a bool variable's value.
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        self.value(name).and_then(CVarValue::as_bool)
+    }
+
+    /// Returns an int variable's value.
+    pub fn int(&self, name: &str) -> Option<i64> {
+        self.value(name).and_then(CVarValue::as_int)
+    }
+
+    /// Returns a number variable's value.
+    pub fn float(&self, name: &str) -> Option<f64> {
+        self.value(name).and_then(CVarValue::as_float)
+    }
+
+    /// Returns a string variable's value.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.value(name).and_then(CVarValue::as_text)
+    }
+
+    /// Changes a variable, clamping numbers to its range, and runs its
+    /// callbacks if the value changed.
+    pub fn set(&mut self, name: &str, value: impl Into<CVarValue>) -> Result<(), Errors> {
+        let value = value.into();
+        let cvar = self
+            .vars
+            .get_mut(name)
+            .ok_or_else(|| Errors::InvalidCVar(format!("unknown variable '{}'", name)))?;
+        let converted = cvar.default.coerce(value.clone()).ok_or_else(|| {
+            Errors::InvalidCVar(format!(
+                "{} is of type {}, not {}",
+                name,
+                cvar.default.type_name(),
+                value.type_name()
+            ))
+        })?;
+        let clamped = cvar.clamp(converted);
+        if clamped != cvar.value {
+            cvar.value = clamped;
+            for callback in &mut cvar.callbacks {
+                callback(&cvar.value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Changes a variable from text, as typed in the console or read from a
+    /// config file.
+    pub fn set_from_str(&mut self, name: &str, text: &str) -> Result<(), Errors> {
+        let cvar = self
+            .get(name)
+            .ok_or_else(|| Errors::InvalidCVar(format!("unknown variable '{}'", name)))?;
+        let value = cvar.default.parse_like(text).ok_or_else(|| {
+            Errors::InvalidCVar(format!(
+                "{} is of type {}, '{}' doesn't parse as one",
+                name,
+                cvar.default.type_name(),
+                text.trim()
+            ))
+        })?;
+        self.set(name, value)
+    }
+
+    /// Puts a variable back to its default.
+    pub fn reset(&mut self, name: &str) -> Result<(), Errors> {
+        let default = self
+            .get(name)
+            .map(|cvar| cvar.default.clone())
+            .ok_or_else(|| Errors::InvalidCVar(format!("unknown variable '{}'", name)))?;
+        self.set(name, default)
+    }
+
+    /// Adds a callback run with the new value whenever a variable changes.
+    pub fn on_change<F>(&mut self, name: &str, callback: F) -> Result<(), Errors>
+    where
+        F: FnMut(&CVarValue) + Send + Sync + 'static,
+    {
+        let cvar = self
+            .vars
+            .get_mut(name)
+            .ok_or_else(|| Errors::InvalidCVar(format!("unknown variable '{}'", name)))?;
+        cvar.callbacks.push(Box::new(callback));
+        Ok(())
+    }
+
+    /// Applies `name = value` lines. Lines starting with `#` are comments.
+    /// Bad values are skipped with a warning so one typo doesn't lose the rest
+    /// of the settings; malformed lines are an error.
+    pub fn apply_text(&mut self, text: &str) -> Result<(), String> {
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'name = value'", number + 1))?;
+            let (name, value) = (name.trim(), value.trim());
+            if name.is_empty() {
+                return Err(format!("line {}: missing variable name", number + 1));
+            }
+            if !self.contains(name) {
+                self.pending.insert(name.to_string(), value.to_string());
+            } else if let Err(error) = self.set_from_str(name, value) {
+                log::warn!("line {}: {}", number + 1, error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Serializes the variables that differ from their defaults, with their
+    /// help as comments, followed by values loaded for unregistered names.
+    /// Text values are quoted, so they can hold `#` and line breaks.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for cvar in self.iter().filter(|cvar| !cvar.is_default()) {
+            for line in cvar.help.lines() {
+                text.push_str(&format!("# {}\n", line));
+            }
+            let value = match &cvar.value {
+                CVarValue::Text(value) => quote(value),
+                value => value.to_string(),
+            };
+            text.push_str(&format!("{} = {}\n", cvar.name, value));
+        }
+        let mut pending: Vec<_> = self.pending.iter().collect();
+        pending.sort();
+        for (name, value) in pending {
+            text.push_str(&format!("{} = {}\n", name, value));
+        }
+        text
+    }
+
+    /// Reads and applies a config file.
+    pub fn load(&mut self, path: &str) -> Result<(), Errors> {
+        let text =
+            fs::read_to_string(path).map_err(|error| Errors::Io(format!("{}: {}", path, error)))?;
+        self.apply_text(&text)
+            .map_err(|message| Errors::InvalidCVar(format!("{}: {}", path, message)))
+    }
+
+    /// Writes the changed variables to a config file.
+    pub fn save(&self, path: &str) -> Result<(), Errors> {
+        fs::write(path, self.to_text()).map_err(|error| Errors::Io(format!("{}: {}", path, error)))
+    }
+}
+
+/// Registers the engine's own variables:
+///
+/// - `r.vsync`: wait for the display's refresh before presenting.
+/// - `r.debug_view`: index of the renderer's debug view, see
+///   [`DebugView::ALL`](crate::graphics::debug_view::DebugView::ALL).
+/// - `time.scale`: speed of game time.
+/// - `time.max_delta`: longest frame time simulated, in seconds.
+pub fn register_engine_cvars(cvars: &mut CVars) {
+    use crate::graphics::debug_view::DebugView;
+
+    cvars.register(CVar::new(
+        "r.vsync",
+        true,
+        "Wait for the display's refresh before presenting",
+    ));
+    cvars.register(
+        CVar::new("r.debug_view", 0, "Renderer debug view, 0 is lit")
+            .with_range(0.0, (DebugView::ALL.len() - 1) as f64),
+    );
+    cvars.register(
+        CVar::new("time.scale", 1.0, "Speed of game time, 1 is normal").with_range(0.0, 100.0),
+    );
+    cvars.register(
+        CVar::new(
+            "time.max_delta",
+            0.25,
+            "Longest frame time simulated, in seconds",
+        )
+        .with_range(0.001, 10.0),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> CVars {
+        let mut cvars = CVars::new();
+        cvars.register(CVar::new(
+            "game.greeting",
+            "hello",
+            "Shown on the title screen\nwhen the game starts",
+        ));
+        cvars.register(CVar::new("game.fov", 70.0, "Field of view"));
+        cvars
+    }
+
+    #[test]
+    fn text_values_survive_saving_and_loading() {
+        let mut cvars = registry();
+        let greeting = "#1 \"best\" game\nC:\\saves\ttab";
+        cvars.set("game.greeting", greeting).unwrap();
+        cvars.set("game.fov", 90.0).unwrap();
+
+        let text = cvars.to_text();
+        assert_eq!(text.lines().count(), 5);
+        let mut loaded = registry();
+        loaded.apply_text(&text).unwrap();
+        assert_eq!(loaded.text("game.greeting"), Some(greeting));
+        assert_eq!(loaded.float("game.fov"), Some(90.0));
+    }
+
+    #[test]
+    fn only_lines_starting_with_a_hash_are_comments() {
+        let mut cvars = registry();
+        cvars
+            .apply_text("  # game.fov = 100\ngame.greeting = press # to start\n")
+            .unwrap();
+        assert_eq!(cvars.float("game.fov"), Some(70.0));
+        assert_eq!(cvars.text("game.greeting"), Some("press # to start"));
+    }
+
+    #[test]
+    fn missing_files_are_io_errors() {
+        let mut cvars = registry();
+        let result = cvars.load("/nonexistent/nyanko/settings.cfg");
+        assert!(matches!(result, Err(Errors::Io(_))));
+    }
+}
@@ -0,0 +1,167 @@
+use std::cell::Cell;
+use std::ops::{Deref, DerefMut};
+
+/// # Tick
+///
+/// A point in a world's history. The world's tick advances every time a
+/// schedule runs a system, and components remember the tick they were added
+/// and last changed at, so a system can ask what changed since it last ran.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tick(u64);
+
+impl Tick {
+    /// Creates a tick from a raw value.
+    pub const fn new(tick: u64) -> Self {
+        Self(tick)
+    }
+
+    /// Returns the raw value of the tick.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Checks if this tick came after `last_run`.
+    pub fn is_newer_than(self, last_run: Tick) -> bool {
+        self.0 > last_run.0
+    }
+}
+
+/// The ticks a component was added and last changed at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: Tick,
+    pub changed: Tick,
+}
+
+impl ComponentTicks {
+    /// Creates ticks for a component added at `tick`.
+    pub fn new(tick: Tick) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    /// Checks if the component was added after `last_run`.
+    pub fn is_added(&self, last_run: Tick) -> bool {
+        self.added.is_newer_than(last_run)
+    }
+
+    /// Checks if the component was added or changed after `last_run`.
+    pub fn is_changed(&self, last_run: Tick) -> bool {
+        self.changed.is_newer_than(last_run)
+    }
+}
+
+/// The ticks of the system running on the current thread: when it last ran,
+/// and the tick its changes are stamped with now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SystemTicks {
+    pub last_run: Tick,
+    pub this_run: Tick,
+}
+
+thread_local! {
+    static SYSTEM_TICKS: Cell<Option<SystemTicks>> = const { Cell::new(None) };
+}
+
+/// Puts back the ticks that were current before a system ran, even if the
+/// system panicked, so the thread doesn't keep stamping writes with them.
+struct RestoreTicks(Option<SystemTicks>);
+
+impl Drop for RestoreTicks {
+    fn drop(&mut self) {
+        SYSTEM_TICKS.with(|current| current.set(self.0));
+    }
+}
+
+/// Runs `func` as a system with the given ticks, restoring the previous ticks
+/// afterwards.
+pub(crate) fn with_system_ticks<R>(ticks: SystemTicks, func: impl FnOnce() -> R) -> R {
+    let _restore = RestoreTicks(SYSTEM_TICKS.with(|current| current.replace(Some(ticks))));
+    func()
+}
+
+/// Returns the ticks of the system running on this thread, if any.
+pub fn system_ticks() -> Option<SystemTicks> {
+    SYSTEM_TICKS.with(Cell::get)
+}
+
+/// Returns the tick the running system last ran at, or the start of time
+/// outside of systems.
+pub(crate) fn last_run() -> Tick {
+    system_ticks().map_or(Tick::default(), |ticks| ticks.last_run)
+}
+
+/// # Mut
+///
+/// Mutable access to a component that marks it changed only when it is
+/// actually written through, so reading through a mutable query doesn't
+/// trigger `Changed` filters.
+///
+/// ## Example
+/// ```ignore
+/// for (_, mut health) in world.query_mut::<Health>().iter_mut() {
+///     if health.current > health.max {
+///         health.current = health.max; // marked changed here only
+///     }
+/// }
+/// ```
+pub struct Mut<'a, T> {
+    value: &'a mut T,
+    ticks: &'a mut ComponentTicks,
+    this_run: Tick,
+}
+
+impl<'a, T> Mut<'a, T> {
+    pub(crate) fn new(value: &'a mut T, ticks: &'a mut ComponentTicks, this_run: Tick) -> Self {
+        Self {
+            value,
+            ticks,
+            this_run,
+        }
+    }
+
+    /// Returns the ticks the component was added and last changed at.
+    pub fn ticks(&self) -> ComponentTicks {
+        *self.ticks
+    }
+
+    /// Marks the component changed without writing to it.
+    pub fn set_changed(&mut self) {
+        self.ticks.changed = self.this_run;
+    }
+
+    /// Returns a shorter-lived `Mut` to the same component, for handing it to
+    /// a function without giving it up.
+    pub fn reborrow(&mut self) -> Mut<'_, T> {
+        Mut::new(self.value, self.ticks, self.this_run)
+    }
+
+    /// Returns the component mutably without marking it changed.
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        self.value
+    }
+
+    /// Returns the component mutably for the rest of the borrow, marking it
+    /// changed.
+    pub fn into_inner(self) -> &'a mut T {
+        self.ticks.changed = self.this_run;
+        self.value
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.ticks.changed = self.this_run;
+        self.value
+    }
+}
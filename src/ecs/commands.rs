@@ -0,0 +1,154 @@
+use std::any::type_name;
+
+use super::entity::Entity;
+use super::storage::Component;
+use super::world::World;
+
+/// A structural change waiting for exclusive access to the world.
+pub(crate) type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+/// # Commands
+///
+/// Records spawns, despawns, inserts and removals that a system can't make
+/// through a shared `&World`, to apply at the next sync point: after each
+/// stage of a [`Schedule`](super::Schedule), or when
+/// [`World::apply_commands`] is called. Commands are handed to the world
+/// when dropped, so systems running in parallel can each record their own.
+///
+/// Spawned entities get their ids straight away, so later commands can
+/// refer to them, but they don't exist in the world until applied. Inserts
+/// into entities despawned by then are skipped with a warning. Commands are
+/// applied in the order they were handed over, which for deterministic
+/// worlds is the order of the systems.
+///
+/// ## Example
+/// ```ignore
+/// fn fire(world: &World) {
+///     let mut commands = world.commands();
+///     for (entity, gun) in world.read::<Gun>().iter() {
+///         if gun.triggered {
+///             commands.spawn().insert(Bullet::new(gun.speed)).insert(Transform::new());
+///         }
+///     }
+///     for (entity, bullet) in world.read::<Bullet>().iter() {
+///         if bullet.expired() {
+///             commands.despawn(*entity);
+///         }
+///     }
+/// }
+/// ```
+pub struct Commands<'w> {
+    world: &'w World,
+    queue: Vec<Command>,
+}
+
+impl<'w> Commands<'w> {
+    /// Creates an empty command list for the world.
+    pub(crate) fn new(world: &'w World) -> Self {
+        Self {
+            world,
+            queue: Vec::new(),
+        }
+    }
+
+    /// Reserves a new entity, spawned when the commands are applied.
+    pub fn spawn(&mut self) -> EntityCommands<'_, 'w> {
+        let entity = self.world.reserve_entity();
+        self.add(move |world| world.spawn_reserved(entity));
+        EntityCommands {
+            entity,
+            commands: self,
+        }
+    }
+
+    /// Returns commands for an existing entity.
+    pub fn entity(&mut self, entity: Entity) -> EntityCommands<'_, 'w> {
+        EntityCommands {
+            entity,
+            commands: self,
+        }
+    }
+
+    /// Attaches a component to an entity, replacing any previous value.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        self.add(move |world| {
+            if world.contains(entity) {
+                world.insert(entity, component);
+            } else {
+                log::warn!(
+                    "Skipped inserting {} into despawned entity {:?}",
+                    type_name::<T>(),
+                    entity
+                );
+            }
+        });
+    }
+
+    /// Removes a component from an entity.
+    pub fn remove<T: Component>(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.remove::<T>(entity);
+        });
+    }
+
+    /// Removes an entity and all of its components.
+    pub fn despawn(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    /// Records any change to make with exclusive world access.
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.queue.push(Box::new(command));
+    }
+
+    /// Returns the number of commands recorded.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks if no commands were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl Drop for Commands<'_> {
+    fn drop(&mut self) {
+        self.world.queue_commands(&mut self.queue);
+    }
+}
+
+/// # Entity Commands
+///
+/// Commands for one entity, returned by [`Commands::spawn`] and
+/// [`Commands::entity`], whose methods chain.
+pub struct EntityCommands<'c, 'w> {
+    entity: Entity,
+    commands: &'c mut Commands<'w>,
+}
+
+impl EntityCommands<'_, '_> {
+    /// Returns the entity.
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    /// Attaches a component, replacing any previous value.
+    pub fn insert<T: Component>(&mut self, component: T) -> &mut Self {
+        self.commands.insert(self.entity, component);
+        self
+    }
+
+    /// Removes a component.
+    pub fn remove<T: Component>(&mut self) -> &mut Self {
+        self.commands.remove::<T>(self.entity);
+        self
+    }
+
+    /// Removes the entity and all of its components.
+    pub fn despawn(self) {
+        self.commands.despawn(self.entity);
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Debug;
+use std::ops::Not;
+
+use super::resource::Resource;
+use super::state::CurrentState;
+use super::world::World;
+
+/// # Run Condition
+///
+/// Decides whether systems run this update. A [`Schedule`](super::Schedule)
+/// checks each condition once at the start of every run; systems gated by a
+/// condition that fails are skipped, and still see everything that changed
+/// while skipped once they run again. Conditions are checked in the order
+/// they were added, which keeps stateful ones like [`on_timer`]
+/// deterministic.
+///
+/// ## Example
+/// ```ignore
+/// let playing = in_state(GameState::Playing);
+/// let autosave = on_timer(60.0).and(resource_exists::<SaveSlot>());
+/// let menu = !in_state(GameState::Playing);
+/// ```
+pub struct RunCondition {
+    check: Box<dyn FnMut(&World) -> bool + Send>,
+}
+
+impl RunCondition {
+    /// Creates a condition from a closure.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: FnMut(&World) -> bool + Send + 'static,
+    {
+        Self {
+            check: Box::new(check),
+        }
+    }
+
+    /// Combines two conditions, passing when both pass. Both are checked
+    /// every time, so stateful conditions keep ticking.
+    pub fn and(mut self, mut other: RunCondition) -> Self {
+        Self::new(move |world| {
+            let first = self.check(world);
+            other.check(world) && first
+        })
+    }
+
+    /// Combines two conditions, passing when either passes. Both are checked
+    /// every time, so stateful conditions keep ticking.
+    pub fn or(mut self, mut other: RunCondition) -> Self {
+        Self::new(move |world| {
+            let first = self.check(world);
+            other.check(world) || first
+        })
+    }
+
+    /// Checks the condition against the world.
+    pub fn check(&mut self, world: &World) -> bool {
+        (self.check)(world)
+    }
+}
+
+impl Not for RunCondition {
+    type Output = RunCondition;
+
+    fn not(mut self) -> RunCondition {
+        RunCondition::new(move |world| !self.check(world))
+    }
+}
+
+/// Passes while the [`CurrentState`] resource is in `state`. Fails if the
+/// resource wasn't inserted.
+pub fn in_state<S>(state: S) -> RunCondition
+where
+    S: Copy + PartialEq + Debug + Send + Sync + 'static,
+{
+    RunCondition::new(move |world| {
+        world
+            .try_resource::<CurrentState<S>>()
+            .is_some_and(|current| current.is(state))
+    })
+}
+
+/// Passes once every `seconds` of game time, so it stops while the game is
+/// paused and follows the time scale. Intervals missed during a long frame
+/// are dropped rather than made up.
+pub fn on_timer(seconds: f32) -> RunCondition {
+    let interval = seconds.max(f32::EPSILON) as f64;
+    let mut remaining = interval;
+    RunCondition::new(move |world| {
+        remaining -= world.time().delta() as f64;
+        if remaining > 0.0 {
+            return false;
+        }
+        remaining = interval + remaining % interval;
+        true
+    })
+}
+
+/// Passes while a resource of this type is inserted.
+pub fn resource_exists<R: Resource>() -> RunCondition {
+    RunCondition::new(|world| world.contains_resource::<R>())
+}
@@ -0,0 +1,183 @@
+use super::schedule::Schedule;
+use super::world::World;
+use crate::custom_errors::Errors;
+
+/// Builds the same simulation twice in deterministic mode, runs both for
+/// `frames` frames and compares `checksum` of the two worlds after every
+/// frame. Returns the final checksum, or an error naming the first frame the
+/// runs diverged on. Use it in a game's tests to catch systems that read the
+/// wall clock, iterate unordered collections or draw unseeded randomness.
+///
+/// ## Example
+/// ```ignore
+/// #[test]
+/// fn simulation_is_deterministic() {
+///     let build = |world: &mut World, schedule: &mut Schedule| {
+///         spawn_level(world);
+///         add_gameplay_systems(schedule);
+///     };
+///     let checksum = |world: &World| {
+///         world.checksum::<Transform>(|transform, hasher| {
+///             hasher.write_f32(transform.translation.x);
+///             hasher.write_f32(transform.translation.z);
+///         })
+///     };
+///     verify_determinism(42, 1.0 / 60.0, 600, build, checksum).unwrap();
+/// }
+/// ```
+pub fn verify_determinism<B, C>(
+    seed: u64,
+    step: f32,
+    frames: u32,
+    build: B,
+    checksum: C,
+) -> Result<u64, Errors>
+where
+    B: Fn(&mut World, &mut Schedule),
+    C: Fn(&World) -> u64,
+{
+    let mut runs: Vec<(World, Schedule)> = (0..2)
+        .map(|_| {
+            let mut world = World::deterministic(seed, step);
+            let mut schedule = Schedule::new();
+            build(&mut world, &mut schedule);
+            (world, schedule)
+        })
+        .collect();
+
+    let mut last = 0;
+    for frame in 0..=frames {
+        // Frame 0 compares the freshly built worlds.
+        let checksums: Vec<u64> = runs
+            .iter_mut()
+            .map(|(world, schedule)| {
+                if frame > 0 {
+                    world.time_mut().update();
+                    schedule.run(world);
+                }
+                checksum(world)
+            })
+            .collect();
+        if checksums[0] != checksums[1] {
+            return Err(Errors::Nondeterministic(format!(
+                "runs diverged on frame {} ({:016x} vs {:016x})",
+                frame, checksums[0], checksums[1]
+            )));
+        }
+        last = checksums[0];
+    }
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::ecs::Access;
+    use crate::jobs::JobSystem;
+
+    struct Position(f32);
+    struct Velocity(f32);
+    struct Heading(f32);
+
+    /// Builds a level whose systems draw randomness from named streams.
+    /// "movement" and "turning" share a stage, as do "jitter" and "spawn",
+    /// so each pair runs in parallel outside of deterministic mode.
+    fn build(job_order_seed: u64) -> impl Fn(&mut World, &mut Schedule) {
+        move |world, schedule| {
+            *schedule = Schedule::with_job_system(Arc::new(JobSystem::with_threads(4)))
+                .with_job_order_seed(job_order_seed);
+            for i in 0..100 {
+                let speed = world.random().with_stream("speed", |rng| rng.f32());
+                let entity = world.spawn();
+                world.insert(entity, Position(i as f32));
+                world.insert(entity, Velocity(speed));
+                world.insert(entity, Heading(0.0));
+            }
+
+            schedule.add_system_fn(
+                "movement",
+                Access::new().read::<Velocity>().write::<Position>(),
+                |world: &World| {
+                    let delta = world.time().delta();
+                    let velocities = world.read::<Velocity>();
+                    for (entity, mut position) in world.query_mut::<Position>().iter_mut() {
+                        position.0 += velocities.get(entity).map_or(0.0, |v| v.0) * delta;
+                    }
+                },
+            );
+            schedule.add_system_fn(
+                "turning",
+                Access::new().write::<Heading>(),
+                |world: &World| {
+                    for (_, mut heading) in world.query_mut::<Heading>().iter_mut() {
+                        heading.0 += world.random().with_stream("turning", |rng| rng.angle());
+                    }
+                },
+            );
+            schedule.add_system_fn(
+                "jitter",
+                Access::new().write::<Velocity>(),
+                |world: &World| {
+                    for (_, mut velocity) in world.query_mut::<Velocity>().iter_mut() {
+                        velocity.0 += world.random().with_stream("jitter", |rng| rng.f32()) - 0.5;
+                    }
+                },
+            );
+            schedule.add_system_fn(
+                "spawn",
+                Access::new().read::<Position>(),
+                |world: &World| {
+                    let position = world.random().with_stream("spawn", |rng| rng.f32());
+                    if position < 0.2 {
+                        world
+                            .commands()
+                            .spawn()
+                            .insert(Position(position))
+                            .insert(Velocity(1.0));
+                    }
+                },
+            );
+        }
+    }
+
+    fn checksum(world: &World) -> u64 {
+        let positions = world.checksum::<Position>(|position, hasher| hasher.write_f32(position.0));
+        let velocities =
+            world.checksum::<Velocity>(|velocity, hasher| hasher.write_f32(velocity.0));
+        let headings = world.checksum::<Heading>(|heading, hasher| hasher.write_f32(heading.0));
+        positions ^ velocities.rotate_left(21) ^ headings.rotate_left(42)
+    }
+
+    #[test]
+    fn deterministic_runs_match() {
+        verify_determinism(7, 1.0 / 60.0, 120, build(1), checksum).unwrap();
+    }
+
+    #[test]
+    fn parallel_runs_match_whatever_the_job_order() {
+        // Seeded with a fixed step like deterministic mode, but with systems
+        // spread over the job system.
+        let run = |job_order_seed: u64| {
+            let mut world = World::new();
+            world.random_mut().reseed(7);
+            world.time_mut().set_fixed_step(Some(1.0 / 60.0));
+            let mut schedule = Schedule::new();
+            build(job_order_seed)(&mut world, &mut schedule);
+            assert_eq!(
+                schedule.stage_names(),
+                vec![vec!["movement", "turning"], vec!["jitter", "spawn"]]
+            );
+            (0..120)
+                .map(|_| {
+                    world.time_mut().update();
+                    schedule.run(&mut world);
+                    checksum(&world)
+                })
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(run(1), run(2));
+        assert_eq!(run(1), run(3));
+    }
+}
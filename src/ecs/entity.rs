@@ -0,0 +1,49 @@
+use std::fmt;
+
+/// # Entity
+///
+/// A lightweight handle that identifies a set of components stored in a `World`.
+///
+/// Handles are generational: despawned entities' indices are reused, but
+/// with a new generation, so a handle kept after its entity was despawned
+/// never refers to the entity that took its place. Check handles that may
+/// have outlived their entity with `World::is_alive`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Creates an entity handle from its index and generation.
+    pub(crate) fn from_raw(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// Returns the index of the entity, shared with despawned entities
+    /// before it.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns how many entities used this index before this one.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Packs the handle into 64 bits, for saving or sending over the network.
+    pub fn to_bits(&self) -> u64 {
+        (self.generation as u64) << 32 | self.index as u64
+    }
+
+    /// Unpacks a handle packed with [`Entity::to_bits`].
+    pub fn from_bits(bits: u64) -> Self {
+        Self::from_raw(bits as u32, (bits >> 32) as u32)
+    }
+}
+
+impl fmt::Debug for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Entity({}v{})", self.index, self.generation)
+    }
+}
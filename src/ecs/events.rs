@@ -0,0 +1,118 @@
+use std::any::Any;
+use std::slice;
+use std::sync::Mutex;
+
+/// # Event
+///
+/// Marker trait for messages sent between systems. Implemented for every
+/// `Send + Sync + 'static` type.
+pub trait Event: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Event for T {}
+
+/// # Events
+///
+/// A double-buffered queue of one event type. Events sent during a schedule run
+/// become readable once the run finishes and stay readable for the whole next
+/// run, so every system sees each event exactly once no matter where it runs
+/// in the schedule. Sending only needs shared access, so any system can send.
+///
+/// ## Example
+/// ```ignore
+/// world.send_event(PlayerDied { player });
+///
+/// // In a system during the next run:
+/// for event in world.events::<PlayerDied>().iter() {
+///     show_game_over(event.player);
+/// }
+/// ```
+pub struct Events<T> {
+    readable: Vec<T>,
+    pending: Mutex<Vec<T>>,
+}
+
+impl<T: Event> Events<T> {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self {
+            readable: Vec::new(),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queues an event for the next run.
+    pub fn send(&self, event: T) {
+        self.pending
+            .lock()
+            .expect("Event queue lock poisoned")
+            .push(event);
+    }
+
+    /// Queues several events for the next run.
+    pub fn send_batch(&self, events: impl IntoIterator<Item = T>) {
+        self.pending
+            .lock()
+            .expect("Event queue lock poisoned")
+            .extend(events);
+    }
+
+    /// Iterates over the events sent during the previous run.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.readable.iter()
+    }
+
+    /// Returns the number of readable events.
+    pub fn len(&self) -> usize {
+        self.readable.len()
+    }
+
+    /// Checks if there are no readable events.
+    pub fn is_empty(&self) -> bool {
+        self.readable.is_empty()
+    }
+
+    /// Makes pending events readable, dropping the previously readable ones.
+    /// The two buffers swap, so their allocations are reused every run.
+    pub fn update(&mut self) {
+        let pending = self.pending.get_mut().expect("Event queue lock poisoned");
+        std::mem::swap(&mut self.readable, pending);
+        pending.clear();
+    }
+
+    /// Drops every readable and pending event.
+    pub fn clear(&mut self) {
+        self.readable.clear();
+        self.pending
+            .get_mut()
+            .expect("Event queue lock poisoned")
+            .clear();
+    }
+}
+
+impl<T: Event> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Type-erased access to an event queue, used by the world to swap every
+/// queue without knowing its event type.
+pub(crate) trait AnyEvents: Send + Sync {
+    fn update(&mut self);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Event> AnyEvents for Events<T> {
+    fn update(&mut self) {
+        Events::update(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Creates an empty queue for an event type.
+pub(crate) fn new_events<T: Event>() -> Box<dyn AnyEvents> {
+    Box::new(Events::<T>::new())
+}
@@ -0,0 +1,29 @@
+pub mod change_detection;
+pub mod commands;
+pub mod condition;
+pub mod determinism;
+pub mod entity;
+pub mod events;
+pub mod query;
+pub mod relation;
+pub mod resource;
+pub mod schedule;
+pub mod state;
+pub mod storage;
+pub mod system;
+pub mod world;
+
+pub use change_detection::{system_ticks, ComponentTicks, Mut, SystemTicks, Tick};
+pub use commands::{Commands, EntityCommands};
+pub use condition::{in_state, on_timer, resource_exists, RunCondition};
+pub use determinism::verify_determinism;
+pub use entity::Entity;
+pub use events::{Event, Events};
+pub use query::{Added, Changed, Query, QueryFilter, QueryMut, With, Without};
+pub use relation::{OnTargetDespawn, Relation};
+pub use resource::{NonSend, NonSendMut, Res, ResMut, Resource};
+pub use schedule::{system_jobs, Schedule, SystemSet};
+pub use state::CurrentState;
+pub use storage::{Component, ComponentStorage};
+pub use system::{Access, ExclusiveSystem, FunctionSystem, System};
+pub use world::{Read, World, Write};
@@ -0,0 +1,503 @@
+use std::any::{type_name, TypeId};
+use std::marker::PhantomData;
+
+use super::change_detection::{last_run, ComponentTicks, Mut, Tick};
+use super::entity::Entity;
+use super::schedule::system_jobs;
+use super::storage::Component;
+use super::world::{Read, World, Write};
+
+/// # Query Filter
+///
+/// Narrows a query down to the entities that pass a test, such as having
+/// another component or having changed since the system last ran. Tuples of
+/// filters pass entities that pass all of them.
+///
+/// Filters lock the storages they look at for reading, so a system using
+/// one must declare read access to those component types.
+pub trait QueryFilter {
+    /// The storages the filter reads while the query lives.
+    type State<'w>;
+
+    /// Locks what the filter needs for a query of the `queried` component type.
+    fn init(world: &World, queried: TypeId) -> Self::State<'_>;
+
+    /// Checks if an entity passes, given the ticks of its queried component.
+    fn matches(
+        state: &Self::State<'_>,
+        entity: Entity,
+        ticks: ComponentTicks,
+        last_run: Tick,
+    ) -> bool;
+}
+
+/// A storage a filter looks at, which may be the one being queried.
+pub enum FilterStorage<'w, T> {
+    /// The filter's component is the queried one, whose ticks are at hand.
+    Queried,
+    Stored(Read<'w, T>),
+    /// The component type isn't registered, so no entity has it.
+    Missing,
+}
+
+impl<'w, T: Component> FilterStorage<'w, T> {
+    fn new(world: &'w World, queried: TypeId) -> Self {
+        if TypeId::of::<T>() == queried {
+            return FilterStorage::Queried;
+        }
+        world
+            .try_read::<T>()
+            .map_or(FilterStorage::Missing, FilterStorage::Stored)
+    }
+}
+
+/// Passes entities that also have a `T`.
+pub struct With<T>(PhantomData<T>);
+
+/// Passes entities that don't have a `T`.
+pub struct Without<T>(PhantomData<T>);
+
+/// Passes entities whose `T` was added since the system last ran.
+pub struct Added<T>(PhantomData<T>);
+
+/// Passes entities whose `T` was added or written to since the system last
+/// ran.
+pub struct Changed<T>(PhantomData<T>);
+
+impl QueryFilter for () {
+    type State<'w> = ();
+
+    fn init(_: &World, _: TypeId) -> Self::State<'_> {}
+
+    fn matches(_: &(), _: Entity, _: ComponentTicks, _: Tick) -> bool {
+        true
+    }
+}
+
+impl<T: Component> QueryFilter for With<T> {
+    type State<'w> = FilterStorage<'w, T>;
+
+    fn init(world: &World, queried: TypeId) -> Self::State<'_> {
+        FilterStorage::new(world, queried)
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity, _: ComponentTicks, _: Tick) -> bool {
+        match state {
+            FilterStorage::Queried => true,
+            FilterStorage::Stored(storage) => storage.contains(entity),
+            FilterStorage::Missing => false,
+        }
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    type State<'w> = FilterStorage<'w, T>;
+
+    fn init(world: &World, queried: TypeId) -> Self::State<'_> {
+        FilterStorage::new(world, queried)
+    }
+
+    fn matches(
+        state: &Self::State<'_>,
+        entity: Entity,
+        ticks: ComponentTicks,
+        last_run: Tick,
+    ) -> bool {
+        !With::<T>::matches(state, entity, ticks, last_run)
+    }
+}
+
+impl<T: Component> QueryFilter for Added<T> {
+    type State<'w> = FilterStorage<'w, T>;
+
+    fn init(world: &World, queried: TypeId) -> Self::State<'_> {
+        FilterStorage::new(world, queried)
+    }
+
+    fn matches(
+        state: &Self::State<'_>,
+        entity: Entity,
+        ticks: ComponentTicks,
+        last_run: Tick,
+    ) -> bool {
+        match state {
+            FilterStorage::Queried => ticks.is_added(last_run),
+            FilterStorage::Stored(storage) => storage.is_added(entity, last_run),
+            FilterStorage::Missing => false,
+        }
+    }
+}
+
+impl<T: Component> QueryFilter for Changed<T> {
+    type State<'w> = FilterStorage<'w, T>;
+
+    fn init(world: &World, queried: TypeId) -> Self::State<'_> {
+        FilterStorage::new(world, queried)
+    }
+
+    fn matches(
+        state: &Self::State<'_>,
+        entity: Entity,
+        ticks: ComponentTicks,
+        last_run: Tick,
+    ) -> bool {
+        match state {
+            FilterStorage::Queried => ticks.is_changed(last_run),
+            FilterStorage::Stored(storage) => storage.is_changed(entity, last_run),
+            FilterStorage::Missing => false,
+        }
+    }
+}
+
+macro_rules! impl_query_filter_tuple {
+    ($($filter:ident),+) => {
+        impl<$($filter: QueryFilter),+> QueryFilter for ($($filter,)+) {
+            type State<'w> = ($($filter::State<'w>,)+);
+
+            fn init(world: &World, queried: TypeId) -> Self::State<'_> {
+                ($($filter::init(world, queried),)+)
+            }
+
+            #[allow(non_snake_case)]
+            fn matches(
+                state: &Self::State<'_>,
+                entity: Entity,
+                ticks: ComponentTicks,
+                last_run: Tick,
+            ) -> bool {
+                let ($($filter,)+) = state;
+                $($filter::matches($filter, entity, ticks, last_run))&&+
+            }
+        }
+    };
+}
+
+impl_query_filter_tuple!(A);
+impl_query_filter_tuple!(A, B);
+impl_query_filter_tuple!(A, B, C);
+impl_query_filter_tuple!(A, B, C, D);
+
+/// # Query
+///
+/// Shared access to the entities with a component of type `T` that pass the
+/// filter `F`. Change filters compare against the tick the running system
+/// last ran at, so each system sees every change exactly once; outside of
+/// systems every component counts as added and changed unless
+/// [`Query::with_last_run`] says otherwise.
+///
+/// ## Example
+/// ```ignore
+/// // only entities whose transform changed since this system last ran
+/// for (entity, transform) in world.query_filtered::<Transform, Changed<Transform>>().iter() {
+///     spatial_index.update(entity, transform.translation);
+/// }
+///
+/// // entities that just got a light
+/// for (entity, light) in world.query_filtered::<PointLight, Added<PointLight>>().iter() {
+///     shadow_maps.allocate(entity, light);
+/// }
+///
+/// // the one entity with a camera
+/// let (camera, view) = world.query_filtered::<Camera, With<MainCamera>>().single();
+/// ```
+pub struct Query<'w, T: Component, F: QueryFilter = ()> {
+    storage: Read<'w, T>,
+    filter: F::State<'w>,
+    last_run: Tick,
+    deterministic: bool,
+}
+
+impl<'w, T: Component, F: QueryFilter> Query<'w, T, F> {
+    /// Locks the storages for a query.
+    pub(crate) fn new(world: &'w World) -> Self {
+        Self {
+            storage: world.read::<T>(),
+            filter: F::init(world, TypeId::of::<T>()),
+            last_run: last_run(),
+            deterministic: world.is_deterministic(),
+        }
+    }
+
+    /// Sets the tick change filters compare against.
+    pub fn with_last_run(mut self, last_run: Tick) -> Self {
+        self.last_run = last_run;
+        self
+    }
+
+    /// Returns the tick change filters compare against.
+    pub fn last_run(&self) -> Tick {
+        self.last_run
+    }
+
+    /// Iterates over the matching entities and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> + use<'_, 'w, T, F> {
+        self.storage
+            .iter_ticks()
+            .filter(|(entity, _, ticks)| F::matches(&self.filter, *entity, *ticks, self.last_run))
+            .map(|(entity, component, _)| (entity, component))
+    }
+
+    /// Returns the entity's component if the entity matches.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let ticks = self.storage.ticks(entity)?;
+        F::matches(&self.filter, entity, ticks, self.last_run)
+            .then(|| self.storage.get(entity))
+            .flatten()
+    }
+
+    /// Returns several entities' components if every entity matches.
+    pub fn get_many<const N: usize>(&self, entities: [Entity; N]) -> Option<[&T; N]> {
+        let components = entities.map(|entity| self.get(entity));
+        components
+            .iter()
+            .all(Option::is_some)
+            .then(|| components.map(|component| component.expect("Checked above")))
+    }
+
+    /// Returns the only matching entity and its component, or `None` if
+    /// none or several match.
+    pub fn get_single(&self) -> Option<(Entity, &T)> {
+        let mut matches = self.iter();
+        let first = matches.next()?;
+        matches.next().is_none().then_some(first)
+    }
+
+    /// Returns the only matching entity and its component, for singletons
+    /// such as the player or the main camera. Panics unless exactly one
+    /// entity matches.
+    pub fn single(&self) -> (Entity, &T) {
+        self.get_single().unwrap_or_else(|| {
+            panic!(
+                "Expected exactly one entity with {} to match the query",
+                type_name::<T>()
+            )
+        })
+    }
+
+    /// Calls `f` on every matching entity and its component in parallel on
+    /// the running schedule's job system, in no particular order.
+    /// Deterministic worlds call it in iteration order on this thread.
+    pub fn par_for_each(&self, f: impl Fn(Entity, &T) + Sync) {
+        if self.deterministic {
+            self.iter()
+                .for_each(|(entity, component)| f(entity, component));
+            return;
+        }
+        let matches: Vec<(Entity, &T)> = self.iter().collect();
+        system_jobs().par_for_each(&matches, |(entity, component)| f(*entity, component));
+    }
+
+    /// Checks if the entity matches.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    /// Returns the number of matching entities.
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Checks if no entity matches.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// # Query Mut
+///
+/// Exclusive access to the entities with a component of type `T` that pass
+/// the filter `F`. Components are handed out as [`Mut`], which marks them
+/// changed only when written to, so a system can look at every component
+/// without tripping `Changed` filters downstream.
+///
+/// ## Example
+/// ```ignore
+/// let mut query = world.query_mut_filtered::<Health, Changed<Armor>>();
+/// for (_, mut health) in query.iter_mut() {
+///     if health.current > health.max {
+///         health.current = health.max;
+///     }
+/// }
+///
+/// // two entities at once, such as both sides of a trade
+/// if let Some([mut buyer, mut seller]) = query.get_many_mut([buyer, seller]) {
+///     buyer.current -= cost;
+///     seller.current += cost;
+/// }
+/// ```
+pub struct QueryMut<'w, T: Component, F: QueryFilter = ()> {
+    storage: Write<'w, T>,
+    filter: F::State<'w>,
+    last_run: Tick,
+    deterministic: bool,
+}
+
+impl<'w, T: Component, F: QueryFilter> QueryMut<'w, T, F> {
+    /// Locks the storages for a query.
+    pub(crate) fn new(world: &'w World) -> Self {
+        let storage = world.write::<T>();
+        Self {
+            storage,
+            filter: F::init(world, TypeId::of::<T>()),
+            last_run: last_run(),
+            deterministic: world.is_deterministic(),
+        }
+    }
+
+    /// Sets the tick change filters compare against.
+    pub fn with_last_run(mut self, last_run: Tick) -> Self {
+        self.last_run = last_run;
+        self
+    }
+
+    /// Returns the tick change filters compare against.
+    pub fn last_run(&self) -> Tick {
+        self.last_run
+    }
+
+    /// Iterates over the matching entities and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> + use<'_, 'w, T, F> {
+        self.storage
+            .iter_ticks()
+            .filter(|(entity, _, ticks)| F::matches(&self.filter, *entity, *ticks, self.last_run))
+            .map(|(entity, component, _)| (entity, component))
+    }
+
+    /// Iterates mutably over the matching entities and their components.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, Mut<'_, T>)> + use<'_, 'w, T, F> {
+        let Self {
+            storage,
+            filter,
+            last_run,
+            ..
+        } = self;
+        let last_run = *last_run;
+        storage.iter_tracked().filter(move |(entity, component)| {
+            F::matches(filter, *entity, component.ticks(), last_run)
+        })
+    }
+
+    /// Returns the entity's component if the entity matches.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let ticks = self.storage.ticks(entity)?;
+        F::matches(&self.filter, entity, ticks, self.last_run)
+            .then(|| self.storage.get(entity))
+            .flatten()
+    }
+
+    /// Returns the entity's component mutably if the entity matches.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<Mut<'_, T>> {
+        let ticks = self.storage.ticks(entity)?;
+        if !F::matches(&self.filter, entity, ticks, self.last_run) {
+            return None;
+        }
+        self.storage.get_tracked(entity)
+    }
+
+    /// Returns several entities' components mutably at once if every entity
+    /// matches and none appears twice.
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> Option<[Mut<'_, T>; N]> {
+        for entity in entities {
+            let ticks = self.storage.ticks(entity)?;
+            if !F::matches(&self.filter, entity, ticks, self.last_run) {
+                return None;
+            }
+        }
+        self.storage.get_many_tracked(entities)
+    }
+
+    /// Returns the only matching entity and its component mutably, or `None`
+    /// if none or several match.
+    pub fn get_single_mut(&mut self) -> Option<(Entity, Mut<'_, T>)> {
+        let mut matches = self.iter_mut();
+        let first = matches.next()?;
+        matches.next().is_none().then_some(first)
+    }
+
+    /// Returns the only matching entity and its component mutably. Panics
+    /// unless exactly one entity matches.
+    pub fn single_mut(&mut self) -> (Entity, Mut<'_, T>) {
+        self.get_single_mut().unwrap_or_else(|| {
+            panic!(
+                "Expected exactly one entity with {} to match the query",
+                type_name::<T>()
+            )
+        })
+    }
+
+    /// Calls `f` on every matching entity and its component in parallel on
+    /// the running schedule's job system, in no particular order. Components
+    /// are marked changed only if written. Deterministic worlds call it in
+    /// iteration order on this thread.
+    pub fn par_for_each_mut(&mut self, f: impl Fn(Entity, Mut<'_, T>) + Sync) {
+        if self.deterministic {
+            self.iter_mut()
+                .for_each(|(entity, component)| f(entity, component));
+            return;
+        }
+        let mut matches: Vec<(Entity, Mut<'_, T>)> = self.iter_mut().collect();
+        system_jobs().par_for_each_mut(&mut matches, |(entity, component)| {
+            f(*entity, component.reborrow())
+        });
+    }
+
+    /// Returns several entities' components if every entity matches.
+    pub fn get_many<const N: usize>(&self, entities: [Entity; N]) -> Option<[&T; N]> {
+        let components = entities.map(|entity| self.get(entity));
+        components
+            .iter()
+            .all(Option::is_some)
+            .then(|| components.map(|component| component.expect("Checked above")))
+    }
+
+    /// Returns the only matching entity and its component, or `None` if
+    /// none or several match.
+    pub fn get_single(&self) -> Option<(Entity, &T)> {
+        let mut matches = self.iter();
+        let first = matches.next()?;
+        matches.next().is_none().then_some(first)
+    }
+
+    /// Returns the only matching entity and its component, for singletons
+    /// such as the player or the main camera. Panics unless exactly one
+    /// entity matches.
+    pub fn single(&self) -> (Entity, &T) {
+        self.get_single().unwrap_or_else(|| {
+            panic!(
+                "Expected exactly one entity with {} to match the query",
+                type_name::<T>()
+            )
+        })
+    }
+
+    /// Calls `f` on every matching entity and its component in parallel on
+    /// the running schedule's job system, in no particular order.
+    /// Deterministic worlds call it in iteration order on this thread.
+    pub fn par_for_each(&self, f: impl Fn(Entity, &T) + Sync) {
+        if self.deterministic {
+            self.iter()
+                .for_each(|(entity, component)| f(entity, component));
+            return;
+        }
+        let matches: Vec<(Entity, &T)> = self.iter().collect();
+        system_jobs().par_for_each(&matches, |(entity, component)| f(*entity, component));
+    }
+
+    /// Checks if the entity matches.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    /// Returns the number of matching entities.
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Checks if no entity matches.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
@@ -0,0 +1,175 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use super::entity::Entity;
+use super::storage::{Component, ComponentStorage};
+use super::world::World;
+use crate::math::StableState;
+
+/// What happens to an entity pointing at another through a relation when the
+/// other is despawned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OnTargetDespawn {
+    /// The relation component is removed from the source.
+    Remove,
+    /// The source is despawned as well, along with anything owned by it.
+    Despawn,
+}
+
+/// # Relation
+///
+/// A component pointing from the entity it's on, the source, at another
+/// entity, the target. Once registered with [`World::register_relation`],
+/// the world keeps a reverse index so [`World::related`] finds every source
+/// pointing at a target, and despawning a target removes the relation from
+/// its sources, or despawns them, instead of leaving them with a dangling
+/// id.
+///
+/// The index follows [`World::insert`], [`World::remove`],
+/// [`World::despawn`] and [`Commands`](super::Commands). To point a relation
+/// at another target, insert a new one rather than changing it through a
+/// write lock.
+///
+/// [`World::register_relation`]: super::World::register_relation
+/// [`World::related`]: super::World::related
+/// [`World::insert`]: super::World::insert
+/// [`World::remove`]: super::World::remove
+/// [`World::despawn`]: super::World::despawn
+///
+/// ## Example
+/// ```ignore
+/// struct Targets(Entity);
+///
+/// impl Relation for Targets {
+///     fn target(&self) -> Entity {
+///         self.0
+///     }
+/// }
+///
+/// struct OwnedBy(Entity);
+///
+/// impl Relation for OwnedBy {
+///     const ON_TARGET_DESPAWN: OnTargetDespawn = OnTargetDespawn::Despawn;
+///
+///     fn target(&self) -> Entity {
+///         self.0
+///     }
+/// }
+///
+/// world.register_relation::<Targets>();
+/// world.register_relation::<OwnedBy>();
+/// world.insert(turret, Targets(player));
+/// world.insert(sword, OwnedBy(player));
+///
+/// let threats = world.related::<Targets>(player); // [turret]
+/// world.despawn(player); // the turret stops targeting, the sword is despawned
+/// ```
+pub trait Relation: Component {
+    /// What happens to sources when their target is despawned.
+    const ON_TARGET_DESPAWN: OnTargetDespawn = OnTargetDespawn::Remove;
+
+    /// Returns the entity this relation points at.
+    fn target(&self) -> Entity;
+
+    /// Called after the relation was removed from `source` because its
+    /// target was despawned, with [`OnTargetDespawn::Remove`].
+    fn on_detach(world: &mut World, source: Entity) {
+        let _ = (world, source);
+    }
+}
+
+/// Both directions of one relation type.
+pub(crate) struct RelationIndex<R> {
+    targets: HashMap<Entity, Entity, StableState>,
+    sources: HashMap<Entity, Vec<Entity>, StableState>,
+    marker: PhantomData<fn() -> R>,
+}
+
+impl<R: Relation> RelationIndex<R> {
+    fn link(&mut self, source: Entity, target: Entity) {
+        self.unlink(source);
+        self.targets.insert(source, target);
+        self.sources.entry(target).or_default().push(source);
+    }
+}
+
+/// Type-erased access to a relation index, used by the world to keep it up
+/// to date without knowing the relation type.
+pub(crate) trait AnyRelationIndex: Send + Sync {
+    /// Records a relation component inserted on `source`.
+    fn insert(&mut self, source: Entity, component: &dyn Any);
+    /// Forgets the relation from `source`, if any.
+    fn unlink(&mut self, source: Entity);
+    /// Indexes every relation in a storage, replacing the index.
+    fn rebuild(&mut self, storage: &dyn Any);
+    /// Returns the sources pointing at `target`.
+    fn sources(&self, target: Entity) -> &[Entity];
+    /// Forgets every source pointing at `target`, returning them.
+    fn take_sources(&mut self, target: Entity) -> Vec<Entity>;
+    /// Returns what happens to sources when their target is despawned.
+    fn on_target_despawn(&self) -> OnTargetDespawn;
+    /// Returns the hook run on sources detached from a despawned target.
+    fn on_detach(&self) -> fn(&mut World, Entity);
+}
+
+impl<R: Relation> AnyRelationIndex for RelationIndex<R> {
+    fn insert(&mut self, source: Entity, component: &dyn Any) {
+        let relation = component
+            .downcast_ref::<R>()
+            .expect("Relation index type mismatch");
+        self.link(source, relation.target());
+    }
+
+    fn unlink(&mut self, source: Entity) {
+        let Some(target) = self.targets.remove(&source) else {
+            return;
+        };
+        if let Some(sources) = self.sources.get_mut(&target) {
+            sources.retain(|&other| other != source);
+            if sources.is_empty() {
+                self.sources.remove(&target);
+            }
+        }
+    }
+
+    fn rebuild(&mut self, storage: &dyn Any) {
+        self.targets.clear();
+        self.sources.clear();
+        let storage = storage
+            .downcast_ref::<ComponentStorage<R>>()
+            .expect("Relation storage type mismatch");
+        for (source, relation) in storage.iter() {
+            self.link(*source, relation.target());
+        }
+    }
+
+    fn sources(&self, target: Entity) -> &[Entity] {
+        self.sources.get(&target).map_or(&[], Vec::as_slice)
+    }
+
+    fn take_sources(&mut self, target: Entity) -> Vec<Entity> {
+        let sources = self.sources.remove(&target).unwrap_or_default();
+        for source in &sources {
+            self.targets.remove(source);
+        }
+        sources
+    }
+
+    fn on_target_despawn(&self) -> OnTargetDespawn {
+        R::ON_TARGET_DESPAWN
+    }
+
+    fn on_detach(&self) -> fn(&mut World, Entity) {
+        R::on_detach
+    }
+}
+
+/// Creates an empty, type-erased index for the relation type.
+pub(crate) fn new_relation_index<R: Relation>() -> Box<dyn AnyRelationIndex> {
+    Box::new(RelationIndex::<R> {
+        targets: HashMap::default(),
+        sources: HashMap::default(),
+        marker: PhantomData,
+    })
+}
@@ -0,0 +1,224 @@
+use std::any::{type_name, Any};
+use std::cell::{Ref, RefCell, RefMut};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
+use std::thread::{self, ThreadId};
+
+use super::change_detection::{ComponentTicks, Tick};
+
+/// # Resource
+///
+/// Marker trait for world-wide singletons, such as input state, settings or
+/// an asset cache, that systems share instead of attaching to an entity.
+/// Implemented for every `Send + Sync + 'static` type.
+pub trait Resource: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Resource for T {}
+
+/// A resource and the ticks it was inserted and last changed at.
+pub(crate) struct ResourceData {
+    pub(crate) value: Box<dyn Any + Send + Sync>,
+    pub(crate) ticks: ComponentTicks,
+}
+
+/// # Res
+///
+/// Shared access to a resource, held for as long as the guard lives.
+pub struct Res<'a, T> {
+    pub(crate) guard: RwLockReadGuard<'a, ResourceData>,
+    pub(crate) last_run: Tick,
+    pub(crate) marker: PhantomData<T>,
+}
+
+impl<T: Resource> Res<'_, T> {
+    /// Checks if the resource was inserted since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.guard.ticks.is_added(self.last_run)
+    }
+
+    /// Checks if the resource was inserted or written to since the system
+    /// last ran.
+    pub fn is_changed(&self) -> bool {
+        self.guard.ticks.is_changed(self.last_run)
+    }
+}
+
+impl<T: Resource> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard
+            .value
+            .downcast_ref()
+            .expect("Resource type mismatch")
+    }
+}
+
+/// # ResMut
+///
+/// Exclusive access to a resource, held for as long as the guard lives.
+/// Writing through it marks the resource changed.
+pub struct ResMut<'a, T> {
+    pub(crate) guard: RwLockWriteGuard<'a, ResourceData>,
+    pub(crate) last_run: Tick,
+    pub(crate) this_run: Tick,
+    pub(crate) marker: PhantomData<T>,
+}
+
+impl<T: Resource> ResMut<'_, T> {
+    /// Checks if the resource was inserted since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.guard.ticks.is_added(self.last_run)
+    }
+
+    /// Checks if the resource was inserted or written to since the system
+    /// last ran.
+    pub fn is_changed(&self) -> bool {
+        self.guard.ticks.is_changed(self.last_run)
+    }
+
+    /// Returns the resource mutably without marking it changed.
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        self.guard
+            .value
+            .downcast_mut()
+            .expect("Resource type mismatch")
+    }
+}
+
+impl<T: Resource> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard
+            .value
+            .downcast_ref()
+            .expect("Resource type mismatch")
+    }
+}
+
+impl<T: Resource> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.ticks.changed = self.this_run;
+        self.bypass_change_detection()
+    }
+}
+
+/// A resource that may only be touched from the thread it was inserted on.
+pub(crate) struct NonSendData {
+    value: ManuallyDrop<RefCell<Box<dyn Any>>>,
+    thread: ThreadId,
+}
+
+// Safety: the value is only reached through `borrow` and `borrow_mut`, which
+// check that the caller is on the owning thread, and it is only dropped
+// there; from any other thread the data is an opaque box.
+unsafe impl Send for NonSendData {}
+unsafe impl Sync for NonSendData {}
+
+impl NonSendData {
+    /// Wraps a value owned by the calling thread.
+    pub(crate) fn new<R: 'static>(value: R) -> Self {
+        Self {
+            value: ManuallyDrop::new(RefCell::new(Box::new(value))),
+            thread: thread::current().id(),
+        }
+    }
+
+    /// Panics unless called from the owning thread.
+    pub(crate) fn check_thread<R>(&self) {
+        assert!(
+            thread::current().id() == self.thread,
+            "Non-send resource '{}' accessed off the thread it was inserted on",
+            type_name::<R>()
+        );
+    }
+
+    /// Borrows the value for shared reading.
+    pub(crate) fn borrow<R: 'static>(&self) -> NonSend<'_, R> {
+        self.check_thread::<R>();
+        let value = self.value.try_borrow().unwrap_or_else(|_| {
+            panic!(
+                "Non-send resource '{}' is borrowed mutably",
+                type_name::<R>()
+            )
+        });
+        NonSend(Ref::map(value, |value| {
+            value.downcast_ref().expect("Resource type mismatch")
+        }))
+    }
+
+    /// Borrows the value for exclusive writing.
+    pub(crate) fn borrow_mut<R: 'static>(&self) -> NonSendMut<'_, R> {
+        self.check_thread::<R>();
+        let value = self.value.try_borrow_mut().unwrap_or_else(|_| {
+            panic!(
+                "Non-send resource '{}' is already borrowed",
+                type_name::<R>()
+            )
+        });
+        NonSendMut(RefMut::map(value, |value| {
+            value.downcast_mut().expect("Resource type mismatch")
+        }))
+    }
+
+    /// Takes the value back out.
+    pub(crate) fn into_inner<R: 'static>(mut self) -> R {
+        self.check_thread::<R>();
+        // Safety: `self` is forgotten right after, so the value isn't dropped twice.
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        std::mem::forget(self);
+        *value
+            .into_inner()
+            .downcast()
+            .expect("Resource type mismatch")
+    }
+}
+
+impl Drop for NonSendData {
+    fn drop(&mut self) {
+        if thread::current().id() != self.thread {
+            log::error!("Leaked a non-send resource dropped off the thread it was inserted on");
+            return;
+        }
+        // Safety: the value is never touched again.
+        unsafe { ManuallyDrop::drop(&mut self.value) }
+    }
+}
+
+/// # NonSend
+///
+/// Shared access to a resource that can't leave the main thread, such as
+/// the renderer holding the OpenGL context. Only systems marked
+/// [`non_send`](super::Access::non_send), which the schedule runs on the
+/// calling thread, can reach it; anywhere else it panics.
+pub struct NonSend<'a, T>(Ref<'a, T>);
+
+impl<T> Deref for NonSend<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// # NonSendMut
+///
+/// Exclusive access to a resource that can't leave the main thread.
+pub struct NonSendMut<'a, T>(RefMut<'a, T>);
+
+impl<T> Deref for NonSendMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for NonSendMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,495 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use super::change_detection::{with_system_ticks, SystemTicks, Tick};
+use super::condition::RunCondition;
+use super::system::{Access, ExclusiveSystem, FunctionSystem, System};
+use super::world::World;
+use crate::jobs::JobSystem;
+use crate::math::random::Rng;
+
+/// # System Set
+///
+/// Systems that share run conditions, such as everything that only runs
+/// while playing. The conditions are checked once per schedule run and the
+/// systems are skipped together when any fails; within the schedule they
+/// are staged like any other system.
+///
+/// ## Example
+/// ```ignore
+/// schedule.add_set(
+///     SystemSet::new()
+///         .run_if(in_state(GameState::Playing))
+///         .with_system(movement_system())
+///         .with_system(steering_system()),
+/// );
+/// schedule.add_set(
+///     SystemSet::new()
+///         .run_if(in_state(GameState::Menu))
+///         .with_system_fn("menu", Access::new().res_mut::<Menu>(), update_menu),
+/// );
+/// ```
+pub struct SystemSet {
+    conditions: Vec<RunCondition>,
+    systems: Vec<Box<dyn System>>,
+}
+
+impl SystemSet {
+    /// Creates an empty set that always runs.
+    pub fn new() -> Self {
+        Self {
+            conditions: Vec::new(),
+            systems: Vec::new(),
+        }
+    }
+
+    /// Adds a condition that must pass for the set to run.
+    pub fn run_if(mut self, condition: RunCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Adds a system to the set.
+    pub fn with_system<S: System + 'static>(mut self, system: S) -> Self {
+        self.systems.push(Box::new(system));
+        self
+    }
+
+    /// Adds a closure as a system to the set.
+    pub fn with_system_fn<F>(self, name: &str, access: Access, func: F) -> Self
+    where
+        F: FnMut(&World) + Send + 'static,
+    {
+        self.with_system(FunctionSystem::new(name, access, func))
+    }
+}
+
+impl Default for SystemSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A group of systems that don't conflict with each other and can run at the same time.
+struct Stage {
+    systems: Vec<usize>,
+}
+
+/// # Schedule
+///
+/// Runs systems against a world. Systems are grouped into stages: a system is
+/// placed in the stage after the last earlier system it conflicts with, so
+/// systems with conflicting access still run in insertion order while everything
+/// else runs in parallel on the job system. Systems marked `non_send` always
+/// run on the calling thread, and worlds in deterministic mode run every
+/// system on it, one after another in stage order. Exclusive systems get a
+/// stage of their own and run with `&mut World`.
+///
+/// Call `run` from the thread owning the OpenGL context: it's the only
+/// thread rendering systems and [`NonSend`](super::NonSend) resources are
+/// reachable from.
+///
+/// Stages are sync points: [`Commands`](super::Commands) recorded by a
+/// stage's systems are applied before the next stage runs.
+///
+/// Systems can be gated by [`RunCondition`]s, alone with
+/// [`Schedule::add_system_if`] or grouped in a [`SystemSet`].
+///
+/// The schedule remembers the world tick each system last ran at, so change
+/// filters in a system's queries see what changed since its previous run.
+///
+/// ## Example
+/// ```ignore
+/// let mut schedule = Schedule::new();
+/// schedule.add_system_fn("movement", Access::new().read::<Velocity>().write::<Position>(), movement);
+/// schedule.add_system_fn("render", Access::new().read::<Position>().non_send_res::<Renderer>(), render);
+/// schedule.add_exclusive_system_fn("spawn_level", spawn_level);
+/// schedule.add_system_if(autosave_system(), on_timer(60.0));
+///
+/// while !window.should_close() {
+///     schedule.run(&mut world);
+///     window.update();
+/// }
+/// ```
+pub struct Schedule {
+    systems: Vec<Box<dyn System>>,
+    last_runs: Vec<Tick>,
+    /// Indices into `conditions` gating each system.
+    gates: Vec<Vec<usize>>,
+    conditions: Vec<RunCondition>,
+    stages: Option<Vec<Stage>>,
+    jobs: Arc<JobSystem>,
+    /// Shuffles the order each stage's systems start in, if set.
+    job_order: Option<Rng>,
+}
+
+impl Schedule {
+    /// Creates an empty schedule that runs on the global job system.
+    pub fn new() -> Self {
+        Self::with_job_system(JobSystem::global())
+    }
+
+    /// Creates an empty schedule that runs on the given job system.
+    pub fn with_job_system(jobs: Arc<JobSystem>) -> Self {
+        Self {
+            systems: Vec::new(),
+            last_runs: Vec::new(),
+            gates: Vec::new(),
+            conditions: Vec::new(),
+            stages: None,
+            jobs,
+            job_order: None,
+        }
+    }
+
+    /// Starts each stage's systems in an order shuffled with `seed` instead
+    /// of insertion order. Results that change with the seed depend on the
+    /// order parallel systems happen to run in.
+    pub fn with_job_order_seed(mut self, seed: u64) -> Self {
+        self.job_order = Some(Rng::new(seed));
+        self
+    }
+
+    /// Adds a system to the end of the schedule.
+    pub fn add_system<S: System + 'static>(&mut self, system: S) -> &mut Self {
+        self.push_system(Box::new(system), Vec::new());
+        self
+    }
+
+    /// Adds a closure taking `&mut World` as an exclusive system to the end of
+    /// the schedule, see [`ExclusiveSystem`].
+    pub fn add_exclusive_system_fn<F>(&mut self, name: &str, func: F) -> &mut Self
+    where
+        F: FnMut(&mut World) + Send + 'static,
+    {
+        self.add_system(ExclusiveSystem::new(name, func))
+    }
+
+    /// Adds a system that only runs when `condition` passes.
+    pub fn add_system_if<S: System + 'static>(
+        &mut self,
+        system: S,
+        condition: RunCondition,
+    ) -> &mut Self {
+        self.conditions.push(condition);
+        let gate = vec![self.conditions.len() - 1];
+        self.push_system(Box::new(system), gate);
+        self
+    }
+
+    /// Adds every system of a set, gated by the set's conditions.
+    pub fn add_set(&mut self, set: SystemSet) -> &mut Self {
+        let first = self.conditions.len();
+        self.conditions.extend(set.conditions);
+        let gate: Vec<usize> = (first..self.conditions.len()).collect();
+        for system in set.systems {
+            self.push_system(system, gate.clone());
+        }
+        self
+    }
+
+    /// Adds a closure as a system to the end of the schedule.
+    pub fn add_system_fn<F>(&mut self, name: &str, access: Access, func: F) -> &mut Self
+    where
+        F: FnMut(&World) + Send + 'static,
+    {
+        self.add_system(FunctionSystem::new(name, access, func))
+    }
+
+    /// Appends a system gated by the given conditions.
+    fn push_system(&mut self, system: Box<dyn System>, gate: Vec<usize>) {
+        self.systems.push(system);
+        self.last_runs.push(Tick::default());
+        self.gates.push(gate);
+        self.stages = None;
+    }
+
+    /// Returns the number of systems in the schedule.
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    /// Checks if the schedule has no systems.
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    /// Returns the system names grouped by the stage they run in.
+    pub fn stage_names(&mut self) -> Vec<Vec<String>> {
+        self.build_stages();
+        let systems = &self.systems;
+        self.stages
+            .as_ref()
+            .expect("Stages were just built")
+            .iter()
+            .map(|stage| {
+                stage
+                    .systems
+                    .iter()
+                    .map(|&index| systems[index].name().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Runs every system whose conditions pass once, then makes the events
+    /// they sent readable.
+    /// Changes made outside the schedule until the next run are stamped with
+    /// a tick after every system's, so all of them see those changes.
+    pub fn run(&mut self, world: &mut World) {
+        for system in &self.systems {
+            for component in system.access().components() {
+                world.register_raw(component.type_id, component.constructor);
+            }
+            for event in system.access().events() {
+                world.register_event_raw(event.type_id, event.constructor);
+            }
+            for register in system.access().relations() {
+                register(world);
+            }
+        }
+        let passed: Vec<bool> = self
+            .conditions
+            .iter_mut()
+            .map(|condition| condition.check(world))
+            .collect();
+        self.build_stages();
+        self.run_stages(world, &passed);
+        world.increment_change_tick();
+        world.update_events();
+    }
+
+    /// Runs every stage in order, spreading each stage's systems over the job
+    /// system and applying their commands after each. Systems gated by a
+    /// failed condition are skipped.
+    fn run_stages(&mut self, world: &mut World, passed: &[bool]) {
+        let stages = self.stages.as_ref().expect("Stages were just built");
+        let gates = &self.gates;
+        let mut systems: Vec<Option<(&mut Box<dyn System>, &mut Tick)>> = self
+            .systems
+            .iter_mut()
+            .zip(self.last_runs.iter_mut())
+            .map(Some)
+            .collect();
+
+        for stage in stages {
+            let mut stage_systems = stage
+                .systems
+                .iter()
+                .filter(|&&index| gates[index].iter().all(|&condition| passed[condition]))
+                .map(|&index| {
+                    systems[index]
+                        .take()
+                        .expect("System scheduled twice in one run")
+                })
+                .collect::<Vec<_>>();
+            if let Some(job_order) = &mut self.job_order {
+                job_order.shuffle(&mut stage_systems);
+            }
+            let exclusive = stage_systems
+                .iter()
+                .any(|(system, _)| system.access().is_exclusive());
+            if exclusive {
+                for (system, last_run) in stage_systems {
+                    run_exclusive_system(&self.jobs, system, last_run, world);
+                }
+            } else {
+                run_stage(&self.jobs, stage_systems, world);
+            }
+            // Give command writes a tick of their own, so the systems that
+            // recorded them see them as changes on their next run.
+            world.increment_change_tick();
+            world.apply_commands();
+        }
+    }
+
+    /// Groups systems into stages if they haven't been grouped since the last change.
+    fn build_stages(&mut self) {
+        if self.stages.is_some() {
+            return;
+        }
+
+        let mut stage_of: Vec<usize> = Vec::with_capacity(self.systems.len());
+        let mut stages: Vec<Stage> = Vec::new();
+        for (index, system) in self.systems.iter().enumerate() {
+            let stage = self.systems[..index]
+                .iter()
+                .enumerate()
+                .filter(|(_, earlier)| earlier.access().conflicts_with(system.access()))
+                .map(|(earlier, _)| stage_of[earlier] + 1)
+                .max()
+                .unwrap_or(0);
+
+            if stage == stages.len() {
+                stages.push(Stage {
+                    systems: Vec::new(),
+                });
+            }
+            stages[stage].systems.push(index);
+            stage_of.push(stage);
+        }
+
+        self.stages = Some(stages);
+    }
+}
+
+thread_local! {
+    static SYSTEM_JOBS: RefCell<Option<Arc<JobSystem>>> = const { RefCell::new(None) };
+}
+
+/// Puts back the job system that was current before a system ran, even if
+/// the system panicked.
+struct RestoreJobs(Option<Arc<JobSystem>>);
+
+impl Drop for RestoreJobs {
+    fn drop(&mut self) {
+        SYSTEM_JOBS.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+/// Runs `func` as a system of a schedule using `jobs`.
+fn with_system_jobs<R>(jobs: &Arc<JobSystem>, func: impl FnOnce() -> R) -> R {
+    let previous = SYSTEM_JOBS.with(|current| current.replace(Some(Arc::clone(jobs))));
+    let _restore = RestoreJobs(previous);
+    func()
+}
+
+/// Returns the job system of the schedule running the current system, or the
+/// global one outside of systems. Parallel work inside systems should use it
+/// so it stays on the pool the schedule was given.
+pub fn system_jobs() -> Arc<JobSystem> {
+    SYSTEM_JOBS
+        .with(|current| current.borrow().clone())
+        .unwrap_or_else(JobSystem::global)
+}
+
+/// Runs one stage's systems, spreading them over the job system.
+fn run_stage(
+    jobs: &Arc<JobSystem>,
+    systems: Vec<(&mut Box<dyn System>, &mut Tick)>,
+    world: &World,
+) {
+    // Parallel systems may send events or draw from shared random
+    // streams in any order, so deterministic worlds run them in turn.
+    if world.is_deterministic() {
+        for (system, last_run) in systems {
+            run_system(jobs, system, last_run, world);
+        }
+        return;
+    }
+
+    let (local, parallel): (Vec<_>, Vec<_>) = systems
+        .into_iter()
+        .partition(|(system, _)| system.access().is_non_send());
+
+    if parallel.len() <= 1 {
+        for (system, last_run) in local.into_iter().chain(parallel) {
+            run_system(jobs, system, last_run, world);
+        }
+        return;
+    }
+
+    jobs.scope(|scope| {
+        for (system, last_run) in parallel {
+            scope.spawn(move || run_system(jobs, system, last_run, world));
+        }
+        for (system, last_run) in local {
+            run_system(jobs, system, last_run, world);
+        }
+    });
+}
+
+/// Runs a system at a new world tick, remembering it as the system's last run.
+fn run_system(
+    jobs: &Arc<JobSystem>,
+    system: &mut Box<dyn System>,
+    last_run: &mut Tick,
+    world: &World,
+) {
+    let this_run = world.increment_change_tick();
+    let ticks = SystemTicks {
+        last_run: *last_run,
+        this_run,
+    };
+    with_system_ticks(ticks, || with_system_jobs(jobs, || system.run(world)));
+    *last_run = this_run;
+}
+
+/// Runs an exclusive system at a new world tick, remembering it as the
+/// system's last run.
+fn run_exclusive_system(
+    jobs: &Arc<JobSystem>,
+    system: &mut Box<dyn System>,
+    last_run: &mut Tick,
+    world: &mut World,
+) {
+    let this_run = world.increment_change_tick();
+    let ticks = SystemTicks {
+        last_run: *last_run,
+        this_run,
+    };
+    with_system_ticks(ticks, || {
+        with_system_jobs(jobs, || system.run_exclusive(world))
+    });
+    *last_run = this_run;
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::ecs::Added;
+
+    struct Spawned;
+
+    #[test]
+    fn commands_are_added_for_the_next_run() {
+        let mut world = World::new();
+        let mut schedule = Schedule::new();
+        let added = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&added);
+        schedule.add_system_fn(
+            "spawner",
+            Access::new().read::<Spawned>(),
+            move |world: &World| {
+                let count = world.query_filtered::<Spawned, Added<Spawned>>().count();
+                seen.fetch_add(count, Ordering::Relaxed);
+                world.commands().spawn().insert(Spawned);
+            },
+        );
+
+        for _ in 0..3 {
+            schedule.run(&mut world);
+        }
+
+        assert_eq!(world.read::<Spawned>().len(), 3);
+        assert_eq!(added.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn systems_use_the_schedules_job_system() {
+        let mut world = World::new();
+        let jobs = Arc::new(JobSystem::with_threads(2));
+        let mut schedule = Schedule::with_job_system(Arc::clone(&jobs));
+        let matched = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&matched);
+        schedule.add_system_fn("check", Access::new(), move |_: &World| {
+            if Arc::ptr_eq(&system_jobs(), &jobs) {
+                seen.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        schedule.run(&mut world);
+
+        assert_eq!(matched.load(Ordering::Relaxed), 1);
+        assert!(Arc::ptr_eq(&system_jobs(), &JobSystem::global()));
+    }
+}
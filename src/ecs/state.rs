@@ -0,0 +1,64 @@
+use std::fmt::Debug;
+
+/// # Current State
+///
+/// A resource holding which state a state-driven part of the game is in,
+/// such as the overall flow between menu, playing and paused. Systems gated
+/// with [`in_state`](super::in_state) only run while it matches, so menus
+/// and gameplay don't need their own flags checked in every system.
+///
+/// Changing it marks the resource changed, so systems can react to entering
+/// a state through [`Res::is_changed`](super::Res::is_changed). Run
+/// conditions are checked once at the start of each schedule run, so a
+/// change takes effect from the next run.
+///
+/// ## Example
+/// ```ignore
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// enum GameState { Menu, Playing, Paused }
+///
+/// world.insert_resource(CurrentState::new(GameState::Menu));
+///
+/// // In a menu system:
+/// world.resource_mut::<CurrentState<GameState>>().set(GameState::Playing);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CurrentState<S> {
+    state: S,
+    previous: Option<S>,
+}
+
+impl<S: Copy + PartialEq + Debug> CurrentState<S> {
+    /// Creates the resource in its initial state.
+    pub fn new(initial: S) -> Self {
+        Self {
+            state: initial,
+            previous: None,
+        }
+    }
+
+    /// Returns the current state.
+    pub fn get(&self) -> S {
+        self.state
+    }
+
+    /// Checks if the current state is `state`.
+    pub fn is(&self, state: S) -> bool {
+        self.state == state
+    }
+
+    /// Returns the state before the last change, if any.
+    pub fn previous(&self) -> Option<S> {
+        self.previous
+    }
+
+    /// Moves to another state. Setting the current state again keeps the
+    /// previous one.
+    pub fn set(&mut self, state: S) {
+        if state != self.state {
+            log::debug!("State changed from {:?} to {:?}", self.state, state);
+            self.previous = Some(self.state);
+            self.state = state;
+        }
+    }
+}
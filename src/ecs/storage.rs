@@ -0,0 +1,301 @@
+use std::any::Any;
+
+use super::change_detection::{ComponentTicks, Mut, Tick};
+use super::entity::Entity;
+
+/// # Component
+///
+/// Marker trait for data that can be attached to entities. Implemented for every
+/// `Send + Sync + 'static` type so systems can share storages across threads.
+pub trait Component: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Component for T {}
+
+/// Marks an entity index without a component in the sparse array.
+const EMPTY: u32 = u32::MAX;
+
+/// # Component Storage
+///
+/// Stores every instance of a single component type in a sparse set: the
+/// components sit back to back in one array, with the entity of each in a
+/// parallel array, and an array indexed by entity index points at each entity's
+/// slot. Iterating walks contiguous memory and looking up an entity is an
+/// index rather than a hash. Removing moves the last component into the
+/// hole, so iteration order depends only on the inserts and removals made,
+/// never on the run, and deterministic simulations visit entities in the
+/// same order.
+///
+/// The index array grows to the highest entity index stored, four bytes
+/// per index.
+///
+/// Every component remembers when it was added and last changed, for
+/// `Added` and `Changed` query filters. Inserting a component and taking it
+/// mutably through [`ComponentStorage::get_mut`] or
+/// [`ComponentStorage::iter_mut`] mark it changed whether or not it is
+/// written; queries hand out [`Mut`] instead, which only marks writes.
+pub struct ComponentStorage<T> {
+    /// Slot of each entity's component by entity index, or `EMPTY`.
+    sparse: Vec<u32>,
+    entities: Vec<Entity>,
+    components: Vec<T>,
+    ticks: Vec<ComponentTicks>,
+    change_tick: Tick,
+}
+
+impl<T: Component> ComponentStorage<T> {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            components: Vec::new(),
+            ticks: Vec::new(),
+            change_tick: Tick::default(),
+        }
+    }
+
+    /// Returns the slot of the entity's component.
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let slot = *self.sparse.get(entity.index() as usize)?;
+        (slot != EMPTY && self.entities[slot as usize] == entity).then_some(slot as usize)
+    }
+
+    /// Inserts a component for the entity, returning the previous value if any.
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(slot) = self.slot(entity) {
+            self.ticks[slot].changed = self.change_tick;
+            return Some(std::mem::replace(&mut self.components[slot], component));
+        }
+        let id = entity.index() as usize;
+        if id >= self.sparse.len() {
+            self.sparse.resize(id + 1, EMPTY);
+        }
+        self.sparse[id] = self.entities.len() as u32;
+        self.entities.push(entity);
+        self.components.push(component);
+        self.ticks.push(ComponentTicks::new(self.change_tick));
+        None
+    }
+
+    /// Removes the entity's component.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slot(entity)?;
+        self.sparse[entity.index() as usize] = EMPTY;
+        self.entities.swap_remove(slot);
+        self.ticks.swap_remove(slot);
+        if let Some(moved) = self.entities.get(slot) {
+            self.sparse[moved.index() as usize] = slot as u32;
+        }
+        Some(self.components.swap_remove(slot))
+    }
+
+    /// Returns the entity's component.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.slot(entity).map(|slot| &self.components[slot])
+    }
+
+    /// Returns the entity's component mutably, marking it changed.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        let slot = self.slot(entity)?;
+        self.ticks[slot].changed = self.change_tick;
+        Some(&mut self.components[slot])
+    }
+
+    /// Returns the entity's component mutably, marked changed only if written.
+    pub fn get_tracked(&mut self, entity: Entity) -> Option<Mut<'_, T>> {
+        let slot = self.slot(entity)?;
+        Some(Mut::new(
+            &mut self.components[slot],
+            &mut self.ticks[slot],
+            self.change_tick,
+        ))
+    }
+
+    /// Returns several entities' components mutably at once, each marked
+    /// changed only if written. Returns `None` if any entity lacks the
+    /// component or appears twice.
+    pub fn get_many_tracked<const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> Option<[Mut<'_, T>; N]> {
+        let mut slots = [0; N];
+        for (i, entity) in entities.iter().enumerate() {
+            let slot = self.slot(*entity)?;
+            if slots[..i].contains(&slot) {
+                return None;
+            }
+            slots[i] = slot;
+        }
+        let change_tick = self.change_tick;
+        let components = self.components.as_mut_ptr();
+        let ticks = self.ticks.as_mut_ptr();
+        // Safety: the slots are in bounds and distinct, so the references
+        // don't alias, and they borrow `self` mutably for their lifetime.
+        Some(slots.map(|slot| unsafe {
+            Mut::new(
+                &mut *components.add(slot),
+                &mut *ticks.add(slot),
+                change_tick,
+            )
+        }))
+    }
+
+    /// Returns the ticks the entity's component was added and last changed at.
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        self.slot(entity).map(|slot| self.ticks[slot])
+    }
+
+    /// Checks if the entity's component was added after `last_run`.
+    pub fn is_added(&self, entity: Entity, last_run: Tick) -> bool {
+        self.ticks(entity)
+            .is_some_and(|ticks| ticks.is_added(last_run))
+    }
+
+    /// Checks if the entity's component was added or changed after `last_run`.
+    pub fn is_changed(&self, entity: Entity, last_run: Tick) -> bool {
+        self.ticks(entity)
+            .is_some_and(|ticks| ticks.is_changed(last_run))
+    }
+
+    /// Marks the entity's component changed without writing to it.
+    pub fn set_changed(&mut self, entity: Entity) {
+        if let Some(slot) = self.slot(entity) {
+            self.ticks[slot].changed = self.change_tick;
+        }
+    }
+
+    /// Checks if the entity has this component.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.slot(entity).is_some()
+    }
+
+    /// Returns the number of stored components.
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Checks if the storage is empty.
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Iterates over all entities and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (&Entity, &T)> + '_ {
+        self.entities.iter().zip(&self.components)
+    }
+
+    /// Iterates mutably over all entities and their components, marking
+    /// every one changed.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Entity, &mut T)> + '_ {
+        self.mark_all_changed();
+        self.entities.iter().zip(&mut self.components)
+    }
+
+    /// Iterates mutably over all entities and their components, marking
+    /// only those written to changed.
+    pub fn iter_tracked(&mut self) -> impl Iterator<Item = (Entity, Mut<'_, T>)> + '_ {
+        let tick = self.change_tick;
+        self.entities
+            .iter()
+            .zip(&mut self.components)
+            .zip(&mut self.ticks)
+            .map(move |((entity, component), ticks)| (*entity, Mut::new(component, ticks, tick)))
+    }
+
+    /// Iterates over all entities, their components and the components'
+    /// ticks.
+    pub fn iter_ticks(&self) -> impl Iterator<Item = (Entity, &T, ComponentTicks)> + '_ {
+        self.entities
+            .iter()
+            .zip(&self.components)
+            .zip(&self.ticks)
+            .map(|((entity, component), ticks)| (*entity, component, *ticks))
+    }
+
+    /// Iterates over the entities that have this component.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().copied()
+    }
+
+    /// Returns the entities and their components as two parallel slices, in
+    /// iteration order.
+    pub fn as_slices(&self) -> (&[Entity], &[T]) {
+        (&self.entities, &self.components)
+    }
+
+    /// Returns the entities and their components as two parallel slices, in
+    /// iteration order, marking every component changed.
+    pub fn as_mut_slices(&mut self) -> (&[Entity], &mut [T]) {
+        self.mark_all_changed();
+        (&self.entities, &mut self.components)
+    }
+
+    /// Returns the tick that inserts and writes are stamped with.
+    pub fn change_tick(&self) -> Tick {
+        self.change_tick
+    }
+
+    /// Marks every component changed.
+    fn mark_all_changed(&mut self) {
+        for ticks in &mut self.ticks {
+            ticks.changed = self.change_tick;
+        }
+    }
+}
+
+impl<T: Component> Default for ComponentStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Type-erased access to a component storage, used by the world to manage
+/// storages without knowing their component type.
+pub(crate) trait AnyStorage: Send + Sync {
+    fn remove_entity(&mut self, entity: Entity);
+    fn take_boxed(&mut self, entity: Entity) -> Option<Box<dyn Any + Send>>;
+    fn insert_boxed(&mut self, entity: Entity, component: Box<dyn Any + Send>);
+    fn new_empty(&self) -> Box<dyn AnyStorage>;
+    fn set_change_tick(&mut self, tick: Tick);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> AnyStorage for ComponentStorage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn take_boxed(&mut self, entity: Entity) -> Option<Box<dyn Any + Send>> {
+        self.remove(entity)
+            .map(|component| Box::new(component) as Box<dyn Any + Send>)
+    }
+
+    fn insert_boxed(&mut self, entity: Entity, component: Box<dyn Any + Send>) {
+        let component = component
+            .downcast::<T>()
+            .expect("Component storage type mismatch");
+        self.insert(entity, *component);
+    }
+
+    fn new_empty(&self) -> Box<dyn AnyStorage> {
+        new_storage::<T>()
+    }
+
+    fn set_change_tick(&mut self, tick: Tick) {
+        self.change_tick = tick;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Creates an empty, type-erased storage for the component type.
+pub(crate) fn new_storage<T: Component>() -> Box<dyn AnyStorage> {
+    Box::new(ComponentStorage::<T>::new())
+}
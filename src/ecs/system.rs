@@ -0,0 +1,329 @@
+use std::any::{type_name, TypeId};
+
+use super::events::{new_events, AnyEvents, Event};
+use super::relation::Relation;
+use super::resource::Resource;
+use super::storage::{new_storage, AnyStorage, Component};
+use super::world::World;
+
+/// A single component type a system touches.
+#[derive(Clone, Copy)]
+pub(crate) struct ComponentAccess {
+    pub(crate) type_id: TypeId,
+    pub(crate) type_name: &'static str,
+    pub(crate) constructor: fn() -> Box<dyn AnyStorage>,
+}
+
+impl ComponentAccess {
+    fn of<T: Component>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+            constructor: new_storage::<T>,
+        }
+    }
+}
+
+/// A resource type a system touches.
+#[derive(Clone, Copy)]
+struct ResourceAccess {
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl ResourceAccess {
+    fn of<R: 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<R>(),
+            type_name: type_name::<R>(),
+        }
+    }
+}
+
+/// An event type a system sends or reads.
+#[derive(Clone, Copy)]
+pub(crate) struct EventAccess {
+    pub(crate) type_id: TypeId,
+    pub(crate) constructor: fn() -> Box<dyn AnyEvents>,
+}
+
+/// # Access
+///
+/// Declares which component and resource types a system reads and writes.
+/// The scheduler uses it to run systems with disjoint access in parallel. Systems marked
+/// `non_send` (for example anything that calls into OpenGL) always run on the
+/// main thread, and `exclusive` systems run alone with the whole world.
+///
+/// ## Example
+/// ```ignore
+/// let access = Access::new().read::<Velocity>().write::<Position>().res::<Gravity>();
+/// let render_access = Access::new().read::<Position>().non_send_res::<Renderer>();
+/// ```
+#[derive(Clone, Default)]
+pub struct Access {
+    reads: Vec<ComponentAccess>,
+    writes: Vec<ComponentAccess>,
+    resource_reads: Vec<ResourceAccess>,
+    resource_writes: Vec<ResourceAccess>,
+    events: Vec<EventAccess>,
+    relations: Vec<fn(&mut World)>,
+    non_send: bool,
+    exclusive: bool,
+}
+
+impl Access {
+    /// Creates an empty access set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares shared access to a component type.
+    pub fn read<T: Component>(mut self) -> Self {
+        self.reads.push(ComponentAccess::of::<T>());
+        self
+    }
+
+    /// Declares exclusive access to a component type.
+    pub fn write<T: Component>(mut self) -> Self {
+        self.writes.push(ComponentAccess::of::<T>());
+        self
+    }
+
+    /// Declares shared access to a resource type.
+    pub fn res<R: Resource>(mut self) -> Self {
+        self.resource_reads.push(ResourceAccess::of::<R>());
+        self
+    }
+
+    /// Declares exclusive access to a resource type.
+    pub fn res_mut<R: Resource>(mut self) -> Self {
+        self.resource_writes.push(ResourceAccess::of::<R>());
+        self
+    }
+
+    /// Declares exclusive access to a non-send resource, pinning the system
+    /// to the main thread.
+    pub fn non_send_res<R: 'static>(mut self) -> Self {
+        self.resource_writes.push(ResourceAccess::of::<R>());
+        self.non_send = true;
+        self
+    }
+
+    /// Declares shared access to a relation type, so the schedule registers
+    /// it with [`World::register_relation`].
+    pub fn relation<R: Relation>(mut self) -> Self {
+        self.reads.push(ComponentAccess::of::<R>());
+        self.relations.push(World::register_relation::<R>);
+        self
+    }
+
+    /// Declares that the system sends or reads an event type, so the schedule
+    /// registers its queue. Events never cause conflicts.
+    pub fn event<T: Event>(mut self) -> Self {
+        self.events.push(EventAccess {
+            type_id: TypeId::of::<T>(),
+            constructor: new_events::<T>,
+        });
+        self
+    }
+
+    /// Marks the system as non-send, pinning it to the main thread.
+    pub fn non_send(mut self) -> Self {
+        self.non_send = true;
+        self
+    }
+
+    /// Checks if the system must run on the main thread.
+    pub fn is_non_send(&self) -> bool {
+        self.non_send
+    }
+
+    /// Marks the system as exclusive: it runs on the main thread with
+    /// `&mut World`, after every earlier system and before every later one.
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self.non_send = true;
+        self
+    }
+
+    /// Checks if the system needs the whole world to itself.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Checks if two systems can't safely run at the same time.
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        let writes_overlap = |writes: &[ComponentAccess], others: &[ComponentAccess]| {
+            writes
+                .iter()
+                .any(|w| others.iter().any(|o| o.type_id == w.type_id))
+        };
+        let resources_overlap = |writes: &[ResourceAccess], others: &[ResourceAccess]| {
+            writes
+                .iter()
+                .any(|w| others.iter().any(|o| o.type_id == w.type_id))
+        };
+        self.exclusive
+            || other.exclusive
+            || writes_overlap(&self.writes, &other.writes)
+            || writes_overlap(&self.writes, &other.reads)
+            || writes_overlap(&other.writes, &self.reads)
+            || resources_overlap(&self.resource_writes, &other.resource_writes)
+            || resources_overlap(&self.resource_writes, &other.resource_reads)
+            || resources_overlap(&other.resource_writes, &self.resource_reads)
+    }
+
+    /// Returns the names of the component types this access touches.
+    pub fn component_names(&self) -> Vec<&'static str> {
+        self.components().map(|c| c.type_name).collect()
+    }
+
+    /// Returns the names of the resource types this access touches.
+    pub fn resource_names(&self) -> Vec<&'static str> {
+        self.resource_reads
+            .iter()
+            .chain(&self.resource_writes)
+            .map(|r| r.type_name)
+            .collect()
+    }
+
+    /// Iterates over every component type this access touches.
+    pub(crate) fn components(&self) -> impl Iterator<Item = &ComponentAccess> {
+        self.reads.iter().chain(self.writes.iter())
+    }
+
+    /// Iterates over every event type this access declares.
+    pub(crate) fn events(&self) -> impl Iterator<Item = &EventAccess> {
+        self.events.iter()
+    }
+
+    /// Iterates over the registration of every relation type this access
+    /// declares.
+    pub(crate) fn relations(&self) -> impl Iterator<Item = &fn(&mut World)> {
+        self.relations.iter()
+    }
+}
+
+/// # System
+///
+/// A unit of game logic run by a `Schedule` once per update.
+pub trait System: Send {
+    /// Name used for debugging and profiling.
+    fn name(&self) -> &str;
+
+    /// Component and resource types the system reads and writes.
+    fn access(&self) -> &Access;
+
+    /// Runs the system against the world.
+    fn run(&mut self, world: &World);
+
+    /// Runs the system with exclusive access to the world. Called instead of
+    /// [`System::run`] for systems whose access is
+    /// [`exclusive`](Access::exclusive).
+    fn run_exclusive(&mut self, world: &mut World) {
+        self.run(world);
+    }
+}
+
+/// # Function System
+///
+/// A system built from a closure and an explicit access declaration.
+///
+/// ## Example
+/// ```ignore
+/// let movement = FunctionSystem::new(
+///     "movement",
+///     Access::new().read::<Velocity>().write::<Position>(),
+///     |world| {
+///         let velocities = world.read::<Velocity>();
+///         for (entity, position) in world.write::<Position>().iter_mut() {
+///             if let Some(velocity) = velocities.get(*entity) {
+///                 position.0 += velocity.0;
+///             }
+///         }
+///     },
+/// );
+/// ```
+pub struct FunctionSystem<F> {
+    name: String,
+    access: Access,
+    func: F,
+}
+
+impl<F: FnMut(&World) + Send> FunctionSystem<F> {
+    /// Creates a new system from a closure.
+    pub fn new(name: &str, access: Access, func: F) -> Self {
+        Self {
+            name: name.to_string(),
+            access,
+            func,
+        }
+    }
+}
+
+impl<F: FnMut(&World) + Send> System for FunctionSystem<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn access(&self) -> &Access {
+        &self.access
+    }
+
+    fn run(&mut self, world: &World) {
+        (self.func)(world);
+    }
+}
+
+/// # Exclusive System
+///
+/// A system built from a closure taking `&mut World`, for work that needs
+/// the whole world at once: spawning and despawning directly, swapping
+/// resources, or driving OpenGL objects that live in the world. It runs on
+/// the main thread between the stages around it, so it never overlaps
+/// another system.
+///
+/// ## Example
+/// ```ignore
+/// schedule.add_system(ExclusiveSystem::new("render", |world| {
+///     let mut renderer = world.non_send_mut::<Renderer>();
+///     renderer.draw(&world.query::<Sprite>());
+/// }));
+/// ```
+pub struct ExclusiveSystem<F> {
+    name: String,
+    access: Access,
+    func: F,
+}
+
+impl<F: FnMut(&mut World) + Send> ExclusiveSystem<F> {
+    /// Creates a new exclusive system from a closure.
+    pub fn new(name: &str, func: F) -> Self {
+        Self {
+            name: name.to_string(),
+            access: Access::new().exclusive(),
+            func,
+        }
+    }
+}
+
+impl<F: FnMut(&mut World) + Send> System for ExclusiveSystem<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn access(&self) -> &Access {
+        &self.access
+    }
+
+    fn run(&mut self, _world: &World) {
+        panic!(
+            "Exclusive system '{}' run without exclusive world access",
+            self.name
+        );
+    }
+
+    fn run_exclusive(&mut self, world: &mut World) {
+        (self.func)(world);
+    }
+}
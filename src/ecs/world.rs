@@ -0,0 +1,902 @@
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::change_detection::{last_run, system_ticks, ComponentTicks, Tick};
+use super::commands::{Command, Commands};
+use super::entity::Entity;
+use super::events::{new_events, AnyEvents, Event, Events};
+use super::query::{Query, QueryFilter, QueryMut};
+use super::relation::{new_relation_index, AnyRelationIndex, OnTargetDespawn, Relation};
+use super::resource::{NonSend, NonSendData, NonSendMut, Res, ResMut, Resource, ResourceData};
+use super::storage::{new_storage, AnyStorage, Component, ComponentStorage};
+use crate::math::{Random, StableHasher};
+use crate::name::Name;
+use crate::time::Time;
+
+/// # World
+///
+/// Owns all entities and their components, and resources: singletons such as
+/// input state or settings shared by every system. Each component type and
+/// each resource lives behind its own lock, so systems touching different
+/// types can run on different threads at the same time.
+///
+/// Worlds are independent of each other, so a game can keep several: the
+/// game itself, a UI world, or a level being loaded and simulated on a
+/// background thread. Entity handles only mean something in the world that
+/// spawned them; move data between worlds with [`World::move_entity`] and
+/// [`World::move_resource`].
+///
+/// ## Example
+/// ```ignore
+/// let mut world = World::new();
+/// let player = world.spawn();
+/// world.insert(player, Position(0.0, 0.0));
+///
+/// for (entity, position) in world.read::<Position>().iter() {
+///     println!("{:?} is at {:?}", entity, position);
+/// }
+///
+/// world.insert_resource(Gravity(-9.81));
+/// let gravity = world.resource::<Gravity>().0;
+///
+/// let loading = thread::spawn(|| load_level("forest.lvl"));
+/// let mut level = loading.join().unwrap();
+/// for entity in level.entities().collect::<Vec<_>>() {
+///     level.move_entity(entity, &mut world);
+/// }
+/// ```
+pub struct World {
+    slots: Vec<EntitySlot>,
+    free: Vec<u32>,
+    // Entries of `free` below this haven't been handed out by
+    // `reserve_entity` yet.
+    free_cursor: AtomicUsize,
+    next_index: AtomicU32,
+    alive_count: usize,
+    storages: HashMap<TypeId, RwLock<Box<dyn AnyStorage>>>,
+    events: HashMap<TypeId, Box<dyn AnyEvents>>,
+    time: Time,
+    random: Random,
+    deterministic: bool,
+    change_tick: AtomicU64,
+    commands: Mutex<Vec<Command>>,
+    relations: HashMap<TypeId, Box<dyn AnyRelationIndex>>,
+    resources: HashMap<TypeId, RwLock<ResourceData>>,
+    non_send: HashMap<TypeId, NonSendData>,
+}
+
+impl World {
+    /// Creates an empty world.
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            free_cursor: AtomicUsize::new(0),
+            next_index: AtomicU32::new(0),
+            alive_count: 0,
+            storages: HashMap::new(),
+            events: HashMap::new(),
+            time: Time::new(),
+            random: Random::from_entropy(),
+            deterministic: false,
+            change_tick: AtomicU64::new(1),
+            commands: Mutex::new(Vec::new()),
+            relations: HashMap::new(),
+            resources: HashMap::new(),
+            non_send: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty world in deterministic mode, see
+    /// [`World::set_deterministic`].
+    pub fn deterministic(seed: u64, step: f32) -> Self {
+        let mut world = Self::new();
+        world.set_deterministic(seed, step);
+        world
+    }
+
+    /// Makes the simulation reproducible, for replays and lockstep
+    /// networking: randomness restarts from `seed`, [`Time::update`] advances
+    /// by exactly `step` seconds without reading the wall clock, and
+    /// schedules run their systems one after another in a fixed order, so
+    /// events and random streams are used in the same order every run.
+    /// Component iteration order is always deterministic.
+    ///
+    /// Floating point math is reproducible across platforms only for basic
+    /// arithmetic and `sqrt`; `sin`, `exp` and friends come from the
+    /// platform's math library and can differ in the last bit between
+    /// operating systems.
+    pub fn set_deterministic(&mut self, seed: u64, step: f32) {
+        self.random.reseed(seed);
+        self.time.set_fixed_step(Some(step));
+        self.deterministic = true;
+    }
+
+    /// Returns to normal mode: wall clock time and parallel systems. The
+    /// random seed is kept.
+    pub fn clear_deterministic(&mut self) {
+        self.time.set_fixed_step(None);
+        self.deterministic = false;
+    }
+
+    /// Checks if the world is in deterministic mode.
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Hashes every component of a type in entity order, with `hash` feeding
+    /// each component's fields to the hasher. Compare checksums of the same
+    /// frame between peers or runs to find where simulations diverged.
+    ///
+    /// ## Example
+    /// ```ignore
+    /// let checksum = world.checksum::<Transform>(|transform, hasher| {
+    ///     hasher.write_f32(transform.translation.x);
+    ///     hasher.write_f32(transform.translation.y);
+    ///     hasher.write_f32(transform.translation.z);
+    /// });
+    /// ```
+    pub fn checksum<T: Component>(&self, hash: impl Fn(&T, &mut StableHasher)) -> u64 {
+        let mut hasher = StableHasher::new();
+        let Some(storage) = self.try_read::<T>() else {
+            return hasher.finish();
+        };
+        let mut components: Vec<(Entity, &T)> = storage
+            .iter()
+            .map(|(entity, component)| (*entity, component))
+            .collect();
+        components.sort_unstable_by_key(|(entity, _)| *entity);
+        for (entity, component) in components {
+            hasher.write_u64(entity.to_bits());
+            hash(component, &mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Returns the world's current tick, which advances every time a
+    /// schedule runs a system.
+    pub fn change_tick(&self) -> Tick {
+        Tick::new(self.change_tick.load(Ordering::Acquire))
+    }
+
+    /// Advances the world's tick, returning the new one.
+    pub(crate) fn increment_change_tick(&self) -> Tick {
+        Tick::new(self.change_tick.fetch_add(1, Ordering::AcqRel) + 1)
+    }
+
+    /// Returns the tick changes made now are stamped with: the running
+    /// system's, or the world's outside of systems.
+    fn write_tick(&self) -> Tick {
+        system_ticks().map_or_else(|| self.change_tick(), |ticks| ticks.this_run)
+    }
+
+    /// Returns the world's clock, which systems should take their delta time from.
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Returns the world's clock for advancing, pausing or scaling.
+    pub fn time_mut(&mut self) -> &mut Time {
+        &mut self.time
+    }
+
+    /// Returns the world's randomness, whose named streams systems can share.
+    pub fn random(&self) -> &Random {
+        &self.random
+    }
+
+    /// Returns the world's randomness for reseeding or drawing from the main generator.
+    pub fn random_mut(&mut self) -> &mut Random {
+        &mut self.random
+    }
+
+    /// Creates a new entity without any components, reusing the index of a
+    /// despawned entity if there is one.
+    pub fn spawn(&mut self) -> Entity {
+        let entity = self.reserve_entity();
+        self.flush_free();
+        self.spawn_reserved(entity);
+        entity
+    }
+
+    /// Returns a new entity without spawning it, for spawning later through
+    /// [`Commands`]. Like [`World::spawn`], it reuses the index of a despawned
+    /// entity if there is one.
+    pub fn reserve_entity(&self) -> Entity {
+        let mut cursor = self.free_cursor.load(Ordering::Relaxed);
+        while cursor > 0 {
+            match self.free_cursor.compare_exchange_weak(
+                cursor,
+                cursor - 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    let index = self.free[cursor - 1];
+                    return Entity::from_raw(index, self.slots[index as usize].generation);
+                }
+                Err(current) => cursor = current,
+            }
+        }
+        Entity::from_raw(self.next_index.fetch_add(1, Ordering::Relaxed), 0)
+    }
+
+    /// Drops the free list entries handed out by [`World::reserve_entity`].
+    fn flush_free(&mut self) {
+        let cursor = *self.free_cursor.get_mut();
+        self.free.truncate(cursor);
+    }
+
+    /// Spawns an entity reserved with [`World::reserve_entity`], or one
+    /// taken from the free list.
+    pub(crate) fn spawn_reserved(&mut self, entity: Entity) {
+        let index = entity.index() as usize;
+        if index >= self.slots.len() {
+            self.slots.resize(index + 1, EntitySlot::default());
+        }
+        let slot = &mut self.slots[index];
+        if !slot.alive {
+            slot.alive = true;
+            slot.generation = entity.generation();
+            self.alive_count += 1;
+        }
+    }
+
+    /// Returns a list of structural changes to record from a system, applied
+    /// at the next sync point. See [`Commands`].
+    pub fn commands(&self) -> Commands<'_> {
+        Commands::new(self)
+    }
+
+    /// Hands recorded commands to the world, emptying `queue`.
+    pub(crate) fn queue_commands(&self, queue: &mut Vec<Command>) {
+        if queue.is_empty() {
+            return;
+        }
+        self.commands
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .append(queue);
+    }
+
+    /// Applies every command recorded since the last call, including ones
+    /// recorded by the commands themselves. `Schedule::run` calls this after
+    /// every stage.
+    pub fn apply_commands(&mut self) {
+        loop {
+            let commands = std::mem::take(
+                self.commands
+                    .get_mut()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            );
+            if commands.is_empty() {
+                return;
+            }
+            for command in commands {
+                command(self);
+            }
+        }
+    }
+
+    /// Moves an entity and all of its components into another world,
+    /// returning its handle there, or `None` if it was already despawned.
+    /// The entity is despawned here, so relations pointing at it are
+    /// handled as for [`World::despawn`]. Relations it holds are moved as
+    /// they are and still name entities of this world; point them at their
+    /// new targets afterwards.
+    pub fn move_entity(&mut self, entity: Entity, destination: &mut World) -> Option<Entity> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        let moved = destination.spawn();
+        let tick = destination.write_tick();
+        for (type_id, storage) in &mut self.storages {
+            let storage = storage.get_mut().expect("Component storage lock poisoned");
+            let Some(component) = storage.take_boxed(entity) else {
+                continue;
+            };
+            if let Some(index) = destination.relations.get_mut(type_id) {
+                index.insert(moved, &*component);
+            }
+            let target = destination
+                .storages
+                .entry(*type_id)
+                .or_insert_with(|| RwLock::new(storage.new_empty()))
+                .get_mut()
+                .expect("Component storage lock poisoned");
+            target.set_change_tick(tick);
+            target.insert_boxed(moved, component);
+        }
+        self.despawn(entity);
+        Some(moved)
+    }
+
+    /// Removes an entity and all of its components, returning `false` if it
+    /// was already despawned. Entities pointing at it through a registered
+    /// [`Relation`] lose that relation or are despawned too, depending on the
+    /// relation. The entity's index is reused by a later spawn with a new
+    /// generation, so the old handle stays dead.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let slot = &mut self.slots[entity.index() as usize];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.flush_free();
+        self.free.push(entity.index());
+        *self.free_cursor.get_mut() = self.free.len();
+        self.alive_count -= 1;
+        for storage in self.storages.values_mut() {
+            storage
+                .get_mut()
+                .expect("Component storage lock poisoned")
+                .remove_entity(entity);
+        }
+
+        let mut detached = Vec::new();
+        let mut orphans = Vec::new();
+        for (type_id, index) in &mut self.relations {
+            index.unlink(entity);
+            let sources = index.take_sources(entity);
+            match index.on_target_despawn() {
+                OnTargetDespawn::Remove => {
+                    let on_detach = index.on_detach();
+                    detached.extend(
+                        sources
+                            .into_iter()
+                            .map(|source| (*type_id, source, on_detach)),
+                    );
+                }
+                OnTargetDespawn::Despawn => orphans.extend(sources),
+            }
+        }
+        for (type_id, source, on_detach) in detached {
+            self.storages
+                .get_mut(&type_id)
+                .expect("Relations are registered with their storage")
+                .get_mut()
+                .expect("Component storage lock poisoned")
+                .remove_entity(source);
+            on_detach(self, source);
+        }
+        for orphan in orphans {
+            self.despawn(orphan);
+        }
+        true
+    }
+
+    /// Checks if the entity was spawned and hasn't been despawned since.
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.slots
+            .get(entity.index() as usize)
+            .is_some_and(|slot| slot.alive && slot.generation == entity.generation())
+    }
+
+    /// Checks if the entity exists in this world, the same as
+    /// [`World::is_alive`].
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.is_alive(entity)
+    }
+
+    /// Returns the number of entities in the world.
+    pub fn entity_count(&self) -> usize {
+        self.alive_count
+    }
+
+    /// Iterates over all entities in the world, in index order.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.alive)
+            .map(|(index, slot)| Entity::from_raw(index as u32, slot.generation))
+    }
+
+    /// Returns an entity with a [`Name`] component of this text, if any.
+    pub fn find_named(&self, name: &str) -> Option<Entity> {
+        let name = Name::get(name)?;
+        self.try_read::<Name>()?
+            .iter()
+            .find(|(_, entity_name)| **entity_name == name)
+            .map(|(entity, _)| *entity)
+    }
+
+    /// Registers a component type so it can be accessed before any entity has it.
+    pub fn register<T: Component>(&mut self) {
+        self.register_raw(TypeId::of::<T>(), new_storage::<T>);
+    }
+
+    /// Registers a storage from its type id and constructor.
+    pub(crate) fn register_raw(
+        &mut self,
+        type_id: TypeId,
+        constructor: fn() -> Box<dyn AnyStorage>,
+    ) {
+        self.storages
+            .entry(type_id)
+            .or_insert_with(|| RwLock::new(constructor()));
+    }
+
+    /// Removes a component type's storage and every component of that type.
+    /// Returns `false` if it wasn't registered.
+    pub fn unregister<T: Component>(&mut self) -> bool {
+        self.relations.remove(&TypeId::of::<T>());
+        self.storages.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    /// Registers a relation type, indexing it for [`World::related`] and
+    /// despawn cleanup, including relations already inserted.
+    pub fn register_relation<R: Relation>(&mut self) {
+        if self.relations.contains_key(&TypeId::of::<R>()) {
+            return;
+        }
+        self.register::<R>();
+        let mut index = new_relation_index::<R>();
+        index.rebuild(self.storage_mut::<R>().as_any());
+        self.relations.insert(TypeId::of::<R>(), index);
+    }
+
+    /// Returns the entities whose relation of type `R` points at `target`, in
+    /// the order the relations were inserted. Empty if the relation isn't
+    /// registered.
+    pub fn related<R: Relation>(&self, target: Entity) -> &[Entity] {
+        self.relations
+            .get(&TypeId::of::<R>())
+            .map_or(&[], |index| index.sources(target))
+    }
+
+    /// Registers an event type so it can be sent and read.
+    pub fn register_event<T: Event>(&mut self) {
+        self.register_event_raw(TypeId::of::<T>(), new_events::<T>);
+    }
+
+    /// Registers an event queue from its type id and constructor.
+    pub(crate) fn register_event_raw(
+        &mut self,
+        type_id: TypeId,
+        constructor: fn() -> Box<dyn AnyEvents>,
+    ) {
+        self.events.entry(type_id).or_insert_with(constructor);
+    }
+
+    /// Removes an event type's queue and any unread events. Returns `false`
+    /// if it wasn't registered.
+    pub fn unregister_event<T: Event>(&mut self) -> bool {
+        self.events.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    /// Returns the queue of an event type.
+    pub fn events<T: Event>(&self) -> &Events<T> {
+        self.try_events::<T>()
+            .unwrap_or_else(|| panic!("Event '{}' is not registered", type_name::<T>()))
+    }
+
+    /// Returns the queue of an event type, if it is registered.
+    pub fn try_events<T: Event>(&self) -> Option<&Events<T>> {
+        self.events.get(&TypeId::of::<T>()).map(|events| {
+            events
+                .as_any()
+                .downcast_ref::<Events<T>>()
+                .expect("Event queue type mismatch")
+        })
+    }
+
+    /// Queues an event, readable from the next schedule run.
+    pub fn send_event<T: Event>(&self, event: T) {
+        self.events::<T>().send(event);
+    }
+
+    /// Makes events sent since the last call readable. `Schedule::run` calls
+    /// this after every run.
+    pub fn update_events(&mut self) {
+        for events in self.events.values_mut() {
+            events.update();
+        }
+    }
+
+    /// Adds a resource, replacing and returning any previous one of its type.
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) -> Option<R> {
+        let tick = self.write_tick();
+        match self.resources.get_mut(&TypeId::of::<R>()) {
+            Some(data) => {
+                let data = data.get_mut().expect("Resource lock poisoned");
+                data.ticks.changed = tick;
+                let previous = std::mem::replace(&mut data.value, Box::new(resource));
+                Some(*previous.downcast().expect("Resource type mismatch"))
+            }
+            None => {
+                let data = ResourceData {
+                    value: Box::new(resource),
+                    ticks: ComponentTicks::new(tick),
+                };
+                self.resources.insert(TypeId::of::<R>(), RwLock::new(data));
+                None
+            }
+        }
+    }
+
+    /// Removes and returns a resource.
+    pub fn remove_resource<R: Resource>(&mut self) -> Option<R> {
+        let data = self.resources.remove(&TypeId::of::<R>())?;
+        let data = data.into_inner().expect("Resource lock poisoned");
+        Some(*data.value.downcast().expect("Resource type mismatch"))
+    }
+
+    /// Checks if a resource of this type was inserted.
+    pub fn contains_resource<R: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    /// Locks a resource for shared reading.
+    pub fn resource<R: Resource>(&self) -> Res<'_, R> {
+        self.try_resource::<R>()
+            .unwrap_or_else(|| panic!("Resource '{}' is not inserted", type_name::<R>()))
+    }
+
+    /// Locks a resource for shared reading, if it was inserted.
+    pub fn try_resource<R: Resource>(&self) -> Option<Res<'_, R>> {
+        let guard = self
+            .resources
+            .get(&TypeId::of::<R>())?
+            .read()
+            .expect("Resource lock poisoned");
+        Some(Res {
+            guard,
+            last_run: last_run(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Locks a resource for exclusive writing.
+    pub fn resource_mut<R: Resource>(&self) -> ResMut<'_, R> {
+        self.try_resource_mut::<R>()
+            .unwrap_or_else(|| panic!("Resource '{}' is not inserted", type_name::<R>()))
+    }
+
+    /// Locks a resource for exclusive writing, if it was inserted.
+    pub fn try_resource_mut<R: Resource>(&self) -> Option<ResMut<'_, R>> {
+        let guard = self
+            .resources
+            .get(&TypeId::of::<R>())?
+            .write()
+            .expect("Resource lock poisoned");
+        Some(ResMut {
+            guard,
+            last_run: last_run(),
+            this_run: self.write_tick(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns a resource mutably without locking, through exclusive world
+    /// access.
+    pub fn get_resource_mut<R: Resource>(&mut self) -> Option<&mut R> {
+        let tick = self.write_tick();
+        let data = self
+            .resources
+            .get_mut(&TypeId::of::<R>())?
+            .get_mut()
+            .expect("Resource lock poisoned");
+        data.ticks.changed = tick;
+        Some(data.value.downcast_mut().expect("Resource type mismatch"))
+    }
+
+    /// Moves a resource into another world, replacing any it had there.
+    /// Returns `false` if this world has none.
+    pub fn move_resource<R: Resource>(&mut self, destination: &mut World) -> bool {
+        match self.remove_resource::<R>() {
+            Some(resource) => {
+                destination.insert_resource(resource);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a resource that must stay on the calling thread, such as the
+    /// renderer owning the OpenGL context, replacing and returning any
+    /// previous one of its type. See [`NonSend`]. Panics, leaving the
+    /// previous one in place, if that was inserted on another thread.
+    pub fn insert_non_send<R: 'static>(&mut self, resource: R) -> Option<R> {
+        if let Some(previous) = self.non_send.get(&TypeId::of::<R>()) {
+            previous.check_thread::<R>();
+        }
+        self.non_send
+            .insert(TypeId::of::<R>(), NonSendData::new(resource))
+            .map(NonSendData::into_inner)
+    }
+
+    /// Removes and returns a non-send resource. Panics off the thread it was
+    /// inserted on.
+    pub fn remove_non_send<R: 'static>(&mut self) -> Option<R> {
+        self.non_send
+            .remove(&TypeId::of::<R>())
+            .map(NonSendData::into_inner)
+    }
+
+    /// Checks if a non-send resource of this type was inserted.
+    pub fn contains_non_send<R: 'static>(&self) -> bool {
+        self.non_send.contains_key(&TypeId::of::<R>())
+    }
+
+    /// Borrows a non-send resource for shared reading. Panics off the thread
+    /// it was inserted on.
+    pub fn non_send<R: 'static>(&self) -> NonSend<'_, R> {
+        self.try_non_send::<R>()
+            .unwrap_or_else(|| panic!("Non-send resource '{}' is not inserted", type_name::<R>()))
+    }
+
+    /// Borrows a non-send resource for shared reading, if it was inserted.
+    pub fn try_non_send<R: 'static>(&self) -> Option<NonSend<'_, R>> {
+        Some(self.non_send.get(&TypeId::of::<R>())?.borrow())
+    }
+
+    /// Borrows a non-send resource for exclusive writing. Panics off the
+    /// thread it was inserted on.
+    pub fn non_send_mut<R: 'static>(&self) -> NonSendMut<'_, R> {
+        self.try_non_send_mut::<R>()
+            .unwrap_or_else(|| panic!("Non-send resource '{}' is not inserted", type_name::<R>()))
+    }
+
+    /// Borrows a non-send resource for exclusive writing, if it was inserted.
+    pub fn try_non_send_mut<R: 'static>(&self) -> Option<NonSendMut<'_, R>> {
+        Some(self.non_send.get(&TypeId::of::<R>())?.borrow_mut())
+    }
+
+    /// Attaches a component to an entity, replacing any previous value.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.contains(entity) {
+            panic!(
+                "Cannot insert {} into despawned entity {:?}",
+                type_name::<T>(),
+                entity
+            );
+        }
+        self.register::<T>();
+        if let Some(index) = self.relations.get_mut(&TypeId::of::<T>()) {
+            index.insert(entity, &component);
+        }
+        self.storage_mut::<T>().insert(entity, component)
+    }
+
+    /// Removes a component from an entity.
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        if !self.storages.contains_key(&TypeId::of::<T>()) {
+            return None;
+        }
+        if let Some(index) = self.relations.get_mut(&TypeId::of::<T>()) {
+            index.unlink(entity);
+        }
+        self.storage_mut::<T>().remove(entity)
+    }
+
+    /// Checks if the entity has a component of this type.
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.storages.contains_key(&TypeId::of::<T>()) && self.read::<T>().contains(entity)
+    }
+
+    /// Marks an entity's component changed without writing to it.
+    pub fn set_changed<T: Component>(&mut self, entity: Entity) {
+        if self.storages.contains_key(&TypeId::of::<T>()) {
+            self.storage_mut::<T>().set_changed(entity);
+        }
+    }
+
+    /// Returns a component mutably without locking, through exclusive world access.
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.storages.contains_key(&TypeId::of::<T>()) {
+            return None;
+        }
+        self.storage_mut::<T>().get_mut(entity)
+    }
+
+    /// Locks a component storage for shared reading.
+    pub fn read<T: Component>(&self) -> Read<'_, T> {
+        let guard = self
+            .lock::<T>()
+            .read()
+            .expect("Component storage lock poisoned");
+        Read {
+            guard,
+            marker: PhantomData,
+        }
+    }
+
+    /// Locks a component storage for shared reading, if the component type is registered.
+    pub fn try_read<T: Component>(&self) -> Option<Read<'_, T>> {
+        self.storages
+            .contains_key(&TypeId::of::<T>())
+            .then(|| self.read::<T>())
+    }
+
+    /// Locks a component storage for exclusive writing.
+    pub fn write<T: Component>(&self) -> Write<'_, T> {
+        let mut guard = self
+            .lock::<T>()
+            .write()
+            .expect("Component storage lock poisoned");
+        guard.set_change_tick(self.write_tick());
+        Write {
+            guard,
+            marker: PhantomData,
+        }
+    }
+
+    /// Queries every entity with a component of type `T`. See [`Query`].
+    pub fn query<T: Component>(&self) -> Query<'_, T> {
+        Query::new(self)
+    }
+
+    /// Queries the entities with a component of type `T` that pass a
+    /// filter, such as `Changed<Transform>`.
+    pub fn query_filtered<T: Component, F: QueryFilter>(&self) -> Query<'_, T, F> {
+        Query::new(self)
+    }
+
+    /// Queries every entity with a component of type `T` for writing.
+    pub fn query_mut<T: Component>(&self) -> QueryMut<'_, T> {
+        QueryMut::new(self)
+    }
+
+    /// Queries the entities with a component of type `T` that pass a
+    /// filter, for writing.
+    pub fn query_mut_filtered<T: Component, F: QueryFilter>(&self) -> QueryMut<'_, T, F> {
+        QueryMut::new(self)
+    }
+
+    /// Returns the lock guarding a component storage.
+    fn lock<T: Component>(&self) -> &RwLock<Box<dyn AnyStorage>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("Component '{}' is not registered", type_name::<T>()))
+    }
+
+    /// Returns a component storage mutably without locking.
+    fn storage_mut<T: Component>(&mut self) -> &mut ComponentStorage<T> {
+        let tick = self.write_tick();
+        let storage = self
+            .storages
+            .get_mut(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("Component '{}' is not registered", type_name::<T>()))
+            .get_mut()
+            .expect("Component storage lock poisoned");
+        storage.set_change_tick(tick);
+        storage
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .expect("Component storage type mismatch")
+    }
+}
+
+/// The generation and state of one entity index.
+#[derive(Clone, Copy, Default)]
+struct EntitySlot {
+    /// Generation of the entity using the index now, or of the next one.
+    generation: u32,
+    alive: bool,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Read
+///
+/// Shared access to a component storage, held for as long as the guard lives.
+pub struct Read<'a, T> {
+    guard: RwLockReadGuard<'a, Box<dyn AnyStorage>>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> Deref for Read<'_, T> {
+    type Target = ComponentStorage<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard
+            .as_any()
+            .downcast_ref::<ComponentStorage<T>>()
+            .expect("Component storage type mismatch")
+    }
+}
+
+/// # Write
+///
+/// Exclusive access to a component storage, held for as long as the guard lives.
+pub struct Write<'a, T> {
+    guard: RwLockWriteGuard<'a, Box<dyn AnyStorage>>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> Deref for Write<'_, T> {
+    type Target = ComponentStorage<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard
+            .as_any()
+            .downcast_ref::<ComponentStorage<T>>()
+            .expect("Component storage type mismatch")
+    }
+}
+
+impl<T: Component> DerefMut for Write<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .expect("Component storage type mismatch")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn reserved_entities_reuse_despawned_indices() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..4).map(|_| world.spawn()).collect();
+        world.despawn(entities[1]);
+        world.despawn(entities[3]);
+
+        let reserved = [
+            world.reserve_entity(),
+            world.reserve_entity(),
+            world.reserve_entity(),
+        ];
+        let mut indices: Vec<u32> = reserved.iter().map(|entity| entity.index()).collect();
+        indices.sort();
+        assert_eq!(indices, [1, 3, 4]);
+
+        for entity in reserved {
+            world.spawn_reserved(entity);
+        }
+        assert!(reserved.iter().all(|&entity| world.is_alive(entity)));
+        assert!(!world.is_alive(entities[1]) && !world.is_alive(entities[3]));
+        assert_eq!(world.spawn().index(), 5);
+    }
+
+    #[test]
+    fn spawn_and_despawn_churn_through_commands_stays_bounded() {
+        let mut world = World::new();
+        let mut alive = Vec::new();
+        for _ in 0..100 {
+            {
+                let mut commands = world.commands();
+                for _ in 0..10 {
+                    alive.push(commands.spawn().id());
+                }
+                for entity in alive.drain(..alive.len() - 10) {
+                    commands.despawn(entity);
+                }
+            }
+            world.apply_commands();
+        }
+        assert_eq!(world.entity_count(), 10);
+        assert!(world.slots.len() <= 30, "{} slots", world.slots.len());
+    }
+
+    #[test]
+    fn replacing_a_non_send_resource_off_its_thread_keeps_it() {
+        use std::rc::Rc;
+
+        let mut world = World::new();
+        world.insert_non_send(Rc::new(1));
+        let replaced = thread::scope(|scope| {
+            scope
+                .spawn(|| world.insert_non_send(Rc::new(2)).is_some())
+                .join()
+        });
+        assert!(replaced.is_err());
+        assert_eq!(**world.non_send::<Rc<i32>>(), 1);
+        assert_eq!(world.insert_non_send(Rc::new(3)).as_deref(), Some(&1));
+    }
+}
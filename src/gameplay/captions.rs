@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::ecs::{Access, FunctionSystem, System, World};
+use crate::graphics::font::SdfFont;
+use crate::graphics::sprite_batch::SpriteBatch;
+use crate::graphics::text::{TextRenderer, TextStyle};
+use crate::graphics::text_layout::{TextAlign, TextLayout, TextLayoutOptions, TextSpan};
+use crate::math::*;
+
+/// Shortest time a caption stays up, in seconds.
+const MIN_CAPTION_DURATION: f32 = 1.5;
+
+/// Extra seconds per character, roughly a comfortable reading speed.
+const SECONDS_PER_CHARACTER: f32 = 0.06;
+
+/// Whether a caption is speech or describes a sound.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CaptionKind {
+    #[default]
+    Speech,
+    /// Shown in brackets, like "[door creaks]". Can be turned off separately.
+    Sound,
+}
+
+/// One subtitle or sound description.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Caption {
+    pub kind: CaptionKind,
+    /// Who speaks, shown before the text.
+    pub speaker: Option<String>,
+    pub text: String,
+    /// Seconds on screen, or `None` to derive it from the text's length.
+    pub duration: Option<f32>,
+}
+
+impl Caption {
+    /// Creates a line of speech.
+    pub fn speech(speaker: Option<&str>, text: &str) -> Self {
+        Self {
+            kind: CaptionKind::Speech,
+            speaker: speaker.map(str::to_string),
+            text: text.to_string(),
+            duration: None,
+        }
+    }
+
+    /// Creates a sound description.
+    pub fn sound(text: &str) -> Self {
+        Self {
+            kind: CaptionKind::Sound,
+            speaker: None,
+            text: text.to_string(),
+            duration: None,
+        }
+    }
+
+    /// Sets how long the caption stays up.
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Returns how long the caption stays up.
+    pub fn display_duration(&self) -> f32 {
+        self.duration.unwrap_or_else(|| {
+            let characters = self.text.chars().count() as f32;
+            (0.9 + characters * SECONDS_PER_CHARACTER).max(MIN_CAPTION_DURATION)
+        })
+    }
+}
+
+/// A caption on screen and how long it has left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActiveCaption {
+    pub caption: Caption,
+    pub remaining: f32,
+}
+
+/// # Captions
+///
+/// Subtitles for speech and sound effects. Sound cues are registered with the
+/// caption they carry; whenever a [`SoundCuePlayed`] event names a cue, its
+/// caption is shown for a time based on its length. Captions can also be shown
+/// directly with [`ShowCaption`]. Keep one on a settings or UI entity and add
+/// [`caption_system`] to the schedule.
+///
+/// ## Example
+/// ```ignore
+/// let mut captions = Captions::new();
+/// captions.register("door_creak", Caption::sound("door creaks"));
+/// captions.register("guard_alert", Caption::speech(Some("Guard"), "Who goes there?"));
+/// world.insert(ui, captions);
+///
+/// // wherever the sound is played:
+/// world.events::<SoundCuePlayed>().send(SoundCuePlayed::new("guard_alert"));
+///
+/// // when drawing the UI:
+/// captions.draw(&mut batch, &mut text, &font, &CaptionStyle::default(), window.logical_size());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Captions {
+    cues: HashMap<String, Caption>,
+    active: Vec<ActiveCaption>,
+    pub enabled: bool,
+    /// Whether sound descriptions are shown, or only speech.
+    pub show_sounds: bool,
+    /// Most captions on screen at once; the oldest go first.
+    pub max_visible: usize,
+}
+
+impl Captions {
+    /// Creates captions with no cues.
+    pub fn new() -> Self {
+        Self {
+            cues: HashMap::new(),
+            active: Vec::new(),
+            enabled: true,
+            show_sounds: true,
+            max_visible: 3,
+        }
+    }
+
+    /// Attaches a caption to a sound cue.
+    pub fn register(&mut self, cue: &str, caption: Caption) {
+        self.cues.insert(cue.to_string(), caption);
+    }
+
+    /// Returns the caption attached to a cue.
+    pub fn cue(&self, cue: &str) -> Option<&Caption> {
+        self.cues.get(cue)
+    }
+
+    /// Shows a caption, unless captions or this kind of caption are turned off.
+    /// The same caption shown again while it is up only restarts its timer.
+    pub fn show(&mut self, caption: Caption) {
+        if !self.enabled || (caption.kind == CaptionKind::Sound && !self.show_sounds) {
+            return;
+        }
+        let remaining = caption.display_duration();
+        if let Some(active) = self
+            .active
+            .iter_mut()
+            .find(|active| active.caption == caption)
+        {
+            active.remaining = remaining;
+            return;
+        }
+        self.active.push(ActiveCaption { caption, remaining });
+        if self.active.len() > self.max_visible {
+            let excess = self.active.len() - self.max_visible;
+            self.active.drain(..excess);
+        }
+    }
+
+    /// Shows the caption of a cue, if it has one.
+    pub fn show_cue(&mut self, cue: &str) {
+        if let Some(caption) = self.cues.get(cue).cloned() {
+            self.show(caption);
+        }
+    }
+
+    /// Counts down the captions on screen and removes expired ones.
+    pub fn update(&mut self, delta_time: f32) {
+        for active in &mut self.active {
+            active.remaining -= delta_time;
+        }
+        self.active.retain(|active| active.remaining > 0.0);
+    }
+
+    /// Removes every caption from the screen.
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    /// Returns the captions on screen, oldest first.
+    pub fn active(&self) -> &[ActiveCaption] {
+        &self.active
+    }
+
+    /// Draws the captions stacked above the bottom of a screen of
+    /// `screen_size` logical pixels, newest at the bottom.
+    pub fn draw(
+        &self,
+        batch: &mut SpriteBatch,
+        text: &mut TextRenderer,
+        font: &Arc<SdfFont>,
+        style: &CaptionStyle,
+        screen_size: Vec2,
+    ) {
+        let max_width = (screen_size.x * style.max_width).max(1.0);
+        let mut bottom = screen_size.y - style.margin;
+        for active in self.active.iter().rev() {
+            let caption = &active.caption;
+            let mut spans = Vec::new();
+            if let Some(speaker) = &caption.speaker {
+                spans
+                    .push(TextSpan::new(&format!("{}: ", speaker)).with_color(style.speaker_color));
+            }
+            spans.push(match caption.kind {
+                CaptionKind::Speech => TextSpan::new(&caption.text),
+                CaptionKind::Sound => {
+                    TextSpan::new(&format!("[{}]", caption.text)).with_color(style.sound_color)
+                }
+            });
+
+            let options = TextLayoutOptions::new(style.text_size)
+                .with_max_width(max_width)
+                .with_align(TextAlign::Center);
+            let layout = TextLayout::new(font, spans, options);
+            let bounds = layout.bounds();
+            let origin = vec2((screen_size.x - max_width) * 0.5, bottom - bounds.max().y);
+
+            // Fade out over the last half second.
+            let alpha = (active.remaining / 0.5).min(1.0);
+            let background = bounds.expand(style.padding);
+            let mut background_color = style.background;
+            background_color.a *= alpha;
+            batch.fill_rect(
+                Rect::new(
+                    origin.x + background.x,
+                    origin.y + background.y,
+                    background.width,
+                    background.height,
+                ),
+                background_color,
+            );
+
+            let mut text_color = style.text_color;
+            text_color.a *= alpha;
+            text.draw_layout_2d(
+                font,
+                &layout,
+                origin,
+                TextStyle::new(style.text_size, text_color),
+            );
+            bottom = origin.y + background.y - style.spacing;
+        }
+    }
+}
+
+impl Default for Captions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How captions look. Sizes are in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaptionStyle {
+    pub text_size: f32,
+    pub text_color: Color,
+    pub speaker_color: Color,
+    pub sound_color: Color,
+    /// Box behind each caption; make it opaque for the best legibility.
+    pub background: Color,
+    pub padding: f32,
+    /// Gap between stacked captions.
+    pub spacing: f32,
+    /// Distance from the bottom of the screen.
+    pub margin: f32,
+    /// Widest a caption gets, as a fraction of the screen width.
+    pub max_width: f32,
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            text_size: 24.0,
+            text_color: Color::WHITE,
+            speaker_color: Color::YELLOW,
+            sound_color: Color::rgb(0.75, 0.75, 0.75),
+            background: Color::new(0.0, 0.0, 0.0, 0.7),
+            padding: 8.0,
+            spacing: 6.0,
+            margin: 48.0,
+            max_width: 0.7,
+        }
+    }
+}
+
+/// Sent when a sound cue starts playing, so its caption can be shown.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SoundCuePlayed {
+    pub cue: String,
+}
+
+impl SoundCuePlayed {
+    /// Creates the event for a cue.
+    pub fn new(cue: &str) -> Self {
+        Self {
+            cue: cue.to_string(),
+        }
+    }
+}
+
+/// A request to show a caption that isn't tied to a cue.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShowCaption {
+    pub caption: Caption,
+}
+
+/// Returns a system that shows captions for [`SoundCuePlayed`] and
+/// [`ShowCaption`] events on every [`Captions`] component and counts them down.
+pub fn caption_system() -> impl System {
+    FunctionSystem::new(
+        "captions",
+        Access::new()
+            .write::<Captions>()
+            .event::<SoundCuePlayed>()
+            .event::<ShowCaption>(),
+        update_captions,
+    )
+}
+
+/// Shows queued captions and expires old ones.
+pub fn update_captions(world: &World) {
+    let delta_time = world.time().delta();
+    let mut captions = world.write::<Captions>();
+    let played = world.events::<SoundCuePlayed>();
+    let shown = world.events::<ShowCaption>();
+    for (_, captions) in captions.iter_mut() {
+        captions.update(delta_time);
+        for cue in played.iter() {
+            captions.show_cue(&cue.cue);
+        }
+        for show in shown.iter() {
+            captions.show(show.caption.clone());
+        }
+    }
+}
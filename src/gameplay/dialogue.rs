@@ -0,0 +1,602 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+use crate::custom_errors::Errors;
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+
+/// One line of dialogue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogueLine {
+    /// Who says the line, or `None` for narration.
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+/// An answer the player can pick at the end of a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// Node to continue at, or `None` to end the conversation.
+    pub target: Option<String>,
+    /// Name sent with [`DialogueChoiceMade`] so game code can react to the choice.
+    pub event: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Step {
+    Line(DialogueLine),
+    Event(String),
+    Jump(Option<String>),
+}
+
+/// A named run of lines, optionally ending in choices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogueNode {
+    pub name: String,
+    steps: Vec<Step>,
+    pub choices: Vec<DialogueChoice>,
+}
+
+impl DialogueNode {
+    /// Iterates over the node's lines.
+    pub fn lines(&self) -> impl Iterator<Item = &DialogueLine> {
+        self.steps.iter().filter_map(|step| match step {
+            Step::Line(line) => Some(line),
+            _ => None,
+        })
+    }
+}
+
+/// # Dialogue
+///
+/// A branching conversation made of named nodes, usually loaded from a data
+/// file so writers can change it without touching code. Play it with a
+/// [`DialogueRunner`].
+///
+/// Each node starts with a `[name]` line and the first node is where the
+/// conversation starts. Inside a node:
+/// - `Speaker: text` is a spoken line and `text` without a colon is narration.
+///   Start narration with `:` if it contains a colon.
+/// - `! name` sends a [`DialogueTriggered`] event when reached.
+/// - `=> node` jumps to another node, `=> end` ends the conversation.
+/// - `-> text => node` offers a choice, shown with the node's last line. Add
+///   `! name` after the target to name the [`DialogueChoiceMade`] event.
+///
+/// Lines starting with `#` are comments. A node without choices or a jump ends
+/// the conversation after its last line.
+///
+/// ```text
+/// [start]
+/// Shopkeeper: Welcome! Looking for anything?
+/// -> Show me your wares. => shop ! open_shop
+/// -> Just browsing. => browsing
+/// -> Goodbye. => end
+///
+/// [browsing]
+/// Shopkeeper: Take your time.
+/// ! wave
+/// => start
+/// ```
+///
+/// ## Example
+/// ```ignore
+/// let shopkeeper = Arc::new(Dialogue::load("assets/dialogue/shopkeeper.txt")?);
+/// world.insert(npc, DialogueRunner::new(shopkeeper));
+/// world.send_event(StartDialogue::new(npc));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dialogue {
+    nodes: Vec<DialogueNode>,
+    names: HashMap<String, usize>,
+}
+
+impl Dialogue {
+    /// Loads a conversation from a file.
+    pub fn load(path: &str) -> Result<Self, Errors> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| Errors::InvalidDialogue(format!("{}: {}", path, error)))?;
+        Self::parse(&source).map_err(|error| match error {
+            Errors::InvalidDialogue(message) => {
+                Errors::InvalidDialogue(format!("{}: {}", path, message))
+            }
+            error => error,
+        })
+    }
+
+    /// Parses a conversation from source text.
+    pub fn parse(source: &str) -> Result<Self, Errors> {
+        let error = |line: usize, message: &str| {
+            Errors::InvalidDialogue(format!("line {}: {}", line + 1, message))
+        };
+        let target = |name: &str| match name.trim() {
+            "end" => None,
+            name => Some(name.to_string()),
+        };
+
+        let mut dialogue = Self::default();
+        let mut targets: Vec<(usize, String)> = Vec::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                let name = name.trim();
+                if name.is_empty() || name == "end" {
+                    return Err(error(number, &format!("invalid node name `{}`", name)));
+                }
+                if dialogue.names.contains_key(name) {
+                    return Err(error(number, &format!("duplicate node `{}`", name)));
+                }
+                dialogue
+                    .names
+                    .insert(name.to_string(), dialogue.nodes.len());
+                dialogue.nodes.push(DialogueNode {
+                    name: name.to_string(),
+                    steps: Vec::new(),
+                    choices: Vec::new(),
+                });
+                continue;
+            }
+
+            let Some(node) = dialogue.nodes.last_mut() else {
+                return Err(error(number, "text outside of a `[node]` section"));
+            };
+
+            if let Some(choice) = line.strip_prefix("->") {
+                let Some((text, rest)) = choice.split_once("=>") else {
+                    return Err(error(number, "expected `-> text => node`"));
+                };
+                let (name, event) = match rest.split_once('!') {
+                    Some((name, event)) => (name, Some(event.trim().to_string())),
+                    None => (rest, None),
+                };
+                let choice = DialogueChoice {
+                    text: text.trim().to_string(),
+                    target: target(name),
+                    event: event.filter(|event| !event.is_empty()),
+                };
+                if let Some(name) = &choice.target {
+                    targets.push((number, name.clone()));
+                }
+                node.choices.push(choice);
+                continue;
+            }
+
+            if !node.choices.is_empty() {
+                return Err(error(number, "only choices can follow a choice"));
+            }
+            let step = if let Some(name) = line.strip_prefix("=>") {
+                let name = target(name);
+                if let Some(name) = &name {
+                    targets.push((number, name.clone()));
+                }
+                Step::Jump(name)
+            } else if let Some(event) = line.strip_prefix('!') {
+                Step::Event(event.trim().to_string())
+            } else {
+                let (speaker, text) = match line.split_once(':') {
+                    Some((speaker, text)) => {
+                        let speaker = speaker.trim();
+                        (
+                            (!speaker.is_empty()).then(|| speaker.to_string()),
+                            text.trim(),
+                        )
+                    }
+                    None => (None, line),
+                };
+                Step::Line(DialogueLine {
+                    speaker,
+                    text: text.to_string(),
+                })
+            };
+            node.steps.push(step);
+        }
+
+        if let Some((number, name)) = targets
+            .into_iter()
+            .find(|(_, name)| !dialogue.names.contains_key(name))
+        {
+            return Err(error(number, &format!("unknown node `{}`", name)));
+        }
+        Ok(dialogue)
+    }
+
+    /// Returns a node by name.
+    pub fn node(&self, name: &str) -> Option<&DialogueNode> {
+        self.nodes.get(*self.names.get(name)?)
+    }
+
+    /// Returns the node the conversation starts at.
+    pub fn start(&self) -> Option<&DialogueNode> {
+        self.nodes.first()
+    }
+
+    /// Iterates over every node in file order.
+    pub fn nodes(&self) -> impl Iterator<Item = &DialogueNode> {
+        self.nodes.iter()
+    }
+}
+
+/// What dialogue UI needs to draw the current line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DialogueView<'a> {
+    pub node: &'a str,
+    pub speaker: Option<&'a str>,
+    pub text: &'a str,
+    /// Choices to show with the line; empty unless the runner waits for a choice.
+    pub choices: &'a [DialogueChoice],
+}
+
+/// # Dialogue Runner
+///
+/// A component playing a [`Dialogue`]. Send [`StartDialogue`],
+/// [`AdvanceDialogue`] and [`ChooseDialogue`] events from input handling and
+/// draw [`DialogueRunner::current`] in UI; the [`dialogue_system`] sends
+/// events back for `!` lines, choices and the end of the conversation.
+///
+/// UI can redraw only when [`DialogueRunner::revision`] changes.
+///
+/// ## Example
+/// ```ignore
+/// if input.is_key_pressed(Key::Space) {
+///     world.send_event(AdvanceDialogue { entity: npc });
+/// }
+///
+/// if let Some(view) = world.read::<DialogueRunner>().get(npc).and_then(DialogueRunner::current) {
+///     draw_dialogue_box(view.speaker, view.text, view.choices);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DialogueRunner {
+    dialogue: Arc<Dialogue>,
+    node: usize,
+    step: usize,
+    line: Option<(usize, usize)>,
+    running: bool,
+    choosing: bool,
+    triggered: Vec<String>,
+    revision: u64,
+}
+
+impl DialogueRunner {
+    /// Creates a runner that isn't playing yet.
+    pub fn new(dialogue: Arc<Dialogue>) -> Self {
+        Self {
+            dialogue,
+            node: 0,
+            step: 0,
+            line: None,
+            running: false,
+            choosing: false,
+            triggered: Vec::new(),
+            revision: 0,
+        }
+    }
+
+    /// Returns the conversation being played.
+    pub fn dialogue(&self) -> &Arc<Dialogue> {
+        &self.dialogue
+    }
+
+    /// Returns a counter that changes whenever the current line or choices change.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Checks if the conversation is playing.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Checks if the runner is waiting for [`DialogueRunner::choose`].
+    pub fn is_choosing(&self) -> bool {
+        self.choosing
+    }
+
+    /// Starts at a node, or at the first node if `node` is `None`. Returns
+    /// `false` if the node doesn't exist.
+    pub fn start(&mut self, node: Option<&str>) -> bool {
+        let index = match node {
+            Some(name) => self.dialogue.names.get(name).copied(),
+            None => (!self.dialogue.nodes.is_empty()).then_some(0),
+        };
+        let Some(index) = index else {
+            log::warn!(
+                "Tried to start unknown dialogue node '{}'",
+                node.unwrap_or("")
+            );
+            return false;
+        };
+        self.line = None;
+        self.running = true;
+        self.enter(index);
+        true
+    }
+
+    /// Stops the conversation.
+    pub fn stop(&mut self) {
+        if self.running {
+            self.running = false;
+            self.choosing = false;
+            self.revision += 1;
+        }
+    }
+
+    /// Moves past the current line. Returns `false` if nothing is playing or a
+    /// choice is needed first.
+    pub fn advance(&mut self) -> bool {
+        if !self.running || self.choosing {
+            return false;
+        }
+        self.step += 1;
+        self.settle();
+        true
+    }
+
+    /// Picks a choice and continues at its target. Returns the choice, or
+    /// `None` if no choice is being offered or the index is out of range.
+    pub fn choose(&mut self, index: usize) -> Option<DialogueChoice> {
+        if !self.running || !self.choosing {
+            return None;
+        }
+        let choice = self.dialogue.nodes[self.node].choices.get(index)?.clone();
+        self.choosing = false;
+        match choice
+            .target
+            .as_ref()
+            .and_then(|name| self.dialogue.names.get(name).copied())
+        {
+            Some(node) => self.enter(node),
+            None => self.stop(),
+        }
+        Some(choice)
+    }
+
+    /// Returns the name of the current node.
+    pub fn node(&self) -> Option<&str> {
+        self.running
+            .then(|| self.dialogue.nodes[self.node].name.as_str())
+    }
+
+    /// Returns the line to show, with any choices offered alongside it.
+    pub fn current(&self) -> Option<DialogueView<'_>> {
+        if !self.running {
+            return None;
+        }
+        let node = &self.dialogue.nodes[self.node];
+        let (speaker, text) = match self.line {
+            Some((line_node, step)) => match &self.dialogue.nodes[line_node].steps[step] {
+                Step::Line(line) => (line.speaker.as_deref(), line.text.as_str()),
+                _ => (None, ""),
+            },
+            None => (None, ""),
+        };
+        Some(DialogueView {
+            node: &node.name,
+            speaker,
+            text,
+            choices: if self.choosing {
+                &node.choices[..]
+            } else {
+                &[]
+            },
+        })
+    }
+
+    /// Takes the names of `!` lines reached since the last call.
+    pub fn drain_triggered(&mut self) -> impl Iterator<Item = String> + '_ {
+        self.triggered.drain(..)
+    }
+
+    fn enter(&mut self, node: usize) {
+        self.node = node;
+        self.step = 0;
+        self.settle();
+    }
+
+    /// Runs events and jumps until the runner rests on a line, reaches the
+    /// choices or finishes.
+    fn settle(&mut self) {
+        self.revision += 1;
+        let dialogue = Arc::clone(&self.dialogue);
+        let mut jumps = 0;
+        loop {
+            let node = &dialogue.nodes[self.node];
+            let Some(step) = node.steps.get(self.step) else {
+                if node.choices.is_empty() {
+                    self.stop();
+                } else {
+                    self.choosing = true;
+                }
+                return;
+            };
+
+            match step {
+                Step::Line(_) => {
+                    self.line = Some((self.node, self.step));
+                    let rest = &node.steps[self.step + 1..];
+                    let last = rest.iter().all(|step| matches!(step, Step::Event(_)));
+                    if last && !node.choices.is_empty() {
+                        self.triggered
+                            .extend(rest.iter().filter_map(|step| match step {
+                                Step::Event(name) => Some(name.clone()),
+                                _ => None,
+                            }));
+                        self.step = node.steps.len();
+                        self.choosing = true;
+                    }
+                    return;
+                }
+                Step::Event(name) => {
+                    self.triggered.push(name.clone());
+                    self.step += 1;
+                }
+                Step::Jump(target) => {
+                    jumps += 1;
+                    let next = target
+                        .as_ref()
+                        .and_then(|name| dialogue.names.get(name).copied());
+                    match next {
+                        Some(next) if jumps <= dialogue.nodes.len() => {
+                            self.node = next;
+                            self.step = 0;
+                        }
+                        Some(_) => {
+                            log::warn!("Dialogue node '{}' jumps in a loop", node.name);
+                            self.stop();
+                            return;
+                        }
+                        None => {
+                            self.stop();
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A request to start an entity's [`DialogueRunner`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StartDialogue {
+    pub entity: Entity,
+    /// Node to start at, or `None` for the first node.
+    pub node: Option<String>,
+}
+
+impl StartDialogue {
+    /// Starts at the first node.
+    pub fn new(entity: Entity) -> Self {
+        Self { entity, node: None }
+    }
+
+    /// Starts at a named node.
+    pub fn at(entity: Entity, node: &str) -> Self {
+        Self {
+            entity,
+            node: Some(node.to_string()),
+        }
+    }
+}
+
+/// A request to move past the current line, usually sent on a button press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdvanceDialogue {
+    pub entity: Entity,
+}
+
+/// A request to pick one of the offered choices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChooseDialogue {
+    pub entity: Entity,
+    pub choice: usize,
+}
+
+/// Sent when the player picked a choice.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogueChoiceMade {
+    pub entity: Entity,
+    /// Node the choice was offered in.
+    pub node: String,
+    pub choice: usize,
+    /// The name given after `!` on the choice.
+    pub event: Option<String>,
+}
+
+/// Sent when a `! name` line is reached.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogueTriggered {
+    pub entity: Entity,
+    pub name: String,
+}
+
+/// Sent when a conversation finishes or is stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DialogueEnded {
+    pub entity: Entity,
+}
+
+/// Returns a system that applies [`StartDialogue`], [`AdvanceDialogue`] and
+/// [`ChooseDialogue`] events to [`DialogueRunner`] components and sends
+/// [`DialogueChoiceMade`], [`DialogueTriggered`] and [`DialogueEnded`] events.
+pub fn dialogue_system() -> impl System {
+    FunctionSystem::new(
+        "dialogue",
+        Access::new()
+            .write::<DialogueRunner>()
+            .event::<StartDialogue>()
+            .event::<AdvanceDialogue>()
+            .event::<ChooseDialogue>()
+            .event::<DialogueChoiceMade>()
+            .event::<DialogueTriggered>()
+            .event::<DialogueEnded>(),
+        update_dialogue,
+    )
+}
+
+/// Applies queued dialogue requests.
+pub fn update_dialogue(world: &World) {
+    let mut runners = world.write::<DialogueRunner>();
+    let mut touched: Vec<(Entity, bool)> = Vec::new();
+    let mut touch = |entity: Entity, runner: &DialogueRunner| {
+        if !touched.iter().any(|(touched, _)| *touched == entity) {
+            touched.push((entity, runner.is_running()));
+        }
+    };
+
+    for start in world.events::<StartDialogue>().iter() {
+        if let Some(runner) = runners.get_mut(start.entity) {
+            touch(start.entity, runner);
+            runner.start(start.node.as_deref());
+        }
+    }
+
+    for advance in world.events::<AdvanceDialogue>().iter() {
+        if let Some(runner) = runners.get_mut(advance.entity) {
+            touch(advance.entity, runner);
+            runner.advance();
+        }
+    }
+
+    let choices = world.events::<DialogueChoiceMade>();
+    for choose in world.events::<ChooseDialogue>().iter() {
+        let Some(runner) = runners.get_mut(choose.entity) else {
+            continue;
+        };
+        touch(choose.entity, runner);
+        let Some(node) = runner.node().map(str::to_string) else {
+            continue;
+        };
+        if let Some(choice) = runner.choose(choose.choice) {
+            choices.send(DialogueChoiceMade {
+                entity: choose.entity,
+                node,
+                choice: choose.choice,
+                event: choice.event,
+            });
+        }
+    }
+
+    let triggered = world.events::<DialogueTriggered>();
+    let ended = world.events::<DialogueEnded>();
+    for (entity, was_running) in touched {
+        let Some(runner) = runners.get_mut(entity) else {
+            continue;
+        };
+        triggered.send_batch(
+            runner
+                .drain_triggered()
+                .map(|name| DialogueTriggered { entity, name }),
+        );
+        if was_running && !runner.is_running() {
+            ended.send(DialogueEnded { entity });
+        }
+    }
+}
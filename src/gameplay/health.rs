@@ -0,0 +1,246 @@
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+
+/// A request to hurt an entity, handled by the [`health_system`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Damage {
+    pub target: Entity,
+    pub amount: f32,
+    /// Who dealt the damage, for kill credit or knockback direction.
+    pub source: Option<Entity>,
+    /// Damage that ignores invulnerability frames, e.g. falling out of the level.
+    pub bypass_invulnerability: bool,
+}
+
+impl Damage {
+    /// Creates damage without a source.
+    pub fn new(target: Entity, amount: f32) -> Self {
+        Self {
+            target,
+            amount,
+            source: None,
+            bypass_invulnerability: false,
+        }
+    }
+
+    /// Sets who dealt the damage.
+    pub fn with_source(mut self, source: Entity) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Makes the damage ignore invulnerability frames.
+    pub fn bypassing_invulnerability(mut self) -> Self {
+        self.bypass_invulnerability = true;
+        self
+    }
+}
+
+/// A request to restore health, handled by the [`health_system`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Heal {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+/// Sent when damage was actually applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Damaged {
+    pub entity: Entity,
+    /// Health lost, after clamping to what was left.
+    pub amount: f32,
+    pub source: Option<Entity>,
+    pub remaining: f32,
+}
+
+/// Sent once when an entity's health reaches zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Died {
+    pub entity: Entity,
+    /// Who dealt the killing blow.
+    pub source: Option<Entity>,
+}
+
+/// # Health
+///
+/// A component for anything that can be hurt and destroyed. Send [`Damage`]
+/// and [`Heal`] events rather than changing it directly so invulnerability
+/// frames apply and [`Damaged`] and [`Died`] events go out.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(player, Health::new(100.0).with_invulnerability(0.75));
+/// schedule.add_system(health_system());
+///
+/// world.send_event(Damage::new(player, 20.0).with_source(enemy));
+///
+/// for died in world.events::<Died>().iter() {
+///     spawn_explosion(died.entity);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    current: f32,
+    pub max: f32,
+    /// Health restored per second while alive.
+    pub regeneration: f32,
+    /// Seconds of invulnerability after taking damage.
+    pub invulnerability: f32,
+    invulnerable_for: f32,
+    dead: bool,
+}
+
+impl Health {
+    /// Creates full health.
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            regeneration: 0.0,
+            invulnerability: 0.0,
+            invulnerable_for: 0.0,
+            dead: false,
+        }
+    }
+
+    /// Sets the seconds of invulnerability after each hit.
+    pub fn with_invulnerability(mut self, seconds: f32) -> Self {
+        self.invulnerability = seconds;
+        self
+    }
+
+    /// Sets the health restored per second.
+    pub fn with_regeneration(mut self, per_second: f32) -> Self {
+        self.regeneration = per_second;
+        self
+    }
+
+    /// Returns the current health.
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Returns the current health as a fraction of the maximum.
+    pub fn fraction(&self) -> f32 {
+        if self.max > 0.0 {
+            (self.current / self.max).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Checks if health ran out.
+    pub fn is_dead(&self) -> bool {
+        self.dead
+    }
+
+    /// Checks if hits are currently ignored.
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_for > 0.0
+    }
+
+    /// Makes the entity ignore hits for a number of seconds, e.g. after respawning.
+    pub fn make_invulnerable(&mut self, seconds: f32) {
+        self.invulnerable_for = self.invulnerable_for.max(seconds);
+    }
+
+    /// Applies damage directly, returning the health lost. Dead or
+    /// invulnerable entities lose nothing unless `bypass_invulnerability` is set.
+    pub fn apply_damage(&mut self, amount: f32, bypass_invulnerability: bool) -> f32 {
+        if self.dead || amount <= 0.0 || (self.is_invulnerable() && !bypass_invulnerability) {
+            return 0.0;
+        }
+        let lost = amount.min(self.current);
+        self.current -= lost;
+        self.invulnerable_for = self.invulnerability;
+        if self.current <= 0.0 {
+            self.dead = true;
+        }
+        lost
+    }
+
+    /// Restores health up to the maximum, returning the amount restored. Dead
+    /// entities can't be healed; use [`Health::revive`].
+    pub fn heal(&mut self, amount: f32) -> f32 {
+        if self.dead || amount <= 0.0 {
+            return 0.0;
+        }
+        let restored = amount.min(self.max - self.current).max(0.0);
+        self.current += restored;
+        restored
+    }
+
+    /// Brings the entity back with a fraction of its maximum health.
+    pub fn revive(&mut self, fraction: f32) {
+        self.dead = false;
+        self.current = (self.max * fraction).clamp(1e-3, self.max);
+    }
+
+    /// Counts down invulnerability and regenerates.
+    pub fn update(&mut self, delta_time: f32) {
+        self.invulnerable_for = (self.invulnerable_for - delta_time).max(0.0);
+        if !self.dead && self.regeneration > 0.0 {
+            self.current = (self.current + self.regeneration * delta_time).min(self.max);
+        }
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+/// Returns a system that applies [`Damage`] and [`Heal`] events to [`Health`]
+/// components and sends [`Damaged`] and [`Died`] events.
+pub fn health_system() -> impl System {
+    FunctionSystem::new(
+        "health",
+        Access::new()
+            .write::<Health>()
+            .event::<Damage>()
+            .event::<Heal>()
+            .event::<Damaged>()
+            .event::<Died>(),
+        update_health,
+    )
+}
+
+/// Updates every health component and applies queued damage and healing.
+pub fn update_health(world: &World) {
+    let delta_time = world.time().delta();
+    let mut healths = world.write::<Health>();
+    for (_, health) in healths.iter_mut() {
+        health.update(delta_time);
+    }
+
+    for heal in world.events::<Heal>().iter() {
+        if let Some(health) = healths.get_mut(heal.target) {
+            health.heal(heal.amount);
+        }
+    }
+
+    let damaged = world.events::<Damaged>();
+    let died = world.events::<Died>();
+    for damage in world.events::<Damage>().iter() {
+        let Some(health) = healths.get_mut(damage.target) else {
+            continue;
+        };
+        let was_dead = health.is_dead();
+        let amount = health.apply_damage(damage.amount, damage.bypass_invulnerability);
+        if amount <= 0.0 {
+            continue;
+        }
+        damaged.send(Damaged {
+            entity: damage.target,
+            amount,
+            source: damage.source,
+            remaining: health.current(),
+        });
+        if !was_dead && health.is_dead() {
+            died.send(Died {
+                entity: damage.target,
+                source: damage.source,
+            });
+        }
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::custom_errors::Errors;
+
+/// # Item Definition
+///
+/// Everything the game knows about a kind of item, shared by every stack of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Path of the icon texture, for inventory UI.
+    pub icon: Option<String>,
+    /// Largest stack in a single slot; `1` for unstackable items.
+    pub max_stack: u32,
+    /// Equipment slot the item goes into, such as `"weapon"` or `"head"`.
+    pub equip_slot: Option<String>,
+    /// Shop price.
+    pub value: u32,
+    /// Named numbers such as `attack` or `heal`, summed over equipped items by
+    /// [`Inventory::stat_total`].
+    pub stats: HashMap<String, f32>,
+    pub tags: Vec<String>,
+}
+
+impl ItemDefinition {
+    /// Creates a stackable item with only an id and a display name.
+    pub fn new(id: &str, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            icon: None,
+            max_stack: 99,
+            equip_slot: None,
+            value: 0,
+            stats: HashMap::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Checks if the item has a tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+
+    /// Returns a stat, or zero if the item doesn't have it.
+    pub fn stat(&self, name: &str) -> f32 {
+        self.stats.get(name).copied().unwrap_or(0.0)
+    }
+}
+
+/// # Item Database
+///
+/// Item definitions keyed by id, usually loaded from a data file so designers
+/// can add items without touching code.
+///
+/// Files list one item per `[id]` section with `key = value` lines. `#` starts
+/// a comment. `stat.<name>` lines add stats and `tags` takes a comma-separated list.
+///
+/// ```text
+/// [iron_sword]
+/// name = Iron Sword
+/// description = A plain but reliable blade.
+/// icon = textures/items/iron_sword.png
+/// max_stack = 1
+/// equip_slot = weapon
+/// value = 40
+/// stat.attack = 6
+/// tags = weapon, metal
+/// ```
+///
+/// ## Example
+/// ```ignore
+/// let items = ItemDatabase::load("assets/items.txt")?;
+/// let sword = items.get("iron_sword").unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemDatabase {
+    items: HashMap<String, ItemDefinition>,
+}
+
+impl ItemDatabase {
+    /// Creates an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads definitions from a file.
+    pub fn load(path: &str) -> Result<Self, Errors> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| Errors::InvalidItemDatabase(format!("{}: {}", path, error)))?;
+        Self::parse(&source).map_err(|error| match error {
+            Errors::InvalidItemDatabase(message) => {
+                Errors::InvalidItemDatabase(format!("{}: {}", path, message))
+            }
+            error => error,
+        })
+    }
+
+    /// Parses definitions from source text.
+    pub fn parse(source: &str) -> Result<Self, Errors> {
+        let error = |line: usize, message: &str| {
+            Errors::InvalidItemDatabase(format!("line {}: {}", line + 1, message))
+        };
+
+        let mut database = Self::new();
+        let mut current: Option<ItemDefinition> = None;
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(id) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                let id = id.trim();
+                if id.is_empty() {
+                    return Err(error(number, "empty item id"));
+                }
+                if database.items.contains_key(id)
+                    || current.as_ref().is_some_and(|item| item.id == id)
+                {
+                    return Err(error(number, &format!("duplicate item `{}`", id)));
+                }
+                if let Some(item) = current.replace(ItemDefinition::new(id, id)) {
+                    database.insert(item);
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(number, "expected `[id]` or `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let Some(item) = current.as_mut() else {
+                return Err(error(number, "property outside of an item section"));
+            };
+            let number_value = |value: &str| {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| error(number, &format!("`{}` must be a number", key)))
+            };
+
+            match key {
+                "name" => item.name = value.to_string(),
+                "description" => item.description = value.to_string(),
+                "icon" => item.icon = Some(value.to_string()),
+                "max_stack" => item.max_stack = (number_value(value)? as u32).max(1),
+                "equip_slot" => item.equip_slot = Some(value.to_string()),
+                "value" => item.value = number_value(value)? as u32,
+                "tags" => {
+                    item.tags = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
+                _ => match key.strip_prefix("stat.") {
+                    Some(stat) => {
+                        item.stats.insert(stat.to_string(), number_value(value)?);
+                    }
+                    None => return Err(error(number, &format!("unknown property `{}`", key))),
+                },
+            }
+        }
+        if let Some(item) = current {
+            database.insert(item);
+        }
+        Ok(database)
+    }
+
+    /// Adds or replaces a definition.
+    pub fn insert(&mut self, item: ItemDefinition) {
+        self.items.insert(item.id.clone(), item);
+    }
+
+    /// Returns a definition.
+    pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+        self.items.get(id)
+    }
+
+    /// Iterates over every definition.
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDefinition> {
+        self.items.values()
+    }
+
+    /// Returns the number of definitions.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Checks if the database has no definitions.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// A number of items of the same kind.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+impl ItemStack {
+    /// Creates a stack.
+    pub fn new(item: &str, count: u32) -> Self {
+        Self {
+            item: item.to_string(),
+            count,
+        }
+    }
+}
+
+/// What inventory UI needs to draw one slot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlotView<'a> {
+    pub index: usize,
+    pub definition: &'a ItemDefinition,
+    pub count: u32,
+}
+
+/// # Inventory
+///
+/// A component holding a fixed number of item slots plus named equipment
+/// slots. Stackable items fill existing stacks before taking empty slots.
+///
+/// UI can redraw only when [`Inventory::revision`] changes and draw each slot
+/// from [`Inventory::slot_views`].
+///
+/// ## Example
+/// ```ignore
+/// let mut bag = Inventory::new(20);
+/// let left_over = bag.add(&items, "potion", 5);
+/// bag.equip(&items, 0);
+/// let attack = base_attack + bag.stat_total(&items, "attack");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    equipment: HashMap<String, ItemStack>,
+    revision: u64,
+}
+
+impl Inventory {
+    /// Creates an empty inventory with a number of slots.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: vec![None; capacity],
+            equipment: HashMap::new(),
+            revision: 0,
+        }
+    }
+
+    /// Returns a counter that changes whenever the contents change.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the number of slots.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Changes the number of slots. Returns the stacks that no longer fit.
+    pub fn resize(&mut self, capacity: usize) -> Vec<ItemStack> {
+        self.revision += 1;
+        if capacity >= self.slots.len() {
+            self.slots.resize(capacity, None);
+            return Vec::new();
+        }
+        self.slots.drain(capacity..).flatten().collect()
+    }
+
+    /// Returns every slot.
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    /// Returns the stack in a slot.
+    pub fn slot(&self, index: usize) -> Option<&ItemStack> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    /// Returns every occupied slot with its definition. Stacks of unknown items are skipped.
+    pub fn slot_views<'a>(
+        &'a self,
+        database: &'a ItemDatabase,
+    ) -> impl Iterator<Item = SlotView<'a>> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let stack = slot.as_ref()?;
+            Some(SlotView {
+                index,
+                definition: database.get(&stack.item)?,
+                count: stack.count,
+            })
+        })
+    }
+
+    /// Returns the total number of an item across all slots.
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Checks if at least `count` of an item are in the slots.
+    pub fn contains(&self, item: &str, count: u32) -> bool {
+        self.count(item) >= count
+    }
+
+    /// Adds items, topping up existing stacks first. Returns how many didn't
+    /// fit. Unknown items don't fit at all.
+    pub fn add(&mut self, database: &ItemDatabase, item: &str, count: u32) -> u32 {
+        let Some(definition) = database.get(item) else {
+            log::warn!("Tried to add unknown item '{}'", item);
+            return count;
+        };
+        let mut remaining = count;
+        for stack in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.item == item && stack.count < definition.max_stack {
+                let moved = remaining.min(definition.max_stack - stack.count);
+                stack.count += moved;
+                remaining -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let moved = remaining.min(definition.max_stack);
+            *slot = Some(ItemStack::new(item, moved));
+            remaining -= moved;
+        }
+        if remaining != count {
+            self.revision += 1;
+        }
+        remaining
+    }
+
+    /// Removes up to `count` of an item, taking from the last slots first.
+    /// Returns how many were removed.
+    pub fn remove(&mut self, item: &str, count: u32) -> u32 {
+        let mut removed = 0;
+        for slot in self.slots.iter_mut().rev() {
+            if removed == count {
+                break;
+            }
+            let Some(stack) = slot.as_mut().filter(|stack| stack.item == item) else {
+                continue;
+            };
+            let taken = (count - removed).min(stack.count);
+            stack.count -= taken;
+            removed += taken;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        if removed > 0 {
+            self.revision += 1;
+        }
+        removed
+    }
+
+    /// Takes the whole stack out of a slot.
+    pub fn take(&mut self, index: usize) -> Option<ItemStack> {
+        let stack = self.slots.get_mut(index)?.take();
+        if stack.is_some() {
+            self.revision += 1;
+        }
+        stack
+    }
+
+    /// Moves a slot onto another, merging stacks of the same item up to the
+    /// stack limit and swapping anything else. This is what dragging an item
+    /// onto another slot does.
+    pub fn move_slot(&mut self, database: &ItemDatabase, from: usize, to: usize) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+        self.revision += 1;
+
+        if let (Some(source), Some(target)) = (&self.slots[from], &self.slots[to]) {
+            if source.item == target.item {
+                let max_stack = database
+                    .get(&source.item)
+                    .map_or(u32::MAX, |item| item.max_stack);
+                let moved = source.count.min(max_stack.saturating_sub(target.count));
+                if moved > 0 {
+                    self.slots[to].as_mut().unwrap().count += moved;
+                    let source = self.slots[from].as_mut().unwrap();
+                    source.count -= moved;
+                    if source.count == 0 {
+                        self.slots[from] = None;
+                    }
+                    return;
+                }
+            }
+        }
+        self.slots.swap(from, to);
+    }
+
+    /// Splits `count` items off a stack into the first empty slot. Returns the
+    /// new slot, or `None` if there is no room or not enough items.
+    pub fn split(&mut self, index: usize, count: u32) -> Option<usize> {
+        let available = self.slot(index)?.count;
+        if count == 0 || count >= available {
+            return None;
+        }
+        let empty = self.slots.iter().position(Option::is_none)?;
+        let stack = self.slots[index].as_mut()?;
+        stack.count -= count;
+        let item = stack.item.clone();
+        self.slots[empty] = Some(ItemStack { item, count });
+        self.revision += 1;
+        Some(empty)
+    }
+
+    /// Returns what is equipped in a slot.
+    pub fn equipped(&self, equip_slot: &str) -> Option<&ItemStack> {
+        self.equipment.get(equip_slot)
+    }
+
+    /// Returns every equipped item by equipment slot.
+    pub fn equipment(&self) -> impl Iterator<Item = (&str, &ItemStack)> {
+        self.equipment
+            .iter()
+            .map(|(slot, stack)| (slot.as_str(), stack))
+    }
+
+    /// Equips one item from a slot into its equipment slot, putting whatever
+    /// was equipped there back into the inventory. Returns `false` if the slot
+    /// is empty, the item can't be equipped, or there is no room for the
+    /// previously equipped item.
+    pub fn equip(&mut self, database: &ItemDatabase, index: usize) -> bool {
+        let Some(stack) = self.slot(index) else {
+            return false;
+        };
+        let Some(equip_slot) = database
+            .get(&stack.item)
+            .and_then(|definition| definition.equip_slot.clone())
+        else {
+            return false;
+        };
+
+        let item = stack.item.clone();
+        let slot = self.slots[index].as_mut().unwrap();
+        slot.count -= 1;
+        if slot.count == 0 {
+            self.slots[index] = None;
+        }
+        self.revision += 1;
+
+        let Some(previous) = self
+            .equipment
+            .insert(equip_slot.clone(), ItemStack::new(&item, 1))
+        else {
+            return true;
+        };
+        if self.add(database, &previous.item, previous.count) == 0 {
+            return true;
+        }
+        if self.slots[index].is_none() {
+            self.slots[index] = Some(previous);
+            return true;
+        }
+
+        self.equipment.insert(equip_slot, previous);
+        self.slots[index].as_mut().unwrap().count += 1;
+        false
+    }
+
+    /// Moves an equipped item back into the inventory. Returns `false` if there was no room.
+    pub fn unequip(&mut self, database: &ItemDatabase, equip_slot: &str) -> bool {
+        let Some(stack) = self.equipment.get(equip_slot).cloned() else {
+            return false;
+        };
+        let left_over = self.add(database, &stack.item, stack.count);
+        if left_over > 0 {
+            self.remove(&stack.item, stack.count - left_over);
+            return false;
+        }
+        self.equipment.remove(equip_slot);
+        self.revision += 1;
+        true
+    }
+
+    /// Sums a stat over every equipped item.
+    pub fn stat_total(&self, database: &ItemDatabase, stat: &str) -> f32 {
+        self.equipment
+            .values()
+            .filter_map(|stack| database.get(&stack.item))
+            .map(|definition| definition.stat(stat))
+            .sum()
+    }
+
+    /// Merges partial stacks and orders slots by item name, leaving empty slots at the end.
+    pub fn sort(&mut self, database: &ItemDatabase) {
+        let mut totals: Vec<(String, u32)> = Vec::new();
+        for stack in self.slots.iter_mut().filter_map(Option::take) {
+            match totals.iter_mut().find(|(item, _)| *item == stack.item) {
+                Some((_, count)) => *count += stack.count,
+                None => totals.push((stack.item, stack.count)),
+            }
+        }
+        let name = |item: &str| {
+            database
+                .get(item)
+                .map_or(item.to_string(), |item| item.name.clone())
+        };
+        totals.sort_by_cached_key(|(item, _)| name(item));
+
+        let mut index = 0;
+        for (item, mut count) in totals {
+            let max_stack = database.get(&item).map_or(u32::MAX, |item| item.max_stack);
+            while count > 0 && index < self.slots.len() {
+                let moved = count.min(max_stack);
+                self.slots[index] = Some(ItemStack::new(&item, moved));
+                count -= moved;
+                index += 1;
+            }
+        }
+        self.revision += 1;
+    }
+}
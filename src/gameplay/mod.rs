@@ -0,0 +1,20 @@
+pub mod captions;
+pub mod dialogue;
+pub mod health;
+pub mod inventory;
+pub mod trigger;
+
+pub use captions::{
+    caption_system, ActiveCaption, Caption, CaptionKind, CaptionStyle, Captions, ShowCaption,
+    SoundCuePlayed,
+};
+pub use dialogue::{
+    dialogue_system, AdvanceDialogue, ChooseDialogue, Dialogue, DialogueChoice,
+    DialogueChoiceMade, DialogueEnded, DialogueLine, DialogueNode, DialogueRunner,
+    DialogueTriggered, DialogueView, StartDialogue,
+};
+pub use health::{health_system, Damage, Damaged, Died, Heal, Health};
+pub use inventory::{Inventory, ItemDatabase, ItemDefinition, ItemStack, SlotView};
+pub use trigger::{
+    trigger_system, Trigger, TriggerActivator, TriggerEvent, TriggerEventKind, TriggerShape,
+};
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+use crate::math::*;
+use crate::scene::Transform;
+
+/// The volume of a [`Trigger`], centered on its entity's translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TriggerShape {
+    Sphere {
+        radius: f32,
+    },
+    /// A box rotated with the entity. Half extents are in world units.
+    Box {
+        half_extents: Vec3,
+    },
+}
+
+impl TriggerShape {
+    /// Checks if a sphere overlaps the shape placed at `transform`.
+    pub fn overlaps_sphere(&self, transform: &Transform, center: Vec3, radius: f32) -> bool {
+        let offset = center - transform.translation;
+        match *self {
+            TriggerShape::Sphere { radius: own } => {
+                offset.magnitude2() <= (own + radius) * (own + radius)
+            }
+            TriggerShape::Box { half_extents } => {
+                let local = transform.rotation.invert() * offset;
+                let closest = vec3(
+                    local.x.clamp(-half_extents.x, half_extents.x),
+                    local.y.clamp(-half_extents.y, half_extents.y),
+                    local.z.clamp(-half_extents.z, half_extents.z),
+                );
+                (local - closest).magnitude2() <= radius * radius
+            }
+        }
+    }
+}
+
+/// Whether an activator started, kept, or stopped overlapping a trigger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TriggerEventKind {
+    Enter,
+    Stay,
+    Exit,
+}
+
+/// Sent by the [`trigger_system`] for every trigger and activator pair whose
+/// overlap started, continued, or ended this update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TriggerEvent {
+    pub trigger: Entity,
+    pub activator: Entity,
+    pub kind: TriggerEventKind,
+}
+
+/// # Trigger
+///
+/// A component for an invisible volume that reports what moves through it
+/// without pushing anything back: checkpoints, pickups, doors that open when
+/// the player comes close, or cutscene starts. Triggers detect entities with a
+/// [`TriggerActivator`] whose `mask` shares a bit with their own.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(checkpoint, Trigger::new(TriggerShape::Box { half_extents: vec3(2.0, 2.0, 0.5) }).once());
+/// world.insert(player, TriggerActivator::new(0.5));
+/// schedule.add_system(trigger_system());
+///
+/// for event in world.events::<TriggerEvent>().iter() {
+///     if event.kind == TriggerEventKind::Enter && event.trigger == checkpoint {
+///         save_game();
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trigger {
+    pub shape: TriggerShape,
+    /// Bits an activator's mask must share with this one to set it off.
+    pub mask: u32,
+    /// Disable the trigger after its first enter event.
+    pub once: bool,
+    pub enabled: bool,
+    overlapping: HashSet<Entity>,
+}
+
+impl Trigger {
+    /// Creates an enabled trigger that reacts to every activator.
+    pub fn new(shape: TriggerShape) -> Self {
+        Self {
+            shape,
+            mask: u32::MAX,
+            once: false,
+            enabled: true,
+            overlapping: HashSet::new(),
+        }
+    }
+
+    /// Creates a spherical trigger.
+    pub fn sphere(radius: f32) -> Self {
+        Self::new(TriggerShape::Sphere { radius })
+    }
+
+    /// Sets which activators the trigger reacts to.
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Disables the trigger after its first enter event.
+    pub fn once(mut self) -> Self {
+        self.once = true;
+        self
+    }
+
+    /// Returns the activators currently inside the trigger.
+    pub fn overlapping(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.overlapping.iter().copied()
+    }
+
+    /// Checks if an activator is inside the trigger.
+    pub fn contains(&self, activator: Entity) -> bool {
+        self.overlapping.contains(&activator)
+    }
+}
+
+/// # Trigger Activator
+///
+/// A component for entities that set off triggers, approximated by a sphere
+/// around their translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TriggerActivator {
+    pub radius: f32,
+    /// Bits a trigger's mask must share with this one to react.
+    pub mask: u32,
+}
+
+impl TriggerActivator {
+    /// Creates an activator matching every trigger.
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            mask: u32::MAX,
+        }
+    }
+
+    /// Sets which triggers the activator sets off.
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+}
+
+impl Default for TriggerActivator {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+/// Returns a system that tests every [`Trigger`] against every
+/// [`TriggerActivator`] and sends [`TriggerEvent`]s.
+pub fn trigger_system() -> impl System {
+    FunctionSystem::new(
+        "triggers",
+        Access::new()
+            .write::<Trigger>()
+            .read::<TriggerActivator>()
+            .read::<Transform>()
+            .event::<TriggerEvent>(),
+        update_triggers,
+    )
+}
+
+/// Updates trigger overlaps and sends their events.
+pub fn update_triggers(world: &World) {
+    let transforms = world.read::<Transform>();
+    let activators: Vec<(Entity, Vec3, TriggerActivator)> = world
+        .read::<TriggerActivator>()
+        .iter()
+        .filter_map(|(entity, activator)| {
+            Some((*entity, transforms.get(*entity)?.translation, *activator))
+        })
+        .collect();
+    let events = world.events::<TriggerEvent>();
+
+    let mut inside = HashSet::new();
+    for (entity, trigger) in world.write::<Trigger>().iter_mut() {
+        inside.clear();
+        if let (true, Some(transform)) = (trigger.enabled, transforms.get(*entity)) {
+            inside.extend(
+                activators
+                    .iter()
+                    .filter(|(activator, _, settings)| {
+                        *activator != *entity && settings.mask & trigger.mask != 0
+                    })
+                    .filter(|(_, center, settings)| {
+                        trigger
+                            .shape
+                            .overlaps_sphere(transform, *center, settings.radius)
+                    })
+                    .map(|(activator, _, _)| *activator),
+            );
+        }
+
+        let event = |activator: Entity, kind: TriggerEventKind| TriggerEvent {
+            trigger: *entity,
+            activator,
+            kind,
+        };
+        events.send_batch(
+            trigger
+                .overlapping
+                .difference(&inside)
+                .map(|a| event(*a, TriggerEventKind::Exit)),
+        );
+        events.send_batch(inside.iter().map(|activator| {
+            if trigger.overlapping.contains(activator) {
+                event(*activator, TriggerEventKind::Stay)
+            } else {
+                event(*activator, TriggerEventKind::Enter)
+            }
+        }));
+
+        if trigger.once
+            && inside
+                .iter()
+                .any(|activator| !trigger.overlapping.contains(activator))
+        {
+            trigger.enabled = false;
+        }
+        std::mem::swap(&mut trigger.overlapping, &mut inside);
+    }
+}
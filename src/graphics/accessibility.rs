@@ -0,0 +1,341 @@
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::lines::LineRenderer;
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::sprite_batch::SpriteBatch;
+use super::text::{TextRenderer, TextStyle};
+use super::texture::Texture;
+use crate::math::*;
+use crate::name;
+
+// Simulation matrices from Machado et al. 2009 at full severity, applied to
+// linear RGB, one row per output channel.
+const COLOR_FILTER_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_scene;
+uniform int u_vision;
+uniform int u_correct;
+uniform float u_strength;
+
+vec3 simulate(vec3 c) {
+    if (u_vision == 1) {
+        return vec3(dot(c, vec3(0.152286, 1.052583, -0.204868)),
+                    dot(c, vec3(0.114503, 0.786281, 0.099216)),
+                    dot(c, vec3(-0.003882, -0.048116, 1.051998)));
+    }
+    if (u_vision == 2) {
+        return vec3(dot(c, vec3(0.367322, 0.860646, -0.227968)),
+                    dot(c, vec3(0.280085, 0.672501, 0.047413)),
+                    dot(c, vec3(-0.011820, 0.042940, 0.968881)));
+    }
+    if (u_vision == 3) {
+        return vec3(dot(c, vec3(1.255528, -0.076749, -0.178779)),
+                    dot(c, vec3(-0.078411, 0.930809, 0.147602)),
+                    dot(c, vec3(0.004733, 0.691367, 0.303900)));
+    }
+    if (u_vision == 4) {
+        return vec3(dot(c, vec3(0.2126, 0.7152, 0.0722)));
+    }
+    return c;
+}
+
+// Daltonization: the detail lost to the deficiency is moved into the channels
+// that are still seen.
+vec3 correct(vec3 c) {
+    vec3 error = c - simulate(c);
+    if (u_vision == 1 || u_vision == 2) {
+        return c + vec3(0.0, 0.7 * error.r + error.g, 0.7 * error.r + error.b);
+    }
+    if (u_vision == 3) {
+        return c + vec3(error.r + 0.7 * error.b, error.g + 0.7 * error.b, 0.0);
+    }
+    return c;
+}
+
+void main() {
+    vec4 scene = texture(u_scene, v_uv);
+    vec3 filtered = u_correct == 1 ? correct(scene.rgb) : simulate(scene.rgb);
+    out_color = vec4(clamp(mix(scene.rgb, filtered, u_strength), 0.0, 1.0), scene.a);
+}
+"#;
+
+/// A kind of color vision deficiency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    /// No red cones.
+    Protanopia,
+    /// No green cones, the most common deficiency.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia,
+    /// No color vision at all.
+    Achromatopsia,
+}
+
+impl ColorVision {
+    fn index(self) -> i32 {
+        match self {
+            ColorVision::Normal => 0,
+            ColorVision::Protanopia => 1,
+            ColorVision::Deuteranopia => 2,
+            ColorVision::Tritanopia => 3,
+            ColorVision::Achromatopsia => 4,
+        }
+    }
+}
+
+/// What the color filter does for a [`ColorVision`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorFilterMode {
+    /// Shifts colors so players with the deficiency can tell them apart.
+    #[default]
+    Correct,
+    /// Shows how the deficiency sees the game, for checking art during development.
+    Simulate,
+}
+
+/// # UI Theme
+///
+/// Colors and outline widths shared by UI elements. The high-contrast theme
+/// uses pure black and white with strong outlines for players with low vision.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UiTheme {
+    pub background: Color,
+    pub panel: Color,
+    pub text: Color,
+    pub accent: Color,
+    pub border: Color,
+    pub border_width: f32,
+    /// Color of whatever has keyboard or controller focus.
+    pub focus: Color,
+}
+
+impl UiTheme {
+    /// Creates the default theme.
+    pub fn standard() -> Self {
+        Self {
+            background: Color::new(0.05, 0.05, 0.07, 0.85),
+            panel: Color::new(0.12, 0.12, 0.16, 0.9),
+            text: Color::rgb(0.92, 0.92, 0.92),
+            accent: Color::rgb(0.35, 0.6, 1.0),
+            border: Color::new(1.0, 1.0, 1.0, 0.15),
+            border_width: 1.0,
+            focus: Color::rgb(0.35, 0.6, 1.0),
+        }
+    }
+
+    /// Creates an opaque black and white theme with thick outlines.
+    pub fn high_contrast() -> Self {
+        Self {
+            background: Color::BLACK,
+            panel: Color::BLACK,
+            text: Color::WHITE,
+            accent: Color::YELLOW,
+            border: Color::WHITE,
+            border_width: 3.0,
+            focus: Color::YELLOW,
+        }
+    }
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// # Accessibility
+///
+/// Player-facing accessibility settings: UI and text size, a color filter for
+/// color vision deficiencies, and a high-contrast theme. Keep one on a settings
+/// entity and apply it to the UI renderers and post-processing each frame.
+///
+/// ## Example
+/// ```ignore
+/// let settings = Accessibility::default()
+///     .with_ui_scale(1.25)
+///     .with_color_vision(ColorVision::Deuteranopia);
+///
+/// settings.apply_ui_scale(window.content_scale(), &mut batch, &mut text, &mut lines);
+/// text.draw_2d(&font, "Start", position, settings.text_style(TextStyle::new(24.0, settings.theme().text)));
+///
+/// if settings.needs_color_filter() {
+///     color_filter.apply(&settings, scene.color_texture(), None);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Accessibility {
+    /// Multiplies the size of all UI, on top of the display's content scale.
+    pub ui_scale: f32,
+    /// Multiplies the size of text only.
+    pub text_scale: f32,
+    pub color_vision: ColorVision,
+    pub color_filter: ColorFilterMode,
+    /// How strongly the color filter applies, from 0 to 1.
+    pub filter_strength: f32,
+    pub high_contrast: bool,
+}
+
+impl Accessibility {
+    /// Sets the UI scale.
+    pub fn with_ui_scale(mut self, ui_scale: f32) -> Self {
+        self.ui_scale = ui_scale;
+        self
+    }
+
+    /// Sets the text scale.
+    pub fn with_text_scale(mut self, text_scale: f32) -> Self {
+        self.text_scale = text_scale;
+        self
+    }
+
+    /// Sets the color vision the filter is made for.
+    pub fn with_color_vision(mut self, color_vision: ColorVision) -> Self {
+        self.color_vision = color_vision;
+        self
+    }
+
+    /// Sets whether the filter corrects or simulates.
+    pub fn with_color_filter(mut self, color_filter: ColorFilterMode) -> Self {
+        self.color_filter = color_filter;
+        self
+    }
+
+    /// Sets whether the high-contrast theme is used.
+    pub fn with_high_contrast(mut self, high_contrast: bool) -> Self {
+        self.high_contrast = high_contrast;
+        self
+    }
+
+    /// Returns the UI theme to draw with.
+    pub fn theme(&self) -> UiTheme {
+        if self.high_contrast {
+            UiTheme::high_contrast()
+        } else {
+            UiTheme::standard()
+        }
+    }
+
+    /// Returns the UI scale to give the renderers on a display with the given
+    /// content scale.
+    pub fn effective_ui_scale(&self, content_scale: f32) -> f32 {
+        content_scale * self.ui_scale.max(0.1)
+    }
+
+    /// Sets the UI scale of the 2D renderers.
+    pub fn apply_ui_scale(
+        &self,
+        content_scale: f32,
+        batch: &mut SpriteBatch,
+        text: &mut TextRenderer,
+        lines: &mut LineRenderer,
+    ) {
+        let scale = self.effective_ui_scale(content_scale);
+        batch.set_ui_scale(scale);
+        text.set_ui_scale(scale);
+        lines.set_ui_scale(scale);
+    }
+
+    /// Scales a text style by the text scale.
+    pub fn text_style(&self, style: TextStyle) -> TextStyle {
+        let scale = self.text_scale.max(0.1);
+        TextStyle {
+            size: style.size * scale,
+            outline_width: style.outline_width * scale,
+            shadow_offset: style.shadow_offset * scale,
+            ..style
+        }
+    }
+
+    /// Checks if the color filter changes anything.
+    pub fn needs_color_filter(&self) -> bool {
+        self.color_vision != ColorVision::Normal && self.filter_strength > 0.0
+    }
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            text_scale: 1.0,
+            color_vision: ColorVision::Normal,
+            color_filter: ColorFilterMode::Correct,
+            filter_strength: 1.0,
+            high_contrast: false,
+        }
+    }
+}
+
+/// # Color Filter
+///
+/// The post-processing pass for [`Accessibility`]'s color vision settings,
+/// drawn as the last step before the UI so the UI's own colors stay exact.
+pub struct ColorFilter {
+    shader: ShaderProgram,
+    triangle: Primitive,
+}
+
+impl ColorFilter {
+    /// Creates the pass.
+    pub fn new() -> Self {
+        let mut shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, COLOR_FILTER_FRAGMENT_SHADER);
+        for uniform in ["u_scene", "u_vision", "u_correct", "u_strength"] {
+            shader.create_uniform(uniform);
+        }
+        Self {
+            shader,
+            triangle: Primitive::fullscreen_triangle(),
+        }
+    }
+
+    /// Filters `scene` into `target`, or into the window if `target` is `None`.
+    pub fn apply(&self, settings: &Accessibility, scene: &Texture, target: Option<&Framebuffer>) {
+        let (width, height) = match target {
+            Some(target) => {
+                target.bind();
+                target.size()
+            }
+            None => {
+                Framebuffer::unbind();
+                (scene.width(), scene.height())
+            }
+        };
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        self.shader.bind();
+        self.shader.set_1i_uniform(name!("u_scene"), 0);
+        self.shader
+            .set_1i_uniform(name!("u_vision"), settings.color_vision.index());
+        self.shader.set_1i_uniform(
+            name!("u_correct"),
+            (settings.color_filter == ColorFilterMode::Correct) as i32,
+        );
+        self.shader.set_1f_uniform(
+            name!("u_strength"),
+            settings.filter_strength.clamp(0.0, 1.0),
+        );
+        scene.bind(0);
+        self.triangle.draw();
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        ShaderProgram::unbind();
+        Framebuffer::unbind();
+    }
+}
+
+impl Default for ColorFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,402 @@
+use std::mem;
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::camera::CameraView;
+use super::gl_wrapper::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use super::primitives::Primitive;
+use super::render_layers::RenderLayers;
+use super::shader_preprocessor::{ShaderDefines, ShaderLibrary};
+use super::texture::Texture;
+use super::texture_packer::PackedTexture;
+use crate::arena::FrameArena;
+use crate::ecs::World;
+use crate::math::*;
+use crate::name;
+use crate::scene::Transform;
+
+const BILLBOARD_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_corner;
+layout (location = 1) in vec4 a_position_mode;
+layout (location = 2) in vec4 a_size_frames;
+layout (location = 3) in vec4 a_color;
+layout (location = 4) in vec4 a_uv_rect;
+layout (location = 5) in vec4 a_axis;
+
+uniform mat4 u_view;
+uniform mat4 u_view_projection;
+uniform vec3 u_camera_position;
+
+out vec2 v_uv;
+out vec4 v_color;
+flat out float v_layer;
+
+const float TAU = 6.28318531;
+
+void main() {
+    vec3 position = a_position_mode.xyz;
+    int mode = int(a_position_mode.w);
+    vec3 camera_right = vec3(u_view[0][0], u_view[1][0], u_view[2][0]);
+    vec3 camera_up = vec3(u_view[0][1], u_view[1][1], u_view[2][1]);
+
+    vec3 right = camera_right;
+    vec3 up = camera_up;
+    if (mode == 1) {
+        up = normalize(a_axis.xyz);
+        vec3 to_camera = u_camera_position - position;
+        vec3 side = cross(up, to_camera);
+        right = length(side) > 0.0001 ? normalize(side) : camera_right;
+    }
+
+    vec3 world = position + right * a_corner.x * a_size_frames.x + up * a_corner.y * a_size_frames.y;
+    gl_Position = u_view_projection * vec4(world, 1.0);
+
+    // Impostors pick one of `frames` pre-rendered angles around the Y axis.
+    vec4 uv_rect = a_uv_rect;
+    float frames = a_size_frames.z;
+    if (frames > 1.0) {
+        vec3 to_camera = u_camera_position - position;
+        float angle = atan(to_camera.x, to_camera.z) / TAU + 0.5;
+        float frame = mod(floor(angle * frames + 0.5), frames);
+        uv_rect.z /= frames;
+        uv_rect.x += frame * uv_rect.z;
+    }
+
+    v_uv = uv_rect.xy + (a_corner + 0.5) * uv_rect.zw;
+    v_color = a_color;
+    v_layer = a_size_frames.w;
+}
+"#;
+
+const BILLBOARD_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+in vec4 v_color;
+flat in float v_layer;
+out vec4 out_color;
+
+#ifdef TEXTURE_ARRAY
+uniform sampler2DArray u_texture;
+#else
+uniform sampler2D u_texture;
+#endif
+uniform float u_alpha_cutoff;
+
+void main() {
+#ifdef TEXTURE_ARRAY
+    vec4 color = texture(u_texture, vec3(v_uv, v_layer)) * v_color;
+#else
+    vec4 color = texture(u_texture, v_uv) * v_color;
+#endif
+    if (color.a < u_alpha_cutoff) {
+        discard;
+    }
+    out_color = color;
+}
+"#;
+
+/// Floats per billboard instance: five `vec4` attributes.
+const INSTANCE_FLOATS: usize = 20;
+
+/// How a billboard turns towards the camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BillboardMode {
+    /// Always faces the camera, like particles and labels.
+    Spherical,
+    /// Rotates only around an axis, like trees and grass that should stay upright.
+    Axis(Vec3),
+}
+
+impl BillboardMode {
+    /// Stays upright around the world Y axis.
+    pub const CYLINDRICAL: BillboardMode = BillboardMode::Axis(Vec3::new(0.0, 1.0, 0.0));
+}
+
+/// # Billboard
+///
+/// A camera-facing textured quad component, drawn centered on its entity's
+/// `Transform` translation. The transform's X and Y scale multiply `size`.
+///
+/// An impostor is a billboard whose texture holds `impostor_frames` views of
+/// an object side by side, rendered from evenly spaced angles around the Y
+/// axis; the frame facing the camera is picked per instance.
+#[derive(Clone)]
+pub struct Billboard {
+    pub size: Vec2,
+    pub color: Color,
+    pub texture: Option<Arc<Texture>>,
+    /// Region of the texture to show, in UV coordinates.
+    pub uv_rect: Rect,
+    /// Layer of an array texture to show.
+    pub layer: u32,
+    pub mode: BillboardMode,
+    pub impostor_frames: u32,
+}
+
+impl Billboard {
+    /// Creates a spherical billboard of the given world size.
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            color: Color::WHITE,
+            texture: None,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+            layer: 0,
+            mode: BillboardMode::Spherical,
+            impostor_frames: 0,
+        }
+    }
+
+    /// Sets the texture.
+    pub fn with_texture(mut self, texture: Arc<Texture>) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// Shows a packed texture, so billboards packed into the same texture
+    /// array are drawn in one call.
+    pub fn with_packed(mut self, packed: &PackedTexture) -> Self {
+        self.texture = Some(Arc::clone(&packed.texture));
+        self.uv_rect = packed.uv_rect;
+        self.layer = packed.layer;
+        self
+    }
+
+    /// Sets the tint color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Shows only a region of the texture, such as one sprite of an atlas.
+    pub fn with_uv_rect(mut self, uv_rect: Rect) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    /// Sets how the billboard faces the camera.
+    pub fn with_mode(mut self, mode: BillboardMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Makes the billboard an upright impostor with `frames` angles laid out
+    /// horizontally across its UV rect.
+    pub fn impostor(mut self, frames: u32) -> Self {
+        self.mode = BillboardMode::CYLINDRICAL;
+        self.impostor_frames = frames;
+        self
+    }
+}
+
+/// # Billboard Renderer
+///
+/// Draws billboards with one instanced draw call per texture, or per texture
+/// array for billboards using [`Billboard::with_packed`].
+///
+/// ## Example
+/// ```ignore
+/// let mut billboards = BillboardRenderer::new();
+///
+/// renderer.render_world_cameras(&world, window_size, |view| {
+///     billboards.render_world(&world, view);
+/// });
+/// ```
+pub struct BillboardRenderer {
+    /// Fragments with less alpha are discarded, so cutout foliage writes correct depth.
+    pub alpha_cutoff: f32,
+    shader: ShaderProgram,
+    array_shader: ShaderProgram,
+    quad: Primitive,
+    instances: BufferObject,
+    white: Texture,
+    data: Vec<f32>,
+    arena: FrameArena,
+    scaled: Vec<(Billboard, Vec3)>,
+}
+
+impl BillboardRenderer {
+    /// Creates a billboard renderer.
+    pub fn new() -> Self {
+        let shader = billboard_shader(&ShaderDefines::new());
+        let array_shader = billboard_shader(&ShaderDefines::new().with("TEXTURE_ARRAY"));
+
+        let quad = Primitive::unit_quad();
+        let instances = BufferObject::new(gl::ARRAY_BUFFER, gl::STREAM_DRAW);
+        quad.bind();
+        instances.bind();
+        let stride = (INSTANCE_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+        for attribute in 0..5 {
+            let offset = attribute * 4 * mem::size_of::<GLfloat>();
+            let attribute = VertexAttribute::new(
+                1 + attribute as GLuint,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                offset as *const _,
+            );
+            attribute.enable();
+            attribute.set_divisor(1);
+        }
+        instances.unbind();
+        Vao::unbind();
+
+        Self {
+            alpha_cutoff: 0.01,
+            shader,
+            array_shader,
+            quad,
+            instances,
+            white: Texture::from_rgba8(1, 1, &[255, 255, 255, 255]),
+            data: Vec::new(),
+            arena: FrameArena::new(),
+            scaled: Vec::new(),
+        }
+    }
+
+    /// Draws billboards placed at world positions.
+    pub fn render<'a, I>(&mut self, view: &CameraView, billboards: I)
+    where
+        I: IntoIterator<Item = (&'a Billboard, Vec3)>,
+    {
+        self.arena.reset();
+        let billboards = self.arena.alloc_from_iter(billboards);
+        if billboards.is_empty() {
+            return;
+        }
+        billboards.sort_by_key(|(billboard, _)| texture_key(billboard));
+
+        for shader in [&self.array_shader, &self.shader] {
+            shader.bind();
+            shader.set_matrix4fv_uniform(name!("u_view"), &view.view);
+            shader.set_matrix4fv_uniform(name!("u_view_projection"), &view.view_projection);
+            shader.set_3f_uniform(
+                name!("u_camera_position"),
+                view.position.x,
+                view.position.y,
+                view.position.z,
+            );
+            shader.set_1i_uniform(name!("u_texture"), 0);
+            shader.set_1f_uniform(name!("u_alpha_cutoff"), self.alpha_cutoff);
+        }
+
+        for batch in billboards.chunk_by(|a, b| texture_key(a.0) == texture_key(b.0)) {
+            self.data.clear();
+            for (billboard, position) in batch {
+                let (mode, axis) = match billboard.mode {
+                    BillboardMode::Spherical => (0.0, Vec3::unit_y()),
+                    BillboardMode::Axis(axis) => (1.0, axis),
+                };
+                let uv = billboard.uv_rect;
+                let color = billboard.color.to_linear();
+                self.data.extend_from_slice(&[
+                    position.x,
+                    position.y,
+                    position.z,
+                    mode,
+                    billboard.size.x,
+                    billboard.size.y,
+                    billboard.impostor_frames as f32,
+                    billboard.layer as f32,
+                    color.r,
+                    color.g,
+                    color.b,
+                    color.a,
+                    uv.x,
+                    uv.y,
+                    uv.width,
+                    uv.height,
+                    axis.x,
+                    axis.y,
+                    axis.z,
+                    0.0,
+                ]);
+            }
+
+            self.instances.bind();
+            self.instances.store_f32_data(&self.data);
+            self.instances.unbind();
+
+            let texture = batch[0].0.texture.as_deref().unwrap_or(&self.white);
+            if texture.target() == gl::TEXTURE_2D_ARRAY {
+                self.array_shader.bind();
+            } else {
+                self.shader.bind();
+            }
+            texture.bind(0);
+            self.quad.draw_instanced(batch.len());
+        }
+
+        ShaderProgram::unbind();
+    }
+
+    /// Draws every entity with a `Billboard` and a `Transform` that `view` can see.
+    pub fn render_world(&mut self, world: &World, view: &CameraView) {
+        let (Some(billboards), Some(transforms)) =
+            (world.try_read::<Billboard>(), world.try_read::<Transform>())
+        else {
+            return;
+        };
+        let layers = world.try_read::<RenderLayers>();
+
+        let mut scaled = mem::take(&mut self.scaled);
+        scaled.clear();
+        for (entity, billboard) in billboards.iter() {
+            let Some(transform) = transforms.get(*entity) else {
+                continue;
+            };
+            if !view.is_visible(layers.as_ref().and_then(|layers| layers.get(*entity))) {
+                continue;
+            }
+            let mut billboard = billboard.clone();
+            billboard.size.x *= transform.scale.x;
+            billboard.size.y *= transform.scale.y;
+            scaled.push((billboard, transform.translation));
+        }
+        self.render(
+            view,
+            scaled
+                .iter()
+                .map(|(billboard, position)| (billboard, *position)),
+        );
+        self.scaled = scaled;
+    }
+}
+
+impl Default for BillboardRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn texture_key(billboard: &Billboard) -> usize {
+    billboard
+        .texture
+        .as_ref()
+        .map_or(0, |texture| Arc::as_ptr(texture) as usize)
+}
+
+/// Compiles the billboard shader for plain or array textures.
+fn billboard_shader(defines: &ShaderDefines) -> ShaderProgram {
+    let mut library = ShaderLibrary::new();
+    let vertex = library
+        .preprocess(BILLBOARD_VERTEX_SHADER, defines)
+        .expect("Billboard shader has no includes");
+    let fragment = library
+        .preprocess(BILLBOARD_FRAGMENT_SHADER, defines)
+        .expect("Billboard shader has no includes");
+    let mut shader = ShaderProgram::from_source(&vertex, &fragment);
+    for uniform in [
+        "u_view",
+        "u_view_projection",
+        "u_camera_position",
+        "u_texture",
+        "u_alpha_cutoff",
+    ] {
+        shader.create_uniform(uniform);
+    }
+    shader
+}
@@ -0,0 +1,258 @@
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::texture::Texture;
+
+use crate::name;
+
+const PREFILTER_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_source;
+uniform float u_threshold;
+uniform float u_knee;
+
+void main() {
+    vec3 color = texture(u_source, v_uv).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+
+    // Soft threshold: a quadratic ramp of width `2 * knee` around the threshold.
+    float soft = clamp(brightness - u_threshold + u_knee, 0.0, 2.0 * u_knee);
+    soft = soft * soft / (4.0 * u_knee + 0.00001);
+    float contribution = max(soft, brightness - u_threshold) / max(brightness, 0.00001);
+
+    out_color = vec4(color * contribution, 1.0);
+}
+"#;
+
+const DOWNSAMPLE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_source;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(u_source, 0));
+    vec3 color = texture(u_source, v_uv + texel * vec2(-1.0, -1.0)).rgb
+        + texture(u_source, v_uv + texel * vec2(1.0, -1.0)).rgb
+        + texture(u_source, v_uv + texel * vec2(-1.0, 1.0)).rgb
+        + texture(u_source, v_uv + texel * vec2(1.0, 1.0)).rgb;
+    out_color = vec4(color * 0.25, 1.0);
+}
+"#;
+
+const UPSAMPLE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_source;
+uniform float u_intensity;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(u_source, 0));
+    vec3 color = texture(u_source, v_uv).rgb * 4.0;
+    color += texture(u_source, v_uv + texel * vec2(-1.0, 0.0)).rgb * 2.0;
+    color += texture(u_source, v_uv + texel * vec2(1.0, 0.0)).rgb * 2.0;
+    color += texture(u_source, v_uv + texel * vec2(0.0, -1.0)).rgb * 2.0;
+    color += texture(u_source, v_uv + texel * vec2(0.0, 1.0)).rgb * 2.0;
+    color += texture(u_source, v_uv + texel * vec2(-1.0, -1.0)).rgb;
+    color += texture(u_source, v_uv + texel * vec2(1.0, -1.0)).rgb;
+    color += texture(u_source, v_uv + texel * vec2(-1.0, 1.0)).rgb;
+    color += texture(u_source, v_uv + texel * vec2(1.0, 1.0)).rgb;
+    out_color = vec4(color / 16.0 * u_intensity, 1.0);
+}
+"#;
+
+/// Tweakable parameters of the [`Bloom`] pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BloomSettings {
+    /// Linear HDR brightness above which pixels start to bloom.
+    pub threshold: f32,
+    /// Width of the soft transition around the threshold.
+    pub knee: f32,
+    /// Strength of the bloom added back to the scene.
+    pub intensity: f32,
+    /// Number of downsampled levels; more levels spread the glow wider.
+    pub levels: u32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.8,
+            levels: 6,
+        }
+    }
+}
+
+/// # Bloom
+///
+/// HDR bloom: pixels brighter than the threshold, such as emissive materials
+/// with an intensity above 1, are extracted, blurred through a chain of
+/// downsampled levels, and added back onto the scene. The scene must be
+/// rendered into a floating-point framebuffer so bright values survive.
+///
+/// ## Example
+/// ```ignore
+/// let mut scene = Framebuffer::with_format(width, height, gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT);
+/// let mut bloom = Bloom::new(BloomSettings::default());
+///
+/// // after drawing the scene into `scene`:
+/// bloom.render(&scene);
+/// bloom.apply(&scene);
+/// ```
+pub struct Bloom {
+    pub settings: BloomSettings,
+    levels: Vec<Framebuffer>,
+    prefilter_shader: ShaderProgram,
+    downsample_shader: ShaderProgram,
+    upsample_shader: ShaderProgram,
+    triangle: Primitive,
+}
+
+impl Bloom {
+    /// Creates a bloom pass.
+    pub fn new(settings: BloomSettings) -> Self {
+        let mut prefilter_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, PREFILTER_FRAGMENT_SHADER);
+        for uniform in ["u_source", "u_threshold", "u_knee"] {
+            prefilter_shader.create_uniform(uniform);
+        }
+        let mut downsample_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, DOWNSAMPLE_FRAGMENT_SHADER);
+        downsample_shader.create_uniform("u_source");
+        let mut upsample_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, UPSAMPLE_FRAGMENT_SHADER);
+        for uniform in ["u_source", "u_intensity"] {
+            upsample_shader.create_uniform(uniform);
+        }
+
+        Self {
+            settings,
+            levels: Vec::new(),
+            prefilter_shader,
+            downsample_shader,
+            upsample_shader,
+            triangle: Primitive::fullscreen_triangle(),
+        }
+    }
+
+    /// Returns the blurred bloom from the last [`Bloom::render`], at half the scene's resolution.
+    pub fn texture(&self) -> Option<&Texture> {
+        self.levels.first().map(|level| level.color_texture())
+    }
+
+    /// Creates or resizes the level chain for a scene size.
+    fn prepare_levels(&mut self, size: (u32, u32)) {
+        let mut sizes = Vec::new();
+        let (mut width, mut height) = (size.0 / 2, size.1 / 2);
+        while sizes.len() < self.settings.levels.max(1) as usize && width >= 2 && height >= 2 {
+            sizes.push((width, height));
+            width /= 2;
+            height /= 2;
+        }
+
+        self.levels.truncate(sizes.len());
+        for (index, (width, height)) in sizes.into_iter().enumerate() {
+            match self.levels.get_mut(index) {
+                Some(level) => level.resize(width, height),
+                None => {
+                    let level = Framebuffer::with_format(
+                        width,
+                        height,
+                        gl::RGBA16F,
+                        gl::RGBA,
+                        gl::HALF_FLOAT,
+                    );
+                    self.levels.push(level);
+                }
+            }
+        }
+    }
+
+    fn draw_into(&self, target: &Framebuffer, source: &Texture) {
+        let (width, height) = target.size();
+        target.bind();
+        source.bind(0);
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+        self.triangle.draw();
+    }
+
+    /// Extracts and blurs the bright parts of an HDR scene.
+    pub fn render(&mut self, scene: &Framebuffer) {
+        self.prepare_levels(scene.size());
+        if self.levels.is_empty() {
+            return;
+        }
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        self.prefilter_shader.bind();
+        self.prefilter_shader.set_1i_uniform(name!("u_source"), 0);
+        self.prefilter_shader
+            .set_1f_uniform(name!("u_threshold"), self.settings.threshold);
+        self.prefilter_shader
+            .set_1f_uniform(name!("u_knee"), self.settings.knee.max(0.0001));
+        self.draw_into(&self.levels[0], scene.color_texture());
+
+        self.downsample_shader.bind();
+        self.downsample_shader.set_1i_uniform(name!("u_source"), 0);
+        for index in 1..self.levels.len() {
+            self.draw_into(&self.levels[index], self.levels[index - 1].color_texture());
+        }
+
+        self.upsample_shader.bind();
+        self.upsample_shader.set_1i_uniform(name!("u_source"), 0);
+        self.upsample_shader
+            .set_1f_uniform(name!("u_intensity"), 1.0);
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+        for index in (1..self.levels.len()).rev() {
+            self.draw_into(&self.levels[index - 1], self.levels[index].color_texture());
+        }
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+
+    /// Adds the bloom from the last [`Bloom::render`] onto `target`.
+    pub fn apply(&self, target: &Framebuffer) {
+        let Some(bloom) = self.texture() else {
+            return;
+        };
+
+        self.upsample_shader.bind();
+        self.upsample_shader.set_1i_uniform(name!("u_source"), 0);
+        self.upsample_shader
+            .set_1f_uniform(name!("u_intensity"), self.settings.intensity);
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+        self.draw_into(target, bloom);
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+}
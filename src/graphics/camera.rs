@@ -0,0 +1,284 @@
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::fog::Fog;
+use super::framebuffer::Framebuffer;
+use super::render_layers::RenderLayers;
+use super::tonemap::ToneMapping;
+use crate::math::*;
+use crate::scene::Transform;
+
+/// # Projection
+///
+/// How a camera maps view space onto its viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Perspective projection with a vertical field of view.
+    Perspective { fovy: Deg<f32>, near: f32, far: f32 },
+    /// Orthographic projection showing `height` world units vertically.
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+impl Projection {
+    /// Returns the projection matrix for a viewport aspect ratio.
+    pub fn matrix(&self, aspect: f32) -> Mat4 {
+        match *self {
+            Projection::Perspective { fovy, near, far } => {
+                projection::perspective(fovy, aspect, near, far)
+            }
+            Projection::Orthographic { height, near, far } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * aspect;
+                projection::orthographic(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+        }
+    }
+}
+
+/// # Clear Mode
+///
+/// What a camera clears inside its viewport before drawing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearMode {
+    /// Clears color and depth.
+    Color(Color),
+    /// Clears depth only, drawing over whatever earlier cameras rendered.
+    DepthOnly,
+    /// Clears nothing.
+    None,
+}
+
+/// # Render Target
+///
+/// Where a camera draws.
+#[derive(Clone, Default)]
+pub enum RenderTarget {
+    /// The window's default framebuffer.
+    #[default]
+    Window,
+    /// An offscreen framebuffer, for minimaps, mirrors, and post-processing.
+    Framebuffer(Arc<Framebuffer>),
+}
+
+impl RenderTarget {
+    /// Binds the target for drawing.
+    pub fn bind(&self) {
+        match self {
+            RenderTarget::Window => Framebuffer::unbind(),
+            RenderTarget::Framebuffer(framebuffer) => framebuffer.bind(),
+        }
+    }
+
+    /// Returns the size of the target, given the window's framebuffer size.
+    pub fn size(&self, window_size: (u32, u32)) -> (u32, u32) {
+        match self {
+            RenderTarget::Window => window_size,
+            RenderTarget::Framebuffer(framebuffer) => framebuffer.size(),
+        }
+    }
+}
+
+/// # Camera
+///
+/// A camera component. Cameras render in ascending `order`, each into a
+/// normalized `viewport` rectangle of its target (`(0, 0, 1, 1)` covers the
+/// whole target), so a main 3D camera, a UI camera, and a minimap camera can
+/// share a frame. A camera only draws entities on one of its `layers`.
+///
+/// ## Example
+/// ```ignore
+/// let main = Camera::perspective(Deg(60.0), 0.1, 1000.0);
+/// let minimap = Camera::orthographic(100.0, 0.1, 500.0)
+///     .with_viewport(Rect::new(0.75, 0.75, 0.25, 0.25))
+///     .with_order(1)
+///     .with_clear(ClearMode::DepthOnly);
+/// ```
+#[derive(Clone)]
+pub struct Camera {
+    pub projection: Projection,
+    pub viewport: Rect,
+    pub order: i32,
+    pub clear: ClearMode,
+    pub layers: RenderLayers,
+    pub target: RenderTarget,
+    pub fog: Option<Fog>,
+    /// How the HDR render is resolved for display, for cameras drawing into
+    /// an HDR framebuffer.
+    pub tone_mapping: Option<ToneMapping>,
+    pub active: bool,
+}
+
+impl Camera {
+    /// Creates a perspective camera covering the whole window.
+    pub fn perspective(fovy: Deg<f32>, near: f32, far: f32) -> Self {
+        Self::new(Projection::Perspective { fovy, near, far })
+    }
+
+    /// Creates an orthographic camera covering the whole window.
+    pub fn orthographic(height: f32, near: f32, far: f32) -> Self {
+        Self::new(Projection::Orthographic { height, near, far })
+    }
+
+    /// Creates a camera with the given projection covering the whole window.
+    pub fn new(projection: Projection) -> Self {
+        Self {
+            projection,
+            viewport: Rect::new(0.0, 0.0, 1.0, 1.0),
+            order: 0,
+            clear: ClearMode::Color(Color::rgb(0.2, 0.3, 0.3)),
+            layers: RenderLayers::all(),
+            target: RenderTarget::Window,
+            fog: None,
+            tone_mapping: None,
+            active: true,
+        }
+    }
+
+    /// Sets the normalized viewport rectangle.
+    pub fn with_viewport(mut self, viewport: Rect) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Sets the render order; lower orders render first.
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets what the camera clears before drawing.
+    pub fn with_clear(mut self, clear: ClearMode) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    /// Sets the layers this camera renders.
+    pub fn with_layers(mut self, layers: RenderLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Sets where the camera draws.
+    pub fn with_target(mut self, target: RenderTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Sets the fog applied to everything this camera renders.
+    pub fn with_fog(mut self, fog: Fog) -> Self {
+        self.fog = Some(fog);
+        self
+    }
+
+    /// Sets the exposure and tonemapper used to resolve this camera's HDR render.
+    pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+        self.tone_mapping = Some(tone_mapping);
+        self
+    }
+
+    /// Returns the viewport in pixels as `(x, y, width, height)` for a target size.
+    pub fn pixel_viewport(&self, target_size: (u32, u32)) -> (i32, i32, i32, i32) {
+        let (width, height) = (target_size.0 as f32, target_size.1 as f32);
+        let x = (self.viewport.x * width).round() as i32;
+        let y = (self.viewport.y * height).round() as i32;
+        let right = ((self.viewport.x + self.viewport.width) * width).round() as i32;
+        let top = ((self.viewport.y + self.viewport.height) * height).round() as i32;
+        (x, y, (right - x).max(1), (top - y).max(1))
+    }
+
+    /// Returns the view matrix for a camera placed at `transform`.
+    pub fn view_matrix(transform: &Transform) -> Mat4 {
+        let rotation = Mat4::from(transform.rotation.invert());
+        rotation * Mat4::from_translation(-transform.translation)
+    }
+}
+
+/// # Camera View
+///
+/// Everything a draw callback needs to render from one camera.
+pub struct CameraView<'a> {
+    pub camera: &'a Camera,
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub view_projection: Mat4,
+    pub position: Vec3,
+    pub viewport: (i32, i32, i32, i32),
+    pub target_size: (u32, u32),
+}
+
+impl<'a> CameraView<'a> {
+    /// Creates the view for a camera at a transform rendering into a target of the given size.
+    pub fn new(camera: &'a Camera, transform: &Transform, target_size: (u32, u32)) -> Self {
+        let viewport = camera.pixel_viewport(target_size);
+        let aspect = viewport.2 as f32 / viewport.3 as f32;
+        let view = Camera::view_matrix(transform);
+        let projection = camera.projection.matrix(aspect);
+        Self {
+            camera,
+            view,
+            projection,
+            view_projection: projection * view,
+            position: transform.translation,
+            viewport,
+            target_size,
+        }
+    }
+
+    /// Projects a world position to target pixels with the origin at the top-left,
+    /// as used by [`SpriteBatch`](super::sprite_batch::SpriteBatch). Returns `None`
+    /// for points behind the camera.
+    pub fn world_to_screen(&self, point: Vec3) -> Option<Vec2> {
+        let clip = self.view_projection * point.extend(1.0);
+        if clip.w <= 1e-6 {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+        let (x, y, width, height) = self.viewport;
+        let gl_y = y as f32 + (ndc.y * 0.5 + 0.5) * height as f32;
+        Some(vec2(
+            x as f32 + (ndc.x * 0.5 + 0.5) * width as f32,
+            self.target_size.1 as f32 - gl_y,
+        ))
+    }
+
+    /// Checks if an entity with the given layers should be drawn by this camera.
+    /// Pass `None` for entities without a `RenderLayers` component.
+    pub fn is_visible(&self, layers: Option<&RenderLayers>) -> bool {
+        self.camera
+            .layers
+            .intersects(&layers.copied().unwrap_or_default())
+    }
+
+    /// Binds the camera's target, sets the viewport, and clears it.
+    pub(crate) fn prepare(&self) {
+        let (x, y, width, height) = self.viewport;
+        self.camera.target.bind();
+        unsafe {
+            gl::Viewport(x, y, width, height);
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(x, y, width, height);
+            let mask: GLbitfield = match self.camera.clear {
+                ClearMode::Color(color) => {
+                    let color = color.to_linear();
+                    gl::ClearColor(color.r, color.g, color.b, color.a);
+                    gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT
+                }
+                ClearMode::DepthOnly => gl::DEPTH_BUFFER_BIT,
+                ClearMode::None => 0,
+            };
+            if mask != 0 {
+                gl::Clear(mask);
+            }
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+    }
+}
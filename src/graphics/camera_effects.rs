@@ -0,0 +1,431 @@
+use super::camera::{Camera, Projection};
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+use crate::math::*;
+use crate::scene::Transform;
+
+/// Moves `current` towards `target`, closing about 63% of the gap every
+/// `smoothing` seconds regardless of frame rate. Zero smoothing snaps.
+fn damp(current: Vec3, target: Vec3, smoothing: f32, delta_time: f32) -> Vec3 {
+    if smoothing <= 0.0 {
+        return target;
+    }
+    current + (target - current) * (1.0 - (-delta_time / smoothing).exp())
+}
+
+/// Returns how far `offset` reaches past `half_extent` on either side.
+fn beyond(offset: f32, half_extent: f32) -> f32 {
+    if offset > half_extent {
+        offset - half_extent
+    } else if offset < -half_extent {
+        offset + half_extent
+    } else {
+        0.0
+    }
+}
+
+/// # Camera Shake
+///
+/// Trauma-based shake. Hits add trauma between zero and one, which wears off
+/// over time; the shake grows with trauma squared, so small hits barely move
+/// the camera while big ones stack into a violent shake. Movement follows
+/// smooth noise rather than random jumps.
+#[derive(Clone, Debug)]
+pub struct CameraShake {
+    trauma: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// Largest offset along each local axis, in world units.
+    pub max_offset: Vec3,
+    /// Largest pitch, yaw, and roll, in degrees.
+    pub max_angles: Vec3,
+    /// How many times per second the shake changes direction, roughly.
+    pub frequency: f32,
+    noise: Noise,
+    time: f32,
+}
+
+impl CameraShake {
+    /// Creates a shake that rolls a few degrees and barely moves, which suits
+    /// both 3D and 2D cameras.
+    pub fn new() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.0,
+            max_offset: vec3(0.3, 0.3, 0.0),
+            max_angles: vec3(2.0, 2.0, 5.0),
+            frequency: 15.0,
+            noise: Noise::new(0x5eed),
+            time: 0.0,
+        }
+    }
+
+    /// Sets the largest offset along each local axis.
+    pub fn with_max_offset(mut self, max_offset: Vec3) -> Self {
+        self.max_offset = max_offset;
+        self
+    }
+
+    /// Sets the largest pitch, yaw, and roll in degrees.
+    pub fn with_max_angles(mut self, max_angles: Vec3) -> Self {
+        self.max_angles = max_angles;
+        self
+    }
+
+    /// Sets the trauma lost per second.
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Sets how fast the shake moves.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Adds trauma, e.g. `0.3` for a hit and `1.0` for an explosion.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Returns the current trauma.
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Returns how strongly the camera is shaking, from zero to one.
+    pub fn intensity(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+
+    /// Advances the noise and wears off trauma.
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        self.trauma = (self.trauma - self.decay * delta_time).max(0.0);
+    }
+
+    /// Returns the local offset and rotation to apply to the camera this frame.
+    pub fn offset(&self) -> (Vec3, Quat) {
+        let intensity = self.intensity();
+        if intensity <= 0.0 {
+            return (Vec3::zero(), Quat::one());
+        }
+        let t = self.time * self.frequency;
+        let channel = |index: f32| self.noise.perlin2(t, index * 17.31) * intensity;
+
+        let offset = vec3(
+            self.max_offset.x * channel(0.0),
+            self.max_offset.y * channel(1.0),
+            self.max_offset.z * channel(2.0),
+        );
+        let rotation = Quat::from(Euler::new(
+            Deg(self.max_angles.x * channel(3.0)),
+            Deg(self.max_angles.y * channel(4.0)),
+            Deg(self.max_angles.z * channel(5.0)),
+        ));
+        (offset, rotation)
+    }
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # FOV Kick
+///
+/// A brief widening or narrowing of a perspective camera's field of view that
+/// eases back, for sprinting, dashing, or landing a heavy hit. Orthographic
+/// cameras are left alone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FovKick {
+    degrees: f32,
+    /// How quickly the kick wears off; the offset shrinks by about 63% every
+    /// `1 / recovery` seconds.
+    pub recovery: f32,
+    /// Largest total offset in degrees, so repeated kicks don't stack forever.
+    pub max_degrees: f32,
+}
+
+impl FovKick {
+    /// Creates a kick that recovers in about half a second.
+    pub fn new() -> Self {
+        Self {
+            degrees: 0.0,
+            recovery: 6.0,
+            max_degrees: 30.0,
+        }
+    }
+
+    /// Widens the field of view by some degrees, or narrows it if negative.
+    pub fn kick(&mut self, degrees: f32) {
+        self.degrees = (self.degrees + degrees).clamp(-self.max_degrees, self.max_degrees);
+    }
+
+    /// Returns the current offset in degrees.
+    pub fn degrees(&self) -> f32 {
+        self.degrees
+    }
+
+    /// Eases the offset back towards zero.
+    pub fn update(&mut self, delta_time: f32) {
+        self.degrees *= (-self.recovery * delta_time).exp();
+        if self.degrees.abs() < 1e-3 {
+            self.degrees = 0.0;
+        }
+    }
+
+    /// Returns the projection with the kick applied.
+    pub fn apply(&self, projection: Projection) -> Projection {
+        match projection {
+            Projection::Perspective { fovy, near, far } => Projection::Perspective {
+                fovy: Deg((fovy.0 + self.degrees).clamp(1.0, 179.0)),
+                near,
+                far,
+            },
+            projection => projection,
+        }
+    }
+}
+
+impl Default for FovKick {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Camera Effects
+///
+/// A component adding shake and FOV kicks on top of whatever placed the
+/// camera. Effects are applied when rendering and never written back to the
+/// [`Transform`] or [`Camera`], so any controller can keep owning those.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(camera, CameraEffects::new());
+/// schedule.add_system(camera_effects_system());
+///
+/// for damaged in world.events::<Damaged>().iter() {
+///     if let Some(effects) = world.write::<CameraEffects>().get_mut(camera) {
+///         effects.shake.add_trauma(damaged.amount / 50.0);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CameraEffects {
+    pub shake: CameraShake,
+    pub fov_kick: FovKick,
+}
+
+impl CameraEffects {
+    /// Creates effects with default settings and nothing playing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the shake settings.
+    pub fn with_shake(mut self, shake: CameraShake) -> Self {
+        self.shake = shake;
+        self
+    }
+
+    /// Sets the FOV kick settings.
+    pub fn with_fov_kick(mut self, fov_kick: FovKick) -> Self {
+        self.fov_kick = fov_kick;
+        self
+    }
+
+    /// Advances every effect.
+    pub fn update(&mut self, delta_time: f32) {
+        self.shake.update(delta_time);
+        self.fov_kick.update(delta_time);
+    }
+
+    /// Returns the camera transform with shake applied in its local space.
+    pub fn apply_transform(&self, transform: &Transform) -> Transform {
+        let (offset, rotation) = self.shake.offset();
+        Transform {
+            translation: transform.translation + transform.rotation * offset,
+            rotation: transform.rotation * rotation,
+            scale: transform.scale,
+        }
+    }
+
+    /// Returns a copy of the camera with the FOV kick applied.
+    pub fn apply_camera(&self, camera: &Camera) -> Camera {
+        let mut camera = camera.clone();
+        camera.projection = self.fov_kick.apply(camera.projection);
+        camera
+    }
+}
+
+/// # Camera Follow
+///
+/// A component that moves a camera after a target entity. The target can
+/// wander inside a dead zone box without moving the camera, the camera leads
+/// in the direction the target is moving, and movement is smoothed.
+///
+/// Works for 2D and 3D alike: for a side-scroller, keep a zero z dead zone and
+/// put the camera's distance from the scene in `offset`. Only the camera's
+/// translation is written, so it combines with controllers that rotate it.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(
+///     camera,
+///     CameraFollow::new(player)
+///         .with_offset(vec3(0.0, 2.0, 10.0))
+///         .with_dead_zone(vec3(1.5, 1.0, 0.0))
+///         .with_look_ahead(0.4, 0.5),
+/// );
+/// schedule.add_system(camera_follow_system());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraFollow {
+    pub target: Option<Entity>,
+    /// Camera position relative to the followed point.
+    pub offset: Vec3,
+    /// Half extents of the box the target can move in without moving the camera.
+    pub dead_zone: Vec3,
+    /// Seconds the camera takes to close most of the distance; zero snaps.
+    pub smoothing: f32,
+    /// Seconds of target velocity the camera leads by.
+    pub look_ahead: f32,
+    /// Seconds the look-ahead takes to settle, so it doesn't jitter.
+    pub look_ahead_smoothing: f32,
+    focus: Option<Vec3>,
+    last_target: Option<Vec3>,
+    lead: Vec3,
+}
+
+impl CameraFollow {
+    /// Creates a follow that tracks a target without dead zone or look-ahead.
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target: Some(target),
+            offset: Vec3::zero(),
+            dead_zone: Vec3::zero(),
+            smoothing: 0.15,
+            look_ahead: 0.0,
+            look_ahead_smoothing: 0.5,
+            focus: None,
+            last_target: None,
+            lead: Vec3::zero(),
+        }
+    }
+
+    /// Sets the camera position relative to the followed point.
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the dead zone half extents.
+    pub fn with_dead_zone(mut self, dead_zone: Vec3) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+
+    /// Sets the seconds the camera takes to catch up.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Sets how many seconds of velocity to lead by and how smoothly.
+    pub fn with_look_ahead(mut self, seconds: f32, smoothing: f32) -> Self {
+        self.look_ahead = seconds;
+        self.look_ahead_smoothing = smoothing;
+        self
+    }
+
+    /// Forgets the followed point so the next update snaps to the target,
+    /// e.g. after a teleport or level load.
+    pub fn reset(&mut self) {
+        self.focus = None;
+        self.last_target = None;
+        self.lead = Vec3::zero();
+    }
+
+    /// Returns where the camera should be, given where it is and where the target is.
+    pub fn update(&mut self, camera: Vec3, target: Vec3, delta_time: f32) -> Vec3 {
+        let Some(focus) = self.focus else {
+            self.focus = Some(target);
+            self.last_target = Some(target);
+            return target + self.offset;
+        };
+
+        let outside = target - focus;
+        let focus = focus
+            + vec3(
+                beyond(outside.x, self.dead_zone.x),
+                beyond(outside.y, self.dead_zone.y),
+                beyond(outside.z, self.dead_zone.z),
+            );
+        self.focus = Some(focus);
+
+        if delta_time > 0.0 {
+            let velocity = (target - self.last_target.unwrap_or(target)) / delta_time;
+            self.lead = damp(
+                self.lead,
+                velocity * self.look_ahead,
+                self.look_ahead_smoothing,
+                delta_time,
+            );
+        }
+        self.last_target = Some(target);
+
+        damp(
+            camera,
+            focus + self.lead + self.offset,
+            self.smoothing,
+            delta_time,
+        )
+    }
+}
+
+/// Returns a system that advances every [`CameraEffects`] component.
+pub fn camera_effects_system() -> impl System {
+    FunctionSystem::new(
+        "camera_effects",
+        Access::new().write::<CameraEffects>(),
+        update_camera_effects,
+    )
+}
+
+/// Advances shake and FOV kicks.
+pub fn update_camera_effects(world: &World) {
+    let delta_time = world.time().delta();
+    for (_, effects) in world.write::<CameraEffects>().iter_mut() {
+        effects.update(delta_time);
+    }
+}
+
+/// Returns a system that moves cameras with a [`CameraFollow`] after their targets.
+pub fn camera_follow_system() -> impl System {
+    FunctionSystem::new(
+        "camera_follow",
+        Access::new().write::<CameraFollow>().write::<Transform>(),
+        update_camera_follow,
+    )
+}
+
+/// Moves every following camera.
+pub fn update_camera_follow(world: &World) {
+    let delta_time = world.time().delta();
+    let mut transforms = world.write::<Transform>();
+    for (entity, follow) in world.write::<CameraFollow>().iter_mut() {
+        let Some(target) = follow
+            .target
+            .and_then(|target| transforms.get(target))
+            .map(|transform| transform.translation)
+        else {
+            continue;
+        };
+        let Some(transform) = transforms.get_mut(*entity) else {
+            continue;
+        };
+        transform.translation = follow.update(transform.translation, target, delta_time);
+    }
+}
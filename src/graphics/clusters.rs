@@ -0,0 +1,398 @@
+use super::camera::{CameraView, Projection};
+use super::gl_wrapper::ShaderProgram;
+use super::light::PointLight;
+use super::texture::Texture;
+use crate::ecs::schedule::system_jobs;
+use crate::math::*;
+use crate::name;
+
+/// GLSL helpers for clustered forward lighting.
+///
+/// Paste (or concatenate) this into a fragment shader, create the uniforms in
+/// [`CLUSTERED_LIGHTS_UNIFORMS`], and add the result to the surface's lighting.
+/// `view_depth` is the positive distance along the camera's forward axis, and
+/// `light_count` can be passed on to `nyanko_debug_view`:
+///
+/// ```glsl
+/// int light_count;
+/// color += nyanko_clustered_lighting(v_world_position, normal, v_view_depth, albedo, light_count);
+/// ```
+pub const CLUSTERED_LIGHTS_GLSL: &str = r#"
+uniform sampler2D u_light_data;
+uniform usampler2D u_light_grid;
+uniform usampler2D u_light_indices;
+uniform vec3 u_cluster_dimensions;
+uniform vec2 u_cluster_depth_range;
+uniform vec4 u_cluster_viewport;
+
+uvec2 nyanko_cluster(vec2 frag_coord, float view_depth) {
+    ivec3 dimensions = ivec3(u_cluster_dimensions);
+    vec2 uv = clamp((frag_coord - u_cluster_viewport.xy) / u_cluster_viewport.zw, 0.0, 0.9999);
+    float near = u_cluster_depth_range.x;
+    float far = u_cluster_depth_range.y;
+    int slice = int(floor(log(max(view_depth, near) / near) / log(far / near) * float(dimensions.z)));
+    slice = clamp(slice, 0, dimensions.z - 1);
+    ivec2 tile = ivec2(uv * vec2(dimensions.xy));
+    return texelFetch(u_light_grid, ivec2(tile.y * dimensions.x + tile.x, slice), 0).rg;
+}
+
+void nyanko_cluster_light(uint index, out vec3 position, out float range, out vec3 radiance) {
+    uint light = texelFetch(u_light_indices, ivec2(int(index % 4096u), int(index / 4096u)), 0).r;
+    vec4 position_range = texelFetch(u_light_data, ivec2(int(light) * 2, 0), 0);
+    position = position_range.xyz;
+    range = position_range.w;
+    radiance = texelFetch(u_light_data, ivec2(int(light) * 2 + 1, 0), 0).rgb;
+}
+
+vec3 nyanko_clustered_lighting(vec3 world_position, vec3 normal, float view_depth, vec3 albedo, out int light_count) {
+    uvec2 cluster = nyanko_cluster(gl_FragCoord.xy, view_depth);
+    light_count = int(cluster.y);
+
+    vec3 result = vec3(0.0);
+    for (uint i = 0u; i < cluster.y; ++i) {
+        vec3 position;
+        float range;
+        vec3 radiance;
+        nyanko_cluster_light(cluster.x + i, position, range, radiance);
+
+        vec3 to_light = position - world_position;
+        float distance = length(to_light);
+        float window = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+        float attenuation = window * window / (distance * distance + 1.0);
+        float diffuse = max(dot(normal, to_light / max(distance, 0.0001)), 0.0);
+        result += albedo * radiance * diffuse * attenuation;
+    }
+    return result;
+}
+"#;
+
+/// Names of the uniforms used by [`CLUSTERED_LIGHTS_GLSL`].
+pub const CLUSTERED_LIGHTS_UNIFORMS: [&str; 6] = [
+    "u_light_data",
+    "u_light_grid",
+    "u_light_indices",
+    "u_cluster_dimensions",
+    "u_cluster_depth_range",
+    "u_cluster_viewport",
+];
+
+/// Width of the light index texture; must match `CLUSTERED_LIGHTS_GLSL`.
+const INDEX_TEXTURE_WIDTH: usize = 4096;
+
+/// Most lights that fit in one row of the light data texture.
+const MAX_CLUSTERED_LIGHTS: usize = 8192;
+
+/// Cluster coordinates on the X, Y and Z axes of the grid.
+type ClusterCoord = (u32, u32, u32);
+
+/// Dimensions of the light cluster grid: screen tiles on X and Y, and
+/// logarithmic depth slices on Z.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClusterConfig {
+    pub tiles_x: u32,
+    pub tiles_y: u32,
+    pub depth_slices: u32,
+    /// Lights beyond this many in a single cluster are dropped.
+    pub max_lights_per_cluster: u32,
+}
+
+impl ClusterConfig {
+    /// Returns the total number of clusters.
+    pub fn cluster_count(&self) -> usize {
+        (self.tiles_x * self.tiles_y * self.depth_slices) as usize
+    }
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            tiles_x: 16,
+            tiles_y: 9,
+            depth_slices: 24,
+            max_lights_per_cluster: 64,
+        }
+    }
+}
+
+/// # Light Clusters
+///
+/// Clustered forward shading: the view frustum is split into a grid of
+/// clusters and every point light is assigned to the clusters its sphere
+/// touches. Shaders then only loop over the lights in their fragment's cluster,
+/// so hundreds of lights can share a scene without per-object light lists.
+///
+/// The assignment runs on the CPU, culling lights against the view in
+/// parallel on the job system, and is uploaded as three textures: light data,
+/// a per-cluster offset and count, and the flattened light indices.
+///
+/// ## Example
+/// ```ignore
+/// let mut clusters = LightClusters::new(ClusterConfig::default());
+///
+/// renderer.render_cameras(cameras, window_size, |view| {
+///     clusters.build(view, &lights);
+///     shader.bind();
+///     clusters.apply_uniforms(&shader, 4);
+///     // draw calls...
+/// });
+/// ```
+pub struct LightClusters {
+    config: ClusterConfig,
+    light_data: Texture,
+    grid: Texture,
+    indices: Texture,
+    depth_range: (f32, f32),
+    viewport: (i32, i32, i32, i32),
+    light_count: usize,
+    assigned_count: usize,
+}
+
+impl LightClusters {
+    /// Creates empty light clusters with the given grid dimensions.
+    pub fn new(config: ClusterConfig) -> Self {
+        let create = || {
+            let texture = Texture::new_2d(1, 1, gl::RGBA32F, gl::RGBA, gl::FLOAT, None);
+            texture.set_filter(gl::NEAREST, gl::NEAREST);
+            texture
+        };
+        let mut clusters = Self {
+            config,
+            light_data: create(),
+            grid: create(),
+            indices: create(),
+            depth_range: (0.1, 100.0),
+            viewport: (0, 0, 1, 1),
+            light_count: 0,
+            assigned_count: 0,
+        };
+        clusters.upload(&[], &vec![[0, 0]; config.cluster_count()], &[]);
+        clusters
+    }
+
+    /// Returns the grid dimensions.
+    pub fn config(&self) -> ClusterConfig {
+        self.config
+    }
+
+    /// Changes the grid dimensions, taking effect on the next build.
+    pub fn set_config(&mut self, config: ClusterConfig) {
+        self.config = config;
+    }
+
+    /// Returns the number of lights in the last build.
+    pub fn light_count(&self) -> usize {
+        self.light_count
+    }
+
+    /// Returns the total number of light-cluster assignments in the last build,
+    /// a measure of shading cost.
+    pub fn assigned_count(&self) -> usize {
+        self.assigned_count
+    }
+
+    /// Assigns each light, with its world position, to the clusters of `view`.
+    pub fn build(&mut self, view: &CameraView, lights: &[(PointLight, Vec3)]) {
+        let lights = if lights.len() > MAX_CLUSTERED_LIGHTS {
+            log::warn!(
+                "{} lights exceed the clustered limit of {}; the rest are ignored",
+                lights.len(),
+                MAX_CLUSTERED_LIGHTS
+            );
+            &lights[..MAX_CLUSTERED_LIGHTS]
+        } else {
+            lights
+        };
+
+        let (near, far) = match view.camera.projection {
+            Projection::Perspective { near, far, .. } => (near, far),
+            Projection::Orthographic { near, far, .. } => (near.max(0.001), far),
+        };
+        self.depth_range = (near, far);
+        self.viewport = view.viewport;
+
+        let config = self.config;
+        let bounds = system_jobs().par_map(lights, |(light, position)| {
+            self.cluster_bounds(view, *position, light.range)
+        });
+        let mut clusters: Vec<Vec<u32>> = vec![Vec::new(); config.cluster_count()];
+        for (index, bounds) in bounds.into_iter().enumerate() {
+            let Some((min, max)) = bounds else {
+                continue;
+            };
+            for z in min.2..=max.2 {
+                for y in min.1..=max.1 {
+                    for x in min.0..=max.0 {
+                        let cluster = ((z * config.tiles_y + y) * config.tiles_x + x) as usize;
+                        if clusters[cluster].len() < config.max_lights_per_cluster as usize {
+                            clusters[cluster].push(index as u32);
+                        }
+                    }
+                }
+            }
+        }
+
+        let light_data: Vec<[f32; 4]> = lights
+            .iter()
+            .flat_map(|(light, position)| {
+                let radiance = light.radiance();
+                [
+                    [position.x, position.y, position.z, light.range],
+                    [radiance.x, radiance.y, radiance.z, 1.0],
+                ]
+            })
+            .collect();
+
+        let mut grid = Vec::with_capacity(clusters.len());
+        let mut indices = Vec::new();
+        for cluster in &clusters {
+            grid.push([indices.len() as u32, cluster.len() as u32]);
+            indices.extend_from_slice(cluster);
+        }
+
+        self.light_count = lights.len();
+        self.assigned_count = indices.len();
+        self.upload(&light_data, &grid, &indices);
+    }
+
+    /// Returns the inclusive cluster coordinate range touched by a light's sphere.
+    fn cluster_bounds(
+        &self,
+        view: &CameraView,
+        position: Vec3,
+        range: f32,
+    ) -> Option<(ClusterCoord, ClusterCoord)> {
+        let config = self.config;
+        let (near, far) = self.depth_range;
+        let center = (view.view * position.extend(1.0)).truncate();
+        let depth = -center.z;
+        if depth + range < near || depth - range > far {
+            return None;
+        }
+
+        let slice = |depth: f32| {
+            let t = (depth.max(near) / near).ln() / (far / near).ln();
+            ((t * config.depth_slices as f32) as u32).min(config.depth_slices - 1)
+        };
+        let (min_z, max_z) = (slice(depth - range), slice(depth + range));
+
+        let mut min_ndc = vec2(1.0f32, 1.0);
+        let mut max_ndc = vec2(-1.0f32, -1.0);
+        for corner in Aabb::new(
+            center - vec3(range, range, range),
+            center + vec3(range, range, range),
+        )
+        .corners()
+        {
+            let clip = view.projection * corner.extend(1.0);
+            if clip.w <= 0.0001 {
+                min_ndc = vec2(-1.0, -1.0);
+                max_ndc = vec2(1.0, 1.0);
+                break;
+            }
+            let ndc = clip.truncate().truncate() / clip.w;
+            min_ndc = vec2(min_ndc.x.min(ndc.x), min_ndc.y.min(ndc.y));
+            max_ndc = vec2(max_ndc.x.max(ndc.x), max_ndc.y.max(ndc.y));
+        }
+        if min_ndc.x > 1.0 || min_ndc.y > 1.0 || max_ndc.x < -1.0 || max_ndc.y < -1.0 {
+            return None;
+        }
+
+        let tile = |ndc: f32, tiles: u32| {
+            let t = (ndc * 0.5 + 0.5).clamp(0.0, 0.9999);
+            (t * tiles as f32) as u32
+        };
+        Some((
+            (
+                tile(min_ndc.x, config.tiles_x),
+                tile(min_ndc.y, config.tiles_y),
+                min_z,
+            ),
+            (
+                tile(max_ndc.x, config.tiles_x),
+                tile(max_ndc.y, config.tiles_y),
+                max_z,
+            ),
+        ))
+    }
+
+    fn upload(&mut self, light_data: &[[f32; 4]], grid: &[[u32; 2]], indices: &[u32]) {
+        let light_bytes: Vec<u8> = light_data
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        let light_width = light_data.len().max(1) as u32;
+        let light_bytes = if light_bytes.is_empty() {
+            vec![0; 16]
+        } else {
+            light_bytes
+        };
+        self.light_data.set_data(
+            light_width,
+            1,
+            gl::RGBA32F,
+            gl::RGBA,
+            gl::FLOAT,
+            &light_bytes,
+        );
+
+        let grid_bytes: Vec<u8> = grid
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        self.grid.set_data(
+            self.config.tiles_x * self.config.tiles_y,
+            self.config.depth_slices,
+            gl::RG32UI,
+            gl::RG_INTEGER,
+            gl::UNSIGNED_INT,
+            &grid_bytes,
+        );
+
+        let rows = indices.len().div_ceil(INDEX_TEXTURE_WIDTH).max(1);
+        let mut index_bytes: Vec<u8> = indices
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        index_bytes.resize(rows * INDEX_TEXTURE_WIDTH * 4, 0);
+        self.indices.set_data(
+            INDEX_TEXTURE_WIDTH as u32,
+            rows as u32,
+            gl::R32UI,
+            gl::RED_INTEGER,
+            gl::UNSIGNED_INT,
+            &index_bytes,
+        );
+    }
+
+    /// Binds the cluster textures to three units starting at `first_unit` and sets
+    /// the uniforms on a shader that includes [`CLUSTERED_LIGHTS_GLSL`].
+    pub fn apply_uniforms(&self, shader: &ShaderProgram, first_unit: u32) {
+        self.light_data.bind(first_unit);
+        self.grid.bind(first_unit + 1);
+        self.indices.bind(first_unit + 2);
+        shader.set_1i_uniform(name!("u_light_data"), first_unit as i32);
+        shader.set_1i_uniform(name!("u_light_grid"), first_unit as i32 + 1);
+        shader.set_1i_uniform(name!("u_light_indices"), first_unit as i32 + 2);
+        shader.set_3f_uniform(
+            name!("u_cluster_dimensions"),
+            self.config.tiles_x as f32,
+            self.config.tiles_y as f32,
+            self.config.depth_slices as f32,
+        );
+        shader.set_2f_uniform(
+            name!("u_cluster_depth_range"),
+            self.depth_range.0,
+            self.depth_range.1,
+        );
+        let (x, y, width, height) = self.viewport;
+        shader.set_4f_uniform(
+            name!("u_cluster_viewport"),
+            x as f32,
+            y as f32,
+            width as f32,
+            height as f32,
+        );
+    }
+}
@@ -0,0 +1,719 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use glfw::Key;
+
+use super::font::SdfFont;
+use super::sprite_batch::SpriteBatch;
+use super::text::{TextRenderer, TextStyle};
+use crate::cvars::{parse_bool, CVar, CVars};
+use crate::ecs::World;
+use crate::input::Input;
+use crate::logger;
+use crate::math::*;
+
+/// Lines of output kept before the oldest are dropped.
+const MAX_LINES: usize = 512;
+
+/// Commands kept for recalling with the up and down arrows.
+const MAX_HISTORY: usize = 64;
+
+/// How much of the slide the console covers each second when opening or
+/// closing.
+const SLIDE_SPEED: f32 = 6.0;
+
+/// Lines scrolled by Page Up and Page Down.
+const PAGE_LINES: usize = 10;
+
+const BUILTIN_COMMANDS: [(&str, &str); 6] = [
+    ("help", "Lists commands, or describes one: help [command]"),
+    ("clear", "Clears the console"),
+    ("set", "Sets a variable: set <name> <value>"),
+    ("get", "Shows a variable: get <name>"),
+    ("reset", "Puts a variable back to its default: reset <name>"),
+    (
+        "vars",
+        "Lists variables, optionally starting with a prefix: vars [prefix]",
+    ),
+];
+
+/// Splits a command line into words at whitespace. Double quotes keep spaces
+/// inside a word, and a backslash escapes the next character.
+pub fn split_command_line(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut characters = line.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => {
+                if let Some(next) = characters.next() {
+                    word.push(next);
+                }
+                in_word = true;
+            }
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            character if character.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            character => {
+                word.push(character);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// # Command Args
+///
+/// The words after a command's name, with helpers that parse them and
+/// describe what went wrong in a message fit for the console.
+///
+/// ## Example
+/// ```ignore
+/// // spawn cube 0 1 0
+/// let kind = args.get(0).ok_or("usage: spawn <kind> <x> <y> <z>")?;
+/// let position = args.vec3(1)?;
+/// let count = args.parse_or(4, 1)?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandArgs {
+    words: Vec<String>,
+}
+
+impl CommandArgs {
+    /// Creates arguments from words already split.
+    pub fn new(words: Vec<String>) -> Self {
+        Self { words }
+    }
+
+    /// Returns the number of arguments.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Checks if there are no arguments.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns an argument, counting from 0.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.words.get(index).map(String::as_str)
+    }
+
+    /// Returns every argument.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Parses a required argument.
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, String> {
+        let word = self
+            .get(index)
+            .ok_or_else(|| format!("missing argument {}", index + 1))?;
+        word.parse()
+            .map_err(|_| format!("argument {} has an invalid value '{}'", index + 1, word))
+    }
+
+    /// Parses an optional argument, using `default` when it's missing.
+    pub fn parse_or<T: FromStr>(&self, index: usize, default: T) -> Result<T, String> {
+        if index < self.len() {
+            self.parse(index)
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// Parses a bool argument, see [`parse_bool`].
+    pub fn bool(&self, index: usize) -> Result<bool, String> {
+        let word = self
+            .get(index)
+            .ok_or_else(|| format!("missing argument {}", index + 1))?;
+        parse_bool(word)
+            .ok_or_else(|| format!("argument {} should be 1 or 0, got '{}'", index + 1, word))
+    }
+
+    /// Parses three arguments starting at `index` as a vector.
+    pub fn vec3(&self, index: usize) -> Result<Vec3, String> {
+        Ok(vec3(
+            self.parse(index)?,
+            self.parse(index + 1)?,
+            self.parse(index + 2)?,
+        ))
+    }
+
+    /// Joins the arguments from `index` on with spaces, for commands taking
+    /// free text.
+    pub fn rest(&self, index: usize) -> String {
+        self.words.get(index..).unwrap_or_default().join(" ")
+    }
+}
+
+/// What a command prints: text on success, an error message otherwise.
+pub type CommandResult = Result<String, String>;
+
+type CommandHandler = Box<dyn FnMut(&mut World, &CommandArgs) -> CommandResult>;
+
+struct Command {
+    help: String,
+    handler: CommandHandler,
+}
+
+/// How the console looks. Sizes are in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConsoleStyle {
+    pub font_size: f32,
+    /// Fraction of the screen height the open console covers.
+    pub height: f32,
+    pub padding: f32,
+    pub background: Color,
+    pub input_background: Color,
+    pub text: Color,
+    pub input: Color,
+    pub warning: Color,
+    pub error: Color,
+}
+
+impl Default for ConsoleStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 16.0,
+            height: 0.45,
+            padding: 8.0,
+            background: Color::new(0.05, 0.05, 0.08, 0.88),
+            input_background: Color::new(0.12, 0.12, 0.16, 0.95),
+            text: Color::new(0.85, 0.85, 0.85, 1.0),
+            input: Color::WHITE,
+            warning: Color::new(1.0, 0.8, 0.3, 1.0),
+            error: Color::new(1.0, 0.4, 0.35, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LineKind {
+    Output,
+    Input,
+    Warning,
+    Error,
+}
+
+/// # Console
+///
+/// A drop-down developer console, toggled with the backtick key. It shows the
+/// log kept by [`logger::init`](crate::logger::init) along with command
+/// output, and runs commands typed into it. Games register their own commands,
+/// which get the world to act on; the built-in `help`, `clear`, `set`, `get`,
+/// `reset` and `vars` list commands and edit [`CVars`].
+///
+/// While open, the console takes the keyboard: skip gameplay input when
+/// [`Console::update`] returns `true`.
+///
+/// The up and down arrows recall earlier commands, Tab completes command and
+/// variable names, and Page Up and Page Down scroll the output.
+///
+/// ## Example
+/// ```ignore
+/// let mut console = Console::new();
+/// console.register_command("spawn", "Spawns a prefab: spawn <kind> <x> <y> <z>", |world, args| {
+///     let kind = args.get(0).ok_or("usage: spawn <kind> <x> <y> <z>")?;
+///     let position = args.vec3(1)?;
+///     let entity = prefabs.spawn(world, kind, position).map_err(|error| error.to_string())?;
+///     Ok(format!("spawned {:?}", entity))
+/// });
+///
+/// // each frame:
+/// let typing = console.update(window.input(), &mut world, &mut cvars);
+/// window.apply_cvars(&cvars);
+/// world.time_mut().apply_cvars(&cvars);
+/// if !typing {
+///     player_controller.update(window.input());
+/// }
+/// // ... draw the game ...
+/// console.draw(&mut batch, &mut text, &font, window.framebuffer_size());
+/// ```
+pub struct Console {
+    commands: BTreeMap<String, Command>,
+    lines: VecDeque<(LineKind, String)>,
+    logged: u64,
+    input: String,
+    caret: usize,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    scroll: usize,
+    open: bool,
+    slide: f32,
+    blink: f32,
+    style: ConsoleStyle,
+}
+
+impl Console {
+    /// Creates a closed console.
+    pub fn new() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+            lines: VecDeque::new(),
+            logged: 0,
+            input: String::new(),
+            caret: 0,
+            history: Vec::new(),
+            history_index: None,
+            scroll: 0,
+            open: false,
+            slide: 0.0,
+            blink: 0.0,
+            style: ConsoleStyle::default(),
+        }
+    }
+
+    /// Sets how the console looks.
+    pub fn with_style(mut self, style: ConsoleStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Checks if the console is open or opening.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens or closes the console.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Adds a command, replacing any with the same name. The handler gets the
+    /// world and the words after the name; what it returns is printed.
+    pub fn register_command<F>(&mut self, name: &str, help: &str, handler: F)
+    where
+        F: FnMut(&mut World, &CommandArgs) -> CommandResult + 'static,
+    {
+        self.commands.insert(
+            name.to_string(),
+            Command {
+                help: help.to_string(),
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    /// Prints a line of output.
+    pub fn print(&mut self, text: &str) {
+        self.push_lines(LineKind::Output, text);
+    }
+
+    /// Prints a line of output in the error color.
+    pub fn print_error(&mut self, text: &str) {
+        self.push_lines(LineKind::Error, text);
+    }
+
+    /// Clears the output.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.scroll = 0;
+    }
+
+    /// Runs a command line as if typed, printing what it returns.
+    pub fn execute(&mut self, line: &str, world: &mut World, cvars: &mut CVars) {
+        let mut words = split_command_line(line);
+        if words.is_empty() {
+            return;
+        }
+        let name = words.remove(0);
+        let args = CommandArgs::new(words);
+        let result = match self.run_builtin(&name, &args, cvars) {
+            Some(result) => result,
+            None => match self.commands.get_mut(&name) {
+                Some(command) => (command.handler)(world, &args),
+                None => Err(format!("unknown command '{}', try 'help'", name)),
+            },
+        };
+        match result {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => self.print(&output),
+            Err(error) => self.print_error(&error),
+        }
+    }
+
+    fn run_builtin(
+        &mut self,
+        name: &str,
+        args: &CommandArgs,
+        cvars: &mut CVars,
+    ) -> Option<CommandResult> {
+        let value_of = |cvars: &CVars, var: &str| {
+            cvars
+                .value(var)
+                .map(|value| format!("{} = {}", var, value))
+                .unwrap_or_default()
+        };
+        let result = match name {
+            "help" => Ok(self.help(args.get(0))),
+            "clear" => {
+                self.clear();
+                Ok(String::new())
+            }
+            "set" => match args.get(0) {
+                Some(var) if args.len() >= 2 => cvars
+                    .set_from_str(var, &args.rest(1))
+                    .map(|()| value_of(cvars, var))
+                    .map_err(|error| error.to_string()),
+                _ => Err("usage: set <name> <value>".to_string()),
+            },
+            "reset" => match args.get(0) {
+                Some(var) => cvars
+                    .reset(var)
+                    .map(|()| value_of(cvars, var))
+                    .map_err(|error| error.to_string()),
+                None => Err("usage: reset <name>".to_string()),
+            },
+            "get" => match args.get(0).map(|var| (var, cvars.get(var))) {
+                Some((_, Some(cvar))) => {
+                    let range = cvar
+                        .range()
+                        .map(|(min, max)| format!(", {} to {}", min, max))
+                        .unwrap_or_default();
+                    Ok(format!(
+                        "{} = {} ({}, default {}{}) - {}",
+                        cvar.name(),
+                        cvar.value(),
+                        cvar.value().type_name(),
+                        cvar.default_value(),
+                        range,
+                        cvar.help()
+                    ))
+                }
+                Some((var, None)) => Err(format!("unknown variable '{}'", var)),
+                None => Err("usage: get <name>".to_string()),
+            },
+            "vars" => {
+                let prefix = args.get(0).unwrap_or("");
+                let listed: Vec<String> = cvars
+                    .iter()
+                    .filter(|cvar| cvar.name().starts_with(prefix))
+                    .map(|cvar| format!("{} = {}", cvar.name(), cvar.value()))
+                    .collect();
+                Ok(listed.join("\n"))
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn help(&self, command: Option<&str>) -> String {
+        let builtin = BUILTIN_COMMANDS.iter().map(|(name, help)| (*name, *help));
+        let registered = self
+            .commands
+            .iter()
+            .map(|(name, command)| (name.as_str(), command.help.as_str()));
+        let mut all: Vec<(&str, &str)> = builtin.chain(registered).collect();
+        all.sort_by_key(|(name, _)| *name);
+        match command {
+            Some(command) => all.iter().find(|(name, _)| *name == command).map_or_else(
+                || format!("unknown command '{}'", command),
+                |(name, help)| format!("{} - {}", name, help),
+            ),
+            None => all
+                .iter()
+                .map(|(name, help)| format!("{} - {}", name, help))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Toggles the console, collects new log lines and, while open, handles
+    /// typing. Returns `true` while the console has the keyboard.
+    pub fn update(&mut self, input: &Input, world: &mut World, cvars: &mut CVars) -> bool {
+        let delta_time = world.time().unscaled_delta();
+        self.blink = (self.blink + delta_time) % 1.0;
+        self.collect_log();
+
+        let toggled = input.key_pressed(Key::GraveAccent);
+        if toggled {
+            self.open = !self.open;
+        }
+        let target = if self.open { 1.0 } else { 0.0 };
+        let step = SLIDE_SPEED * delta_time;
+        self.slide += (target - self.slide).clamp(-step, step);
+        if !self.open || toggled {
+            return toggled;
+        }
+
+        for character in input.text().chars() {
+            if character != '`' && !character.is_control() {
+                self.insert(character);
+            }
+        }
+        if input.key_pressed(Key::Backspace) && self.caret > 0 {
+            self.caret -= 1;
+            self.remove_at_caret();
+        }
+        if input.key_pressed(Key::Delete) {
+            self.remove_at_caret();
+        }
+        if input.key_pressed(Key::Left) {
+            self.caret = self.caret.saturating_sub(1);
+            self.blink = 0.0;
+        }
+        if input.key_pressed(Key::Right) {
+            self.caret = (self.caret + 1).min(self.input.chars().count());
+            self.blink = 0.0;
+        }
+        if input.key_pressed(Key::Home) {
+            self.caret = 0;
+        }
+        if input.key_pressed(Key::End) {
+            self.caret = self.input.chars().count();
+        }
+        if input.key_pressed(Key::Up) {
+            self.recall(true);
+        }
+        if input.key_pressed(Key::Down) {
+            self.recall(false);
+        }
+        if input.key_pressed(Key::Tab) {
+            self.complete(cvars);
+        }
+        if input.key_pressed(Key::PageUp) {
+            self.scroll = (self.scroll + PAGE_LINES).min(self.lines.len().saturating_sub(1));
+        }
+        if input.key_pressed(Key::PageDown) {
+            self.scroll = self.scroll.saturating_sub(PAGE_LINES);
+        }
+        if input.key_pressed(Key::Escape) {
+            self.open = false;
+        }
+        if input.key_pressed(Key::Enter) || input.key_pressed(Key::KpEnter) {
+            self.submit(world, cvars);
+        }
+        true
+    }
+
+    fn collect_log(&mut self) {
+        let (lines, logged) = logger::recent_lines_since(self.logged);
+        self.logged = logged;
+        for line in lines {
+            let kind = if line.contains(" ERROR ") {
+                LineKind::Error
+            } else if line.contains(" WARN ") {
+                LineKind::Warning
+            } else {
+                LineKind::Output
+            };
+            self.push_lines(kind, &line);
+        }
+    }
+
+    fn push_lines(&mut self, kind: LineKind, text: &str) {
+        for line in text.lines() {
+            if self.lines.len() == MAX_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back((kind, line.to_string()));
+            // Keep scrolled-back output still while new lines arrive.
+            if self.scroll > 0 {
+                self.scroll = (self.scroll + 1).min(self.lines.len() - 1);
+            }
+        }
+    }
+
+    fn byte_index(&self, caret: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(caret)
+            .map_or(self.input.len(), |(index, _)| index)
+    }
+
+    fn insert(&mut self, character: char) {
+        let index = self.byte_index(self.caret);
+        self.input.insert(index, character);
+        self.caret += 1;
+        self.blink = 0.0;
+    }
+
+    fn remove_at_caret(&mut self) {
+        if self.caret < self.input.chars().count() {
+            let index = self.byte_index(self.caret);
+            self.input.remove(index);
+        }
+        self.blink = 0.0;
+    }
+
+    fn set_input(&mut self, text: &str) {
+        self.input = text.to_string();
+        self.caret = self.input.chars().count();
+    }
+
+    fn submit(&mut self, world: &mut World, cvars: &mut CVars) {
+        let line = std::mem::take(&mut self.input);
+        self.caret = 0;
+        self.history_index = None;
+        self.scroll = 0;
+        self.push_lines(LineKind::Input, &format!("> {}", line));
+        if line.trim().is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&line) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(line.clone());
+        }
+        self.execute(&line, world, cvars);
+    }
+
+    fn recall(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        self.history_index = index;
+        let text = index.map_or_else(String::new, |index| self.history[index].clone());
+        self.set_input(&text);
+    }
+
+    fn complete(&mut self, cvars: &CVars) {
+        // Completes the command name, or the variable after `set`, `get` and
+        // `reset`.
+        let (prefix, partial) = match self.input.split_once(' ') {
+            Some((command, rest))
+                if matches!(command, "set" | "get" | "reset") && !rest.contains(' ') =>
+            {
+                (format!("{} ", command), rest.to_string())
+            }
+            Some(_) => return,
+            None => (String::new(), self.input.clone()),
+        };
+        let candidates: Vec<String> = if prefix.is_empty() {
+            BUILTIN_COMMANDS
+                .iter()
+                .map(|(name, _)| *name)
+                .chain(self.commands.keys().map(String::as_str))
+                .filter(|name| name.starts_with(&partial))
+                .map(str::to_string)
+                .collect()
+        } else {
+            cvars
+                .iter()
+                .map(CVar::name)
+                .filter(|name| name.starts_with(&partial))
+                .map(str::to_string)
+                .collect()
+        };
+        let Some(first) = candidates.first() else {
+            return;
+        };
+        let common = candidates.iter().fold(first.clone(), |common, name| {
+            common
+                .chars()
+                .zip(name.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        });
+        if candidates.len() == 1 {
+            self.set_input(&format!("{}{} ", prefix, common));
+        } else {
+            if common.len() > partial.len() {
+                self.set_input(&format!("{}{}", prefix, common));
+            }
+            let listed = candidates.join("  ");
+            self.print(&listed);
+        }
+    }
+
+    fn line_height(&self) -> f32 {
+        self.style.font_size * 1.25
+    }
+
+    /// Draws the console over everything else on a target of the given pixel
+    /// size, sliding down from the top edge.
+    pub fn draw(
+        &self,
+        batch: &mut SpriteBatch,
+        text: &mut TextRenderer,
+        font: &Arc<SdfFont>,
+        target_size: (u32, u32),
+    ) {
+        if self.slide <= 0.0 {
+            return;
+        }
+        let style = &self.style;
+        let screen = vec2(target_size.0 as f32, target_size.1 as f32) / batch.ui_scale();
+        let eased = 1.0 - (1.0 - self.slide).powi(3);
+        let height = screen.y * style.height;
+        let top = -(1.0 - eased) * height;
+        let line_height = self.line_height();
+        let input_y = top + height - style.padding - line_height;
+        let prompt = format!("> {}", self.input);
+        let before_caret: String = prompt.chars().take(self.caret + 2).collect();
+        let caret_x = style.padding + font.measure(&before_caret, style.font_size).x;
+
+        batch.begin(target_size);
+        batch.fill_rect(Rect::new(0.0, top, screen.x, height), style.background);
+        batch.fill_rect(
+            Rect::new(
+                0.0,
+                input_y - style.padding * 0.5,
+                screen.x,
+                line_height + style.padding,
+            ),
+            style.input_background,
+        );
+        if self.blink < 0.5 {
+            batch.fill_rect(
+                Rect::new(caret_x, input_y, 2.0, style.font_size),
+                style.input,
+            );
+        }
+        batch.end();
+
+        let text_style = |color: Color| TextStyle::new(style.font_size, color);
+        text.draw_2d(
+            font,
+            &prompt,
+            vec2(style.padding, input_y),
+            text_style(style.input),
+        );
+        let mut y = input_y - style.padding - line_height;
+        for (kind, line) in self.lines.iter().rev().skip(self.scroll) {
+            if y < top {
+                break;
+            }
+            let color = match kind {
+                LineKind::Output => style.text,
+                LineKind::Input => style.input,
+                LineKind::Warning => style.warning,
+                LineKind::Error => style.error,
+            };
+            text.draw_2d(font, line, vec2(style.padding, y), text_style(color));
+            y -= line_height;
+        }
+        text.flush_2d(target_size);
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
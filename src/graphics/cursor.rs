@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use super::image::{rgba8_to_glfw, Image};
+use super::texture::Texture;
+use crate::math::*;
+
+/// Cursor shapes provided by the operating system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SystemCursor {
+    #[default]
+    Arrow,
+    /// The text cursor, for text fields.
+    IBeam,
+    Crosshair,
+    /// The pointing hand, for buttons and links.
+    Hand,
+    HorizontalResize,
+    VerticalResize,
+}
+
+impl SystemCursor {
+    pub(crate) fn to_glfw(self) -> glfw::StandardCursor {
+        match self {
+            SystemCursor::Arrow => glfw::StandardCursor::Arrow,
+            SystemCursor::IBeam => glfw::StandardCursor::IBeam,
+            SystemCursor::Crosshair => glfw::StandardCursor::Crosshair,
+            SystemCursor::Hand => glfw::StandardCursor::Hand,
+            SystemCursor::HorizontalResize => glfw::StandardCursor::HResize,
+            SystemCursor::VerticalResize => glfw::StandardCursor::VResize,
+        }
+    }
+}
+
+/// # Cursor Image
+///
+/// Pixels for a custom hardware cursor. The hotspot is the pixel, from the
+/// top-left, that points at things.
+///
+/// ## Example
+/// ```ignore
+/// let sword = Arc::new(CursorImage::from_texture(&sword_texture, (0, 0)));
+/// window.set_cursor(&CursorIcon::Image(sword));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CursorImage {
+    pub width: u32,
+    pub height: u32,
+    /// 8-bit RGBA pixels, top row first.
+    pub pixels: Vec<u8>,
+    pub hotspot: (u32, u32),
+}
+
+impl CursorImage {
+    /// Creates a cursor from 8-bit RGBA pixels, top row first.
+    pub fn from_rgba8(width: u32, height: u32, pixels: Vec<u8>, hotspot: (u32, u32)) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+            hotspot: (
+                hotspot.0.min(width.saturating_sub(1)),
+                hotspot.1.min(height.saturating_sub(1)),
+            ),
+        }
+    }
+
+    /// Creates a cursor from a texture by reading its pixels back from the GPU.
+    /// Cursors are usually 32 or 64 pixels square; larger ones may be refused
+    /// by the system.
+    pub fn from_texture(texture: &Texture, hotspot: (u32, u32)) -> Self {
+        Self::from_rgba8(
+            texture.width(),
+            texture.height(),
+            texture.read_rgba8(),
+            hotspot,
+        )
+    }
+
+    /// Creates a cursor from a loaded image.
+    pub fn from_image(image: &Image, hotspot: (u32, u32)) -> Self {
+        Self::from_rgba8(image.width, image.height, image.pixels.clone(), hotspot)
+    }
+
+    pub(crate) fn to_glfw(&self) -> glfw::PixelImage {
+        rgba8_to_glfw(self.width, self.height, &self.pixels)
+    }
+}
+
+/// What the mouse cursor looks like.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CursorIcon {
+    System(SystemCursor),
+    Image(Arc<CursorImage>),
+    /// Hides the cursor while it is over the window.
+    Hidden,
+}
+
+impl Default for CursorIcon {
+    fn default() -> Self {
+        CursorIcon::System(SystemCursor::Arrow)
+    }
+}
+
+impl From<SystemCursor> for CursorIcon {
+    fn from(cursor: SystemCursor) -> Self {
+        CursorIcon::System(cursor)
+    }
+}
+
+/// # Cursor Regions
+///
+/// Per-element cursor changes for UI. Each frame, elements register the screen
+/// rectangle they cover together with the cursor they want, such as a hand
+/// over buttons or a text cursor over text fields, and the window picks the
+/// cursor of the last region under the mouse. Register elements in draw order
+/// so the topmost one wins.
+///
+/// ## Example
+/// ```ignore
+/// let mut regions = CursorRegions::new();
+/// regions.push(button_rect, SystemCursor::Hand);
+/// regions.push(name_field_rect, SystemCursor::IBeam);
+///
+/// window.apply_cursor_regions(&regions);
+/// regions.clear();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CursorRegions {
+    regions: Vec<(Rect, CursorIcon)>,
+}
+
+impl CursorRegions {
+    /// Creates an empty set of regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a region in logical pixels, the coordinates UI is drawn in, origin
+    /// at the top-left.
+    pub fn push(&mut self, rect: Rect, cursor: impl Into<CursorIcon>) {
+        self.regions.push((rect, cursor.into()));
+    }
+
+    /// Returns the cursor of the topmost region containing `position`.
+    pub fn resolve(&self, position: Vec2) -> Option<&CursorIcon> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|(rect, _)| rect.contains(position))
+            .map(|(_, cursor)| cursor)
+    }
+
+    /// Removes every region, usually at the end of the frame.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Returns the number of regions.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Checks if there are no regions.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
@@ -0,0 +1,146 @@
+use std::f32::consts::TAU;
+
+use super::fog::Fog;
+use super::light::DirectionalLight;
+use super::sky::Sky;
+use crate::math::*;
+
+/// # Day/Night Cycle
+///
+/// Drives the sun across the sky over a configurable day length. Each update
+/// it can point a [`DirectionalLight`] and a [`Sky`] at the sun, warm the
+/// light's color near the horizon, and pull fog towards the horizon color.
+///
+/// `time_of_day` runs from 0 to 1: 0 is midnight, 0.25 sunrise, 0.5 noon and
+/// 0.75 sunset. The sun rises in +X and sets in -X.
+///
+/// ## Example
+/// ```ignore
+/// let mut cycle = DayNightCycle::new(600.0).with_time_of_day(0.3);
+///
+/// cycle.update(dt);
+/// cycle.apply(&mut sun, &mut sky, camera.fog.as_mut());
+/// shader.set_1f_uniform(name!("u_ambient"), cycle.ambient_intensity());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DayNightCycle {
+    /// Real-time seconds in a full day.
+    pub day_length: f32,
+    pub time_of_day: f32,
+    /// Angle in radians the sun's path is tilted away from straight overhead,
+    /// towards +Z; roughly the latitude.
+    pub tilt: f32,
+    pub paused: bool,
+    /// Color temperature in Kelvin when the sun is on the horizon.
+    pub horizon_temperature: f32,
+    /// Color temperature in Kelvin when the sun is overhead.
+    pub noon_temperature: f32,
+    pub day_intensity: f32,
+    pub day_ambient: f32,
+    pub night_ambient: f32,
+}
+
+impl DayNightCycle {
+    /// Creates a cycle with a day lasting `day_length` seconds, starting at noon.
+    pub fn new(day_length: f32) -> Self {
+        Self {
+            day_length,
+            time_of_day: 0.5,
+            tilt: 0.4,
+            paused: false,
+            horizon_temperature: 2000.0,
+            noon_temperature: 6500.0,
+            day_intensity: 1.0,
+            day_ambient: 0.3,
+            night_ambient: 0.03,
+        }
+    }
+
+    /// Sets the starting time of day.
+    pub fn with_time_of_day(mut self, time_of_day: f32) -> Self {
+        self.time_of_day = time_of_day.rem_euclid(1.0);
+        self
+    }
+
+    /// Sets the tilt of the sun's path.
+    pub fn with_tilt(mut self, tilt: f32) -> Self {
+        self.tilt = tilt;
+        self
+    }
+
+    /// Advances the time of day.
+    pub fn update(&mut self, dt: f32) {
+        if self.paused || self.day_length <= 0.0 {
+            return;
+        }
+        self.time_of_day = (self.time_of_day + dt / self.day_length).rem_euclid(1.0);
+    }
+
+    /// Returns the time of day in hours, from 0 to 24.
+    pub fn hours(&self) -> f32 {
+        self.time_of_day * 24.0
+    }
+
+    /// Checks if the sun is above the horizon.
+    pub fn is_day(&self) -> bool {
+        self.sun_direction().y > 0.0
+    }
+
+    /// Returns the unit direction pointing towards the sun.
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = (self.time_of_day - 0.25) * TAU;
+        vec3(
+            angle.cos(),
+            angle.sin() * self.tilt.cos(),
+            angle.sin() * self.tilt.sin(),
+        )
+    }
+
+    /// Returns how much daylight there is, from 0 at night to 1 once the sun is up.
+    pub fn daylight(&self) -> f32 {
+        let elevation = self.sun_direction().y;
+        let t = ((elevation + 0.1) / 0.4).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// Returns the sun's color temperature in Kelvin for its current elevation.
+    pub fn color_temperature(&self) -> f32 {
+        let elevation = self.sun_direction().y.clamp(0.0, 1.0);
+        let t = elevation.sqrt();
+        self.horizon_temperature + (self.noon_temperature - self.horizon_temperature) * t
+    }
+
+    /// Returns the sun's color.
+    pub fn sun_color(&self) -> Color {
+        Color::from_temperature(self.color_temperature())
+    }
+
+    /// Returns the ambient light intensity.
+    pub fn ambient_intensity(&self) -> f32 {
+        self.night_ambient + (self.day_ambient - self.night_ambient) * self.daylight()
+    }
+
+    /// Points the light and sky at the sun and, if given, tints the fog to the
+    /// sky's horizon color.
+    pub fn apply(&self, light: &mut DirectionalLight, sky: &mut Sky, fog: Option<&mut Fog>) {
+        self.apply_to_light(light);
+        sky.follow_light(light);
+        if let Some(fog) = fog {
+            let alpha = fog.color.a;
+            fog.color = sky.horizon_color().with_alpha(alpha);
+        }
+    }
+
+    /// Points a light at the sun and sets its color and intensity.
+    pub fn apply_to_light(&self, light: &mut DirectionalLight) {
+        light.direction = -self.sun_direction();
+        light.color = self.sun_color();
+        light.intensity = self.day_intensity * self.daylight();
+    }
+}
+
+impl Default for DayNightCycle {
+    fn default() -> Self {
+        Self::new(600.0)
+    }
+}
@@ -0,0 +1,152 @@
+use super::gl_wrapper::{is_gles, ShaderProgram};
+
+use crate::name;
+
+/// GLSL helpers implementing the shader-side debug views.
+///
+/// Paste (or concatenate) this into a fragment shader and pass the final lit
+/// color through `nyanko_debug_view` before writing it out:
+///
+/// ```glsl
+/// out_color = nyanko_debug_view(lit_color, v_normal, v_uv, light_count);
+/// ```
+pub const DEBUG_VIEW_GLSL: &str = r#"
+uniform int u_debug_view;
+
+vec3 nyanko_heat(float t) {
+    t = clamp(t, 0.0, 1.0);
+    vec3 cold = mix(vec3(0.0, 0.0, 0.5), vec3(0.0, 0.8, 0.2), smoothstep(0.0, 0.33, t));
+    vec3 warm = mix(vec3(1.0, 0.9, 0.0), vec3(1.0, 0.0, 0.0), smoothstep(0.66, 1.0, t));
+    return mix(cold, warm, smoothstep(0.33, 0.66, t));
+}
+
+vec3 nyanko_uv_checker(vec2 uv) {
+    vec2 cell = floor(uv * 8.0);
+    float checker = mod(cell.x + cell.y, 2.0);
+    return mix(vec3(0.15), vec3(0.85), checker) * 0.7 + vec3(fract(uv), 0.0) * 0.3;
+}
+
+vec4 nyanko_debug_view(vec4 color, vec3 normal, vec2 uv, int light_count) {
+    if (u_debug_view == 2) {
+        return vec4(normalize(normal) * 0.5 + 0.5, 1.0);
+    }
+    if (u_debug_view == 3) {
+        return vec4(0.08, 0.04, 0.02, 1.0);
+    }
+    if (u_debug_view == 4) {
+        return vec4(nyanko_uv_checker(uv), 1.0);
+    }
+    if (u_debug_view == 5) {
+        return vec4(nyanko_heat(float(light_count) / 16.0), 1.0);
+    }
+    return color;
+}
+"#;
+
+/// Name of the uniform selecting the debug view in `DEBUG_VIEW_GLSL`.
+pub const DEBUG_VIEW_UNIFORM: &str = "u_debug_view";
+
+/// # Debug View
+///
+/// Renderer visualization modes for diagnosing content problems.
+///
+/// - `Wireframe` draws polygons as lines.
+/// - `Normals` shows world-space normals as colors.
+/// - `Overdraw` draws every fragment additively so heavily overdrawn areas glow.
+/// - `UvChecker` replaces textures with a checkerboard to reveal UV stretching.
+/// - `LightComplexity` colors surfaces by the number of lights affecting them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DebugView {
+    #[default]
+    Lit,
+    Wireframe,
+    Normals,
+    Overdraw,
+    UvChecker,
+    LightComplexity,
+}
+
+impl DebugView {
+    /// Every debug view, in cycling order.
+    pub const ALL: [DebugView; 6] = [
+        DebugView::Lit,
+        DebugView::Wireframe,
+        DebugView::Normals,
+        DebugView::Overdraw,
+        DebugView::UvChecker,
+        DebugView::LightComplexity,
+    ];
+
+    /// Returns the value passed to the `u_debug_view` uniform.
+    pub fn shader_index(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns the next view, wrapping around after the last one.
+    pub fn next(&self) -> DebugView {
+        Self::ALL[(self.shader_index() as usize + 1) % Self::ALL.len()]
+    }
+
+    /// Returns a human-readable name for HUDs and logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugView::Lit => "Lit",
+            DebugView::Wireframe => "Wireframe",
+            DebugView::Normals => "Normals",
+            DebugView::Overdraw => "Overdraw",
+            DebugView::UvChecker => "UV Checker",
+            DebugView::LightComplexity => "Light Complexity",
+        }
+    }
+
+    /// Sets the `u_debug_view` uniform on a shader that includes `DEBUG_VIEW_GLSL`.
+    pub fn apply_uniform(&self, shader: &ShaderProgram) {
+        shader.set_1i_uniform(name!(DEBUG_VIEW_UNIFORM), self.shader_index());
+    }
+
+    /// Applies the fixed-function GL state this view needs. Wireframe draws
+    /// filled on OpenGL ES, which has no polygon mode.
+    pub(crate) fn apply_gl_state(&self) {
+        unsafe {
+            match self {
+                DebugView::Wireframe => {
+                    set_polygon_mode(gl::LINE);
+                }
+                DebugView::Overdraw => {
+                    set_polygon_mode(gl::FILL);
+                    gl::Disable(gl::DEPTH_TEST);
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::ONE, gl::ONE);
+                }
+                _ => {
+                    set_polygon_mode(gl::FILL);
+                }
+            }
+        }
+    }
+
+    /// Restores the GL state changed by `apply_gl_state`.
+    pub(crate) fn reset_gl_state(&self) {
+        unsafe {
+            match self {
+                DebugView::Wireframe => {
+                    set_polygon_mode(gl::FILL);
+                }
+                DebugView::Overdraw => {
+                    gl::Enable(gl::DEPTH_TEST);
+                    gl::Disable(gl::BLEND);
+                    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn set_polygon_mode(mode: gl::types::GLenum) {
+    if !is_gles() {
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, mode);
+        }
+    }
+}
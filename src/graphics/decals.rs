@@ -0,0 +1,276 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::camera::CameraView;
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::primitives::Primitive;
+use super::texture::Texture;
+use crate::math::*;
+use crate::name;
+use crate::name::Name;
+use crate::scene::Transform;
+
+const DECAL_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 a_position;
+
+uniform mat4 u_model;
+uniform mat4 u_view_projection;
+
+void main() {
+    gl_Position = u_view_projection * u_model * vec4(a_position, 1.0);
+}
+"#;
+
+const DECAL_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+out vec4 out_color;
+
+uniform sampler2D u_decal_texture;
+uniform sampler2D u_depth_texture;
+uniform mat4 u_inverse_view_projection;
+uniform mat4 u_inverse_model;
+uniform vec4 u_viewport;
+uniform vec4 u_color;
+
+void main() {
+    vec2 screen_uv = (gl_FragCoord.xy - u_viewport.xy) / u_viewport.zw;
+    float depth = texelFetch(u_depth_texture, ivec2(gl_FragCoord.xy), 0).r;
+
+    vec4 world = u_inverse_view_projection * vec4(vec3(screen_uv, depth) * 2.0 - 1.0, 1.0);
+    vec3 local = (u_inverse_model * vec4(world.xyz / world.w, 1.0)).xyz;
+    if (any(greaterThan(abs(local), vec3(0.5)))) {
+        discard;
+    }
+
+    out_color = texture(u_decal_texture, local.xz + 0.5) * u_color;
+}
+"#;
+
+/// # Decal
+///
+/// A texture projected onto whatever geometry lies inside a box. The box is
+/// the unit cube placed by `transform`; the texture is projected down the
+/// box's local Y axis, so scale X and Z for the decal's size and Y for how far
+/// it reaches into surfaces.
+#[derive(Clone)]
+pub struct Decal {
+    pub transform: Transform,
+    pub texture: Arc<Texture>,
+    pub color: Color,
+    /// Seconds the decal stays fully visible; `None` keeps it until evicted.
+    pub lifetime: Option<f32>,
+    /// Seconds spent fading out after the lifetime ends.
+    pub fade_duration: f32,
+    age: f32,
+}
+
+impl Decal {
+    /// Creates a permanent decal.
+    pub fn new(transform: Transform, texture: Arc<Texture>) -> Self {
+        Self {
+            transform,
+            texture,
+            color: Color::WHITE,
+            lifetime: None,
+            fade_duration: 1.0,
+            age: 0.0,
+        }
+    }
+
+    /// Makes the decal fade out after `lifetime` seconds over `fade_duration` seconds.
+    pub fn with_lifetime(mut self, lifetime: f32, fade_duration: f32) -> Self {
+        self.lifetime = Some(lifetime);
+        self.fade_duration = fade_duration;
+        self
+    }
+
+    /// Tints the decal.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Returns the current opacity multiplier from fading.
+    pub fn opacity(&self) -> f32 {
+        match self.lifetime {
+            Some(lifetime) if self.age > lifetime => {
+                1.0 - ((self.age - lifetime) / self.fade_duration.max(f32::EPSILON)).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Checks if the decal has completely faded out.
+    pub fn is_expired(&self) -> bool {
+        self.lifetime
+            .is_some_and(|lifetime| self.age >= lifetime + self.fade_duration)
+    }
+}
+
+/// # Decal Renderer
+///
+/// Draws screen-space decals (bullet holes, blood, road markings) over an
+/// already rendered scene by reconstructing world positions from the depth
+/// buffer. Only the newest `max_decals` are kept; spawning more evicts the
+/// oldest.
+///
+/// ## Example
+/// ```ignore
+/// let mut decals = DecalRenderer::new(256, window.framebuffer_size());
+///
+/// decals.spawn(
+///     Decal::new(Transform::from_translation(hit_point).with_scale(vec3(0.2, 0.1, 0.2)), bullet_hole.clone())
+///         .with_lifetime(10.0, 2.0),
+/// );
+///
+/// decals.update(dt);
+/// // after drawing opaque geometry into `scene`:
+/// decals.render(&camera_view, &scene);
+/// ```
+pub struct DecalRenderer {
+    decals: VecDeque<Decal>,
+    max_decals: usize,
+    shader: ShaderProgram,
+    cube: Primitive,
+    depth_copy: Framebuffer,
+}
+
+impl DecalRenderer {
+    /// Creates a decal renderer for scene framebuffers of the given size.
+    pub fn new(max_decals: usize, size: (u32, u32)) -> Self {
+        let mut shader = ShaderProgram::from_source(DECAL_VERTEX_SHADER, DECAL_FRAGMENT_SHADER);
+        for uniform in [
+            "u_model",
+            "u_view_projection",
+            "u_decal_texture",
+            "u_depth_texture",
+            "u_inverse_view_projection",
+            "u_inverse_model",
+            "u_viewport",
+            "u_color",
+        ] {
+            shader.create_uniform(uniform);
+        }
+
+        Self {
+            decals: VecDeque::new(),
+            max_decals,
+            shader,
+            cube: Primitive::unit_cube(),
+            depth_copy: Self::create_depth_copy(size),
+        }
+    }
+
+    fn create_depth_copy(size: (u32, u32)) -> Framebuffer {
+        Framebuffer::with_depth_texture(size.0, size.1, gl::R8, gl::RED, gl::UNSIGNED_BYTE)
+    }
+
+    /// Adds a decal, evicting the oldest one if the cap is reached.
+    pub fn spawn(&mut self, decal: Decal) {
+        while self.decals.len() >= self.max_decals.max(1) {
+            self.decals.pop_front();
+        }
+        self.decals.push_back(decal);
+    }
+
+    /// Removes every decal.
+    pub fn clear(&mut self) {
+        self.decals.clear();
+    }
+
+    /// Returns the number of live decals.
+    pub fn len(&self) -> usize {
+        self.decals.len()
+    }
+
+    /// Checks if there are no live decals.
+    pub fn is_empty(&self) -> bool {
+        self.decals.is_empty()
+    }
+
+    /// Ages decals and removes the ones that have faded out.
+    pub fn update(&mut self, dt: f32) {
+        for decal in &mut self.decals {
+            decal.age += dt;
+        }
+        self.decals.retain(|decal| !decal.is_expired());
+    }
+
+    /// Draws every decal into `scene`, which must contain the camera's depth.
+    pub fn render(&mut self, view: &CameraView, scene: &Framebuffer) {
+        if self.decals.is_empty() {
+            return;
+        }
+
+        let (width, height) = scene.size();
+        self.depth_copy.resize(width, height);
+        self.depth_copy.copy_depth_from(scene);
+        scene.bind();
+
+        let inverse_view_projection = view
+            .view_projection
+            .invert()
+            .expect("View projection matrix is not invertible");
+        let (x, y, viewport_width, viewport_height) = view.viewport;
+
+        self.shader.bind();
+        self.shader
+            .set_matrix4fv_uniform(name!("u_view_projection"), &view.view_projection);
+        self.shader
+            .set_matrix4fv_uniform(name!("u_inverse_view_projection"), &inverse_view_projection);
+        self.shader.set_4f_uniform(
+            name!("u_viewport"),
+            x as f32,
+            y as f32,
+            viewport_width as f32,
+            viewport_height as f32,
+        );
+        self.shader.set_1i_uniform(name!("u_decal_texture"), 0);
+        self.shader.set_1i_uniform(name!("u_depth_texture"), 1);
+        self.depth_copy
+            .depth_texture()
+            .expect("Depth copy has a depth texture")
+            .bind(1);
+
+        unsafe {
+            gl::Viewport(x, y, viewport_width, viewport_height);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::FRONT);
+        }
+
+        let (u_model, u_inverse_model, u_color) = (
+            Name::new("u_model"),
+            Name::new("u_inverse_model"),
+            Name::new("u_color"),
+        );
+        for decal in &self.decals {
+            let model = decal.transform.matrix();
+            let Some(inverse_model) = model.invert() else {
+                continue;
+            };
+            let color = decal.color.with_alpha(decal.color.a * decal.opacity());
+
+            self.shader.set_matrix4fv_uniform(u_model, &model);
+            self.shader
+                .set_matrix4fv_uniform(u_inverse_model, &inverse_model);
+            self.shader.set_color_uniform(u_color, color);
+            decal.texture.bind(0);
+            self.cube.draw();
+        }
+
+        unsafe {
+            gl::CullFace(gl::BACK);
+            gl::Disable(gl::CULL_FACE);
+            gl::Disable(gl::BLEND);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        ShaderProgram::unbind();
+    }
+}
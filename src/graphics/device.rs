@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::os::raw::c_void;
+
+use gl::types::*;
+
+use super::gl_wrapper::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use super::texture::Texture;
+use crate::custom_errors::Errors;
+use crate::math::*;
+
+/// The graphics API a [`GraphicsDevice`] renders with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    OpenGl,
+}
+
+/// A buffer created by a [`GraphicsDevice`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferId(u32);
+
+/// A texture created by a [`GraphicsDevice`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(u32);
+
+/// A shader created by a [`GraphicsDevice`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShaderId(u32);
+
+/// Vertex and index buffers bound together with their layout, created by a
+/// [`GraphicsDevice`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GeometryId(u32);
+
+/// What a buffer holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BufferKind {
+    Vertex,
+    /// `u32` indices.
+    Index,
+}
+
+/// How often a buffer's contents change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BufferUsage {
+    /// Uploaded once and drawn many times.
+    Static,
+    /// Rewritten often, e.g. every frame.
+    Dynamic,
+}
+
+/// The pixel format of a texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// 8-bit RGBA sampled as is.
+    Rgba8,
+    /// 8-bit sRGB-encoded RGBA, sampled as linear colors.
+    Srgba8,
+    /// 16-bit floating point RGBA, for HDR colors.
+    Rgba16Float,
+    /// 32-bit floating point single channel, for data.
+    R32Float,
+}
+
+impl TextureFormat {
+    fn to_gl(self) -> (GLenum, GLenum, GLenum) {
+        match self {
+            TextureFormat::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            TextureFormat::Srgba8 => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            TextureFormat::Rgba16Float => (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT),
+            TextureFormat::R32Float => (gl::R32F, gl::RED, gl::FLOAT),
+        }
+    }
+}
+
+/// The size and format of a 2D texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureDescriptor {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    /// Whether a mipmap chain is generated from the uploaded pixels.
+    pub mipmaps: bool,
+}
+
+/// The type of a single vertex attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexFormat {
+    Float32,
+    Float32x2,
+    Float32x3,
+    Float32x4,
+}
+
+impl VertexFormat {
+    fn components(self) -> GLint {
+        match self {
+            VertexFormat::Float32 => 1,
+            VertexFormat::Float32x2 => 2,
+            VertexFormat::Float32x3 => 3,
+            VertexFormat::Float32x4 => 4,
+        }
+    }
+}
+
+/// One attribute of a vertex: the shader location it feeds and where it sits
+/// in the vertex, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexAttributeDescriptor {
+    pub location: u32,
+    pub format: VertexFormat,
+    pub offset: usize,
+}
+
+/// How the vertices of a buffer are laid out.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    /// Size of one vertex in bytes.
+    pub stride: usize,
+    pub attributes: Vec<VertexAttributeDescriptor>,
+    /// Whether the buffer advances once per instance instead of per vertex.
+    pub per_instance: bool,
+}
+
+/// The buffers a piece of geometry draws from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeometryDescriptor {
+    pub vertex_buffers: Vec<(BufferId, VertexLayout)>,
+    pub index_buffer: Option<BufferId>,
+}
+
+/// Shader code in the language of a backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderSource<'a> {
+    /// GLSL vertex and fragment shaders, for OpenGL.
+    Glsl { vertex: &'a str, fragment: &'a str },
+    /// A WGSL module with `vs_main` and `fs_main` entry points.
+    Wgsl(&'a str),
+}
+
+/// A value for a shader uniform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformValue {
+    Int(i32),
+    Float(f32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    Mat4(Mat4),
+    /// An sRGB color, uploaded as a linear `vec4`.
+    Color(Color),
+}
+
+/// How vertices are assembled into primitives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Topology {
+    #[default]
+    Triangles,
+    Lines,
+    Points,
+}
+
+/// Everything needed for one draw.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawCall<'a> {
+    pub shader: ShaderId,
+    pub geometry: GeometryId,
+    /// Textures bound to units 0, 1, 2 and so on.
+    pub textures: &'a [TextureId],
+    pub topology: Topology,
+    /// The vertices, or indices if the geometry has an index buffer, to draw.
+    pub range: Range<u32>,
+    pub instances: u32,
+}
+
+/// # Graphics Device
+///
+/// The operations the renderer needs from a graphics API: creating buffers,
+/// textures and shaders, and drawing with them. Resources are referred to by
+/// ids, so each backend can keep its own objects behind them. [`OpenGlDevice`]
+/// is the OpenGL implementation; a wgpu backend for Vulkan, Metal and DX12
+/// would implement the same trait.
+///
+/// ## Example
+/// ```ignore
+/// let mut device = OpenGlDevice::new();
+///
+/// let vertices = device.create_buffer(BufferKind::Vertex, BufferUsage::Static, bytes_of(&positions));
+/// let geometry = device.create_geometry(&GeometryDescriptor {
+///     vertex_buffers: vec![(vertices, VertexLayout {
+///         stride: 8,
+///         attributes: vec![VertexAttributeDescriptor { location: 0, format: VertexFormat::Float32x2, offset: 0 }],
+///         per_instance: false,
+///     })],
+///     index_buffer: None,
+/// });
+/// let shader = device.create_shader(ShaderSource::Glsl { vertex: VS, fragment: FS })?;
+///
+/// device.set_uniform(shader, "u_color", UniformValue::Color(Color::rgb(1.0, 0.5, 0.0)));
+/// device.draw(&DrawCall {
+///     shader,
+///     geometry,
+///     textures: &[],
+///     topology: Topology::Triangles,
+///     range: 0..3,
+///     instances: 1,
+/// });
+/// ```
+pub trait GraphicsDevice {
+    /// Returns the graphics API this device renders with.
+    fn backend(&self) -> Backend;
+
+    /// Creates a buffer filled with `data`.
+    fn create_buffer(&mut self, kind: BufferKind, usage: BufferUsage, data: &[u8]) -> BufferId;
+
+    /// Overwrites part of a buffer, starting `offset` bytes in. The buffer must
+    /// already be large enough.
+    fn update_buffer(&mut self, buffer: BufferId, offset: usize, data: &[u8]);
+
+    /// Destroys a buffer.
+    fn destroy_buffer(&mut self, buffer: BufferId);
+
+    /// Creates a 2D texture, optionally filled with pixels, top row first.
+    fn create_texture(&mut self, descriptor: &TextureDescriptor, data: Option<&[u8]>) -> TextureId;
+
+    /// Replaces every pixel of a texture.
+    fn update_texture(&mut self, texture: TextureId, data: &[u8]);
+
+    /// Destroys a texture.
+    fn destroy_texture(&mut self, texture: TextureId);
+
+    /// Creates a shader, failing if the source is not in a language the
+    /// backend understands.
+    fn create_shader(&mut self, source: ShaderSource) -> Result<ShaderId, Errors>;
+
+    /// Sets a uniform of a shader, kept until it is set again.
+    fn set_uniform(&mut self, shader: ShaderId, name: &str, value: UniformValue);
+
+    /// Destroys a shader.
+    fn destroy_shader(&mut self, shader: ShaderId);
+
+    /// Binds buffers together with their layouts for drawing.
+    fn create_geometry(&mut self, descriptor: &GeometryDescriptor) -> GeometryId;
+
+    /// Destroys a geometry. Its buffers are left alone.
+    fn destroy_geometry(&mut self, geometry: GeometryId);
+
+    /// Draws into the current target.
+    fn draw(&mut self, call: &DrawCall);
+}
+
+/// # OpenGL Device
+///
+/// The [`GraphicsDevice`] backed by the current OpenGL context, built on the
+/// engine's OpenGL wrappers. Create it after loading OpenGL.
+#[derive(Default)]
+pub struct OpenGlDevice {
+    next_id: u32,
+    buffers: HashMap<u32, (BufferObject, BufferKind)>,
+    textures: HashMap<u32, (Texture, TextureDescriptor)>,
+    shaders: HashMap<u32, ShaderProgram>,
+    geometries: HashMap<u32, (Vao, bool)>,
+}
+
+impl OpenGlDevice {
+    /// Creates a device for the current OpenGL context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the OpenGL texture behind an id, to use it with the rest of the
+    /// engine's renderers.
+    pub fn texture(&self, texture: TextureId) -> Option<&Texture> {
+        self.textures.get(&texture.0).map(|(texture, _)| texture)
+    }
+
+    fn allocate_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+impl GraphicsDevice for OpenGlDevice {
+    fn backend(&self) -> Backend {
+        Backend::OpenGl
+    }
+
+    fn create_buffer(&mut self, kind: BufferKind, usage: BufferUsage, data: &[u8]) -> BufferId {
+        let target = match kind {
+            BufferKind::Vertex => gl::ARRAY_BUFFER,
+            BufferKind::Index => gl::ELEMENT_ARRAY_BUFFER,
+        };
+        let usage = match usage {
+            BufferUsage::Static => gl::STATIC_DRAW,
+            BufferUsage::Dynamic => gl::DYNAMIC_DRAW,
+        };
+        // Index buffers bind to the current VAO, so keep none bound.
+        Vao::unbind();
+        let buffer = BufferObject::new(target, usage);
+        buffer.bind();
+        buffer.store_bytes(data);
+        buffer.unbind();
+
+        let id = self.allocate_id();
+        self.buffers.insert(id, (buffer, kind));
+        BufferId(id)
+    }
+
+    fn update_buffer(&mut self, buffer: BufferId, offset: usize, data: &[u8]) {
+        if let Some((buffer, _)) = self.buffers.get(&buffer.0) {
+            Vao::unbind();
+            buffer.bind();
+            buffer.update_bytes(offset, data);
+            buffer.unbind();
+        }
+    }
+
+    fn destroy_buffer(&mut self, buffer: BufferId) {
+        if let Some((buffer, _)) = self.buffers.remove(&buffer.0) {
+            buffer.delete();
+        }
+    }
+
+    fn create_texture(&mut self, descriptor: &TextureDescriptor, data: Option<&[u8]>) -> TextureId {
+        let (internal_format, format, data_type) = descriptor.format.to_gl();
+        let texture = Texture::new_2d(
+            descriptor.width,
+            descriptor.height,
+            internal_format,
+            format,
+            data_type,
+            data,
+        );
+        if descriptor.mipmaps {
+            texture.generate_mipmaps();
+        }
+
+        let id = self.allocate_id();
+        self.textures.insert(id, (texture, *descriptor));
+        TextureId(id)
+    }
+
+    fn update_texture(&mut self, texture: TextureId, data: &[u8]) {
+        if let Some((texture, descriptor)) = self.textures.get_mut(&texture.0) {
+            let (internal_format, format, data_type) = descriptor.format.to_gl();
+            texture.set_data(
+                descriptor.width,
+                descriptor.height,
+                internal_format,
+                format,
+                data_type,
+                data,
+            );
+            if descriptor.mipmaps {
+                texture.generate_mipmaps();
+            }
+        }
+    }
+
+    fn destroy_texture(&mut self, texture: TextureId) {
+        // Textures delete themselves when dropped.
+        self.textures.remove(&texture.0);
+    }
+
+    fn create_shader(&mut self, source: ShaderSource) -> Result<ShaderId, Errors> {
+        let ShaderSource::Glsl { vertex, fragment } = source else {
+            return Err(Errors::UnsupportedByBackend(
+                "OpenGL only compiles GLSL shaders".to_string(),
+            ));
+        };
+        let shader = ShaderProgram::from_source(vertex, fragment);
+
+        let id = self.allocate_id();
+        self.shaders.insert(id, shader);
+        Ok(ShaderId(id))
+    }
+
+    fn set_uniform(&mut self, shader: ShaderId, name: &str, value: UniformValue) {
+        let Some(shader) = self.shaders.get_mut(&shader.0) else {
+            return;
+        };
+        if !shader.has_uniform(name) {
+            shader.create_uniform(name);
+        }
+        shader.bind();
+        match value {
+            UniformValue::Int(value) => shader.set_1i_uniform(name, value),
+            UniformValue::Float(value) => shader.set_1f_uniform(name, value),
+            UniformValue::Vec2(value) => shader.set_2f_uniform(name, value.x, value.y),
+            UniformValue::Vec3(value) => shader.set_3f_uniform(name, value.x, value.y, value.z),
+            UniformValue::Vec4(value) => {
+                shader.set_4f_uniform(name, value.x, value.y, value.z, value.w)
+            }
+            UniformValue::Mat4(value) => shader.set_matrix4fv_uniform(name, &value),
+            UniformValue::Color(value) => shader.set_color_uniform(name, value),
+        }
+    }
+
+    fn destroy_shader(&mut self, shader: ShaderId) {
+        if let Some(shader) = self.shaders.remove(&shader.0) {
+            shader.delete();
+        }
+    }
+
+    fn create_geometry(&mut self, descriptor: &GeometryDescriptor) -> GeometryId {
+        let vao = Vao::new();
+        vao.bind();
+        for (buffer, layout) in &descriptor.vertex_buffers {
+            let Some((buffer, _)) = self.buffers.get(&buffer.0) else {
+                continue;
+            };
+            buffer.bind();
+            for attribute in &layout.attributes {
+                let attribute_pointer = VertexAttribute::new(
+                    attribute.location,
+                    attribute.format.components(),
+                    gl::FLOAT,
+                    gl::FALSE,
+                    layout.stride as GLsizei,
+                    attribute.offset as *const c_void,
+                );
+                attribute_pointer.enable();
+                attribute_pointer.set_divisor(layout.per_instance as GLuint);
+            }
+        }
+        let indexed = match descriptor
+            .index_buffer
+            .and_then(|buffer| self.buffers.get(&buffer.0))
+        {
+            Some((buffer, _)) => {
+                buffer.bind();
+                true
+            }
+            None => false,
+        };
+        Vao::unbind();
+
+        let id = self.allocate_id();
+        self.geometries.insert(id, (vao, indexed));
+        GeometryId(id)
+    }
+
+    fn destroy_geometry(&mut self, geometry: GeometryId) {
+        if let Some((vao, _)) = self.geometries.remove(&geometry.0) {
+            vao.delete();
+        }
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+        let (Some(shader), Some((vao, indexed))) = (
+            self.shaders.get(&call.shader.0),
+            self.geometries.get(&call.geometry.0),
+        ) else {
+            return;
+        };
+        shader.bind();
+        for (unit, texture) in call.textures.iter().enumerate() {
+            if let Some((texture, _)) = self.textures.get(&texture.0) {
+                texture.bind(unit as u32);
+            }
+        }
+
+        let mode = match call.topology {
+            Topology::Triangles => gl::TRIANGLES,
+            Topology::Lines => gl::LINES,
+            Topology::Points => gl::POINTS,
+        };
+        let count = call.range.end.saturating_sub(call.range.start) as GLsizei;
+        vao.bind();
+        unsafe {
+            if *indexed {
+                gl::DrawElementsInstanced(
+                    mode,
+                    count,
+                    gl::UNSIGNED_INT,
+                    (call.range.start as usize * std::mem::size_of::<u32>()) as *const c_void,
+                    call.instances as GLsizei,
+                );
+            } else {
+                gl::DrawArraysInstanced(
+                    mode,
+                    call.range.start as GLint,
+                    count,
+                    call.instances as GLsizei,
+                );
+            }
+        }
+        Vao::unbind();
+    }
+}
@@ -0,0 +1,170 @@
+use super::gl_wrapper::ShaderProgram;
+use crate::math::*;
+use crate::name;
+
+/// GLSL helpers implementing distance and height fog.
+///
+/// Paste (or concatenate) this into a fragment shader, create the uniforms in
+/// [`FOG_UNIFORMS`], and pass the final color through `nyanko_fog`:
+///
+/// ```glsl
+/// out_color.rgb = nyanko_fog(out_color.rgb, v_world_position, u_camera_position);
+/// ```
+pub const FOG_GLSL: &str = r#"
+uniform int u_fog_mode;
+uniform vec4 u_fog_color;
+uniform vec3 u_fog_params;
+uniform vec3 u_fog_height;
+
+float nyanko_fog_factor(vec3 world_position, vec3 camera_position) {
+    float distance = length(world_position - camera_position);
+    float fog = 0.0;
+    if (u_fog_mode == 1) {
+        fog = clamp((distance - u_fog_params.x) / max(u_fog_params.y - u_fog_params.x, 0.0001), 0.0, 1.0);
+    } else if (u_fog_mode == 2) {
+        fog = 1.0 - exp(-u_fog_params.z * distance);
+    } else if (u_fog_mode == 3) {
+        float d = u_fog_params.z * distance;
+        fog = 1.0 - exp(-d * d);
+    }
+
+    // Height fog: integrate exponentially falling density along the view ray.
+    if (u_fog_height.z > 0.0) {
+        float falloff = u_fog_height.y;
+        vec3 ray = world_position - camera_position;
+        float start = exp(-falloff * (camera_position.y - u_fog_height.x));
+        float slope = falloff * ray.y;
+        float integral = abs(slope) > 0.0001 ? start * (1.0 - exp(-slope)) / slope : start;
+        fog = max(fog, 1.0 - exp(-u_fog_height.z * distance * integral));
+    }
+
+    return fog * u_fog_color.a;
+}
+
+vec3 nyanko_fog(vec3 color, vec3 world_position, vec3 camera_position) {
+    return mix(color, u_fog_color.rgb, nyanko_fog_factor(world_position, camera_position));
+}
+"#;
+
+/// Names of the uniforms used by [`FOG_GLSL`].
+pub const FOG_UNIFORMS: [&str; 4] = ["u_fog_mode", "u_fog_color", "u_fog_params", "u_fog_height"];
+
+/// How fog thickens with distance from the camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogMode {
+    /// No distance fog; height fog still applies.
+    None,
+    /// Fades from clear at `start` to fully fogged at `end`.
+    Linear { start: f32, end: f32 },
+    /// `1 - e^(-density * distance)`.
+    Exponential { density: f32 },
+    /// `1 - e^(-(density * distance)^2)`, which keeps the foreground clearer.
+    ExponentialSquared { density: f32 },
+}
+
+/// Fog that pools near the ground and thins out with altitude.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightFog {
+    /// World height at which the fog has its full `density`.
+    pub base_height: f32,
+    /// How quickly the fog thins above `base_height`.
+    pub falloff: f32,
+    pub density: f32,
+}
+
+/// # Fog
+///
+/// Per-camera fog settings, applied by shaders that include [`FOG_GLSL`].
+///
+/// ## Example
+/// ```ignore
+/// let camera = Camera::perspective(Deg(60.0), 0.1, 500.0).with_fog(
+///     Fog::exponential_squared(Color::rgb(0.6, 0.7, 0.8), 0.01)
+///         .with_height_fog(HeightFog { base_height: 0.0, falloff: 0.2, density: 0.05 }),
+/// );
+///
+/// shader.bind();
+/// apply_fog_uniforms(view.camera.fog.as_ref(), &shader);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub mode: FogMode,
+    /// Fog color; alpha scales the overall fog amount.
+    pub color: Color,
+    pub height: Option<HeightFog>,
+}
+
+impl Fog {
+    /// Creates linear fog between two distances.
+    pub fn linear(color: Color, start: f32, end: f32) -> Self {
+        Self::new(FogMode::Linear { start, end }, color)
+    }
+
+    /// Creates exponential fog.
+    pub fn exponential(color: Color, density: f32) -> Self {
+        Self::new(FogMode::Exponential { density }, color)
+    }
+
+    /// Creates exponential-squared fog.
+    pub fn exponential_squared(color: Color, density: f32) -> Self {
+        Self::new(FogMode::ExponentialSquared { density }, color)
+    }
+
+    /// Creates fog with the given mode and color.
+    pub fn new(mode: FogMode, color: Color) -> Self {
+        Self {
+            mode,
+            color,
+            height: None,
+        }
+    }
+
+    /// Adds height fog.
+    pub fn with_height_fog(mut self, height: HeightFog) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Returns the fog amount from 0 to 1 at a point, matching `nyanko_fog_factor`
+    /// without height fog. Useful for fading gameplay elements with the scene.
+    pub fn distance_factor(&self, distance: f32) -> f32 {
+        let fog = match self.mode {
+            FogMode::None => 0.0,
+            FogMode::Linear { start, end } => {
+                ((distance - start) / (end - start).max(0.0001)).clamp(0.0, 1.0)
+            }
+            FogMode::Exponential { density } => 1.0 - (-density * distance).exp(),
+            FogMode::ExponentialSquared { density } => 1.0 - (-(density * distance).powi(2)).exp(),
+        };
+        fog * self.color.a
+    }
+}
+
+/// Sets the fog uniforms on a shader that includes [`FOG_GLSL`]. `None` disables fog.
+pub fn apply_fog_uniforms(fog: Option<&Fog>, shader: &ShaderProgram) {
+    let Some(fog) = fog else {
+        shader.set_1i_uniform(name!("u_fog_mode"), 0);
+        shader.set_3f_uniform(name!("u_fog_height"), 0.0, 0.0, 0.0);
+        return;
+    };
+
+    let (mode, start, end, density) = match fog.mode {
+        FogMode::None => (0, 0.0, 0.0, 0.0),
+        FogMode::Linear { start, end } => (1, start, end, 0.0),
+        FogMode::Exponential { density } => (2, 0.0, 0.0, density),
+        FogMode::ExponentialSquared { density } => (3, 0.0, 0.0, density),
+    };
+    shader.set_1i_uniform(name!("u_fog_mode"), mode);
+    shader.set_color_uniform(name!("u_fog_color"), fog.color);
+    shader.set_3f_uniform(name!("u_fog_params"), start, end, density);
+
+    match fog.height {
+        Some(height) => shader.set_3f_uniform(
+            name!("u_fog_height"),
+            height.base_height,
+            height.falloff,
+            height.density,
+        ),
+        None => shader.set_3f_uniform(name!("u_fog_height"), 0.0, 0.0, 0.0),
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::fs;
+
+use ab_glyph::{Font, FontArc, GlyphId, ScaleFont};
+
+use super::texture::Texture;
+use crate::custom_errors::Errors;
+use crate::math::*;
+
+/// Settings for building an [`SdfFont`] atlas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdfFontOptions {
+    /// Pixel size glyphs are rasterized at. Larger sizes keep sharper corners.
+    pub size: f32,
+    /// Distance in pixels the field extends outside each glyph; limits how wide
+    /// outlines and shadows can be.
+    pub spread: u32,
+    /// Characters baked into the atlas.
+    pub characters: String,
+}
+
+impl Default for SdfFontOptions {
+    fn default() -> Self {
+        Self {
+            size: 48.0,
+            spread: 6,
+            characters: (' '..='~').chain('\u{a0}'..='\u{ff}').collect(),
+        }
+    }
+}
+
+/// Placement of one glyph in an [`SdfFont`] atlas. Sizes are in pixels at the
+/// font's base size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfGlyph {
+    /// Region of the atlas, in UV coordinates.
+    pub uv_rect: Rect,
+    /// Size of the glyph's quad, including the distance field padding.
+    pub size: Vec2,
+    /// Offset from the pen position on the baseline to the quad's top-left corner, Y down.
+    pub offset: Vec2,
+    /// Horizontal distance to the next pen position.
+    pub advance: f32,
+}
+
+/// # SDF Font
+///
+/// A font baked into a signed distance field atlas. Each texel stores the
+/// distance to the nearest glyph edge instead of coverage, so text stays crisp
+/// when scaled far beyond the baked size, and outlines and shadows come almost
+/// for free in the shader.
+///
+/// The atlas stores `0.5` on glyph edges, rising to `1.0` at `spread` pixels
+/// inside and falling to `0.0` at `spread` pixels outside.
+///
+/// ## Example
+/// ```ignore
+/// let font = Arc::new(SdfFont::from_file("assets/fonts/NotoSans-Regular.ttf", SdfFontOptions::default())?);
+/// let width = font.measure("Hello!", 24.0).x;
+/// ```
+pub struct SdfFont {
+    font: FontArc,
+    size: f32,
+    spread: u32,
+    glyphs: HashMap<char, SdfGlyph>,
+    glyph_ids: HashMap<char, GlyphId>,
+    texture: Texture,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+}
+
+impl SdfFont {
+    /// Loads a TrueType or OpenType font file and bakes its atlas.
+    pub fn from_file(path: &str, options: SdfFontOptions) -> Result<Self, Errors> {
+        let data =
+            fs::read(path).map_err(|error| Errors::InvalidFont(format!("{}: {}", path, error)))?;
+        Self::from_bytes(data, options)
+    }
+
+    /// Parses TrueType or OpenType font data and bakes its atlas.
+    pub fn from_bytes(data: Vec<u8>, options: SdfFontOptions) -> Result<Self, Errors> {
+        let font =
+            FontArc::try_from_vec(data).map_err(|error| Errors::InvalidFont(error.to_string()))?;
+        let scaled = font.as_scaled(options.size);
+        let spread = options.spread;
+
+        let mut bitmaps = Vec::new();
+        let mut glyph_ids = HashMap::new();
+        for character in options.characters.chars() {
+            let id = font.glyph_id(character);
+            glyph_ids.insert(character, id);
+            let advance = scaled.h_advance(id);
+
+            let Some(outline) = font.outline_glyph(id.with_scale(options.size)) else {
+                bitmaps.push((character, advance, None));
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            let width = bounds.width().ceil() as usize;
+            let height = bounds.height().ceil() as usize;
+            let mut coverage = vec![0.0f32; width * height];
+            outline.draw(|x, y, value| {
+                if let Some(pixel) = coverage.get_mut(y as usize * width + x as usize) {
+                    *pixel = value;
+                }
+            });
+
+            let field = distance_field(&coverage, width, height, spread as usize);
+            let offset = vec2(bounds.min.x - spread as f32, bounds.min.y - spread as f32);
+            bitmaps.push((character, advance, Some((field, offset))));
+        }
+
+        let padded: Vec<(usize, usize)> = bitmaps
+            .iter()
+            .map(|(_, _, bitmap)| match bitmap {
+                Some((field, _)) => (field.width, field.height),
+                None => (0, 0),
+            })
+            .collect();
+        let (atlas_width, atlas_height, positions) = pack_shelves(&padded);
+
+        let mut pixels = vec![0u8; atlas_width * atlas_height];
+        let mut glyphs = HashMap::new();
+        for ((character, advance, bitmap), (x, y)) in bitmaps.into_iter().zip(positions) {
+            let glyph = match bitmap {
+                Some((field, offset)) => {
+                    for row in 0..field.height {
+                        let start = (y + row) * atlas_width + x;
+                        pixels[start..start + field.width].copy_from_slice(
+                            &field.values[row * field.width..(row + 1) * field.width],
+                        );
+                    }
+                    SdfGlyph {
+                        uv_rect: Rect::new(
+                            x as f32 / atlas_width as f32,
+                            y as f32 / atlas_height as f32,
+                            field.width as f32 / atlas_width as f32,
+                            field.height as f32 / atlas_height as f32,
+                        ),
+                        size: vec2(field.width as f32, field.height as f32),
+                        offset,
+                        advance,
+                    }
+                }
+                None => SdfGlyph {
+                    uv_rect: Rect::new(0.0, 0.0, 0.0, 0.0),
+                    size: vec2(0.0, 0.0),
+                    offset: vec2(0.0, 0.0),
+                    advance,
+                },
+            };
+            glyphs.insert(character, glyph);
+        }
+
+        let texture = Texture::new_2d(
+            atlas_width as u32,
+            atlas_height as u32,
+            gl::R8,
+            gl::RED,
+            gl::UNSIGNED_BYTE,
+            Some(&pixels),
+        );
+
+        Ok(Self {
+            ascent: scaled.ascent(),
+            descent: scaled.descent(),
+            line_gap: scaled.line_gap(),
+            font,
+            size: options.size,
+            spread,
+            glyphs,
+            glyph_ids,
+            texture,
+        })
+    }
+
+    /// Returns the pixel size the atlas was baked at.
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Returns the distance field spread in pixels at the base size.
+    pub fn spread(&self) -> u32 {
+        self.spread
+    }
+
+    /// Returns the atlas texture.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns a baked glyph.
+    pub fn glyph(&self, character: char) -> Option<&SdfGlyph> {
+        self.glyphs.get(&character)
+    }
+
+    /// Returns the distance from the baseline to the top of the tallest glyphs at the base size.
+    pub fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    /// Returns the (negative) distance from the baseline to the bottom of the lowest glyphs.
+    pub fn descent(&self) -> f32 {
+        self.descent
+    }
+
+    /// Returns the distance between baselines at the base size.
+    pub fn line_height(&self) -> f32 {
+        self.ascent - self.descent + self.line_gap
+    }
+
+    /// Returns the kerning adjustment between two characters at the base size.
+    pub fn kern(&self, first: char, second: char) -> f32 {
+        match (self.glyph_ids.get(&first), self.glyph_ids.get(&second)) {
+            (Some(first), Some(second)) => self.font.as_scaled(self.size).kern(*first, *second),
+            _ => 0.0,
+        }
+    }
+
+    /// Measures the width of the widest line and the total height of `text` at a pixel size.
+    pub fn measure(&self, text: &str, size: f32) -> Vec2 {
+        let scale = size / self.size;
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let mut pen = 0.0;
+            let mut previous = None;
+            for character in line.chars() {
+                if let Some(previous) = previous {
+                    pen += self.kern(previous, character);
+                }
+                pen += self.glyph(character).map_or(0.0, |glyph| glyph.advance);
+                previous = Some(character);
+            }
+            width = width.max(pen);
+            lines += 1;
+        }
+        vec2(width, self.line_height() * lines as f32) * scale
+    }
+}
+
+/// A single-channel distance field bitmap.
+struct DistanceField {
+    width: usize,
+    height: usize,
+    values: Vec<u8>,
+}
+
+/// Converts a coverage bitmap to an 8-bit signed distance field padded by `spread`.
+fn distance_field(coverage: &[f32], width: usize, height: usize, spread: usize) -> DistanceField {
+    let padded_width = width + spread * 2;
+    let padded_height = height + spread * 2;
+    let inside: Vec<bool> = (0..padded_width * padded_height)
+        .map(|index| {
+            let (x, y) = (index % padded_width, index / padded_width);
+            x >= spread
+                && y >= spread
+                && x < spread + width
+                && y < spread + height
+                && coverage[(y - spread) * width + (x - spread)] > 0.5
+        })
+        .collect();
+
+    let to_inside = squared_distances(&inside, padded_width, padded_height, true);
+    let to_outside = squared_distances(&inside, padded_width, padded_height, false);
+    let values = to_inside
+        .iter()
+        .zip(&to_outside)
+        .map(|(to_inside, to_outside)| {
+            let signed = to_inside.sqrt() - to_outside.sqrt();
+            let value = 0.5 - signed / (2.0 * spread.max(1) as f32);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect();
+
+    DistanceField {
+        width: padded_width,
+        height: padded_height,
+        values,
+    }
+}
+
+/// Exact squared Euclidean distance from every pixel to the nearest pixel
+/// whose mask equals `target`, using Felzenszwalb and Huttenlocher's
+/// separable transform.
+fn squared_distances(mask: &[bool], width: usize, height: usize, target: bool) -> Vec<f32> {
+    const FAR: f32 = 1e20;
+    let mut grid: Vec<f32> = mask
+        .iter()
+        .map(|&value| if value == target { 0.0 } else { FAR })
+        .collect();
+
+    let size = width.max(height);
+    let mut input = vec![0.0; size];
+    let mut output = vec![0.0; size];
+    let mut parabolas = vec![0; size];
+    let mut boundaries = vec![0.0; size + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            input[y] = grid[y * width + x];
+        }
+        transform_1d(
+            &input[..height],
+            &mut output,
+            &mut parabolas,
+            &mut boundaries,
+        );
+        for y in 0..height {
+            grid[y * width + x] = output[y];
+        }
+    }
+    for y in 0..height {
+        input[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_1d(
+            &input[..width],
+            &mut output,
+            &mut parabolas,
+            &mut boundaries,
+        );
+        grid[y * width..(y + 1) * width].copy_from_slice(&output[..width]);
+    }
+    grid
+}
+
+/// One-dimensional squared distance transform: the lower envelope of parabolas
+/// rooted at each sample.
+fn transform_1d(
+    input: &[f32],
+    output: &mut [f32],
+    parabolas: &mut [usize],
+    boundaries: &mut [f32],
+) {
+    let count = input.len();
+    if count == 0 {
+        return;
+    }
+
+    let intersection = |q: usize, p: usize| {
+        ((input[q] + (q * q) as f32) - (input[p] + (p * p) as f32))
+            / (2.0 * q as f32 - 2.0 * p as f32)
+    };
+
+    let mut k = 0;
+    parabolas[0] = 0;
+    boundaries[0] = f32::NEG_INFINITY;
+    boundaries[1] = f32::INFINITY;
+    for q in 1..count {
+        let mut s = intersection(q, parabolas[k]);
+        while s <= boundaries[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+        k += 1;
+        parabolas[k] = q;
+        boundaries[k] = s;
+        boundaries[k + 1] = f32::INFINITY;
+    }
+
+    k = 0;
+    for (q, value) in output.iter_mut().enumerate().take(count) {
+        while boundaries[k + 1] < q as f32 {
+            k += 1;
+        }
+        let distance = q as f32 - parabolas[k] as f32;
+        *value = distance * distance + input[parabolas[k]];
+    }
+}
+
+/// Packs rectangles into rows on a power-of-two atlas, returning its size and
+/// the top-left corner of each rectangle. A one pixel gap avoids bleeding.
+fn pack_shelves(sizes: &[(usize, usize)]) -> (usize, usize, Vec<(usize, usize)>) {
+    let area: usize = sizes.iter().map(|(w, h)| (w + 1) * (h + 1)).sum();
+    let widest = sizes.iter().map(|(w, _)| w + 1).max().unwrap_or(1);
+    let width = ((area as f32 * 1.2).sqrt() as usize)
+        .max(widest)
+        .max(64)
+        .next_power_of_two();
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(sizes[index].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for index in order {
+        let (w, h) = sizes[index];
+        if w == 0 || h == 0 {
+            continue;
+        }
+        if x + w + 1 > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        positions[index] = (x, y);
+        x += w + 1;
+        shelf_height = shelf_height.max(h + 1);
+    }
+
+    let height = (y + shelf_height).max(1).next_power_of_two();
+    (width, height, positions)
+}
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::gpu_resources::{self, GpuObject, ShareGroup};
+use super::texture::Texture;
+
+/// The depth-stencil attachment of a framebuffer.
+enum DepthAttachment {
+    /// A renderbuffer, which is fastest when depth is never sampled.
+    Renderbuffer(GLuint),
+    /// A texture, for effects that read scene depth (decals, SSAO, fog).
+    Texture(Texture),
+}
+
+/// # Framebuffer
+///
+/// An offscreen render target with a color texture and a depth-stencil
+/// attachment. Deleted when dropped.
+pub struct Framebuffer {
+    id: GLuint,
+    color: Arc<Texture>,
+    depth_stencil: DepthAttachment,
+    internal_format: GLenum,
+    format: GLenum,
+    data_type: GLenum,
+    group: ShareGroup,
+}
+
+impl Framebuffer {
+    /// Creates an 8-bit sRGB framebuffer. Shaders write linear colors, which
+    /// are stored sRGB-encoded for precision in the darks and decoded back to
+    /// linear when sampled. Use [`Framebuffer::with_format`] with `gl::RGBA8`
+    /// for non-color data.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_format(width, height, gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE)
+    }
+
+    /// Creates a 16-bit floating point framebuffer for HDR rendering, where
+    /// colors may go above 1 until they are tone mapped.
+    pub fn hdr(width: u32, height: u32) -> Self {
+        Self::with_format(width, height, gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT)
+    }
+
+    /// Creates a framebuffer with the given color format.
+    pub fn with_format(
+        width: u32,
+        height: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+    ) -> Self {
+        Self::create(width, height, internal_format, format, data_type, false)
+    }
+
+    /// Creates a framebuffer with the given color format whose depth can be sampled.
+    pub fn with_depth_texture(
+        width: u32,
+        height: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+    ) -> Self {
+        Self::create(width, height, internal_format, format, data_type, true)
+    }
+
+    fn create(
+        width: u32,
+        height: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+        sampled_depth: bool,
+    ) -> Self {
+        let color = Arc::new(Texture::new_2d(
+            width,
+            height,
+            internal_format,
+            format,
+            data_type,
+            None,
+        ));
+
+        let mut id = 0;
+        let depth_stencil;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                color.id(),
+                0,
+            );
+
+            if sampled_depth {
+                let texture = Texture::new_2d(
+                    width,
+                    height,
+                    gl::DEPTH24_STENCIL8,
+                    gl::DEPTH_STENCIL,
+                    gl::UNSIGNED_INT_24_8,
+                    None,
+                );
+                texture.set_filter(gl::NEAREST, gl::NEAREST);
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_STENCIL_ATTACHMENT,
+                    gl::TEXTURE_2D,
+                    texture.id(),
+                    0,
+                );
+                depth_stencil = DepthAttachment::Texture(texture);
+            } else {
+                let mut renderbuffer = 0;
+                gl::GenRenderbuffers(1, &mut renderbuffer);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+                gl::RenderbufferStorage(
+                    gl::RENDERBUFFER,
+                    gl::DEPTH24_STENCIL8,
+                    width as GLsizei,
+                    height as GLsizei,
+                );
+                gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_STENCIL_ATTACHMENT,
+                    gl::RENDERBUFFER,
+                    renderbuffer,
+                );
+                depth_stencil = DepthAttachment::Renderbuffer(renderbuffer);
+            }
+
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                panic!("Framebuffer {}x{} is incomplete", width, height);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Self {
+            id,
+            color,
+            depth_stencil,
+            internal_format,
+            format,
+            data_type,
+            group: ShareGroup::current(),
+        }
+    }
+
+    /// Returns the OpenGL id.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns the color attachment.
+    pub fn color_texture(&self) -> &Texture {
+        &self.color
+    }
+
+    /// Returns a shared handle to the color attachment, e.g. for drawing it with a
+    /// [`SpriteBatch`](super::sprite_batch::SpriteBatch). The texture outlives the framebuffer.
+    pub fn shared_color_texture(&self) -> &Arc<Texture> {
+        &self.color
+    }
+
+    /// Returns the depth-stencil attachment if it was created as a texture.
+    pub fn depth_texture(&self) -> Option<&Texture> {
+        match &self.depth_stencil {
+            DepthAttachment::Texture(texture) => Some(texture),
+            DepthAttachment::Renderbuffer(_) => None,
+        }
+    }
+
+    /// Returns the size in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.color.width(), self.color.height())
+    }
+
+    /// Binds the framebuffer for drawing.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+    }
+
+    /// Binds the default (window) framebuffer.
+    pub fn unbind() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Copies the depth-stencil contents of another framebuffer of the same size into this one.
+    pub fn copy_depth_from(&self, source: &Framebuffer) {
+        let (width, height) = source.size();
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, source.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.id);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                width as GLint,
+                height as GLint,
+                0,
+                0,
+                width as GLint,
+                height as GLint,
+                gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT,
+                gl::NEAREST,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Recreates the attachments at a new size, keeping the formats.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if self.size() != (width, height) {
+            let sampled_depth = self.depth_texture().is_some();
+            *self = Self::create(
+                width,
+                height,
+                self.internal_format,
+                self.format,
+                self.data_type,
+                sampled_depth,
+            );
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        if let DepthAttachment::Renderbuffer(renderbuffer) = self.depth_stencil {
+            gpu_resources::release(self.group, GpuObject::Renderbuffer(renderbuffer));
+        }
+        gpu_resources::release(self.group, GpuObject::Framebuffer(self.id));
+    }
+}
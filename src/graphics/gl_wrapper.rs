@@ -5,13 +5,20 @@ use std::io::Read;
 use std::mem;
 use std::os::raw::*;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use gl::types::*;
 use cgmath::*;
 
+use super::gpu_resources::{self, GpuObject, ShareGroup};
+use crate::math::Color;
+use crate::memory::{self, MemoryCategory};
+use crate::name::Name;
+
 /// # Vertex Array Object (VAO)
 pub struct Vao {
     id: GLuint,
+    group: ShareGroup,
 }
 
 impl Vao {
@@ -21,7 +28,10 @@ impl Vao {
         unsafe {
             gl::GenVertexArrays(1, &mut id);
         }
-        Self { id }
+        Self {
+            id,
+            group: ShareGroup::current(),
+        }
     }
 
     /// Binds the VAO.
@@ -37,13 +47,22 @@ impl Vao {
             gl::BindVertexArray(0);
         }
     }
+
+    /// Deletes the VAO at the next safe point, see [`gpu_resources::release`].
+    pub fn delete(self) {
+        gpu_resources::release(self.group, GpuObject::VertexArray(self.id));
+    }
 }
 
 /// # Buffer Object (VBO)
+///
+/// Its size counts towards [`MemoryCategory::Buffers`] until it is deleted.
 pub struct BufferObject {
     id: GLuint,
     target: GLenum,
     usage: GLenum,
+    size: AtomicUsize,
+    group: ShareGroup,
 }
 
 impl BufferObject {
@@ -53,7 +72,14 @@ impl BufferObject {
         unsafe {
             gl::GenBuffers(1, &mut id);
         }
-        Self { id, target, usage }
+        memory::allocate(MemoryCategory::Buffers, 0);
+        Self {
+            id,
+            target,
+            usage,
+            size: AtomicUsize::new(0),
+            group: ShareGroup::current(),
+        }
     }
 
     /// Binds the buffer object.
@@ -72,27 +98,87 @@ impl BufferObject {
 
     /// Stores float data in the buffer.
     pub fn store_f32_data(&self, data: &[f32]) {
+        self.buffer_data(mem::size_of_val(data), data.as_ptr() as *const c_void);
+    }
+
+    /// Stores integer data in the buffer.
+    pub fn store_i32_data(&self, data: &[i32]) {
+        self.buffer_data(mem::size_of_val(data), data.as_ptr() as *const c_void);
+    }
+
+    /// Stores unsigned integer data in the buffer.
+    pub fn store_u32_data(&self, data: &[u32]) {
+        self.buffer_data(mem::size_of_val(data), data.as_ptr() as *const c_void);
+    }
+
+    /// Overwrites part of the buffer with float data, starting `offset` floats in.
+    /// The buffer must already be large enough.
+    pub fn update_f32_data(&self, offset: usize, data: &[f32]) {
         unsafe {
-            gl::BufferData(
+            gl::BufferSubData(
                 self.target,
-                (data.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                (offset * mem::size_of::<GLfloat>()) as GLintptr,
+                mem::size_of_val(data) as GLsizeiptr,
                 data.as_ptr() as *const c_void,
-                self.usage,
             );
         }
     }
 
-    /// Stores integer data in the buffer.
-    pub fn store_i32_data(&self, data: &[i32]) {
+    /// Overwrites part of the buffer with unsigned integer data, starting
+    /// `offset` integers in. The buffer must already be large enough.
+    pub fn update_u32_data(&self, offset: usize, data: &[u32]) {
+        unsafe {
+            gl::BufferSubData(
+                self.target,
+                (offset * mem::size_of::<GLuint>()) as GLintptr,
+                mem::size_of_val(data) as GLsizeiptr,
+                data.as_ptr() as *const c_void,
+            );
+        }
+    }
+
+    /// Allocates `size` bytes of uninitialized storage.
+    pub fn allocate(&self, size: usize) {
+        self.buffer_data(size, ptr::null());
+    }
+
+    /// Stores raw bytes in the buffer.
+    pub fn store_bytes(&self, data: &[u8]) {
+        self.buffer_data(data.len(), data.as_ptr() as *const c_void);
+    }
+
+    /// Overwrites part of the buffer with raw bytes, starting `offset` bytes in.
+    /// The buffer must already be large enough.
+    pub fn update_bytes(&self, offset: usize, data: &[u8]) {
         unsafe {
-            gl::BufferData(
+            gl::BufferSubData(
                 self.target,
-                (data.len() * mem::size_of::<GLint>()) as GLsizeiptr,
+                offset as GLintptr,
+                data.len() as GLsizeiptr,
                 data.as_ptr() as *const c_void,
-                self.usage,
             );
         }
     }
+
+    /// Deletes the buffer object at the next safe point, see
+    /// [`gpu_resources::release`].
+    pub fn delete(self) {
+        memory::free(MemoryCategory::Buffers, self.size());
+        gpu_resources::release(self.group, GpuObject::Buffer(self.id));
+    }
+
+    /// Returns the size of the buffer's storage in bytes.
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    fn buffer_data(&self, size: usize, data: *const c_void) {
+        unsafe {
+            gl::BufferData(self.target, size as GLsizeiptr, data, self.usage);
+        }
+        let old_size = self.size.swap(size, Ordering::Relaxed);
+        memory::resize(MemoryCategory::Buffers, old_size, size);
+    }
 }
 
 /// # Vertex Attribute
@@ -129,12 +215,55 @@ impl VertexAttribute {
             gl::DisableVertexAttribArray(self.index);
         }
     }
+
+    /// Sets how many instances share each value; 0 advances per vertex, 1 per instance.
+    pub fn set_divisor(&self, divisor: GLuint) {
+        unsafe {
+            gl::VertexAttribDivisor(self.index, divisor);
+        }
+    }
+}
+
+static GLES: AtomicBool = AtomicBool::new(false);
+
+/// Checks if the engine renders with OpenGL ES, which lacks some desktop
+/// OpenGL features and compiles GLSL ES shaders.
+pub fn is_gles() -> bool {
+    GLES.load(Ordering::Relaxed)
+}
+
+/// Sets whether the current context is OpenGL ES. Done by `Window::init_gl`.
+pub fn set_gles(gles: bool) {
+    GLES.store(gles, Ordering::Relaxed);
+}
+
+/// Rewrites a `#version 330 core` shader as GLSL ES 3.00 for OpenGL ES 3,
+/// with high precision defaults for the types ES leaves unset.
+pub fn to_glsl_es(source: &str) -> String {
+    const HEADER: &str = "#version 300 es
+precision highp float;
+precision highp int;
+precision highp sampler2D;
+precision highp sampler2DArray;
+precision highp sampler3D;
+precision highp samplerCube;
+precision highp sampler2DShadow;
+precision highp usampler2D;";
+
+    match source.trim_start().strip_prefix("#version 330 core") {
+        Some(body) => format!("{}{}", HEADER, body),
+        None => source.to_string(),
+    }
 }
 
 /// # Shader Program
+///
+/// Uniforms are looked up by [`Name`]. Passing a `&str` interns it on every
+/// call, so hot paths keep the names of their uniforms around instead.
 pub struct ShaderProgram {
     id: GLuint,
-    uniforms: HashMap<String, GLint>,
+    uniforms: HashMap<Name, GLint>,
+    group: ShareGroup,
 }
 
 impl ShaderProgram {
@@ -142,7 +271,33 @@ impl ShaderProgram {
     pub fn new(vertex_shader_path: &str, fragment_shader_path: &str) -> Self {
         let vertex_shader_source = Self::load_shader_source(vertex_shader_path);
         let fragment_shader_source = Self::load_shader_source(fragment_shader_path);
+        Self::from_source(&vertex_shader_source, &fragment_shader_source)
+    }
 
+    /// Creates a new shader program from vertex and fragment shader source code.
+    /// On OpenGL ES the sources are translated with [`to_glsl_es`].
+    pub fn from_source(vertex_shader_source: &str, fragment_shader_source: &str) -> Self {
+        Self::link(vertex_shader_source, fragment_shader_source, false)
+    }
+
+    /// Compiles and links a program, asking the driver to keep its binary
+    /// around for [`ShaderProgram::binary`] if `retrievable` is set.
+    pub(crate) fn link(
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        retrievable: bool,
+    ) -> Self {
+        let (vertex_shader_source, fragment_shader_source) = if is_gles() {
+            (
+                to_glsl_es(vertex_shader_source),
+                to_glsl_es(fragment_shader_source),
+            )
+        } else {
+            (
+                vertex_shader_source.to_string(),
+                fragment_shader_source.to_string(),
+            )
+        };
         unsafe {
             let vertex_shader = Self::compile_shader(&vertex_shader_source, gl::VERTEX_SHADER);
             let fragment_shader = Self::compile_shader(&fragment_shader_source, gl::FRAGMENT_SHADER);
@@ -150,6 +305,9 @@ impl ShaderProgram {
             let id = gl::CreateProgram();
             gl::AttachShader(id, vertex_shader);
             gl::AttachShader(id, fragment_shader);
+            if retrievable {
+                gl::ProgramParameteri(id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as GLint);
+            }
             gl::LinkProgram(id);
 
             gl::DeleteShader(vertex_shader);
@@ -158,7 +316,60 @@ impl ShaderProgram {
             Self {
                 id,
                 uniforms: HashMap::new(),
+                group: ShareGroup::current(),
+            }
+        }
+    }
+
+    /// Creates a shader program from a binary returned by
+    /// [`ShaderProgram::binary`]. Returns `None` if the driver rejects it,
+    /// which it may do after any driver update.
+    pub fn from_binary(format: GLenum, binary: &[u8]) -> Option<Self> {
+        unsafe {
+            let id = gl::CreateProgram();
+            gl::ProgramBinary(
+                id,
+                format,
+                binary.as_ptr() as *const c_void,
+                binary.len() as GLsizei,
+            );
+            let mut linked = 0;
+            gl::GetProgramiv(id, gl::LINK_STATUS, &mut linked);
+            if linked == 0 {
+                gl::DeleteProgram(id);
+                return None;
+            }
+            Some(Self {
+                id,
+                uniforms: HashMap::new(),
+                group: ShareGroup::current(),
+            })
+        }
+    }
+
+    /// Returns the linked program's binary and its driver-specific format, or
+    /// `None` if it failed to link or the driver won't hand it out.
+    pub fn binary(&self) -> Option<(GLenum, Vec<u8>)> {
+        unsafe {
+            let mut linked = 0;
+            gl::GetProgramiv(self.id, gl::LINK_STATUS, &mut linked);
+            let mut length = 0;
+            gl::GetProgramiv(self.id, gl::PROGRAM_BINARY_LENGTH, &mut length);
+            if linked == 0 || length <= 0 {
+                return None;
             }
+            let mut binary = vec![0u8; length as usize];
+            let mut written = 0;
+            let mut format = 0;
+            gl::GetProgramBinary(
+                self.id,
+                length,
+                &mut written,
+                &mut format,
+                binary.as_mut_ptr() as *mut c_void,
+            );
+            binary.truncate(written.max(0) as usize);
+            (!binary.is_empty()).then_some((format, binary))
         }
     }
 
@@ -193,27 +404,99 @@ impl ShaderProgram {
         }
     }
 
+    /// Deletes the shader program at the next safe point, see
+    /// [`gpu_resources::release`].
+    pub fn delete(self) {
+        gpu_resources::release(self.group, GpuObject::Program(self.id));
+    }
+
     /// Creates a uniform location in the shader program.
-    pub fn create_uniform(&mut self, name: &str) {
+    pub fn create_uniform(&mut self, name: impl Into<Name>) {
+        let name = name.into();
         let location = unsafe {
-            gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr())
+            gl::GetUniformLocation(self.id, CString::new(name.as_str()).unwrap().as_ptr())
         };
         if location < 0 {
             panic!("Uniform '{}' not found in shader program", name);
         } else {
-            self.uniforms.insert(name.to_string(), location);
+            self.uniforms.insert(name, location);
         }
     }
 
+    /// Checks if a uniform location has been created.
+    pub fn has_uniform(&self, name: impl Into<Name>) -> bool {
+        self.uniforms.contains_key(&name.into())
+    }
+
+    /// Returns the location of a created uniform.
+    fn location(&self, name: impl Into<Name>) -> GLint {
+        *self.uniforms.get(&name.into()).expect("Uniform not found")
+    }
+
     /// Sets a matrix uniform (4x4 float) in the shader program.
-    pub fn set_matrix4fv_uniform(&self, name: &str, matrix: &Matrix4<f32>) {
+    pub fn set_matrix4fv_uniform(&self, name: impl Into<Name>, matrix: &Matrix4<f32>) {
         unsafe {
             gl::UniformMatrix4fv(
-                *self.uniforms.get(name).expect("Uniform not found"),
+                self.location(name),
                 1,
                 gl::FALSE,
                 matrix.as_ptr(),
             );
         }
     }
+
+    /// Sets an integer uniform in the shader program.
+    pub fn set_1i_uniform(&self, name: impl Into<Name>, value: i32) {
+        unsafe {
+            gl::Uniform1i(self.location(name), value);
+        }
+    }
+
+    /// Sets a float uniform in the shader program.
+    pub fn set_1f_uniform(&self, name: impl Into<Name>, value: f32) {
+        unsafe {
+            gl::Uniform1f(self.location(name), value);
+        }
+    }
+
+    /// Sets a 2-component float vector uniform in the shader program.
+    pub fn set_2f_uniform(&self, name: impl Into<Name>, x: f32, y: f32) {
+        unsafe {
+            gl::Uniform2f(self.location(name), x, y);
+        }
+    }
+
+    /// Sets a 3-component float vector uniform in the shader program.
+    pub fn set_3f_uniform(&self, name: impl Into<Name>, x: f32, y: f32, z: f32) {
+        unsafe {
+            gl::Uniform3f(self.location(name), x, y, z);
+        }
+    }
+
+    /// Sets a 4-component float vector uniform in the shader program.
+    pub fn set_4f_uniform(&self, name: impl Into<Name>, x: f32, y: f32, z: f32, w: f32) {
+        unsafe {
+            gl::Uniform4f(self.location(name), x, y, z, w);
+        }
+    }
+
+    /// Sets a `vec4` uniform to a color, converted to linear space for
+    /// lighting and blending.
+    pub fn set_color_uniform(&self, name: impl Into<Name>, color: Color) {
+        let linear = color.to_linear();
+        self.set_4f_uniform(name, linear.r, linear.g, linear.b, linear.a);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_shaders_become_glsl_es() {
+        let es = to_glsl_es("#version 330 core\nout vec4 color;\nvoid main() {}\n");
+        assert!(es.starts_with("#version 300 es\nprecision highp float;"));
+        assert!(es.ends_with("\nout vec4 color;\nvoid main() {}\n"));
+        assert_eq!(to_glsl_es("#version 300 es\n"), "#version 300 es\n");
+    }
 }
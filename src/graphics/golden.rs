@@ -0,0 +1,324 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::camera::{Camera, CameraView};
+use super::image::Image;
+use super::render_texture::{RenderTexture, RenderTextureSize};
+use super::window::{Window, WindowOptions};
+use crate::custom_errors::Errors;
+use crate::scene::Transform;
+
+/// Environment variable that makes [`GoldenTest::check`] write references
+/// instead of comparing against them, e.g. `NYANKO_UPDATE_GOLDEN=1 cargo test`.
+pub const UPDATE_GOLDEN_VAR: &str = "NYANKO_UPDATE_GOLDEN";
+
+/// Largest possible YIQ color distance, between black and white.
+const MAX_DELTA: f32 = 35215.0;
+
+/// Creates a hidden window whose OpenGL context offscreen rendering can use.
+/// Tests drawing on the GPU need one, and should run on one thread
+/// (`--test-threads=1`) since contexts are current per thread.
+pub fn offscreen_window() -> Window {
+    let mut window = Window::with_options(
+        64,
+        64,
+        "golden",
+        WindowOptions::new()
+            .with_visible(false)
+            .with_resizable(false),
+    );
+    window.init_gl();
+    window
+}
+
+/// Renders a camera into an offscreen texture of `size` and reads it back as
+/// an image, top row first. `draw` gets the camera's view like any other
+/// camera pass.
+pub fn render_offscreen<F>(camera: Camera, transform: Transform, size: (u32, u32), draw: F) -> Image
+where
+    F: FnOnce(&CameraView),
+{
+    let mut target = RenderTexture::new(camera, RenderTextureSize::Fixed(size.0, size.1))
+        .with_transform(transform);
+    target.render(size, draw);
+    let texture = target.texture();
+    let (width, height) = (texture.width(), texture.height());
+    let pixels = texture.read_rgba8();
+    // Framebuffers store rows bottom-up.
+    let row = (width * 4) as usize;
+    let flipped = pixels.chunks_exact(row).rev().flatten().copied().collect();
+    Image::from_rgba8(width, height, flipped)
+}
+
+/// How different two images may be and still match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoldenTolerance {
+    /// Largest perceptual color distance of a matching pixel, from 0 (exact)
+    /// to 1 (black versus white). The default of 0.1 ignores differences too
+    /// small to see.
+    pub threshold: f32,
+    /// Fraction of pixels that may differ, for driver rounding noise.
+    pub max_differing: f32,
+    /// Whether pixels matching a neighbour of the other image are tolerated,
+    /// so edges that moved by a pixel or anti-alias differently don't fail.
+    pub allow_shift: bool,
+}
+
+impl GoldenTolerance {
+    /// Creates a tolerance that allows no differing pixels.
+    pub fn exact() -> Self {
+        Self {
+            threshold: 0.0,
+            max_differing: 0.0,
+            allow_shift: false,
+        }
+    }
+
+    /// Sets the largest perceptual color distance of a matching pixel.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the fraction of pixels that may differ.
+    pub fn with_max_differing(mut self, max_differing: f32) -> Self {
+        self.max_differing = max_differing;
+        self
+    }
+
+    /// Sets whether edges shifted by a pixel are tolerated.
+    pub fn with_allow_shift(mut self, allow_shift: bool) -> Self {
+        self.allow_shift = allow_shift;
+        self
+    }
+}
+
+impl Default for GoldenTolerance {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            max_differing: 0.001,
+            allow_shift: true,
+        }
+    }
+}
+
+/// The result of comparing an image against a reference.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageComparison {
+    /// Pixels further apart than the threshold.
+    pub differing: usize,
+    /// Pixels that differ but match a neighbour, not counted as differing.
+    pub shifted: usize,
+    pub total: usize,
+    /// Largest perceptual distance of any pixel, from 0 to 1.
+    pub max_distance: f32,
+    /// The reference faded to gray, with differing pixels in red and shifted
+    /// ones in yellow.
+    pub diff: Image,
+}
+
+impl ImageComparison {
+    /// Returns the fraction of pixels that differ.
+    pub fn differing_fraction(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.differing as f32 / self.total as f32
+        }
+    }
+
+    /// Checks if few enough pixels differ for the tolerance.
+    pub fn passed(&self, tolerance: &GoldenTolerance) -> bool {
+        self.differing_fraction() <= tolerance.max_differing
+    }
+}
+
+/// Returns the perceptual distance of two RGBA pixels, from 0 to 1. Colors
+/// are blended over white by their alpha and compared in YIQ space, weighted
+/// like the eye is sensitive: brightness most, then the orange-blue axis.
+pub fn pixel_distance(a: [u8; 4], b: [u8; 4]) -> f32 {
+    if a == b {
+        return 0.0;
+    }
+    let yiq = |pixel: [u8; 4]| {
+        let alpha = pixel[3] as f32 / 255.0;
+        let blend = |channel: u8| 255.0 + (channel as f32 - 255.0) * alpha;
+        let (r, g, b) = (blend(pixel[0]), blend(pixel[1]), blend(pixel[2]));
+        (
+            r * 0.2988953 + g * 0.5866225 + b * 0.11448223,
+            r * 0.59597796 - g * 0.2741761 - b * 0.3218019,
+            r * 0.21147017 - g * 0.5226171 + b * 0.31114694,
+        )
+    };
+    let (ya, ia, qa) = yiq(a);
+    let (yb, ib, qb) = yiq(b);
+    let (y, i, q) = (ya - yb, ia - ib, qa - qb);
+    ((0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_DELTA)
+        .sqrt()
+        .min(1.0)
+}
+
+/// Compares two images of the same size pixel by pixel.
+pub fn compare_images(
+    reference: &Image,
+    actual: &Image,
+    tolerance: &GoldenTolerance,
+) -> Result<ImageComparison, Errors> {
+    if (reference.width, reference.height) != (actual.width, actual.height) {
+        return Err(Errors::GoldenImage(format!(
+            "expected {}x{} pixels, got {}x{}",
+            reference.width, reference.height, actual.width, actual.height
+        )));
+    }
+    let (width, height) = (reference.width, reference.height);
+    let matches_neighbour = |image: &Image, x: u32, y: u32, pixel: [u8; 4]| {
+        (y.saturating_sub(1)..=(y + 1).min(height - 1)).any(|ny| {
+            (x.saturating_sub(1)..=(x + 1).min(width - 1))
+                .any(|nx| pixel_distance(image.pixel(nx, ny), pixel) <= tolerance.threshold)
+        })
+    };
+
+    let mut comparison = ImageComparison {
+        differing: 0,
+        shifted: 0,
+        total: (width * height) as usize,
+        max_distance: 0.0,
+        diff: Image::from_rgba8(width, height, Vec::with_capacity(reference.pixels.len())),
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let (expected, got) = (reference.pixel(x, y), actual.pixel(x, y));
+            let distance = pixel_distance(expected, got);
+            comparison.max_distance = comparison.max_distance.max(distance);
+            let color = if distance <= tolerance.threshold {
+                let gray = (255.0 - pixel_distance(expected, [0, 0, 0, 0]) * 25.5) as u8;
+                [gray, gray, gray, 255]
+            } else if tolerance.allow_shift
+                && matches_neighbour(reference, x, y, got)
+                && matches_neighbour(actual, x, y, expected)
+            {
+                comparison.shifted += 1;
+                [255, 200, 0, 255]
+            } else {
+                comparison.differing += 1;
+                [255, 0, 0, 255]
+            };
+            comparison.diff.pixels.extend_from_slice(&color);
+        }
+    }
+    Ok(comparison)
+}
+
+/// # Golden Test
+///
+/// Checks rendered images against reference PNGs stored with the tests, so
+/// renderer changes that alter the output are caught automatically. On a
+/// mismatch the rendered image and a diff image are written to the output
+/// folder for inspection. A missing reference fails too, after writing the
+/// rendered image as a candidate; run with [`UPDATE_GOLDEN_VAR`] set to
+/// accept new and changed images as references.
+///
+/// ## Example
+/// ```ignore
+/// #[test]
+/// fn draws_lit_cube() {
+///     let _window = offscreen_window();
+///     let camera = Camera::perspective(Deg(60.0), 0.1, 100.0);
+///     let eye = Transform::from_translation(vec3(0.0, 2.0, 5.0));
+///     let image = render_offscreen(camera, eye, (256, 256), |view| draw_scene(view));
+///     GoldenTest::new("tests/golden").check("lit_cube", &image).unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoldenTest {
+    pub reference_directory: PathBuf,
+    pub output_directory: PathBuf,
+    pub tolerance: GoldenTolerance,
+    /// Whether references are written instead of compared against, set from
+    /// [`UPDATE_GOLDEN_VAR`] by default.
+    pub update: bool,
+}
+
+impl GoldenTest {
+    /// Creates a check against references in a folder, writing failures to
+    /// `target/golden`.
+    pub fn new(reference_directory: impl Into<PathBuf>) -> Self {
+        Self {
+            reference_directory: reference_directory.into(),
+            output_directory: PathBuf::from("target/golden"),
+            tolerance: GoldenTolerance::default(),
+            update: std::env::var_os(UPDATE_GOLDEN_VAR).is_some_and(|value| value != "0"),
+        }
+    }
+
+    /// Sets the folder rendered and diff images of failures are written to.
+    pub fn with_output_directory(mut self, output_directory: impl Into<PathBuf>) -> Self {
+        self.output_directory = output_directory.into();
+        self
+    }
+
+    /// Sets how different images may be.
+    pub fn with_tolerance(mut self, tolerance: GoldenTolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets whether references are written instead of compared against.
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Returns the path of a reference image.
+    pub fn reference_path(&self, name: &str) -> PathBuf {
+        self.reference_directory.join(format!("{}.png", name))
+    }
+
+    /// Compares an image against the reference called `name`.
+    pub fn check(&self, name: &str, image: &Image) -> Result<(), Errors> {
+        let reference_path = self.reference_path(name);
+        if self.update {
+            save(&self.reference_directory, &reference_path, image)?;
+            log::info!("Updated golden image {}", reference_path.display());
+            return Ok(());
+        }
+        if !reference_path.exists() {
+            let candidate = self.output_directory.join(format!("{}.png", name));
+            save(&self.output_directory, &candidate, image)?;
+            return Err(Errors::GoldenImage(format!(
+                "{} has no reference, the rendered image is in {}; set {}=1 to accept it",
+                name,
+                candidate.display(),
+                UPDATE_GOLDEN_VAR
+            )));
+        }
+
+        let reference = Image::load(&reference_path.to_string_lossy())?;
+        let comparison = compare_images(&reference, image, &self.tolerance)
+            .map_err(|error| Errors::GoldenImage(format!("{}: {}", name, error)))?;
+        if comparison.passed(&self.tolerance) {
+            return Ok(());
+        }
+        let actual = self.output_directory.join(format!("{}.actual.png", name));
+        let diff = self.output_directory.join(format!("{}.diff.png", name));
+        save(&self.output_directory, &actual, image)?;
+        save(&self.output_directory, &diff, &comparison.diff)?;
+        Err(Errors::GoldenImage(format!(
+            "{}: {} of {} pixels ({:.3}%) differ, up to {:.2}; see {} and {}",
+            name,
+            comparison.differing,
+            comparison.total,
+            comparison.differing_fraction() * 100.0,
+            comparison.max_distance,
+            actual.display(),
+            diff.display()
+        )))
+    }
+}
+
+fn save(directory: &Path, path: &Path, image: &Image) -> Result<(), Errors> {
+    fs::create_dir_all(directory)
+        .map_err(|error| Errors::GoldenImage(format!("{}: {}", directory.display(), error)))?;
+    image.save(&path.to_string_lossy())
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use super::image::Image;
+use super::mesh::{Mesh, MeshBuilder};
+use super::texture::Texture;
+use crate::custom_errors::Errors;
+use crate::name::{AssetPath, Name};
+
+struct TextureAsset {
+    image: Image,
+    srgb: bool,
+    texture: Texture,
+}
+
+impl TextureAsset {
+    fn upload(image: &Image, srgb: bool) -> Texture {
+        if srgb {
+            image.to_texture()
+        } else {
+            image.to_data_texture()
+        }
+    }
+}
+
+struct MeshAsset {
+    builder: MeshBuilder,
+    mesh: Mesh,
+}
+
+/// # GPU Assets
+///
+/// Textures and meshes kept together with the CPU-side data they were built
+/// from, so they can be rebuilt after the OpenGL context is lost to a GPU
+/// reset. Textures keep their [`Image`] and meshes their [`MeshBuilder`].
+///
+/// Once [`ContextRestored`](super::window::ContextRestored) arrives, call
+/// [`GpuAssets::restore`] to upload everything again into the new context.
+/// Textures come back with default sampling settings.
+///
+/// ## Example
+/// ```ignore
+/// let mut assets = GpuAssets::new();
+/// assets.load_texture("assets/textures/brick.png")?;
+/// assets.add_mesh("ground", ground_builder);
+///
+/// // every frame
+/// window.send_context_events(&world);
+/// if !world.events::<ContextRestored>().is_empty() {
+///     assets.restore();
+/// }
+/// assets.texture("assets/textures/brick.png").unwrap().bind(0);
+/// assets.mesh("ground").unwrap().draw();
+/// ```
+#[derive(Default)]
+pub struct GpuAssets {
+    textures: HashMap<AssetPath, TextureAsset>,
+    meshes: HashMap<Name, MeshAsset>,
+}
+
+impl GpuAssets {
+    /// Creates an empty set of assets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a PNG file as an sRGB color texture, or returns the one already
+    /// loaded from that path.
+    pub fn load_texture(&mut self, path: &str) -> Result<&Texture, Errors> {
+        let path = AssetPath::new(path);
+        if !self.textures.contains_key(&path) {
+            let image = Image::load(path.as_str())?;
+            return Ok(self.add_texture(path, image, true));
+        }
+        Ok(&self.textures[&path].texture)
+    }
+
+    /// Uploads an image as a texture, sRGB color art or data such as normal
+    /// maps, replacing the texture of the same path.
+    pub fn add_texture(
+        &mut self,
+        path: impl Into<AssetPath>,
+        image: Image,
+        srgb: bool,
+    ) -> &Texture {
+        let texture = TextureAsset::upload(&image, srgb);
+        let path = path.into();
+        self.textures.insert(
+            path,
+            TextureAsset {
+                image,
+                srgb,
+                texture,
+            },
+        );
+        &self.textures[&path].texture
+    }
+
+    /// Returns a texture by path.
+    pub fn texture(&self, path: impl Into<AssetPath>) -> Option<&Texture> {
+        self.textures.get(&path.into()).map(|asset| &asset.texture)
+    }
+
+    /// Returns the pixels a texture was uploaded from.
+    pub fn image(&self, path: impl Into<AssetPath>) -> Option<&Image> {
+        self.textures.get(&path.into()).map(|asset| &asset.image)
+    }
+
+    /// Removes a texture, deleting it on the GPU.
+    pub fn remove_texture(&mut self, path: impl Into<AssetPath>) -> Option<Image> {
+        self.textures.remove(&path.into()).map(|asset| asset.image)
+    }
+
+    /// Uploads a builder's geometry as a mesh, replacing the mesh of the same
+    /// name.
+    pub fn add_mesh(&mut self, name: impl Into<Name>, mut builder: MeshBuilder) -> &Mesh {
+        let mesh = Mesh::new(&mut builder);
+        let name = name.into();
+        self.meshes.insert(name, MeshAsset { builder, mesh });
+        &self.meshes[&name].mesh
+    }
+
+    /// Returns a mesh by name.
+    pub fn mesh(&self, name: impl Into<Name>) -> Option<&Mesh> {
+        self.meshes.get(&name.into()).map(|asset| &asset.mesh)
+    }
+
+    /// Edits a mesh's geometry and uploads what changed. Returns `false` if
+    /// there is no mesh of that name.
+    pub fn edit_mesh(
+        &mut self,
+        name: impl Into<Name>,
+        edit: impl FnOnce(&mut MeshBuilder),
+    ) -> bool {
+        let Some(asset) = self.meshes.get_mut(&name.into()) else {
+            return false;
+        };
+        edit(&mut asset.builder);
+        asset.mesh.sync(&mut asset.builder);
+        true
+    }
+
+    /// Removes a mesh, returning its geometry.
+    pub fn remove_mesh(&mut self, name: impl Into<Name>) -> Option<MeshBuilder> {
+        self.meshes.remove(&name.into()).map(|asset| asset.builder)
+    }
+
+    /// Returns the number of textures and meshes.
+    pub fn len(&self) -> usize {
+        self.textures.len() + self.meshes.len()
+    }
+
+    /// Checks if there are no textures or meshes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Uploads every texture and mesh again from its CPU-side data, into the
+    /// current context. Call after the context was recreated.
+    pub fn restore(&mut self) {
+        for asset in self.textures.values_mut() {
+            asset.texture = TextureAsset::upload(&asset.image, asset.srgb);
+        }
+        for asset in self.meshes.values_mut() {
+            asset.mesh = Mesh::new(&mut asset.builder);
+        }
+        log::info!(
+            "Restored {} textures and {} meshes",
+            self.textures.len(),
+            self.meshes.len()
+        );
+    }
+}
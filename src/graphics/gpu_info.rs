@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+
+use gl::types::*;
+
+/// The oldest OpenGL version the engine's shaders are written for.
+pub const REQUIRED_GL_VERSION: (u32, u32) = (3, 3);
+
+/// The oldest OpenGL ES version the engine's shaders can be translated to.
+pub const REQUIRED_GLES_VERSION: (u32, u32) = (3, 0);
+
+/// # GPU Info
+///
+/// What the current OpenGL context supports: its version, limits and
+/// extensions. Query it after loading OpenGL, and check it before using
+/// optional features so older drivers get a fallback instead of a crash.
+///
+/// ## Example
+/// ```ignore
+/// window.init_gl();
+/// let gpu = window.gpu_info().unwrap();
+///
+/// let samples = gpu.max_samples.min(4);
+/// if gpu.supports_compute() {
+///     particles.use_compute();
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GpuInfo {
+    pub vendor: String,
+    pub renderer: String,
+    /// The full version string reported by the driver.
+    pub version_string: String,
+    /// The OpenGL version as `(major, minor)`, or the OpenGL ES version if
+    /// `is_gles` is set.
+    pub version: (u32, u32),
+    /// Whether the context is OpenGL ES rather than desktop OpenGL.
+    pub is_gles: bool,
+    pub shading_language_version: String,
+    /// Largest width or height of a 2D texture in pixels.
+    pub max_texture_size: u32,
+    /// Most samples a multisampled framebuffer can have.
+    pub max_samples: u32,
+    /// Number of textures a fragment shader can sample at once.
+    pub max_texture_units: u32,
+    pub extensions: HashSet<String>,
+}
+
+impl GpuInfo {
+    /// Queries the capabilities of the current OpenGL context.
+    pub fn query() -> Self {
+        let mut version = (0, 0);
+        let mut extension_count = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut version.0);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut version.1);
+            gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut extension_count);
+        }
+        let extensions = (0..extension_count.max(0) as GLuint)
+            .map(|index| unsafe { gl_string(gl::GetStringi(gl::EXTENSIONS, index)) })
+            .collect();
+        let version_string = unsafe { gl_string(gl::GetString(gl::VERSION)) };
+
+        Self {
+            vendor: unsafe { gl_string(gl::GetString(gl::VENDOR)) },
+            renderer: unsafe { gl_string(gl::GetString(gl::RENDERER)) },
+            is_gles: version_string.starts_with("OpenGL ES"),
+            version_string,
+            version: (version.0.max(0) as u32, version.1.max(0) as u32),
+            shading_language_version: unsafe {
+                gl_string(gl::GetString(gl::SHADING_LANGUAGE_VERSION))
+            },
+            max_texture_size: get_integer(gl::MAX_TEXTURE_SIZE),
+            max_samples: get_integer(gl::MAX_SAMPLES),
+            max_texture_units: get_integer(gl::MAX_TEXTURE_IMAGE_UNITS),
+            extensions,
+        }
+    }
+
+    /// Checks if the context is at least OpenGL `major.minor`. Always `false`
+    /// on OpenGL ES, see [`GpuInfo::supports_es_version`].
+    pub fn supports_version(&self, major: u32, minor: u32) -> bool {
+        !self.is_gles && self.version >= (major, minor)
+    }
+
+    /// Checks if the context is at least OpenGL ES `major.minor`.
+    pub fn supports_es_version(&self, major: u32, minor: u32) -> bool {
+        self.is_gles && self.version >= (major, minor)
+    }
+
+    /// Checks if the driver exposes an extension, e.g. `"GL_ARB_compute_shader"`.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    /// Checks if compute shaders are available.
+    pub fn supports_compute(&self) -> bool {
+        self.supports_version(4, 3)
+            || self.supports_es_version(3, 1)
+            || self.has_extension("GL_ARB_compute_shader")
+    }
+
+    /// Checks if shader storage buffers are available.
+    pub fn supports_storage_buffers(&self) -> bool {
+        self.supports_version(4, 3)
+            || self.supports_es_version(3, 1)
+            || self.has_extension("GL_ARB_shader_storage_buffer_object")
+    }
+
+    /// Checks if the driver can report errors and warnings through a debug callback.
+    pub fn supports_debug_output(&self) -> bool {
+        self.supports_version(4, 3)
+            || self.supports_es_version(3, 2)
+            || self.has_extension("GL_KHR_debug")
+    }
+
+    /// Checks if anisotropic texture filtering is available.
+    pub fn supports_anisotropy(&self) -> bool {
+        self.supports_version(4, 6)
+            || self.has_extension("GL_ARB_texture_filter_anisotropic")
+            || self.has_extension("GL_EXT_texture_filter_anisotropic")
+    }
+
+    /// Checks if the driver can report GPU resets, see
+    /// [`WindowOptions::with_robustness`](super::window::WindowOptions::with_robustness).
+    pub fn supports_robustness(&self) -> bool {
+        self.supports_version(4, 5)
+            || self.supports_es_version(3, 2)
+            || self.has_extension("GL_KHR_robustness")
+            || self.has_extension("GL_ARB_robustness")
+    }
+
+    /// Checks if the context is new enough for the engine's shaders.
+    pub fn meets_requirements(&self) -> bool {
+        self.supports_version(REQUIRED_GL_VERSION.0, REQUIRED_GL_VERSION.1)
+            || self.supports_es_version(REQUIRED_GLES_VERSION.0, REQUIRED_GLES_VERSION.1)
+    }
+
+    /// Routes driver errors and warnings to the log. Returns `false` and logs
+    /// a warning if the driver doesn't support debug output.
+    pub fn enable_debug_output(&self) -> bool {
+        if !self.supports_debug_output() || !gl::DebugMessageCallback::is_loaded() {
+            log::warn!(
+                "OpenGL debug output is not supported by {} ({}), continuing without it",
+                self.renderer,
+                self.version_string
+            );
+            return false;
+        }
+        unsafe {
+            gl::Enable(gl::DEBUG_OUTPUT);
+            gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            gl::DebugMessageCallback(Some(debug_callback), std::ptr::null());
+        }
+        true
+    }
+}
+
+fn get_integer(name: GLenum) -> u32 {
+    let mut value = 0;
+    unsafe {
+        gl::GetIntegerv(name, &mut value);
+    }
+    value.max(0) as u32
+}
+
+unsafe fn gl_string(string: *const GLubyte) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(string as *const c_char)
+        .to_string_lossy()
+        .into_owned()
+}
+
+extern "system" fn debug_callback(
+    _source: GLenum,
+    _kind: GLenum,
+    _id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    _user_data: *mut c_void,
+) {
+    let message = unsafe {
+        let bytes = std::slice::from_raw_parts(message as *const u8, length.max(0) as usize);
+        String::from_utf8_lossy(bytes)
+    };
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH => log::error!("OpenGL: {}", message),
+        gl::DEBUG_SEVERITY_MEDIUM => log::warn!("OpenGL: {}", message),
+        gl::DEBUG_SEVERITY_LOW => log::info!("OpenGL: {}", message),
+        _ => log::debug!("OpenGL: {}", message),
+    }
+}
@@ -0,0 +1,244 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+use gl::types::*;
+
+static PENDING: Mutex<Vec<(ShareGroup, GpuObject)>> = Mutex::new(Vec::new());
+static RETIRED: Mutex<Vec<ShareGroup>> = Mutex::new(Vec::new());
+static GL_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+static NEXT_GROUP: AtomicU64 = AtomicU64::new(1);
+static RELEASED: AtomicU64 = AtomicU64::new(0);
+static DELETED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static CURRENT_GROUP: Cell<ShareGroup> = const { Cell::new(ShareGroup(0)) };
+}
+
+/// # Share Group
+///
+/// Identifies a set of OpenGL contexts sharing their objects: a window and
+/// the windows created from it with
+/// [`Window::create_shared`](super::window::Window::create_shared). Object
+/// names only mean something within their group, so every GPU object
+/// remembers the group it was created in and is deleted there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShareGroup(u64);
+
+impl ShareGroup {
+    /// Creates a group for a new context that shares nothing.
+    pub(crate) fn new() -> Self {
+        Self(NEXT_GROUP.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Returns the group of the context current on the calling thread.
+    pub fn current() -> Self {
+        CURRENT_GROUP.with(Cell::get)
+    }
+}
+
+/// An OpenGL object name waiting to be deleted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GpuObject {
+    Texture(GLuint),
+    Buffer(GLuint),
+    VertexArray(GLuint),
+    Framebuffer(GLuint),
+    Renderbuffer(GLuint),
+    Program(GLuint),
+    Shader(GLuint),
+}
+
+/// Counts of GPU objects released and deleted since startup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GpuResourceStats {
+    /// Released objects waiting for the next flush.
+    pub pending: usize,
+    pub released: u64,
+    pub deleted: u64,
+}
+
+/// Records the calling thread as the one owning the OpenGL context. Called
+/// by [`Window::init_gl`](super::window::Window::init_gl).
+pub fn set_gl_thread() {
+    *lock(&GL_THREAD) = Some(thread::current().id());
+}
+
+/// Records `group` as the group of the context just made current on the
+/// calling thread. Called by [`Window`](super::window::Window) whenever it
+/// makes its context current.
+pub fn make_current(group: ShareGroup) {
+    CURRENT_GROUP.with(|current| current.set(group));
+}
+
+/// Checks if the calling thread owns the OpenGL context.
+pub fn is_gl_thread() -> bool {
+    *lock(&GL_THREAD) == Some(thread::current().id())
+}
+
+/// Queues a GPU object for deletion at the next safe point. Safe to call
+/// from any thread, which is what makes dropping the last `Arc<Texture>` on a
+/// loading thread or a job worker harmless: OpenGL calls off the context's
+/// thread either fail or crash the driver.
+///
+/// Every engine type owning GPU memory releases through here when dropped,
+/// so `Arc` reference counts decide when a resource dies, and this queue
+/// decides where and when it is actually deleted. `group` is the share group
+/// the object was created in, see [`ShareGroup::current`]; objects of a
+/// group whose context is gone are dropped without deleting.
+pub fn release(group: ShareGroup, object: GpuObject) {
+    if object_name(object) == 0 {
+        return;
+    }
+    RELEASED.fetch_add(1, Ordering::Relaxed);
+    if lock(&RETIRED).contains(&group) {
+        return;
+    }
+    lock(&PENDING).push((group, object));
+}
+
+/// Deletes the queued objects of the share group current on this thread and
+/// returns how many there were. Must run on the context's thread at a point
+/// where no draw still needs them;
+/// [`Window::present`](super::window::Window::present) calls it after
+/// swapping buffers. Does nothing elsewhere or before OpenGL is loaded.
+pub fn flush_deletions() -> usize {
+    if !is_gl_thread() || !gl::DeleteTextures::is_loaded() {
+        return 0;
+    }
+    let pending = take_group(ShareGroup::current());
+    if pending.is_empty() {
+        return 0;
+    }
+
+    let names = |kind: fn(GpuObject) -> Option<GLuint>| -> Vec<GLuint> {
+        pending.iter().filter_map(|object| kind(*object)).collect()
+    };
+    let textures = names(|object| match object {
+        GpuObject::Texture(name) => Some(name),
+        _ => None,
+    });
+    let buffers = names(|object| match object {
+        GpuObject::Buffer(name) => Some(name),
+        _ => None,
+    });
+    let vertex_arrays = names(|object| match object {
+        GpuObject::VertexArray(name) => Some(name),
+        _ => None,
+    });
+    let framebuffers = names(|object| match object {
+        GpuObject::Framebuffer(name) => Some(name),
+        _ => None,
+    });
+    let renderbuffers = names(|object| match object {
+        GpuObject::Renderbuffer(name) => Some(name),
+        _ => None,
+    });
+    unsafe {
+        // Framebuffers first, so their attachments aren't deleted while
+        // still attached.
+        if !framebuffers.is_empty() {
+            gl::DeleteFramebuffers(framebuffers.len() as GLsizei, framebuffers.as_ptr());
+        }
+        if !renderbuffers.is_empty() {
+            gl::DeleteRenderbuffers(renderbuffers.len() as GLsizei, renderbuffers.as_ptr());
+        }
+        if !textures.is_empty() {
+            gl::DeleteTextures(textures.len() as GLsizei, textures.as_ptr());
+        }
+        if !vertex_arrays.is_empty() {
+            gl::DeleteVertexArrays(vertex_arrays.len() as GLsizei, vertex_arrays.as_ptr());
+        }
+        if !buffers.is_empty() {
+            gl::DeleteBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+        }
+        // Programs and shaders have no batched delete.
+        for object in &pending {
+            match *object {
+                GpuObject::Program(name) => gl::DeleteProgram(name),
+                GpuObject::Shader(name) => gl::DeleteShader(name),
+                _ => {}
+            }
+        }
+    }
+    DELETED.fetch_add(pending.len() as u64, Ordering::Relaxed);
+    pending.len()
+}
+
+/// Forgets the queued objects of `group` without deleting them, for when
+/// the last context of the group is gone and their names mean nothing
+/// anymore. Objects of the group released later are dropped too. Returns how
+/// many were dropped.
+pub fn discard_pending(group: ShareGroup) -> usize {
+    lock(&RETIRED).push(group);
+    let discarded = take_group(group).len();
+    if discarded > 0 {
+        log::debug!("Discarded {} GPU objects of a lost context", discarded);
+    }
+    discarded
+}
+
+/// Returns release and deletion counts, e.g. for a debug overlay or to spot
+/// leaks.
+pub fn stats() -> GpuResourceStats {
+    GpuResourceStats {
+        pending: lock(&PENDING).len(),
+        released: RELEASED.load(Ordering::Relaxed),
+        deleted: DELETED.load(Ordering::Relaxed),
+    }
+}
+
+/// Removes and returns the queued objects of one group.
+fn take_group(group: ShareGroup) -> Vec<GpuObject> {
+    let mut pending = lock(&PENDING);
+    let mut taken = Vec::new();
+    pending.retain(|&(owner, object)| {
+        if owner == group {
+            taken.push(object);
+        }
+        owner != group
+    });
+    taken
+}
+
+fn object_name(object: GpuObject) -> GLuint {
+    match object {
+        GpuObject::Texture(name)
+        | GpuObject::Buffer(name)
+        | GpuObject::VertexArray(name)
+        | GpuObject::Framebuffer(name)
+        | GpuObject::Renderbuffer(name)
+        | GpuObject::Program(name)
+        | GpuObject::Shader(name) => name,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    // A panic while holding the lock leaves the data intact; keep going.
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discarding_a_group_keeps_other_groups_queued() {
+        let lost = ShareGroup::new();
+        let alive = ShareGroup::new();
+        release(lost, GpuObject::Texture(7));
+        release(alive, GpuObject::Texture(7));
+        release(alive, GpuObject::Buffer(3));
+
+        assert_eq!(discard_pending(lost), 1);
+        release(lost, GpuObject::Texture(8));
+        assert!(take_group(lost).is_empty());
+        assert_eq!(
+            take_group(alive),
+            vec![GpuObject::Texture(7), GpuObject::Buffer(3)]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use super::texture::Texture;
+use crate::custom_errors::Errors;
+use crate::ecs::schedule::system_jobs;
+
+/// # Image
+///
+/// 8-bit RGBA pixels in CPU memory, top row first, as decoded from an image
+/// file. Used for window icons and cursors, or uploaded as a texture.
+///
+/// ## Example
+/// ```ignore
+/// let icon = Image::load("assets/icon.png")?;
+/// window.set_icon(&[icon]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image from 8-bit RGBA pixels, top row first.
+    pub fn from_rgba8(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Loads a PNG file, converting any color type to RGBA.
+    pub fn load(path: &str) -> Result<Self, Errors> {
+        let file = File::open(path)
+            .map_err(|error| Errors::InvalidImage(format!("{}: {}", path, error)))?;
+        Self::decode_png(BufReader::new(file))
+            .map_err(|error| Errors::InvalidImage(format!("{}: {}", path, error)))
+    }
+
+    /// Loads several PNG files, decoding them in parallel on the job system.
+    /// Results keep the order of `paths`.
+    pub fn load_all(paths: &[&str]) -> Vec<Result<Self, Errors>> {
+        system_jobs().par_map(paths, |path| Self::load(path))
+    }
+
+    /// Saves the image as an RGBA PNG file.
+    pub fn save(&self, path: &str) -> Result<(), Errors> {
+        let error = |message: String| Errors::InvalidImage(format!("{}: {}", path, message));
+        let file = File::create(path).map_err(|io| error(io.to_string()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|encoding| error(encoding.to_string()))?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|encoding| error(encoding.to_string()))
+    }
+
+    /// Returns the RGBA value of a pixel, counting rows from the top.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    /// Returns the image at half the size, rounded down but at least one
+    /// pixel, each pixel averaging the two by two pixels it covers. Used to
+    /// build mip levels.
+    pub fn half_size(&self) -> Self {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, y0) = ((x * 2).min(self.width - 1), (y * 2).min(self.height - 1));
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                let samples = [
+                    self.pixel(x0, y0),
+                    self.pixel(x1, y0),
+                    self.pixel(x0, y1),
+                    self.pixel(x1, y1),
+                ];
+                for channel in 0..4 {
+                    let sum: u32 = samples.iter().map(|pixel| pixel[channel] as u32).sum();
+                    pixels.push(((sum + 2) / 4) as u8);
+                }
+            }
+        }
+        Self::from_rgba8(width, height, pixels)
+    }
+
+    fn decode_png(source: impl std::io::Read) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(source);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let data = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => data.to_vec(),
+            png::ColorType::Rgb => data
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => data
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            // Palettes are expanded to RGB or RGBA by the transformations.
+            png::ColorType::Grayscale | png::ColorType::Indexed => data
+                .iter()
+                .flat_map(|&value| [value, value, value, 255])
+                .collect(),
+        };
+        Ok(Self::from_rgba8(info.width, info.height, pixels))
+    }
+
+    /// Uploads the image as an sRGB color texture.
+    pub fn to_texture(&self) -> Texture {
+        Texture::from_srgba8(self.width, self.height, &self.pixels)
+    }
+
+    /// Uploads the image as a texture sampled without color conversion, for
+    /// data such as normal maps.
+    pub fn to_data_texture(&self) -> Texture {
+        Texture::from_rgba8(self.width, self.height, &self.pixels)
+    }
+
+    pub(crate) fn to_glfw(&self) -> glfw::PixelImage {
+        rgba8_to_glfw(self.width, self.height, &self.pixels)
+    }
+}
+
+/// Packs 8-bit RGBA pixels the way GLFW expects them for icons and cursors.
+pub(crate) fn rgba8_to_glfw(width: u32, height: u32, pixels: &[u8]) -> glfw::PixelImage {
+    glfw::PixelImage {
+        width,
+        height,
+        pixels: pixels
+            .chunks_exact(4)
+            .map(|pixel| u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]))
+            .collect(),
+    }
+}
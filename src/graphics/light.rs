@@ -0,0 +1,111 @@
+use crate::math::*;
+
+/// # Directional Light
+///
+/// A light infinitely far away shining in a single direction, like the sun.
+/// `direction` is the direction the light travels in, so a noon sun points
+/// straight down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    pub direction: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    /// Creates a white light travelling in `direction`.
+    pub fn new(direction: Vec3) -> Self {
+        Self {
+            direction: direction.normalize(),
+            color: Color::WHITE,
+            intensity: 1.0,
+        }
+    }
+
+    /// Sets the light's color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the light's intensity.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Returns the unit direction pointing from the scene towards the light.
+    pub fn to_light(&self) -> Vec3 {
+        -self.direction.normalize()
+    }
+
+    /// Returns the light's linear color multiplied by its intensity.
+    pub fn radiance(&self) -> Vec3 {
+        let linear = self.color.to_linear();
+        vec3(linear.r, linear.g, linear.b) * self.intensity
+    }
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self::new(vec3(-0.3, -1.0, -0.2))
+    }
+}
+
+/// # Point Light
+///
+/// A light radiating in every direction from its entity's `Transform`, fading
+/// to nothing at `range`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    pub cast_shadows: bool,
+    /// Extra weight when competing for the limited number of shadow maps.
+    pub shadow_priority: f32,
+}
+
+impl PointLight {
+    /// Creates a white light reaching `range` world units.
+    pub fn new(range: f32) -> Self {
+        Self {
+            color: Color::WHITE,
+            intensity: 1.0,
+            range,
+            cast_shadows: false,
+            shadow_priority: 1.0,
+        }
+    }
+
+    /// Sets the light's color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the light's intensity.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Makes the light cast shadows, competing for a shadow map with `priority`.
+    pub fn with_shadows(mut self, priority: f32) -> Self {
+        self.cast_shadows = true;
+        self.shadow_priority = priority;
+        self
+    }
+
+    /// Returns the light's linear color multiplied by its intensity.
+    pub fn radiance(&self) -> Vec3 {
+        let linear = self.color.to_linear();
+        vec3(linear.r, linear.g, linear.b) * self.intensity
+    }
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        Self::new(10.0)
+    }
+}
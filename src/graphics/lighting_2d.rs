@@ -0,0 +1,616 @@
+use std::mem;
+
+use gl::types::*;
+
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::texture::Texture;
+use crate::math::projection::orthographic;
+use crate::math::*;
+use crate::name;
+
+const SHADOW_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_position;
+
+uniform mat4 u_projection;
+
+out vec2 v_position;
+
+void main() {
+    v_position = a_position;
+    gl_Position = u_projection * vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+const SHADOW_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+out vec4 out_color;
+
+void main() {
+    out_color = vec4(0.0);
+}
+"#;
+
+const LIGHT_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_position;
+out vec4 out_color;
+
+uniform sampler2D u_normals;
+uniform vec2 u_viewport;
+uniform vec2 u_center;
+uniform vec3 u_color;
+uniform float u_radius;
+uniform float u_falloff;
+uniform float u_height;
+uniform float u_y_sign;
+uniform vec2 u_cone_direction;
+uniform float u_cone_outer;
+uniform float u_cone_inner;
+
+void main() {
+    vec2 offset = v_position - u_center;
+    float distance = length(offset);
+    if (distance >= u_radius) {
+        discard;
+    }
+    float attenuation = pow(1.0 - distance / u_radius, u_falloff);
+
+    if (u_cone_outer > -1.0) {
+        float cosine = dot(offset / max(distance, 0.0001), u_cone_direction);
+        attenuation *= smoothstep(u_cone_outer, u_cone_inner, cosine);
+    }
+
+    vec3 normal = texture(u_normals, gl_FragCoord.xy / u_viewport).xyz * 2.0 - 1.0;
+    vec3 to_light = normalize(vec3(-offset.x, -offset.y * u_y_sign, u_height));
+    float diffuse = max(dot(normalize(normal), to_light), 0.0);
+
+    out_color = vec4(u_color * attenuation * diffuse, 1.0);
+}
+"#;
+
+const COMPOSITE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_scene;
+uniform sampler2D u_lights;
+uniform vec3 u_ambient;
+
+void main() {
+    vec4 scene = texture(u_scene, v_uv);
+    vec3 light = u_ambient + texture(u_lights, v_uv).rgb;
+    out_color = vec4(scene.rgb * light, scene.a);
+}
+"#;
+
+/// Normal map color for a surface facing the viewer, used where no
+/// normal-mapped sprite was drawn.
+const FLAT_NORMAL: Color = Color::new(0.5, 0.5, 1.0, 1.0);
+
+/// A light's cone, for flashlights and spotlights.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightCone {
+    /// Direction the cone points, as an angle from +X towards +Y.
+    pub direction: Rad<f32>,
+    /// Half-angle of the fully lit part.
+    pub inner: Rad<f32>,
+    /// Half-angle where the light reaches zero.
+    pub outer: Rad<f32>,
+}
+
+/// # Light 2D
+///
+/// A point or cone light in the same coordinates as the sprites it lights.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light2d {
+    pub position: Vec2,
+    pub color: Color,
+    pub intensity: f32,
+    /// Distance at which the light fades out completely.
+    pub radius: f32,
+    /// How quickly the light fades with distance; `1` is linear, higher is
+    /// more concentrated around the center.
+    pub falloff: f32,
+    /// Height above the sprites, in the same units as `radius`. Lower lights
+    /// rake across normal maps for stronger relief.
+    pub height: f32,
+    pub cone: Option<LightCone>,
+    pub casts_shadows: bool,
+}
+
+impl Light2d {
+    /// Creates a point light.
+    pub fn point(position: Vec2, radius: f32, color: Color) -> Self {
+        Self {
+            position,
+            color,
+            intensity: 1.0,
+            radius,
+            falloff: 2.0,
+            height: radius * 0.25,
+            cone: None,
+            casts_shadows: true,
+        }
+    }
+
+    /// Creates a cone light pointing along `direction`, spreading `angle` to each side.
+    pub fn cone<A: Into<Rad<f32>>>(
+        position: Vec2,
+        radius: f32,
+        direction: A,
+        angle: A,
+        color: Color,
+    ) -> Self {
+        let outer = angle.into();
+        Self {
+            cone: Some(LightCone {
+                direction: direction.into(),
+                inner: outer * 0.8,
+                outer,
+            }),
+            ..Self::point(position, radius, color)
+        }
+    }
+
+    /// Sets the brightness multiplier.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Sets the falloff exponent.
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Sets the height above the sprites.
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Stops occluders from blocking the light.
+    pub fn without_shadows(mut self) -> Self {
+        self.casts_shadows = false;
+        self
+    }
+}
+
+/// # Occluder 2D
+///
+/// An outline that blocks light, such as a wall, crate, or character.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occluder2d {
+    pub points: Vec<Vec2>,
+    /// Whether the last point connects back to the first.
+    pub closed: bool,
+}
+
+impl Occluder2d {
+    /// Creates a closed polygon.
+    pub fn polygon(points: Vec<Vec2>) -> Self {
+        Self {
+            points,
+            closed: true,
+        }
+    }
+
+    /// Creates an open chain of segments, e.g. for terrain edges.
+    pub fn chain(points: Vec<Vec2>) -> Self {
+        Self {
+            points,
+            closed: false,
+        }
+    }
+
+    /// Creates a rectangle.
+    pub fn rect(rect: Rect) -> Self {
+        let (min, max) = (rect.min(), rect.max());
+        Self::polygon(vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y)])
+    }
+
+    /// Creates a circle approximated by `segments` edges.
+    pub fn circle(center: Vec2, radius: f32, segments: usize) -> Self {
+        let segments = segments.max(3);
+        Self::polygon(
+            (0..segments)
+                .map(|index| {
+                    let angle = index as f32 / segments as f32 * std::f32::consts::TAU;
+                    center + vec2(angle.cos(), angle.sin()) * radius
+                })
+                .collect(),
+        )
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let count = if self.closed {
+            self.points.len()
+        } else {
+            self.points.len().saturating_sub(1)
+        };
+        (0..count).map(|index| {
+            (
+                self.points[index],
+                self.points[(index + 1) % self.points.len()],
+            )
+        })
+    }
+}
+
+/// # Lighting 2D
+///
+/// Lights and shadows for sprite scenes. Lights are drawn one at a time into
+/// a light map, each masked by the shadows its occluders cast through the
+/// stencil buffer, then the light map multiplies the scene together with an
+/// ambient color.
+///
+/// Sprites can be normal-mapped by drawing their normal maps, with the same
+/// sprite batch and geometry, between [`Lighting2d::begin_normals`] and
+/// [`Lighting2d::end_normals`]. Anything left undrawn is treated as facing the
+/// viewer.
+///
+/// Lights and occluders use the same coordinates as the sprite batch: pixels
+/// with a top-left origin by default, or whatever `projection` is passed.
+///
+/// ## Example
+/// ```ignore
+/// let mut lighting = Lighting2d::new();
+/// lighting.ambient = Color::rgb(0.1, 0.1, 0.15);
+///
+/// // Draw the scene into `scene` as usual, then:
+/// lighting.begin_normals(size);
+/// batch.begin(size);
+/// batch.draw_sprite(&wall_normals, wall_rect, full_uv, Color::WHITE);
+/// batch.end();
+/// lighting.end_normals();
+///
+/// lighting.add_occluder(Occluder2d::rect(wall_rect));
+/// lighting.add_light(Light2d::point(torch, 300.0, Color::rgb(1.0, 0.7, 0.4)));
+/// lighting.render(size);
+/// lighting.composite(scene.color_texture(), None);
+/// ```
+pub struct Lighting2d {
+    pub ambient: Color,
+    lights: Vec<Light2d>,
+    occluders: Vec<Occluder2d>,
+    light_map: Option<Framebuffer>,
+    normals: Option<Framebuffer>,
+    shadow_shader: ShaderProgram,
+    light_shader: ShaderProgram,
+    composite_shader: ShaderProgram,
+    vao: Vao,
+    vertex_buffer: BufferObject,
+    triangle: Primitive,
+    vertices: Vec<f32>,
+}
+
+impl Lighting2d {
+    /// Creates the lighting pass with a dim ambient light.
+    pub fn new() -> Self {
+        let mut shadow_shader =
+            ShaderProgram::from_source(SHADOW_VERTEX_SHADER, SHADOW_FRAGMENT_SHADER);
+        shadow_shader.create_uniform("u_projection");
+        let mut light_shader =
+            ShaderProgram::from_source(SHADOW_VERTEX_SHADER, LIGHT_FRAGMENT_SHADER);
+        for uniform in [
+            "u_projection",
+            "u_normals",
+            "u_viewport",
+            "u_center",
+            "u_color",
+            "u_radius",
+            "u_falloff",
+            "u_height",
+            "u_y_sign",
+            "u_cone_direction",
+            "u_cone_outer",
+            "u_cone_inner",
+        ] {
+            light_shader.create_uniform(uniform);
+        }
+        let mut composite_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, COMPOSITE_FRAGMENT_SHADER);
+        for uniform in ["u_scene", "u_lights", "u_ambient"] {
+            composite_shader.create_uniform(uniform);
+        }
+
+        let vao = Vao::new();
+        vao.bind();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::STREAM_DRAW);
+        vertex_buffer.bind();
+        let position = VertexAttribute::new(
+            0,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            2 * mem::size_of::<GLfloat>() as GLsizei,
+            std::ptr::null(),
+        );
+        position.enable();
+        Vao::unbind();
+
+        Self {
+            ambient: Color::rgb(0.15, 0.15, 0.2),
+            lights: Vec::new(),
+            occluders: Vec::new(),
+            light_map: None,
+            normals: None,
+            shadow_shader,
+            light_shader,
+            composite_shader,
+            vao,
+            vertex_buffer,
+            triangle: Primitive::fullscreen_triangle(),
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Queues a light for the next [`Lighting2d::render`].
+    pub fn add_light(&mut self, light: Light2d) {
+        self.lights.push(light);
+    }
+
+    /// Queues an occluder for the next [`Lighting2d::render`].
+    pub fn add_occluder(&mut self, occluder: Occluder2d) {
+        self.occluders.push(occluder);
+    }
+
+    /// Returns the light map from the last [`Lighting2d::render`].
+    pub fn light_map(&self) -> Option<&Texture> {
+        self.light_map.as_ref().map(Framebuffer::color_texture)
+    }
+
+    /// Creates or resizes the light map and normal buffer.
+    fn prepare(&mut self, size: (u32, u32)) {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        for (target, format) in [
+            (&mut self.light_map, gl::RGBA16F),
+            (&mut self.normals, gl::RGBA8),
+        ] {
+            match target {
+                Some(framebuffer) if framebuffer.size() == (width, height) => {}
+                Some(framebuffer) => framebuffer.resize(width, height),
+                None => {
+                    let data_type = if format == gl::RGBA16F {
+                        gl::HALF_FLOAT
+                    } else {
+                        gl::UNSIGNED_BYTE
+                    };
+                    *target = Some(Framebuffer::with_format(
+                        width,
+                        height,
+                        format,
+                        gl::RGBA,
+                        data_type,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Binds and clears the normal buffer for drawing sprite normal maps.
+    pub fn begin_normals(&mut self, size: (u32, u32)) {
+        self.prepare(size);
+        let normals = self.normals.as_ref().unwrap();
+        normals.bind();
+        unsafe {
+            gl::Viewport(0, 0, size.0 as i32, size.1 as i32);
+            gl::ClearColor(FLAT_NORMAL.r, FLAT_NORMAL.g, FLAT_NORMAL.b, FLAT_NORMAL.a);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    /// Finishes drawing normal maps.
+    pub fn end_normals(&mut self) {
+        Framebuffer::unbind();
+    }
+
+    /// Renders the queued lights into the light map in pixel coordinates with
+    /// a top-left origin, then clears the queue.
+    pub fn render(&mut self, size: (u32, u32)) {
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        self.render_with_projection(orthographic(0.0, width, height, 0.0, -1.0, 1.0), size);
+    }
+
+    /// Renders the queued lights with a custom projection, e.g. a 2D camera's,
+    /// then clears the queue.
+    pub fn render_with_projection(&mut self, projection: Mat4, size: (u32, u32)) {
+        let had_normals = self.normals.is_some();
+        self.prepare(size);
+        if !had_normals {
+            self.begin_normals(size);
+        }
+        let (Some(light_map), Some(normals)) = (&self.light_map, &self.normals) else {
+            return;
+        };
+
+        light_map.bind();
+        unsafe {
+            gl::Viewport(0, 0, size.0 as i32, size.1 as i32);
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::ClearStencil(0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::STENCIL_TEST);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+        self.vao.bind();
+        self.vertex_buffer.bind();
+
+        // Y grows downwards on screen when the projection flips it.
+        let y_sign = if projection.y.y < 0.0 { -1.0 } else { 1.0 };
+        self.shadow_shader.bind();
+        self.shadow_shader
+            .set_matrix4fv_uniform(name!("u_projection"), &projection);
+        self.light_shader.bind();
+        self.light_shader
+            .set_matrix4fv_uniform(name!("u_projection"), &projection);
+        self.light_shader.set_1i_uniform(name!("u_normals"), 0);
+        self.light_shader
+            .set_2f_uniform(name!("u_viewport"), size.0 as f32, size.1 as f32);
+        self.light_shader.set_1f_uniform(name!("u_y_sign"), y_sign);
+        normals.color_texture().bind(0);
+
+        for light in &self.lights {
+            unsafe {
+                gl::Clear(gl::STENCIL_BUFFER_BIT);
+            }
+            if light.casts_shadows {
+                self.vertices.clear();
+                for occluder in &self.occluders {
+                    for (start, end) in occluder.edges() {
+                        push_shadow(&mut self.vertices, light, start, end);
+                    }
+                }
+                if !self.vertices.is_empty() {
+                    self.shadow_shader.bind();
+                    unsafe {
+                        gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+                        gl::StencilFunc(gl::ALWAYS, 1, 0xff);
+                        gl::StencilOp(gl::KEEP, gl::KEEP, gl::REPLACE);
+                    }
+                    self.draw_vertices();
+                    unsafe {
+                        gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                    }
+                }
+            }
+
+            self.light_shader.bind();
+            let color = light.color.to_linear();
+            let intensity = light.intensity;
+            self.light_shader
+                .set_2f_uniform(name!("u_center"), light.position.x, light.position.y);
+            self.light_shader.set_3f_uniform(
+                name!("u_color"),
+                color.r * intensity,
+                color.g * intensity,
+                color.b * intensity,
+            );
+            self.light_shader
+                .set_1f_uniform(name!("u_radius"), light.radius.max(1e-3));
+            self.light_shader
+                .set_1f_uniform(name!("u_falloff"), light.falloff.max(1e-3));
+            self.light_shader
+                .set_1f_uniform(name!("u_height"), light.height.max(1e-3));
+            let (direction, outer, inner) = match light.cone {
+                Some(cone) => (
+                    vec2(cone.direction.0.cos(), cone.direction.0.sin()),
+                    cone.outer.0.cos(),
+                    cone.inner.0.min(cone.outer.0 - 1e-3).cos(),
+                ),
+                None => (vec2(1.0, 0.0), -2.0, -1.0),
+            };
+            self.light_shader
+                .set_2f_uniform(name!("u_cone_direction"), direction.x, direction.y);
+            self.light_shader
+                .set_1f_uniform(name!("u_cone_outer"), outer);
+            self.light_shader
+                .set_1f_uniform(name!("u_cone_inner"), inner);
+
+            let (min, max) = (
+                light.position - vec2(light.radius, light.radius),
+                light.position + vec2(light.radius, light.radius),
+            );
+            self.vertices.clear();
+            self.vertices.extend_from_slice(&[
+                min.x, min.y, max.x, min.y, max.x, max.y, min.x, min.y, max.x, max.y, min.x, max.y,
+            ]);
+            unsafe {
+                gl::Enable(gl::BLEND);
+                gl::StencilFunc(gl::EQUAL, 0, 0xff);
+                gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+            }
+            self.draw_vertices();
+            unsafe {
+                gl::Disable(gl::BLEND);
+            }
+        }
+
+        unsafe {
+            gl::Disable(gl::STENCIL_TEST);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        Vao::unbind();
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+
+        self.lights.clear();
+        self.occluders.clear();
+    }
+
+    fn draw_vertices(&self) {
+        self.vertex_buffer.store_f32_data(&self.vertices);
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, (self.vertices.len() / 2) as GLsizei);
+        }
+    }
+
+    /// Draws `scene` lit by the last light map into `target`, or into the
+    /// window if `target` is `None`.
+    pub fn composite(&self, scene: &Texture, target: Option<&Framebuffer>) {
+        let Some(light_map) = self.light_map() else {
+            return;
+        };
+        let (width, height) = match target {
+            Some(target) => {
+                target.bind();
+                target.size()
+            }
+            None => {
+                Framebuffer::unbind();
+                (light_map.width(), light_map.height())
+            }
+        };
+
+        self.composite_shader.bind();
+        self.composite_shader.set_1i_uniform(name!("u_scene"), 0);
+        self.composite_shader.set_1i_uniform(name!("u_lights"), 1);
+        let ambient = self.ambient.to_linear();
+        self.composite_shader
+            .set_3f_uniform(name!("u_ambient"), ambient.r, ambient.g, ambient.b);
+        scene.bind(0);
+        light_map.bind(1);
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        self.triangle.draw();
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+}
+
+impl Default for Lighting2d {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds the shadow an edge casts away from a light, as two triangles
+/// stretching past the light's radius.
+fn push_shadow(vertices: &mut Vec<f32>, light: &Light2d, start: Vec2, end: Vec2) {
+    let reach = light.radius * 4.0;
+    let project = |point: Vec2| {
+        let away = point - light.position;
+        let length = away.magnitude();
+        if length <= 1e-4 {
+            point
+        } else {
+            point + away * (reach / length)
+        }
+    };
+    let (far_start, far_end) = (project(start), project(end));
+    for point in [start, end, far_end, start, far_end, far_start] {
+        vertices.extend_from_slice(&[point.x, point.y]);
+    }
+}
@@ -0,0 +1,332 @@
+use std::mem;
+
+use gl::types::*;
+
+use super::gl_wrapper::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use super::primitives::Primitive;
+use crate::math::projection::orthographic;
+use crate::math::*;
+use crate::name;
+
+const LINE_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_corner;
+layout (location = 1) in vec4 a_start;
+layout (location = 2) in vec4 a_end;
+layout (location = 3) in vec4 a_color;
+layout (location = 4) in vec4 a_style;
+
+uniform mat4 u_view_projection;
+uniform vec2 u_viewport_size;
+
+flat out vec2 v_start;
+flat out vec2 v_end;
+flat out vec2 v_distances;
+flat out vec3 v_style;
+out vec2 v_pixel;
+out vec4 v_color;
+
+const float NEAR = 0.0001;
+
+void main() {
+    vec4 clip_start = u_view_projection * vec4(a_start.xyz, 1.0);
+    vec4 clip_end = u_view_projection * vec4(a_end.xyz, 1.0);
+    if (clip_start.w < NEAR && clip_end.w < NEAR) {
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        return;
+    }
+
+    // Clip the segment against the near plane so both ends project.
+    vec2 distances = vec2(a_start.w, a_end.w);
+    if (clip_start.w < NEAR) {
+        float t = (NEAR - clip_start.w) / (clip_end.w - clip_start.w);
+        clip_start = mix(clip_start, clip_end, t);
+        distances.x = mix(distances.x, distances.y, t);
+    } else if (clip_end.w < NEAR) {
+        float t = (NEAR - clip_end.w) / (clip_start.w - clip_end.w);
+        clip_end = mix(clip_end, clip_start, t);
+        distances.y = mix(distances.y, distances.x, t);
+    }
+
+    vec2 half_viewport = u_viewport_size * 0.5;
+    vec2 start = clip_start.xy / clip_start.w * half_viewport;
+    vec2 end = clip_end.xy / clip_end.w * half_viewport;
+    vec2 direction = end - start;
+    direction = length(direction) > 0.0001 ? normalize(direction) : vec2(1.0, 0.0);
+    vec2 normal = vec2(-direction.y, direction.x);
+
+    // Expand into a quad one pixel wider than the line on every side for anti-aliasing.
+    float half_width = a_style.x * 0.5 + 1.0;
+    float t = a_corner.x + 0.5;
+    vec4 clip = mix(clip_start, clip_end, t);
+    vec2 offset = (normal * a_corner.y * 2.0 + direction * a_corner.x * 2.0) * half_width;
+    clip.xy += offset / half_viewport * clip.w;
+    gl_Position = clip;
+
+    v_start = start;
+    v_end = end;
+    v_distances = distances;
+    v_style = a_style.xyz;
+    v_pixel = mix(start, end, t) + offset;
+    v_color = a_color;
+}
+"#;
+
+const LINE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+flat in vec2 v_start;
+flat in vec2 v_end;
+flat in vec2 v_distances;
+flat in vec3 v_style;
+in vec2 v_pixel;
+in vec4 v_color;
+out vec4 out_color;
+
+void main() {
+    vec2 segment = v_end - v_start;
+    float length_squared = dot(segment, segment);
+    float t = length_squared > 0.0 ? clamp(dot(v_pixel - v_start, segment) / length_squared, 0.0, 1.0) : 0.0;
+    float distance = length(v_pixel - (v_start + segment * t));
+
+    // Round caps double as round joins where segments meet.
+    float coverage = clamp(v_style.x * 0.5 - distance + 0.5, 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    float dash = v_style.y;
+    float gap = v_style.z;
+    if (dash > 0.0 && mod(mix(v_distances.x, v_distances.y, t), dash + gap) > dash) {
+        discard;
+    }
+
+    out_color = vec4(v_color.rgb, v_color.a * coverage);
+}
+"#;
+
+/// Floats per line segment instance: four `vec4` attributes.
+const SEGMENT_FLOATS: usize = 16;
+
+/// How a line is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineStyle {
+    pub color: Color,
+    /// Width in pixels.
+    pub width: f32,
+    /// Dash and gap lengths in the line's own units; `None` draws a solid line.
+    pub dash: Option<(f32, f32)>,
+}
+
+impl LineStyle {
+    /// Creates a solid line style.
+    pub fn new(color: Color, width: f32) -> Self {
+        Self {
+            color,
+            width,
+            dash: None,
+        }
+    }
+
+    /// Makes the line dashed.
+    pub fn dashed(mut self, dash: f32, gap: f32) -> Self {
+        self.dash = Some((dash, gap));
+        self
+    }
+}
+
+/// # Line Renderer
+///
+/// An immediate-mode renderer for thick, anti-aliased lines and polylines.
+/// Segments are expanded into screen-space quads, so widths are in pixels
+/// regardless of distance, and get round caps that also form round joins.
+/// Dash lengths are measured along the line in world units (or pixels for
+/// [`LineRenderer::flush_2d`]), so patterns continue smoothly around corners.
+///
+/// ## Example
+/// ```ignore
+/// let mut lines = LineRenderer::new();
+///
+/// lines.line(vec3(0.0, 0.0, 0.0), vec3(0.0, 5.0, 0.0), LineStyle::new(Color::RED, 3.0));
+/// lines.polyline(&trajectory, LineStyle::new(Color::YELLOW, 2.0).dashed(0.5, 0.25), false);
+/// lines.flush(&camera_view.view_projection, window.framebuffer_size());
+/// ```
+pub struct LineRenderer {
+    shader: ShaderProgram,
+    quad: Primitive,
+    instances: BufferObject,
+    data: Vec<f32>,
+    ui_scale: f32,
+}
+
+impl LineRenderer {
+    /// Creates a line renderer.
+    pub fn new() -> Self {
+        let mut shader = ShaderProgram::from_source(LINE_VERTEX_SHADER, LINE_FRAGMENT_SHADER);
+        for uniform in ["u_view_projection", "u_viewport_size"] {
+            shader.create_uniform(uniform);
+        }
+
+        let quad = Primitive::unit_quad();
+        let instances = BufferObject::new(gl::ARRAY_BUFFER, gl::STREAM_DRAW);
+        quad.bind();
+        instances.bind();
+        let stride = (SEGMENT_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+        for attribute in 0..4 {
+            let offset = attribute * 4 * mem::size_of::<GLfloat>();
+            let attribute = VertexAttribute::new(
+                1 + attribute as GLuint,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                offset as *const _,
+            );
+            attribute.enable();
+            attribute.set_divisor(1);
+        }
+        instances.unbind();
+        Vao::unbind();
+
+        Self {
+            shader,
+            quad,
+            instances,
+            data: Vec::new(),
+            ui_scale: 1.0,
+        }
+    }
+
+    /// Sets how many physical pixels one unit covers in
+    /// [`LineRenderer::flush_2d`], usually the window's content scale.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale.max(f32::EPSILON);
+    }
+
+    /// Returns the UI scale.
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Returns the number of queued segments.
+    pub fn segment_count(&self) -> usize {
+        self.data.len() / SEGMENT_FLOATS
+    }
+
+    fn push_segment(&mut self, start: Vec3, end: Vec3, distances: (f32, f32), style: &LineStyle) {
+        let (dash, gap) = style.dash.unwrap_or((0.0, 0.0));
+        let color = style.color.to_linear();
+        self.data.extend_from_slice(&[
+            start.x,
+            start.y,
+            start.z,
+            distances.0,
+            end.x,
+            end.y,
+            end.z,
+            distances.1,
+            color.r,
+            color.g,
+            color.b,
+            color.a,
+            style.width,
+            dash,
+            gap,
+            0.0,
+        ]);
+    }
+
+    /// Queues a 3D line segment.
+    pub fn line(&mut self, start: Vec3, end: Vec3, style: LineStyle) {
+        let length = (end - start).magnitude();
+        self.push_segment(start, end, (0.0, length), &style);
+    }
+
+    /// Queues connected 3D segments through `points`, optionally closing the loop.
+    pub fn polyline(&mut self, points: &[Vec3], style: LineStyle, closed: bool) {
+        if points.len() < 2 {
+            return;
+        }
+        let mut distance = 0.0;
+        let count = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for index in 0..count {
+            let start = points[index];
+            let end = points[(index + 1) % points.len()];
+            let length = (end - start).magnitude();
+            self.push_segment(start, end, (distance, distance + length), &style);
+            distance += length;
+        }
+    }
+
+    /// Queues a 2D line segment in pixels, for [`LineRenderer::flush_2d`].
+    pub fn line_2d(&mut self, start: Vec2, end: Vec2, style: LineStyle) {
+        self.line(start.extend(0.0), end.extend(0.0), style);
+    }
+
+    /// Queues connected 2D segments in pixels, for [`LineRenderer::flush_2d`].
+    pub fn polyline_2d(&mut self, points: &[Vec2], style: LineStyle, closed: bool) {
+        let points: Vec<Vec3> = points.iter().map(|point| point.extend(0.0)).collect();
+        self.polyline(&points, style, closed);
+    }
+
+    /// Draws and clears the queued segments with a view-projection matrix,
+    /// for a viewport of the given size in pixels.
+    pub fn flush(&mut self, view_projection: &Mat4, viewport_size: (u32, u32)) {
+        if self.data.is_empty() {
+            return;
+        }
+
+        self.shader.bind();
+        self.shader
+            .set_matrix4fv_uniform(name!("u_view_projection"), view_projection);
+        self.shader.set_2f_uniform(
+            name!("u_viewport_size"),
+            viewport_size.0 as f32,
+            viewport_size.1 as f32,
+        );
+
+        self.instances.bind();
+        self.instances.store_f32_data(&self.data);
+        self.instances.unbind();
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        self.quad.draw_instanced(self.segment_count());
+        unsafe {
+            gl::Disable(gl::BLEND);
+        }
+
+        ShaderProgram::unbind();
+        self.data.clear();
+    }
+
+    /// Draws and clears the queued segments as 2D pixel coordinates with the
+    /// origin at the top-left of a viewport of the given size.
+    pub fn flush_2d(&mut self, viewport_size: (u32, u32)) {
+        let (width, height) = (
+            viewport_size.0 as f32 / self.ui_scale,
+            viewport_size.1 as f32 / self.ui_scale,
+        );
+        let projection = orthographic(0.0, width, height, 0.0, -1.0, 1.0);
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        // Line widths are measured against the viewport, so a logical
+        // viewport makes them logical pixels too.
+        self.flush(&projection, (width.round() as u32, height.round() as u32));
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Default for LineRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use super::gl_wrapper::ShaderProgram;
+use super::texture::Texture;
+use crate::math::*;
+use crate::name;
+
+/// GLSL helpers for reading [`Material`] properties.
+///
+/// Paste (or concatenate) this into a fragment shader, create the uniforms in
+/// [`MATERIAL_UNIFORMS`], and add the emissive term after lighting so it is
+/// unaffected by shadows and can exceed 1.0 for bloom:
+///
+/// ```glsl
+/// vec4 albedo = nyanko_base_color(v_uv);
+/// vec3 color = albedo.rgb * lighting + nyanko_emissive(v_uv);
+/// ```
+pub const MATERIAL_GLSL: &str = r#"
+uniform vec4 u_base_color;
+uniform sampler2D u_base_color_texture;
+uniform int u_has_base_color_texture;
+uniform vec3 u_emissive;
+uniform sampler2D u_emissive_texture;
+uniform int u_has_emissive_texture;
+
+vec4 nyanko_base_color(vec2 uv) {
+    vec4 color = u_base_color;
+    if (u_has_base_color_texture != 0) {
+        color *= texture(u_base_color_texture, uv);
+    }
+    return color;
+}
+
+vec3 nyanko_emissive(vec2 uv) {
+    vec3 emissive = u_emissive;
+    if (u_has_emissive_texture != 0) {
+        emissive *= texture(u_emissive_texture, uv).rgb;
+    }
+    return emissive;
+}
+"#;
+
+/// Names of the uniforms used by [`MATERIAL_GLSL`].
+pub const MATERIAL_UNIFORMS: [&str; 6] = [
+    "u_base_color",
+    "u_base_color_texture",
+    "u_has_base_color_texture",
+    "u_emissive",
+    "u_emissive_texture",
+    "u_has_emissive_texture",
+];
+
+/// # Material
+///
+/// Surface properties shared by the standard shaders. Colors are authored in
+/// sRGB and converted to linear when uploaded. Emission is multiplied by
+/// `emissive_intensity` and written in linear HDR, so values above 1.0 pass a
+/// bloom threshold and glow.
+///
+/// Textures are expected to be sRGB-encoded (`gl::SRGB8_ALPHA8`) so sampling
+/// returns linear values.
+///
+/// ## Example
+/// ```ignore
+/// let neon = Material::new(Color::BLACK)
+///     .with_emissive(Color::from_hex("#ff2bd6").unwrap(), 8.0)
+///     .with_emissive_texture(sign_mask.clone());
+///
+/// shader.bind();
+/// neon.apply_uniforms(&shader, 0);
+/// ```
+#[derive(Clone)]
+pub struct Material {
+    pub base_color: Color,
+    pub base_color_texture: Option<Arc<Texture>>,
+    pub emissive: Color,
+    pub emissive_intensity: f32,
+    pub emissive_texture: Option<Arc<Texture>>,
+}
+
+impl Material {
+    /// Creates a non-emissive material with a base color.
+    pub fn new(base_color: Color) -> Self {
+        Self {
+            base_color,
+            base_color_texture: None,
+            emissive: Color::BLACK,
+            emissive_intensity: 1.0,
+            emissive_texture: None,
+        }
+    }
+
+    /// Sets the base color texture, multiplied by the base color.
+    pub fn with_base_color_texture(mut self, texture: Arc<Texture>) -> Self {
+        self.base_color_texture = Some(texture);
+        self
+    }
+
+    /// Makes the material emit light of a color at an HDR intensity.
+    pub fn with_emissive(mut self, color: Color, intensity: f32) -> Self {
+        self.emissive = color;
+        self.emissive_intensity = intensity;
+        self
+    }
+
+    /// Sets the emissive texture, multiplied by the emissive color.
+    pub fn with_emissive_texture(mut self, texture: Arc<Texture>) -> Self {
+        self.emissive_texture = Some(texture);
+        self
+    }
+
+    /// Checks if the material emits any light.
+    pub fn is_emissive(&self) -> bool {
+        self.emissive_intensity > 0.0
+            && (self.emissive.r > 0.0 || self.emissive.g > 0.0 || self.emissive.b > 0.0)
+    }
+
+    /// Returns the linear HDR emission, before any emissive texture.
+    pub fn emissive_radiance(&self) -> Vec3 {
+        let linear = self.emissive.to_linear();
+        vec3(linear.r, linear.g, linear.b) * self.emissive_intensity
+    }
+
+    /// Sets the uniforms on a shader that includes [`MATERIAL_GLSL`], binding
+    /// textures to `first_unit` and `first_unit + 1`.
+    pub fn apply_uniforms(&self, shader: &ShaderProgram, first_unit: u32) {
+        shader.set_color_uniform(name!("u_base_color"), self.base_color);
+        shader.set_1i_uniform(name!("u_base_color_texture"), first_unit as i32);
+        shader.set_1i_uniform(
+            name!("u_has_base_color_texture"),
+            self.base_color_texture.is_some() as i32,
+        );
+        if let Some(texture) = &self.base_color_texture {
+            texture.bind(first_unit);
+        }
+
+        let emissive = self.emissive_radiance();
+        shader.set_3f_uniform(name!("u_emissive"), emissive.x, emissive.y, emissive.z);
+        shader.set_1i_uniform(name!("u_emissive_texture"), first_unit as i32 + 1);
+        shader.set_1i_uniform(
+            name!("u_has_emissive_texture"),
+            self.emissive_texture.is_some() as i32,
+        );
+        if let Some(texture) = &self.emissive_texture {
+            texture.bind(first_unit + 1);
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new(Color::WHITE)
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use super::font::SdfFont;
+use super::sprite_batch::SpriteBatch;
+use super::text::{TextRenderer, TextStyle};
+use crate::math::*;
+use crate::memory::{self, format_bytes, MemoryBudgets};
+
+/// # Memory HUD
+///
+/// An overlay listing the estimated memory of every category, with GPU and
+/// CPU totals, against their budgets if given. Categories over budget are
+/// drawn in the warning color.
+///
+/// ## Example
+/// ```ignore
+/// let hud = MemoryHud::new();
+///
+/// // after drawing the frame
+/// hud.draw(&mut batch, &mut text, &font, Some(&budgets), window_size);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryHud {
+    /// Top-left corner in UI pixels.
+    pub position: Vec2,
+    pub font_size: f32,
+    pub padding: f32,
+    pub background: Color,
+    pub text: Color,
+    pub warning: Color,
+}
+
+impl MemoryHud {
+    /// Creates a HUD in the top-left corner.
+    pub fn new() -> Self {
+        Self {
+            position: vec2(8.0, 8.0),
+            font_size: 14.0,
+            padding: 6.0,
+            background: Color::new(0.05, 0.05, 0.08, 0.75),
+            text: Color::new(0.85, 0.85, 0.85, 1.0),
+            warning: Color::new(1.0, 0.4, 0.35, 1.0),
+        }
+    }
+
+    /// Sets the top-left corner.
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the font size.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Returns the lines the HUD shows and whether each is over budget.
+    pub fn lines(&self, budgets: Option<&MemoryBudgets>) -> Vec<(String, bool)> {
+        let stats = memory::stats();
+        let mut lines = vec![(
+            format!(
+                "GPU {}  CPU {}",
+                format_bytes(stats.gpu_bytes()),
+                format_bytes(stats.cpu_bytes())
+            ),
+            false,
+        )];
+        for (category, usage) in &stats.categories {
+            let budget = budgets.and_then(|budgets| budgets.budget(*category));
+            let mut line = format!(
+                "{:<10} {:>10} ({})",
+                category.name(),
+                format_bytes(usage.bytes),
+                usage.count
+            );
+            if let Some(budget) = budget {
+                line += &format!(" / {}", format_bytes(budget));
+            }
+            lines.push((line, budget.is_some_and(|budget| usage.bytes > budget)));
+        }
+        lines
+    }
+
+    /// Draws the HUD over everything else on a target of the given pixel
+    /// size.
+    pub fn draw(
+        &self,
+        batch: &mut SpriteBatch,
+        text: &mut TextRenderer,
+        font: &Arc<SdfFont>,
+        budgets: Option<&MemoryBudgets>,
+        target_size: (u32, u32),
+    ) {
+        let lines = self.lines(budgets);
+        let line_height = self.font_size * 1.25;
+        let width = lines
+            .iter()
+            .map(|(line, _)| font.measure(line, self.font_size).x)
+            .fold(0.0, f32::max);
+
+        batch.begin(target_size);
+        batch.fill_rect(
+            Rect::new(
+                self.position.x,
+                self.position.y,
+                width + self.padding * 2.0,
+                lines.len() as f32 * line_height + self.padding * 2.0,
+            ),
+            self.background,
+        );
+        batch.end();
+
+        let mut position = self.position + vec2(self.padding, self.padding);
+        for (line, over_budget) in &lines {
+            let color = if *over_budget {
+                self.warning
+            } else {
+                self.text
+            };
+            text.draw_2d(font, line, position, TextStyle::new(self.font_size, color));
+            position.y += line_height;
+        }
+        text.flush_2d(target_size);
+    }
+}
+
+impl Default for MemoryHud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,432 @@
+use std::mem;
+use std::ops::Range;
+
+use gl::types::*;
+
+use super::gl_wrapper::{BufferObject, Vao, VertexAttribute};
+use crate::math::*;
+
+/// Floats per vertex: position, normal, UV, and color.
+const VERTEX_FLOATS: usize = 12;
+
+/// One vertex of a [`MeshBuilder`]. Attributes are bound at position 0,
+/// normal 1, UV 2, and color 3.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+    pub color: Color,
+}
+
+impl MeshVertex {
+    /// Creates a white vertex without a normal or UV.
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            normal: Vec3::zero(),
+            uv: Vec2::zero(),
+            color: Color::WHITE,
+        }
+    }
+
+    /// Sets the normal.
+    pub fn with_normal(mut self, normal: Vec3) -> Self {
+        self.normal = normal;
+        self
+    }
+
+    /// Sets the texture coordinates.
+    pub fn with_uv(mut self, uv: Vec2) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    /// Sets the vertex color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn write(&self, out: &mut Vec<f32>) {
+        let (p, n, c) = (self.position, self.normal, self.color);
+        out.extend_from_slice(&[
+            p.x, p.y, p.z, n.x, n.y, n.z, self.uv.x, self.uv.y, c.r, c.g, c.b, c.a,
+        ]);
+    }
+}
+
+/// Grows `dirty` to cover `range`.
+fn mark(dirty: &mut Option<Range<usize>>, range: Range<usize>) {
+    if range.is_empty() {
+        return;
+    }
+    *dirty = Some(match dirty.take() {
+        Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+        None => range,
+    });
+}
+
+/// # Mesh Builder
+///
+/// Vertices and triangle indices kept on the CPU so games can build and edit
+/// geometry at runtime, such as voxel chunks, destructible walls, or editor
+/// previews. Upload it with [`Mesh::new`]; after editing, [`Mesh::sync`]
+/// sends only the vertices and indices that changed.
+///
+/// ## Example
+/// ```ignore
+/// let mut builder = MeshBuilder::new();
+/// let a = builder.push_vertex(MeshVertex::new(vec3(0.0, 0.0, 0.0)));
+/// let b = builder.push_vertex(MeshVertex::new(vec3(1.0, 0.0, 0.0)));
+/// let c = builder.push_vertex(MeshVertex::new(vec3(0.0, 1.0, 0.0)));
+/// builder.push_triangle(a, b, c);
+/// builder.recompute_normals();
+/// let mut mesh = Mesh::new(&mut builder);
+///
+/// builder.vertex_mut(c).position.y = 2.0;
+/// mesh.sync(&mut builder);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshBuilder {
+    vertices: Vec<MeshVertex>,
+    indices: Vec<u32>,
+    dirty_vertices: Option<Range<usize>>,
+    dirty_indices: Option<Range<usize>>,
+}
+
+impl MeshBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the vertices.
+    pub fn vertices(&self) -> &[MeshVertex] {
+        &self.vertices
+    }
+
+    /// Returns the triangle indices, three per triangle.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Returns the number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Returns the number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Checks if there are no triangles.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Adds a vertex and returns its index.
+    pub fn push_vertex(&mut self, vertex: MeshVertex) -> u32 {
+        let index = self.vertices.len();
+        self.vertices.push(vertex);
+        mark(&mut self.dirty_vertices, index..index + 1);
+        index as u32
+    }
+
+    /// Adds a triangle, wound counter-clockwise for the front face.
+    pub fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        let start = self.indices.len();
+        self.indices.extend_from_slice(&[a, b, c]);
+        mark(&mut self.dirty_indices, start..start + 3);
+    }
+
+    /// Adds a quad as two triangles, with corners in counter-clockwise order.
+    pub fn push_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.push_triangle(a, b, c);
+        self.push_triangle(a, c, d);
+    }
+
+    /// Appends another builder's geometry, moved by `transform`, e.g. to merge
+    /// the blocks of a voxel chunk.
+    pub fn append(&mut self, other: &MeshBuilder, transform: &Mat4) {
+        let base = self.vertices.len() as u32;
+        let normal_matrix = transform.invert().unwrap_or(Mat4::identity()).transpose();
+        for vertex in &other.vertices {
+            let normal = (normal_matrix * vertex.normal.extend(0.0)).truncate();
+            self.push_vertex(MeshVertex {
+                position: (*transform * vertex.position.extend(1.0)).truncate(),
+                normal: if normal.magnitude2() > 0.0 {
+                    normal.normalize()
+                } else {
+                    normal
+                },
+                ..*vertex
+            });
+        }
+        let start = self.indices.len();
+        self.indices
+            .extend(other.indices.iter().map(|index| index + base));
+        mark(&mut self.dirty_indices, start..self.indices.len());
+    }
+
+    /// Returns a vertex for editing and marks it for upload.
+    pub fn vertex_mut(&mut self, index: u32) -> &mut MeshVertex {
+        let index = index as usize;
+        mark(&mut self.dirty_vertices, index..index + 1);
+        &mut self.vertices[index]
+    }
+
+    /// Replaces a vertex.
+    pub fn set_vertex(&mut self, index: u32, vertex: MeshVertex) {
+        *self.vertex_mut(index) = vertex;
+    }
+
+    /// Replaces the indices of a triangle.
+    pub fn set_triangle(&mut self, triangle: usize, a: u32, b: u32, c: u32) {
+        let start = triangle * 3;
+        self.indices[start..start + 3].copy_from_slice(&[a, b, c]);
+        mark(&mut self.dirty_indices, start..start + 3);
+    }
+
+    /// Removes triangles, keeping their vertices. Later triangles shift down.
+    pub fn remove_triangles(&mut self, triangles: Range<usize>) {
+        let (start, end) = (
+            triangles.start * 3,
+            (triangles.end * 3).min(self.indices.len()),
+        );
+        if start >= end {
+            return;
+        }
+        self.indices.drain(start..end);
+        mark(&mut self.dirty_indices, start..self.indices.len());
+    }
+
+    /// Removes everything.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.dirty_vertices = None;
+        self.dirty_indices = None;
+    }
+
+    /// Sets every normal to the area-weighted average of the faces around it,
+    /// giving smooth shading across shared vertices.
+    pub fn recompute_normals(&mut self) {
+        let mut normals = vec![Vec3::zero(); self.vertices.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+            let (pa, pb, pc) = (
+                self.vertices[a].position,
+                self.vertices[b].position,
+                self.vertices[c].position,
+            );
+            // The cross product's length is twice the area, which weights it.
+            let face = (pb - pa).cross(pc - pa);
+            normals[a] += face;
+            normals[b] += face;
+            normals[c] += face;
+        }
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = if normal.magnitude2() > 1e-12 {
+                normal.normalize()
+            } else {
+                Vec3::unit_y()
+            };
+        }
+        mark(&mut self.dirty_vertices, 0..self.vertices.len());
+    }
+
+    /// Gives every triangle its own vertices with the face normal, for a
+    /// faceted look. Increases the vertex count to three per triangle.
+    pub fn flatten(&mut self) {
+        let mut vertices = Vec::with_capacity(self.indices.len());
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] =
+                [triangle[0], triangle[1], triangle[2]].map(|index| self.vertices[index as usize]);
+            let face = (b.position - a.position).cross(c.position - a.position);
+            let normal = if face.magnitude2() > 1e-12 {
+                face.normalize()
+            } else {
+                Vec3::unit_y()
+            };
+            vertices.extend([a, b, c].map(|vertex| vertex.with_normal(normal)));
+        }
+        self.vertices = vertices;
+        self.indices = (0..self.vertices.len() as u32).collect();
+        mark(&mut self.dirty_vertices, 0..self.vertices.len());
+        mark(&mut self.dirty_indices, 0..self.indices.len());
+    }
+
+    /// Returns the box around every vertex.
+    pub fn bounds(&self) -> Option<Aabb> {
+        let positions: Vec<Vec3> = self.vertices.iter().map(|vertex| vertex.position).collect();
+        Aabb::from_points(&positions)
+    }
+
+    /// Takes the ranges changed since the last upload.
+    fn take_dirty(&mut self) -> (Option<Range<usize>>, Option<Range<usize>>) {
+        (self.dirty_vertices.take(), self.dirty_indices.take())
+    }
+}
+
+/// # Mesh
+///
+/// Indexed triangles on the GPU, built from a [`MeshBuilder`]. Storage grows
+/// as needed and is reused, so frequent small edits only upload what changed.
+pub struct Mesh {
+    vao: Vao,
+    vertex_buffer: BufferObject,
+    index_buffer: BufferObject,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    index_count: usize,
+    bounds: Option<Aabb>,
+}
+
+impl Mesh {
+    /// Uploads a builder's geometry.
+    pub fn new(builder: &mut MeshBuilder) -> Self {
+        let vao = Vao::new();
+        vao.bind();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW);
+        vertex_buffer.bind();
+        let index_buffer = BufferObject::new(gl::ELEMENT_ARRAY_BUFFER, gl::DYNAMIC_DRAW);
+        index_buffer.bind();
+
+        let stride = (VERTEX_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+        for (index, components, offset) in [(0, 3, 0), (1, 3, 3), (2, 2, 6), (3, 4, 8)] {
+            let attribute = VertexAttribute::new(
+                index,
+                components,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (offset * mem::size_of::<GLfloat>()) as *const _,
+            );
+            attribute.enable();
+        }
+        Vao::unbind();
+
+        let mut mesh = Self {
+            vao,
+            vertex_buffer,
+            index_buffer,
+            vertex_capacity: 0,
+            index_capacity: 0,
+            index_count: 0,
+            bounds: None,
+        };
+        mesh.upload(builder);
+        mesh
+    }
+
+    /// Returns the number of indices drawn.
+    pub fn index_count(&self) -> usize {
+        self.index_count
+    }
+
+    /// Returns the box around the geometry as of the last upload.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    /// Replaces the whole GPU copy with the builder's geometry.
+    pub fn upload(&mut self, builder: &mut MeshBuilder) {
+        builder.take_dirty();
+        self.vao.bind();
+
+        let mut vertices = Vec::with_capacity(builder.vertices.len() * VERTEX_FLOATS);
+        for vertex in &builder.vertices {
+            vertex.write(&mut vertices);
+        }
+        self.vertex_buffer.bind();
+        self.vertex_buffer.store_f32_data(&vertices);
+        self.vertex_capacity = builder.vertices.len();
+
+        self.index_buffer.bind();
+        self.index_buffer.store_u32_data(&builder.indices);
+        self.index_capacity = builder.indices.len();
+        self.index_count = builder.indices.len();
+
+        Vao::unbind();
+        self.bounds = builder.bounds();
+    }
+
+    /// Uploads what changed in the builder since the last upload. Falls back
+    /// to a full upload when the geometry outgrew the GPU storage.
+    pub fn sync(&mut self, builder: &mut MeshBuilder) {
+        if builder.vertices.len() > self.vertex_capacity
+            || builder.indices.len() > self.index_capacity
+        {
+            self.upload(builder);
+            return;
+        }
+        let (vertices, indices) = builder.take_dirty();
+        if let Some(range) = vertices {
+            self.update_vertices(builder, range);
+        }
+        if let Some(range) = indices {
+            self.update_indices(builder, range);
+        }
+        self.index_count = builder.indices.len();
+        self.bounds = builder.bounds();
+    }
+
+    /// Uploads a range of the builder's vertices into the same place on the GPU.
+    pub fn update_vertices(&mut self, builder: &MeshBuilder, range: Range<usize>) {
+        let end = range
+            .end
+            .min(builder.vertices.len())
+            .min(self.vertex_capacity);
+        if range.start >= end {
+            return;
+        }
+        let mut data = Vec::with_capacity((end - range.start) * VERTEX_FLOATS);
+        for vertex in &builder.vertices[range.start..end] {
+            vertex.write(&mut data);
+        }
+        self.vertex_buffer.bind();
+        self.vertex_buffer
+            .update_f32_data(range.start * VERTEX_FLOATS, &data);
+        self.vertex_buffer.unbind();
+    }
+
+    /// Uploads a range of the builder's indices into the same place on the GPU.
+    pub fn update_indices(&mut self, builder: &MeshBuilder, range: Range<usize>) {
+        let end = range
+            .end
+            .min(builder.indices.len())
+            .min(self.index_capacity);
+        if range.start >= end {
+            return;
+        }
+        self.vao.bind();
+        self.index_buffer.bind();
+        self.index_buffer
+            .update_u32_data(range.start, &builder.indices[range.start..end]);
+        Vao::unbind();
+    }
+
+    /// Draws the mesh with the currently bound shader.
+    pub fn draw(&self) {
+        if self.index_count == 0 {
+            return;
+        }
+        self.vao.bind();
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                self.index_count as GLsizei,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+        }
+        Vao::unbind();
+    }
+
+    /// Binds the mesh's vertex array, e.g. to attach per-instance attributes.
+    pub fn bind(&self) {
+        self.vao.bind();
+    }
+}
@@ -0,0 +1,420 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::image::Image;
+use super::texture::{mip_level_count, mip_size, Texture};
+use crate::custom_errors::Errors;
+use crate::jobs::{JobHandle, JobSystem};
+use crate::math::*;
+
+/// Identifies a texture registered with a [`MipStreamer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StreamedTextureId(usize);
+
+/// Memory use of streamed textures, e.g. for a debug overlay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MipStreamingStats {
+    pub textures: usize,
+    /// Estimated bytes of the mip levels on the GPU.
+    pub resident_bytes: usize,
+    /// Estimated bytes of the levels the last update asked for.
+    pub wanted_bytes: usize,
+    pub budget_bytes: usize,
+    /// Textures whose detailed levels are being read.
+    pub loading: usize,
+}
+
+/// Detailed mip levels of a texture read in the background.
+type MipLoad = JobHandle<Result<Vec<Image>, String>>;
+
+struct StreamedTexture {
+    path: PathBuf,
+    texture: Arc<Texture>,
+    srgb: bool,
+    /// Number of levels in the full chain.
+    levels: u32,
+    /// First level of the always-resident tail.
+    tail: u32,
+    /// Most detailed level on the GPU.
+    resident: u32,
+    /// Most detailed level the last update asked for.
+    wanted: u32,
+    /// Closest request since the last update.
+    distance: f32,
+    /// The most detailed level being read, and its job.
+    loading: Option<(u32, MipLoad)>,
+    /// Levels read but not uploaded yet, from the most detailed one given.
+    ready: Option<(u32, Vec<Image>)>,
+    /// Set when reading failed, so it isn't retried every frame.
+    failed: bool,
+}
+
+impl StreamedTexture {
+    /// Returns the estimated bytes of one level.
+    fn level_bytes(&self, level: u32) -> usize {
+        let (width, height) = mip_size(self.texture.width(), self.texture.height(), level);
+        (width * height * 4) as usize
+    }
+
+    /// Returns the estimated bytes of the levels from `first` down to 1x1.
+    fn bytes_from(&self, first: u32) -> usize {
+        (first..self.levels)
+            .map(|level| self.level_bytes(level))
+            .sum()
+    }
+
+    /// Uploads levels read in the background, least detailed first, while
+    /// the update's upload allowance lasts.
+    fn upload_ready(&mut self, uploads: &mut usize) {
+        let Some((first, levels)) = &mut self.ready else {
+            return;
+        };
+        // Levels freed since the read started leave a gap; read again.
+        if *first + levels.len() as u32 != self.resident {
+            self.ready = None;
+            return;
+        }
+        let (internal_format, format, data_type) = formats(self.srgb);
+        while *uploads > 0 && self.resident > (*first).max(self.wanted) {
+            let Some(image) = levels.pop() else {
+                break;
+            };
+            self.resident -= 1;
+            self.texture.set_mip_level(
+                self.resident,
+                internal_format,
+                format,
+                data_type,
+                Some(&image.pixels),
+            );
+            self.texture.set_mip_range(self.resident, self.levels - 1);
+            *uploads -= 1;
+        }
+        if self.resident <= (*first).max(self.wanted) {
+            self.ready = None;
+        }
+    }
+
+    /// Frees levels more detailed than wanted.
+    fn free_unwanted(&mut self) {
+        if self.wanted <= self.resident {
+            return;
+        }
+        let (internal_format, format, data_type) = formats(self.srgb);
+        self.texture.set_mip_range(self.wanted, self.levels - 1);
+        for level in self.resident..self.wanted {
+            self.texture
+                .set_mip_level(level, internal_format, format, data_type, None);
+        }
+        self.resident = self.wanted;
+    }
+}
+
+/// # Mip Streamer
+///
+/// Keeps only the mip levels of big textures that the camera can actually
+/// see in detail on the GPU. The small levels of every texture always stay
+/// resident, so something can be drawn at once; the detailed ones are read
+/// from disk in the background as the camera comes close, and freed again
+/// as it moves away or when textures together exceed the VRAM budget, which
+/// drops detail of the furthest textures first.
+///
+/// Each frame, report how close every visible use of a texture is with
+/// [`MipStreamer::request`], then call [`MipStreamer::update`]. Textures keep
+/// their identity while levels come and go, so materials can hold on to
+/// them.
+///
+/// ## Example
+/// ```ignore
+/// let mut streamer = MipStreamer::new(256 * 1024 * 1024);
+/// let rock = streamer.add("assets/textures/rock_4k.png", true)?;
+/// material.albedo = Some(streamer.texture(rock).clone());
+///
+/// // every frame
+/// for (transform, model) in visible_models {
+///     streamer.request_at(rock, camera_position, transform.position, model.radius);
+/// }
+/// streamer.update();
+/// ```
+pub struct MipStreamer {
+    /// Estimated bytes all streamed textures may take on the GPU.
+    pub budget_bytes: usize,
+    /// Levels this many pixels wide or smaller always stay resident.
+    pub resident_size: u32,
+    /// Distance up to which the full resolution is used; every doubling of
+    /// the distance drops one level.
+    pub full_detail_distance: f32,
+    /// Most textures read at once.
+    pub max_in_flight: usize,
+    /// Most mip levels uploaded per update, to spread the cost over frames.
+    pub uploads_per_update: usize,
+    jobs: Arc<JobSystem>,
+    textures: Vec<Option<StreamedTexture>>,
+}
+
+impl MipStreamer {
+    /// Creates a streamer with a VRAM budget in bytes, keeping levels of up
+    /// to 64 pixels resident and using full detail within 8 units.
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            resident_size: 64,
+            full_detail_distance: 8.0,
+            max_in_flight: 2,
+            uploads_per_update: 2,
+            jobs: JobSystem::global(),
+            textures: Vec::new(),
+        }
+    }
+
+    /// Sets the size of levels that always stay resident.
+    pub fn with_resident_size(mut self, resident_size: u32) -> Self {
+        self.resident_size = resident_size.max(1);
+        self
+    }
+
+    /// Sets the distance up to which the full resolution is used.
+    pub fn with_full_detail_distance(mut self, full_detail_distance: f32) -> Self {
+        self.full_detail_distance = full_detail_distance;
+        self
+    }
+
+    /// Sets the most textures read at once.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Sets the most mip levels uploaded per update.
+    pub fn with_uploads_per_update(mut self, uploads_per_update: usize) -> Self {
+        self.uploads_per_update = uploads_per_update.max(1);
+        self
+    }
+
+    /// Sets the job system textures are read on.
+    pub fn with_job_system(mut self, jobs: Arc<JobSystem>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Registers a PNG texture, color if `srgb` is set and data otherwise.
+    /// Only its resident levels are uploaded; the image is read again when
+    /// more detail is needed.
+    pub fn add(
+        &mut self,
+        path: impl Into<PathBuf>,
+        srgb: bool,
+    ) -> Result<StreamedTextureId, Errors> {
+        let path = path.into();
+        let image = Image::load(&path.to_string_lossy())?;
+        let (width, height) = (image.width, image.height);
+        let levels = mip_level_count(width, height);
+        let tail = (0..levels)
+            .find(|&level| {
+                let (width, height) = mip_size(width, height, level);
+                width.max(height) <= self.resident_size
+            })
+            .unwrap_or(levels - 1);
+
+        let chain = mip_chain(image, tail, levels);
+        let data: Vec<&[u8]> = chain.iter().map(|image| image.pixels.as_slice()).collect();
+        let (internal_format, format, data_type) = formats(srgb);
+        let texture = Texture::new_2d_mips(
+            width,
+            height,
+            internal_format,
+            format,
+            data_type,
+            tail,
+            &data,
+        );
+
+        let streamed = StreamedTexture {
+            path,
+            texture: Arc::new(texture),
+            srgb,
+            levels,
+            tail,
+            resident: tail,
+            wanted: tail,
+            distance: f32::INFINITY,
+            loading: None,
+            ready: None,
+            failed: false,
+        };
+        let index = match self.textures.iter().position(Option::is_none) {
+            Some(index) => index,
+            None => {
+                self.textures.push(None);
+                self.textures.len() - 1
+            }
+        };
+        self.textures[index] = Some(streamed);
+        Ok(StreamedTextureId(index))
+    }
+
+    /// Stops streaming a texture. The texture stays valid with the levels it
+    /// has for as long as others hold on to it.
+    pub fn remove(&mut self, id: StreamedTextureId) {
+        if let Some(slot) = self.textures.get_mut(id.0) {
+            *slot = None;
+        }
+    }
+
+    /// Returns the texture of a streamed texture.
+    pub fn texture(&self, id: StreamedTextureId) -> &Arc<Texture> {
+        &self.get(id).texture
+    }
+
+    /// Returns the most detailed mip level currently on the GPU.
+    pub fn resident_level(&self, id: StreamedTextureId) -> u32 {
+        self.get(id).resident
+    }
+
+    /// Reports that a texture is visible at a distance this frame. Of
+    /// several requests, the closest counts.
+    pub fn request(&mut self, id: StreamedTextureId, distance: f32) {
+        let streamed = self.get_mut(id);
+        streamed.distance = streamed.distance.min(distance.max(0.0));
+    }
+
+    /// Reports that a texture is visible on an object with a bounding radius
+    /// at a position, seen from the camera.
+    pub fn request_at(&mut self, id: StreamedTextureId, camera: Vec3, position: Vec3, radius: f32) {
+        self.request(id, (position - camera).magnitude() - radius);
+    }
+
+    /// Picks the levels every texture should have from this frame's requests
+    /// and the budget, frees levels no longer needed, starts reading missing
+    /// ones and uploads those that were read.
+    pub fn update(&mut self) {
+        self.choose_levels();
+
+        let mut in_flight = self.stats().loading;
+        let mut uploads = self.uploads_per_update;
+        for streamed in self.textures.iter_mut().flatten() {
+            streamed.free_unwanted();
+
+            if let Some((first, handle)) = &mut streamed.loading {
+                if let Some(result) = handle.try_take() {
+                    let first = *first;
+                    streamed.loading = None;
+                    in_flight -= 1;
+                    match result {
+                        Ok(levels) => streamed.ready = Some((first, levels)),
+                        Err(error) => {
+                            log::warn!("Failed to stream {}: {}", streamed.path.display(), error);
+                            streamed.failed = true;
+                        }
+                    }
+                }
+            }
+            streamed.upload_ready(&mut uploads);
+
+            let idle = streamed.loading.is_none() && streamed.ready.is_none();
+            if streamed.wanted < streamed.resident
+                && idle
+                && !streamed.failed
+                && in_flight < self.max_in_flight
+            {
+                let path = streamed.path.clone();
+                let (first, until) = (streamed.wanted, streamed.resident);
+                let handle = self.jobs.spawn(move || {
+                    let image =
+                        Image::load(&path.to_string_lossy()).map_err(|error| error.to_string())?;
+                    Ok(mip_chain(image, first, until))
+                });
+                streamed.loading = Some((first, handle));
+                in_flight += 1;
+            }
+            streamed.distance = f32::INFINITY;
+        }
+    }
+
+    /// Returns memory use and how many textures are being read.
+    pub fn stats(&self) -> MipStreamingStats {
+        let mut stats = MipStreamingStats {
+            budget_bytes: self.budget_bytes,
+            ..Default::default()
+        };
+        for streamed in self.textures.iter().flatten() {
+            stats.textures += 1;
+            stats.resident_bytes += streamed.bytes_from(streamed.resident);
+            stats.wanted_bytes += streamed.bytes_from(streamed.wanted);
+            stats.loading += streamed.loading.is_some() as usize;
+        }
+        stats
+    }
+
+    /// Sets the wanted level of every texture from its distance, then drops
+    /// detail from the furthest textures until the budget is met.
+    fn choose_levels(&mut self) {
+        let full_detail_distance = self.full_detail_distance.max(f32::EPSILON);
+        let mut total = 0;
+        for streamed in self.textures.iter_mut().flatten() {
+            streamed.wanted = if streamed.distance.is_finite() {
+                let drop = (streamed.distance / full_detail_distance).max(1.0).log2();
+                (drop as u32).min(streamed.tail)
+            } else {
+                streamed.tail
+            };
+            total += streamed.bytes_from(streamed.wanted);
+        }
+
+        while total > self.budget_bytes {
+            let furthest = self
+                .textures
+                .iter_mut()
+                .flatten()
+                .filter(|streamed| streamed.wanted < streamed.tail)
+                .max_by(|a, b| {
+                    a.distance
+                        .total_cmp(&b.distance)
+                        .then(a.level_bytes(a.wanted).cmp(&b.level_bytes(b.wanted)))
+                });
+            let Some(streamed) = furthest else {
+                break;
+            };
+            total -= streamed.level_bytes(streamed.wanted);
+            streamed.wanted += 1;
+        }
+    }
+
+    fn get(&self, id: StreamedTextureId) -> &StreamedTexture {
+        self.textures[id.0]
+            .as_ref()
+            .expect("Streamed texture was removed")
+    }
+
+    fn get_mut(&mut self, id: StreamedTextureId) -> &mut StreamedTexture {
+        self.textures[id.0]
+            .as_mut()
+            .expect("Streamed texture was removed")
+    }
+}
+
+/// Returns the internal format, format and data type of 8-bit RGBA levels.
+fn formats(srgb: bool) -> (GLenum, GLenum, GLenum) {
+    let internal_format = if srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA8 };
+    (internal_format, gl::RGBA, gl::UNSIGNED_BYTE)
+}
+
+/// Builds the mip levels of an image from `first` up to, but not including,
+/// `until`.
+fn mip_chain(image: Image, first: u32, until: u32) -> Vec<Image> {
+    let mut levels = Vec::with_capacity(until.saturating_sub(first) as usize);
+    let mut current = image;
+    for level in 0..until {
+        let next = (level + 1 < until).then(|| current.half_size());
+        if level >= first {
+            levels.push(current);
+        }
+        match next {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    levels
+}
@@ -1,2 +1,55 @@
+pub mod accessibility;
+pub mod billboard;
+pub mod bloom;
+pub mod camera;
+pub mod camera_effects;
+pub mod console;
+pub mod clusters;
+pub mod cursor;
+pub mod day_night;
+pub mod debug_view;
+pub mod decals;
+pub mod device;
+pub mod fog;
+pub mod font;
+pub mod framebuffer;
 pub mod gl_wrapper;
+pub mod golden;
+pub mod gpu_assets;
+pub mod gpu_info;
+pub mod gpu_resources;
+pub mod image;
+pub mod light;
+pub mod lighting_2d;
+pub mod lines;
+pub mod material;
+pub mod memory_hud;
+pub mod mesh;
+pub mod mip_streaming;
+pub mod nine_patch;
+pub mod primitives;
+pub mod recording;
+pub mod reflection;
+pub mod render_layers;
+pub mod render_texture;
+pub mod renderer;
+pub mod shader_cache;
+pub mod shader_preprocessor;
+pub mod shader_validation;
+pub mod shadows;
+pub mod shapes;
+pub mod sky;
+pub mod split_screen;
+pub mod sprite_batch;
+pub mod ssao;
+pub mod text;
+pub mod text_layout;
+pub mod texture;
+pub mod texture_packer;
+pub mod tonemap;
+pub mod transitions;
+pub mod transparency;
+pub mod virtual_controls;
+pub mod water;
 pub mod window;
+pub mod world_ui;
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use super::sprite_batch::{SpriteBatch, SpriteVertex};
+use super::texture::Texture;
+use crate::math::*;
+
+/// Border widths of a nine-patch, in source texture pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    /// Creates insets with individual sides.
+    pub fn new(left: f32, right: f32, top: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    /// Creates insets with the same width on every side.
+    pub fn uniform(width: f32) -> Self {
+        Self::new(width, width, width, width)
+    }
+}
+
+/// # Nine-Patch
+///
+/// A sliced sprite: the texture region is cut into a 3x3 grid by `borders`.
+/// When drawn at any size, the corners keep their size, the edges stretch
+/// along one axis, and the center stretches along both, so panels and buttons
+/// scale without distorting their frames.
+///
+/// ## Example
+/// ```ignore
+/// let panel = NinePatch::new(panel_texture.clone(), Insets::uniform(12.0));
+///
+/// batch.begin(window.framebuffer_size());
+/// batch.draw_nine_patch(&panel, Rect::new(40.0, 40.0, 320.0, 180.0), Color::WHITE);
+/// batch.end();
+/// ```
+#[derive(Clone)]
+pub struct NinePatch {
+    pub texture: Arc<Texture>,
+    /// Region of the texture holding the patch, in UV coordinates.
+    pub uv_rect: Rect,
+    pub borders: Insets,
+    /// Multiplies the drawn border sizes, e.g. for UI scaling.
+    pub border_scale: f32,
+    /// Whether to draw the middle cell; disable for frames with a hollow center.
+    pub draw_center: bool,
+}
+
+impl NinePatch {
+    /// Creates a nine-patch covering a whole texture.
+    pub fn new(texture: Arc<Texture>, borders: Insets) -> Self {
+        Self {
+            texture,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+            borders,
+            border_scale: 1.0,
+            draw_center: true,
+        }
+    }
+
+    /// Uses only a region of the texture, such as one entry of a UI atlas.
+    pub fn with_uv_rect(mut self, uv_rect: Rect) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    /// Sets the border scale.
+    pub fn with_border_scale(mut self, scale: f32) -> Self {
+        self.border_scale = scale;
+        self
+    }
+
+    /// Leaves the middle cell empty.
+    pub fn hollow(mut self) -> Self {
+        self.draw_center = false;
+        self
+    }
+
+    /// Returns the smallest size the patch can be drawn at without shrinking its borders.
+    pub fn min_size(&self) -> Vec2 {
+        let borders = self.borders;
+        vec2(borders.left + borders.right, borders.top + borders.bottom) * self.border_scale
+    }
+}
+
+impl SpriteBatch {
+    /// Draws a nine-patch stretched over `destination`. If the destination is
+    /// smaller than the borders, they shrink proportionally.
+    pub fn draw_nine_patch(&mut self, patch: &NinePatch, destination: Rect, color: Color) {
+        let texture_size = vec2(
+            patch.texture.width() as f32 * patch.uv_rect.width,
+            patch.texture.height() as f32 * patch.uv_rect.height,
+        );
+        let borders = patch.borders;
+
+        let min_size = patch.min_size();
+        let fit_x = if min_size.x > destination.width {
+            destination.width / min_size.x
+        } else {
+            1.0
+        };
+        let fit_y = if min_size.y > destination.height {
+            destination.height / min_size.y
+        } else {
+            1.0
+        };
+        let scale_x = patch.border_scale * fit_x;
+        let scale_y = patch.border_scale * fit_y;
+
+        let (min, max) = (destination.min(), destination.max());
+        let xs = [
+            min.x,
+            min.x + borders.left * scale_x,
+            max.x - borders.right * scale_x,
+            max.x,
+        ];
+        let ys = [
+            min.y,
+            min.y + borders.top * scale_y,
+            max.y - borders.bottom * scale_y,
+            max.y,
+        ];
+
+        let (uv_min, uv_max) = (patch.uv_rect.min(), patch.uv_rect.max());
+        let us = [
+            uv_min.x,
+            uv_min.x + borders.left / texture_size.x * patch.uv_rect.width,
+            uv_max.x - borders.right / texture_size.x * patch.uv_rect.width,
+            uv_max.x,
+        ];
+        let vs = [
+            uv_min.y,
+            uv_min.y + borders.top / texture_size.y * patch.uv_rect.height,
+            uv_max.y - borders.bottom / texture_size.y * patch.uv_rect.height,
+            uv_max.y,
+        ];
+
+        let mut vertices = Vec::with_capacity(16);
+        for row in 0..4 {
+            for column in 0..4 {
+                vertices.push(SpriteVertex::new(
+                    vec2(xs[column], ys[row]),
+                    vec2(us[column], vs[row]),
+                    color,
+                ));
+            }
+        }
+
+        let mut indices = Vec::with_capacity(54);
+        for row in 0..3u32 {
+            for column in 0..3u32 {
+                if !patch.draw_center && row == 1 && column == 1 {
+                    continue;
+                }
+                let top_left = row * 4 + column;
+                let bottom_left = top_left + 4;
+                indices.extend_from_slice(&[
+                    top_left,
+                    top_left + 1,
+                    bottom_left + 1,
+                    top_left,
+                    bottom_left + 1,
+                    bottom_left,
+                ]);
+            }
+        }
+
+        self.draw_triangles(Some(&patch.texture), &vertices, &indices);
+    }
+}
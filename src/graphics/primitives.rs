@@ -0,0 +1,124 @@
+use std::mem;
+use std::ptr;
+
+use gl::types::*;
+
+use super::gl_wrapper::{BufferObject, Vao, VertexAttribute};
+
+#[rustfmt::skip]
+const UNIT_CUBE: [f32; 108] = [
+    -0.5, -0.5, -0.5,  0.5,  0.5, -0.5,  0.5, -0.5, -0.5,
+     0.5,  0.5, -0.5, -0.5, -0.5, -0.5, -0.5,  0.5, -0.5,
+    -0.5, -0.5,  0.5,  0.5, -0.5,  0.5,  0.5,  0.5,  0.5,
+     0.5,  0.5,  0.5, -0.5,  0.5,  0.5, -0.5, -0.5,  0.5,
+    -0.5,  0.5,  0.5, -0.5,  0.5, -0.5, -0.5, -0.5, -0.5,
+    -0.5, -0.5, -0.5, -0.5, -0.5,  0.5, -0.5,  0.5,  0.5,
+     0.5,  0.5,  0.5,  0.5, -0.5, -0.5,  0.5,  0.5, -0.5,
+     0.5, -0.5, -0.5,  0.5,  0.5,  0.5,  0.5, -0.5,  0.5,
+    -0.5, -0.5, -0.5,  0.5, -0.5, -0.5,  0.5, -0.5,  0.5,
+     0.5, -0.5,  0.5, -0.5, -0.5,  0.5, -0.5, -0.5, -0.5,
+    -0.5,  0.5, -0.5,  0.5,  0.5,  0.5,  0.5,  0.5, -0.5,
+     0.5,  0.5,  0.5, -0.5,  0.5, -0.5, -0.5,  0.5,  0.5,
+];
+
+#[rustfmt::skip]
+const UNIT_QUAD: [f32; 12] = [
+    -0.5, -0.5,  0.5, -0.5,  0.5,  0.5,
+    -0.5, -0.5,  0.5,  0.5, -0.5,  0.5,
+];
+
+const FULLSCREEN_TRIANGLE: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+
+/// Vertex shader for [`Primitive::fullscreen_triangle`] passes, writing screen UVs to `v_uv`.
+pub const FULLSCREEN_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_position;
+
+out vec2 v_uv;
+
+void main() {
+    v_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+/// # Primitive
+///
+/// A simple built-in mesh with positions at attribute 0, drawn as triangles.
+/// Used by effects that need a cube, a quad, or a fullscreen pass.
+pub struct Primitive {
+    vao: Vao,
+    _vbo: BufferObject,
+    vertex_count: GLsizei,
+}
+
+impl Primitive {
+    /// A cube from -0.5 to 0.5 on every axis, wound counter-clockwise outward.
+    pub fn unit_cube() -> Self {
+        Self::new(&UNIT_CUBE, 3)
+    }
+
+    /// A quad from -0.5 to 0.5 on X and Y, facing +Z.
+    pub fn unit_quad() -> Self {
+        Self::new(&UNIT_QUAD, 2)
+    }
+
+    /// A single triangle covering the whole screen in clip space, for post-processing.
+    pub fn fullscreen_triangle() -> Self {
+        Self::new(&FULLSCREEN_TRIANGLE, 2)
+    }
+
+    fn new(positions: &[f32], components: GLint) -> Self {
+        let vao = Vao::new();
+        vao.bind();
+
+        let vbo = BufferObject::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW);
+        vbo.bind();
+        vbo.store_f32_data(positions);
+
+        let position = VertexAttribute::new(
+            0,
+            components,
+            gl::FLOAT,
+            gl::FALSE,
+            components * mem::size_of::<GLfloat>() as GLsizei,
+            ptr::null(),
+        );
+        position.enable();
+        Vao::unbind();
+
+        Self {
+            vao,
+            _vbo: vbo,
+            vertex_count: positions.len() as GLsizei / components,
+        }
+    }
+
+    /// Draws the primitive with the currently bound shader.
+    pub fn draw(&self) {
+        self.vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count);
+        }
+        Vao::unbind();
+    }
+
+    /// Draws many instances of the primitive with the currently bound shader.
+    pub fn draw_instanced(&self, instance_count: usize) {
+        self.vao.bind();
+        unsafe {
+            gl::DrawArraysInstanced(
+                gl::TRIANGLES,
+                0,
+                self.vertex_count,
+                instance_count as GLsizei,
+            );
+        }
+        Vao::unbind();
+    }
+
+    /// Binds the primitive's vertex array, e.g. to attach per-instance attributes.
+    pub fn bind(&self) {
+        self.vao.bind();
+    }
+}
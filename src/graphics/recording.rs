@@ -0,0 +1,419 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use gl::types::*;
+
+use crate::custom_errors::Errors;
+
+/// What a [`Recorder`] produces.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptureFormat {
+    /// Numbered PNG files (`frame_00000.png`, ...) in a directory.
+    ImageSequence { directory: PathBuf },
+    /// An animated, looping GIF.
+    Gif { path: PathBuf },
+    /// An H.264 video, encoded by piping frames to an `ffmpeg` executable.
+    Mp4 { path: PathBuf, ffmpeg: PathBuf },
+}
+
+/// Settings for a recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaptureSettings {
+    pub format: CaptureFormat,
+    /// Frames per second of the output, independent of the game's frame rate.
+    pub fps: f32,
+    /// Stops automatically after this many seconds; `None` records until stopped.
+    pub duration: Option<f32>,
+    /// Shrinks frames by this integer factor, averaging pixels. GIFs get large quickly.
+    pub downscale: u32,
+}
+
+impl CaptureSettings {
+    /// Records an image sequence at 30 fps until stopped.
+    pub fn image_sequence(directory: &str) -> Self {
+        Self::new(CaptureFormat::ImageSequence {
+            directory: directory.into(),
+        })
+    }
+
+    /// Records a GIF at 20 fps and half resolution until stopped.
+    pub fn gif(path: &str) -> Self {
+        Self {
+            fps: 20.0,
+            downscale: 2,
+            ..Self::new(CaptureFormat::Gif { path: path.into() })
+        }
+    }
+
+    /// Records an MP4 at 30 fps until stopped, using `ffmpeg` from the `PATH`.
+    pub fn mp4(path: &str) -> Self {
+        Self::new(CaptureFormat::Mp4 {
+            path: path.into(),
+            ffmpeg: "ffmpeg".into(),
+        })
+    }
+
+    fn new(format: CaptureFormat) -> Self {
+        Self {
+            format,
+            fps: 30.0,
+            duration: None,
+            downscale: 1,
+        }
+    }
+
+    /// Stops the recording automatically after a number of seconds.
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = Some(seconds);
+        self
+    }
+
+    /// Sets the output frame rate.
+    pub fn with_fps(mut self, fps: f32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// Sets the integer downscale factor.
+    pub fn with_downscale(mut self, downscale: u32) -> Self {
+        self.downscale = downscale;
+        self
+    }
+}
+
+/// Writes frames in one format; runs on the encoder thread.
+enum Sink {
+    Images { directory: PathBuf, next: usize },
+    Gif(Box<gif::Encoder<BufWriter<File>>>, u16),
+    Ffmpeg(Child, ChildStdin),
+}
+
+impl Sink {
+    fn open(settings: &CaptureSettings, width: u32, height: u32) -> Result<Self, Errors> {
+        match &settings.format {
+            CaptureFormat::ImageSequence { directory } => {
+                fs::create_dir_all(directory).map_err(capture_error)?;
+                Ok(Sink::Images {
+                    directory: directory.clone(),
+                    next: 0,
+                })
+            }
+            CaptureFormat::Gif { path } => {
+                let file = BufWriter::new(File::create(path).map_err(capture_error)?);
+                let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])
+                    .map_err(capture_error)?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(capture_error)?;
+                let delay = (100.0 / settings.fps).round().max(2.0) as u16;
+                Ok(Sink::Gif(Box::new(encoder), delay))
+            }
+            CaptureFormat::Mp4 { path, ffmpeg } => {
+                let mut child = Command::new(ffmpeg)
+                    .args([
+                        "-y",
+                        "-loglevel",
+                        "error",
+                        "-f",
+                        "rawvideo",
+                        "-pixel_format",
+                        "rgba",
+                    ])
+                    .args(["-video_size", &format!("{}x{}", width, height)])
+                    .args(["-framerate", &settings.fps.to_string(), "-i", "-"])
+                    .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .map_err(|error| {
+                        Errors::Capture(format!("Failed to start {}: {}", ffmpeg.display(), error))
+                    })?;
+                let stdin = child.stdin.take().expect("ffmpeg stdin is piped");
+                Ok(Sink::Ffmpeg(child, stdin))
+            }
+        }
+    }
+
+    fn write(&mut self, frame: &mut Frame) -> Result<(), Errors> {
+        match self {
+            Sink::Images { directory, next } => {
+                let path = directory.join(format!("frame_{:05}.png", next));
+                *next += 1;
+                let file = BufWriter::new(File::create(path).map_err(capture_error)?);
+                let mut encoder = png::Encoder::new(file, frame.width, frame.height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header().map_err(capture_error)?;
+                writer
+                    .write_image_data(&frame.pixels)
+                    .map_err(capture_error)
+            }
+            Sink::Gif(encoder, delay) => {
+                let mut gif_frame = gif::Frame::from_rgba_speed(
+                    frame.width as u16,
+                    frame.height as u16,
+                    &mut frame.pixels,
+                    10,
+                );
+                gif_frame.delay = *delay;
+                encoder.write_frame(&gif_frame).map_err(capture_error)
+            }
+            Sink::Ffmpeg(_, stdin) => stdin.write_all(&frame.pixels).map_err(capture_error),
+        }
+    }
+
+    fn finish(self) -> Result<(), Errors> {
+        match self {
+            Sink::Images { .. } | Sink::Gif(..) => Ok(()),
+            Sink::Ffmpeg(mut child, stdin) => {
+                drop(stdin);
+                let status = child.wait().map_err(capture_error)?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(Errors::Capture(format!("ffmpeg exited with {}", status)))
+                }
+            }
+        }
+    }
+}
+
+fn capture_error(error: impl std::fmt::Display) -> Errors {
+    Errors::Capture(error.to_string())
+}
+
+/// A captured frame, top row first, tightly packed RGBA.
+struct Frame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// A recording in progress.
+struct Session {
+    settings: CaptureSettings,
+    size: (u32, u32),
+    elapsed: f32,
+    next_frame_time: f32,
+    frames: usize,
+    sender: Sender<Frame>,
+    errors: Receiver<Errors>,
+    encoder: JoinHandle<Result<(), Errors>>,
+}
+
+/// # Recorder
+///
+/// Captures the window's back buffer into PNG sequences, GIFs or MP4 videos,
+/// for sharing demos and building regression footage. Frames are grabbed at the
+/// output frame rate regardless of the game's, and encoded on a background
+/// thread so only the pixel read-back costs frame time.
+///
+/// The window size is fixed when recording starts; stop and restart after a resize.
+///
+/// ## Example
+/// ```ignore
+/// let mut recorder = Recorder::new();
+///
+/// if input.key_pressed(Key::F9) {
+///     recorder.start(CaptureSettings::gif("clip.gif").with_duration(5.0), window.framebuffer_size())?;
+/// }
+///
+/// // After rendering, before swapping buffers:
+/// recorder.capture(world.time().unscaled_delta())?;
+/// window.update();
+/// ```
+#[derive(Default)]
+pub struct Recorder {
+    session: Option<Session>,
+}
+
+impl Recorder {
+    /// Creates an idle recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if a recording is in progress.
+    pub fn is_recording(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Returns the number of frames captured by the current recording.
+    pub fn frames_captured(&self) -> usize {
+        self.session.as_ref().map_or(0, |session| session.frames)
+    }
+
+    /// Returns the seconds recorded so far.
+    pub fn elapsed(&self) -> f32 {
+        self.session.as_ref().map_or(0.0, |session| session.elapsed)
+    }
+
+    /// Starts recording a window framebuffer of the given size, stopping any
+    /// recording in progress.
+    pub fn start(
+        &mut self,
+        settings: CaptureSettings,
+        window_size: (u32, u32),
+    ) -> Result<(), Errors> {
+        self.stop()?;
+
+        let downscale = settings.downscale.max(1);
+        let size = (window_size.0 / downscale, window_size.1 / downscale);
+        if size.0 == 0 || size.1 == 0 || settings.fps <= 0.0 {
+            return Err(Errors::Capture("Nothing to record".to_string()));
+        }
+        // Most H.264 encoders need even dimensions.
+        let size = match settings.format {
+            CaptureFormat::Mp4 { .. } => (size.0 & !1, size.1 & !1),
+            _ => size,
+        };
+        let mut sink = Sink::open(&settings, size.0, size.1)?;
+
+        let (sender, frames) = mpsc::channel::<Frame>();
+        let (error_sender, errors) = mpsc::channel();
+        let encoder = thread::Builder::new()
+            .name("nyanko-recorder".to_string())
+            .spawn(move || {
+                for mut frame in frames {
+                    if let Err(error) = sink.write(&mut frame) {
+                        let _ = error_sender.send(error);
+                        return Ok(());
+                    }
+                }
+                sink.finish()
+            })
+            .map_err(capture_error)?;
+
+        log::info!(
+            "Recording {}x{} at {} fps to {:?}",
+            size.0,
+            size.1,
+            settings.fps,
+            settings.format
+        );
+        self.session = Some(Session {
+            settings,
+            size,
+            elapsed: 0.0,
+            next_frame_time: 0.0,
+            frames: 0,
+            sender,
+            errors,
+            encoder,
+        });
+        Ok(())
+    }
+
+    /// Advances the recording clock and reads back the current frame when the
+    /// next output frame is due. Call after rendering, before swapping buffers.
+    /// Stops on its own when the duration is reached.
+    pub fn capture(&mut self, delta_time: f32) -> Result<(), Errors> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(());
+        };
+        if let Ok(error) = session.errors.try_recv() {
+            self.session = None;
+            return Err(error);
+        }
+
+        let interval = 1.0 / session.settings.fps;
+        // A slow frame repeats the image so the output keeps real-time pacing.
+        while session.next_frame_time <= session.elapsed {
+            let frame = read_frame(session.size, session.settings.downscale.max(1));
+            if session.sender.send(frame).is_err() {
+                break;
+            }
+            session.frames += 1;
+            session.next_frame_time += interval;
+        }
+        session.elapsed += delta_time;
+
+        if session
+            .settings
+            .duration
+            .is_some_and(|duration| session.elapsed >= duration)
+        {
+            self.stop()?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the recording, waiting for the encoder to flush.
+    pub fn stop(&mut self) -> Result<(), Errors> {
+        let Some(session) = self.session.take() else {
+            return Ok(());
+        };
+        drop(session.sender);
+        let result = session
+            .encoder
+            .join()
+            .unwrap_or_else(|_| Err(Errors::Capture("Encoder thread panicked".to_string())));
+        if let Ok(error) = session.errors.try_recv() {
+            return Err(error);
+        }
+        log::info!("Recording finished after {} frames", session.frames);
+        result
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.stop() {
+            log::error!("{}", error);
+        }
+    }
+}
+
+/// Reads the bound framebuffer's back buffer, flips it to top row first, and
+/// averages `downscale` x `downscale` blocks into one pixel.
+fn read_frame(size: (u32, u32), downscale: u32) -> Frame {
+    let (source_width, source_height) = (size.0 * downscale, size.1 * downscale);
+    let mut source = vec![0u8; (source_width * source_height * 4) as usize];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            source_width as GLsizei,
+            source_height as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            source.as_mut_ptr() as *mut _,
+        );
+    }
+
+    let (width, height) = size;
+    let area = downscale * downscale;
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+            for sy in 0..downscale {
+                // OpenGL rows start at the bottom.
+                let row = source_height - 1 - (y * downscale + sy);
+                for sx in 0..downscale {
+                    let index = ((row * source_width + x * downscale + sx) * 4) as usize;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += source[index + channel] as u32;
+                    }
+                }
+            }
+            let index = ((y * width + x) * 4) as usize;
+            for channel in 0..3 {
+                pixels[index + channel] = (sum[channel] / area) as u8;
+            }
+            // The window's alpha is meaningless for a capture.
+            pixels[index + 3] = 255;
+        }
+    }
+
+    Frame {
+        width,
+        height,
+        pixels,
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use super::camera::{Camera, CameraView, RenderTarget};
+use super::framebuffer::Framebuffer;
+use super::texture::Texture;
+use crate::math::projection::oblique_near_plane;
+use crate::math::*;
+
+/// # Planar Reflection
+///
+/// Renders the scene mirrored across a plane into its own texture, for water,
+/// mirrors and polished floors. The mirrored camera uses an oblique near plane
+/// matching the reflection plane, so anything below the surface is clipped
+/// without touching the scene's shaders.
+///
+/// ## Example
+/// ```ignore
+/// let mut reflection = PlanarReflection::new(Plane::new(vec3(0.0, 1.0, 0.0), 0.0), window.framebuffer_size());
+///
+/// renderer.render_cameras(cameras, window_size, |view| {
+///     reflection.render(view, |mirrored| draw_scene(mirrored));
+///     draw_scene(view);
+///     water.render(view, &water_transform, &reflection);
+/// });
+/// ```
+pub struct PlanarReflection {
+    pub plane: Plane,
+    /// Distance the clip plane is pushed below the surface to hide seams at the waterline.
+    pub clip_offset: f32,
+    camera: Camera,
+    framebuffer: Arc<Framebuffer>,
+}
+
+impl PlanarReflection {
+    /// Creates a reflection across `plane` rendered at the given resolution.
+    pub fn new(plane: Plane, size: (u32, u32)) -> Self {
+        let framebuffer = Arc::new(Framebuffer::new(size.0, size.1));
+        Self {
+            plane: plane.normalized(),
+            clip_offset: 0.05,
+            camera: Self::create_camera(&framebuffer),
+            framebuffer,
+        }
+    }
+
+    fn create_camera(framebuffer: &Arc<Framebuffer>) -> Camera {
+        Camera::perspective(Deg(60.0), 0.1, 1000.0)
+            .with_target(RenderTarget::Framebuffer(framebuffer.clone()))
+    }
+
+    /// Returns the texture holding the mirrored scene.
+    pub fn texture(&self) -> &Texture {
+        self.framebuffer.color_texture()
+    }
+
+    /// Returns the framebuffer the mirrored scene is rendered into.
+    pub fn framebuffer(&self) -> &Arc<Framebuffer> {
+        &self.framebuffer
+    }
+
+    /// Recreates the reflection texture at a new resolution.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if self.framebuffer.size() == (width, height) {
+            return;
+        }
+        self.framebuffer = Arc::new(Framebuffer::new(width, height));
+        self.camera = Self::create_camera(&self.framebuffer);
+    }
+
+    /// Renders the scene as seen by `view` mirrored across the plane. `draw` is
+    /// called with the mirrored view and should draw everything that can be
+    /// reflected. The view's own target and viewport are restored afterwards, so
+    /// this can be called from inside a camera's draw callback.
+    pub fn render<F: FnMut(&CameraView)>(&mut self, view: &CameraView, mut draw: F) {
+        self.camera.clear = view.camera.clear;
+        self.camera.layers = view.camera.layers;
+        self.camera.fog = view.camera.fog;
+
+        let (width, height) = self.framebuffer.size();
+        let reflection = self.plane.reflection_matrix();
+        let mirrored_view = view.view * reflection;
+
+        let clip_plane = Plane::new(self.plane.normal, self.plane.distance + self.clip_offset)
+            .transformed(&mirrored_view);
+        let projection = oblique_near_plane(view.projection, clip_plane.to_vector4());
+
+        let mirrored = CameraView {
+            camera: &self.camera,
+            view: mirrored_view,
+            projection,
+            view_projection: projection * mirrored_view,
+            position: self.plane.reflect_point(view.position),
+            viewport: (0, 0, width as i32, height as i32),
+            target_size: (width, height),
+        };
+
+        mirrored.prepare();
+        unsafe {
+            // Mirroring flips triangle winding.
+            gl::FrontFace(gl::CW);
+        }
+        draw(&mirrored);
+        unsafe {
+            gl::FrontFace(gl::CCW);
+        }
+
+        let (x, y, width, height) = view.viewport;
+        view.camera.target.bind();
+        unsafe {
+            gl::Viewport(x, y, width, height);
+        }
+    }
+}
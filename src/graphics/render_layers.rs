@@ -0,0 +1,94 @@
+/// # Render Layers
+///
+/// A bitmask of up to 32 layers. Put it on renderable entities and on cameras:
+/// a camera only draws entities whose layers intersect its own. Entities without
+/// the component live on layer 0 only.
+///
+/// ## Example
+/// ```ignore
+/// const WEAPON_LAYER: u8 = 1;
+/// const MINIMAP_LAYER: u8 = 2;
+///
+/// world.insert(weapon, RenderLayers::layer(WEAPON_LAYER));
+/// world.insert(map_icon, RenderLayers::layer(MINIMAP_LAYER));
+///
+/// let main_camera = Camera::perspective(Deg(60.0), 0.1, 1000.0)
+///     .with_layers(RenderLayers::default().with(WEAPON_LAYER));
+/// let minimap_camera = Camera::orthographic(100.0, 0.1, 500.0)
+///     .with_layers(RenderLayers::default().with(MINIMAP_LAYER));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderLayers(u32);
+
+impl RenderLayers {
+    /// The layer entities without a `RenderLayers` component belong to.
+    pub const DEFAULT_LAYER: u8 = 0;
+    /// The highest usable layer index.
+    pub const MAX_LAYER: u8 = 31;
+
+    /// Creates a mask containing a single layer.
+    pub fn layer(layer: u8) -> Self {
+        Self::none().with(layer)
+    }
+
+    /// Creates a mask from raw bits.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Creates a mask containing every layer.
+    pub const fn all() -> Self {
+        Self(u32::MAX)
+    }
+
+    /// Creates an empty mask.
+    pub const fn none() -> Self {
+        Self(0)
+    }
+
+    /// Returns the mask with a layer added.
+    pub fn with(self, layer: u8) -> Self {
+        Self(self.0 | Self::bit(layer))
+    }
+
+    /// Returns the mask with a layer removed.
+    pub fn without(self, layer: u8) -> Self {
+        Self(self.0 & !Self::bit(layer))
+    }
+
+    /// Checks if the mask contains a layer.
+    pub fn contains(&self, layer: u8) -> bool {
+        self.0 & Self::bit(layer) != 0
+    }
+
+    /// Checks if two masks share at least one layer.
+    pub fn intersects(&self, other: &RenderLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns the raw bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Iterates over the layers in the mask.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=Self::MAX_LAYER).filter(|layer| self.contains(*layer))
+    }
+
+    fn bit(layer: u8) -> u32 {
+        assert!(
+            layer <= Self::MAX_LAYER,
+            "Render layer {} is out of range (0..={})",
+            layer,
+            Self::MAX_LAYER
+        );
+        1 << layer
+    }
+}
+
+impl Default for RenderLayers {
+    fn default() -> Self {
+        Self::layer(Self::DEFAULT_LAYER)
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::camera::{Camera, CameraView, RenderTarget};
+use super::framebuffer::Framebuffer;
+use super::sprite_batch::SpriteBatch;
+use super::texture::Texture;
+use crate::math::*;
+use crate::scene::Transform;
+
+/// How a [`RenderTexture`] picks its resolution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderTextureSize {
+    /// A fixed size in pixels.
+    Fixed(u32, u32),
+    /// A fraction of the window's framebuffer size, following it on resize.
+    Relative(f32),
+}
+
+impl RenderTextureSize {
+    /// Returns the pixel size for a window framebuffer size, at least 1x1.
+    pub fn resolve(&self, window_size: (u32, u32)) -> (u32, u32) {
+        let (width, height) = match *self {
+            RenderTextureSize::Fixed(width, height) => (width, height),
+            RenderTextureSize::Relative(fraction) => (
+                (window_size.0 as f32 * fraction).round() as u32,
+                (window_size.1 as f32 * fraction).round() as u32,
+            ),
+        };
+        (width.max(1), height.max(1))
+    }
+}
+
+/// # Render Texture
+///
+/// A secondary camera that renders into its own framebuffer, exposed as a
+/// texture for UI or materials: minimaps, rear-view mirrors, security camera
+/// screens, portraits. The framebuffer is owned here and recreated when the
+/// requested size changes; the camera's target is kept pointing at it.
+///
+/// ## Example
+/// ```ignore
+/// let mut minimap = RenderTexture::new(
+///     Camera::orthographic(80.0, 0.1, 200.0).with_clear(ClearMode::Color(Color::BLACK)),
+///     RenderTextureSize::Fixed(256, 256),
+/// );
+/// minimap.transform = Transform::from_translation(vec3(player.x, 100.0, player.z));
+/// minimap.transform.look_at(player, -Vec3::unit_z());
+///
+/// minimap.render(window.framebuffer_size(), |view| draw_scene(view));
+/// batch.begin(window.framebuffer_size());
+/// minimap.draw(&mut batch, Rect::new(16.0, 16.0, 200.0, 200.0), Color::WHITE);
+/// batch.end();
+/// ```
+pub struct RenderTexture {
+    pub camera: Camera,
+    pub transform: Transform,
+    pub size: RenderTextureSize,
+    framebuffer: Arc<Framebuffer>,
+}
+
+impl RenderTexture {
+    /// Creates a render texture, retargeting the camera to it. Relative sizes
+    /// start at 1x1 until the first render.
+    pub fn new(camera: Camera, size: RenderTextureSize) -> Self {
+        let (width, height) = size.resolve((1, 1));
+        let framebuffer = Arc::new(Framebuffer::new(width, height));
+        Self {
+            camera: camera.with_target(RenderTarget::Framebuffer(framebuffer.clone())),
+            transform: Transform::new(),
+            size,
+            framebuffer,
+        }
+    }
+
+    /// Sets the camera's transform.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Returns the framebuffer being rendered into.
+    pub fn framebuffer(&self) -> &Arc<Framebuffer> {
+        &self.framebuffer
+    }
+
+    /// Returns the rendered image. It is stored bottom-up; draw it with
+    /// [`RenderTexture::uv_rect`] to show it upright.
+    pub fn texture(&self) -> &Arc<Texture> {
+        self.framebuffer.shared_color_texture()
+    }
+
+    /// Returns the UV rectangle that shows the image upright in a top-left origin
+    /// [`SpriteBatch`].
+    pub fn uv_rect(&self) -> Rect {
+        Rect::new(0.0, 1.0, 1.0, -1.0)
+    }
+
+    /// Recreates the framebuffer if the requested size changed for this window size.
+    pub fn update_size(&mut self, window_size: (u32, u32)) {
+        let size = self.size.resolve(window_size);
+        if self.framebuffer.size() != size {
+            self.framebuffer = Arc::new(Framebuffer::new(size.0, size.1));
+            self.camera.target = RenderTarget::Framebuffer(self.framebuffer.clone());
+        }
+    }
+
+    /// Renders the camera into the texture, calling `draw` with its view, then
+    /// restores the window framebuffer and the previous viewport.
+    pub fn render<F>(&mut self, window_size: (u32, u32), draw: F)
+    where
+        F: FnOnce(&CameraView),
+    {
+        self.update_size(window_size);
+
+        let mut previous_viewport = [0 as GLint; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+        }
+
+        let view = CameraView::new(&self.camera, &self.transform, self.framebuffer.size());
+        view.prepare();
+        draw(&view);
+
+        Framebuffer::unbind();
+        let [x, y, width, height] = previous_viewport;
+        unsafe {
+            gl::Viewport(x, y, width, height);
+        }
+    }
+
+    /// Draws the rendered image upright into a rectangle of a sprite batch.
+    pub fn draw(&self, batch: &mut SpriteBatch, destination: Rect, color: Color) {
+        batch.draw_sprite(self.texture(), destination, self.uv_rect(), color);
+    }
+}
@@ -0,0 +1,227 @@
+use super::camera::{Camera, CameraView};
+use super::camera_effects::CameraEffects;
+use super::clusters::{ClusterConfig, LightClusters};
+use super::debug_view::DebugView;
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use crate::arena::FrameArena;
+use crate::cvars::CVars;
+use crate::ecs::World;
+use crate::math::Color;
+use crate::scene::Transform;
+
+/// Renderer-wide settings chosen at creation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RendererConfig {
+    /// Dimensions of the clustered forward lighting grid.
+    pub light_clusters: ClusterConfig,
+}
+
+/// # Renderer
+///
+/// Owns frame-level render state: clearing, depth testing, the active debug
+/// view, and a [`FrameArena`] for data that only lives until the next frame.
+///
+/// ## Example
+/// ```ignore
+/// let mut renderer = Renderer::new();
+///
+/// while !window.should_close() {
+///     renderer.begin_frame();
+///     shader.bind();
+///     renderer.apply_debug_uniform(&shader);
+///     // draw calls...
+///     renderer.end_frame();
+///     window.update();
+/// }
+/// ```
+pub struct Renderer {
+    clear_color: Color,
+    debug_view: DebugView,
+    applied_debug_view: Option<DebugView>,
+    config: RendererConfig,
+    light_clusters: LightClusters,
+    cvar_debug_view: Option<i64>,
+    frame_arena: FrameArena,
+}
+
+impl Renderer {
+    /// Creates a renderer with the default configuration. The GL context must
+    /// already be current.
+    pub fn new() -> Self {
+        Self::with_config(RendererConfig::default())
+    }
+
+    /// Creates a renderer with the given configuration. The GL context must
+    /// already be current.
+    pub fn with_config(config: RendererConfig) -> Self {
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LESS);
+        }
+        Self {
+            clear_color: Color::rgb(0.2, 0.3, 0.3),
+            debug_view: DebugView::Lit,
+            applied_debug_view: None,
+            config,
+            light_clusters: LightClusters::new(config.light_clusters),
+            cvar_debug_view: None,
+            frame_arena: FrameArena::new(),
+        }
+    }
+
+    /// Returns the renderer's configuration.
+    pub fn config(&self) -> &RendererConfig {
+        &self.config
+    }
+
+    /// Changes the clustered lighting grid dimensions.
+    pub fn set_cluster_config(&mut self, config: ClusterConfig) {
+        self.config.light_clusters = config;
+        self.light_clusters.set_config(config);
+    }
+
+    /// Returns the clustered lighting state shared by every camera.
+    pub fn light_clusters(&self) -> &LightClusters {
+        &self.light_clusters
+    }
+
+    /// Returns the clustered lighting state for rebuilding.
+    pub fn light_clusters_mut(&mut self) -> &mut LightClusters {
+        &mut self.light_clusters
+    }
+
+    /// Returns the arena for data used until the next frame begins.
+    pub fn frame_arena(&self) -> &FrameArena {
+        &self.frame_arena
+    }
+
+    /// Sets the color the frame is cleared to.
+    pub fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    /// Returns the active debug view.
+    pub fn debug_view(&self) -> DebugView {
+        self.debug_view
+    }
+
+    /// Switches to another debug view, taking effect next frame.
+    pub fn set_debug_view(&mut self, view: DebugView) {
+        if view != self.debug_view {
+            log::info!("Debug view: {}", view.name());
+        }
+        self.debug_view = view;
+    }
+
+    /// Switches to the next debug view.
+    pub fn cycle_debug_view(&mut self) {
+        self.set_debug_view(self.debug_view.next());
+    }
+
+    /// Applies `r.debug_view` when it changes, so views picked in code stay
+    /// until the variable is set again. Call once per frame.
+    pub fn apply_cvars(&mut self, cvars: &CVars) {
+        if let Some(index) = cvars.int("r.debug_view") {
+            if self.cvar_debug_view != Some(index) {
+                self.cvar_debug_view = Some(index);
+                if let Some(view) = DebugView::ALL.get(index as usize) {
+                    self.set_debug_view(*view);
+                }
+            }
+        }
+    }
+
+    /// Sets the debug view uniform on a shader that includes `DEBUG_VIEW_GLSL`.
+    pub fn apply_debug_uniform(&self, shader: &ShaderProgram) {
+        self.debug_view.apply_uniform(shader);
+    }
+
+    /// Prepares GL state, clears the framebuffer and frees the previous
+    /// frame's arena allocations.
+    pub fn begin_frame(&mut self) {
+        self.frame_arena.reset();
+        if self.applied_debug_view != Some(self.debug_view) {
+            if let Some(previous) = self.applied_debug_view {
+                previous.reset_gl_state();
+            }
+            self.debug_view.apply_gl_state();
+            self.applied_debug_view = Some(self.debug_view);
+        }
+
+        let color = match self.debug_view {
+            DebugView::Overdraw => Color::BLACK,
+            _ => self.clear_color.to_linear(),
+        };
+        unsafe {
+            gl::ClearColor(color.r, color.g, color.b, color.a);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Renders every active camera in ascending order, calling `draw` once per camera
+    /// after its target is bound and its viewport cleared.
+    pub fn render_cameras<'a, I, F>(&mut self, cameras: I, window_size: (u32, u32), mut draw: F)
+    where
+        I: IntoIterator<Item = (&'a Camera, &'a Transform)>,
+        F: FnMut(&CameraView),
+    {
+        let cameras = self
+            .frame_arena
+            .alloc_from_iter(cameras.into_iter().filter(|(camera, _)| camera.active));
+        cameras.sort_by_key(|(camera, _)| camera.order);
+
+        for (camera, transform) in cameras.iter() {
+            let view = CameraView::new(camera, transform, camera.target.size(window_size));
+            view.prepare();
+            draw(&view);
+        }
+
+        Framebuffer::unbind();
+        unsafe {
+            gl::Viewport(0, 0, window_size.0 as i32, window_size.1 as i32);
+        }
+    }
+
+    /// Renders every entity that has both a `Camera` and a `Transform`, with
+    /// any [`CameraEffects`] applied.
+    pub fn render_world_cameras<F>(&mut self, world: &World, window_size: (u32, u32), draw: F)
+    where
+        F: FnMut(&CameraView),
+    {
+        let (Some(cameras), Some(transforms)) =
+            (world.try_read::<Camera>(), world.try_read::<Transform>())
+        else {
+            return;
+        };
+        let effects = world.try_read::<CameraEffects>();
+        let pairs: Vec<(Camera, Transform)> = cameras
+            .iter()
+            .filter_map(|(entity, camera)| {
+                let transform = transforms.get(*entity)?;
+                let effect = effects.as_ref().and_then(|effects| effects.get(*entity));
+                Some(match effect {
+                    Some(effects) => (
+                        effects.apply_camera(camera),
+                        effects.apply_transform(transform),
+                    ),
+                    None => (camera.clone(), *transform),
+                })
+            })
+            .collect();
+        self.render_cameras(
+            pairs.iter().map(|(camera, transform)| (camera, transform)),
+            window_size,
+            draw,
+        );
+    }
+
+    /// Finishes the frame.
+    pub fn end_frame(&mut self) {}
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use gl::types::*;
+
+use super::gl_wrapper::{is_gles, ShaderProgram};
+use super::gpu_info::GpuInfo;
+use crate::math::StableHasher;
+
+/// Start of every cache file, followed by the layout version.
+const MAGIC: &[u8; 4] = b"NYSB";
+const FORMAT_VERSION: u32 = 1;
+/// Magic, layout version and binary format.
+const HEADER_LEN: usize = 12;
+
+/// Counts of programs the cache served, compiled and failed to reuse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShaderCacheStats {
+    /// Programs loaded from a cached binary.
+    pub hits: u32,
+    /// Programs compiled from source, with no binary cached yet.
+    pub misses: u32,
+    /// Cached binaries the driver rejected, usually after a driver update.
+    pub rejected: u32,
+}
+
+/// # Shader Cache
+///
+/// Keeps the binaries of linked shader programs on disk, so later runs load
+/// them instead of compiling every shader again. Binaries are keyed by a
+/// hash of the sources and of the GPU and driver, since they only work on
+/// the driver that produced them; a binary the driver rejects anyway is
+/// deleted and compiled again. Drivers without program binary support
+/// simply compile every time.
+///
+/// ## Example
+/// ```ignore
+/// let mut cache = ShaderCache::new("cache/shaders");
+/// let mut shader = cache.program(&vertex_source, &fragment_source);
+/// shader.create_uniform("u_mvp");
+///
+/// let variants = ShaderVariants::load("assets/shaders/standard.vert", "assets/shaders/standard.frag")
+///     .with_cache(cache);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShaderCache {
+    directory: PathBuf,
+    enabled: bool,
+    /// Identifies the GPU and driver, queried on first use.
+    driver: Option<u64>,
+    stats: ShaderCacheStats,
+}
+
+impl ShaderCache {
+    /// Creates a cache storing binaries in a folder, created when needed.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            enabled: true,
+            driver: None,
+            stats: ShaderCacheStats::default(),
+        }
+    }
+
+    /// Sets whether binaries are used, e.g. to turn caching off while
+    /// debugging shader compilation.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Returns the folder binaries are stored in.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Returns how many programs were loaded, compiled and rejected.
+    pub fn stats(&self) -> ShaderCacheStats {
+        self.stats
+    }
+
+    /// Returns a linked program for vertex and fragment shader source code,
+    /// loaded from its cached binary if there is one and compiled and cached
+    /// otherwise. Needs a current OpenGL context.
+    pub fn program(
+        &mut self,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> ShaderProgram {
+        if !self.enabled || !supports_program_binaries() {
+            return ShaderProgram::from_source(vertex_shader_source, fragment_shader_source);
+        }
+        let path = self.path(vertex_shader_source, fragment_shader_source);
+
+        if let Some((format, binary)) = read_binary(&path) {
+            if let Some(program) = ShaderProgram::from_binary(format, &binary) {
+                self.stats.hits += 1;
+                return program;
+            }
+            log::debug!(
+                "Driver rejected cached shader {}, recompiling",
+                path.display()
+            );
+            self.stats.rejected += 1;
+            let _ = fs::remove_file(&path);
+        }
+
+        self.stats.misses += 1;
+        let program = ShaderProgram::link(vertex_shader_source, fragment_shader_source, true);
+        if let Some((format, binary)) = program.binary() {
+            if let Err(error) = self.write_binary(&path, format, &binary) {
+                log::warn!("Failed to cache shader {}: {}", path.display(), error);
+            }
+        }
+        program
+    }
+
+    /// Deletes every cached binary, returning how many there were.
+    pub fn clear(&mut self) -> usize {
+        let Ok(entries) = fs::read_dir(&self.directory) else {
+            return 0;
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "bin"))
+            .filter(|path| fs::remove_file(path).is_ok())
+            .count()
+    }
+
+    /// Returns the file a program's binary is cached in.
+    fn path(&mut self, vertex_shader_source: &str, fragment_shader_source: &str) -> PathBuf {
+        let driver = *self.driver.get_or_insert_with(|| {
+            let gpu = GpuInfo::query();
+            let mut hasher = StableHasher::new();
+            gpu.vendor.hash(&mut hasher);
+            gpu.renderer.hash(&mut hasher);
+            gpu.version_string.hash(&mut hasher);
+            hasher.finish()
+        });
+        let mut hasher = StableHasher::with_seed(driver);
+        FORMAT_VERSION.hash(&mut hasher);
+        is_gles().hash(&mut hasher);
+        vertex_shader_source.hash(&mut hasher);
+        fragment_shader_source.hash(&mut hasher);
+        self.directory.join(format!("{:016x}.bin", hasher.finish()))
+    }
+
+    fn write_binary(&self, path: &Path, format: GLenum, binary: &[u8]) -> std::io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        let mut data = Vec::with_capacity(HEADER_LEN + binary.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(&format.to_le_bytes());
+        data.extend_from_slice(binary);
+        // Write then rename, so another run never reads half a binary.
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, data)?;
+        fs::rename(&temporary, path)
+    }
+}
+
+/// Reads a cached binary and its format, or `None` if there is no valid one.
+fn read_binary(path: &Path) -> Option<(GLenum, Vec<u8>)> {
+    let mut data = fs::read(path).ok()?;
+    if data.len() <= HEADER_LEN
+        || &data[0..4] != MAGIC
+        || data[4..8] != FORMAT_VERSION.to_le_bytes()
+    {
+        return None;
+    }
+    let format = GLenum::from_le_bytes(data[8..12].try_into().ok()?);
+    data.drain(..HEADER_LEN);
+    Some((format, data))
+}
+
+/// Checks if the driver can save and load program binaries at all.
+fn supports_program_binaries() -> bool {
+    if !gl::GetProgramBinary::is_loaded() || !gl::ProgramBinary::is_loaded() {
+        return false;
+    }
+    let mut formats = 0;
+    unsafe {
+        gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats);
+    }
+    formats > 0
+}
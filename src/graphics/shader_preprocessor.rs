@@ -0,0 +1,471 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::clusters::CLUSTERED_LIGHTS_GLSL;
+use super::debug_view::DEBUG_VIEW_GLSL;
+use super::fog::FOG_GLSL;
+use super::gl_wrapper::ShaderProgram;
+use super::material::MATERIAL_GLSL;
+use super::shader_cache::ShaderCache;
+use super::shadows::POINT_SHADOW_GLSL;
+use super::transparency::OIT_GLSL;
+use crate::custom_errors::Errors;
+use crate::name::AssetPath;
+
+/// Deepest chain of nested includes before giving up.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// # Shader Defines
+///
+/// The `#define`s selecting one permutation of a shader, kept sorted so the
+/// same set always produces the same source and cache key.
+///
+/// ## Example
+/// ```ignore
+/// let defines = ShaderDefines::new()
+///     .with("NORMAL_MAP")
+///     .with_value("NUM_CASCADES", 4);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderDefines {
+    defines: BTreeMap<String, String>,
+}
+
+impl ShaderDefines {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a flag, defined without a value.
+    pub fn with(mut self, name: &str) -> Self {
+        self.set(name, "");
+        self
+    }
+
+    /// Adds a define with a value.
+    pub fn with_value(mut self, name: &str, value: impl ToString) -> Self {
+        self.set(name, &value.to_string());
+        self
+    }
+
+    /// Adds a flag if `enabled`, so material options map straight to defines.
+    pub fn with_if(self, name: &str, enabled: bool) -> Self {
+        if enabled {
+            self.with(name)
+        } else {
+            self
+        }
+    }
+
+    /// Adds or replaces a define.
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.defines.insert(name.to_string(), value.to_string());
+    }
+
+    /// Removes a define.
+    pub fn remove(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    /// Checks if a define is set.
+    pub fn contains(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    /// Returns a define's value, empty for flags.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.defines.get(name).map(String::as_str)
+    }
+
+    /// Iterates over the defines in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defines
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns a string naming this permutation, such as
+    /// `NORMAL_MAP;NUM_CASCADES=4`.
+    pub fn key(&self) -> String {
+        let mut key = String::new();
+        for (name, value) in self.iter() {
+            if !key.is_empty() {
+                key.push(';');
+            }
+            key.push_str(name);
+            if !value.is_empty() {
+                let _ = write!(key, "={}", value);
+            }
+        }
+        key
+    }
+}
+
+/// # Shader Library
+///
+/// Resolves `#include "name"` directives and injects `#define`s into GLSL
+/// sources. Includes are looked up among the registered sources first, then
+/// next to the including file, then in the search directories. The engine's
+/// GLSL helpers are registered as `nyanko/fog.glsl`, `nyanko/material.glsl`,
+/// `nyanko/clustered_lights.glsl`, `nyanko/debug_view.glsl`,
+/// `nyanko/point_shadow.glsl` and `nyanko/oit.glsl`.
+///
+/// Each file is included at most once per shader, so shared helpers can be
+/// included from several places without include guards. `#line` directives
+/// keep compiler errors pointing at the right line; the string number is
+/// the include's position in [`ShaderLibrary::last_sources`].
+///
+/// ## Example
+/// ```ignore
+/// let mut library = ShaderLibrary::new().with_search_directory("assets/shaders");
+/// library.register("game/wind.glsl", WIND_GLSL);
+///
+/// let source = library.load("assets/shaders/foliage.frag", &ShaderDefines::new().with("WIND"))?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShaderLibrary {
+    sources: HashMap<AssetPath, String>,
+    search_directories: Vec<PathBuf>,
+    last_sources: Vec<String>,
+}
+
+impl ShaderLibrary {
+    /// Creates a library holding the engine's GLSL helpers.
+    pub fn new() -> Self {
+        let mut library = Self {
+            sources: HashMap::new(),
+            search_directories: Vec::new(),
+            last_sources: Vec::new(),
+        };
+        library.register("nyanko/fog.glsl", FOG_GLSL);
+        library.register("nyanko/material.glsl", MATERIAL_GLSL);
+        library.register("nyanko/clustered_lights.glsl", CLUSTERED_LIGHTS_GLSL);
+        library.register("nyanko/debug_view.glsl", DEBUG_VIEW_GLSL);
+        library.register("nyanko/point_shadow.glsl", POINT_SHADOW_GLSL);
+        library.register("nyanko/oit.glsl", OIT_GLSL);
+        library
+    }
+
+    /// Adds a directory includes are searched in.
+    pub fn with_search_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.search_directories.push(directory.into());
+        self
+    }
+
+    /// Registers source code under an include name.
+    pub fn register(&mut self, name: &str, source: &str) {
+        self.sources
+            .insert(AssetPath::new(name), source.to_string());
+    }
+
+    /// Returns the files included by the last preprocessed shader, indexed by
+    /// the string number of their `#line` directives. Index 0 is the shader
+    /// itself.
+    pub fn last_sources(&self) -> &[String] {
+        &self.last_sources
+    }
+
+    /// Reads a shader file and preprocesses it.
+    pub fn load(&mut self, path: &str, defines: &ShaderDefines) -> Result<String, Errors> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| Errors::ShaderPreprocess(format!("{}: {}", path, error)))?;
+        self.preprocess_file(&source, Some(Path::new(path)), defines)
+    }
+
+    /// Preprocesses shader source that didn't come from a file, so includes
+    /// are only looked up among registered sources and search directories.
+    pub fn preprocess(&mut self, source: &str, defines: &ShaderDefines) -> Result<String, Errors> {
+        self.preprocess_file(source, None, defines)
+    }
+
+    fn preprocess_file(
+        &mut self,
+        source: &str,
+        path: Option<&Path>,
+        defines: &ShaderDefines,
+    ) -> Result<String, Errors> {
+        let name = path.map_or_else(|| "<source>".to_string(), |path| path.display().to_string());
+        self.last_sources = vec![name.clone()];
+
+        let mut output = String::new();
+        let mut body = source;
+        let mut first_line = 1;
+        // `#version` must stay first; the defines go right after it. Inline
+        // sources often start with an empty line, which is skipped.
+        let start = source.len() - source.trim_start().len();
+        let version = source[start..].lines().next().unwrap_or("");
+        if version.starts_with("#version") {
+            output.push_str(version.trim());
+            output.push('\n');
+            body = source[start..]
+                .split_once('\n')
+                .map_or("", |(_, rest)| rest);
+            first_line = source[..start].matches('\n').count() + 2;
+        }
+        for (define, value) in defines.iter() {
+            let _ = writeln!(
+                output,
+                "{}",
+                format!("#define {} {}", define, value).trim_end()
+            );
+        }
+
+        let mut stack = vec![name];
+        self.expand(body, path, first_line, 0, &mut stack, &mut output)?;
+        Ok(output)
+    }
+
+    fn expand(
+        &mut self,
+        source: &str,
+        path: Option<&Path>,
+        first_line: usize,
+        string_number: usize,
+        stack: &mut Vec<String>,
+        output: &mut String,
+    ) -> Result<(), Errors> {
+        let _ = writeln!(output, "#line {} {}", first_line, string_number);
+        for (index, line) in source.lines().enumerate() {
+            let Some(target) = parse_include(line) else {
+                output.push_str(line);
+                output.push('\n');
+                continue;
+            };
+            let here = format!(
+                "{}:{}",
+                stack.last().expect("Stack starts non-empty"),
+                first_line + index
+            );
+            let target = target
+                .map_err(|message| Errors::ShaderPreprocess(format!("{}: {}", here, message)))?;
+            let (name, included, included_path) = self.resolve(target, path).ok_or_else(|| {
+                Errors::ShaderPreprocess(format!("{}: can't find include \"{}\"", here, target))
+            })?;
+
+            if stack.contains(&name) {
+                return Err(Errors::ShaderPreprocess(format!(
+                    "{}: include cycle {} -> {}",
+                    here,
+                    stack.join(" -> "),
+                    name
+                )));
+            }
+            if stack.len() > MAX_INCLUDE_DEPTH {
+                return Err(Errors::ShaderPreprocess(format!(
+                    "{}: includes nested deeper than {}",
+                    here, MAX_INCLUDE_DEPTH
+                )));
+            }
+            if !self.last_sources.contains(&name) {
+                self.last_sources.push(name.clone());
+                let number = self.last_sources.len() - 1;
+                stack.push(name);
+                self.expand(
+                    &included,
+                    included_path.as_deref(),
+                    1,
+                    number,
+                    stack,
+                    output,
+                )?;
+                stack.pop();
+            }
+            let _ = writeln!(output, "#line {} {}", first_line + index + 1, string_number);
+        }
+        Ok(())
+    }
+
+    /// Finds an include, returning its unique name, source and file path.
+    fn resolve(
+        &self,
+        target: &str,
+        from: Option<&Path>,
+    ) -> Option<(String, String, Option<PathBuf>)> {
+        if let Some(source) = self.sources.get(&AssetPath::new(target)) {
+            return Some((target.to_string(), source.clone(), None));
+        }
+        let beside = from
+            .and_then(Path::parent)
+            .map(|directory| directory.join(target));
+        beside
+            .into_iter()
+            .chain(
+                self.search_directories
+                    .iter()
+                    .map(|directory| directory.join(target)),
+            )
+            .find_map(|candidate| {
+                let source = fs::read_to_string(&candidate).ok()?;
+                let name = fs::canonicalize(&candidate)
+                    .unwrap_or_else(|_| candidate.clone())
+                    .display()
+                    .to_string();
+                Some((name, source, Some(candidate)))
+            })
+    }
+}
+
+impl Default for ShaderLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the target of an `#include` line, or `None` for other lines.
+fn parse_include(line: &str) -> Option<Result<&str, String>> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("include")?.trim();
+    let target = rest
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .or_else(|| {
+            rest.strip_prefix('<')
+                .and_then(|rest| rest.strip_suffix('>'))
+        });
+    Some(target.ok_or_else(|| format!("expected #include \"name\", got '{}'", line.trim())))
+}
+
+/// Called on a freshly compiled permutation, e.g. to create its uniforms,
+/// which can depend on the defines.
+pub type VariantSetup = dyn Fn(&mut ShaderProgram, &ShaderDefines) + Send + Sync;
+
+/// # Shader Variants
+///
+/// One uber-shader compiled on demand into a permutation per set of defines,
+/// such as `SKINNED`, `NORMAL_MAP` or `NUM_CASCADES=4`, so material options
+/// don't need copies of the shader. Each permutation is compiled once and
+/// shared afterwards.
+///
+/// ## Example
+/// ```ignore
+/// let mut variants = ShaderVariants::load("assets/shaders/standard.vert", "assets/shaders/standard.frag")
+///     .with_setup(|program, defines| {
+///         program.create_uniform("u_model");
+///         if defines.contains("NORMAL_MAP") {
+///             program.create_uniform("u_normal_map");
+///         }
+///     });
+///
+/// let defines = ShaderDefines::new()
+///     .with_if("NORMAL_MAP", material.normal_map.is_some())
+///     .with_if("SKINNED", mesh.is_skinned());
+/// let shader = variants.get(&defines)?;
+/// shader.bind();
+/// ```
+pub struct ShaderVariants {
+    vertex: ShaderStageSource,
+    fragment: ShaderStageSource,
+    library: ShaderLibrary,
+    setup: Option<Box<VariantSetup>>,
+    cache: Option<ShaderCache>,
+    programs: HashMap<ShaderDefines, Arc<ShaderProgram>>,
+}
+
+/// Where a stage's source comes from.
+enum ShaderStageSource {
+    File(String),
+    Source(String),
+}
+
+impl ShaderVariants {
+    /// Creates variants of shaders given as source code.
+    pub fn from_source(vertex_source: &str, fragment_source: &str) -> Self {
+        Self::new(
+            ShaderStageSource::Source(vertex_source.to_string()),
+            ShaderStageSource::Source(fragment_source.to_string()),
+        )
+    }
+
+    /// Creates variants of shader files, read again whenever
+    /// [`ShaderVariants::clear`] drops the compiled permutations.
+    pub fn load(vertex_path: &str, fragment_path: &str) -> Self {
+        Self::new(
+            ShaderStageSource::File(vertex_path.to_string()),
+            ShaderStageSource::File(fragment_path.to_string()),
+        )
+    }
+
+    fn new(vertex: ShaderStageSource, fragment: ShaderStageSource) -> Self {
+        Self {
+            vertex,
+            fragment,
+            library: ShaderLibrary::new(),
+            setup: None,
+            cache: None,
+            programs: HashMap::new(),
+        }
+    }
+
+    /// Sets the library includes are resolved with.
+    pub fn with_library(mut self, library: ShaderLibrary) -> Self {
+        self.library = library;
+        self
+    }
+
+    /// Sets what runs on each newly compiled permutation.
+    pub fn with_setup<F>(mut self, setup: F) -> Self
+    where
+        F: Fn(&mut ShaderProgram, &ShaderDefines) + Send + Sync + 'static,
+    {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Sets a cache permutations are loaded from and saved to, so they are
+    /// only compiled on the first run.
+    pub fn with_cache(mut self, cache: ShaderCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Returns the library includes are resolved with, e.g. to register more.
+    pub fn library_mut(&mut self) -> &mut ShaderLibrary {
+        &mut self.library
+    }
+
+    /// Returns the permutation for a set of defines, compiling it on first use.
+    pub fn get(&mut self, defines: &ShaderDefines) -> Result<Arc<ShaderProgram>, Errors> {
+        if let Some(program) = self.programs.get(defines) {
+            return Ok(Arc::clone(program));
+        }
+        let vertex = self.preprocess(true, defines)?;
+        let fragment = self.preprocess(false, defines)?;
+        let mut program = match &mut self.cache {
+            Some(cache) => cache.program(&vertex, &fragment),
+            None => ShaderProgram::from_source(&vertex, &fragment),
+        };
+        if let Some(setup) = &self.setup {
+            setup(&mut program, defines);
+        }
+        log::debug!("Compiled shader permutation [{}]", defines.key());
+        let program = Arc::new(program);
+        self.programs.insert(defines.clone(), Arc::clone(&program));
+        Ok(program)
+    }
+
+    /// Returns the preprocessed source of one stage of a permutation, e.g. to
+    /// inspect what the compiler sees.
+    pub fn preprocess(&mut self, vertex: bool, defines: &ShaderDefines) -> Result<String, Errors> {
+        let stage = if vertex { &self.vertex } else { &self.fragment };
+        match stage {
+            ShaderStageSource::File(path) => self.library.load(path, defines),
+            ShaderStageSource::Source(source) => self.library.preprocess(source, defines),
+        }
+    }
+
+    /// Returns the number of compiled permutations.
+    pub fn variant_count(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// Drops every compiled permutation, so the next [`ShaderVariants::get`]
+    /// recompiles from the current sources, e.g. after editing a shader.
+    /// Programs still held elsewhere stay alive until released.
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+}
@@ -0,0 +1,530 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::shader_preprocessor::{ShaderDefines, ShaderLibrary};
+use crate::custom_errors::Errors;
+
+/// Numbers the temporary SPIR-V outputs of concurrent validations.
+static OUTPUT_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// A programmable pipeline stage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Geometry,
+    Compute,
+}
+
+impl ShaderStage {
+    /// Returns the stage of a shader file from its extension: `.vert`/`.vs`,
+    /// `.frag`/`.fs`, `.geom`/`.gs` or `.comp`/`.cs`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "vert" | "vs" => Some(Self::Vertex),
+            "frag" | "fs" => Some(Self::Fragment),
+            "geom" | "gs" => Some(Self::Geometry),
+            "comp" | "cs" => Some(Self::Compute),
+            _ => None,
+        }
+    }
+
+    /// Returns the name `glslangValidator` knows the stage by.
+    fn glslang_name(self) -> &'static str {
+        match self {
+            Self::Vertex => "vert",
+            Self::Fragment => "frag",
+            Self::Geometry => "geom",
+            Self::Compute => "comp",
+        }
+    }
+
+    #[cfg(feature = "shaderc")]
+    fn shaderc_kind(self) -> shaderc::ShaderKind {
+        match self {
+            Self::Vertex => shaderc::ShaderKind::Vertex,
+            Self::Fragment => shaderc::ShaderKind::Fragment,
+            Self::Geometry => shaderc::ShaderKind::Geometry,
+            Self::Compute => shaderc::ShaderKind::Compute,
+        }
+    }
+}
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A compiler message, pointing at the file it's about even when that is
+/// an included one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderDiagnostic {
+    pub severity: Severity,
+    pub file: String,
+    /// The line in `file`, if the compiler named one.
+    pub line: Option<u32>,
+    pub message: String,
+    /// The permutation being compiled, empty without defines.
+    pub defines: String,
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, ": {}: {}", severity, self.message)?;
+        if !self.defines.is_empty() {
+            write!(f, " [{}]", self.defines)?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of validating one shader permutation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShaderValidation {
+    pub diagnostics: Vec<ShaderDiagnostic>,
+    /// The compiled SPIR-V module, if SPIR-V output is on and it compiled.
+    pub spirv: Option<Vec<u8>>,
+}
+
+impl ShaderValidation {
+    /// Checks if there are no errors; warnings are allowed.
+    pub fn passed(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity < Severity::Error)
+    }
+
+    /// Returns the error diagnostics.
+    pub fn errors(&self) -> impl Iterator<Item = &ShaderDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// # Shader Validator
+///
+/// Compiles GLSL offline so broken shaders fail the asset build instead of a
+/// player's game. With the `shaderc` feature the compiler is built in;
+/// otherwise, or after [`with_glslang`], the Khronos reference compiler
+/// `glslangValidator` is run as an external tool. Sources go through a
+/// [`ShaderLibrary`] first, the same way they do at runtime, and diagnostics
+/// are mapped back to the original file and line, including inside includes.
+/// Each permutation a shader is used with can be checked, since code behind
+/// an `#ifdef` is only compiled when it's defined.
+///
+/// By default shaders are compiled to SPIR-V for OpenGL, which is the
+/// strictest check and what Vulkan-style pipelines would load. The built-in
+/// compiler always compiles to SPIR-V; with `glslangValidator`, shaders using
+/// plain uniforms without `layout(location)` need [`with_spirv(false)`]
+/// to be checked as OpenGL GLSL instead.
+///
+/// [`with_glslang`]: ShaderValidator::with_glslang
+/// [`with_spirv(false)`]: ShaderValidator::with_spirv
+///
+/// ## Example
+/// ```ignore
+/// // build.rs or an asset packing tool
+/// let validator = ShaderValidator::new()
+///     .with_library(ShaderLibrary::new().with_search_directory("assets/shaders/include"))
+///     .with_permutations(vec![
+///         ShaderDefines::new(),
+///         ShaderDefines::new().with("SKINNED"),
+///         ShaderDefines::new().with("NORMAL_MAP").with_value("NUM_CASCADES", 4),
+///     ]);
+/// if let Err(error) = validator.check_directory("assets/shaders") {
+///     panic!("{}", error);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShaderValidator {
+    /// The external compiler, or `None` for the built-in one.
+    glslang: Option<PathBuf>,
+    library: ShaderLibrary,
+    permutations: Vec<ShaderDefines>,
+    spirv: bool,
+}
+
+impl ShaderValidator {
+    /// Creates a validator using the built-in compiler, or
+    /// `glslangValidator` from the `PATH` without the `shaderc` feature.
+    pub fn new() -> Self {
+        Self {
+            glslang: (!cfg!(feature = "shaderc")).then(|| "glslangValidator".into()),
+            library: ShaderLibrary::new(),
+            permutations: vec![ShaderDefines::new()],
+            spirv: true,
+        }
+    }
+
+    /// Sets a `glslangValidator` executable to use instead of the built-in
+    /// compiler.
+    pub fn with_glslang(mut self, glslang: impl Into<PathBuf>) -> Self {
+        self.glslang = Some(glslang.into());
+        self
+    }
+
+    /// Sets the library includes are resolved with.
+    pub fn with_library(mut self, library: ShaderLibrary) -> Self {
+        self.library = library;
+        self
+    }
+
+    /// Sets the permutations every shader is validated with.
+    pub fn with_permutations(mut self, permutations: Vec<ShaderDefines>) -> Self {
+        self.permutations = permutations;
+        self
+    }
+
+    /// Sets whether the SPIR-V module is kept, and with `glslangValidator`
+    /// whether shaders are compiled to SPIR-V or only checked as GLSL.
+    pub fn with_spirv(mut self, spirv: bool) -> Self {
+        self.spirv = spirv;
+        self
+    }
+
+    /// Validates a shader file with one set of defines, taking the stage
+    /// from its extension. Errors are only returned when the shader can't be
+    /// read or the compiler can't run; compile errors are diagnostics.
+    pub fn validate_file(
+        &self,
+        path: &Path,
+        defines: &ShaderDefines,
+    ) -> Result<ShaderValidation, Errors> {
+        let stage = ShaderStage::from_path(path).ok_or_else(|| {
+            Errors::ShaderValidation(format!("{}: unknown shader stage", path.display()))
+        })?;
+        let mut library = self.library.clone();
+        let source = match library.load(&path.to_string_lossy(), defines) {
+            Ok(source) => source,
+            // Missing includes and the like are the shader's fault too.
+            Err(Errors::ShaderPreprocess(message)) => {
+                // Messages start with the "file:line" of the directive.
+                let located = message.split_once(": ").and_then(|(location, rest)| {
+                    let (file, line) = location.rsplit_once(':')?;
+                    Some((file.to_string(), line.parse().ok()?, rest.to_string()))
+                });
+                let (file, line, message) = match located {
+                    Some((file, line, rest)) => (file, Some(line), rest),
+                    None => (path.display().to_string(), None, message),
+                };
+                return Ok(ShaderValidation {
+                    diagnostics: vec![ShaderDiagnostic {
+                        severity: Severity::Error,
+                        file,
+                        line,
+                        message,
+                        defines: defines.key(),
+                    }],
+                    spirv: None,
+                });
+            }
+            Err(error) => return Err(error),
+        };
+        self.compile(&source, stage, library.last_sources(), defines)
+    }
+
+    /// Validates every shader file in a folder and its subfolders with every
+    /// permutation, returning the results of those with diagnostics.
+    pub fn validate_directory(
+        &self,
+        directory: impl AsRef<Path>,
+    ) -> Result<Vec<(PathBuf, ShaderValidation)>, Errors> {
+        let mut files = Vec::new();
+        collect_shaders(directory.as_ref(), &mut files)?;
+        files.sort();
+
+        let mut results = Vec::new();
+        for file in files {
+            for defines in &self.permutations {
+                let validation = self.validate_file(&file, defines)?;
+                if !validation.diagnostics.is_empty() {
+                    results.push((file.clone(), validation));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Validates a folder like [`ShaderValidator::validate_directory`],
+    /// logging warnings and failing with every error if there are any.
+    pub fn check_directory(&self, directory: impl AsRef<Path>) -> Result<(), Errors> {
+        let mut errors = Vec::new();
+        for (_, validation) in self.validate_directory(directory)? {
+            for diagnostic in &validation.diagnostics {
+                match diagnostic.severity {
+                    Severity::Warning => log::warn!("{}", diagnostic),
+                    Severity::Error => errors.push(diagnostic.to_string()),
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Errors::ShaderValidation(format!(
+                "{} errors\n{}",
+                errors.len(),
+                errors.join("\n")
+            )))
+        }
+    }
+
+    /// Runs the compiler on preprocessed source.
+    fn compile(
+        &self,
+        source: &str,
+        stage: ShaderStage,
+        sources: &[String],
+        defines: &ShaderDefines,
+    ) -> Result<ShaderValidation, Errors> {
+        match &self.glslang {
+            Some(glslang) => self.run_glslang(glslang, source, stage, sources, defines),
+            #[cfg(feature = "shaderc")]
+            None => self.run_shaderc(source, stage, sources, defines),
+            #[cfg(not(feature = "shaderc"))]
+            None => unreachable!("glslangValidator is always set without shaderc"),
+        }
+    }
+
+    /// Compiles with the built-in compiler.
+    #[cfg(feature = "shaderc")]
+    fn run_shaderc(
+        &self,
+        source: &str,
+        stage: ShaderStage,
+        sources: &[String],
+        defines: &ShaderDefines,
+    ) -> Result<ShaderValidation, Errors> {
+        let start_error =
+            || Errors::ShaderValidation("Failed to start the shader compiler".to_string());
+        let compiler = shaderc::Compiler::new().ok_or_else(start_error)?;
+        let mut options = shaderc::CompileOptions::new().ok_or_else(start_error)?;
+        // OpenGL SPIR-V, with locations and bindings the source leaves out
+        // assigned automatically.
+        options.set_target_env(
+            shaderc::TargetEnv::OpenGL,
+            shaderc::EnvVersion::OpenGL4_5 as u32,
+        );
+        options.set_auto_map_locations(true);
+        options.set_auto_bind_uniforms(true);
+
+        // Naming the input "0" makes every message start with a string
+        // number, like those of the `#line` directives.
+        let (messages, artifact) = match compiler.compile_into_spirv(
+            source,
+            stage.shaderc_kind(),
+            "0",
+            "main",
+            Some(&options),
+        ) {
+            Ok(artifact) => (artifact.get_warning_messages(), Some(artifact)),
+            Err(shaderc::Error::CompilationError(_, messages)) => (messages, None),
+            Err(error) => return Err(Errors::ShaderValidation(error.to_string())),
+        };
+
+        let mut diagnostics: Vec<ShaderDiagnostic> = messages
+            .lines()
+            .filter_map(|line| parse_shaderc_diagnostic(line, sources, defines))
+            .collect();
+        if artifact.is_none() && diagnostics.is_empty() {
+            diagnostics.push(ShaderDiagnostic {
+                severity: Severity::Error,
+                file: sources.first().cloned().unwrap_or_default(),
+                line: None,
+                message: "compilation failed".to_string(),
+                defines: defines.key(),
+            });
+        }
+        let spirv = artifact
+            .filter(|_| self.spirv)
+            .map(|artifact| artifact.as_binary_u8().to_vec());
+        Ok(ShaderValidation { diagnostics, spirv })
+    }
+
+    /// Runs `glslangValidator`.
+    fn run_glslang(
+        &self,
+        glslang: &Path,
+        source: &str,
+        stage: ShaderStage,
+        sources: &[String],
+        defines: &ShaderDefines,
+    ) -> Result<ShaderValidation, Errors> {
+        let mut command = Command::new(glslang);
+        command.args(["--stdin", "-S", stage.glslang_name()]);
+        let output_path = self.spirv.then(|| {
+            std::env::temp_dir().join(format!(
+                "nyanko-{}-{}.spv",
+                std::process::id(),
+                OUTPUT_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ))
+        });
+        if let Some(output_path) = &output_path {
+            // OpenGL SPIR-V, with locations and bindings the source leaves out
+            // assigned automatically.
+            command
+                .args(["-G", "--aml", "--amb", "-o"])
+                .arg(output_path);
+        }
+
+        let start_error = |error: std::io::Error| {
+            Errors::ShaderValidation(format!("Failed to run {}: {}", glslang.display(), error))
+        };
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(start_error)?;
+        child
+            .stdin
+            .take()
+            .expect("glslang stdin is piped")
+            .write_all(source.as_bytes())
+            .map_err(start_error)?;
+        let output = child.wait_with_output().map_err(start_error)?;
+
+        let mut diagnostics: Vec<ShaderDiagnostic> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&output.stderr).lines())
+            .filter_map(|line| parse_diagnostic(line, sources, defines))
+            .collect();
+        if !output.status.success() && diagnostics.is_empty() {
+            diagnostics.push(ShaderDiagnostic {
+                severity: Severity::Error,
+                file: sources.first().cloned().unwrap_or_default(),
+                line: None,
+                message: format!("{} exited with {}", glslang.display(), output.status),
+                defines: defines.key(),
+            });
+        }
+        let spirv = output_path.and_then(|path| {
+            let spirv = fs::read(&path).ok();
+            let _ = fs::remove_file(&path);
+            spirv.filter(|_| output.status.success())
+        });
+        Ok(ShaderValidation { diagnostics, spirv })
+    }
+}
+
+impl Default for ShaderValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a `glslangValidator` message such as
+/// `ERROR: 2:14: 'albedo' : undeclared identifier`, where `2` is the string
+/// number of a `#line` directive. Returns `None` for other output.
+fn parse_diagnostic(
+    line: &str,
+    sources: &[String],
+    defines: &ShaderDefines,
+) -> Option<ShaderDiagnostic> {
+    let (severity, rest) = if let Some(rest) = line.strip_prefix("ERROR: ") {
+        (Severity::Error, rest)
+    } else if let Some(rest) = line.strip_prefix("WARNING: ") {
+        (Severity::Warning, rest)
+    } else {
+        return None;
+    };
+    // Summaries like "1 compilation errors.  No code generated."
+    if rest.contains("compilation errors") || rest.starts_with("Linking") {
+        return None;
+    }
+
+    let main_file = sources.first().cloned().unwrap_or_default();
+    let mut parts = rest.splitn(3, ':');
+    let located = match (parts.next(), parts.next(), parts.next()) {
+        (Some(string), Some(line), Some(message)) => line
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|line| (string.trim(), line, message.trim())),
+        _ => None,
+    };
+    let (file, line, message) = match located {
+        Some((string, line, message)) => {
+            let file = string
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| sources.get(index).cloned())
+                .unwrap_or(main_file);
+            (file, Some(line), message)
+        }
+        None => (main_file, None, rest.trim()),
+    };
+    Some(ShaderDiagnostic {
+        severity,
+        file,
+        line,
+        message: message.to_string(),
+        defines: defines.key(),
+    })
+}
+
+/// Parses a shaderc message such as
+/// `2:14: error: 'albedo' : undeclared identifier`, where `2` is the string
+/// number of a `#line` directive. Returns `None` for other output, like the
+/// closing "1 error generated.".
+#[cfg(feature = "shaderc")]
+fn parse_shaderc_diagnostic(
+    line: &str,
+    sources: &[String],
+    defines: &ShaderDefines,
+) -> Option<ShaderDiagnostic> {
+    let (severity, location, message) =
+        if let Some((location, message)) = line.split_once(": error: ") {
+            (Severity::Error, location, message)
+        } else if let Some((location, message)) = line.split_once(": warning: ") {
+            (Severity::Warning, location, message)
+        } else {
+            return None;
+        };
+    let (string, line) = match location.split_once(':') {
+        Some((string, line)) => (string, line.trim().parse::<u32>().ok()),
+        None => (location, None),
+    };
+    let file = string
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| sources.get(index).cloned())
+        .unwrap_or_else(|| sources.first().cloned().unwrap_or_default());
+    Some(ShaderDiagnostic {
+        severity,
+        file,
+        line,
+        message: message.trim().to_string(),
+        defines: defines.key(),
+    })
+}
+
+/// Adds the shader files in a folder and its subfolders to `files`.
+fn collect_shaders(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), Errors> {
+    let entries = fs::read_dir(directory)
+        .map_err(|error| Errors::ShaderValidation(format!("{}: {}", directory.display(), error)))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_shaders(&path, files)?;
+        } else if ShaderStage::from_path(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
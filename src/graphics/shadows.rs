@@ -0,0 +1,382 @@
+use std::f32::consts::SQRT_2;
+
+use gl::types::*;
+
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::gpu_resources::{self, GpuObject, ShareGroup};
+use super::light::PointLight;
+use super::texture::Texture;
+use crate::math::projection::perspective;
+use crate::math::*;
+use crate::name;
+use crate::name::Name;
+
+const POINT_SHADOW_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 a_position;
+
+uniform mat4 u_model;
+uniform mat4 u_view_projection;
+
+out vec3 v_world_position;
+
+void main() {
+    vec4 world = u_model * vec4(a_position, 1.0);
+    v_world_position = world.xyz;
+    gl_Position = u_view_projection * world;
+}
+"#;
+
+const POINT_SHADOW_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec3 v_world_position;
+
+uniform vec3 u_light_position;
+uniform float u_far;
+
+void main() {
+    gl_FragDepth = length(v_world_position - u_light_position) / u_far;
+}
+"#;
+
+/// GLSL helper for sampling a [`PointShadowMap`].
+///
+/// Paste (or concatenate) this into a lighting shader and multiply the light's
+/// contribution by the result, which is 0 in shadow and 1 when lit:
+///
+/// ```glsl
+/// uniform samplerCube u_shadow_map;
+/// light *= nyanko_point_shadow(u_shadow_map, v_world_position, u_light_position, u_light_range, 0.05);
+/// ```
+pub const POINT_SHADOW_GLSL: &str = r#"
+float nyanko_point_shadow(samplerCube shadow_map, vec3 world_position, vec3 light_position, float far, float bias) {
+    vec3 to_fragment = world_position - light_position;
+    float closest = texture(shadow_map, to_fragment).r * far;
+    return length(to_fragment) - bias > closest ? 0.0 : 1.0;
+}
+"#;
+
+/// Near plane distance used for every cube face.
+const POINT_SHADOW_NEAR: f32 = 0.05;
+
+/// One face of a cube map, in OpenGL's face order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    /// Every face, in OpenGL's order.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    /// Returns the face's texture target, such as `gl::TEXTURE_CUBE_MAP_POSITIVE_X`.
+    pub fn gl_target(&self) -> GLenum {
+        gl::TEXTURE_CUBE_MAP_POSITIVE_X + *self as GLenum
+    }
+
+    /// Returns the direction the face looks in.
+    pub fn direction(&self) -> Vec3 {
+        match self {
+            CubeFace::PositiveX => Vec3::unit_x(),
+            CubeFace::NegativeX => -Vec3::unit_x(),
+            CubeFace::PositiveY => Vec3::unit_y(),
+            CubeFace::NegativeY => -Vec3::unit_y(),
+            CubeFace::PositiveZ => Vec3::unit_z(),
+            CubeFace::NegativeZ => -Vec3::unit_z(),
+        }
+    }
+
+    /// Returns the up vector OpenGL's cube map layout expects for the face.
+    pub fn up(&self) -> Vec3 {
+        match self {
+            CubeFace::PositiveY => Vec3::unit_z(),
+            CubeFace::NegativeY => -Vec3::unit_z(),
+            _ => -Vec3::unit_y(),
+        }
+    }
+
+    /// Returns the view matrix for rendering the face from `position`.
+    pub fn view_matrix(&self, position: Vec3) -> Mat4 {
+        let eye = Point3::from_vec(position);
+        Mat4::look_at_rh(eye, eye + self.direction(), self.up())
+    }
+
+    /// Checks if a sphere relative to the cube's center can be seen through the face.
+    pub fn contains_sphere(&self, center: Vec3, radius: f32) -> bool {
+        let direction = self.direction();
+        let forward = center.dot(direction);
+        if forward < -radius {
+            return false;
+        }
+
+        let up = self.up();
+        let side = direction.cross(up);
+        let slack = radius * SQRT_2;
+        forward + slack >= center.dot(up).abs() && forward + slack >= center.dot(side).abs()
+    }
+}
+
+/// # Point Shadow Map
+///
+/// A depth cube map storing, for every direction around a point light, the
+/// distance to the nearest occluder divided by the light's range.
+pub struct PointShadowMap {
+    id: GLuint,
+    texture: Texture,
+    group: ShareGroup,
+}
+
+impl PointShadowMap {
+    /// Creates a shadow map with faces of `size` pixels.
+    pub fn new(size: u32) -> Self {
+        let texture = Texture::new_cube(
+            size,
+            gl::DEPTH_COMPONENT24,
+            gl::DEPTH_COMPONENT,
+            gl::UNSIGNED_INT,
+        );
+        texture.set_filter(gl::NEAREST, gl::NEAREST);
+
+        let mut id = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                CubeFace::PositiveX.gl_target(),
+                texture.id(),
+                0,
+            );
+            gl::DrawBuffers(1, &gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                panic!("Point shadow map {}x{} is incomplete", size, size);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Self {
+            id,
+            texture,
+            group: ShareGroup::current(),
+        }
+    }
+
+    /// Returns the depth cube map.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the size of each face in pixels.
+    pub fn size(&self) -> u32 {
+        self.texture.width()
+    }
+
+    fn bind_face(&self, face: CubeFace) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                face.gl_target(),
+                self.texture.id(),
+                0,
+            );
+            gl::Viewport(0, 0, self.size() as GLsizei, self.size() as GLsizei);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+}
+
+impl Drop for PointShadowMap {
+    fn drop(&mut self) {
+        gpu_resources::release(self.group, GpuObject::Framebuffer(self.id));
+    }
+}
+
+/// One cube face being rendered into a point shadow map. The depth shader is
+/// already bound; set each object's model matrix and draw its mesh.
+pub struct PointShadowFace<'a> {
+    pub face: CubeFace,
+    pub light_position: Vec3,
+    pub range: f32,
+    pub view_projection: Mat4,
+    shader: &'a ShaderProgram,
+    model_uniform: Name,
+}
+
+impl PointShadowFace<'_> {
+    /// Checks if a world-space bounding sphere can cast a shadow into this face.
+    pub fn is_visible(&self, center: Vec3, radius: f32) -> bool {
+        let relative = center - self.light_position;
+        relative.magnitude() - radius < self.range && self.face.contains_sphere(relative, radius)
+    }
+
+    /// Sets the model matrix of the next draw.
+    pub fn set_model(&self, model: &Mat4) {
+        self.shader.set_matrix4fv_uniform(self.model_uniform, model);
+    }
+}
+
+/// Which light a shadow map was rendered for this frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointShadowAssignment {
+    /// Index into the lights passed to [`PointShadows::render`].
+    pub light: usize,
+    /// Index of the map, for [`PointShadows::map`].
+    pub map: usize,
+}
+
+/// # Point Shadows
+///
+/// Renders omnidirectional shadows for point lights within a fixed budget.
+/// Every frame the shadow-casting lights are ranked by priority, intensity,
+/// range and closeness to the camera, and only the best `max_shadowed_lights`
+/// get a shadow map.
+///
+/// ## Example
+/// ```ignore
+/// let mut shadows = PointShadows::new(512, 4);
+///
+/// let assignments = shadows.render(&lights, camera_position, |face| {
+///     for (mesh, transform, bounds) in &objects {
+///         if face.is_visible(bounds.center, bounds.radius) {
+///             face.set_model(&transform.matrix());
+///             mesh.draw();
+///         }
+///     }
+/// });
+/// ```
+pub struct PointShadows {
+    pub max_shadowed_lights: usize,
+    resolution: u32,
+    maps: Vec<PointShadowMap>,
+    assignments: Vec<PointShadowAssignment>,
+    shader: ShaderProgram,
+}
+
+impl PointShadows {
+    /// Creates a shadow renderer with cube faces of `resolution` pixels.
+    pub fn new(resolution: u32, max_shadowed_lights: usize) -> Self {
+        let mut shader =
+            ShaderProgram::from_source(POINT_SHADOW_VERTEX_SHADER, POINT_SHADOW_FRAGMENT_SHADER);
+        for uniform in ["u_model", "u_view_projection", "u_light_position", "u_far"] {
+            shader.create_uniform(uniform);
+        }
+
+        Self {
+            max_shadowed_lights,
+            resolution,
+            maps: Vec::new(),
+            assignments: Vec::new(),
+            shader,
+        }
+    }
+
+    /// Returns a shadow map by index.
+    pub fn map(&self, index: usize) -> Option<&PointShadowMap> {
+        self.maps.get(index)
+    }
+
+    /// Returns the lights that received shadow maps in the last render.
+    pub fn assignments(&self) -> &[PointShadowAssignment] {
+        &self.assignments
+    }
+
+    /// Returns the shadow map rendered for a light index this frame, if any.
+    pub fn map_for_light(&self, light: usize) -> Option<&PointShadowMap> {
+        self.assignments
+            .iter()
+            .find(|assignment| assignment.light == light)
+            .and_then(|assignment| self.map(assignment.map))
+    }
+
+    /// Ranks the shadow-casting lights, best first, and returns their indices
+    /// within the budget.
+    pub fn select(&self, lights: &[(PointLight, Vec3)], camera_position: Vec3) -> Vec<usize> {
+        let mut ranked: Vec<(usize, f32)> = lights
+            .iter()
+            .enumerate()
+            .filter(|(_, (light, _))| light.cast_shadows && light.range > 0.0)
+            .map(|(index, (light, position))| {
+                let distance = (*position - camera_position).magnitude() - light.range;
+                let score =
+                    light.shadow_priority * light.intensity * light.range / distance.max(1.0);
+                (index, score)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(self.max_shadowed_lights);
+        ranked.into_iter().map(|(index, _)| index).collect()
+    }
+
+    /// Renders shadow maps for the best lights, calling `draw` once per cube face.
+    /// Returns which light each map belongs to.
+    pub fn render<F>(
+        &mut self,
+        lights: &[(PointLight, Vec3)],
+        camera_position: Vec3,
+        mut draw: F,
+    ) -> &[PointShadowAssignment]
+    where
+        F: FnMut(&PointShadowFace),
+    {
+        let selected = self.select(lights, camera_position);
+        while self.maps.len() < selected.len() {
+            self.maps.push(PointShadowMap::new(self.resolution));
+        }
+
+        self.assignments.clear();
+        self.shader.bind();
+        let model_uniform = Name::new("u_model");
+        for (map_index, &light_index) in selected.iter().enumerate() {
+            let (light, position) = &lights[light_index];
+            let projection = perspective(Deg(90.0), 1.0, POINT_SHADOW_NEAR, light.range);
+            self.shader.set_3f_uniform(
+                name!("u_light_position"),
+                position.x,
+                position.y,
+                position.z,
+            );
+            self.shader.set_1f_uniform(name!("u_far"), light.range);
+
+            for face in CubeFace::ALL {
+                let view_projection = projection * face.view_matrix(*position);
+                self.maps[map_index].bind_face(face);
+                self.shader
+                    .set_matrix4fv_uniform(name!("u_view_projection"), &view_projection);
+                draw(&PointShadowFace {
+                    face,
+                    light_position: *position,
+                    range: light.range,
+                    view_projection,
+                    shader: &self.shader,
+                    model_uniform,
+                });
+            }
+
+            self.assignments.push(PointShadowAssignment {
+                light: light_index,
+                map: map_index,
+            });
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+        &self.assignments
+    }
+}
@@ -0,0 +1,265 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use super::sprite_batch::{SpriteBatch, SpriteVertex};
+use crate::math::polygon::{signed_area, triangulate};
+use crate::math::*;
+
+/// Width in pixels of the transparent fringe that anti-aliases shape edges.
+const FEATHER: f32 = 1.0;
+
+/// Smallest cosine between a corner's miter and its edges before the miter is clamped.
+const MITER_LIMIT: f32 = 0.25;
+
+/// # Shapes
+///
+/// Immediate 2D vector drawing on a [`SpriteBatch`], in the batch's pixel
+/// coordinates. Everything except axis-aligned rectangles gets a one pixel
+/// anti-aliased fringe, and shapes batch together with sprites.
+///
+/// ## Example
+/// ```ignore
+/// batch.begin(window.framebuffer_size());
+/// batch.fill_rounded_rect(Rect::new(20.0, 20.0, 200.0, 80.0), 12.0, Color::rgb(0.1, 0.1, 0.15));
+/// batch.stroke_circle(vec2(400.0, 300.0), 50.0, 3.0, Color::YELLOW);
+/// batch.fill_polygon(&[vec2(500.0, 100.0), vec2(560.0, 180.0), vec2(440.0, 180.0)], Color::RED);
+/// batch.end();
+/// ```
+impl SpriteBatch {
+    /// Fills an axis-aligned rectangle with crisp edges.
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let (min, max) = (rect.min(), rect.max());
+        let uv = vec2(0.5, 0.5);
+        let vertices = [
+            SpriteVertex::new(min, uv, color),
+            SpriteVertex::new(vec2(max.x, min.y), uv, color),
+            SpriteVertex::new(max, uv, color),
+            SpriteVertex::new(vec2(min.x, max.y), uv, color),
+        ];
+        self.draw_triangles(None, &vertices, &[0, 1, 2, 0, 2, 3]);
+    }
+
+    /// Outlines a rectangle, centering the stroke on its edges.
+    pub fn stroke_rect(&mut self, rect: Rect, width: f32, color: Color) {
+        self.stroke_polygon(&rect_points(rect), width, color);
+    }
+
+    /// Fills a rectangle with rounded corners.
+    pub fn fill_rounded_rect(&mut self, rect: Rect, radius: f32, color: Color) {
+        self.fill_polygon(&rounded_rect_points(rect, radius), color);
+    }
+
+    /// Outlines a rectangle with rounded corners.
+    pub fn stroke_rounded_rect(&mut self, rect: Rect, radius: f32, width: f32, color: Color) {
+        self.stroke_polygon(&rounded_rect_points(rect, radius), width, color);
+    }
+
+    /// Fills a circle.
+    pub fn fill_circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.fill_polygon(
+            &arc_points(center, radius, 0.0, TAU, circle_segments(radius)),
+            color,
+        );
+    }
+
+    /// Outlines a circle.
+    pub fn stroke_circle(&mut self, center: Vec2, radius: f32, width: f32, color: Color) {
+        self.stroke_polygon(
+            &arc_points(center, radius, 0.0, TAU, circle_segments(radius)),
+            width,
+            color,
+        );
+    }
+
+    /// Fills a simple polygon, convex or concave, with either winding.
+    pub fn fill_polygon(&mut self, points: &[Vec2], color: Color) {
+        let points = dedup_points(points);
+        let count = points.len();
+        if count < 3 {
+            return;
+        }
+
+        let area = signed_area(&points);
+        let miters = miters(&points, true, area.signum());
+        let transparent = color.with_alpha(0.0);
+        let uv = vec2(0.5, 0.5);
+
+        let mut vertices = Vec::with_capacity(count * 2);
+        for (point, miter) in points.iter().zip(&miters) {
+            vertices.push(SpriteVertex::new(
+                *point - *miter * (FEATHER * 0.5),
+                uv,
+                color,
+            ));
+        }
+        for (point, miter) in points.iter().zip(&miters) {
+            vertices.push(SpriteVertex::new(
+                *point + *miter * (FEATHER * 0.5),
+                uv,
+                transparent,
+            ));
+        }
+
+        let mut indices = triangulate(&points);
+        let count = count as u32;
+        for i in 0..count {
+            let j = (i + 1) % count;
+            indices.extend_from_slice(&[i, j, count + j, i, count + j, count + i]);
+        }
+        self.draw_triangles(None, &vertices, &indices);
+    }
+
+    /// Outlines a closed polygon with mitered corners.
+    pub fn stroke_polygon(&mut self, points: &[Vec2], width: f32, color: Color) {
+        self.stroke_path(points, true, width, color);
+    }
+
+    /// Draws connected line segments with mitered joins and flat ends.
+    pub fn stroke_polyline(&mut self, points: &[Vec2], width: f32, color: Color) {
+        self.stroke_path(points, false, width, color);
+    }
+
+    fn stroke_path(&mut self, points: &[Vec2], closed: bool, width: f32, color: Color) {
+        let points = dedup_points(points);
+        let count = points.len();
+        if count < 2 || width <= 0.0 {
+            return;
+        }
+
+        // Strokes thinner than the fringe fade out instead of shrinking further.
+        let color = color.with_alpha(color.a * (width / FEATHER).min(1.0));
+        let core = (width * 0.5 - FEATHER * 0.5).max(0.0);
+        let rings = [
+            (-(core + FEATHER), 0.0),
+            (-core, 1.0),
+            (core, 1.0),
+            (core + FEATHER, 0.0),
+        ];
+        let miters = miters(&points, closed, 1.0);
+        let uv = vec2(0.5, 0.5);
+
+        let mut vertices = Vec::with_capacity(count * rings.len());
+        for (point, miter) in points.iter().zip(&miters) {
+            for (offset, alpha) in rings {
+                vertices.push(SpriteVertex::new(
+                    *point + *miter * offset,
+                    uv,
+                    color.with_alpha(color.a * alpha),
+                ));
+            }
+        }
+
+        let segments = if closed { count } else { count - 1 };
+        let mut indices = Vec::with_capacity(segments * 18);
+        for i in 0..segments {
+            let a = (i * rings.len()) as u32;
+            let b = (((i + 1) % count) * rings.len()) as u32;
+            for ring in 0..rings.len() as u32 - 1 {
+                indices.extend_from_slice(&[
+                    a + ring,
+                    b + ring,
+                    b + ring + 1,
+                    a + ring,
+                    b + ring + 1,
+                    a + ring + 1,
+                ]);
+            }
+        }
+        self.draw_triangles(None, &vertices, &indices);
+    }
+}
+
+/// Returns the corners of a rectangle.
+fn rect_points(rect: Rect) -> [Vec2; 4] {
+    let (min, max) = (rect.min(), rect.max());
+    [min, vec2(max.x, min.y), max, vec2(min.x, max.y)]
+}
+
+/// Returns the outline of a rectangle with rounded corners.
+fn rounded_rect_points(rect: Rect, radius: f32) -> Vec<Vec2> {
+    let radius = radius.min(rect.width * 0.5).min(rect.height * 0.5).max(0.0);
+    if radius <= 0.0 {
+        return rect_points(rect).to_vec();
+    }
+
+    let (min, max) = (rect.min(), rect.max());
+    let segments = (circle_segments(radius) / 4).max(2);
+    let corners = [
+        (vec2(max.x - radius, max.y - radius), 0.0),
+        (vec2(min.x + radius, max.y - radius), FRAC_PI_2),
+        (vec2(min.x + radius, min.y + radius), PI),
+        (vec2(max.x - radius, min.y + radius), PI + FRAC_PI_2),
+    ];
+
+    let mut points = Vec::with_capacity(corners.len() * (segments + 1));
+    for (center, start) in corners {
+        let arc = arc_points(center, radius, start, FRAC_PI_2, segments);
+        points.extend(arc);
+        points.push(center + vec2((start + FRAC_PI_2).cos(), (start + FRAC_PI_2).sin()) * radius);
+    }
+    points
+}
+
+/// Returns `segments` points along an arc, starting at `start` and spanning
+/// `sweep` radians, excluding the end point.
+fn arc_points(center: Vec2, radius: f32, start: f32, sweep: f32, segments: usize) -> Vec<Vec2> {
+    (0..segments)
+        .map(|i| {
+            let angle = start + sweep * i as f32 / segments as f32;
+            center + vec2(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Picks a segment count that keeps circles smooth at any size.
+fn circle_segments(radius: f32) -> usize {
+    ((radius.max(0.0).sqrt() * 6.0) as usize).clamp(12, 128)
+}
+
+/// Removes consecutive duplicate points, which have no direction.
+fn dedup_points(points: &[Vec2]) -> Vec<Vec2> {
+    let mut result: Vec<Vec2> = Vec::with_capacity(points.len());
+    for point in points {
+        if result
+            .last()
+            .is_none_or(|last| (*point - *last).magnitude2() > 1e-8)
+        {
+            result.push(*point);
+        }
+    }
+    if result.len() > 1 && (result[0] - result[result.len() - 1]).magnitude2() <= 1e-8 {
+        result.pop();
+    }
+    result
+}
+
+/// Returns the offset direction at every vertex, scaled so offsetting by `d`
+/// moves each edge by `d`. `side` flips the normals; pass the polygon's winding
+/// sign to get outward offsets.
+fn miters(points: &[Vec2], closed: bool, side: f32) -> Vec<Vec2> {
+    let count = points.len();
+    let normal = |a: Vec2, b: Vec2| {
+        let direction = (b - a).normalize();
+        vec2(direction.y, -direction.x) * side
+    };
+
+    (0..count)
+        .map(|i| {
+            let previous =
+                (closed || i > 0).then(|| normal(points[(i + count - 1) % count], points[i]));
+            let next =
+                (closed || i + 1 < count).then(|| normal(points[i], points[(i + 1) % count]));
+            match (previous, next) {
+                (Some(a), Some(b)) => {
+                    let sum = a + b;
+                    if sum.magnitude2() < 1e-8 {
+                        return a;
+                    }
+                    let miter = sum.normalize();
+                    miter / miter.dot(a).max(MITER_LIMIT)
+                }
+                (Some(a), None) | (None, Some(a)) => a,
+                (None, None) => vec2(0.0, 0.0),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,258 @@
+use super::camera::CameraView;
+use super::gl_wrapper::ShaderProgram;
+use super::light::DirectionalLight;
+use super::primitives::Primitive;
+use crate::math::*;
+use crate::name;
+
+const SKY_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_position;
+
+uniform mat4 u_inverse_view_projection;
+
+out vec3 v_direction;
+
+void main() {
+    vec4 world = u_inverse_view_projection * vec4(a_position, 1.0, 1.0);
+    v_direction = world.xyz / world.w;
+    gl_Position = vec4(a_position, 1.0, 1.0);
+}
+"#;
+
+const SKY_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec3 v_direction;
+out vec4 out_color;
+
+uniform vec3 u_sun_direction;
+uniform vec3 u_rayleigh;
+uniform float u_mie;
+uniform float u_mie_g;
+uniform float u_sun_intensity;
+uniform float u_sun_size;
+uniform vec3 u_ground_color;
+uniform float u_exposure;
+
+vec3 sunlight(vec3 sun) {
+    float air_mass = 1.0 / (max(sun.y, 0.0) + 0.05);
+    return exp(-u_rayleigh * air_mass) * u_sun_intensity * smoothstep(-0.1, 0.05, sun.y);
+}
+
+void main() {
+    vec3 direction = normalize(v_direction);
+    vec3 sun = normalize(u_sun_direction);
+    float mu = dot(direction, sun);
+
+    vec3 light = sunlight(sun);
+    float air_mass = 1.0 / (max(direction.y, 0.0) + 0.05);
+    float rayleigh_phase = 0.75 * (1.0 + mu * mu);
+    float g = u_mie_g;
+    float mie_phase = (1.0 - g * g) / pow(1.0 + g * g - 2.0 * g * mu, 1.5);
+
+    vec3 color = light * ((1.0 - exp(-u_rayleigh * air_mass)) * rayleigh_phase + u_mie * mie_phase);
+    color += vec3(0.002, 0.003, 0.006);
+    color += light * smoothstep(cos(u_sun_size), cos(u_sun_size * 0.8), mu) * 20.0;
+
+    float ground = smoothstep(0.0, -0.05, direction.y);
+    color = mix(color, u_ground_color * (dot(light, vec3(0.333)) * 0.5 + 0.02), ground);
+
+    out_color = vec4(1.0 - exp(-color * u_exposure), 1.0);
+}
+"#;
+
+/// # Sky
+///
+/// A simple analytic atmosphere: Rayleigh scattering for the blue sky and red
+/// sunsets, Mie scattering for the glow around the sun, and a sun disk. Draw it
+/// after opaque geometry; it only covers pixels still at the far plane.
+///
+/// ## Example
+/// ```ignore
+/// let mut sky = Sky::new();
+/// sky.follow_light(&sun);
+///
+/// // after opaque geometry:
+/// sky.render(&camera_view);
+/// ```
+pub struct Sky {
+    /// Unit direction pointing towards the sun.
+    pub sun_direction: Vec3,
+    /// Rayleigh scattering per air mass for red, green, and blue.
+    pub rayleigh: Vec3,
+    /// Mie scattering strength; raise it for hazy skies.
+    pub mie: f32,
+    /// Mie anisotropy; closer to 1 concentrates the glow around the sun.
+    pub mie_g: f32,
+    pub sun_intensity: f32,
+    /// Angular radius of the sun disk in radians.
+    pub sun_size: f32,
+    pub ground_color: Color,
+    pub exposure: f32,
+    shader: ShaderProgram,
+    triangle: Primitive,
+}
+
+impl Sky {
+    /// Creates a sky with a clear daytime atmosphere.
+    pub fn new() -> Self {
+        let mut shader = ShaderProgram::from_source(SKY_VERTEX_SHADER, SKY_FRAGMENT_SHADER);
+        for uniform in [
+            "u_inverse_view_projection",
+            "u_sun_direction",
+            "u_rayleigh",
+            "u_mie",
+            "u_mie_g",
+            "u_sun_intensity",
+            "u_sun_size",
+            "u_ground_color",
+            "u_exposure",
+        ] {
+            shader.create_uniform(uniform);
+        }
+
+        Self {
+            sun_direction: vec3(0.3, 0.8, 0.2).normalize(),
+            rayleigh: vec3(0.06, 0.14, 0.33),
+            mie: 0.02,
+            mie_g: 0.76,
+            sun_intensity: 1.5,
+            sun_size: 0.02,
+            ground_color: Color::rgb(0.35, 0.3, 0.25),
+            exposure: 1.5,
+            shader,
+            triangle: Primitive::fullscreen_triangle(),
+        }
+    }
+
+    /// Points the sun at a directional light.
+    pub fn follow_light(&mut self, light: &DirectionalLight) {
+        self.sun_direction = light.to_light();
+    }
+
+    /// Returns the sunlight reaching the ground after passing through the atmosphere,
+    /// in linear space. Zero once the sun has set.
+    pub fn sunlight(&self) -> Vec3 {
+        let sun = self.sun_direction.normalize();
+        let air_mass = 1.0 / (sun.y.max(0.0) + 0.05);
+        let visibility = smoothstep(-0.1, 0.05, sun.y);
+        vec3(
+            (-self.rayleigh.x * air_mass).exp(),
+            (-self.rayleigh.y * air_mass).exp(),
+            (-self.rayleigh.z * air_mass).exp(),
+        ) * (self.sun_intensity * visibility)
+    }
+
+    /// Evaluates the sky color in a direction on the CPU, matching the shader
+    /// without the sun disk. Useful for picking fog and ambient colors.
+    pub fn sample(&self, direction: Vec3) -> Color {
+        let direction = direction.normalize();
+        let mu = direction.dot(self.sun_direction.normalize());
+        let light = self.sunlight();
+        let air_mass = 1.0 / (direction.y.max(0.0) + 0.05);
+        let rayleigh_phase = 0.75 * (1.0 + mu * mu);
+        let g = self.mie_g;
+        let mie_phase = (1.0 - g * g) / (1.0 + g * g - 2.0 * g * mu).powf(1.5);
+
+        let scatter = |light: f32, rayleigh: f32, night: f32| {
+            light * ((1.0 - (-rayleigh * air_mass).exp()) * rayleigh_phase + self.mie * mie_phase)
+                + night
+        };
+        let mut color = vec3(
+            scatter(light.x, self.rayleigh.x, 0.002),
+            scatter(light.y, self.rayleigh.y, 0.003),
+            scatter(light.z, self.rayleigh.z, 0.006),
+        );
+
+        let ground = smoothstep(0.0, -0.05, direction.y);
+        let ground_color = self.ground_color.to_linear();
+        let ground_light = (light.x + light.y + light.z) / 3.0 * 0.5 + 0.02;
+        color = color.lerp(
+            vec3(ground_color.r, ground_color.g, ground_color.b) * ground_light,
+            ground,
+        );
+
+        let tonemap = |c: f32| 1.0 - (-c * self.exposure).exp();
+        Color::from_linear(Color::new(
+            tonemap(color.x),
+            tonemap(color.y),
+            tonemap(color.z),
+            1.0,
+        ))
+    }
+
+    /// Returns the average sky color along the horizon facing the sun, a good fog color.
+    pub fn horizon_color(&self) -> Color {
+        let sun = self.sun_direction;
+        let toward_sun = vec3(sun.x, 0.0, sun.z);
+        let toward_sun = if toward_sun.magnitude2() > 0.0 {
+            toward_sun.normalize()
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+        let side = vec3(-toward_sun.z, 0.0, toward_sun.x);
+        let samples = [toward_sun, side, -toward_sun, -side];
+        let sum = samples
+            .iter()
+            .map(|direction| self.sample(*direction + vec3(0.0, 0.05, 0.0)).to_linear())
+            .fold(Color::TRANSPARENT, |sum, color| {
+                Color::new(sum.r + color.r, sum.g + color.g, sum.b + color.b, 1.0)
+            });
+        Color::from_linear(Color::new(sum.r / 4.0, sum.g / 4.0, sum.b / 4.0, 1.0))
+    }
+
+    /// Draws the sky behind everything already in the depth buffer.
+    pub fn render(&self, view: &CameraView) {
+        let mut rotation = view.view;
+        rotation.w = vec4(0.0, 0.0, 0.0, 1.0);
+        let inverse_view_projection = (view.projection * rotation)
+            .invert()
+            .expect("View projection matrix is not invertible");
+        let sun = self.sun_direction.normalize();
+        let ground = self.ground_color.to_linear();
+
+        self.shader.bind();
+        self.shader
+            .set_matrix4fv_uniform(name!("u_inverse_view_projection"), &inverse_view_projection);
+        self.shader
+            .set_3f_uniform(name!("u_sun_direction"), sun.x, sun.y, sun.z);
+        self.shader.set_3f_uniform(
+            name!("u_rayleigh"),
+            self.rayleigh.x,
+            self.rayleigh.y,
+            self.rayleigh.z,
+        );
+        self.shader.set_1f_uniform(name!("u_mie"), self.mie);
+        self.shader.set_1f_uniform(name!("u_mie_g"), self.mie_g);
+        self.shader
+            .set_1f_uniform(name!("u_sun_intensity"), self.sun_intensity);
+        self.shader
+            .set_1f_uniform(name!("u_sun_size"), self.sun_size);
+        self.shader
+            .set_3f_uniform(name!("u_ground_color"), ground.r, ground.g, ground.b);
+        self.shader
+            .set_1f_uniform(name!("u_exposure"), self.exposure);
+
+        unsafe {
+            gl::DepthFunc(gl::LEQUAL);
+            gl::DepthMask(gl::FALSE);
+        }
+        self.triangle.draw();
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            gl::DepthFunc(gl::LESS);
+        }
+        ShaderProgram::unbind();
+    }
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,116 @@
+use super::camera::Camera;
+use crate::math::*;
+
+/// # Split Screen Layout
+///
+/// How the window is divided between local players.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitScreenLayout {
+    /// Picks a layout from the player count: stacked for two, one wide view on top
+    /// of two for three, and quadrants for four.
+    #[default]
+    Auto,
+    /// Views stacked top to bottom.
+    Horizontal,
+    /// Views side by side, left to right.
+    Vertical,
+}
+
+/// # Split Screen
+///
+/// Divides the window into one to four player views for local multiplayer.
+/// Player 0 is always the top (or top-left) view.
+///
+/// ## Example
+/// ```ignore
+/// let split = SplitScreen::new(2, SplitScreenLayout::Auto);
+/// split.configure(&mut [&mut player_one_camera, &mut player_two_camera]);
+///
+/// // Pin each player's health bar to the top-left corner of their view.
+/// for player in 0..split.player_count() {
+///     let position = split.ui_anchor(player, Anchor::TopLeft, window.framebuffer_size());
+///     draw_health_bar(position + vec2(16.0, 16.0), health[player]);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SplitScreen {
+    player_count: usize,
+    layout: SplitScreenLayout,
+}
+
+impl SplitScreen {
+    /// Creates a split for one to four players.
+    pub fn new(player_count: usize, layout: SplitScreenLayout) -> Self {
+        assert!(
+            (1..=4).contains(&player_count),
+            "Split screen supports 1 to 4 players, got {}",
+            player_count
+        );
+        Self {
+            player_count,
+            layout,
+        }
+    }
+
+    /// Returns the number of players.
+    pub fn player_count(&self) -> usize {
+        self.player_count
+    }
+
+    /// Returns a player's normalized viewport, with the origin at the bottom-left like OpenGL.
+    pub fn viewport(&self, player: usize) -> Rect {
+        assert!(
+            player < self.player_count,
+            "Player {} is out of range",
+            player
+        );
+        let n = self.player_count as f32;
+        let i = player as f32;
+
+        match (self.layout, self.player_count) {
+            (_, 1) => Rect::new(0.0, 0.0, 1.0, 1.0),
+            (SplitScreenLayout::Vertical, _) => Rect::new(i / n, 0.0, 1.0 / n, 1.0),
+            (SplitScreenLayout::Horizontal, _) | (SplitScreenLayout::Auto, 2) => {
+                Rect::new(0.0, 1.0 - (i + 1.0) / n, 1.0, 1.0 / n)
+            }
+            (SplitScreenLayout::Auto, 3) => match player {
+                0 => Rect::new(0.0, 0.5, 1.0, 0.5),
+                _ => Rect::new((i - 1.0) * 0.5, 0.0, 0.5, 0.5),
+            },
+            (SplitScreenLayout::Auto, _) => {
+                let column = (player % 2) as f32;
+                let row = (player / 2) as f32;
+                Rect::new(column * 0.5, 0.5 - row * 0.5, 0.5, 0.5)
+            }
+        }
+    }
+
+    /// Assigns each player's viewport to their camera, in player order.
+    pub fn configure(&self, cameras: &mut [&mut Camera]) {
+        assert_eq!(
+            cameras.len(),
+            self.player_count,
+            "Expected one camera per player"
+        );
+        for (player, camera) in cameras.iter_mut().enumerate() {
+            camera.viewport = self.viewport(player);
+        }
+    }
+
+    /// Returns a player's view in window pixels with a top-left origin, for UI layout.
+    pub fn ui_rect(&self, player: usize, window_size: (u32, u32)) -> Rect {
+        let viewport = self.viewport(player);
+        let (width, height) = (window_size.0 as f32, window_size.1 as f32);
+        Rect::new(
+            viewport.x * width,
+            (1.0 - viewport.y - viewport.height) * height,
+            viewport.width * width,
+            viewport.height * height,
+        )
+    }
+
+    /// Returns an anchor point inside a player's view in window pixels with a top-left origin.
+    pub fn ui_anchor(&self, player: usize, anchor: Anchor, window_size: (u32, u32)) -> Vec2 {
+        self.ui_rect(player, window_size).anchor_point(anchor)
+    }
+}
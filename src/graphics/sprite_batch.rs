@@ -0,0 +1,366 @@
+use std::mem;
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::gl_wrapper::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use super::shader_preprocessor::{ShaderDefines, ShaderLibrary};
+use super::texture::Texture;
+use super::texture_packer::PackedTexture;
+use crate::math::projection::orthographic;
+use crate::math::*;
+use crate::name;
+
+const SPRITE_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_position;
+layout (location = 1) in vec2 a_uv;
+layout (location = 2) in vec4 a_color;
+layout (location = 3) in float a_layer;
+
+uniform mat4 u_projection;
+
+out vec2 v_uv;
+out vec4 v_color;
+flat out float v_layer;
+
+void main() {
+    v_uv = a_uv;
+    v_color = a_color;
+    v_layer = a_layer;
+    gl_Position = u_projection * vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+const SPRITE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+in vec4 v_color;
+flat in float v_layer;
+out vec4 out_color;
+
+#ifdef TEXTURE_ARRAY
+uniform sampler2DArray u_texture;
+#else
+uniform sampler2D u_texture;
+#endif
+
+void main() {
+#ifdef TEXTURE_ARRAY
+    out_color = texture(u_texture, vec3(v_uv, v_layer)) * v_color;
+#else
+    out_color = texture(u_texture, v_uv) * v_color;
+#endif
+}
+"#;
+
+/// Floats per vertex: position, UV, color and array layer.
+const VERTEX_FLOATS: usize = 9;
+
+/// A vertex of a [`SpriteBatch`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteVertex {
+    pub position: Vec2,
+    pub uv: Vec2,
+    pub color: Color,
+}
+
+impl SpriteVertex {
+    /// Creates a vertex.
+    pub fn new(position: Vec2, uv: Vec2, color: Color) -> Self {
+        Self {
+            position,
+            uv,
+            color,
+        }
+    }
+}
+
+/// # Sprite Batch
+///
+/// Collects textured 2D triangles in pixel coordinates (origin at the top-left)
+/// and draws them with as few draw calls as possible, flushing only when the
+/// texture changes. Sprites, shapes and UI all go through it, so they can be
+/// interleaved freely. Sprites packed into one texture array by a
+/// [`TexturePacker`](super::texture_packer::TexturePacker) share a draw call
+/// whichever image they show.
+///
+/// ## Example
+/// ```ignore
+/// let mut batch = SpriteBatch::new();
+///
+/// batch.begin(window.framebuffer_size());
+/// batch.draw_sprite(&player_texture, Rect::new(100.0, 100.0, 64.0, 64.0), Rect::new(0.0, 0.0, 1.0, 1.0), Color::WHITE);
+/// batch.end();
+/// ```
+pub struct SpriteBatch {
+    shader: ShaderProgram,
+    array_shader: ShaderProgram,
+    vao: Vao,
+    vertex_buffer: BufferObject,
+    index_buffer: BufferObject,
+    vertices: Vec<f32>,
+    indices: Vec<i32>,
+    texture: Option<Arc<Texture>>,
+    white: Arc<Texture>,
+    projection: Mat4,
+    draw_calls: usize,
+    ui_scale: f32,
+}
+
+impl SpriteBatch {
+    /// Creates a sprite batch.
+    pub fn new() -> Self {
+        let shader = sprite_shader(&ShaderDefines::new());
+        let array_shader = sprite_shader(&ShaderDefines::new().with("TEXTURE_ARRAY"));
+
+        let vao = Vao::new();
+        vao.bind();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::STREAM_DRAW);
+        vertex_buffer.bind();
+        let index_buffer = BufferObject::new(gl::ELEMENT_ARRAY_BUFFER, gl::STREAM_DRAW);
+        index_buffer.bind();
+
+        let stride = (VERTEX_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+        for (index, components, offset) in [(0, 2, 0), (1, 2, 2), (2, 4, 4), (3, 1, 8)] {
+            let attribute = VertexAttribute::new(
+                index,
+                components,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (offset * mem::size_of::<GLfloat>()) as *const _,
+            );
+            attribute.enable();
+        }
+        Vao::unbind();
+
+        Self {
+            shader,
+            array_shader,
+            vao,
+            vertex_buffer,
+            index_buffer,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            texture: None,
+            white: Arc::new(Texture::from_rgba8(1, 1, &[255, 255, 255, 255])),
+            projection: Mat4::identity(),
+            draw_calls: 0,
+            ui_scale: 1.0,
+        }
+    }
+
+    /// Returns a 1x1 white texture, used for untextured geometry.
+    pub fn white_texture(&self) -> &Arc<Texture> {
+        &self.white
+    }
+
+    /// Returns the number of draw calls issued since the last `begin`.
+    pub fn draw_calls(&self) -> usize {
+        self.draw_calls
+    }
+
+    /// Starts a batch for a viewport of the given size in pixels.
+    pub fn begin(&mut self, viewport_size: (u32, u32)) {
+        let (width, height) = (
+            viewport_size.0 as f32 / self.ui_scale,
+            viewport_size.1 as f32 / self.ui_scale,
+        );
+        self.begin_with_projection(orthographic(0.0, width, height, 0.0, -1.0, 1.0));
+    }
+
+    /// Sets how many physical pixels one unit covers in [`SpriteBatch::begin`],
+    /// usually the window's content scale so UI keeps its size on high-DPI
+    /// displays.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale.max(f32::EPSILON);
+    }
+
+    /// Returns the UI scale.
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Starts a batch with a custom projection, e.g. for a 2D camera.
+    pub fn begin_with_projection(&mut self, projection: Mat4) {
+        self.projection = projection;
+        self.draw_calls = 0;
+        self.vertices.clear();
+        self.indices.clear();
+        self.texture = None;
+    }
+
+    /// Adds indexed triangles. `indices` refer to `vertices`; pass `None` as the
+    /// texture for flat-colored geometry.
+    pub fn draw_triangles(
+        &mut self,
+        texture: Option<&Arc<Texture>>,
+        vertices: &[SpriteVertex],
+        indices: &[u32],
+    ) {
+        self.draw_layer_triangles(texture, 0, vertices, indices);
+    }
+
+    /// Adds indexed triangles sampling one layer of an array texture.
+    pub fn draw_layer_triangles(
+        &mut self,
+        texture: Option<&Arc<Texture>>,
+        layer: u32,
+        vertices: &[SpriteVertex],
+        indices: &[u32],
+    ) {
+        let texture = texture.unwrap_or(&self.white).clone();
+        if self
+            .texture
+            .as_ref()
+            .is_some_and(|current| !Arc::ptr_eq(current, &texture))
+        {
+            self.flush();
+        }
+        if self.texture.is_none() {
+            self.texture = Some(texture);
+        }
+
+        let base = (self.vertices.len() / VERTEX_FLOATS) as i32;
+        for vertex in vertices {
+            let color = vertex.color.to_linear();
+            self.vertices.extend_from_slice(&[
+                vertex.position.x,
+                vertex.position.y,
+                vertex.uv.x,
+                vertex.uv.y,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+                layer as f32,
+            ]);
+        }
+        self.indices
+            .extend(indices.iter().map(|index| base + *index as i32));
+    }
+
+    /// Adds a textured quad covering `destination`, showing `uv_rect` of the texture.
+    pub fn draw_sprite(
+        &mut self,
+        texture: &Arc<Texture>,
+        destination: Rect,
+        uv_rect: Rect,
+        color: Color,
+    ) {
+        let (min, max) = (destination.min(), destination.max());
+        let (uv_min, uv_max) = (uv_rect.min(), uv_rect.max());
+        let vertices = [
+            SpriteVertex::new(min, uv_min, color),
+            SpriteVertex::new(vec2(max.x, min.y), vec2(uv_max.x, uv_min.y), color),
+            SpriteVertex::new(max, uv_max, color),
+            SpriteVertex::new(vec2(min.x, max.y), vec2(uv_min.x, uv_max.y), color),
+        ];
+        self.draw_triangles(Some(texture), &vertices, &[0, 1, 2, 0, 2, 3]);
+    }
+
+    /// Adds a quad covering `destination`, showing a packed texture.
+    pub fn draw_packed(&mut self, packed: &PackedTexture, destination: Rect, color: Color) {
+        self.draw_packed_region(packed, destination, Rect::new(0.0, 0.0, 1.0, 1.0), color);
+    }
+
+    /// Adds a quad covering `destination`, showing `uv_rect` of a packed
+    /// texture, in UV coordinates relative to its region.
+    pub fn draw_packed_region(
+        &mut self,
+        packed: &PackedTexture,
+        destination: Rect,
+        uv_rect: Rect,
+        color: Color,
+    ) {
+        let (min, max) = (destination.min(), destination.max());
+        let uv_rect = packed.sub_rect(uv_rect);
+        let (uv_min, uv_max) = (uv_rect.min(), uv_rect.max());
+        let vertices = [
+            SpriteVertex::new(min, uv_min, color),
+            SpriteVertex::new(vec2(max.x, min.y), vec2(uv_max.x, uv_min.y), color),
+            SpriteVertex::new(max, uv_max, color),
+            SpriteVertex::new(vec2(min.x, max.y), vec2(uv_min.x, uv_max.y), color),
+        ];
+        self.draw_layer_triangles(
+            Some(&packed.texture),
+            packed.layer,
+            &vertices,
+            &[0, 1, 2, 0, 2, 3],
+        );
+    }
+
+    /// Draws everything queued so far.
+    pub fn flush(&mut self) {
+        let Some(texture) = self.texture.take() else {
+            return;
+        };
+        if self.indices.is_empty() {
+            return;
+        }
+
+        let shader = if texture.target() == gl::TEXTURE_2D_ARRAY {
+            &self.array_shader
+        } else {
+            &self.shader
+        };
+        shader.bind();
+        shader.set_matrix4fv_uniform(name!("u_projection"), &self.projection);
+        shader.set_1i_uniform(name!("u_texture"), 0);
+        texture.bind(0);
+
+        self.vao.bind();
+        self.vertex_buffer.bind();
+        self.vertex_buffer.store_f32_data(&self.vertices);
+        self.index_buffer.bind();
+        self.index_buffer.store_i32_data(&self.indices);
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DrawElements(
+                gl::TRIANGLES,
+                self.indices.len() as GLsizei,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        Vao::unbind();
+        ShaderProgram::unbind();
+
+        self.draw_calls += 1;
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Draws everything queued and ends the batch.
+    pub fn end(&mut self) {
+        self.flush();
+    }
+}
+
+impl Default for SpriteBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compiles the sprite shader for plain or array textures.
+fn sprite_shader(defines: &ShaderDefines) -> ShaderProgram {
+    let mut library = ShaderLibrary::new();
+    let vertex = library
+        .preprocess(SPRITE_VERTEX_SHADER, defines)
+        .expect("Sprite shader has no includes");
+    let fragment = library
+        .preprocess(SPRITE_FRAGMENT_SHADER, defines)
+        .expect("Sprite shader has no includes");
+    let mut shader = ShaderProgram::from_source(&vertex, &fragment);
+    for uniform in ["u_projection", "u_texture"] {
+        shader.create_uniform(uniform);
+    }
+    shader
+}
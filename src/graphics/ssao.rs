@@ -0,0 +1,291 @@
+use super::camera::CameraView;
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::texture::Texture;
+use crate::math::*;
+use crate::name;
+
+const SSAO_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out float out_occlusion;
+
+uniform sampler2D u_depth_texture;
+uniform mat4 u_projection;
+uniform mat4 u_inverse_projection;
+uniform int u_kernel_size;
+uniform float u_radius;
+uniform float u_bias;
+uniform float u_intensity;
+
+const float GOLDEN_ANGLE = 2.39996323;
+
+vec3 view_position(vec2 uv) {
+    float depth = texture(u_depth_texture, uv).r;
+    vec4 position = u_inverse_projection * vec4(vec3(uv, depth) * 2.0 - 1.0, 1.0);
+    return position.xyz / position.w;
+}
+
+float interleaved_gradient_noise(vec2 pixel) {
+    return fract(52.9829189 * fract(dot(pixel, vec2(0.06711056, 0.00583715))));
+}
+
+void main() {
+    if (texture(u_depth_texture, v_uv).r >= 1.0) {
+        out_occlusion = 1.0;
+        return;
+    }
+
+    vec3 position = view_position(v_uv);
+    vec3 normal = normalize(cross(dFdx(position), dFdy(position)));
+
+    float angle = interleaved_gradient_noise(gl_FragCoord.xy) * 6.28318531;
+    vec3 random = vec3(cos(angle), sin(angle), 0.0);
+    vec3 tangent = normalize(random - normal * dot(random, normal));
+    mat3 tbn = mat3(tangent, cross(normal, tangent), normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < u_kernel_size; ++i) {
+        // Spiral the samples over the hemisphere, denser near the center.
+        float t = (float(i) + 0.5) / float(u_kernel_size);
+        float sample_angle = float(i) * GOLDEN_ANGLE;
+        float z = 1.0 - t;
+        float r = sqrt(1.0 - z * z);
+        vec3 direction = vec3(cos(sample_angle) * r, sin(sample_angle) * r, z);
+
+        float scale = mix(0.1, 1.0, t * t);
+        vec3 sample_position = position + tbn * direction * (u_radius * scale);
+
+        vec4 offset = u_projection * vec4(sample_position, 1.0);
+        vec2 sample_uv = offset.xy / offset.w * 0.5 + 0.5;
+        float sample_depth = view_position(sample_uv).z;
+
+        float range = smoothstep(0.0, 1.0, u_radius / abs(position.z - sample_depth));
+        occlusion += (sample_depth >= sample_position.z + u_bias ? 1.0 : 0.0) * range;
+    }
+
+    out_occlusion = pow(1.0 - occlusion / float(u_kernel_size), u_intensity);
+}
+"#;
+
+const BLUR_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out float out_occlusion;
+
+uniform sampler2D u_occlusion;
+uniform vec2 u_direction;
+uniform int u_blur_radius;
+
+void main() {
+    vec2 texel = u_direction / vec2(textureSize(u_occlusion, 0));
+    float sum = 0.0;
+    for (int i = -u_blur_radius; i <= u_blur_radius; ++i) {
+        sum += texture(u_occlusion, v_uv + texel * float(i)).r;
+    }
+    out_occlusion = sum / float(u_blur_radius * 2 + 1);
+}
+"#;
+
+const COMPOSITE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_occlusion;
+
+void main() {
+    out_color = vec4(vec3(texture(u_occlusion, v_uv).r), 1.0);
+}
+"#;
+
+/// Tweakable parameters of the [`Ssao`] pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SsaoSettings {
+    /// World-space radius of the sampled hemisphere.
+    pub radius: f32,
+    /// Depth offset that prevents flat surfaces from occluding themselves.
+    pub bias: f32,
+    /// Exponent applied to the final ambient term; higher is darker.
+    pub intensity: f32,
+    /// Number of samples per pixel.
+    pub kernel_size: u32,
+    /// Half-width in pixels of the separable blur; 0 disables blurring.
+    pub blur_radius: u32,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            bias: 0.025,
+            intensity: 1.0,
+            kernel_size: 32,
+            blur_radius: 2,
+        }
+    }
+}
+
+/// # Screen-Space Ambient Occlusion
+///
+/// Darkens creases, corners and contact points by testing how much of the
+/// hemisphere above each pixel is covered by nearby depth. Normals are
+/// reconstructed from depth, so it only needs a scene rendered into a
+/// framebuffer created with [`Framebuffer::with_depth_texture`].
+///
+/// ## Example
+/// ```ignore
+/// let mut ssao = Ssao::new(window.framebuffer_size(), SsaoSettings::default());
+///
+/// // after drawing opaque geometry into `scene`:
+/// ssao.render(&camera_view, &scene);
+/// ssao.apply(&scene);
+/// ```
+pub struct Ssao {
+    pub settings: SsaoSettings,
+    occlusion: Framebuffer,
+    blurred: Framebuffer,
+    ssao_shader: ShaderProgram,
+    blur_shader: ShaderProgram,
+    composite_shader: ShaderProgram,
+    triangle: Primitive,
+}
+
+impl Ssao {
+    /// Creates an SSAO pass for scenes of the given size.
+    pub fn new(size: (u32, u32), settings: SsaoSettings) -> Self {
+        let mut ssao_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, SSAO_FRAGMENT_SHADER);
+        for uniform in [
+            "u_depth_texture",
+            "u_projection",
+            "u_inverse_projection",
+            "u_kernel_size",
+            "u_radius",
+            "u_bias",
+            "u_intensity",
+        ] {
+            ssao_shader.create_uniform(uniform);
+        }
+
+        let mut blur_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, BLUR_FRAGMENT_SHADER);
+        for uniform in ["u_occlusion", "u_direction", "u_blur_radius"] {
+            blur_shader.create_uniform(uniform);
+        }
+
+        let mut composite_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, COMPOSITE_FRAGMENT_SHADER);
+        composite_shader.create_uniform("u_occlusion");
+
+        Self {
+            settings,
+            occlusion: Self::create_target(size),
+            blurred: Self::create_target(size),
+            ssao_shader,
+            blur_shader,
+            composite_shader,
+            triangle: Primitive::fullscreen_triangle(),
+        }
+    }
+
+    fn create_target(size: (u32, u32)) -> Framebuffer {
+        Framebuffer::with_format(size.0, size.1, gl::R8, gl::RED, gl::UNSIGNED_BYTE)
+    }
+
+    /// Returns the ambient occlusion term from the last [`Ssao::render`]; 1 is unoccluded.
+    pub fn texture(&self) -> &Texture {
+        self.occlusion.color_texture()
+    }
+
+    /// Computes ambient occlusion for the scene as seen by `view`.
+    pub fn render(&mut self, view: &CameraView, scene: &Framebuffer) {
+        let (width, height) = scene.size();
+        self.occlusion.resize(width, height);
+        self.blurred.resize(width, height);
+
+        let inverse_projection = view
+            .projection
+            .invert()
+            .expect("Projection matrix is not invertible");
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+
+        self.occlusion.bind();
+        self.ssao_shader.bind();
+        self.ssao_shader
+            .set_matrix4fv_uniform(name!("u_projection"), &view.projection);
+        self.ssao_shader
+            .set_matrix4fv_uniform(name!("u_inverse_projection"), &inverse_projection);
+        self.ssao_shader.set_1i_uniform(
+            name!("u_kernel_size"),
+            self.settings.kernel_size.max(1) as i32,
+        );
+        self.ssao_shader
+            .set_1f_uniform(name!("u_radius"), self.settings.radius);
+        self.ssao_shader
+            .set_1f_uniform(name!("u_bias"), self.settings.bias);
+        self.ssao_shader
+            .set_1f_uniform(name!("u_intensity"), self.settings.intensity);
+        self.ssao_shader.set_1i_uniform(name!("u_depth_texture"), 0);
+        scene
+            .depth_texture()
+            .expect("SSAO needs a framebuffer created with Framebuffer::with_depth_texture")
+            .bind(0);
+        self.triangle.draw();
+
+        if self.settings.blur_radius > 0 {
+            self.blur_shader.bind();
+            self.blur_shader
+                .set_1i_uniform(name!("u_blur_radius"), self.settings.blur_radius as i32);
+            self.blur_shader.set_1i_uniform(name!("u_occlusion"), 0);
+
+            self.blurred.bind();
+            self.blur_shader
+                .set_2f_uniform(name!("u_direction"), 1.0, 0.0);
+            self.occlusion.color_texture().bind(0);
+            self.triangle.draw();
+
+            self.occlusion.bind();
+            self.blur_shader
+                .set_2f_uniform(name!("u_direction"), 0.0, 1.0);
+            self.blurred.color_texture().bind(0);
+            self.triangle.draw();
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    /// Multiplies the occlusion into `target`'s color.
+    pub fn apply(&self, target: &Framebuffer) {
+        let (width, height) = target.size();
+        target.bind();
+        self.composite_shader.bind();
+        self.composite_shader
+            .set_1i_uniform(name!("u_occlusion"), 0);
+        self.texture().bind(0);
+
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ZERO, gl::SRC_COLOR);
+        }
+        self.triangle.draw();
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+}
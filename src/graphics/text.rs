@@ -0,0 +1,420 @@
+use std::mem;
+use std::sync::Arc;
+
+use gl::types::*;
+
+use super::font::SdfFont;
+use super::gl_wrapper::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use super::text_layout::{TextLayout, TextLayoutOptions};
+use crate::math::projection::orthographic;
+use crate::math::*;
+use crate::name;
+use crate::pool::Pool;
+
+const TEXT_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 a_position;
+layout (location = 1) in vec2 a_uv;
+layout (location = 2) in vec4 a_color;
+layout (location = 3) in vec4 a_outline_color;
+layout (location = 4) in vec4 a_shadow_color;
+layout (location = 5) in vec4 a_params;
+
+uniform mat4 u_view_projection;
+
+out vec2 v_uv;
+out vec4 v_color;
+out vec4 v_outline_color;
+out vec4 v_shadow_color;
+out vec4 v_params;
+
+void main() {
+    v_uv = a_uv;
+    v_color = a_color;
+    v_outline_color = a_outline_color;
+    v_shadow_color = a_shadow_color;
+    v_params = a_params;
+    gl_Position = u_view_projection * vec4(a_position, 1.0);
+}
+"#;
+
+const TEXT_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+in vec4 v_color;
+in vec4 v_outline_color;
+in vec4 v_shadow_color;
+in vec4 v_params;
+out vec4 out_color;
+
+uniform sampler2D u_atlas;
+
+void main() {
+    // v_params: outline width, shadow softness (both in distance units), shadow UV offset.
+    float distance = texture(u_atlas, v_uv).r;
+    float smoothing = max(fwidth(distance) * 0.7, 0.001);
+    float edge = 0.5 - v_params.x;
+
+    float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    float outlined = smoothstep(edge - smoothing, edge + smoothing, distance);
+    vec4 body = mix(v_outline_color, v_color, fill);
+    body.a *= outlined;
+
+    float shadow_distance = texture(u_atlas, v_uv - v_params.zw).r;
+    float spread = v_params.y + smoothing;
+    float shadow = smoothstep(edge - spread, edge + spread, shadow_distance) * v_shadow_color.a;
+
+    float alpha = body.a + shadow * (1.0 - body.a);
+    vec3 color = body.rgb * body.a + v_shadow_color.rgb * shadow * (1.0 - body.a);
+    out_color = vec4(color / max(alpha, 0.0001), alpha);
+}
+"#;
+
+/// Floats per vertex: position, UV, fill, outline and shadow colors, and effect parameters.
+const VERTEX_FLOATS: usize = 21;
+
+/// How a run of text is drawn. Lengths are in pixels for screen text and in
+/// world units for world-space text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    /// Height of the font's em square.
+    pub size: f32,
+    pub color: Color,
+    /// Outline thickness outside the glyph edge; 0 disables the outline.
+    pub outline_width: f32,
+    pub outline_color: Color,
+    /// Offset of the drop shadow, Y down; a transparent color disables it.
+    pub shadow_offset: Vec2,
+    pub shadow_color: Color,
+    /// How far the shadow's edge is blurred.
+    pub shadow_softness: f32,
+}
+
+impl TextStyle {
+    /// Creates a plain style.
+    pub fn new(size: f32, color: Color) -> Self {
+        Self {
+            size,
+            color,
+            outline_width: 0.0,
+            outline_color: Color::TRANSPARENT,
+            shadow_offset: vec2(0.0, 0.0),
+            shadow_color: Color::TRANSPARENT,
+            shadow_softness: 0.0,
+        }
+    }
+
+    /// Adds an outline around the glyphs.
+    pub fn with_outline(mut self, width: f32, color: Color) -> Self {
+        self.outline_width = width;
+        self.outline_color = color;
+        self
+    }
+
+    /// Adds a drop shadow behind the glyphs.
+    pub fn with_shadow(mut self, offset: Vec2, color: Color, softness: f32) -> Self {
+        self.shadow_offset = offset;
+        self.shadow_color = color;
+        self.shadow_softness = softness;
+        self
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(16.0, Color::WHITE)
+    }
+}
+
+/// Queued glyphs sharing one font atlas.
+struct TextBatch {
+    font: Arc<SdfFont>,
+    vertices: Vec<f32>,
+    indices: Vec<i32>,
+}
+
+/// # Text Renderer
+///
+/// Draws [`SdfFont`] text as screen-space overlays or as labels placed in the
+/// 3D world. Glyph edges are reconstructed from the distance field per pixel,
+/// so text is sharp at any size or distance, and outlines and soft drop shadows
+/// are evaluated in the same shader without extra passes.
+///
+/// Outlines and shadows can extend at most the font's spread beyond the glyph.
+///
+/// ## Example
+/// ```ignore
+/// let mut text = TextRenderer::new();
+/// let style = TextStyle::new(32.0, Color::WHITE)
+///     .with_outline(2.0, Color::BLACK)
+///     .with_shadow(vec2(2.0, 3.0), Color::BLACK.with_alpha(0.6), 1.5);
+///
+/// text.draw_2d(&font, "Score: 1200", vec2(20.0, 20.0), style);
+/// text.flush_2d(window.framebuffer_size());
+///
+/// let label = Mat4::from_translation(enemy_position + vec3(0.0, 2.0, 0.0));
+/// text.draw_3d(&font, "Goblin", &label, TextStyle::new(0.5, Color::RED));
+/// text.flush(&camera_view.view_projection);
+/// ```
+pub struct TextRenderer {
+    shader: ShaderProgram,
+    vao: Vao,
+    vertex_buffer: BufferObject,
+    index_buffer: BufferObject,
+    batches: Vec<TextBatch>,
+    vertex_pool: Pool<Vec<f32>>,
+    index_pool: Pool<Vec<i32>>,
+    ui_scale: f32,
+}
+
+impl TextRenderer {
+    /// Creates a text renderer.
+    pub fn new() -> Self {
+        let mut shader = ShaderProgram::from_source(TEXT_VERTEX_SHADER, TEXT_FRAGMENT_SHADER);
+        for uniform in ["u_view_projection", "u_atlas"] {
+            shader.create_uniform(uniform);
+        }
+
+        let vao = Vao::new();
+        vao.bind();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::STREAM_DRAW);
+        vertex_buffer.bind();
+        let index_buffer = BufferObject::new(gl::ELEMENT_ARRAY_BUFFER, gl::STREAM_DRAW);
+        index_buffer.bind();
+
+        let stride = (VERTEX_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+        for (index, components, offset) in [
+            (0, 3, 0),
+            (1, 2, 3),
+            (2, 4, 5),
+            (3, 4, 9),
+            (4, 4, 13),
+            (5, 4, 17),
+        ] {
+            let attribute = VertexAttribute::new(
+                index,
+                components,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (offset * mem::size_of::<GLfloat>()) as *const _,
+            );
+            attribute.enable();
+        }
+        Vao::unbind();
+
+        Self {
+            shader,
+            vao,
+            vertex_buffer,
+            index_buffer,
+            batches: Vec::new(),
+            vertex_pool: Pool::new(),
+            index_pool: Pool::new(),
+            ui_scale: 1.0,
+        }
+    }
+
+    /// Sets how many physical pixels one unit of screen text covers, usually
+    /// the window's content scale so text stays readable on high-DPI displays.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale.max(f32::EPSILON);
+    }
+
+    /// Returns the UI scale.
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Returns the number of glyphs queued.
+    pub fn glyph_count(&self) -> usize {
+        self.batches
+            .iter()
+            .map(|batch| batch.indices.len() / 6)
+            .sum()
+    }
+
+    /// Queues screen text in pixels, with `position` at the top-left of the first line.
+    pub fn draw_2d(&mut self, font: &Arc<SdfFont>, text: &str, position: Vec2, style: TextStyle) {
+        let layout = TextLayout::from_text(font, text, TextLayoutOptions::new(style.size));
+        self.draw_layout_2d(font, &layout, position, style);
+    }
+
+    /// Queues world-space text. In the model's local space the text starts at
+    /// the origin and runs along +X with +Y up, `style.size` units tall.
+    pub fn draw_3d(&mut self, font: &Arc<SdfFont>, text: &str, model: &Mat4, style: TextStyle) {
+        let layout = TextLayout::from_text(font, text, TextLayoutOptions::new(style.size));
+        self.draw_layout_3d(font, &layout, model, style);
+    }
+
+    /// Queues a laid out block of screen text with its top-left at `position`.
+    /// Sizes come from the layout; spans override the style's colors.
+    pub fn draw_layout_2d(
+        &mut self,
+        font: &Arc<SdfFont>,
+        layout: &TextLayout,
+        position: Vec2,
+        style: TextStyle,
+    ) {
+        self.queue(font, layout, style, |point| {
+            position.extend(0.0) + point.extend(0.0)
+        });
+    }
+
+    /// Queues a laid out block of world-space text, placed like [`TextRenderer::draw_3d`].
+    pub fn draw_layout_3d(
+        &mut self,
+        font: &Arc<SdfFont>,
+        layout: &TextLayout,
+        model: &Mat4,
+        style: TextStyle,
+    ) {
+        self.queue(font, layout, style, |point| {
+            (*model * vec4(point.x, -point.y, 0.0, 1.0)).truncate()
+        });
+    }
+
+    /// Appends the glyph quads of a layout. `place` maps layout points (Y down,
+    /// origin at the top-left) to positions.
+    fn queue<F>(&mut self, font: &Arc<SdfFont>, layout: &TextLayout, style: TextStyle, place: F)
+    where
+        F: Fn(Vec2) -> Vec3,
+    {
+        if !self
+            .batches
+            .last()
+            .is_some_and(|batch| Arc::ptr_eq(&batch.font, font))
+        {
+            self.batches.push(TextBatch {
+                font: font.clone(),
+                vertices: self.vertex_pool.take(),
+                indices: self.index_pool.take(),
+            });
+        }
+        let batch = self.batches.last_mut().unwrap();
+        let atlas = font.texture();
+
+        for placed in layout.glyphs() {
+            let Some(glyph) = font.glyph(placed.character) else {
+                continue;
+            };
+            if glyph.size.x <= 0.0 {
+                continue;
+            }
+
+            let span = &layout.spans()[placed.span];
+            let color = span.color.unwrap_or(style.color);
+            let outline_color = match span.outline_color {
+                Some(outline_color) => outline_color,
+                None if style.outline_width > 0.0 => style.outline_color,
+                None => color,
+            };
+            let scale = placed.size / font.size();
+            let to_distance = 1.0 / (scale * 2.0 * font.spread().max(1) as f32);
+            let params = [
+                (style.outline_width * to_distance).clamp(0.0, 0.49),
+                style.shadow_softness.max(0.0) * to_distance,
+                style.shadow_offset.x / scale / atlas.width() as f32,
+                style.shadow_offset.y / scale / atlas.height() as f32,
+            ];
+
+            let min = placed.position + glyph.offset * scale;
+            let max = min + glyph.size * scale;
+            let (uv_min, uv_max) = (glyph.uv_rect.min(), glyph.uv_rect.max());
+            let base = (batch.vertices.len() / VERTEX_FLOATS) as i32;
+            for (corner, uv) in [
+                (min, uv_min),
+                (vec2(max.x, min.y), vec2(uv_max.x, uv_min.y)),
+                (max, uv_max),
+                (vec2(min.x, max.y), vec2(uv_min.x, uv_max.y)),
+            ] {
+                let position = place(corner);
+                batch
+                    .vertices
+                    .extend_from_slice(&[position.x, position.y, position.z, uv.x, uv.y]);
+                batch.vertices.extend_from_slice(&color.to_linear_array());
+                batch
+                    .vertices
+                    .extend_from_slice(&outline_color.to_linear_array());
+                batch
+                    .vertices
+                    .extend_from_slice(&style.shadow_color.to_linear_array());
+                batch.vertices.extend_from_slice(&params);
+            }
+            batch
+                .indices
+                .extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+    }
+
+    /// Draws queued world-space text, depth tested against the scene, and clears the queue.
+    pub fn flush(&mut self, view_projection: &Mat4) {
+        self.draw(view_projection, true);
+    }
+
+    /// Draws queued screen text over everything for a viewport in pixels, and clears the queue.
+    pub fn flush_2d(&mut self, viewport_size: (u32, u32)) {
+        let (width, height) = (
+            viewport_size.0 as f32 / self.ui_scale,
+            viewport_size.1 as f32 / self.ui_scale,
+        );
+        self.draw(&orthographic(0.0, width, height, 0.0, -1.0, 1.0), false);
+    }
+
+    fn draw(&mut self, view_projection: &Mat4, depth_test: bool) {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        self.shader.bind();
+        self.shader
+            .set_matrix4fv_uniform(name!("u_view_projection"), view_projection);
+        self.shader.set_1i_uniform(name!("u_atlas"), 0);
+        self.vao.bind();
+
+        unsafe {
+            if depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+                gl::DepthMask(gl::FALSE);
+            } else {
+                gl::Disable(gl::DEPTH_TEST);
+            }
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+
+        for batch in self.batches.drain(..) {
+            if !batch.indices.is_empty() {
+                batch.font.texture().bind(0);
+                self.vertex_buffer.bind();
+                self.vertex_buffer.store_f32_data(&batch.vertices);
+                self.index_buffer.bind();
+                self.index_buffer.store_i32_data(&batch.indices);
+                unsafe {
+                    gl::DrawElements(
+                        gl::TRIANGLES,
+                        batch.indices.len() as GLsizei,
+                        gl::UNSIGNED_INT,
+                        std::ptr::null(),
+                    );
+                }
+            }
+            self.vertex_pool.give(batch.vertices);
+            self.index_pool.give(batch.indices);
+        }
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        Vao::unbind();
+        ShaderProgram::unbind();
+    }
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
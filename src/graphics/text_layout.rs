@@ -0,0 +1,588 @@
+use std::ops::Range;
+
+use super::font::SdfFont;
+use crate::math::*;
+
+/// Horizontal alignment of each line within a [`TextLayout`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A run of text sharing one look. Unset fields fall back to the
+/// [`TextStyle`](super::text::TextStyle) it is drawn with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub color: Option<Color>,
+    pub outline_color: Option<Color>,
+    /// Multiplier on the layout's font size.
+    pub scale: f32,
+}
+
+impl TextSpan {
+    /// Creates an unstyled span.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            color: None,
+            outline_color: None,
+            scale: 1.0,
+        }
+    }
+
+    /// Overrides the fill color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Overrides the outline color.
+    pub fn with_outline_color(mut self, color: Color) -> Self {
+        self.outline_color = Some(color);
+        self
+    }
+
+    /// Scales the span relative to the layout's size.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// Parses lightweight markup into spans.
+///
+/// Supported tags, which may nest:
+/// - `[color=#ff8800]...[/color]`
+/// - `[outline=#000]...[/outline]`
+/// - `[size=1.5]...[/size]`, relative to the layout's size
+///
+/// `[[` produces a literal `[`. Unknown or malformed tags are kept as text.
+///
+/// ```ignore
+/// let spans = parse_markup("Deal [color=#ff4040]25[/color] damage, [size=0.8]maybe[/size]");
+/// ```
+pub fn parse_markup(markup: &str) -> Vec<TextSpan> {
+    let mut spans: Vec<TextSpan> = Vec::new();
+    let mut stack = vec![TextSpan::new("")];
+    let mut text = String::new();
+
+    let flush = |spans: &mut Vec<TextSpan>, text: &mut String, style: &TextSpan| {
+        if !text.is_empty() {
+            spans.push(TextSpan {
+                text: std::mem::take(text),
+                ..style.clone()
+            });
+        }
+    };
+
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("[[") {
+            text.push('[');
+            rest = after;
+            continue;
+        }
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        let current = stack.last().unwrap().clone();
+
+        let opened = match tag.split_once('=') {
+            Some(("color", value)) => {
+                Color::from_hex(value).map(|color| current.clone().with_color(color))
+            }
+            Some(("outline", value)) => {
+                Color::from_hex(value).map(|color| current.clone().with_outline_color(color))
+            }
+            Some(("size", value)) => value
+                .parse::<f32>()
+                .ok()
+                .filter(|scale| *scale > 0.0)
+                .map(|scale| current.clone().with_scale(current.scale * scale)),
+            _ => None,
+        };
+        let closes = matches!(tag, "/color" | "/outline" | "/size") && stack.len() > 1;
+
+        if let Some(style) = opened {
+            flush(&mut spans, &mut text, &current);
+            stack.push(style);
+        } else if closes {
+            flush(&mut spans, &mut text, &current);
+            stack.pop();
+        } else {
+            text.push_str(&rest[..=end]);
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    flush(&mut spans, &mut text, stack.last().unwrap());
+    spans
+}
+
+/// Settings for [`TextLayout::new`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextLayoutOptions {
+    /// Font size in pixels, or world units for world-space text.
+    pub size: f32,
+    /// Lines wrap at word boundaries to stay within this width.
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+    /// Multiplier on the font's line height.
+    pub line_spacing: f32,
+}
+
+impl TextLayoutOptions {
+    /// Creates options for unwrapped, left-aligned text.
+    pub fn new(size: f32) -> Self {
+        Self {
+            size,
+            max_width: None,
+            align: TextAlign::Left,
+            line_spacing: 1.0,
+        }
+    }
+
+    /// Wraps lines to a maximum width.
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Sets the line alignment.
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Sets the line spacing multiplier.
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+impl Default for TextLayoutOptions {
+    fn default() -> Self {
+        Self::new(16.0)
+    }
+}
+
+/// The font measurements a layout is built from, in pixels at the font's
+/// base size.
+trait LayoutMetrics {
+    fn size(&self) -> f32;
+    fn advance(&self, character: char) -> Option<f32>;
+    fn kern(&self, first: char, second: char) -> f32;
+    fn ascent(&self) -> f32;
+    fn line_height(&self) -> f32;
+}
+
+impl LayoutMetrics for SdfFont {
+    fn size(&self) -> f32 {
+        self.size()
+    }
+
+    fn advance(&self, character: char) -> Option<f32> {
+        self.glyph(character).map(|glyph| glyph.advance)
+    }
+
+    fn kern(&self, first: char, second: char) -> f32 {
+        self.kern(first, second)
+    }
+
+    fn ascent(&self) -> f32 {
+        self.ascent()
+    }
+
+    fn line_height(&self) -> f32 {
+        self.line_height()
+    }
+}
+
+/// A glyph placed by a [`TextLayout`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutGlyph {
+    pub character: char,
+    /// Pen position on the baseline, relative to the layout's top-left, Y down.
+    pub position: Vec2,
+    /// Font size of the glyph.
+    pub size: f32,
+    /// Index of the span the glyph came from.
+    pub span: usize,
+}
+
+/// A line of a [`TextLayout`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutLine {
+    /// Glyphs on the line, as indices into [`TextLayout::glyphs`].
+    pub glyphs: Range<usize>,
+    /// Line extent, excluding trailing spaces.
+    pub bounds: Rect,
+    /// Y of the baseline.
+    pub baseline: f32,
+}
+
+/// # Text Layout
+///
+/// Styled text broken into lines and positioned glyph by glyph, ready to be
+/// drawn by a [`TextRenderer`](super::text::TextRenderer) or measured for UI.
+/// Lines break on `\n` and, with a maximum width, at the last space that keeps
+/// the line within it; words longer than a line are split between characters.
+///
+/// ## Example
+/// ```ignore
+/// let layout = TextLayout::from_markup(
+///     &font,
+///     "[color=#ffd700]Quest complete![/color]\nYou found [size=1.2]3[/size] apples.",
+///     TextLayoutOptions::new(20.0).with_max_width(300.0).with_align(TextAlign::Center),
+/// );
+/// let panel = Rect::new(40.0, 40.0, layout.size().x + 16.0, layout.size().y + 16.0);
+/// text.draw_layout_2d(&font, &layout, vec2(48.0, 48.0), TextStyle::default());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    spans: Vec<TextSpan>,
+    glyphs: Vec<LayoutGlyph>,
+    lines: Vec<LayoutLine>,
+    options: TextLayoutOptions,
+    size: Vec2,
+}
+
+impl TextLayout {
+    /// Lays out spans.
+    pub fn new(font: &SdfFont, spans: Vec<TextSpan>, options: TextLayoutOptions) -> Self {
+        Self::with_metrics(font, spans, options)
+    }
+
+    fn with_metrics(
+        font: &impl LayoutMetrics,
+        spans: Vec<TextSpan>,
+        options: TextLayoutOptions,
+    ) -> Self {
+        let mut layout = Self {
+            spans,
+            glyphs: Vec::new(),
+            lines: Vec::new(),
+            options,
+            size: vec2(0.0, 0.0),
+        };
+        layout.build(font);
+        layout
+    }
+
+    /// Lays out plain text.
+    pub fn from_text(font: &SdfFont, text: &str, options: TextLayoutOptions) -> Self {
+        Self::new(font, vec![TextSpan::new(text)], options)
+    }
+
+    /// Lays out text written in the [`parse_markup`] syntax.
+    pub fn from_markup(font: &SdfFont, markup: &str, options: TextLayoutOptions) -> Self {
+        Self::new(font, parse_markup(markup), options)
+    }
+
+    /// Returns the spans the layout was built from.
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    /// Returns the placed glyphs, including spaces.
+    pub fn glyphs(&self) -> &[LayoutGlyph] {
+        &self.glyphs
+    }
+
+    /// Returns the lines, top to bottom.
+    pub fn lines(&self) -> &[LayoutLine] {
+        &self.lines
+    }
+
+    /// Returns the options the layout was built with.
+    pub fn options(&self) -> &TextLayoutOptions {
+        &self.options
+    }
+
+    /// Returns the width of the widest line and the total height. With a
+    /// maximum width, the width is at most that (unless a single glyph is wider).
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// Returns the area covered by the lines, relative to the layout's top-left.
+    /// Centered and right-aligned lines are placed within the maximum width, or
+    /// the widest line when there is none.
+    pub fn bounds(&self) -> Rect {
+        let mut lines = self.lines.iter().map(|line| line.bounds);
+        let Some(first) = lines.next() else {
+            return Rect::new(0.0, 0.0, 0.0, 0.0);
+        };
+        let (min, max) = lines.fold((first.min(), first.max()), |(min, max), rect| {
+            (
+                vec2(min.x.min(rect.x), min.y.min(rect.y)),
+                vec2(max.x.max(rect.max().x), max.y.max(rect.max().y)),
+            )
+        });
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    fn build(&mut self, font: &impl LayoutMetrics) {
+        let base = self.options.size / font.size();
+        let max_width = self.options.max_width.unwrap_or(f32::INFINITY);
+
+        // Break into lines of (character, span, x) with x measured from the line start.
+        let mut lines: Vec<Vec<(char, usize, f32)>> = vec![Vec::new()];
+        let mut pen = 0.0;
+        for (span_index, span) in self.spans.iter().enumerate() {
+            let scale = base * span.scale;
+            for character in span.text.chars() {
+                if character == '\n' {
+                    lines.push(Vec::new());
+                    pen = 0.0;
+                    continue;
+                }
+                let Some(advance) = font.advance(character) else {
+                    continue;
+                };
+
+                let line = lines.last_mut().unwrap();
+                if let Some((previous, _, _)) = line.last() {
+                    pen += font.kern(*previous, character) * scale;
+                }
+                let advance = advance * scale;
+
+                if pen + advance > max_width && !character.is_whitespace() && !line.is_empty() {
+                    let split = line
+                        .iter()
+                        .rposition(|(c, _, _)| c.is_whitespace())
+                        .map_or(line.len(), |space| space + 1);
+                    let carried: Vec<(char, usize, f32)> = line.drain(split..).collect();
+                    let shift = carried.first().map_or(pen, |(_, _, x)| *x);
+                    let next: Vec<(char, usize, f32)> = carried
+                        .into_iter()
+                        .map(|(c, s, x)| (c, s, x - shift))
+                        .collect();
+                    pen -= shift;
+                    if next.is_empty() {
+                        pen = 0.0;
+                    }
+                    lines.push(next);
+                }
+
+                lines.last_mut().unwrap().push((character, span_index, pen));
+                pen += advance;
+            }
+        }
+
+        let widths: Vec<f32> = lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .rev()
+                    .find(|(c, _, _)| !c.is_whitespace())
+                    .map_or(0.0, |(c, s, x)| {
+                        let scale = base * self.spans[*s].scale;
+                        x + font.advance(*c).map_or(0.0, |advance| advance * scale)
+                    })
+            })
+            .collect();
+        let widest = widths.iter().copied().fold(0.0, f32::max);
+        let area = if max_width.is_finite() {
+            max_width
+        } else {
+            widest
+        };
+
+        let mut top = 0.0;
+        for (line, width) in lines.into_iter().zip(widths) {
+            let scale = line
+                .iter()
+                .map(|(_, span, _)| base * self.spans[*span].scale)
+                .fold(0.0, f32::max);
+            let scale = if scale > 0.0 { scale } else { base };
+            let baseline = top + font.ascent() * scale;
+            let height = font.line_height() * scale * self.options.line_spacing;
+            let left = match self.options.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (area - width) * 0.5,
+                TextAlign::Right => area - width,
+            };
+
+            let start = self.glyphs.len();
+            self.glyphs
+                .extend(line.into_iter().map(|(character, span, x)| LayoutGlyph {
+                    character,
+                    position: vec2(left + x, baseline),
+                    size: self.options.size * self.spans[span].scale,
+                    span,
+                }));
+            self.lines.push(LayoutLine {
+                glyphs: start..self.glyphs.len(),
+                bounds: Rect::new(left, top, width, height),
+                baseline,
+            });
+            top += height;
+        }
+
+        self.size = vec2(widest, top);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monospaced font 10 pixels wide with one kerning pair, `AV`. It has
+    /// no glyphs for control characters.
+    struct Monospace;
+
+    impl LayoutMetrics for Monospace {
+        fn size(&self) -> f32 {
+            10.0
+        }
+
+        fn advance(&self, character: char) -> Option<f32> {
+            (!character.is_control()).then_some(10.0)
+        }
+
+        fn kern(&self, first: char, second: char) -> f32 {
+            if (first, second) == ('A', 'V') {
+                -1.0
+            } else {
+                0.0
+            }
+        }
+
+        fn ascent(&self) -> f32 {
+            8.0
+        }
+
+        fn line_height(&self) -> f32 {
+            12.0
+        }
+    }
+
+    fn layout(markup: &str, options: TextLayoutOptions) -> TextLayout {
+        TextLayout::with_metrics(&Monospace, parse_markup(markup), options)
+    }
+
+    fn line_text(layout: &TextLayout, line: usize) -> String {
+        layout.glyphs()[layout.lines()[line].glyphs.clone()]
+            .iter()
+            .map(|glyph| glyph.character)
+            .collect()
+    }
+
+    #[test]
+    fn markup_tags_nest() {
+        let red = Color::from_hex("#ff0000").unwrap();
+        let spans = parse_markup("a[color=#ff0000]b[size=2]c[/size]d[/color]e");
+        assert_eq!(
+            spans,
+            vec![
+                TextSpan::new("a"),
+                TextSpan::new("b").with_color(red),
+                TextSpan::new("c").with_color(red).with_scale(2.0),
+                TextSpan::new("d").with_color(red),
+                TextSpan::new("e"),
+            ]
+        );
+
+        let spans = parse_markup("[size=2][outline=#000][size=1.5]x[/size][/outline][/size]");
+        assert_eq!(
+            spans,
+            vec![TextSpan::new("x")
+                .with_outline_color(Color::from_hex("#000").unwrap())
+                .with_scale(3.0)]
+        );
+    }
+
+    #[test]
+    fn malformed_markup_is_kept_as_text() {
+        for markup in [
+            "[[x] [bogus]y[/color]",
+            "[color=#zz]z [size=-1]w",
+            "unclosed [color=#fff",
+        ] {
+            let expected = markup.replacen("[[", "[", 1);
+            assert_eq!(parse_markup(markup), vec![TextSpan::new(&expected)]);
+        }
+        assert_eq!(parse_markup(""), Vec::new());
+    }
+
+    #[test]
+    fn lines_wrap_at_the_last_space() {
+        let layout = layout(
+            "hello world",
+            TextLayoutOptions::new(10.0).with_max_width(60.0),
+        );
+        assert_eq!(layout.lines().len(), 2);
+        assert_eq!(line_text(&layout, 0), "hello ");
+        assert_eq!(line_text(&layout, 1), "world");
+        // The trailing space doesn't count towards the width.
+        assert_eq!(layout.lines()[0].bounds, Rect::new(0.0, 0.0, 50.0, 12.0));
+        assert_eq!(layout.glyphs()[6].position, vec2(0.0, 20.0));
+        assert_eq!(layout.size(), vec2(50.0, 24.0));
+    }
+
+    #[test]
+    fn words_longer_than_a_line_are_split() {
+        let layout = layout(
+            "abcdefgh",
+            TextLayoutOptions::new(10.0).with_max_width(30.0),
+        );
+        let lines: Vec<String> = (0..layout.lines().len())
+            .map(|line| line_text(&layout, line))
+            .collect();
+        assert_eq!(lines, vec!["abc", "def", "gh"]);
+    }
+
+    #[test]
+    fn lines_are_aligned_within_the_widest() {
+        let centered = layout(
+            "ab\nabcd",
+            TextLayoutOptions::new(10.0).with_align(TextAlign::Center),
+        );
+        assert_eq!(centered.lines()[0].bounds.x, 10.0);
+        assert_eq!(centered.lines()[1].bounds.x, 0.0);
+
+        let right = layout(
+            "ab\nabcd",
+            TextLayoutOptions::new(10.0)
+                .with_align(TextAlign::Right)
+                .with_max_width(100.0),
+        );
+        assert_eq!(right.lines()[0].bounds.x, 80.0);
+        assert_eq!(right.bounds(), Rect::new(60.0, 0.0, 40.0, 24.0));
+    }
+
+    #[test]
+    fn larger_spans_make_taller_lines() {
+        let layout = layout(
+            "[size=2]A[/size]b\nc",
+            TextLayoutOptions::new(10.0).with_line_spacing(1.5),
+        );
+        let lines = layout.lines();
+        assert_eq!((lines[0].baseline, lines[0].bounds.height), (16.0, 36.0));
+        assert_eq!((lines[1].baseline, lines[1].bounds.height), (44.0, 18.0));
+        assert_eq!(layout.glyphs()[0].size, 20.0);
+        assert_eq!(layout.glyphs()[1].position, vec2(20.0, 16.0));
+        assert_eq!(layout.size(), vec2(30.0, 54.0));
+    }
+
+    #[test]
+    fn kerning_and_missing_glyphs() {
+        let layout = layout("AV\tx", TextLayoutOptions::new(20.0));
+        let positions: Vec<(char, f32)> = layout
+            .glyphs()
+            .iter()
+            .map(|glyph| (glyph.character, glyph.position.x))
+            .collect();
+        assert_eq!(positions, vec![('A', 0.0), ('V', 18.0), ('x', 38.0)]);
+    }
+}
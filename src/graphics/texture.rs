@@ -0,0 +1,675 @@
+use std::cell::Cell;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use gl::types::*;
+
+use super::gl_wrapper::is_gles;
+use super::gpu_info::GpuInfo;
+use super::gpu_resources::{self, GpuObject, ShareGroup};
+use crate::memory::{self, MemoryCategory};
+
+// From EXT_texture_filter_anisotropic, which the 4.5 core bindings don't
+// include.
+const TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FF;
+
+thread_local! {
+    // Textures are created on the thread owning the GL context, so the
+    // defaults live there too.
+    static DEFAULT_SETTINGS: Cell<TextureSettings> = const { Cell::new(TextureSettings::new()) };
+    static MAX_ANISOTROPY: Cell<Option<f32>> = const { Cell::new(None) };
+}
+
+/// # Texture Settings
+///
+/// How textures are sampled: anisotropic filtering, trilinear filtering between
+/// mip levels, and a bias towards sharper or blurrier mip levels. The global
+/// default is applied to every texture when it is created and can be
+/// overridden per texture with [`Texture::set_settings`].
+///
+/// ## Example
+/// ```ignore
+/// let max = TextureSettings::max_anisotropy();
+/// TextureSettings::set_default(TextureSettings::new().with_anisotropy(max.min(8.0)));
+///
+/// // Pixel art stays crisp.
+/// sprite_sheet.set_settings(TextureSettings::new().with_trilinear(false));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureSettings {
+    /// Anisotropic filtering level; `1` turns it off. Clamped to
+    /// [`TextureSettings::max_anisotropy`].
+    pub anisotropy: f32,
+    /// Blends between mip levels instead of picking the nearest one.
+    pub trilinear: bool,
+    /// Added to the mip level; negative values sharpen, positive values blur.
+    /// Ignored on OpenGL ES, which has no texture-wide bias.
+    pub mip_bias: f32,
+}
+
+impl TextureSettings {
+    /// Creates settings with trilinear filtering and no anisotropy.
+    pub const fn new() -> Self {
+        Self {
+            anisotropy: 1.0,
+            trilinear: true,
+            mip_bias: 0.0,
+        }
+    }
+
+    /// Sets the anisotropic filtering level.
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    /// Sets whether mip levels are blended.
+    pub fn with_trilinear(mut self, trilinear: bool) -> Self {
+        self.trilinear = trilinear;
+        self
+    }
+
+    /// Sets the mip level bias.
+    pub fn with_mip_bias(mut self, mip_bias: f32) -> Self {
+        self.mip_bias = mip_bias;
+        self
+    }
+
+    /// Returns the settings new textures are created with.
+    pub fn default_settings() -> Self {
+        DEFAULT_SETTINGS.with(Cell::get)
+    }
+
+    /// Sets the settings new textures are created with. Existing textures keep
+    /// theirs.
+    pub fn set_default(settings: TextureSettings) {
+        DEFAULT_SETTINGS.with(|default| default.set(settings));
+    }
+
+    /// Returns the highest anisotropic filtering level the hardware supports,
+    /// or `1` if it doesn't support anisotropic filtering.
+    pub fn max_anisotropy() -> f32 {
+        MAX_ANISOTROPY.with(|cached| {
+            if let Some(max) = cached.get() {
+                return max;
+            }
+            let gpu = GpuInfo::query();
+            let supported = gpu.has_extension("GL_EXT_texture_filter_anisotropic")
+                || gpu.has_extension("GL_ARB_texture_filter_anisotropic");
+            let mut max = 1.0;
+            if supported {
+                unsafe { gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max) };
+            }
+            let max = max.max(1.0);
+            cached.set(Some(max));
+            max
+        })
+    }
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Texture
+///
+/// An OpenGL texture object. The texture is deleted when dropped.
+///
+/// Its estimated memory counts towards [`MemoryCategory::Textures`].
+pub struct Texture {
+    id: GLuint,
+    target: GLenum,
+    width: u32,
+    height: u32,
+    settings: TextureSettings,
+    /// Bytes of one pixel across every layer or face.
+    pixel_bytes: usize,
+    /// Bit mask of the mip levels holding memory.
+    resident_levels: AtomicU32,
+    memory_bytes: AtomicUsize,
+    group: ShareGroup,
+}
+
+impl Texture {
+    /// Creates a 2D texture, optionally uploading initial pixel data.
+    pub fn new_2d(
+        width: u32,
+        height: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+        data: Option<&[u8]>,
+    ) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                format,
+                data_type,
+                data.map_or(ptr::null(), |d| d.as_ptr() as *const c_void),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        let mut texture = Self::tracked(
+            id,
+            gl::TEXTURE_2D,
+            width,
+            height,
+            bytes_per_pixel(internal_format),
+        );
+        texture.set_resident_levels(1);
+        texture.set_settings(TextureSettings::default_settings());
+        texture
+    }
+
+    /// Creates a 2D texture of `width` by `height` pixels holding only the
+    /// mip levels from `first_level` on, one slice of pixel data per level.
+    /// The missing detailed levels take no memory and can be uploaded later
+    /// with [`Texture::set_mip_level`].
+    pub fn new_2d_mips(
+        width: u32,
+        height: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+        first_level: u32,
+        levels: &[&[u8]],
+    ) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        let mut texture = Self::tracked(
+            id,
+            gl::TEXTURE_2D,
+            width,
+            height,
+            bytes_per_pixel(internal_format),
+        );
+        for (index, data) in levels.iter().enumerate() {
+            texture.set_mip_level(
+                first_level + index as u32,
+                internal_format,
+                format,
+                data_type,
+                Some(data),
+            );
+        }
+        let last_level = first_level + (levels.len() as u32).max(1) - 1;
+        texture.set_mip_range(first_level, last_level);
+        texture.apply_mip_filter();
+        texture.set_settings(TextureSettings::default_settings());
+        texture
+    }
+
+    /// Creates an empty 2D array texture of `layers` layers, each `width` by
+    /// `height` pixels, sampled as `sampler2DArray`.
+    pub fn new_2d_array(
+        width: u32,
+        height: u32,
+        layers: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+    ) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, id);
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                internal_format as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                layers as GLsizei,
+                0,
+                format,
+                data_type,
+                ptr::null(),
+            );
+            for (parameter, value) in [
+                (gl::TEXTURE_MIN_FILTER, gl::LINEAR),
+                (gl::TEXTURE_MAG_FILTER, gl::LINEAR),
+                (gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE),
+            ] {
+                gl::TexParameteri(gl::TEXTURE_2D_ARRAY, parameter, value as GLint);
+            }
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, 0);
+        }
+        let mut texture = Self::tracked(
+            id,
+            gl::TEXTURE_2D_ARRAY,
+            width,
+            height,
+            bytes_per_pixel(internal_format) * layers as usize,
+        );
+        texture.set_resident_levels(1);
+        texture.set_settings(TextureSettings::default_settings());
+        texture
+    }
+
+    /// Creates an empty cube map with square faces of `size` pixels.
+    pub fn new_cube(size: u32, internal_format: GLenum, format: GLenum, data_type: GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+            for face in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                    0,
+                    internal_format as GLint,
+                    size as GLsizei,
+                    size as GLsizei,
+                    0,
+                    format,
+                    data_type,
+                    ptr::null(),
+                );
+            }
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAG_FILTER,
+                gl::LINEAR as GLint,
+            );
+            for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, wrap, gl::CLAMP_TO_EDGE as GLint);
+            }
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+        let mut texture = Self::tracked(
+            id,
+            gl::TEXTURE_CUBE_MAP,
+            size,
+            size,
+            bytes_per_pixel(internal_format) * 6,
+        );
+        texture.set_resident_levels(1);
+        texture.set_settings(TextureSettings::default_settings());
+        texture
+    }
+
+    /// Creates an 8-bit RGBA texture from pixel data that is sampled as is,
+    /// such as normal maps, masks or lookup tables.
+    pub fn from_rgba8(width: u32, height: u32, pixels: &[u8]) -> Self {
+        Self::new_2d(
+            width,
+            height,
+            gl::RGBA8,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            Some(pixels),
+        )
+    }
+
+    /// Creates a texture from 8-bit sRGB-encoded RGBA pixel data, such as color
+    /// art. Sampling returns linear values.
+    pub fn from_srgba8(width: u32, height: u32, pixels: &[u8]) -> Self {
+        Self::new_2d(
+            width,
+            height,
+            gl::SRGB8_ALPHA8,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            Some(pixels),
+        )
+    }
+
+    fn tracked(id: GLuint, target: GLenum, width: u32, height: u32, pixel_bytes: usize) -> Self {
+        memory::allocate(MemoryCategory::Textures, 0);
+        Self {
+            id,
+            target,
+            width,
+            height,
+            settings: TextureSettings::new(),
+            pixel_bytes,
+            resident_levels: AtomicU32::new(0),
+            memory_bytes: AtomicUsize::new(0),
+            group: ShareGroup::current(),
+        }
+    }
+
+    /// Returns the OpenGL id.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns the texture target, such as `gl::TEXTURE_2D`.
+    pub fn target(&self) -> GLenum {
+        self.target
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the estimated video memory of the resident mip levels in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.memory_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Binds the texture to a texture unit.
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target, self.id);
+        }
+    }
+
+    /// Unbinds any texture of this target from a texture unit.
+    pub fn unbind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target, 0);
+        }
+    }
+
+    /// Replaces the storage of a 2D texture with new pixel data, resizing it if needed.
+    pub fn set_data(
+        &mut self,
+        width: u32,
+        height: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+        data: &[u8],
+    ) {
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                self.target,
+                0,
+                internal_format as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                format,
+                data_type,
+                data.as_ptr() as *const c_void,
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+        self.width = width;
+        self.height = height;
+        self.pixel_bytes = bytes_per_pixel(internal_format);
+        self.set_resident_levels(1);
+    }
+
+    /// Uploads pixel data into a region of one layer of a 2D array texture.
+    pub fn set_layer_data(
+        &self,
+        layer: u32,
+        region: (u32, u32, u32, u32),
+        format: GLenum,
+        data_type: GLenum,
+        data: &[u8],
+    ) {
+        let (x, y, width, height) = region;
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage3D(
+                self.target,
+                0,
+                x as GLint,
+                y as GLint,
+                layer as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                1,
+                format,
+                data_type,
+                data.as_ptr() as *const c_void,
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    /// Uploads one mip level of a 2D texture, sized from the full size, or
+    /// frees its memory if `data` is `None`.
+    pub fn set_mip_level(
+        &self,
+        level: u32,
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+        data: Option<&[u8]>,
+    ) {
+        let (width, height) = match data {
+            Some(_) => mip_size(self.width, self.height, level),
+            None => (0, 0),
+        };
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                self.target,
+                level as GLint,
+                internal_format as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                format,
+                data_type,
+                data.map_or(ptr::null(), |d| d.as_ptr() as *const c_void),
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+        let levels = self.resident_levels.load(Ordering::Relaxed);
+        self.set_resident_levels(match data {
+            Some(_) => levels | 1 << level,
+            None => levels & !(1 << level),
+        });
+    }
+
+    /// Limits sampling to the mip levels from `base` to `max`, so levels
+    /// outside the range may be missing.
+    pub fn set_mip_range(&self, base: u32, max: u32) {
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::TexParameteri(self.target, gl::TEXTURE_BASE_LEVEL, base as GLint);
+            gl::TexParameteri(self.target, gl::TEXTURE_MAX_LEVEL, max as GLint);
+        }
+    }
+
+    /// Reads the base level of a 2D texture back as 8-bit RGBA pixels, in the
+    /// same row order they were uploaded in.
+    pub fn read_rgba8(&self) -> Vec<u8> {
+        let mut pixels = vec![0; (self.width * self.height * 4) as usize];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            if is_gles() {
+                // OpenGL ES can't read textures directly, only framebuffers.
+                let mut framebuffer = 0;
+                gl::GenFramebuffers(1, &mut framebuffer);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    self.target,
+                    self.id,
+                    0,
+                );
+                gl::ReadPixels(
+                    0,
+                    0,
+                    self.width as GLsizei,
+                    self.height as GLsizei,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as *mut c_void,
+                );
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                gl::DeleteFramebuffers(1, &framebuffer);
+            } else {
+                gl::BindTexture(self.target, self.id);
+                gl::GetTexImage(
+                    self.target,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as *mut c_void,
+                );
+            }
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+        }
+        pixels
+    }
+
+    /// Sets the minification and magnification filters.
+    pub fn set_filter(&self, min_filter: GLenum, mag_filter: GLenum) {
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::TexParameteri(self.target, gl::TEXTURE_MIN_FILTER, min_filter as GLint);
+            gl::TexParameteri(self.target, gl::TEXTURE_MAG_FILTER, mag_filter as GLint);
+        }
+    }
+
+    /// Sets the wrapping mode on both axes.
+    pub fn set_wrap(&self, wrap: GLenum) {
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::TexParameteri(self.target, gl::TEXTURE_WRAP_S, wrap as GLint);
+            gl::TexParameteri(self.target, gl::TEXTURE_WRAP_T, wrap as GLint);
+        }
+    }
+
+    /// Generates the mipmap chain and switches to mipmapped filtering, blending
+    /// between levels if the texture's settings ask for trilinear filtering.
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::GenerateMipmap(self.target);
+        }
+        self.set_resident_levels(u32::MAX >> (32 - mip_level_count(self.width, self.height)));
+        self.apply_mip_filter();
+    }
+
+    /// Returns the sampling settings.
+    pub fn settings(&self) -> TextureSettings {
+        self.settings
+    }
+
+    /// Overrides the sampling settings for this texture.
+    pub fn set_settings(&mut self, settings: TextureSettings) {
+        self.settings = settings;
+        let anisotropy = settings
+            .anisotropy
+            .clamp(1.0, TextureSettings::max_anisotropy());
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            if TextureSettings::max_anisotropy() > 1.0 {
+                gl::TexParameterf(self.target, TEXTURE_MAX_ANISOTROPY_EXT, anisotropy);
+            }
+            if !is_gles() {
+                gl::TexParameterf(self.target, gl::TEXTURE_LOD_BIAS, settings.mip_bias);
+            }
+
+            let mut min_filter = 0;
+            gl::GetTexParameteriv(self.target, gl::TEXTURE_MIN_FILTER, &mut min_filter);
+            let mipmapped = [gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR_MIPMAP_NEAREST]
+                .contains(&(min_filter as GLenum));
+            if mipmapped {
+                self.apply_mip_filter();
+            }
+        }
+    }
+
+    fn apply_mip_filter(&self) {
+        let filter = if self.settings.trilinear {
+            gl::LINEAR_MIPMAP_LINEAR
+        } else {
+            gl::LINEAR_MIPMAP_NEAREST
+        };
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+            gl::TexParameteri(self.target, gl::TEXTURE_MIN_FILTER, filter as GLint);
+        }
+    }
+
+    /// Records which mip levels hold memory and updates the tracked size.
+    fn set_resident_levels(&self, levels: u32) {
+        self.resident_levels.store(levels, Ordering::Relaxed);
+        let bytes = (0..32)
+            .filter(|level| levels & 1 << level != 0)
+            .map(|level| {
+                let (width, height) = mip_size(self.width, self.height, level);
+                width as usize * height as usize * self.pixel_bytes
+            })
+            .sum();
+        let old_bytes = self.memory_bytes.swap(bytes, Ordering::Relaxed);
+        memory::resize(MemoryCategory::Textures, old_bytes, bytes);
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        memory::free(MemoryCategory::Textures, self.memory_bytes());
+        gpu_resources::release(self.group, GpuObject::Texture(self.id));
+    }
+}
+
+/// Returns the number of mip levels of a full chain down to 1x1.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Returns the size of a mip level.
+pub fn mip_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Returns the estimated bytes per pixel of an internal format, such as `4`
+/// for `gl::RGBA8`. Drivers pad three-channel formats, so they count as four.
+pub fn bytes_per_pixel(internal_format: GLenum) -> usize {
+    match internal_format {
+        gl::R8 | gl::RED | gl::STENCIL_INDEX8 => 1,
+        gl::RG8 | gl::R16F | gl::DEPTH_COMPONENT16 => 2,
+        gl::RGBA16F | gl::RGB16F | gl::RG32F | gl::RG32UI => 8,
+        gl::RGBA32F | gl::RGB32F => 16,
+        _ => 4,
+    }
+}
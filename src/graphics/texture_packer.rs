@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::image::Image;
+use super::texture::Texture;
+use crate::custom_errors::Errors;
+use crate::math::*;
+use crate::name::Name;
+
+/// # Packed Texture
+///
+/// A region of a texture to draw: one layer of a texture array built by a
+/// [`TexturePacker`], or a whole plain texture. Renderers batch everything
+/// sharing a texture, so sprites packed into the same array draw together
+/// whichever image they show.
+///
+/// ## Example
+/// ```ignore
+/// let coin = atlas.get("assets/sprites/coin.png").unwrap();
+/// batch.draw_packed(coin, Rect::new(10.0, 10.0, 32.0, 32.0), Color::WHITE);
+/// ```
+#[derive(Clone)]
+pub struct PackedTexture {
+    pub texture: Arc<Texture>,
+    /// The array layer, always 0 for plain textures.
+    pub layer: u32,
+    /// Region of the layer in UV coordinates.
+    pub uv_rect: Rect,
+    /// Size of the region in pixels.
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PackedTexture {
+    /// Creates a packed texture showing the whole of a plain texture.
+    pub fn from_texture(texture: Arc<Texture>) -> Self {
+        Self {
+            width: texture.width(),
+            height: texture.height(),
+            texture,
+            layer: 0,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+        }
+    }
+
+    /// Returns a sub-region in UV coordinates relative to this region, such
+    /// as one frame of a sprite sheet.
+    pub fn sub_rect(&self, uv_rect: Rect) -> Rect {
+        Rect::new(
+            self.uv_rect.x + uv_rect.x * self.uv_rect.width,
+            self.uv_rect.y + uv_rect.y * self.uv_rect.height,
+            uv_rect.width * self.uv_rect.width,
+            uv_rect.height * self.uv_rect.height,
+        )
+    }
+}
+
+impl fmt::Debug for PackedTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PackedTexture")
+            .field("texture", &self.texture.id())
+            .field("layer", &self.layer)
+            .field("uv_rect", &self.uv_rect)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+/// Where an image goes in the array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Placement {
+    layer: u32,
+    x: u32,
+    y: u32,
+}
+
+/// A row of a page holding images up to its height.
+struct Shelf {
+    layer: u32,
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+/// # Texture Packer
+///
+/// Packs many small images into the layers of one texture array at load
+/// time, so sprites using any of them can be drawn in a single batch instead
+/// of one per texture. Images bigger than [`TexturePacker::max_packed_size`]
+/// would waste too much of a page and get textures of their own instead, so
+/// every image can go through the packer.
+///
+/// Edge pixels are repeated into the padding around each image, so filtering
+/// never blends in a neighbour.
+///
+/// ## Example
+/// ```ignore
+/// let mut packer = TexturePacker::new();
+/// packer.add_files(&["assets/sprites/player.png", "assets/sprites/coin.png", "assets/sprites/tree.png"])?;
+/// let atlas = packer.build();
+///
+/// let player = atlas.get("assets/sprites/player.png").unwrap();
+/// ```
+pub struct TexturePacker {
+    /// Width and height of every array layer in pixels.
+    pub page_size: u32,
+    /// Pixels of repeated edge around every image.
+    pub padding: u32,
+    /// Images wider or taller than this get their own texture.
+    pub max_packed_size: u32,
+    /// Whether images are sRGB color art rather than data.
+    pub srgb: bool,
+    images: Vec<(String, Image)>,
+}
+
+impl TexturePacker {
+    /// Creates a packer of sRGB images into 2048 pixel layers, packing images
+    /// of up to 512 pixels with 2 pixels of padding.
+    pub fn new() -> Self {
+        Self {
+            page_size: 2048,
+            padding: 2,
+            max_packed_size: 512,
+            srgb: true,
+            images: Vec::new(),
+        }
+    }
+
+    /// Sets the size of the array layers.
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the padding around every image.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the size above which images get their own texture.
+    pub fn with_max_packed_size(mut self, max_packed_size: u32) -> Self {
+        self.max_packed_size = max_packed_size;
+        self
+    }
+
+    /// Sets whether images are sRGB color art rather than data.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Adds an image under a name. A later image of the same name replaces it.
+    pub fn add(&mut self, name: &str, image: Image) {
+        self.images.retain(|(existing, _)| existing != name);
+        self.images.push((name.to_string(), image));
+    }
+
+    /// Adds a PNG file, named by its path.
+    pub fn add_file(&mut self, path: &str) -> Result<(), Errors> {
+        let image = Image::load(path)?;
+        self.add(path, image);
+        Ok(())
+    }
+
+    /// Adds several PNG files, named by their paths, decoding them in parallel.
+    /// Nothing is added if any of them fails to load.
+    pub fn add_files(&mut self, paths: &[&str]) -> Result<(), Errors> {
+        let images = Image::load_all(paths)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        for (path, image) in paths.iter().zip(images) {
+            self.add(path, image);
+        }
+        Ok(())
+    }
+
+    /// Returns the number of images added.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Checks if no images were added.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Checks if an image is small enough to be packed.
+    pub fn fits(&self, image: &Image) -> bool {
+        let limit = self
+            .max_packed_size
+            .min(self.page_size.saturating_sub(self.padding * 2));
+        image.width > 0 && image.height > 0 && image.width.max(image.height) <= limit
+    }
+
+    /// Packs the images and uploads them, returning the packed textures by
+    /// name.
+    pub fn build(self) -> TextureAtlas {
+        let (placements, layers) = self.pack();
+        let size = self.page_size;
+        let mut pages = vec![vec![0u8; (size * size * 4) as usize]; layers as usize];
+        for ((_, image), placement) in self.images.iter().zip(&placements) {
+            if let Some(placement) = placement {
+                blit_padded(
+                    &mut pages[placement.layer as usize],
+                    size,
+                    image,
+                    placement,
+                    self.padding,
+                );
+            }
+        }
+
+        let (internal_format, format, data_type) = if self.srgb {
+            (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE)
+        } else {
+            (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE)
+        };
+        let array = (layers > 0).then(|| {
+            let array =
+                Texture::new_2d_array(size, size, layers, internal_format, format, data_type);
+            for (layer, page) in pages.iter().enumerate() {
+                array.set_layer_data(layer as u32, (0, 0, size, size), format, data_type, page);
+            }
+            Arc::new(array)
+        });
+
+        let mut regions = HashMap::new();
+        for ((name, image), placement) in self.images.into_iter().zip(placements) {
+            let packed = match (placement, &array) {
+                (Some(placement), Some(array)) => PackedTexture {
+                    texture: Arc::clone(array),
+                    layer: placement.layer,
+                    uv_rect: Rect::new(
+                        (placement.x + self.padding) as f32 / size as f32,
+                        (placement.y + self.padding) as f32 / size as f32,
+                        image.width as f32 / size as f32,
+                        image.height as f32 / size as f32,
+                    ),
+                    width: image.width,
+                    height: image.height,
+                },
+                _ => {
+                    let texture = if self.srgb {
+                        image.to_texture()
+                    } else {
+                        image.to_data_texture()
+                    };
+                    PackedTexture::from_texture(Arc::new(texture))
+                }
+            };
+            regions.insert(Name::new(&name), packed);
+        }
+        log::debug!(
+            "Packed {} of {} textures into {} layers",
+            regions
+                .values()
+                .filter(|packed| array
+                    .as_ref()
+                    .is_some_and(|array| Arc::ptr_eq(array, &packed.texture)))
+                .count(),
+            regions.len(),
+            layers
+        );
+        TextureAtlas { array, regions }
+    }
+
+    /// Places the images on shelves, tallest first, returning where each
+    /// went, or `None` for those too big, and the number of layers used.
+    fn pack(&self) -> (Vec<Option<Placement>>, u32) {
+        let mut order: Vec<usize> = (0..self.images.len())
+            .filter(|&index| self.fits(&self.images[index].1))
+            .collect();
+        order.sort_by_key(|&index| {
+            let image = &self.images[index].1;
+            (
+                std::cmp::Reverse(image.height),
+                std::cmp::Reverse(image.width),
+            )
+        });
+
+        let mut placements = vec![None; self.images.len()];
+        let mut shelves: Vec<Shelf> = Vec::new();
+        // Top of the free space on each layer.
+        let mut layer_tops: Vec<u32> = Vec::new();
+        for index in order {
+            let image = &self.images[index].1;
+            let (width, height) = (
+                image.width + self.padding * 2,
+                image.height + self.padding * 2,
+            );
+
+            // The shelf wasting the least height, else a new shelf.
+            let fitting = shelves
+                .iter()
+                .enumerate()
+                .filter(|(_, shelf)| shelf.height >= height && shelf.x + width <= self.page_size)
+                .min_by_key(|(_, shelf)| shelf.height - height)
+                .map(|(index, _)| index);
+            let shelf = match fitting {
+                Some(shelf) => shelf,
+                None => {
+                    let top = layer_tops
+                        .iter()
+                        .position(|top| top + height <= self.page_size);
+                    let layer = top.unwrap_or_else(|| {
+                        layer_tops.push(0);
+                        layer_tops.len() - 1
+                    });
+                    shelves.push(Shelf {
+                        layer: layer as u32,
+                        y: layer_tops[layer],
+                        height,
+                        x: 0,
+                    });
+                    layer_tops[layer] += height;
+                    shelves.len() - 1
+                }
+            };
+            let shelf = &mut shelves[shelf];
+            placements[index] = Some(Placement {
+                layer: shelf.layer,
+                x: shelf.x,
+                y: shelf.y,
+            });
+            shelf.x += width;
+        }
+        (placements, layer_tops.len() as u32)
+    }
+}
+
+impl Default for TexturePacker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Texture Atlas
+///
+/// The images of a [`TexturePacker`], looked up by name.
+pub struct TextureAtlas {
+    array: Option<Arc<Texture>>,
+    regions: HashMap<Name, PackedTexture>,
+}
+
+impl TextureAtlas {
+    /// Returns a packed image by name.
+    pub fn get(&self, name: impl Into<Name>) -> Option<&PackedTexture> {
+        self.regions.get(&name.into())
+    }
+
+    /// Returns the texture array packed images are in, if any were packed.
+    pub fn array(&self) -> Option<&Arc<Texture>> {
+        self.array.as_ref()
+    }
+
+    /// Iterates over the images and their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PackedTexture)> {
+        self.regions
+            .iter()
+            .map(|(name, packed)| (name.as_str(), packed))
+    }
+
+    /// Returns the number of images.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Checks if there are no images.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+/// Copies an image into a page at a placement, repeating its edge pixels
+/// into the padding around it.
+fn blit_padded(
+    page: &mut [u8],
+    page_size: u32,
+    image: &Image,
+    placement: &Placement,
+    padding: u32,
+) {
+    let (width, height) = (image.width + padding * 2, image.height + padding * 2);
+    for y in 0..height {
+        let source_y = y.saturating_sub(padding).min(image.height - 1);
+        for x in 0..width {
+            let source_x = x.saturating_sub(padding).min(image.width - 1);
+            let source = ((source_y * image.width + source_x) * 4) as usize;
+            let target = (((placement.y + y) * page_size + placement.x + x) * 4) as usize;
+            page[target..target + 4].copy_from_slice(&image.pixels[source..source + 4]);
+        }
+    }
+}
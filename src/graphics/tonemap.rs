@@ -0,0 +1,404 @@
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::texture::Texture;
+
+use crate::name;
+
+/// Size of the luminance buffer the scene is downsampled into for auto-exposure.
+const LUMINANCE_SIZE: u32 = 256;
+
+/// Scene luminance that auto-exposure maps to middle gray.
+const MIDDLE_GRAY: f32 = 0.18;
+
+const LUMINANCE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out float out_luminance;
+
+uniform sampler2D u_scene;
+
+void main() {
+    vec3 color = texture(u_scene, v_uv).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    out_luminance = log(max(luminance, 0.0001));
+}
+"#;
+
+const ADAPTATION_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+out float out_exposure;
+
+uniform sampler2D u_luminance;
+uniform sampler2D u_previous;
+uniform float u_max_level;
+uniform float u_key;
+uniform vec2 u_range;
+uniform vec2 u_speed;
+uniform float u_delta_time;
+uniform int u_reset;
+
+void main() {
+    float average = exp(textureLod(u_luminance, vec2(0.5), u_max_level).r);
+    float target = clamp(u_key / max(average, 0.0001), u_range.x, u_range.y);
+    if (u_reset == 1) {
+        out_exposure = target;
+        return;
+    }
+    float previous = texture(u_previous, vec2(0.5)).r;
+    // Eyes adapt faster to brightness than to darkness.
+    float speed = target < previous ? u_speed.x : u_speed.y;
+    out_exposure = previous + (target - previous) * (1.0 - exp(-u_delta_time * speed));
+}
+"#;
+
+const TONEMAP_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_scene;
+uniform sampler2D u_exposure;
+uniform int u_auto_exposure;
+uniform float u_manual_exposure;
+uniform int u_tonemapper;
+
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 hable(vec3 x) {
+    const float a = 0.15, b = 0.50, c = 0.10, d = 0.20, e = 0.02, f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+void main() {
+    vec4 scene = texture(u_scene, v_uv);
+    float exposure = u_auto_exposure == 1 ? texture(u_exposure, vec2(0.5)).r : u_manual_exposure;
+    vec3 color = scene.rgb * exposure;
+
+    if (u_tonemapper == 1) {
+        color = color / (1.0 + color);
+    } else if (u_tonemapper == 2) {
+        color = aces(color);
+    } else if (u_tonemapper == 3) {
+        const float white = 11.2;
+        color = hable(color * 2.0) / hable(vec3(white));
+    }
+    out_color = vec4(clamp(color, 0.0, 1.0), scene.a);
+}
+"#;
+
+/// The curve that compresses HDR colors into the displayable range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Tonemapper {
+    /// Clips everything above 1.
+    Linear,
+    /// Simple and soft, but washes out bright colors.
+    Reinhard,
+    /// The ACES filmic fit: contrasty with saturated highlights.
+    #[default]
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2: gentle toe and shoulder.
+    Filmic,
+}
+
+impl Tonemapper {
+    fn index(self) -> i32 {
+        match self {
+            Tonemapper::Linear => 0,
+            Tonemapper::Reinhard => 1,
+            Tonemapper::Aces => 2,
+            Tonemapper::Filmic => 3,
+        }
+    }
+}
+
+/// How bright the scene is made before tone mapping, in stops (EV).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exposure {
+    /// A fixed exposure; `0` leaves the scene as is and each step doubles or
+    /// halves it.
+    Manual(f32),
+    /// Eye adaptation: exposure follows the scene's average brightness.
+    Auto {
+        /// Stops added on top of the measured exposure.
+        compensation: f32,
+        /// Darkest exposure allowed, for very bright scenes.
+        min: f32,
+        /// Brightest exposure allowed, for very dark scenes.
+        max: f32,
+        /// How quickly the eye adapts when the scene gets brighter, per second.
+        speed_up: f32,
+        /// How quickly the eye adapts when the scene gets darker, per second.
+        speed_down: f32,
+    },
+}
+
+impl Exposure {
+    /// Creates eye adaptation that adapts within about a second and covers
+    /// moonlit nights to bright sunlight.
+    pub fn auto() -> Self {
+        Exposure::Auto {
+            compensation: 0.0,
+            min: -8.0,
+            max: 8.0,
+            speed_up: 3.0,
+            speed_down: 1.0,
+        }
+    }
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Exposure::Manual(0.0)
+    }
+}
+
+/// # Tone Mapping
+///
+/// How a camera turns its HDR render into displayable colors: an exposure,
+/// fixed or adapting to the scene, and a tonemapper curve. Set on a camera
+/// with [`Camera::with_tone_mapping`](super::camera::Camera::with_tone_mapping)
+/// and applied by a [`ToneMappingPass`].
+///
+/// ## Example
+/// ```ignore
+/// let camera = Camera::perspective(Deg(60.0), 0.1, 1000.0)
+///     .with_target(RenderTarget::Framebuffer(hdr.clone()))
+///     .with_tone_mapping(ToneMapping::new(Tonemapper::Filmic).with_exposure(Exposure::auto()));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ToneMapping {
+    pub tonemapper: Tonemapper,
+    pub exposure: Exposure,
+}
+
+impl ToneMapping {
+    /// Creates tone mapping with a tonemapper and no exposure change.
+    pub fn new(tonemapper: Tonemapper) -> Self {
+        Self {
+            tonemapper,
+            exposure: Exposure::default(),
+        }
+    }
+
+    /// Sets the exposure.
+    pub fn with_exposure(mut self, exposure: Exposure) -> Self {
+        self.exposure = exposure;
+        self
+    }
+}
+
+/// # Tone Mapping Pass
+///
+/// Resolves an HDR render, such as an RGBA16F [`Framebuffer::hdr`], into a
+/// displayable image. With auto-exposure, the scene's average luminance is
+/// measured on the GPU every frame and the exposure eases towards it, so keep
+/// one pass per camera to keep each camera's adaptation separate.
+///
+/// ## Example
+/// ```ignore
+/// let hdr = Arc::new(Framebuffer::hdr(width, height));
+/// let mut tone_mapping = ToneMappingPass::new();
+///
+/// renderer.render_cameras(cameras, window_size, |view| draw_scene(view));
+/// if let Some(settings) = &camera.tone_mapping {
+///     tone_mapping.apply(settings, hdr.color_texture(), None, delta_time);
+/// }
+/// ```
+pub struct ToneMappingPass {
+    luminance_shader: ShaderProgram,
+    adaptation_shader: ShaderProgram,
+    tonemap_shader: ShaderProgram,
+    luminance: Framebuffer,
+    adaptation: [Framebuffer; 2],
+    current: usize,
+    adapted: bool,
+    triangle: Primitive,
+}
+
+impl ToneMappingPass {
+    /// Creates the pass and its luminance buffers.
+    pub fn new() -> Self {
+        let mut luminance_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, LUMINANCE_FRAGMENT_SHADER);
+        luminance_shader.create_uniform("u_scene");
+
+        let mut adaptation_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, ADAPTATION_FRAGMENT_SHADER);
+        for uniform in [
+            "u_luminance",
+            "u_previous",
+            "u_max_level",
+            "u_key",
+            "u_range",
+            "u_speed",
+            "u_delta_time",
+            "u_reset",
+        ] {
+            adaptation_shader.create_uniform(uniform);
+        }
+
+        let mut tonemap_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, TONEMAP_FRAGMENT_SHADER);
+        for uniform in [
+            "u_scene",
+            "u_exposure",
+            "u_auto_exposure",
+            "u_manual_exposure",
+            "u_tonemapper",
+        ] {
+            tonemap_shader.create_uniform(uniform);
+        }
+
+        let single_channel =
+            |size: u32| Framebuffer::with_format(size, size, gl::R16F, gl::RED, gl::HALF_FLOAT);
+
+        Self {
+            luminance_shader,
+            adaptation_shader,
+            tonemap_shader,
+            luminance: single_channel(LUMINANCE_SIZE),
+            adaptation: [single_channel(1), single_channel(1)],
+            current: 0,
+            adapted: false,
+            triangle: Primitive::fullscreen_triangle(),
+        }
+    }
+
+    /// Makes auto-exposure jump straight to the scene's brightness on the next
+    /// frame instead of adapting, e.g. after a cut.
+    pub fn reset_adaptation(&mut self) {
+        self.adapted = false;
+    }
+
+    /// Tone maps `scene` into `target`, or into the window if `target` is
+    /// `None`. `delta_time` drives eye adaptation.
+    pub fn apply(
+        &mut self,
+        settings: &ToneMapping,
+        scene: &Texture,
+        target: Option<&Framebuffer>,
+        delta_time: f32,
+    ) {
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        let auto_exposure = match settings.exposure {
+            Exposure::Manual(_) => {
+                self.adapted = false;
+                false
+            }
+            Exposure::Auto {
+                compensation,
+                min,
+                max,
+                speed_up,
+                speed_down,
+            } => {
+                self.adapt(
+                    scene,
+                    compensation,
+                    (min, max),
+                    (speed_up, speed_down),
+                    delta_time,
+                );
+                true
+            }
+        };
+
+        let (width, height) = match target {
+            Some(target) => {
+                target.bind();
+                target.size()
+            }
+            None => {
+                Framebuffer::unbind();
+                (scene.width(), scene.height())
+            }
+        };
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+
+        let manual_exposure = match settings.exposure {
+            Exposure::Manual(ev) => ev.exp2(),
+            Exposure::Auto { .. } => 1.0,
+        };
+        self.tonemap_shader.bind();
+        self.tonemap_shader.set_1i_uniform(name!("u_scene"), 0);
+        self.tonemap_shader.set_1i_uniform(name!("u_exposure"), 1);
+        self.tonemap_shader
+            .set_1i_uniform(name!("u_auto_exposure"), auto_exposure as i32);
+        self.tonemap_shader
+            .set_1f_uniform(name!("u_manual_exposure"), manual_exposure);
+        self.tonemap_shader
+            .set_1i_uniform(name!("u_tonemapper"), settings.tonemapper.index());
+        scene.bind(0);
+        self.adaptation[self.current].color_texture().bind(1);
+        self.triangle.draw();
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+
+    /// Measures the scene's average luminance and eases the exposure towards it.
+    fn adapt(
+        &mut self,
+        scene: &Texture,
+        compensation: f32,
+        range: (f32, f32),
+        speed: (f32, f32),
+        delta_time: f32,
+    ) {
+        self.luminance.bind();
+        unsafe {
+            gl::Viewport(0, 0, LUMINANCE_SIZE as i32, LUMINANCE_SIZE as i32);
+        }
+        self.luminance_shader.bind();
+        self.luminance_shader.set_1i_uniform(name!("u_scene"), 0);
+        scene.bind(0);
+        self.triangle.draw();
+        self.luminance.color_texture().generate_mipmaps();
+
+        let previous = self.current;
+        self.current = 1 - self.current;
+        self.adaptation[self.current].bind();
+        unsafe {
+            gl::Viewport(0, 0, 1, 1);
+        }
+        let max_level = (LUMINANCE_SIZE as f32).log2();
+        self.adaptation_shader.bind();
+        self.adaptation_shader
+            .set_1i_uniform(name!("u_luminance"), 0);
+        self.adaptation_shader
+            .set_1i_uniform(name!("u_previous"), 1);
+        self.adaptation_shader
+            .set_1f_uniform(name!("u_max_level"), max_level);
+        self.adaptation_shader
+            .set_1f_uniform(name!("u_key"), MIDDLE_GRAY * compensation.exp2());
+        self.adaptation_shader
+            .set_2f_uniform(name!("u_range"), range.0.exp2(), range.1.exp2());
+        self.adaptation_shader
+            .set_2f_uniform(name!("u_speed"), speed.0.max(0.0), speed.1.max(0.0));
+        self.adaptation_shader
+            .set_1f_uniform(name!("u_delta_time"), delta_time.max(0.0));
+        self.adaptation_shader
+            .set_1i_uniform(name!("u_reset"), !self.adapted as i32);
+        self.luminance.color_texture().bind(0);
+        self.adaptation[previous].color_texture().bind(1);
+        self.triangle.draw();
+        self.adapted = true;
+    }
+}
+
+impl Default for ToneMappingPass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::HashMap;
+
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::texture::Texture;
+use crate::ecs::{Access, FunctionSystem, System, World};
+use crate::math::*;
+use crate::name;
+use crate::state_machine::{State, StateMachine};
+
+const COPY_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_source;
+
+void main() {
+    out_color = texture(u_source, v_uv);
+}
+"#;
+
+const TRANSITION_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_from;
+uniform sampler2D u_to;
+uniform float u_progress;
+uniform int u_mode;
+uniform vec4 u_color;
+uniform vec2 u_direction;
+uniform float u_softness;
+uniform float u_aspect;
+
+// How far the new screen has been revealed at this pixel, given how far along
+// the reveal front the pixel lies.
+float reveal(float position) {
+    float edge = u_progress * (1.0 + u_softness);
+    return 1.0 - smoothstep(edge - u_softness, edge, position);
+}
+
+void main() {
+    vec4 to = texture(u_to, v_uv);
+    if (u_mode == 0) {
+        out_color = mix(to, vec4(u_color.rgb, 1.0), u_progress * u_color.a);
+        return;
+    }
+
+    vec4 from = texture(u_from, v_uv);
+    float amount = u_progress;
+    if (u_mode == 2) {
+        vec2 offset = v_uv - 0.5;
+        float extent = 0.5 * (abs(u_direction.x) + abs(u_direction.y));
+        amount = reveal(dot(offset, u_direction) / (2.0 * extent) + 0.5);
+    } else if (u_mode == 3) {
+        vec2 offset = (v_uv - 0.5) * vec2(u_aspect, 1.0);
+        amount = reveal(length(offset) / length(vec2(u_aspect, 1.0) * 0.5));
+    }
+    out_color = mix(from, to, amount);
+}
+"#;
+
+/// The direction a wipe's edge travels across the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl WipeDirection {
+    /// Returns the direction in texture space, where +Y is up.
+    fn vector(self) -> Vec2 {
+        match self {
+            WipeDirection::Left => vec2(-1.0, 0.0),
+            WipeDirection::Right => vec2(1.0, 0.0),
+            WipeDirection::Up => vec2(0.0, 1.0),
+            WipeDirection::Down => vec2(0.0, -1.0),
+        }
+    }
+}
+
+/// How one screen gives way to the next.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransitionEffect {
+    /// Fades out to a color, switches, then fades back in.
+    Fade(Color),
+    /// Blends the last frame of the old screen into the new one.
+    Crossfade,
+    /// Sweeps the new screen in over the old one behind a moving edge.
+    Wipe {
+        direction: WipeDirection,
+        softness: f32,
+    },
+    /// Opens the new screen from the center in a growing circle.
+    Iris { softness: f32 },
+    /// A custom fragment shader blending the old screen into the new one. It
+    /// receives `v_uv`, `u_from` and `u_to` samplers and `u_progress` from 0 to 1.
+    Shader(String),
+}
+
+impl TransitionEffect {
+    /// Creates a fade through black.
+    pub fn fade_black() -> Self {
+        TransitionEffect::Fade(Color::BLACK)
+    }
+
+    /// Creates a wipe with a soft edge.
+    pub fn wipe(direction: WipeDirection) -> Self {
+        TransitionEffect::Wipe {
+            direction,
+            softness: 0.05,
+        }
+    }
+
+    /// Creates an iris with a soft edge.
+    pub fn iris() -> Self {
+        TransitionEffect::Iris { softness: 0.05 }
+    }
+
+    /// Checks if the effect covers the screen before switching rather than
+    /// blending a snapshot of the old screen into the new one.
+    pub fn covers(&self) -> bool {
+        matches!(self, TransitionEffect::Fade(_))
+    }
+}
+
+/// Where a [`ScreenTransition`] is in its timeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransitionPhase {
+    Idle,
+    /// Covering the old screen, for effects that cover.
+    Out,
+    /// Waiting one frame for the renderer to snapshot the old screen.
+    Capture,
+    /// Revealing the new screen.
+    In,
+}
+
+/// # Screen Transition
+///
+/// Switches a [`StateMachine`] on the same entity to a new state behind a
+/// transition effect, so changing between menus, levels or cutscenes looks
+/// polished without custom render-target code. The state is switched at the
+/// midpoint, when the screen is fully covered or once the old screen has been
+/// captured; the machine's enter and exit hooks run then as usual.
+///
+/// Draw every frame with [`TransitionRenderer::draw`] after rendering the
+/// scene to a framebuffer.
+///
+/// ## Example
+/// ```ignore
+/// world.insert(game, StateMachine::new(Screen::Title));
+/// world.insert(game, ScreenTransition::<Screen>::new());
+/// schedule.add_system(screen_transition_system::<Screen>());
+///
+/// // Starting a game from the title screen:
+/// world.write::<ScreenTransition<Screen>>().get_mut(game).unwrap().start(
+///     Screen::Playing,
+///     TransitionEffect::fade_black(),
+///     0.8,
+/// );
+///
+/// // Every frame, after drawing the scene into `scene`:
+/// transitions.draw(&transition, scene.color_texture(), None);
+/// ```
+pub struct ScreenTransition<S: State> {
+    effect: TransitionEffect,
+    duration: f32,
+    elapsed: f32,
+    phase: TransitionPhase,
+    target: Option<S>,
+    /// Whether a frame has been drawn since entering the capture phase.
+    captured: bool,
+}
+
+impl<S: State> ScreenTransition<S> {
+    /// Creates an idle transition.
+    pub fn new() -> Self {
+        Self {
+            effect: TransitionEffect::Crossfade,
+            duration: 0.0,
+            elapsed: 0.0,
+            phase: TransitionPhase::Idle,
+            target: None,
+            captured: false,
+        }
+    }
+
+    /// Starts switching to `state` over `duration` seconds. Returns `false`
+    /// and does nothing if a transition is already running.
+    pub fn start(&mut self, state: S, effect: TransitionEffect, duration: f32) -> bool {
+        if self.is_active() {
+            return false;
+        }
+        self.phase = if effect.covers() {
+            TransitionPhase::Out
+        } else {
+            TransitionPhase::Capture
+        };
+        self.effect = effect;
+        self.duration = duration.max(0.0);
+        self.elapsed = 0.0;
+        self.target = Some(state);
+        self.captured = false;
+        true
+    }
+
+    /// Advances the transition and returns the state to switch to once the
+    /// midpoint is reached.
+    pub fn update(&mut self, delta_time: f32) -> Option<S> {
+        match self.phase {
+            TransitionPhase::Idle => None,
+            TransitionPhase::Out => {
+                self.elapsed += delta_time;
+                if self.elapsed < self.half_duration() {
+                    return None;
+                }
+                self.elapsed = 0.0;
+                self.phase = TransitionPhase::In;
+                self.target.take()
+            }
+            TransitionPhase::Capture => {
+                // Switch only once the old screen has been drawn at least
+                // once while capturing, whenever in the frame we started.
+                if !self.captured {
+                    self.captured = true;
+                    return None;
+                }
+                self.elapsed = 0.0;
+                self.phase = TransitionPhase::In;
+                self.target.take()
+            }
+            TransitionPhase::In => {
+                self.elapsed += delta_time;
+                if self.elapsed >= self.in_duration() {
+                    self.phase = TransitionPhase::Idle;
+                }
+                None
+            }
+        }
+    }
+
+    fn half_duration(&self) -> f32 {
+        self.duration * 0.5
+    }
+
+    fn in_duration(&self) -> f32 {
+        if self.effect.covers() {
+            self.half_duration()
+        } else {
+            self.duration
+        }
+    }
+
+    /// Returns the effect of the current or last transition.
+    pub fn effect(&self) -> &TransitionEffect {
+        &self.effect
+    }
+
+    /// Returns the current phase.
+    pub fn phase(&self) -> TransitionPhase {
+        self.phase
+    }
+
+    /// Checks if a transition is running.
+    pub fn is_active(&self) -> bool {
+        self.phase != TransitionPhase::Idle
+    }
+
+    /// Returns the state being switched to, until the switch happens.
+    pub fn target(&self) -> Option<S> {
+        self.target
+    }
+
+    /// Returns how far the effect has progressed, from 0 to 1. For covering
+    /// effects this is how much of the screen is covered, so it rises and
+    /// falls again; otherwise it is how much of the new screen is revealed.
+    pub fn amount(&self) -> f32 {
+        let ratio = |elapsed: f32, duration: f32| {
+            if duration <= 0.0 {
+                1.0
+            } else {
+                (elapsed / duration).clamp(0.0, 1.0)
+            }
+        };
+        match self.phase {
+            TransitionPhase::Idle => 0.0,
+            TransitionPhase::Capture => 0.0,
+            TransitionPhase::Out => ratio(self.elapsed, self.half_duration()),
+            TransitionPhase::In if self.effect.covers() => {
+                1.0 - ratio(self.elapsed, self.in_duration())
+            }
+            TransitionPhase::In => ratio(self.elapsed, self.in_duration()),
+        }
+    }
+}
+
+impl<S: State> Default for ScreenTransition<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns a system that advances every `ScreenTransition<S>` and switches the
+/// entity's `StateMachine<S>` at the midpoint. Add it before the state machine
+/// system so the switch happens in the same frame.
+pub fn screen_transition_system<S: State>() -> impl System {
+    FunctionSystem::new(
+        &format!("screen_transition<{}>", std::any::type_name::<S>()),
+        Access::new()
+            .write::<ScreenTransition<S>>()
+            .write::<StateMachine<S>>(),
+        update_screen_transitions::<S>,
+    )
+}
+
+/// Advances every `ScreenTransition<S>` once.
+pub fn update_screen_transitions<S: State>(world: &World) {
+    let delta_time = world.time().delta();
+    let mut machines = world.write::<StateMachine<S>>();
+    for (entity, transition) in world.write::<ScreenTransition<S>>().iter_mut() {
+        if let Some(state) = transition.update(delta_time) {
+            match machines.get_mut(*entity) {
+                Some(machine) => machine.request(state),
+                None => log::warn!(
+                    "Screen transition on {:?} has no state machine to switch",
+                    entity
+                ),
+            }
+        }
+    }
+}
+
+/// # Transition Renderer
+///
+/// Draws a [`ScreenTransition`] over the rendered scene. While no transition
+/// is running the scene is copied through unchanged, so it can always sit at
+/// the end of the frame.
+///
+/// ## Example
+/// ```ignore
+/// let mut transitions = TransitionRenderer::new();
+///
+/// scene.bind();
+/// renderer.render(&world);
+/// Framebuffer::unbind();
+///
+/// let transition = world.read::<ScreenTransition<Screen>>();
+/// transitions.draw(transition.get(game).unwrap(), scene.color_texture(), None);
+/// ```
+pub struct TransitionRenderer {
+    copy_shader: ShaderProgram,
+    transition_shader: ShaderProgram,
+    custom_shaders: HashMap<String, ShaderProgram>,
+    snapshot: Option<Framebuffer>,
+    triangle: Primitive,
+}
+
+impl TransitionRenderer {
+    /// Creates the transition shaders.
+    pub fn new() -> Self {
+        let mut copy_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, COPY_FRAGMENT_SHADER);
+        copy_shader.create_uniform("u_source");
+        let transition_shader =
+            Self::create_shader(TRANSITION_FRAGMENT_SHADER, &TRANSITION_UNIFORMS);
+
+        Self {
+            copy_shader,
+            transition_shader,
+            custom_shaders: HashMap::new(),
+            snapshot: None,
+            triangle: Primitive::fullscreen_triangle(),
+        }
+    }
+
+    fn create_shader(fragment_shader: &str, uniforms: &[&str]) -> ShaderProgram {
+        let mut shader = ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, fragment_shader);
+        for &uniform in uniforms {
+            shader.create_uniform(uniform);
+        }
+        shader
+    }
+
+    /// Draws `scene` into `target`, or into the window if `target` is `None`,
+    /// with the transition applied. Snapshots the old screen when the
+    /// transition asks for it.
+    pub fn draw<S: State>(
+        &mut self,
+        transition: &ScreenTransition<S>,
+        scene: &Texture,
+        target: Option<&Framebuffer>,
+    ) {
+        let size = (scene.width(), scene.height());
+        if transition.phase() == TransitionPhase::Capture {
+            self.capture(scene, size);
+        }
+
+        let (width, height) = match target {
+            Some(target) => {
+                target.bind();
+                target.size()
+            }
+            None => {
+                Framebuffer::unbind();
+                size
+            }
+        };
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        match (transition.phase(), self.snapshot.as_ref()) {
+            (TransitionPhase::Idle | TransitionPhase::Capture, _) => self.copy(scene),
+            (_, None) if !transition.effect().covers() => self.copy(scene),
+            (_, snapshot) => {
+                let shader = match transition.effect() {
+                    TransitionEffect::Shader(source) => &*self
+                        .custom_shaders
+                        .entry(source.clone())
+                        .or_insert_with(|| {
+                            Self::create_shader(source, &["u_from", "u_to", "u_progress"])
+                        }),
+                    _ => &self.transition_shader,
+                };
+                shader.bind();
+                shader.set_1i_uniform(name!("u_from"), 0);
+                shader.set_1i_uniform(name!("u_to"), 1);
+                shader.set_1f_uniform(name!("u_progress"), transition.amount());
+                set_effect_uniforms(shader, transition.effect(), size);
+                if let Some(snapshot) = snapshot {
+                    snapshot.color_texture().bind(0);
+                }
+                scene.bind(1);
+                self.triangle.draw();
+            }
+        }
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+
+    /// Copies `scene` into the snapshot, resizing it as needed.
+    fn capture(&mut self, scene: &Texture, size: (u32, u32)) {
+        match &mut self.snapshot {
+            Some(snapshot) if snapshot.size() == size => {}
+            Some(snapshot) => snapshot.resize(size.0, size.1),
+            None => self.snapshot = Some(Framebuffer::new(size.0, size.1)),
+        }
+        if let Some(snapshot) = &self.snapshot {
+            snapshot.bind();
+            unsafe {
+                gl::Viewport(0, 0, size.0 as i32, size.1 as i32);
+                gl::Disable(gl::DEPTH_TEST);
+            }
+            self.copy(scene);
+        }
+    }
+
+    fn copy(&self, scene: &Texture) {
+        self.copy_shader.bind();
+        self.copy_shader.set_1i_uniform(name!("u_source"), 0);
+        scene.bind(0);
+        self.triangle.draw();
+    }
+}
+
+impl Default for TransitionRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const TRANSITION_UNIFORMS: [&str; 8] = [
+    "u_from",
+    "u_to",
+    "u_progress",
+    "u_mode",
+    "u_color",
+    "u_direction",
+    "u_softness",
+    "u_aspect",
+];
+
+/// Sets the uniforms the built-in transition shader uses to pick an effect.
+fn set_effect_uniforms(shader: &ShaderProgram, effect: &TransitionEffect, size: (u32, u32)) {
+    let (mode, color, direction, softness) = match effect {
+        TransitionEffect::Fade(color) => (0, *color, vec2(0.0, 0.0), 0.0),
+        TransitionEffect::Crossfade => (1, Color::BLACK, vec2(0.0, 0.0), 0.0),
+        TransitionEffect::Wipe {
+            direction,
+            softness,
+        } => (2, Color::BLACK, direction.vector(), *softness),
+        TransitionEffect::Iris { softness } => (3, Color::BLACK, vec2(0.0, 0.0), *softness),
+        TransitionEffect::Shader(_) => return,
+    };
+    shader.set_1i_uniform(name!("u_mode"), mode);
+    shader.set_color_uniform(name!("u_color"), color);
+    shader.set_2f_uniform(name!("u_direction"), direction.x, direction.y);
+    shader.set_1f_uniform(name!("u_softness"), softness.max(1e-4));
+    shader.set_1f_uniform(name!("u_aspect"), size.0 as f32 / size.1.max(1) as f32);
+}
@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+
+use gl::types::*;
+
+use super::framebuffer::Framebuffer;
+use super::gl_wrapper::ShaderProgram;
+use super::gpu_resources::{self, GpuObject, ShareGroup};
+use super::primitives::{Primitive, FULLSCREEN_VERTEX_SHADER};
+use super::texture::Texture;
+use crate::math::*;
+use crate::name;
+
+/// GLSL outputs and weighting for weighted blended order-independent transparency.
+///
+/// Paste (or concatenate) this into the fragment shader of transparent
+/// surfaces drawn between [`WeightedBlendedOit::begin`] and
+/// [`WeightedBlendedOit::end`], and write the final color through
+/// `nyanko_write_oit` instead of a regular output:
+///
+/// ```glsl
+/// nyanko_write_oit(vec4(lit_color, alpha));
+/// ```
+pub const OIT_GLSL: &str = r#"
+layout (location = 0) out vec4 out_accumulation;
+layout (location = 1) out float out_revealage;
+
+void nyanko_write_oit(vec4 color) {
+    // Weight nearer and more opaque fragments higher (McGuire & Bavoil 2013, eq. 10).
+    float depth = gl_FragCoord.z;
+    float weight = clamp(pow(min(1.0, color.a * 10.0) + 0.01, 3.0) * 1e8 * pow(1.0 - depth * 0.9, 3.0), 1e-2, 3e3);
+    out_accumulation = vec4(color.rgb * color.a, color.a) * weight;
+    out_revealage = color.a;
+}
+"#;
+
+const COMPOSITE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_accumulation;
+uniform sampler2D u_revealage;
+
+void main() {
+    float revealage = texture(u_revealage, v_uv).r;
+    if (revealage >= 1.0) {
+        discard;
+    }
+    vec4 accumulation = texture(u_accumulation, v_uv);
+    vec3 average = accumulation.rgb / max(accumulation.a, 0.00001);
+    out_color = vec4(average, 1.0 - revealage);
+}
+"#;
+
+/// How transparent surfaces are composited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TransparencyMode {
+    /// Sort surfaces back to front and alpha blend them. Exact for
+    /// non-intersecting surfaces, but wrong wherever they intersect.
+    #[default]
+    Sorted,
+    /// Weighted blended order-independent transparency. No sorting and no
+    /// popping, at the cost of an approximate result for heavy layering.
+    WeightedBlended,
+}
+
+/// Sorts transparent items from farthest to nearest to the camera, for
+/// [`TransparencyMode::Sorted`].
+pub fn sort_back_to_front<T, F>(items: &mut [T], camera_position: Vec3, mut position: F)
+where
+    F: FnMut(&T) -> Vec3,
+{
+    items.sort_by(|a, b| {
+        let a = (position(a) - camera_position).magnitude2();
+        let b = (position(b) - camera_position).magnitude2();
+        b.partial_cmp(&a).unwrap_or(Ordering::Equal)
+    });
+}
+
+/// # Weighted Blended OIT
+///
+/// Order-independent transparency using weighted blended accumulation.
+/// Transparent surfaces write into an accumulation and a revealage target,
+/// depth-tested against the opaque scene, and are then resolved over the
+/// scene in a single pass.
+///
+/// ## Example
+/// ```ignore
+/// let mut oit = WeightedBlendedOit::new(window.framebuffer_size());
+///
+/// // after drawing opaque geometry into `scene`:
+/// oit.begin(&scene);
+/// glass_shader.bind();
+/// // draw transparent surfaces in any order...
+/// oit.end(&scene);
+/// ```
+pub struct WeightedBlendedOit {
+    id: GLuint,
+    accumulation: Texture,
+    revealage: Texture,
+    depth: GLuint,
+    size: (u32, u32),
+    composite_shader: ShaderProgram,
+    triangle: Primitive,
+    group: ShareGroup,
+}
+
+impl WeightedBlendedOit {
+    /// Creates the accumulation targets for scenes of the given size.
+    pub fn new(size: (u32, u32)) -> Self {
+        let mut composite_shader =
+            ShaderProgram::from_source(FULLSCREEN_VERTEX_SHADER, COMPOSITE_FRAGMENT_SHADER);
+        for uniform in ["u_accumulation", "u_revealage"] {
+            composite_shader.create_uniform(uniform);
+        }
+
+        let (id, accumulation, revealage, depth) = Self::create_targets(size);
+        Self {
+            id,
+            accumulation,
+            revealage,
+            depth,
+            size,
+            composite_shader,
+            triangle: Primitive::fullscreen_triangle(),
+            group: ShareGroup::current(),
+        }
+    }
+
+    fn create_targets(size: (u32, u32)) -> (GLuint, Texture, Texture, GLuint) {
+        let (width, height) = size;
+        let accumulation =
+            Texture::new_2d(width, height, gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT, None);
+        let revealage = Texture::new_2d(width, height, gl::R8, gl::RED, gl::UNSIGNED_BYTE, None);
+
+        let mut id = 0;
+        let mut depth = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                accumulation.id(),
+                0,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT1,
+                gl::TEXTURE_2D,
+                revealage.id(),
+                0,
+            );
+
+            gl::GenRenderbuffers(1, &mut depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH24_STENCIL8,
+                width as GLsizei,
+                height as GLsizei,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth,
+            );
+
+            let buffers = [gl::COLOR_ATTACHMENT0, gl::COLOR_ATTACHMENT1];
+            gl::DrawBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                panic!("OIT framebuffer {}x{} is incomplete", width, height);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        (id, accumulation, revealage, depth)
+    }
+
+    fn delete_targets(&mut self) {
+        gpu_resources::release(self.group, GpuObject::Renderbuffer(self.depth));
+        gpu_resources::release(self.group, GpuObject::Framebuffer(self.id));
+    }
+
+    /// Recreates the targets at a new size.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if self.size == (width, height) {
+            return;
+        }
+        self.delete_targets();
+        let (id, accumulation, revealage, depth) = Self::create_targets((width, height));
+        self.id = id;
+        self.accumulation = accumulation;
+        self.revealage = revealage;
+        self.depth = depth;
+        self.size = (width, height);
+    }
+
+    /// Copies the opaque depth from `scene`, clears the accumulation targets, and
+    /// sets up blending for transparent draws.
+    pub fn begin(&mut self, scene: &Framebuffer) {
+        let (width, height) = scene.size();
+        self.resize(width, height);
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, scene.id());
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.id);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                width as GLint,
+                height as GLint,
+                0,
+                0,
+                width as GLint,
+                height as GLint,
+                gl::DEPTH_BUFFER_BIT,
+                gl::NEAREST,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, width as GLsizei, height as GLsizei);
+
+            let zero = [0.0f32; 4];
+            let one = [1.0f32; 4];
+            gl::ClearBufferfv(gl::COLOR, 0, zero.as_ptr());
+            gl::ClearBufferfv(gl::COLOR, 1, one.as_ptr());
+
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunci(0, gl::ONE, gl::ONE);
+            gl::BlendFunci(1, gl::ZERO, gl::ONE_MINUS_SRC_COLOR);
+        }
+    }
+
+    /// Restores GL state and resolves the transparent surfaces over `scene`.
+    pub fn end(&self, scene: &Framebuffer) {
+        let (width, height) = scene.size();
+        scene.bind();
+        self.composite_shader.bind();
+        self.composite_shader
+            .set_1i_uniform(name!("u_accumulation"), 0);
+        self.composite_shader
+            .set_1i_uniform(name!("u_revealage"), 1);
+        self.accumulation.bind(0);
+        self.revealage.bind(1);
+
+        unsafe {
+            gl::Viewport(0, 0, width as GLsizei, height as GLsizei);
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        self.triangle.draw();
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        Framebuffer::unbind();
+        ShaderProgram::unbind();
+    }
+}
+
+impl Drop for WeightedBlendedOit {
+    fn drop(&mut self) {
+        self.delete_targets();
+    }
+}
@@ -0,0 +1,368 @@
+use glfw::{Key, MouseButton};
+
+use super::sprite_batch::SpriteBatch;
+use crate::input::gestures::MOUSE_TOUCH_ID;
+use crate::input::{Input, Touch};
+use crate::math::*;
+
+/// How far a joystick or d-pad must lean, as a fraction of full tilt, before
+/// its direction keys are held.
+const DIRECTION_THRESHOLD: f32 = 0.4;
+
+/// The keys a joystick or d-pad holds for each direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DirectionKeys {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+}
+
+impl DirectionKeys {
+    /// The arrow keys.
+    pub fn arrows() -> Self {
+        Self {
+            up: Key::Up,
+            down: Key::Down,
+            left: Key::Left,
+            right: Key::Right,
+        }
+    }
+
+    /// W, A, S and D.
+    pub fn wasd() -> Self {
+        Self {
+            up: Key::W,
+            down: Key::S,
+            left: Key::A,
+            right: Key::D,
+        }
+    }
+}
+
+/// The kind of an on-screen control and its size in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VirtualControlKind {
+    /// An analog stick; the knob follows the finger within `radius`.
+    Joystick {
+        radius: f32,
+        /// Fraction of full tilt ignored around the center.
+        dead_zone: f32,
+        keys: DirectionKeys,
+    },
+    /// A round button.
+    Button { radius: f32, key: Key },
+    /// A cross with eight directions, `size` pixels across.
+    DPad { size: f32, keys: DirectionKeys },
+}
+
+/// # Virtual Control
+///
+/// One on-screen joystick, button or d-pad, placed relative to a corner or edge
+/// of the screen so it stays put when the screen size changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VirtualControl {
+    pub kind: VirtualControlKind,
+    pub anchor: Anchor,
+    /// Offset of the control's center from the anchor, in logical pixels.
+    pub offset: Vec2,
+    touch: Option<u64>,
+    value: Vec2,
+    held_keys: Vec<Key>,
+}
+
+impl VirtualControl {
+    /// Creates a joystick holding `keys` when tilted.
+    pub fn joystick(anchor: Anchor, offset: Vec2, radius: f32, keys: DirectionKeys) -> Self {
+        Self::new(
+            VirtualControlKind::Joystick {
+                radius,
+                dead_zone: 0.15,
+                keys,
+            },
+            anchor,
+            offset,
+        )
+    }
+
+    /// Creates a button holding `key` while touched.
+    pub fn button(anchor: Anchor, offset: Vec2, radius: f32, key: Key) -> Self {
+        Self::new(VirtualControlKind::Button { radius, key }, anchor, offset)
+    }
+
+    /// Creates a d-pad holding `keys` for the pressed directions.
+    pub fn dpad(anchor: Anchor, offset: Vec2, size: f32, keys: DirectionKeys) -> Self {
+        Self::new(VirtualControlKind::DPad { size, keys }, anchor, offset)
+    }
+
+    /// Creates a control of any kind.
+    pub fn new(kind: VirtualControlKind, anchor: Anchor, offset: Vec2) -> Self {
+        Self {
+            kind,
+            anchor,
+            offset,
+            touch: None,
+            value: Vec2::zero(),
+            held_keys: Vec::new(),
+        }
+    }
+
+    /// Returns the control's direction with Y pointing up, each axis from -1
+    /// to 1: the stick's tilt, the d-pad's pressed directions, or `(1, 0)`
+    /// while a button is held.
+    pub fn value(&self) -> Vec2 {
+        self.value
+    }
+
+    /// Checks if a finger is on the control.
+    pub fn is_held(&self) -> bool {
+        self.touch.is_some()
+    }
+
+    /// Returns the control's center on a screen of `screen_size` logical pixels.
+    pub fn center(&self, screen_size: Vec2) -> Vec2 {
+        let fraction = self.anchor.fraction();
+        vec2(fraction.x * screen_size.x, fraction.y * screen_size.y) + self.offset
+    }
+
+    fn contains(&self, center: Vec2, point: Vec2) -> bool {
+        match self.kind {
+            VirtualControlKind::Joystick { radius, .. }
+            | VirtualControlKind::Button { radius, .. } => point.distance(center) <= radius,
+            VirtualControlKind::DPad { size, .. } => {
+                Rect::from_center(center, vec2(size, size)).contains(point)
+            }
+        }
+    }
+
+    fn update_value(&mut self, center: Vec2, position: Option<Vec2>) {
+        let Some(position) = position else {
+            self.value = Vec2::zero();
+            return;
+        };
+        // Screen Y points down, control values point up.
+        let offset = vec2(position.x - center.x, center.y - position.y);
+        self.value = match self.kind {
+            VirtualControlKind::Joystick {
+                radius, dead_zone, ..
+            } => {
+                let tilt = offset / radius.max(f32::EPSILON);
+                let amount = tilt.magnitude();
+                if amount <= dead_zone {
+                    Vec2::zero()
+                } else {
+                    // Rescaled so the edge of the dead zone starts from zero.
+                    tilt / amount * ((amount.min(1.0) - dead_zone) / (1.0 - dead_zone))
+                }
+            }
+            VirtualControlKind::Button { .. } => vec2(1.0, 0.0),
+            VirtualControlKind::DPad { size, .. } => {
+                if offset.magnitude() < size * 0.1 {
+                    Vec2::zero()
+                } else {
+                    let direction = offset.normalize();
+                    let snap = |axis: f32| {
+                        if axis > DIRECTION_THRESHOLD {
+                            1.0
+                        } else if axis < -DIRECTION_THRESHOLD {
+                            -1.0
+                        } else {
+                            0.0
+                        }
+                    };
+                    vec2(snap(direction.x), snap(direction.y))
+                }
+            }
+        };
+    }
+
+    fn wanted_keys(&self) -> Vec<Key> {
+        let directions = |keys: &DirectionKeys| {
+            let mut wanted = Vec::new();
+            if self.value.y > DIRECTION_THRESHOLD {
+                wanted.push(keys.up);
+            }
+            if self.value.y < -DIRECTION_THRESHOLD {
+                wanted.push(keys.down);
+            }
+            if self.value.x < -DIRECTION_THRESHOLD {
+                wanted.push(keys.left);
+            }
+            if self.value.x > DIRECTION_THRESHOLD {
+                wanted.push(keys.right);
+            }
+            wanted
+        };
+        match &self.kind {
+            VirtualControlKind::Joystick { keys, .. } | VirtualControlKind::DPad { keys, .. } => {
+                directions(keys)
+            }
+            VirtualControlKind::Button { key, .. } if self.is_held() => vec![*key],
+            VirtualControlKind::Button { .. } => Vec::new(),
+        }
+    }
+}
+
+/// # Virtual Controls
+///
+/// On-screen joysticks, buttons and d-pads for touch screens. Each control
+/// holds keyboard keys in the [`Input`] while touched, so games written for
+/// the keyboard are playable on mobile without extra input code. Each finger
+/// drives the control it first landed on until it lifts. The left mouse button
+/// acts as a finger, for testing on desktop.
+///
+/// ## Example
+/// ```ignore
+/// let mut controls = VirtualControls::new();
+/// controls.add(VirtualControl::joystick(Anchor::BottomLeft, vec2(120.0, -120.0), 70.0, DirectionKeys::wasd()));
+/// let jump = controls.add(VirtualControl::button(Anchor::BottomRight, vec2(-100.0, -100.0), 45.0, Key::Space));
+///
+/// window.input_mut().begin_frame();
+/// controls.update(window.input_mut(), window.logical_size());
+///
+/// batch.begin(window.framebuffer_size());
+/// controls.draw(&mut batch, window.logical_size());
+/// batch.end();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct VirtualControls {
+    controls: Vec<VirtualControl>,
+    pub color: Color,
+    /// Color of controls while they are held.
+    pub active_color: Color,
+    pub visible: bool,
+}
+
+impl VirtualControls {
+    /// Creates an empty set of controls.
+    pub fn new() -> Self {
+        Self {
+            controls: Vec::new(),
+            color: Color::new(1.0, 1.0, 1.0, 0.25),
+            active_color: Color::new(1.0, 1.0, 1.0, 0.5),
+            visible: true,
+        }
+    }
+
+    /// Adds a control and returns its index.
+    pub fn add(&mut self, control: VirtualControl) -> usize {
+        self.controls.push(control);
+        self.controls.len() - 1
+    }
+
+    /// Returns a control by index.
+    pub fn get(&self, index: usize) -> Option<&VirtualControl> {
+        self.controls.get(index)
+    }
+
+    /// Returns every control.
+    pub fn controls(&self) -> &[VirtualControl] {
+        &self.controls
+    }
+
+    /// Checks if a touch is driving a control, so games can ignore it for
+    /// other purposes such as aiming.
+    pub fn is_touch_claimed(&self, id: u64) -> bool {
+        self.controls
+            .iter()
+            .any(|control| control.touch == Some(id))
+    }
+
+    /// Updates the controls from this frame's touches and holds or releases
+    /// their keys. `screen_size` is in logical pixels.
+    pub fn update(&mut self, input: &mut Input, screen_size: Vec2) {
+        let scale = input.display_scale();
+        let mut touches: Vec<Touch> = input.touches().to_vec();
+        if touches.is_empty() && input.mouse_down(MouseButton::Button1) {
+            touches.push(Touch {
+                id: MOUSE_TOUCH_ID,
+                position: input.cursor_position(),
+            });
+        }
+        for touch in &mut touches {
+            touch.position = scale.to_logical(touch.position);
+        }
+
+        for index in 0..self.controls.len() {
+            let center = self.controls[index].center(screen_size);
+            let current = self.controls[index]
+                .touch
+                .and_then(|id| touches.iter().find(|touch| touch.id == id));
+            let touch = current.or_else(|| {
+                touches.iter().find(|touch| {
+                    !self.is_touch_claimed(touch.id)
+                        && self.controls[index].contains(center, touch.position)
+                })
+            });
+
+            let control = &mut self.controls[index];
+            control.touch = touch.map(|touch| touch.id);
+            control.update_value(center, touch.map(|touch| touch.position));
+
+            let wanted = control.wanted_keys();
+            for key in &control.held_keys {
+                if !wanted.contains(key) {
+                    input.set_key_held(*key, false);
+                }
+            }
+            for key in &wanted {
+                if !control.held_keys.contains(key) {
+                    input.set_key_held(*key, true);
+                }
+            }
+            control.held_keys = wanted;
+        }
+    }
+
+    /// Draws the controls on a batch begun over a screen of `screen_size`
+    /// logical pixels.
+    pub fn draw(&self, batch: &mut SpriteBatch, screen_size: Vec2) {
+        if !self.visible {
+            return;
+        }
+        for control in &self.controls {
+            let center = control.center(screen_size);
+            let color = if control.is_held() {
+                self.active_color
+            } else {
+                self.color
+            };
+            match control.kind {
+                VirtualControlKind::Joystick { radius, .. } => {
+                    batch.stroke_circle(center, radius, 3.0, self.color);
+                    let knob = center + vec2(control.value.x, -control.value.y) * radius;
+                    batch.fill_circle(knob, radius * 0.4, color);
+                }
+                VirtualControlKind::Button { radius, .. } => {
+                    batch.fill_circle(center, radius, color);
+                }
+                VirtualControlKind::DPad { size, .. } => {
+                    let arm = size / 3.0;
+                    for direction in [
+                        vec2(0.0, 1.0),
+                        vec2(0.0, -1.0),
+                        vec2(-1.0, 0.0),
+                        vec2(1.0, 0.0),
+                    ] {
+                        let pressed = control.value.dot(direction) > 0.0;
+                        let arm_center = center + vec2(direction.x, -direction.y) * arm;
+                        batch.fill_rect(
+                            Rect::from_center(arm_center, vec2(arm, arm)),
+                            if pressed {
+                                self.active_color
+                            } else {
+                                self.color
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for VirtualControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,170 @@
+use super::camera::CameraView;
+use super::gl_wrapper::ShaderProgram;
+use super::primitives::Primitive;
+use super::reflection::PlanarReflection;
+use crate::math::*;
+use crate::name;
+use crate::scene::Transform;
+
+const WATER_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 a_position;
+
+uniform mat4 u_model;
+uniform mat4 u_view_projection;
+
+out vec3 v_world_position;
+out vec4 v_clip_position;
+
+void main() {
+    vec4 world = u_model * vec4(a_position.x, 0.0, -a_position.y, 1.0);
+    v_world_position = world.xyz;
+    v_clip_position = u_view_projection * world;
+    gl_Position = v_clip_position;
+}
+"#;
+
+const WATER_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec3 v_world_position;
+in vec4 v_clip_position;
+out vec4 out_color;
+
+uniform sampler2D u_reflection;
+uniform vec3 u_camera_position;
+uniform vec3 u_normal;
+uniform vec4 u_color;
+uniform float u_time;
+uniform float u_wave_scale;
+uniform float u_wave_speed;
+uniform float u_distortion;
+
+vec2 waves(vec2 p) {
+    float t = u_time * u_wave_speed;
+    return vec2(
+        sin(p.x * 1.7 + t) + sin(p.y * 2.3 - t * 1.3),
+        cos(p.y * 1.9 + t * 0.8) + cos(p.x * 2.9 - t * 1.1)
+    ) * 0.5;
+}
+
+void main() {
+    vec2 ripple = waves(v_world_position.xz * u_wave_scale);
+    vec2 screen_uv = v_clip_position.xy / v_clip_position.w * 0.5 + 0.5;
+    vec3 reflection = texture(u_reflection, clamp(screen_uv + ripple * u_distortion, 0.001, 0.999)).rgb;
+
+    vec3 normal = normalize(u_normal + vec3(ripple.x, 0.0, ripple.y) * u_distortion * 4.0);
+    vec3 to_camera = normalize(u_camera_position - v_world_position);
+    float fresnel = pow(1.0 - max(dot(normal, to_camera), 0.0), 3.0);
+
+    vec3 color = mix(u_color.rgb, reflection, clamp(0.2 + 0.8 * fresnel, 0.0, 1.0));
+    out_color = vec4(color, mix(u_color.a, 1.0, fresnel));
+}
+"#;
+
+/// # Water
+///
+/// A flat, animated water surface that shows a [`PlanarReflection`] through
+/// rippling distortion and a fresnel blend with the water's own color. The
+/// surface is the unit quad in the transform's local XZ plane, facing local +Y.
+pub struct Water {
+    pub color: Color,
+    /// How many ripples fit in a world unit.
+    pub wave_scale: f32,
+    pub wave_speed: f32,
+    /// Strength of the ripple offset applied to the reflection, in screen UV.
+    pub distortion: f32,
+    time: f32,
+    shader: ShaderProgram,
+    quad: Primitive,
+}
+
+impl Water {
+    /// Creates a water surface with default settings.
+    pub fn new() -> Self {
+        let mut shader = ShaderProgram::from_source(WATER_VERTEX_SHADER, WATER_FRAGMENT_SHADER);
+        for uniform in [
+            "u_model",
+            "u_view_projection",
+            "u_reflection",
+            "u_camera_position",
+            "u_normal",
+            "u_color",
+            "u_time",
+            "u_wave_scale",
+            "u_wave_speed",
+            "u_distortion",
+        ] {
+            shader.create_uniform(uniform);
+        }
+
+        Self {
+            color: Color::new(0.1, 0.3, 0.4, 0.8),
+            wave_scale: 0.5,
+            wave_speed: 1.0,
+            distortion: 0.01,
+            time: 0.0,
+            shader,
+            quad: Primitive::unit_quad(),
+        }
+    }
+
+    /// Returns the reflection plane for a water surface placed at `transform`.
+    pub fn plane(transform: &Transform) -> Plane {
+        Plane::from_point_normal(transform.translation, transform.up())
+    }
+
+    /// Advances the wave animation.
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+
+    /// Draws the water surface placed at `transform` as seen by `view`.
+    pub fn render(
+        &mut self,
+        view: &CameraView,
+        transform: &Transform,
+        reflection: &PlanarReflection,
+    ) {
+        let normal = transform.up();
+
+        self.shader.bind();
+        self.shader
+            .set_matrix4fv_uniform(name!("u_model"), &transform.matrix());
+        self.shader
+            .set_matrix4fv_uniform(name!("u_view_projection"), &view.view_projection);
+        self.shader.set_3f_uniform(
+            name!("u_camera_position"),
+            view.position.x,
+            view.position.y,
+            view.position.z,
+        );
+        self.shader
+            .set_3f_uniform(name!("u_normal"), normal.x, normal.y, normal.z);
+        self.shader.set_color_uniform(name!("u_color"), self.color);
+        self.shader.set_1f_uniform(name!("u_time"), self.time);
+        self.shader
+            .set_1f_uniform(name!("u_wave_scale"), self.wave_scale);
+        self.shader
+            .set_1f_uniform(name!("u_wave_speed"), self.wave_speed);
+        self.shader
+            .set_1f_uniform(name!("u_distortion"), self.distortion);
+        self.shader.set_1i_uniform(name!("u_reflection"), 0);
+        reflection.texture().bind(0);
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        self.quad.draw();
+        unsafe {
+            gl::Disable(gl::BLEND);
+        }
+        ShaderProgram::unbind();
+    }
+}
+
+impl Default for Water {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,10 +1,234 @@
 use glfw::{Action, Context, Key, WindowEvent};
+use std::path::PathBuf;
 use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use super::cursor::{CursorIcon, CursorRegions};
+use super::gl_wrapper::{is_gles, set_gles};
+use super::gpu_info::{GpuInfo, REQUIRED_GLES_VERSION, REQUIRED_GL_VERSION};
+use super::gpu_resources::{self, ShareGroup};
+use super::image::Image;
+use crate::crash;
+use crate::custom_errors::Errors;
+use crate::cvars::CVars;
+use crate::ecs::World;
+use crate::input::Input;
+use crate::math::*;
+
+/// The OpenGL flavor a window's context is created with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GraphicsApi {
+    /// Desktop OpenGL 3.3 or newer.
+    #[default]
+    OpenGl,
+    /// OpenGL ES 3.0 or newer, for the Raspberry Pi and other embedded devices.
+    OpenGlEs,
+}
+
+/// How a window is created: its border, transparency and input behaviour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowOptions {
+    /// Whether the window has a title bar and border.
+    pub decorated: bool,
+    pub resizable: bool,
+    /// Whether the framebuffer's alpha shows the desktop behind the window.
+    /// Clear with a zero alpha to see through.
+    pub transparent: bool,
+    /// Whether the window stays above other windows.
+    pub floating: bool,
+    /// Whether the OpenGL context reports GPU resets, such as driver updates or
+    /// timeouts, so the window can recover instead of drawing garbage.
+    pub robust: bool,
+    /// Whether the window is shown, `false` for offscreen rendering such as
+    /// golden-image tests.
+    pub visible: bool,
+    pub api: GraphicsApi,
+}
+
+impl WindowOptions {
+    /// Creates the options of a normal desktop window.
+    pub fn new() -> Self {
+        Self {
+            decorated: true,
+            resizable: true,
+            transparent: false,
+            floating: false,
+            robust: false,
+            visible: true,
+            api: GraphicsApi::OpenGl,
+        }
+    }
+
+    /// Creates the options of an overlay: borderless, transparent and on top,
+    /// like a streaming widget or desktop pet. Clicks still reach the window:
+    /// letting them pass through to the windows below needs GLFW 3.4, and
+    /// the bundled GLFW 3.3 doesn't support it.
+    pub fn overlay() -> Self {
+        Self {
+            decorated: false,
+            resizable: false,
+            transparent: true,
+            floating: true,
+            robust: false,
+            visible: true,
+            api: GraphicsApi::OpenGl,
+        }
+    }
+
+    /// Sets whether the window has a title bar and border.
+    pub fn with_decorated(mut self, decorated: bool) -> Self {
+        self.decorated = decorated;
+        self
+    }
+
+    /// Sets whether the window can be resized.
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Sets whether the framebuffer is transparent.
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Sets whether the window stays on top.
+    pub fn with_floating(mut self, floating: bool) -> Self {
+        self.floating = floating;
+        self
+    }
+
+    /// Sets whether the context reports GPU resets, for long-running tools.
+    pub fn with_robustness(mut self, robust: bool) -> Self {
+        self.robust = robust;
+        self
+    }
+
+    /// Sets whether the window is shown.
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets the OpenGL flavor of the context.
+    pub fn with_api(mut self, api: GraphicsApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    fn apply_hints(&self, glfw: &mut glfw::Glfw) {
+        glfw.window_hint(glfw::WindowHint::SRgbCapable(true));
+        glfw.window_hint(glfw::WindowHint::Decorated(self.decorated));
+        glfw.window_hint(glfw::WindowHint::Resizable(self.resizable));
+        glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(self.transparent));
+        glfw.window_hint(glfw::WindowHint::Floating(self.floating));
+        glfw.window_hint(glfw::WindowHint::Visible(self.visible));
+        glfw.window_hint(glfw::WindowHint::ContextRobustness(if self.robust {
+            glfw::ContextRobustnessHint::LoseContextOnReset
+        } else {
+            glfw::ContextRobustnessHint::NoRobustness
+        }));
+        if self.api == GraphicsApi::OpenGlEs {
+            glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::OpenGlEs));
+            glfw.window_hint(glfw::WindowHint::ContextVersion(3, 0));
+            // Embedded Linux drivers usually only provide ES contexts through EGL.
+            glfw.window_hint(glfw::WindowHint::ContextCreationApi(
+                glfw::ContextCreationApi::Egl,
+            ));
+        }
+    }
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How a window's pixels relate to each other on high-DPI displays.
+///
+/// GLFW reports the cursor and window size in screen coordinates, which are
+/// physical pixels on Windows and Linux but logical points on macOS. Physical
+/// pixels are what the framebuffer holds. Logical pixels are physical pixels
+/// divided by the content scale, the unit UI should be authored in so it keeps
+/// its size on 4K displays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayScale {
+    /// Framebuffer pixels per screen coordinate.
+    pub pixel_ratio: f32,
+    /// Physical pixels per logical pixel, from the system's display settings.
+    pub content_scale: f32,
+}
+
+impl DisplayScale {
+    /// Converts screen coordinates, such as the cursor position, to physical pixels.
+    pub fn to_physical(&self, screen: Vec2) -> Vec2 {
+        screen * self.pixel_ratio
+    }
+
+    /// Converts screen coordinates to logical pixels.
+    pub fn to_logical(&self, screen: Vec2) -> Vec2 {
+        screen * (self.pixel_ratio / self.content_scale)
+    }
+
+    /// Converts physical pixels to logical pixels.
+    pub fn physical_to_logical(&self, physical: Vec2) -> Vec2 {
+        physical / self.content_scale
+    }
+}
+
+impl Default for DisplayScale {
+    fn default() -> Self {
+        Self {
+            pixel_ratio: 1.0,
+            content_scale: 1.0,
+        }
+    }
+}
+
+/// Sent when files are dragged from the system onto a window and released.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilesDropped {
+    pub paths: Vec<PathBuf>,
+    /// Cursor position at the drop in window coordinates, origin at the top-left.
+    pub position: Vec2,
+}
+
+/// Sent when the window's OpenGL context was lost to a GPU reset. Every GPU
+/// resource, such as textures, meshes and shaders, is gone; drop them before the
+/// context is recreated on the next [`Window::send_context_events`], because
+/// their ids would otherwise delete objects of the new context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContextLost {
+    /// Whether this application's own commands caused the reset.
+    pub guilty: bool,
+}
+
+/// Sent once a lost OpenGL context has been recreated. Rebuild GPU resources
+/// from their CPU-side data, e.g. with
+/// [`GpuAssets::restore`](super::gpu_assets::GpuAssets::restore).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContextRestored;
+
+/// Sent when the app goes to the background, such as when its window is
+/// minimized, the desktop counterpart of a mobile app being paused. Games
+/// usually pause time and audio until [`AppResumed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AppSuspended;
+
+/// Sent when a suspended app comes back to the foreground.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AppResumed;
 
 /// # Window
 ///
 /// An abstraction layer for creating a GLFW window.
 ///
+/// Each window has its own events, input state and default framebuffer. More
+/// windows can be opened from an existing one, sharing its OpenGL resources or
+/// not.
+///
 /// ## Example
 /// ```
 /// let mut window = Window::new(1280, 720, "Window Title");
@@ -14,35 +238,296 @@ use std::sync::mpsc::Receiver;
 ///     window.update();
 /// }
 /// ```
+///
+/// With a second window:
+/// ```ignore
+/// let mut inspector = window.create_shared(400, 720, "Inspector");
+///
+/// while !window.should_close() {
+///     window.poll_events();
+///
+///     window.make_current();
+///     draw_scene();
+///     window.present();
+///
+///     if !inspector.should_close() {
+///         inspector.make_current();
+///         draw_inspector();
+///         inspector.present();
+///     }
+/// }
+/// ```
 pub struct Window {
     glfw: glfw::Glfw,
     window_handle: glfw::Window,
     events: Receiver<(f64, WindowEvent)>,
+    input: Input,
+    cursor: CursorIcon,
+    default_cursor: CursorIcon,
+    title: String,
+    title_status: Option<String>,
+    modified: bool,
+    dropped: Vec<FilesDropped>,
+    options: WindowOptions,
+    gpu_info: Option<GpuInfo>,
+    context_lost: bool,
+    suspended: bool,
+    suspended_sent: bool,
+    /// Shared by every window whose context shares objects with this one.
+    share_group: Arc<ShareGroup>,
+    cvar_vsync: Option<bool>,
 }
 
 impl Window {
     /// Create a new window with the given settings.
     pub fn new(width: u32, height: u32, title: &str) -> Self {
-        let glfw = glfw::init(glfw::FAIL_ON_ERRORS).expect("Failed to initialize GLFW");
+        Self::with_options(width, height, title, WindowOptions::new())
+    }
 
-        let (mut window, events) = glfw
-            .create_window(width, height, title, glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window");
+    /// Create a new window with border, transparency and input options.
+    pub fn with_options(width: u32, height: u32, title: &str, options: WindowOptions) -> Self {
+        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).expect("Failed to initialize GLFW");
 
-        window.set_framebuffer_size_polling(true);
-        window.set_key_polling(true);
+        options.apply_hints(&mut glfw);
+        let created = glfw.create_window(width, height, title, glfw::WindowMode::Windowed);
+        glfw.default_window_hints();
+        let (window, events) = created.expect("Failed to create GLFW window");
 
-        Self {
+        let mut window = Self::from_handle(glfw, window, events, title);
+        window.options = options;
+        window
+    }
+
+    /// Create another window whose OpenGL context shares textures, buffers and
+    /// shaders with this one, e.g. an editor panel showing the same assets.
+    /// Vertex arrays and framebuffers are never shared between contexts, so
+    /// create those again for the new window.
+    pub fn create_shared(&self, width: u32, height: u32, title: &str) -> Self {
+        let mut glfw = self.glfw.clone();
+        WindowOptions::new().apply_hints(&mut glfw);
+        let created =
+            self.window_handle
+                .create_shared(width, height, title, glfw::WindowMode::Windowed);
+        glfw.default_window_hints();
+        let (window, events) = created.expect("Failed to create GLFW window");
+
+        let mut window = Self::from_handle(glfw, window, events, title);
+        window.share_group = Arc::clone(&self.share_group);
+        window
+    }
+
+    /// Create another window with its own OpenGL context that shares nothing
+    /// with this one.
+    pub fn create_separate(&self, width: u32, height: u32, title: &str) -> Self {
+        let mut glfw = self.glfw.clone();
+        WindowOptions::new().apply_hints(&mut glfw);
+        let created = glfw.create_window(width, height, title, glfw::WindowMode::Windowed);
+        glfw.default_window_hints();
+        let (window, events) = created.expect("Failed to create GLFW window");
+
+        Self::from_handle(glfw, window, events, title)
+    }
+
+    fn from_handle(
+        glfw: glfw::Glfw,
+        mut window: glfw::Window,
+        events: Receiver<(f64, WindowEvent)>,
+        title: &str,
+    ) -> Self {
+        Self::enable_polling(&mut window);
+
+        let mut window = Self {
             glfw,
             window_handle: window,
             events,
-        }
+            input: Input::new(),
+            cursor: CursorIcon::default(),
+            default_cursor: CursorIcon::default(),
+            title: title.to_string(),
+            title_status: None,
+            modified: false,
+            dropped: Vec::new(),
+            options: WindowOptions::new(),
+            gpu_info: None,
+            context_lost: false,
+            suspended: false,
+            suspended_sent: false,
+            share_group: Arc::new(ShareGroup::new()),
+            cvar_vsync: None,
+        };
+        let scale = window.display_scale();
+        window.input.set_display_scale(scale);
+        window
     }
 
-    /// Load OpenGL functions.
+    fn enable_polling(window: &mut glfw::Window) {
+        window.set_framebuffer_size_polling(true);
+        window.set_key_polling(true);
+        window.set_char_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        window.set_drag_and_drop_polling(true);
+        window.set_size_polling(true);
+        window.set_content_scale_polling(true);
+        window.set_iconify_polling(true);
+    }
+
+    /// Load OpenGL functions and turn on sRGB encoding for the window, so
+    /// shaders output linear colors and the display gets gamma-correct ones.
+    /// Also queries the GPU's capabilities, see [`Window::gpu_info`].
     pub fn init_gl(&mut self) {
         self.window_handle.make_current();
         gl::load_with(|s| self.window_handle.get_proc_address(s) as *const _);
+        gpu_resources::set_gl_thread();
+        gpu_resources::make_current(*self.share_group);
+
+        let info = GpuInfo::query();
+        set_gles(info.is_gles);
+        // OpenGL ES always encodes into sRGB framebuffers and has no switch for it.
+        if !info.is_gles {
+            unsafe { gl::Enable(gl::FRAMEBUFFER_SRGB) };
+        }
+        let description = format!(
+            "OpenGL {} on {} ({})",
+            info.version_string, info.renderer, info.vendor
+        );
+        log::info!("{}", description);
+        crash::set_gpu_info(&description);
+        if !info.meets_requirements() {
+            log::warn!(
+                "{} is older than the required OpenGL {}.{} or OpenGL ES {}.{}, rendering may fail",
+                info.version_string,
+                REQUIRED_GL_VERSION.0,
+                REQUIRED_GL_VERSION.1,
+                REQUIRED_GLES_VERSION.0,
+                REQUIRED_GLES_VERSION.1
+            );
+        }
+        self.gpu_info = Some(info);
+    }
+
+    /// Check if the OpenGL context was lost to a GPU reset. Always `false`
+    /// unless the window was created with [`WindowOptions::with_robustness`]
+    /// on a driver that supports it.
+    pub fn is_context_lost(&self) -> bool {
+        self.reset_status().is_some()
+    }
+
+    fn reset_status(&self) -> Option<gl::types::GLenum> {
+        if !gl::GetGraphicsResetStatus::is_loaded() || !self.window_handle.is_current() {
+            return None;
+        }
+        let status = unsafe { gl::GetGraphicsResetStatus() };
+        (status != gl::NO_ERROR).then_some(status)
+    }
+
+    /// Replace the window and its OpenGL context with new ones of the same
+    /// size, title and options, and load OpenGL again. Every GPU resource of the
+    /// old context is invalid afterwards.
+    ///
+    /// Recovery isn't supported for contexts shared through
+    /// [`Window::create_shared`]: the new context shares nothing, and the
+    /// other windows keep a share group whose objects may be gone. A warning
+    /// is logged in that case; recreate those windows too.
+    pub fn recreate_context(&mut self) {
+        let shared_with = Arc::strong_count(&self.share_group) - 1;
+        if shared_with > 0 {
+            log::warn!(
+                "Recreating an OpenGL context shared with {} other window(s); \
+                 their shared resources aren't recovered",
+                shared_with
+            );
+        }
+        let old_group = std::mem::replace(&mut self.share_group, Arc::new(ShareGroup::new()));
+
+        let (width, height) = self.window_size();
+        let position = self.window_handle.get_pos();
+
+        self.options.apply_hints(&mut self.glfw);
+        let created =
+            self.glfw
+                .create_window(width, height, &self.title, glfw::WindowMode::Windowed);
+        self.glfw.default_window_hints();
+        let (mut window, events) = created.expect("Failed to recreate GLFW window");
+
+        Self::enable_polling(&mut window);
+        window.set_pos(position.0, position.1);
+        // The old window is destroyed when it is dropped here.
+        self.window_handle = window;
+        // Names released for the old context would delete unrelated objects
+        // of the new one. Windows still sharing the old group flush its
+        // releases themselves.
+        if Arc::strong_count(&old_group) == 1 {
+            gpu_resources::discard_pending(*old_group);
+        }
+        self.events = events;
+        self.context_lost = false;
+
+        self.init_gl();
+        self.refresh_title();
+        let cursor = self.cursor.clone();
+        self.cursor = CursorIcon::default();
+        self.set_cursor(&cursor);
+    }
+
+    /// Send [`ContextLost`] once the context is lost, then recreate it on the
+    /// next call and send [`ContextRestored`], giving systems a frame in between
+    /// to drop their GPU resources. Call once per frame.
+    pub fn send_context_events(&mut self, world: &World) {
+        if self.context_lost {
+            self.recreate_context();
+            world.events::<ContextRestored>().send(ContextRestored);
+        } else if let Some(status) = self.reset_status() {
+            log::warn!("OpenGL context lost, recreating it");
+            self.context_lost = true;
+            world.events::<ContextLost>().send(ContextLost {
+                guilty: status == gl::GUILTY_CONTEXT_RESET,
+            });
+        }
+    }
+
+    /// Check if the app is in the background, as of the last processed events.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Send [`AppSuspended`] or [`AppResumed`] when the app went to the
+    /// background or came back since the last call. Call once per frame.
+    pub fn send_lifecycle_events(&mut self, world: &World) {
+        if self.suspended == self.suspended_sent {
+            return;
+        }
+        self.suspended_sent = self.suspended;
+        if self.suspended {
+            world.events::<AppSuspended>().send(AppSuspended);
+        } else {
+            world.events::<AppResumed>().send(AppResumed);
+        }
+    }
+
+    /// Get the capabilities of this window's OpenGL context, once
+    /// [`Window::init_gl`] has been called.
+    pub fn gpu_info(&self) -> Option<&GpuInfo> {
+        self.gpu_info.as_ref()
+    }
+
+    /// Make this window's OpenGL context current, so following draw calls
+    /// render into it. Load OpenGL functions once with [`Window::init_gl`]
+    /// before switching between windows.
+    pub fn make_current(&mut self) {
+        self.window_handle.make_current();
+        gpu_resources::make_current(*self.share_group);
+        // sRGB encoding is per-context state, so windows with separate contexts
+        // need it enabled too.
+        if gl::Enable::is_loaded() && !is_gles() {
+            unsafe { gl::Enable(gl::FRAMEBUFFER_SRGB) };
+        }
+    }
+
+    /// Check if this window's OpenGL context is current.
+    pub fn is_current(&self) -> bool {
+        self.window_handle.is_current()
     }
 
     /// Check if the window should close.
@@ -50,20 +535,301 @@ impl Window {
         self.window_handle.should_close()
     }
 
+    /// Get the size of the window's framebuffer in pixels.
+    pub fn framebuffer_size(&self) -> (u32, u32) {
+        let (width, height) = self.window_handle.get_framebuffer_size();
+        (width as u32, height as u32)
+    }
+
+    /// Get the size of the window in screen coordinates.
+    pub fn window_size(&self) -> (u32, u32) {
+        let (width, height) = self.window_handle.get_size();
+        (width as u32, height as u32)
+    }
+
+    /// Get the size of the window in logical pixels.
+    pub fn logical_size(&self) -> Vec2 {
+        let (width, height) = self.framebuffer_size();
+        self.display_scale()
+            .physical_to_logical(vec2(width as f32, height as f32))
+    }
+
+    /// Get the content scale of the monitor the window is on, e.g. `1.5` at
+    /// 150% display scaling. Pass it to the UI renderers' `set_ui_scale`.
+    pub fn content_scale(&self) -> f32 {
+        let (x, y) = self.window_handle.get_content_scale();
+        x.max(y).max(f32::EPSILON)
+    }
+
+    /// Get how screen coordinates, physical and logical pixels relate.
+    pub fn display_scale(&self) -> DisplayScale {
+        let (width, _) = self.window_handle.get_size();
+        let (framebuffer_width, _) = self.window_handle.get_framebuffer_size();
+        DisplayScale {
+            pixel_ratio: if width > 0 {
+                framebuffer_width as f32 / width as f32
+            } else {
+                1.0
+            },
+            content_scale: self.content_scale(),
+        }
+    }
+
+    /// Get the keyboard and mouse state.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    /// Get the keyboard and mouse state, e.g. to begin a frame or inject a replay.
+    pub fn input_mut(&mut self) -> &mut Input {
+        &mut self.input
+    }
+
+    /// Get the title, without any status or unsaved-changes marker.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Change the title.
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.refresh_title();
+    }
+
+    /// Show a status after the title, such as the frame rate, or remove it with `None`.
+    pub fn set_title_status(&mut self, status: Option<&str>) {
+        if self.title_status.as_deref() == status {
+            return;
+        }
+        self.title_status = status.map(str::to_string);
+        self.refresh_title();
+    }
+
+    /// Mark the title with an asterisk while there are unsaved changes.
+    pub fn set_modified(&mut self, modified: bool) {
+        if self.modified != modified {
+            self.modified = modified;
+            self.refresh_title();
+        }
+    }
+
+    /// Check if the title shows unsaved changes.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    fn refresh_title(&mut self) {
+        let mut title = String::new();
+        if self.modified {
+            title.push('*');
+        }
+        title.push_str(&self.title);
+        if let Some(status) = &self.title_status {
+            title.push_str(" - ");
+            title.push_str(status);
+        }
+        self.window_handle.set_title(&title);
+    }
+
+    /// Set the window and taskbar icon. Pass several sizes, such as 16, 32 and
+    /// 48 pixels, to let the system pick the closest; an empty slice restores
+    /// the default icon. Ignored on macOS, where the icon comes from the bundle.
+    pub fn set_icon(&mut self, images: &[Image]) {
+        self.window_handle
+            .set_icon_from_pixels(images.iter().map(Image::to_glfw).collect());
+    }
+
+    /// Load PNG files and set them as the window icon.
+    pub fn load_icon(&mut self, paths: &[&str]) -> Result<(), Errors> {
+        let images = Image::load_all(paths)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        self.set_icon(&images);
+        Ok(())
+    }
+
+    /// Ask for the user's attention, e.g. by flashing the taskbar entry, when
+    /// something needs them while the window is in the background.
+    pub fn request_attention(&mut self) {
+        self.window_handle.request_attention();
+    }
+
+    /// Check if the window has input focus.
+    pub fn is_focused(&self) -> bool {
+        self.window_handle.is_focused()
+    }
+
+    /// Get the options the window currently has.
+    pub fn options(&self) -> WindowOptions {
+        self.options
+    }
+
+    /// Show or hide the title bar and border.
+    pub fn set_decorated(&mut self, decorated: bool) {
+        self.window_handle.set_decorated(decorated);
+        self.options.decorated = decorated;
+    }
+
+    /// Keep the window above other windows, or stop.
+    pub fn set_floating(&mut self, floating: bool) {
+        self.window_handle.set_floating(floating);
+        self.options.floating = floating;
+    }
+
+    /// Wait for the display's refresh before presenting, or present as soon as
+    /// a frame is ready. Applies to the current OpenGL context.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let interval = if vsync {
+            glfw::SwapInterval::Sync(1)
+        } else {
+            glfw::SwapInterval::None
+        };
+        self.glfw.set_swap_interval(interval);
+    }
+
+    /// Applies `r.vsync` when it changes. Call once per frame.
+    pub fn apply_cvars(&mut self, cvars: &CVars) {
+        if let Some(vsync) = cvars.bool("r.vsync") {
+            if self.cvar_vsync != Some(vsync) {
+                self.cvar_vsync = Some(vsync);
+                self.set_vsync(vsync);
+            }
+        }
+    }
+
+    /// Set the opacity of the whole window, including any border, from 0 to 1.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.window_handle.set_opacity(opacity.clamp(0.0, 1.0));
+    }
+
+    /// Get the text on the system clipboard, if it holds any. Images and other
+    /// formats aren't available through GLFW and read as `None`.
+    ///
+    /// ```ignore
+    /// if window.input().shortcut_pressed(Key::V) {
+    ///     if let Some(text) = window.clipboard_text() {
+    ///         field.insert(&text);
+    ///     }
+    /// }
+    /// ```
+    pub fn clipboard_text(&self) -> Option<String> {
+        self.window_handle.get_clipboard_string()
+    }
+
+    /// Put text on the system clipboard.
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        self.window_handle.set_clipboard_string(text);
+    }
+
+    /// Get the cursor currently shown over the window.
+    pub fn cursor(&self) -> &CursorIcon {
+        &self.cursor
+    }
+
+    /// Change the mouse cursor. Setting the cursor that is already shown does nothing.
+    pub fn set_cursor(&mut self, cursor: &CursorIcon) {
+        if *cursor == self.cursor {
+            return;
+        }
+        match cursor {
+            CursorIcon::System(shape) => {
+                self.window_handle.set_cursor_mode(glfw::CursorMode::Normal);
+                self.window_handle
+                    .set_cursor(Some(glfw::Cursor::standard(shape.to_glfw())));
+            }
+            CursorIcon::Image(image) => {
+                self.window_handle.set_cursor_mode(glfw::CursorMode::Normal);
+                let (x, y) = image.hotspot;
+                self.window_handle
+                    .set_cursor(Some(glfw::Cursor::create_from_pixels(
+                        image.to_glfw(),
+                        x,
+                        y,
+                    )));
+            }
+            CursorIcon::Hidden => {
+                self.window_handle.set_cursor_mode(glfw::CursorMode::Hidden);
+            }
+        }
+        self.cursor = cursor.clone();
+    }
+
+    /// Set the cursor shown when no cursor region is under the mouse.
+    pub fn set_default_cursor(&mut self, cursor: CursorIcon) {
+        self.default_cursor = cursor;
+    }
+
+    /// Show the cursor of the region under the mouse, or the default cursor.
+    pub fn apply_cursor_regions(&mut self, regions: &CursorRegions) {
+        let cursor = regions
+            .resolve(self.input.cursor_logical())
+            .unwrap_or(&self.default_cursor)
+            .clone();
+        self.set_cursor(&cursor);
+    }
+
+    /// Take the files dropped onto the window since the last call.
+    pub fn take_dropped_files(&mut self) -> Vec<FilesDropped> {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// Send the files dropped since the last call to the world as
+    /// [`FilesDropped`] events. The event type must be registered.
+    pub fn send_dropped_files(&mut self, world: &World) {
+        if !self.dropped.is_empty() {
+            world
+                .events::<FilesDropped>()
+                .send_batch(self.take_dropped_files());
+        }
+    }
+
     /// Poll events and swap buffers.
     pub fn update(&mut self) {
+        self.poll_events();
+        self.present();
+    }
+
+    /// Poll events for every window. With several windows, call this once per
+    /// frame and then [`Window::present`] on each window instead of
+    /// [`Window::update`].
+    pub fn poll_events(&mut self) {
         self.glfw.poll_events();
+    }
+
+    /// Swap buffers, delete GPU objects released during the frame and
+    /// process this window's events.
+    pub fn present(&mut self) {
         self.window_handle.swap_buffers();
+        if self.window_handle.is_current() {
+            gpu_resources::flush_deletions();
+        }
         self.process_events();
     }
 
     /// Process window events, including resizing and key presses.
     fn process_events(&mut self) {
         for (_, event) in glfw::flush_messages(&self.events) {
+            self.input.handle_event(&event);
             match event {
                 WindowEvent::FramebufferSize(width, height) => {
-                    unsafe { gl::Viewport(0, 0, width, height) };
+                    if self.window_handle.is_current() {
+                        unsafe { gl::Viewport(0, 0, width, height) };
+                    }
+                    let scale = self.display_scale();
+                    self.input.set_display_scale(scale);
                 }
+                WindowEvent::Size(..) | WindowEvent::ContentScale(..) => {
+                    let scale = self.display_scale();
+                    self.input.set_display_scale(scale);
+                }
+                WindowEvent::FileDrop(paths) => {
+                    let (x, y) = self.window_handle.get_cursor_pos();
+                    self.dropped.push(FilesDropped {
+                        paths,
+                        position: vec2(x as f32, y as f32),
+                    });
+                }
+                WindowEvent::Iconify(iconified) => self.suspended = iconified,
                 WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
                     self.window_handle.set_should_close(true);
                 }
@@ -72,3 +838,13 @@ impl Window {
         }
     }
 }
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        // The context goes away with the last window of its group, and with
+        // it the names still queued for deletion.
+        if Arc::strong_count(&self.share_group) == 1 {
+            gpu_resources::discard_pending(*self.share_group);
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use std::sync::Arc;
+
+use super::camera::CameraView;
+use super::font::SdfFont;
+use super::sprite_batch::SpriteBatch;
+use super::text::{TextRenderer, TextStyle};
+use super::text_layout::{TextLayout, TextLayoutOptions};
+use crate::math::*;
+
+/// How a UI element follows a point in the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldAnchor {
+    /// World-space offset from the followed position, e.g. above a character's head.
+    pub offset: Vec3,
+    /// Pixel offset applied after projection, Y down.
+    pub screen_offset: Vec2,
+    /// Distance at which the element is drawn at its natural size.
+    pub reference_distance: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Distance where fading out begins.
+    pub fade_start: f32,
+    /// Distance where the element is fully hidden.
+    pub fade_end: f32,
+}
+
+impl WorldAnchor {
+    /// Creates an anchor that keeps a constant size and never fades.
+    pub fn new() -> Self {
+        Self {
+            offset: vec3(0.0, 0.0, 0.0),
+            screen_offset: vec2(0.0, 0.0),
+            reference_distance: 0.0,
+            min_scale: 1.0,
+            max_scale: 1.0,
+            fade_start: f32::INFINITY,
+            fade_end: f32::INFINITY,
+        }
+    }
+
+    /// Sets the world-space offset from the followed position.
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the pixel offset applied after projection.
+    pub fn with_screen_offset(mut self, screen_offset: Vec2) -> Self {
+        self.screen_offset = screen_offset;
+        self
+    }
+
+    /// Shrinks the element with distance like a world object, at natural size at
+    /// `reference_distance`, clamped between `min_scale` and `max_scale`.
+    pub fn with_distance_scaling(
+        mut self,
+        reference_distance: f32,
+        min_scale: f32,
+        max_scale: f32,
+    ) -> Self {
+        self.reference_distance = reference_distance;
+        self.min_scale = min_scale;
+        self.max_scale = max_scale;
+        self
+    }
+
+    /// Fades the element out between two distances from the camera.
+    pub fn with_fade(mut self, start: f32, end: f32) -> Self {
+        self.fade_start = start;
+        self.fade_end = end;
+        self
+    }
+
+    /// Projects the anchor for a camera. Returns `None` when it is behind the
+    /// camera, fully faded, or outside the camera's viewport.
+    pub fn place(&self, view: &CameraView, position: Vec3) -> Option<ScreenPlacement> {
+        let world = position + self.offset;
+        let screen = view.world_to_screen(world)? + self.screen_offset;
+        let distance = (world - view.position).magnitude();
+
+        let scale = if self.reference_distance > 0.0 {
+            (self.reference_distance / distance.max(1e-4)).clamp(self.min_scale, self.max_scale)
+        } else {
+            1.0
+        };
+        let opacity = if distance <= self.fade_start {
+            1.0
+        } else if distance >= self.fade_end {
+            0.0
+        } else {
+            1.0 - (distance - self.fade_start) / (self.fade_end - self.fade_start)
+        };
+        if opacity <= 0.0 {
+            return None;
+        }
+
+        let (x, y, width, height) = view.viewport;
+        let top = view.target_size.1 as i32 - y - height;
+        let viewport = Rect::new(x as f32, top as f32, width as f32, height as f32);
+        if !viewport.contains(screen) {
+            return None;
+        }
+
+        Some(ScreenPlacement {
+            position: screen,
+            distance,
+            scale,
+            opacity,
+        })
+    }
+}
+
+impl Default for WorldAnchor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where a [`WorldAnchor`] lands on screen this frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenPlacement {
+    /// Pixel position with the origin at the target's top-left.
+    pub position: Vec2,
+    /// Distance from the camera, for sorting.
+    pub distance: f32,
+    pub scale: f32,
+    /// Combined distance fade, 0 to 1.
+    pub opacity: f32,
+}
+
+/// The look of a [`HealthBar`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthBarStyle {
+    /// Size in pixels at scale 1.
+    pub size: Vec2,
+    pub fill: Color,
+    /// Color of the fill when empty; the fill blends toward it as health drops.
+    pub low_fill: Color,
+    /// Color of the recently lost portion that drains behind the fill.
+    pub trail: Color,
+    pub background: Color,
+    pub border: Color,
+    pub border_width: f32,
+    pub corner_radius: f32,
+}
+
+impl HealthBarStyle {
+    /// Creates a green-to-red bar of the given pixel size.
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            fill: Color::rgb(0.2, 0.85, 0.3),
+            low_fill: Color::rgb(0.9, 0.15, 0.1),
+            trail: Color::rgb(1.0, 0.85, 0.4),
+            background: Color::new(0.0, 0.0, 0.0, 0.6),
+            border: Color::new(0.0, 0.0, 0.0, 0.9),
+            border_width: 1.0,
+            corner_radius: 2.0,
+        }
+    }
+}
+
+impl Default for HealthBarStyle {
+    fn default() -> Self {
+        Self::new(vec2(60.0, 8.0))
+    }
+}
+
+/// # Health Bar
+///
+/// The state of a bar showing a 0 to 1 fraction. Lost value lingers as a trail
+/// that drains after a short delay, making hits readable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthBar {
+    pub value: f32,
+    pub trail: f32,
+    /// Seconds the trail holds before draining.
+    pub trail_delay: f32,
+    /// Fraction per second the trail drains.
+    pub trail_speed: f32,
+    hold: f32,
+}
+
+impl HealthBar {
+    /// Creates a bar at a value.
+    pub fn new(value: f32) -> Self {
+        let value = value.clamp(0.0, 1.0);
+        Self {
+            value,
+            trail: value,
+            trail_delay: 0.4,
+            trail_speed: 0.8,
+            hold: 0.0,
+        }
+    }
+
+    /// Creates a bar from current and maximum amounts.
+    pub fn from_amount(current: f32, max: f32) -> Self {
+        Self::new(if max > 0.0 { current / max } else { 0.0 })
+    }
+
+    /// Sets the value. Decreases leave a trail; increases move it along.
+    pub fn set(&mut self, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        if value < self.value {
+            self.hold = self.trail_delay;
+        }
+        self.value = value;
+        self.trail = self.trail.max(value);
+    }
+
+    /// Advances the trail animation.
+    pub fn update(&mut self, delta_time: f32) {
+        if self.hold > 0.0 {
+            self.hold -= delta_time;
+        } else {
+            self.trail = (self.trail - self.trail_speed * delta_time).max(self.value);
+        }
+    }
+}
+
+impl Default for HealthBar {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+struct QueuedBar {
+    placement: ScreenPlacement,
+    bar: HealthBar,
+    style: HealthBarStyle,
+}
+
+struct QueuedLabel {
+    placement: ScreenPlacement,
+    font: Arc<SdfFont>,
+    layout: TextLayout,
+    style: TextStyle,
+}
+
+/// # World UI
+///
+/// Screen-space labels and health bars pinned to world positions. Elements are
+/// projected through the camera, scaled and faded by distance as their
+/// [`WorldAnchor`] asks, culled when off-screen or behind the camera, and drawn
+/// far to near so closer elements overlap farther ones. Bars are drawn before
+/// labels.
+///
+/// ## Example
+/// ```ignore
+/// let anchor = WorldAnchor::new()
+///     .with_offset(vec3(0.0, 2.2, 0.0))
+///     .with_distance_scaling(10.0, 0.5, 1.5)
+///     .with_fade(40.0, 50.0);
+///
+/// for (transform, health, name) in &enemies {
+///     world_ui.bar(&view, transform.translation, &anchor, &health.bar, &HealthBarStyle::default());
+///     world_ui.label(&view, transform.translation, &anchor.with_screen_offset(vec2(0.0, -10.0)), &font, name, TextStyle::new(14.0, Color::WHITE));
+/// }
+/// world_ui.draw(&mut batch, &mut text, window.framebuffer_size());
+/// ```
+#[derive(Default)]
+pub struct WorldUi {
+    bars: Vec<QueuedBar>,
+    labels: Vec<QueuedLabel>,
+}
+
+impl WorldUi {
+    /// Creates an empty world UI queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of queued elements.
+    pub fn len(&self) -> usize {
+        self.bars.len() + self.labels.len()
+    }
+
+    /// Checks if nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queues a health bar centered on the anchor. Returns `false` if it was culled.
+    pub fn bar(
+        &mut self,
+        view: &CameraView,
+        position: Vec3,
+        anchor: &WorldAnchor,
+        bar: &HealthBar,
+        style: &HealthBarStyle,
+    ) -> bool {
+        let Some(placement) = anchor.place(view, position) else {
+            return false;
+        };
+        self.bars.push(QueuedBar {
+            placement,
+            bar: *bar,
+            style: *style,
+        });
+        true
+    }
+
+    /// Queues text centered horizontally on the anchor, with its bottom edge at
+    /// the anchor. Returns `false` if it was culled.
+    pub fn label(
+        &mut self,
+        view: &CameraView,
+        position: Vec3,
+        anchor: &WorldAnchor,
+        font: &Arc<SdfFont>,
+        text: &str,
+        style: TextStyle,
+    ) -> bool {
+        let Some(placement) = anchor.place(view, position) else {
+            return false;
+        };
+        let size = style.size * placement.scale;
+        let layout = TextLayout::from_markup(font, text, TextLayoutOptions::new(size));
+        self.labels.push(QueuedLabel {
+            placement,
+            font: font.clone(),
+            layout,
+            style: TextStyle { size, ..style },
+        });
+        true
+    }
+
+    /// Draws and clears everything queued, for a target of the given pixel size.
+    pub fn draw(
+        &mut self,
+        batch: &mut SpriteBatch,
+        text: &mut TextRenderer,
+        target_size: (u32, u32),
+    ) {
+        self.bars
+            .sort_by(|a, b| b.placement.distance.total_cmp(&a.placement.distance));
+        self.labels
+            .sort_by(|a, b| b.placement.distance.total_cmp(&a.placement.distance));
+
+        // Placements are in target pixels; the renderers may draw in logical
+        // pixels with a UI scale, which also scales the elements themselves.
+        if !self.bars.is_empty() {
+            let ui_scale = batch.ui_scale();
+            batch.begin(target_size);
+            for mut queued in self.bars.drain(..) {
+                queued.placement.position /= ui_scale;
+                draw_bar(batch, &queued);
+            }
+            batch.end();
+        }
+
+        for queued in self.labels.drain(..) {
+            let opacity = queued.placement.opacity;
+            let fade = |color: Color| color.with_alpha(color.a * opacity);
+            let style = TextStyle {
+                color: fade(queued.style.color),
+                outline_color: fade(queued.style.outline_color),
+                shadow_color: fade(queued.style.shadow_color),
+                ..queued.style
+            };
+            let size = queued.layout.size();
+            let position = queued.placement.position / text.ui_scale() - vec2(size.x * 0.5, size.y);
+            text.draw_layout_2d(&queued.font, &queued.layout, position, style);
+        }
+        text.flush_2d(target_size);
+    }
+}
+
+fn draw_bar(batch: &mut SpriteBatch, queued: &QueuedBar) {
+    let QueuedBar {
+        placement,
+        bar,
+        style,
+    } = queued;
+    let fade = |color: Color| color.with_alpha(color.a * placement.opacity);
+    let size = style.size * placement.scale;
+    let radius = style.corner_radius * placement.scale;
+    let outer = Rect::new(
+        placement.position.x - size.x * 0.5,
+        placement.position.y - size.y * 0.5,
+        size.x,
+        size.y,
+    );
+
+    batch.fill_rounded_rect(outer, radius, fade(style.background));
+    let inner = Rect::new(
+        outer.x + style.border_width,
+        outer.y + style.border_width,
+        (outer.width - style.border_width * 2.0).max(0.0),
+        (outer.height - style.border_width * 2.0).max(0.0),
+    );
+    let portion = |fraction: f32| Rect::new(inner.x, inner.y, inner.width * fraction, inner.height);
+
+    if bar.trail > bar.value {
+        batch.fill_rect(portion(bar.trail), fade(style.trail));
+    }
+    if bar.value > 0.0 {
+        let fill = style.low_fill.lerp(style.fill, bar.value);
+        batch.fill_rect(portion(bar.value), fade(fill));
+    }
+    if style.border_width > 0.0 {
+        batch.stroke_rounded_rect(outer, radius, style.border_width, fade(style.border));
+    }
+}
@@ -0,0 +1,520 @@
+use std::ffi::{c_char, c_void, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::custom_errors::Errors;
+use crate::ecs::World;
+
+/// Version of [`GameApi`]; a library exporting another version is refused.
+pub const GAME_API_VERSION: u32 = 2;
+
+/// Name of the function [`export_game_api!`](crate::export_game_api) defines.
+pub const GAME_API_SYMBOL: &str = "nyanko_game_api";
+
+/// How often the library file is checked for changes.
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the library file must stay unchanged before it's loaded, so a
+/// build still writing it isn't picked up half done.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// The entry points a game library exports, see
+/// [`export_game_api!`](crate::export_game_api).
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct GameApi {
+    pub version: u32,
+    /// Called right after loading, before `load`. Set by
+    /// [`export_game_api!`](crate::export_game_api) to [`init_library`].
+    pub init: fn(&HostContext),
+    /// Called after loading, with the state the previous library saved, or
+    /// `None` the first time.
+    pub load: fn(&mut World, Option<&[u8]>),
+    /// Called once per frame.
+    pub update: fn(&mut World),
+    /// Called before unloading. Saves the game's own state and removes every
+    /// component, event and system whose type or code lives in the library.
+    pub unload: fn(&mut World) -> Vec<u8>,
+}
+
+/// What the engine hands a game library when loading it. The library links
+/// its own copies of the engine's dependencies, so its `gl` function pointers
+/// and `log` logger start out empty; this lets it reach the engine's.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct HostContext {
+    /// Looks up a GL function in the engine's current context.
+    pub gl_proc_address: extern "C" fn(*const c_char) -> *const c_void,
+    /// The engine's logger.
+    pub logger: &'static dyn log::Log,
+    /// The engine's maximum log level.
+    pub max_level: log::LevelFilter,
+}
+
+impl HostContext {
+    /// Describes the running engine.
+    fn current() -> Self {
+        Self {
+            gl_proc_address: host_gl_proc_address,
+            logger: log::logger(),
+            max_level: log::max_level(),
+        }
+    }
+}
+
+extern "C" fn host_gl_proc_address(name: *const c_char) -> *const c_void {
+    // Safety: `init_library` passes a nul-terminated name.
+    unsafe { glfw::ffi::glfwGetProcAddress(name) }
+}
+
+/// Loads the library's GL functions from the engine's context and sends its
+/// log output to the engine's logger. Runs inside the game library.
+#[doc(hidden)]
+pub fn init_library(host: &HostContext) {
+    gl::load_with(|name| match CString::new(name) {
+        Ok(name) => (host.gl_proc_address)(name.as_ptr()),
+        Err(_) => std::ptr::null(),
+    });
+    if log::set_logger(host.logger).is_ok() {
+        log::set_max_level(host.max_level);
+    }
+}
+
+/// Exports a game library's [`GameApi`] for [`HotReloader`].
+///
+/// ```ignore
+/// nyanko_engine::export_game_api!(load, update, unload);
+/// ```
+#[macro_export]
+macro_rules! export_game_api {
+    ($load:path, $update:path, $unload:path) => {
+        #[no_mangle]
+        pub extern "C" fn nyanko_game_api() -> *const $crate::hot_reload::GameApi {
+            static API: $crate::hot_reload::GameApi = $crate::hot_reload::GameApi {
+                version: $crate::hot_reload::GAME_API_VERSION,
+                init: $crate::hot_reload::init_library,
+                load: $load,
+                update: $update,
+                unload: $unload,
+            };
+            &API
+        }
+    };
+}
+
+/// A loaded dynamic library, closed when dropped.
+struct Library {
+    handle: platform::Handle,
+    path: PathBuf,
+}
+
+impl Library {
+    fn open(path: &Path) -> Result<Self, Errors> {
+        let handle = platform::open(path)
+            .map_err(|message| Errors::HotReload(format!("{}: {}", path.display(), message)))?;
+        Ok(Self {
+            handle,
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn game_api(&self) -> Result<GameApi, Errors> {
+        let error =
+            |message: String| Errors::HotReload(format!("{}: {}", self.path.display(), message));
+        let symbol = platform::symbol(self.handle, GAME_API_SYMBOL).map_err(error)?;
+        // Safety: the symbol is the function defined by `export_game_api!`.
+        let api = unsafe {
+            let entry =
+                std::mem::transmute::<*const c_void, extern "C" fn() -> *const GameApi>(symbol);
+            *entry()
+        };
+        if api.version != GAME_API_VERSION {
+            return Err(error(format!(
+                "exports game API version {}, the engine expects {}",
+                api.version, GAME_API_VERSION
+            )));
+        }
+        Ok(api)
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        platform::close(self.handle);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// # Hot Reloader
+///
+/// Runs game logic from a dynamic library and swaps in a new build whenever the
+/// file changes, while the engine keeps the window, GPU resources and world
+/// alive. Before a swap the old library's `unload` saves whatever state it
+/// needs as bytes and removes its own types from the world; the new library's
+/// `load` gets those bytes back.
+///
+/// The game's logic lives in its own crate built as a `cdylib` that depends
+/// on the engine, and exports its entry points with
+/// [`export_game_api!`](crate::export_game_api). Engine components such as
+/// transforms stay in the world across reloads. Types defined in the library
+/// must not, since their code goes away with it: keep them in the saved
+/// state, and [`World::unregister`] their storages in `unload`. Build both
+/// with the same compiler and engine version, as Rust types are shared
+/// across the boundary as-is.
+///
+/// The library also gets its own copy of every static in the engine and its
+/// dependencies. When it loads, its GL functions are pointed at the engine's
+/// context and its `log` calls at the engine's logger, see [`HostContext`].
+/// Nothing else is shared: the library has its own [`Name`](crate::name::Name)
+/// interner, global job system, cvars, crash context and GPU deletion queue.
+/// So pass strings rather than names across the boundary, and leave those
+/// systems to the engine's side.
+///
+/// The library is copied before loading, so the build can overwrite the
+/// original while the copy is in use.
+///
+/// ## Example
+/// ```ignore
+/// // game_logic/src/lib.rs, with `crate-type = ["cdylib"]`
+/// fn load(world: &mut World, state: Option<&[u8]>) {
+///     world.register::<Enemy>();
+///     if let Some(state) = state {
+///         restore_enemies(world, state);
+///     }
+/// }
+///
+/// fn update(world: &mut World) {
+///     move_enemies(world);
+/// }
+///
+/// fn unload(world: &mut World) -> Vec<u8> {
+///     let state = save_enemies(world);
+///     world.unregister::<Enemy>();
+///     state
+/// }
+///
+/// nyanko_engine::export_game_api!(load, update, unload);
+///
+/// // the game's executable
+/// let mut game = HotReloader::open("target/debug/libgame_logic.so", &mut world)?;
+/// while !window.should_close() {
+///     if game.update(&mut world) {
+///         log::info!("Reloaded game code ({} times)", game.reload_count());
+///     }
+///     // ... render ...
+/// }
+/// game.shutdown(&mut world);
+/// ```
+pub struct HotReloader {
+    path: PathBuf,
+    library: Option<Library>,
+    api: GameApi,
+    modified: Option<SystemTime>,
+    changed: Option<(SystemTime, Instant)>,
+    last_check: Instant,
+    reloads: u32,
+    copies: u32,
+}
+
+impl HotReloader {
+    /// Loads a game library and calls its `load` with no saved state.
+    pub fn open(path: impl Into<PathBuf>, world: &mut World) -> Result<Self, Errors> {
+        let path = path.into();
+        let modified = modified_time(&path);
+        let mut reloader = Self {
+            path,
+            library: None,
+            api: GameApi {
+                version: GAME_API_VERSION,
+                init: |_| {},
+                load: |_, _| {},
+                update: |_| {},
+                unload: |_| Vec::new(),
+            },
+            modified,
+            changed: None,
+            last_check: Instant::now(),
+            reloads: 0,
+            copies: 0,
+        };
+        let (library, api) = reloader.load_copy()?;
+        (api.load)(world, None);
+        reloader.library = Some(library);
+        reloader.api = api;
+        log::info!("Loaded game code from {}", reloader.path.display());
+        Ok(reloader)
+    }
+
+    /// Returns the path of the library being watched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of times the library was reloaded.
+    pub fn reload_count(&self) -> u32 {
+        self.reloads
+    }
+
+    /// Reloads the library if a new build has finished, then runs the game's
+    /// `update`. Returns `true` if the library was reloaded. A build that
+    /// fails to load is logged and the running code kept.
+    pub fn update(&mut self, world: &mut World) -> bool {
+        let reloaded = self.poll_changes() && self.reload_or_log(world);
+        if self.library.is_some() {
+            (self.api.update)(world);
+        }
+        reloaded
+    }
+
+    /// Reloads the library now, whether or not it changed.
+    pub fn reload(&mut self, world: &mut World) -> Result<(), Errors> {
+        // The new build is loaded before the old one is unloaded, so a broken
+        // build leaves the running game untouched.
+        let (library, api) = self.load_copy()?;
+        let state = match self.library.take() {
+            Some(old) => {
+                let state = (self.api.unload)(world);
+                drop(old);
+                state
+            }
+            None => Vec::new(),
+        };
+        (api.load)(world, Some(&state));
+        self.library = Some(library);
+        self.api = api;
+        self.reloads += 1;
+        Ok(())
+    }
+
+    /// Calls the game's `unload` and closes the library. Call before the
+    /// world is dropped.
+    pub fn shutdown(&mut self, world: &mut World) {
+        if let Some(library) = self.library.take() {
+            (self.api.unload)(world);
+            drop(library);
+        }
+    }
+
+    fn reload_or_log(&mut self, world: &mut World) -> bool {
+        match self.reload(world) {
+            Ok(()) => {
+                log::info!("Reloaded game code from {}", self.path.display());
+                true
+            }
+            Err(error) => {
+                log::error!("{}", error);
+                false
+            }
+        }
+    }
+
+    /// Checks the library's modification time, returning `true` once a
+    /// change has settled.
+    fn poll_changes(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let Some(modified) = modified_time(&self.path) else {
+            return false;
+        };
+        if Some(modified) == self.modified {
+            self.changed = None;
+            return false;
+        }
+        match self.changed {
+            Some((time, since)) if time == modified => {
+                if since.elapsed() < SETTLE_TIME {
+                    return false;
+                }
+                self.modified = Some(modified);
+                self.changed = None;
+                true
+            }
+            _ => {
+                self.changed = Some((modified, Instant::now()));
+                false
+            }
+        }
+    }
+
+    fn load_copy(&mut self) -> Result<(Library, GameApi), Errors> {
+        let error =
+            |message: String| Errors::HotReload(format!("{}: {}", self.path.display(), message));
+        let directory = std::env::temp_dir().join("nyanko-hot-reload");
+        fs::create_dir_all(&directory).map_err(|io| error(io.to_string()))?;
+        let stem = self
+            .path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("game");
+        let extension = self
+            .path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("");
+        self.copies += 1;
+        let copy = directory.join(format!(
+            "{}-{}-{}.{}",
+            stem,
+            std::process::id(),
+            self.copies,
+            extension
+        ));
+        fs::copy(&self.path, &copy).map_err(|io| error(io.to_string()))?;
+        let library = match Library::open(&copy) {
+            Ok(library) => library,
+            Err(open_error) => {
+                let _ = fs::remove_file(&copy);
+                return Err(open_error);
+            }
+        };
+        let api = library.game_api()?;
+        (api.init)(&HostContext::current());
+        Ok((library, api))
+    }
+}
+
+impl Drop for HotReloader {
+    fn drop(&mut self) {
+        // Without `shutdown` the world may still hold values whose drop code
+        // lives in the library, so it has to stay loaded.
+        if let Some(library) = self.library.take() {
+            log::warn!("HotReloader dropped without shutdown, leaving the game library loaded");
+            std::mem::forget(library);
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::ffi::{c_char, c_int, c_void, CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub type Handle = *mut c_void;
+
+    const RTLD_NOW: c_int = 2;
+
+    #[cfg_attr(target_os = "linux", link(name = "dl"))]
+    extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        fn dlclose(handle: *mut c_void) -> c_int;
+        fn dlerror() -> *const c_char;
+    }
+
+    fn last_error() -> String {
+        // Safety: dlerror returns null or a C string valid until the next call.
+        unsafe {
+            let message = dlerror();
+            if message.is_null() {
+                "unknown error".to_string()
+            } else {
+                CStr::from_ptr(message).to_string_lossy().into_owned()
+            }
+        }
+    }
+
+    pub fn open(path: &Path) -> Result<Handle, String> {
+        let path = CString::new(path.as_os_str().as_bytes()).map_err(|error| error.to_string())?;
+        let handle = unsafe { dlopen(path.as_ptr(), RTLD_NOW) };
+        if handle.is_null() {
+            Err(last_error())
+        } else {
+            Ok(handle)
+        }
+    }
+
+    pub fn symbol(handle: Handle, name: &str) -> Result<*const c_void, String> {
+        let name = CString::new(name).map_err(|error| error.to_string())?;
+        let symbol = unsafe { dlsym(handle, name.as_ptr()) };
+        if symbol.is_null() {
+            Err(last_error())
+        } else {
+            Ok(symbol)
+        }
+    }
+
+    pub fn close(handle: Handle) {
+        unsafe { dlclose(handle) };
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::{c_char, c_void, CString};
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub type Handle = *mut c_void;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LoadLibraryW(filename: *const u16) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, name: *const c_char) -> *mut c_void;
+        fn FreeLibrary(module: *mut c_void) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    pub fn open(path: &Path) -> Result<Handle, String> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let handle = unsafe { LoadLibraryW(wide.as_ptr()) };
+        if handle.is_null() {
+            Err(format!("LoadLibrary failed with error {}", unsafe {
+                GetLastError()
+            }))
+        } else {
+            Ok(handle)
+        }
+    }
+
+    pub fn symbol(handle: Handle, name: &str) -> Result<*const c_void, String> {
+        let name = CString::new(name).map_err(|error| error.to_string())?;
+        let symbol = unsafe { GetProcAddress(handle, name.as_ptr()) };
+        if symbol.is_null() {
+            Err(format!("symbol {:?} not found", name))
+        } else {
+            Ok(symbol)
+        }
+    }
+
+    pub fn close(handle: Handle) {
+        unsafe { FreeLibrary(handle) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::sync::Mutex;
+
+    static REQUESTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    extern "C" fn fake_gl_proc_address(name: *const c_char) -> *const c_void {
+        let name = unsafe { CStr::from_ptr(name) };
+        REQUESTED
+            .lock()
+            .unwrap()
+            .push(name.to_string_lossy().into_owned());
+        std::ptr::null()
+    }
+
+    #[test]
+    fn library_gl_functions_come_from_the_host() {
+        init_library(&HostContext {
+            gl_proc_address: fake_gl_proc_address,
+            ..HostContext::current()
+        });
+        let requested = REQUESTED.lock().unwrap();
+        assert!(requested.iter().any(|name| name == "glClear"));
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use glfw::MouseButton;
+
+use super::{Input, Touch};
+use crate::math::*;
+
+/// Touch id used for the mouse when it stands in for a finger.
+pub const MOUSE_TOUCH_ID: u64 = u64::MAX;
+
+/// Thresholds that tell gestures apart.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureSettings {
+    /// How far a finger may move, in window coordinates, and still tap.
+    pub tap_distance: f32,
+    /// Longest press in seconds that still counts as a tap.
+    pub tap_duration: f32,
+    /// How long a finger must rest before it is a long press.
+    pub long_press_duration: f32,
+    /// Slowest lift-off speed, in window coordinates per second, that swipes.
+    pub swipe_velocity: f32,
+    /// Whether the left mouse button acts as a finger and the scroll wheel
+    /// pinches, for testing touch controls on desktop.
+    pub mouse_as_touch: bool,
+    /// How much one scroll step zooms when the mouse acts as touch.
+    pub scroll_zoom: f32,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        Self {
+            tap_distance: 10.0,
+            tap_duration: 0.3,
+            long_press_duration: 0.5,
+            swipe_velocity: 800.0,
+            mouse_as_touch: true,
+            scroll_zoom: 0.1,
+        }
+    }
+}
+
+/// The main direction of a swipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A gesture recognized from touches. Positions are in window coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// A quick touch that barely moved.
+    Tap { position: Vec2 },
+    /// A finger resting in place, sent once when it has rested long enough.
+    LongPress { position: Vec2 },
+    /// One finger dragging, or two fingers moving together.
+    Pan { position: Vec2, delta: Vec2 },
+    /// A fast drag that ended with the finger lifting.
+    Swipe {
+        direction: SwipeDirection,
+        velocity: Vec2,
+    },
+    /// Two fingers spreading or closing; `scale` is the change since the last
+    /// frame, above 1 when zooming in.
+    Pinch { center: Vec2, scale: f32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TrackedTouch {
+    start: Vec2,
+    last: Vec2,
+    held: f32,
+    velocity: Vec2,
+    moved: bool,
+    long_pressed: bool,
+}
+
+/// # Gesture Recognizer
+///
+/// Turns the touches of an [`Input`] into taps, long presses, pans, swipes and
+/// pinches. Update it once per frame after the input frame begins. Gestures
+/// made with two fingers never also produce taps or swipes.
+///
+/// ## Example
+/// ```ignore
+/// let mut gestures = GestureRecognizer::new(GestureSettings::default());
+///
+/// for gesture in gestures.update(window.input(), delta_time) {
+///     match *gesture {
+///         Gesture::Pinch { scale, .. } => camera.zoom *= scale,
+///         Gesture::Pan { delta, .. } => camera.position -= delta,
+///         Gesture::Tap { position } => select_at(position),
+///         _ => {}
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GestureRecognizer {
+    settings: GestureSettings,
+    tracked: HashMap<u64, TrackedTouch>,
+    gestures: Vec<Gesture>,
+    pinch: Option<(Vec2, f32)>,
+    multi_touch: bool,
+}
+
+impl GestureRecognizer {
+    /// Creates a recognizer with the given thresholds.
+    pub fn new(settings: GestureSettings) -> Self {
+        Self {
+            settings,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the thresholds.
+    pub fn settings(&self) -> &GestureSettings {
+        &self.settings
+    }
+
+    /// Returns the thresholds for changing.
+    pub fn settings_mut(&mut self) -> &mut GestureSettings {
+        &mut self.settings
+    }
+
+    /// Returns the gestures recognized by the last update.
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Recognizes this frame's gestures.
+    pub fn update(&mut self, input: &Input, delta_time: f32) -> &[Gesture] {
+        self.gestures.clear();
+        let touches = self.touches(input);
+
+        let lifted: Vec<u64> = self
+            .tracked
+            .keys()
+            .filter(|id| !touches.iter().any(|touch| touch.id == **id))
+            .copied()
+            .collect();
+        for id in lifted {
+            let touch = self.tracked.remove(&id).unwrap();
+            if self.multi_touch {
+                continue;
+            }
+            if !touch.moved && !touch.long_pressed && touch.held <= self.settings.tap_duration {
+                self.gestures.push(Gesture::Tap {
+                    position: touch.last,
+                });
+            } else if touch.moved && touch.velocity.magnitude() >= self.settings.swipe_velocity {
+                self.gestures.push(Gesture::Swipe {
+                    direction: swipe_direction(touch.velocity),
+                    velocity: touch.velocity,
+                });
+            }
+        }
+
+        for touch in &touches {
+            let tracked = self.tracked.entry(touch.id).or_insert(TrackedTouch {
+                start: touch.position,
+                last: touch.position,
+                held: 0.0,
+                velocity: Vec2::zero(),
+                moved: false,
+                long_pressed: false,
+            });
+            let delta = touch.position - tracked.last;
+            if delta_time > 0.0 {
+                // Smoothed so a single jittery frame before lift-off doesn't
+                // decide whether it was a swipe.
+                tracked.velocity = tracked.velocity.lerp(delta / delta_time, 0.5);
+            }
+            tracked.held += delta_time;
+            tracked.last = touch.position;
+            if touch.position.distance(tracked.start) > self.settings.tap_distance {
+                tracked.moved = true;
+            }
+
+            if touches.len() == 1 {
+                if tracked.moved && delta != Vec2::zero() {
+                    self.gestures.push(Gesture::Pan {
+                        position: touch.position,
+                        delta,
+                    });
+                } else if !tracked.moved
+                    && !tracked.long_pressed
+                    && tracked.held >= self.settings.long_press_duration
+                {
+                    tracked.long_pressed = true;
+                    self.gestures.push(Gesture::LongPress {
+                        position: touch.position,
+                    });
+                }
+            }
+        }
+
+        if touches.len() >= 2 {
+            self.multi_touch = true;
+            let (a, b) = (touches[0].position, touches[1].position);
+            let center = (a + b) * 0.5;
+            let distance = a.distance(b);
+            if let Some((previous_center, previous_distance)) = self.pinch {
+                if previous_distance > 0.0 && distance != previous_distance {
+                    self.gestures.push(Gesture::Pinch {
+                        center,
+                        scale: distance / previous_distance,
+                    });
+                }
+                if center != previous_center {
+                    self.gestures.push(Gesture::Pan {
+                        position: center,
+                        delta: center - previous_center,
+                    });
+                }
+            }
+            self.pinch = Some((center, distance));
+        } else {
+            self.pinch = None;
+        }
+        if touches.is_empty() {
+            self.multi_touch = false;
+        }
+
+        if self.settings.mouse_as_touch && input.scroll().y != 0.0 {
+            self.gestures.push(Gesture::Pinch {
+                center: input.cursor_position(),
+                scale: (1.0 + self.settings.scroll_zoom).powf(input.scroll().y),
+            });
+        }
+
+        &self.gestures
+    }
+
+    /// Returns the fingers to recognize, with the mouse standing in for one if
+    /// no real finger is down.
+    fn touches(&self, input: &Input) -> Vec<Touch> {
+        if !input.touches().is_empty() {
+            return input.touches().to_vec();
+        }
+        if self.settings.mouse_as_touch && input.mouse_down(MouseButton::Button1) {
+            return vec![Touch {
+                id: MOUSE_TOUCH_ID,
+                position: input.cursor_position(),
+            }];
+        }
+        Vec::new()
+    }
+}
+
+fn swipe_direction(velocity: Vec2) -> SwipeDirection {
+    if velocity.x.abs() >= velocity.y.abs() {
+        if velocity.x > 0.0 {
+            SwipeDirection::Right
+        } else {
+            SwipeDirection::Left
+        }
+    } else if velocity.y > 0.0 {
+        SwipeDirection::Down
+    } else {
+        SwipeDirection::Up
+    }
+}
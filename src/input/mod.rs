@@ -0,0 +1,318 @@
+pub mod gestures;
+pub mod replay;
+
+use glfw::{Action, Key, MouseButton, WindowEvent};
+
+use crate::graphics::window::DisplayScale;
+use crate::math::*;
+
+/// # Input Frame
+///
+/// The complete input state of one frame: held keys and mouse buttons, the
+/// cursor, and what was scrolled and typed during the frame. Keys and buttons
+/// are stored as GLFW codes so frames can be saved and replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputFrame {
+    /// GLFW key codes held down, sorted.
+    pub keys: Vec<i32>,
+    /// GLFW mouse button codes held down, sorted.
+    pub mouse_buttons: Vec<i32>,
+    /// Cursor position in window coordinates, origin at the top-left.
+    pub cursor: Vec2,
+    /// Scroll offset accumulated during the frame.
+    pub scroll: Vec2,
+    /// Text typed during the frame.
+    pub text: String,
+    /// Fingers on the screen, sorted by id.
+    pub touches: Vec<Touch>,
+}
+
+/// A finger on a touch screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Touch {
+    /// Stays the same from the moment the finger lands until it lifts.
+    pub id: u64,
+    /// Position in window coordinates, origin at the top-left.
+    pub position: Vec2,
+}
+
+/// What happened to a finger, as reported by the platform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The system took the touch away, e.g. for a gesture.
+    Cancelled,
+}
+
+impl Default for InputFrame {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            mouse_buttons: Vec::new(),
+            cursor: vec2(0.0, 0.0),
+            scroll: vec2(0.0, 0.0),
+            text: String::new(),
+            touches: Vec::new(),
+        }
+    }
+}
+
+impl InputFrame {
+    /// Checks if a key code is held.
+    pub fn has_key(&self, code: i32) -> bool {
+        self.keys.binary_search(&code).is_ok()
+    }
+
+    /// Checks if a mouse button code is held.
+    pub fn has_mouse_button(&self, code: i32) -> bool {
+        self.mouse_buttons.binary_search(&code).is_ok()
+    }
+
+    /// Returns the touch with an id, if that finger is down.
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches
+            .binary_search_by_key(&id, |touch| touch.id)
+            .ok()
+            .map(|index| &self.touches[index])
+    }
+}
+
+fn set_code(codes: &mut Vec<i32>, code: i32, down: bool) {
+    match (codes.binary_search(&code), down) {
+        (Err(index), true) => codes.insert(index, code),
+        (Ok(index), false) => {
+            codes.remove(index);
+        }
+        _ => {}
+    }
+}
+
+/// # Input
+///
+/// Keyboard, mouse and touch state built from window events. Events update a pending
+/// frame; [`Input::begin_frame`] publishes it, so queries are stable for the
+/// whole frame and "pressed this frame" compares against the previous frame.
+///
+/// Because a frame is plain data, the whole stream can be recorded and fed back
+/// with [`Input::set_frame`], see [`replay`].
+///
+/// ## Example
+/// ```ignore
+/// window.input_mut().begin_frame();
+/// let input = window.input();
+/// if input.key_pressed(Key::Space) {
+///     player.jump();
+/// }
+/// if input.mouse_down(MouseButton::Button1) {
+///     aim_at(input.cursor_position());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Input {
+    pending: InputFrame,
+    current: InputFrame,
+    previous: InputFrame,
+    display_scale: DisplayScale,
+    primary_touch: Option<u64>,
+}
+
+impl Input {
+    /// Creates an input state with nothing held.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the pending frame from a window event.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Key(key, _, action, _) => {
+                let down = matches!(action, Action::Press | Action::Repeat);
+                set_code(&mut self.pending.keys, *key as i32, down);
+            }
+            WindowEvent::MouseButton(button, action, _) => {
+                let down = matches!(action, Action::Press | Action::Repeat);
+                set_code(&mut self.pending.mouse_buttons, *button as i32, down);
+            }
+            WindowEvent::CursorPos(x, y) => self.pending.cursor = vec2(*x as f32, *y as f32),
+            WindowEvent::Scroll(x, y) => self.pending.scroll += vec2(*x as f32, *y as f32),
+            WindowEvent::Char(character) => self.pending.text.push(*character),
+            _ => {}
+        }
+    }
+
+    /// Updates the pending frame from a touch reported by the platform. The
+    /// first finger down also acts as the left mouse button and moves the
+    /// cursor, so mouse-driven UI works on touch screens.
+    pub fn handle_touch(&mut self, id: u64, phase: TouchPhase, position: Vec2) {
+        let touches = &mut self.pending.touches;
+        let index = touches.binary_search_by_key(&id, |touch| touch.id);
+        match (phase, index) {
+            (TouchPhase::Started | TouchPhase::Moved, Ok(index)) => {
+                touches[index].position = position;
+            }
+            (TouchPhase::Started | TouchPhase::Moved, Err(index)) => {
+                touches.insert(index, Touch { id, position });
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, Ok(index)) => {
+                touches.remove(index);
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, Err(_)) => {}
+        }
+
+        let primary_button = MouseButton::Button1 as i32;
+        match (self.primary_touch, phase) {
+            (None, TouchPhase::Started) => {
+                self.primary_touch = Some(id);
+                self.pending.cursor = position;
+                set_code(&mut self.pending.mouse_buttons, primary_button, true);
+            }
+            (Some(primary), TouchPhase::Started | TouchPhase::Moved) if primary == id => {
+                self.pending.cursor = position;
+            }
+            (Some(primary), TouchPhase::Ended | TouchPhase::Cancelled) if primary == id => {
+                self.primary_touch = None;
+                self.pending.cursor = position;
+                set_code(&mut self.pending.mouse_buttons, primary_button, false);
+            }
+            _ => {}
+        }
+    }
+
+    /// Publishes the events received since the last call as the current frame.
+    pub fn begin_frame(&mut self) {
+        let next = self.pending.clone();
+        self.pending.scroll = vec2(0.0, 0.0);
+        self.pending.text.clear();
+        self.previous = std::mem::replace(&mut self.current, next);
+    }
+
+    /// Replaces the current frame, e.g. with a recorded one. Held state carries
+    /// over into later live frames until real events change it.
+    pub fn set_frame(&mut self, frame: InputFrame) {
+        self.previous = std::mem::replace(&mut self.current, frame.clone());
+        self.pending = InputFrame {
+            scroll: vec2(0.0, 0.0),
+            text: String::new(),
+            ..frame
+        };
+    }
+
+    /// Returns the current frame.
+    pub fn frame(&self) -> &InputFrame {
+        &self.current
+    }
+
+    /// Checks if a key is held.
+    pub fn key_down(&self, key: Key) -> bool {
+        self.current.has_key(key as i32)
+    }
+
+    /// Checks if a key went down this frame.
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.current.has_key(key as i32) && !self.previous.has_key(key as i32)
+    }
+
+    /// Checks if a key was let go this frame.
+    pub fn key_released(&self, key: Key) -> bool {
+        !self.current.has_key(key as i32) && self.previous.has_key(key as i32)
+    }
+
+    /// Holds or releases a key as if it were on a keyboard, from this frame on,
+    /// for on-screen controls.
+    pub fn set_key_held(&mut self, key: Key, down: bool) {
+        set_code(&mut self.current.keys, key as i32, down);
+        set_code(&mut self.pending.keys, key as i32, down);
+    }
+
+    /// Checks if a shortcut such as copy (`C`), cut (`X`) or paste (`V`) was
+    /// pressed this frame: `key` together with Control, or Command on macOS.
+    pub fn shortcut_pressed(&self, key: Key) -> bool {
+        let modifier = if cfg!(target_os = "macos") {
+            self.key_down(Key::LeftSuper) || self.key_down(Key::RightSuper)
+        } else {
+            self.key_down(Key::LeftControl) || self.key_down(Key::RightControl)
+        };
+        modifier && self.key_pressed(key)
+    }
+
+    /// Checks if a mouse button is held.
+    pub fn mouse_down(&self, button: MouseButton) -> bool {
+        self.current.has_mouse_button(button as i32)
+    }
+
+    /// Checks if a mouse button went down this frame.
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.current.has_mouse_button(button as i32)
+            && !self.previous.has_mouse_button(button as i32)
+    }
+
+    /// Checks if a mouse button was let go this frame.
+    pub fn mouse_released(&self, button: MouseButton) -> bool {
+        !self.current.has_mouse_button(button as i32)
+            && self.previous.has_mouse_button(button as i32)
+    }
+
+    /// Returns the cursor position in window coordinates.
+    pub fn cursor_position(&self) -> Vec2 {
+        self.current.cursor
+    }
+
+    /// Returns the cursor position in framebuffer pixels, for picking in the
+    /// rendered image.
+    pub fn cursor_physical(&self) -> Vec2 {
+        self.display_scale.to_physical(self.current.cursor)
+    }
+
+    /// Returns the cursor position in logical pixels, for UI drawn with a UI scale.
+    pub fn cursor_logical(&self) -> Vec2 {
+        self.display_scale.to_logical(self.current.cursor)
+    }
+
+    /// Returns how the window's coordinates relate, as last set by the window.
+    pub fn display_scale(&self) -> DisplayScale {
+        self.display_scale
+    }
+
+    /// Sets how the window's coordinates relate. The window keeps this up to date.
+    pub fn set_display_scale(&mut self, display_scale: DisplayScale) {
+        self.display_scale = display_scale;
+    }
+
+    /// Returns how far the cursor moved since the previous frame.
+    pub fn cursor_delta(&self) -> Vec2 {
+        self.current.cursor - self.previous.cursor
+    }
+
+    /// Returns the scroll offset of this frame.
+    pub fn scroll(&self) -> Vec2 {
+        self.current.scroll
+    }
+
+    /// Returns the text typed this frame.
+    pub fn text(&self) -> &str {
+        &self.current.text
+    }
+
+    /// Returns the fingers on the screen.
+    pub fn touches(&self) -> &[Touch] {
+        &self.current.touches
+    }
+
+    /// Returns the fingers that landed this frame.
+    pub fn touches_started(&self) -> impl Iterator<Item = &Touch> {
+        self.current
+            .touches
+            .iter()
+            .filter(|touch| self.previous.touch(touch.id).is_none())
+    }
+
+    /// Returns the fingers that lifted this frame, at their last position.
+    pub fn touches_ended(&self) -> impl Iterator<Item = &Touch> {
+        self.previous
+            .touches
+            .iter()
+            .filter(|touch| self.current.touch(touch.id).is_none())
+    }
+}
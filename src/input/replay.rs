@@ -0,0 +1,336 @@
+use std::fmt::Write as _;
+use std::fs;
+
+use super::{Input, InputFrame, Touch};
+use crate::custom_errors::Errors;
+use crate::math::*;
+
+const REPLAY_HEADER: &str = "nyanko-replay 1";
+
+/// # Replay
+///
+/// A recorded input stream: the RNG seed and fixed timestep of the run, and
+/// one [`InputFrame`] per simulation step. Feeding the frames back to the same
+/// build with the same seed and timestep reproduces the run exactly, as long
+/// as the simulation only depends on input, [`Time`](crate::time::Time) and
+/// [`Random`](crate::math::Random).
+///
+/// Saved as a line-based text file, one frame per line, that is easy to diff
+/// and to attach to bug reports.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    /// Seconds per simulation step.
+    pub timestep: f32,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Replay {
+    /// Creates an empty replay.
+    pub fn new(seed: u64, timestep: f32) -> Self {
+        Self {
+            seed,
+            timestep,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Returns the length in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 * self.timestep
+    }
+
+    /// Writes the replay to a file.
+    pub fn save(&self, path: &str) -> Result<(), Errors> {
+        fs::write(path, self.to_text())
+            .map_err(|error| Errors::InvalidReplay(format!("{}: {}", path, error)))
+    }
+
+    /// Reads a replay from a file.
+    pub fn load(path: &str) -> Result<Self, Errors> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| Errors::InvalidReplay(format!("{}: {}", path, error)))?;
+        Self::from_text(&text)
+            .map_err(|message| Errors::InvalidReplay(format!("{}: {}", path, message)))
+    }
+
+    /// Serializes the replay. Consecutive identical frames are written once with a repeat count.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\nseed {}\ntimestep {}\n",
+            REPLAY_HEADER, self.seed, self.timestep
+        );
+        let mut index = 0;
+        while index < self.frames.len() {
+            let frame = &self.frames[index];
+            let repeat = self.frames[index..]
+                .iter()
+                .take_while(|other| *other == frame)
+                .count();
+            let join = |codes: &[i32]| {
+                codes
+                    .iter()
+                    .map(|code| code.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            let _ = write!(
+                text,
+                "{} k={} m={} c={},{} s={},{}",
+                repeat,
+                join(&frame.keys),
+                join(&frame.mouse_buttons),
+                frame.cursor.x,
+                frame.cursor.y,
+                frame.scroll.x,
+                frame.scroll.y,
+            );
+            // Touches are left out when there are none, so desktop replays
+            // stay the same.
+            if !frame.touches.is_empty() {
+                let touches = frame
+                    .touches
+                    .iter()
+                    .map(|touch| format!("{}:{},{}", touch.id, touch.position.x, touch.position.y))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                let _ = write!(text, " p={}", touches);
+            }
+            let _ = writeln!(text, " t={}", frame.text.escape_default());
+            index += repeat;
+        }
+        text
+    }
+
+    /// Parses a replay written by [`Replay::to_text`].
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(REPLAY_HEADER) {
+            return Err("not a replay file".to_string());
+        }
+        let mut header = |name: &str| -> Result<String, String> {
+            lines
+                .next()
+                .and_then(|(_, line)| line.strip_prefix(name))
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| format!("missing `{}`", name))
+        };
+        let seed = header("seed")?
+            .parse()
+            .map_err(|_| "invalid seed".to_string())?;
+        let timestep = header("timestep")?
+            .parse()
+            .map_err(|_| "invalid timestep".to_string())?;
+
+        let mut replay = Self::new(seed, timestep);
+        for (number, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (repeat, frame) =
+                parse_frame(line).map_err(|message| format!("line {}: {}", number + 1, message))?;
+            replay.frames.extend(std::iter::repeat_n(frame, repeat));
+        }
+        Ok(replay)
+    }
+}
+
+fn parse_frame(line: &str) -> Result<(usize, InputFrame), String> {
+    let (repeat, rest) = line.split_once(' ').ok_or("missing fields")?;
+    let repeat = repeat.parse().map_err(|_| "invalid repeat count")?;
+    let (fields, text) = rest.split_once(" t=").ok_or("missing text")?;
+
+    let codes = |value: &str| -> Result<Vec<i32>, String> {
+        value
+            .split(',')
+            .filter(|code| !code.is_empty())
+            .map(|code| code.parse().map_err(|_| format!("invalid code `{}`", code)))
+            .collect()
+    };
+    let pair = |value: &str| -> Result<Vec2, String> {
+        let (x, y) = value.split_once(',').ok_or("expected `x,y`")?;
+        match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => Ok(vec2(x, y)),
+            _ => Err(format!("invalid pair `{}`", value)),
+        }
+    };
+
+    let mut frame = InputFrame {
+        text: unescape(text)?,
+        ..InputFrame::default()
+    };
+    for field in fields.split(' ') {
+        match field.split_once('=') {
+            Some(("k", value)) => frame.keys = codes(value)?,
+            Some(("m", value)) => frame.mouse_buttons = codes(value)?,
+            Some(("c", value)) => frame.cursor = pair(value)?,
+            Some(("s", value)) => frame.scroll = pair(value)?,
+            Some(("p", value)) => {
+                frame.touches = value
+                    .split(';')
+                    .filter(|touch| !touch.is_empty())
+                    .map(|touch| {
+                        let (id, position) = touch.split_once(':').ok_or("expected `id:x,y`")?;
+                        let id = id
+                            .parse()
+                            .map_err(|_| format!("invalid touch id `{}`", id))?;
+                        Ok(Touch {
+                            id,
+                            position: pair(position)?,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+            }
+            _ => return Err(format!("unknown field `{}`", field)),
+        }
+    }
+    frame.keys.sort_unstable();
+    frame.mouse_buttons.sort_unstable();
+    frame.touches.sort_unstable_by_key(|touch| touch.id);
+    Ok((repeat, frame))
+}
+
+/// Reverses [`str::escape_default`].
+fn unescape(text: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('u') => {
+                let code: String = chars
+                    .by_ref()
+                    .skip_while(|c| *c == '{')
+                    .take_while(|c| *c != '}')
+                    .collect();
+                let value = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or("invalid unicode escape")?;
+                result.push(value);
+            }
+            Some(other) => result.push(other),
+            None => return Err("dangling escape".to_string()),
+        }
+    }
+    Ok(result)
+}
+
+/// What an [`InputReplay`] is doing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayState {
+    Idle,
+    Recording,
+    Playing,
+    /// Playback reached the last frame.
+    Finished,
+}
+
+/// # Input Replay
+///
+/// Records the input of every fixed simulation step, or plays a recording back
+/// in place of live input. Call [`InputReplay::step`] once per fixed step,
+/// after the live input frame has begun and before the simulation reads it.
+///
+/// ## Example
+/// ```ignore
+/// let mut replay = InputReplay::new();
+/// replay.start_recording(world.random().seed(), 1.0 / 60.0);
+///
+/// // Every fixed step:
+/// replay.step(window.input_mut());
+/// simulate(&mut world, window.input(), 1.0 / 60.0);
+///
+/// replay.stop().save("bug-1234.replay")?;
+///
+/// // Later, to reproduce:
+/// let recording = Replay::load("bug-1234.replay")?;
+/// world.random_mut().reseed(recording.seed);
+/// replay.play(recording);
+/// ```
+#[derive(Clone, Debug)]
+pub struct InputReplay {
+    state: ReplayState,
+    replay: Replay,
+    position: usize,
+}
+
+impl InputReplay {
+    /// Creates an idle replay controller.
+    pub fn new() -> Self {
+        Self {
+            state: ReplayState::Idle,
+            replay: Replay::default(),
+            position: 0,
+        }
+    }
+
+    /// Returns what the controller is doing.
+    pub fn state(&self) -> ReplayState {
+        self.state
+    }
+
+    /// Returns the recording being written or played.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns the index of the next frame to record or play.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Starts a new recording.
+    pub fn start_recording(&mut self, seed: u64, timestep: f32) {
+        self.replay = Replay::new(seed, timestep);
+        self.position = 0;
+        self.state = ReplayState::Recording;
+    }
+
+    /// Starts playing a recording from the beginning.
+    pub fn play(&mut self, replay: Replay) {
+        self.replay = replay;
+        self.position = 0;
+        self.state = ReplayState::Playing;
+    }
+
+    /// Stops recording or playback, returning the recording.
+    pub fn stop(&mut self) -> Replay {
+        self.state = ReplayState::Idle;
+        self.position = 0;
+        std::mem::take(&mut self.replay)
+    }
+
+    /// Records the current input frame, or replaces it with the next recorded one.
+    pub fn step(&mut self, input: &mut Input) {
+        match self.state {
+            ReplayState::Recording => {
+                self.replay.frames.push(input.frame().clone());
+                self.position += 1;
+            }
+            ReplayState::Playing => match self.replay.frames.get(self.position) {
+                Some(frame) => {
+                    input.set_frame(frame.clone());
+                    self.position += 1;
+                }
+                None => {
+                    input.set_frame(InputFrame::default());
+                    self.state = ReplayState::Finished;
+                }
+            },
+            ReplayState::Idle | ReplayState::Finished => {}
+        }
+    }
+}
+
+impl Default for InputReplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
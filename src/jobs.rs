@@ -0,0 +1,493 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+static NEXT_SYSTEM_ID: AtomicUsize = AtomicUsize::new(0);
+static GLOBAL: OnceLock<Arc<JobSystem>> = OnceLock::new();
+static IO: OnceLock<Arc<JobSystem>> = OnceLock::new();
+
+/// Worker threads of the [`JobSystem::io`] pool.
+const IO_THREADS: usize = 2;
+
+thread_local! {
+    /// The job system and queue index of the current thread, if it is a worker.
+    static WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+    /// How many job panics this thread is currently catching.
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Returns whether a panic on this thread would be caught by the job system,
+/// to be handed to whoever waits for the job.
+pub(crate) fn catching_panic() -> bool {
+    CATCHING.with(|catching| catching.get() > 0)
+}
+
+/// Runs `f`, catching a panic so it can be handed to whoever waits for it.
+fn catch<R>(f: impl FnOnce() -> R) -> thread::Result<R> {
+    struct Catching;
+    impl Drop for Catching {
+        fn drop(&mut self) {
+            CATCHING.with(|catching| catching.set(catching.get() - 1));
+        }
+    }
+
+    CATCHING.with(|catching| catching.set(catching.get() + 1));
+    let _catching = Catching;
+    panic::catch_unwind(AssertUnwindSafe(f))
+}
+
+/// Re-raises a panic caught by [`catch`] on the thread waiting for it.
+fn resume(payload: Box<dyn Any + Send>) -> ! {
+    crate::crash::report_resumed(payload.as_ref());
+    panic::resume_unwind(payload)
+}
+
+/// State shared between the job system and its worker threads.
+struct Shared {
+    id: usize,
+    local_queues: Vec<Mutex<VecDeque<Job>>>,
+    injector: Mutex<VecDeque<Job>>,
+    queued: AtomicUsize,
+    frame_pending: AtomicUsize,
+    sleep_lock: Mutex<()>,
+    wake: Condvar,
+    shutdown: AtomicBool,
+}
+
+impl Shared {
+    /// Queues a job, preferring the current worker's own queue.
+    fn push(&self, job: Job) {
+        match WORKER.with(|w| w.get()) {
+            Some((id, index)) if id == self.id => {
+                self.local_queues[index]
+                    .lock()
+                    .expect("Job queue poisoned")
+                    .push_back(job);
+            }
+            _ => {
+                self.injector
+                    .lock()
+                    .expect("Job queue poisoned")
+                    .push_back(job);
+            }
+        }
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        let _guard = self.sleep_lock.lock().expect("Job sleep lock poisoned");
+        self.wake.notify_one();
+    }
+
+    /// Takes the next job: own queue first, then the injector, then steals from other workers.
+    fn find_job(&self) -> Option<Job> {
+        let local = WORKER.with(|w| w.get()).filter(|(id, _)| *id == self.id);
+
+        let job = local
+            .and_then(|(_, index)| {
+                self.local_queues[index]
+                    .lock()
+                    .expect("Job queue poisoned")
+                    .pop_front()
+            })
+            .or_else(|| {
+                self.injector
+                    .lock()
+                    .expect("Job queue poisoned")
+                    .pop_front()
+            })
+            .or_else(|| {
+                let start = local.map(|(_, index)| index + 1).unwrap_or(0);
+                let count = self.local_queues.len();
+                (0..count)
+                    .map(|offset| (start + offset) % count)
+                    .find_map(|victim| {
+                        self.local_queues[victim]
+                            .lock()
+                            .expect("Job queue poisoned")
+                            .pop_back()
+                    })
+            });
+
+        if job.is_some() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        job
+    }
+
+    /// Runs queued jobs on the current thread until the condition holds.
+    fn help_until(&self, done: impl Fn() -> bool) {
+        while !done() {
+            match self.find_job() {
+                Some(job) => job(),
+                None => thread::yield_now(),
+            }
+        }
+    }
+
+    /// Main loop of a worker thread.
+    fn worker_loop(&self, index: usize) {
+        WORKER.with(|w| w.set(Some((self.id, index))));
+        loop {
+            if let Some(job) = self.find_job() {
+                job();
+                continue;
+            }
+
+            let guard = self.sleep_lock.lock().expect("Job sleep lock poisoned");
+            if self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            if self.queued.load(Ordering::SeqCst) == 0 {
+                drop(self.wake.wait(guard).expect("Job sleep lock poisoned"));
+            }
+        }
+    }
+}
+
+/// # Job System
+///
+/// A work-stealing thread pool shared by the engine and game code. Each worker
+/// owns a queue; jobs spawned from a worker go to its own queue and idle workers
+/// steal from the back of busy ones. Threads that wait on jobs (scopes, handles,
+/// frame tasks) run queued work while they wait instead of blocking.
+///
+/// The engine runs schedule stages, light culling, image decoding and
+/// streamed asset loads on it.
+///
+/// ## Example
+/// ```ignore
+/// let jobs = JobSystem::global();
+///
+/// let handle = jobs.spawn(|| load_level_data("levels/forest.ron"));
+///
+/// jobs.par_for_each_mut(&mut particles, |particle| particle.update(dt));
+///
+/// let level = handle.wait();
+/// ```
+pub struct JobSystem {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl JobSystem {
+    /// Creates a job system with one worker per hardware thread, minus the main thread.
+    pub fn new() -> Self {
+        let threads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self::with_threads(threads.saturating_sub(1).max(1))
+    }
+
+    /// Creates a job system with a fixed number of worker threads.
+    pub fn with_threads(thread_count: usize) -> Self {
+        let thread_count = thread_count.max(1);
+        let shared = Arc::new(Shared {
+            id: NEXT_SYSTEM_ID.fetch_add(1, Ordering::Relaxed),
+            local_queues: (0..thread_count)
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+            injector: Mutex::new(VecDeque::new()),
+            queued: AtomicUsize::new(0),
+            frame_pending: AtomicUsize::new(0),
+            sleep_lock: Mutex::new(()),
+            wake: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let workers = (0..thread_count)
+            .map(|index| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("nyanko-worker-{}", index))
+                    .spawn(move || shared.worker_loop(index))
+                    .expect("Failed to spawn job worker thread")
+            })
+            .collect();
+
+        Self { shared, workers }
+    }
+
+    /// Returns the engine-wide job system, creating it on first use.
+    pub fn global() -> Arc<JobSystem> {
+        Arc::clone(GLOBAL.get_or_init(|| Arc::new(JobSystem::new())))
+    }
+
+    /// Returns the engine-wide pool for blocking file reads, creating it on
+    /// first use. Its few threads mostly wait on the disk, so streaming never
+    /// ties up the workers that run systems.
+    pub fn io() -> Arc<JobSystem> {
+        Arc::clone(IO.get_or_init(|| Arc::new(JobSystem::with_threads(IO_THREADS))))
+    }
+
+    /// Returns the number of worker threads.
+    pub fn thread_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Spawns a background job and returns a handle to its result.
+    pub fn spawn<T, F>(&self, f: F) -> JobHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let slot = Arc::new(JobSlot {
+            result: Mutex::new(None),
+            finished: AtomicBool::new(false),
+        });
+        let job_slot = Arc::clone(&slot);
+        self.shared.push(Box::new(move || {
+            let result = catch(f);
+            *job_slot.result.lock().expect("Job result lock poisoned") = Some(result);
+            job_slot.finished.store(true, Ordering::Release);
+        }));
+
+        JobHandle {
+            slot,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Spawns a job that must finish before the current frame ends.
+    pub fn spawn_frame<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let shared = Arc::clone(&self.shared);
+        shared.frame_pending.fetch_add(1, Ordering::SeqCst);
+        self.shared.push(Box::new(move || {
+            if catch(f).is_err() {
+                log::error!("A frame-scoped job panicked");
+            }
+            shared.frame_pending.fetch_sub(1, Ordering::SeqCst);
+        }));
+    }
+
+    /// Waits for every frame-scoped job, running queued work on this thread meanwhile.
+    pub fn end_frame(&self) {
+        let shared = &self.shared;
+        shared.help_until(|| shared.frame_pending.load(Ordering::SeqCst) == 0);
+    }
+
+    /// Runs jobs that may borrow from the caller's stack and waits for all of them.
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        let scope = Scope {
+            shared: &self.shared,
+            state: Arc::new(ScopeState {
+                pending: AtomicUsize::new(0),
+                panic: Mutex::new(None),
+            }),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+
+        let result = catch(|| f(&scope));
+        let state = &scope.state;
+        self.shared
+            .help_until(|| state.pending.load(Ordering::Acquire) == 0);
+
+        let job_panic = state
+            .panic
+            .lock()
+            .expect("Scope panic lock poisoned")
+            .take();
+        match (result, job_panic) {
+            (Err(payload), _) | (Ok(_), Some(payload)) => resume(payload),
+            (Ok(result), None) => result,
+        }
+    }
+
+    /// Calls `f` on every item in parallel.
+    pub fn par_for_each<T, F>(&self, items: &[T], f: F)
+    where
+        T: Sync,
+        F: Fn(&T) + Sync,
+    {
+        let chunk_size = self.chunk_size(items.len());
+        let f = &f;
+        self.scope(|scope| {
+            for chunk in items.chunks(chunk_size) {
+                scope.spawn(move || chunk.iter().for_each(f));
+            }
+        });
+    }
+
+    /// Calls `f` on every item in parallel, with mutable access.
+    pub fn par_for_each_mut<T, F>(&self, items: &mut [T], f: F)
+    where
+        T: Send,
+        F: Fn(&mut T) + Sync,
+    {
+        let chunk_size = self.chunk_size(items.len());
+        let f = &f;
+        self.scope(|scope| {
+            for chunk in items.chunks_mut(chunk_size) {
+                scope.spawn(move || chunk.iter_mut().for_each(f));
+            }
+        });
+    }
+
+    /// Maps every item in parallel, keeping the input order.
+    pub fn par_map<T, U, F>(&self, items: &[T], f: F) -> Vec<U>
+    where
+        T: Sync,
+        U: Send,
+        F: Fn(&T) -> U + Sync,
+    {
+        let chunk_size = self.chunk_size(items.len());
+        let mut output: Vec<Vec<U>> = items.chunks(chunk_size).map(|_| Vec::new()).collect();
+        let f = &f;
+        self.scope(|scope| {
+            for (chunk, out) in items.chunks(chunk_size).zip(output.iter_mut()) {
+                scope.spawn(move || out.extend(chunk.iter().map(f)));
+            }
+        });
+        output.into_iter().flatten().collect()
+    }
+
+    /// Picks a chunk size giving each worker a few chunks to balance uneven work.
+    fn chunk_size(&self, len: usize) -> usize {
+        let chunks = (self.thread_count() + 1) * 4;
+        len.div_ceil(chunks).max(1)
+    }
+}
+
+impl Default for JobSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for JobSystem {
+    fn drop(&mut self) {
+        {
+            let _guard = self
+                .shared
+                .sleep_lock
+                .lock()
+                .expect("Job sleep lock poisoned");
+            self.shared.shutdown.store(true, Ordering::SeqCst);
+            self.shared.wake.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Result storage for a spawned job.
+struct JobSlot<T> {
+    result: Mutex<Option<thread::Result<T>>>,
+    finished: AtomicBool,
+}
+
+/// # Job Handle
+///
+/// A handle to the result of a job started with `JobSystem::spawn`.
+pub struct JobHandle<T> {
+    slot: Arc<JobSlot<T>>,
+    shared: Arc<Shared>,
+}
+
+impl<T> JobHandle<T> {
+    /// Checks if the job has finished.
+    pub fn is_finished(&self) -> bool {
+        self.slot.finished.load(Ordering::Acquire)
+    }
+
+    /// Takes the result if the job has finished, without blocking.
+    pub fn try_take(&mut self) -> Option<T> {
+        if !self.is_finished() {
+            return None;
+        }
+        match self
+            .slot
+            .result
+            .lock()
+            .expect("Job result lock poisoned")
+            .take()?
+        {
+            Ok(value) => Some(value),
+            Err(payload) => resume(payload),
+        }
+    }
+
+    /// Waits for the job to finish, running queued work on this thread meanwhile.
+    pub fn wait(mut self) -> T {
+        let slot = &self.slot;
+        self.shared
+            .help_until(|| slot.finished.load(Ordering::Acquire));
+        self.try_take().expect("Job result was already taken")
+    }
+}
+
+/// Bookkeeping for the jobs spawned inside one scope.
+struct ScopeState {
+    pending: AtomicUsize,
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+/// # Scope
+///
+/// Spawns jobs that may borrow data living outside the scope. Created by
+/// `JobSystem::scope`, which waits for every job before returning.
+pub struct Scope<'scope, 'env: 'scope> {
+    shared: &'scope Arc<Shared>,
+    state: Arc<ScopeState>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Spawns a job that may borrow from the enclosing scope.
+    pub fn spawn<F>(&'scope self, f: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        let state = Arc::clone(&self.state);
+        state.pending.fetch_add(1, Ordering::AcqRel);
+
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            if let Err(payload) = catch(f) {
+                state
+                    .panic
+                    .lock()
+                    .expect("Scope panic lock poisoned")
+                    .get_or_insert(payload);
+            }
+            state.pending.fetch_sub(1, Ordering::AcqRel);
+        });
+
+        // SAFETY: `JobSystem::scope` doesn't return until `pending` drops back to
+        // zero, so the job can't outlive anything it borrows.
+        let job: Job = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Job>(job) };
+        self.shared.push(job);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_panics_are_marked_as_caught_until_handed_back() {
+        let jobs = JobSystem::with_threads(2);
+        assert!(jobs.spawn(catching_panic).wait());
+        assert!(jobs.scope(|_| catching_panic()));
+        assert!(!catching_panic());
+
+        let caught = panic::catch_unwind(AssertUnwindSafe(|| {
+            jobs.spawn(|| panic!("job failed")).wait()
+        }));
+        assert!(caught.is_err());
+        assert!(!catching_panic());
+    }
+}
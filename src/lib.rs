@@ -1,3 +1,24 @@
+pub mod ai;
+pub mod arena;
+pub mod audio;
+pub mod benchmark;
+pub mod crash;
+pub mod cvars;
 pub mod custom_errors;
+pub mod ecs;
+pub mod gameplay;
 pub mod graphics;
-pub mod logger;
\ No newline at end of file
+pub mod hot_reload;
+pub mod input;
+pub mod jobs;
+pub mod localization;
+pub mod logger;
+pub mod math;
+pub mod memory;
+pub mod name;
+pub mod pool;
+pub mod scene;
+pub mod state_machine;
+pub mod telemetry;
+pub mod time;
+pub mod voxel;
\ No newline at end of file
@@ -0,0 +1,640 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::custom_errors::Errors;
+
+/// # String Table
+///
+/// Translated strings for one language, keyed by message id.
+///
+/// Two formats can be loaded:
+/// - A subset of [Fluent](https://projectfluent.org) (`.ftl`): `key = value`
+///   messages, indented continuation lines, `.attribute = value` lines (stored
+///   as `key.attribute`), `-term = value` terms and `#` comments. Select
+///   expressions and functions are not supported.
+/// - JSON (`.json`): an object of strings. Nested objects are flattened with
+///   dots, so `{"menu": {"play": "Play"}}` defines `menu.play`.
+///
+/// Values may contain placeables, filled in by [`interpolate`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+}
+
+impl StringTable {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a table, picking the format from the file extension.
+    pub fn load(path: &str) -> Result<Self, Errors> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| Errors::InvalidStringTable(format!("{}: {}", path, error)))?;
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str());
+        match extension {
+            Some("ftl") => Self::from_ftl(&source),
+            Some("json") => Self::from_json(&source),
+            _ => Err(Errors::InvalidStringTable(format!(
+                "{}: expected a .ftl or .json file",
+                path
+            ))),
+        }
+        .map_err(|error| match error {
+            Errors::InvalidStringTable(message) => {
+                Errors::InvalidStringTable(format!("{}: {}", path, message))
+            }
+            error => error,
+        })
+    }
+
+    /// Parses Fluent source.
+    pub fn from_ftl(source: &str) -> Result<Self, Errors> {
+        let mut table = Self::new();
+        let mut message: Option<String> = None;
+        let mut current: Option<(String, Vec<String>)> = None;
+
+        let finish = |table: &mut Self, current: &mut Option<(String, Vec<String>)>| {
+            if let Some((key, lines)) = current.take() {
+                table.insert(&key, &join_ftl_lines(&lines));
+            }
+        };
+
+        for (number, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if line.starts_with('#') {
+                finish(&mut table, &mut current);
+                message = None;
+                continue;
+            }
+            if trimmed.is_empty() {
+                if let Some((_, lines)) = current.as_mut() {
+                    lines.push(String::new());
+                }
+                continue;
+            }
+
+            let indented = line.starts_with(char::is_whitespace);
+            if indented && trimmed.starts_with('.') && message.is_some() {
+                let Some((name, value)) = trimmed[1..].split_once('=') else {
+                    return Err(ftl_error(number, "expected `.attribute = value`"));
+                };
+                finish(&mut table, &mut current);
+                let key = format!("{}.{}", message.as_deref().unwrap_or_default(), name.trim());
+                current = Some((key, vec![value.trim().to_string()]));
+            } else if indented {
+                match current.as_mut() {
+                    Some((_, lines)) => lines.push(line.to_string()),
+                    None => return Err(ftl_error(number, "unexpected indented line")),
+                }
+            } else {
+                let Some((key, value)) = line.split_once('=') else {
+                    return Err(ftl_error(number, "expected `key = value`"));
+                };
+                let key = key.trim();
+                let name = key.strip_prefix('-').unwrap_or(key);
+                if name.is_empty()
+                    || !name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(ftl_error(number, &format!("invalid identifier `{}`", key)));
+                }
+                finish(&mut table, &mut current);
+                message = Some(key.to_string());
+                current = Some((key.to_string(), vec![value.trim().to_string()]));
+            }
+        }
+        finish(&mut table, &mut current);
+        Ok(table)
+    }
+
+    /// Parses a JSON object of strings.
+    pub fn from_json(source: &str) -> Result<Self, Errors> {
+        let mut table = Self::new();
+        let mut parser = JsonParser {
+            chars: source.chars().peekable(),
+        };
+        parser.skip_whitespace();
+        parser.object("", &mut table)?;
+        parser.skip_whitespace();
+        if parser.chars.next().is_some() {
+            return Err(json_error("unexpected data after the top-level object"));
+        }
+        Ok(table)
+    }
+
+    /// Returns the string for a key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    /// Adds or replaces a string.
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.strings.insert(key.to_string(), value.to_string());
+    }
+
+    /// Returns every key.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.strings.keys().map(String::as_str)
+    }
+
+    /// Returns the number of strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Checks if the table has no strings.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// Joins a Fluent message's lines, removing the indentation common to the
+/// continuation lines and trailing blank lines.
+fn join_ftl_lines(lines: &[String]) -> String {
+    let indent = lines[1..]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut parts: Vec<&str> = Vec::with_capacity(lines.len());
+    parts.push(&lines[0]);
+    for line in &lines[1..] {
+        parts.push(line.get(indent..).unwrap_or("").trim_end());
+    }
+    while parts.last().is_some_and(|part| part.is_empty()) {
+        parts.pop();
+    }
+    if parts.first().is_some_and(|part| part.is_empty()) {
+        parts.remove(0);
+    }
+    parts.join("\n")
+}
+
+fn ftl_error(line: usize, message: &str) -> Errors {
+    Errors::InvalidStringTable(format!("line {}: {}", line + 1, message))
+}
+
+fn json_error(message: &str) -> Errors {
+    Errors::InvalidStringTable(message.to_string())
+}
+
+/// A minimal JSON reader that only accepts objects and strings.
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Errors> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(json_error(&format!(
+                "expected `{}`, found `{}`",
+                expected, c
+            ))),
+            None => Err(json_error(&format!(
+                "expected `{}`, found the end",
+                expected
+            ))),
+        }
+    }
+
+    fn object(&mut self, prefix: &str, table: &mut StringTable) -> Result<(), Errors> {
+        self.expect('{')?;
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(());
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            let key = if prefix.is_empty() {
+                key
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            self.expect(':')?;
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('{') => self.object(&key, table)?,
+                Some('"') => {
+                    let value = self.string()?;
+                    table.insert(&key, &value);
+                }
+                _ => {
+                    return Err(json_error(&format!(
+                        "`{}` must be a string or an object",
+                        key
+                    )))
+                }
+            }
+
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(()),
+                _ => return Err(json_error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Errors> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(result),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some('r') => result.push('\r'),
+                    Some('b') => result.push('\u{8}'),
+                    Some('f') => result.push('\u{c}'),
+                    Some('u') => {
+                        let high = self.hex4()?;
+                        let code = if (0xd800..0xdc00).contains(&high) {
+                            if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                                return Err(json_error("unpaired surrogate escape"));
+                            }
+                            let low = self.hex4()?;
+                            0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+                        } else {
+                            high
+                        };
+                        result.push(
+                            char::from_u32(code)
+                                .ok_or_else(|| json_error("invalid unicode escape"))?,
+                        );
+                    }
+                    Some(c @ ('"' | '\\' | '/')) => result.push(c),
+                    _ => return Err(json_error("invalid escape sequence")),
+                },
+                Some(c) => result.push(c),
+                None => return Err(json_error("unterminated string")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, Errors> {
+        let digits: String = (0..4).filter_map(|_| self.chars.next()).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| json_error("invalid unicode escape"))
+    }
+}
+
+/// Fills the placeables of a localized string.
+///
+/// - `{ $name }` (Fluent) or `{name}` is replaced by the matching argument.
+/// - `{ -term }` is replaced by the term's value from `table`.
+/// - `{ "text" }` is replaced by the literal text.
+///
+/// Anything else, including placeables without a matching argument, is left as is.
+///
+/// ```ignore
+/// let text = interpolate("{ $player } found { $count } coins", &[("player", &"Mio"), ("count", &12)], None);
+/// ```
+pub fn interpolate(
+    template: &str,
+    args: &[(&str, &dyn Display)],
+    table: Option<&StringTable>,
+) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        let inner = rest[1..end].trim();
+        let name = inner.strip_prefix('$').unwrap_or(inner);
+        let literal = inner
+            .strip_prefix('"')
+            .and_then(|inner| inner.strip_suffix('"'));
+        let argument = args.iter().find(|(key, _)| *key == name);
+        let term = inner
+            .starts_with('-')
+            .then(|| table.and_then(|table| table.get(inner)))
+            .flatten();
+
+        match (argument, term, literal) {
+            (Some((_, value)), _, _) => result.push_str(&value.to_string()),
+            (_, Some(term), _) => result.push_str(&interpolate(term, args, None)),
+            (_, _, Some(literal)) => result.push_str(literal),
+            _ => result.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Sent through [`Localization::subscribe`] channels whenever the active strings change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanguageChanged {
+    pub previous: Option<String>,
+    pub language: String,
+}
+
+/// # Localization
+///
+/// Holds a string table per language and looks up keys in the active one,
+/// falling back to a fallback language and finally to the key itself, so
+/// missing translations are visible but never fatal.
+///
+/// Switching language (or reloading the active table) bumps
+/// [`Localization::revision`] and notifies subscribers, so UI can re-run its
+/// text layout.
+///
+/// ## Example
+/// ```ignore
+/// let mut localization = Localization::new();
+/// localization.load_language("en", "assets/locale/en.ftl")?;
+/// localization.load_language("ja", "assets/locale/ja.json")?;
+/// localization.set_fallback("en");
+/// localization.set_language("ja")?;
+///
+/// let changes = localization.subscribe();
+/// let title = localization.format("greeting", &[("name", &player.name)]);
+///
+/// // Later, in the UI update:
+/// if changes.try_iter().count() > 0 {
+///     rebuild_labels(&localization);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Localization {
+    tables: HashMap<String, StringTable>,
+    language: Option<String>,
+    fallback: Option<String>,
+    revision: u64,
+    subscribers: Vec<Sender<LanguageChanged>>,
+}
+
+impl Localization {
+    /// Creates an empty localization with no languages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a language's strings. Replacing the active or fallback
+    /// language notifies subscribers, which makes hot-reloading edited files live.
+    pub fn add_language(&mut self, language: &str, table: StringTable) {
+        self.tables.insert(language.to_string(), table);
+        if self.language.as_deref() == Some(language) || self.fallback.as_deref() == Some(language)
+        {
+            self.notify(self.language.clone());
+        }
+    }
+
+    /// Loads a language's strings from an `.ftl` or `.json` file.
+    pub fn load_language(&mut self, language: &str, path: &str) -> Result<(), Errors> {
+        let table = StringTable::load(path)?;
+        self.add_language(language, table);
+        Ok(())
+    }
+
+    /// Returns the loaded language codes.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(String::as_str)
+    }
+
+    /// Returns a language's strings.
+    pub fn table(&self, language: &str) -> Option<&StringTable> {
+        self.tables.get(language)
+    }
+
+    /// Returns the active language.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// Switches the active language and notifies subscribers.
+    pub fn set_language(&mut self, language: &str) -> Result<(), Errors> {
+        if !self.tables.contains_key(language) {
+            return Err(Errors::InvalidStringTable(format!(
+                "language `{}` is not loaded",
+                language
+            )));
+        }
+        if self.language.as_deref() != Some(language) {
+            let previous = self.language.replace(language.to_string());
+            self.notify(previous);
+        }
+        Ok(())
+    }
+
+    /// Returns the language used for keys missing from the active one.
+    pub fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    /// Sets the language used for keys missing from the active one.
+    pub fn set_fallback(&mut self, language: &str) {
+        self.fallback = Some(language.to_string());
+    }
+
+    /// Returns a counter that increases whenever the active strings change.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns a channel that receives every language change.
+    pub fn subscribe(&mut self) -> Receiver<LanguageChanged> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Returns the translation of a key, or `None` if no loaded language has it.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        [self.language.as_deref(), self.fallback.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|language| self.tables.get(language))
+            .find_map(|table| table.get(key))
+    }
+
+    /// Returns the translation of a key, or the key itself if it is missing.
+    pub fn tr(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    /// Returns the translation of a key with its placeables filled in.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        match self.get(key) {
+            Some(template) => interpolate(template, args, self.active_table()),
+            None => {
+                log::warn!("Missing localized string `{}`", key);
+                key.to_string()
+            }
+        }
+    }
+
+    fn active_table(&self) -> Option<&StringTable> {
+        self.language
+            .as_deref()
+            .and_then(|language| self.tables.get(language))
+    }
+
+    fn notify(&mut self, previous: Option<String>) {
+        self.revision += 1;
+        let Some(language) = self.language.clone() else {
+            return;
+        };
+        let change = LanguageChanged { previous, language };
+        self.subscribers
+            .retain(|subscriber| subscriber.send(change.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_invalid(result: Result<StringTable, Errors>, expected: &str) {
+        match result {
+            Err(Errors::InvalidStringTable(message)) => {
+                assert!(message.contains(expected), "{}", message)
+            }
+            other => panic!(
+                "expected an error containing `{}`, got {:?}",
+                expected, other
+            ),
+        }
+    }
+
+    #[test]
+    fn ftl_messages_attributes_and_terms() {
+        let table = StringTable::from_ftl(
+            "# Menu\n\
+             greeting = Hello, { $name }!\n\
+             -brand = Nyanko\n\
+             about = About { -brand }\n\
+             \x20   .tooltip = Learn more\n\
+             long =\n\
+             \x20   First line\n\
+             \x20     indented\n\
+             \n\
+             \x20   Last line\n\
+             \n\
+             ## Settings\n\
+             volume=Volume\n",
+        )
+        .unwrap();
+        assert_eq!(table.len(), 6);
+        assert_eq!(table.get("greeting"), Some("Hello, { $name }!"));
+        assert_eq!(table.get("-brand"), Some("Nyanko"));
+        assert_eq!(table.get("about"), Some("About { -brand }"));
+        assert_eq!(table.get("about.tooltip"), Some("Learn more"));
+        assert_eq!(
+            table.get("long"),
+            Some("First line\n  indented\n\nLast line")
+        );
+        assert_eq!(table.get("volume"), Some("Volume"));
+    }
+
+    #[test]
+    fn ftl_errors_name_the_line() {
+        assert_invalid(
+            StringTable::from_ftl("ok = fine\nno value here"),
+            "line 2: expected `key = value`",
+        );
+        assert_invalid(
+            StringTable::from_ftl("  stray = indent"),
+            "line 1: unexpected indented line",
+        );
+        assert_invalid(
+            StringTable::from_ftl("ok = fine\n\nbad key = x"),
+            "line 3: invalid identifier `bad key`",
+        );
+        assert_invalid(
+            StringTable::from_ftl("ok = fine\n    .broken"),
+            "line 2: expected `.attribute = value`",
+        );
+    }
+
+    #[test]
+    fn json_objects_are_flattened() {
+        let table = StringTable::from_json(
+            r#" {
+                "title": "Nyanko",
+                "menu": { "play": "Play", "options": { "audio": "Audio" }, "empty": {} },
+                "escapes": "a\"b\\c\/d\n\t\u00e9\ud83d\ude00"
+            } "#,
+        )
+        .unwrap();
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.get("title"), Some("Nyanko"));
+        assert_eq!(table.get("menu.play"), Some("Play"));
+        assert_eq!(table.get("menu.options.audio"), Some("Audio"));
+        assert_eq!(table.get("escapes"), Some("a\"b\\c/d\n\t\u{e9}\u{1f600}"));
+        assert!(StringTable::from_json("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        assert_invalid(
+            StringTable::from_json(r#"{"count": 3}"#),
+            "`count` must be a string or an object",
+        );
+        assert_invalid(
+            StringTable::from_json(r#"{"a": "b"} x"#),
+            "after the top-level",
+        );
+        assert_invalid(StringTable::from_json(r#"{"a": "b"#), "unterminated string");
+        assert_invalid(
+            StringTable::from_json(r#"{"a": "b" "c": "d"}"#),
+            "expected `,`",
+        );
+        assert_invalid(StringTable::from_json(r#"{"a": "\q"}"#), "invalid escape");
+        assert_invalid(
+            StringTable::from_json(r#"{"a": "\ud83d"}"#),
+            "unpaired surrogate",
+        );
+        assert_invalid(
+            StringTable::from_json(r#"["a"]"#),
+            "expected `{`, found `[`",
+        );
+    }
+
+    #[test]
+    fn placeables_are_filled_from_arguments_and_terms() {
+        let table = StringTable::from_ftl("-brand = Nyanko { $edition }").unwrap();
+        let text = interpolate(
+            "{ $player } found {count} coins in { -brand }{ \"!\" } { $missing }",
+            &[("player", &"Mio"), ("count", &12), ("edition", &"Deluxe")],
+            Some(&table),
+        );
+        assert_eq!(text, "Mio found 12 coins in Nyanko Deluxe! { $missing }");
+    }
+
+    #[test]
+    fn missing_keys_fall_back() {
+        let mut localization = Localization::new();
+        localization.add_language("en", StringTable::from_ftl("a = A\nb = B").unwrap());
+        localization.add_language("ja", StringTable::from_json(r#"{"a": "エー"}"#).unwrap());
+        localization.set_fallback("en");
+        localization.set_language("ja").unwrap();
+        assert_eq!(localization.tr("a"), "エー");
+        assert_eq!(localization.tr("b"), "B");
+        assert_eq!(localization.tr("c"), "c");
+        assert!(localization.set_language("fr").is_err());
+    }
+}
@@ -1,5 +1,138 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+/// Number of recent log lines kept for crash reports.
+const RECENT_CAPACITY: usize = 256;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static LOGGED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Set while this thread is storing a line, so a log call made from
+    /// inside (a panic hook, a `Display` impl that logs) can't deadlock.
+    static IN_LOGGER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Clears [`IN_LOGGER`] when dropped, even while unwinding.
+struct InLogger;
+
+impl Drop for InLogger {
+    fn drop(&mut self) {
+        IN_LOGGER.with(|flag| flag.set(false));
+    }
+}
+
+/// Forwards to `env_logger` and keeps the most recent lines in memory.
+struct RingLogger {
+    inner: env_logger::Logger,
+    started: Instant,
+}
+
+impl log::Log for RingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        self.inner.log(record);
+        let line = format!(
+            "[{:>9.3} {:<5} {}] {}",
+            self.started.elapsed().as_secs_f32(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if IN_LOGGER.with(|flag| flag.replace(true)) {
+            return;
+        }
+        let _guard = InLogger;
+        let mut recent = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+        LOGGED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Starts logging to stderr, configured by the `RUST_LOG` environment
+/// variable. The most recent lines are also kept for crash reports.
 pub fn init() {
-  env_logger::init();
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter();
+    let logger = RingLogger {
+        inner,
+        started: Instant::now(),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Returns the most recent log lines, oldest first.
+pub fn recent_lines() -> Vec<String> {
+    match RECENT.lock() {
+        Ok(recent) => recent.iter().cloned().collect(),
+        Err(poisoned) => poisoned.into_inner().iter().cloned().collect(),
+    }
 }
 
-pub use log::*;
\ No newline at end of file
+/// Returns the lines logged after the first `seen` that are still kept, and
+/// the number of lines logged so far to pass next time.
+pub fn recent_lines_since(seen: u64) -> (Vec<String>, u64) {
+    let collect = |recent: &VecDeque<String>| {
+        let logged = LOGGED.load(Ordering::Relaxed);
+        let first_kept = logged - recent.len() as u64;
+        let skip = seen.saturating_sub(first_kept) as usize;
+        (recent.iter().skip(skip).cloned().collect(), logged)
+    };
+    match RECENT.lock() {
+        Ok(recent) => collect(&recent),
+        Err(poisoned) => collect(&poisoned.into_inner()),
+    }
+}
+
+pub use log::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Log;
+
+    #[test]
+    fn lines_are_kept_while_another_thread_holds_the_buffer() {
+        let logger = RingLogger {
+            inner: env_logger::Builder::new()
+                .filter_level(LevelFilter::Trace)
+                .build(),
+            started: Instant::now(),
+        };
+        let (_, seen) = recent_lines_since(0);
+        std::thread::scope(|scope| {
+            let recent = RECENT.lock().unwrap();
+            let writer = scope.spawn(|| {
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("kept"))
+                        .level(Level::Info)
+                        .build(),
+                )
+            });
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            drop(recent);
+            writer.join().unwrap();
+        });
+        let (lines, _) = recent_lines_since(seen);
+        assert!(lines.iter().any(|line| line.ends_with("kept")));
+    }
+}
@@ -0,0 +1,111 @@
+use cgmath::*;
+
+/// # Axis-Aligned Bounding Box (AABB)
+///
+/// A 3D box aligned with the world axes, used for culling, picking, and broad
+/// phase collision.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    /// Creates a box from its minimum and maximum corners.
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates a box from its center and half extents.
+    pub fn from_center_half_extents(center: Vector3<f32>, half_extents: Vector3<f32>) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    /// Creates the smallest box containing every point, or `None` if there are no points.
+    pub fn from_points(points: &[Vector3<f32>]) -> Option<Self> {
+        let (first, rest) = points.split_first()?;
+        let mut aabb = Self::new(*first, *first);
+        for point in rest {
+            aabb.expand_to_include(*point);
+        }
+        Some(aabb)
+    }
+
+    /// Returns the center point.
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Returns the full size along each axis.
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// Returns half the size along each axis.
+    pub fn half_extents(&self) -> Vector3<f32> {
+        self.size() * 0.5
+    }
+
+    /// Checks if a point lies inside the box.
+    pub fn contains_point(&self, point: Vector3<f32>) -> bool {
+        point.x >= self.min.x
+            && point.y >= self.min.y
+            && point.z >= self.min.z
+            && point.x <= self.max.x
+            && point.y <= self.max.y
+            && point.z <= self.max.z
+    }
+
+    /// Checks if two boxes overlap.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// Grows the box to contain a point.
+    pub fn expand_to_include(&mut self, point: Vector3<f32>) {
+        self.min = vec3(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = vec3(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        let mut aabb = *self;
+        aabb.expand_to_include(other.min);
+        aabb.expand_to_include(other.max);
+        aabb
+    }
+
+    /// Returns the eight corners of the box.
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            vec3(min.x, min.y, min.z),
+            vec3(max.x, min.y, min.z),
+            vec3(min.x, max.y, min.z),
+            vec3(max.x, max.y, min.z),
+            vec3(min.x, min.y, max.z),
+            vec3(max.x, min.y, max.z),
+            vec3(min.x, max.y, max.z),
+            vec3(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Returns the box enclosing this box after a transformation.
+    pub fn transformed(&self, matrix: &Matrix4<f32>) -> Aabb {
+        let corners = self.corners().map(|c| (*matrix * c.extend(1.0)).truncate());
+        Aabb::from_points(&corners).expect("A box always has corners")
+    }
+}
@@ -0,0 +1,275 @@
+use cgmath::Vector4;
+
+/// # Color
+///
+/// An RGBA color with components in `0.0..=1.0`, stored in sRGB space the way
+/// colors are usually authored (color pickers, hex codes). Convert with
+/// `to_linear` before doing lighting math or blending on it.
+///
+/// ## Example
+/// ```ignore
+/// let orange = Color::from_hex("#ff8800").unwrap();
+/// let faded = orange.with_alpha(0.5);
+/// let shader_value = orange.to_linear().to_array();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const RED: Color = Color::rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Color = Color::rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Color = Color::rgb(0.0, 0.0, 1.0);
+    pub const YELLOW: Color = Color::rgb(1.0, 1.0, 0.0);
+    pub const CYAN: Color = Color::rgb(0.0, 1.0, 1.0);
+    pub const MAGENTA: Color = Color::rgb(1.0, 0.0, 1.0);
+    pub const GRAY: Color = Color::rgb(0.5, 0.5, 0.5);
+    pub const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+
+    /// Creates a color from RGBA components.
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Creates an opaque color from RGB components.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    /// Creates a color from 8-bit RGBA components.
+    pub fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::new(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    /// Approximates the color of a black body at a temperature in Kelvin,
+    /// from candle light (~1900K) through daylight (~6500K) to blue sky (~10000K).
+    pub fn from_temperature(kelvin: f32) -> Self {
+        let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+        let r = if t <= 66.0 {
+            255.0
+        } else {
+            329.69873 * (t - 60.0).powf(-0.13320476)
+        };
+        let g = if t <= 66.0 {
+            99.4708 * t.ln() - 161.11957
+        } else {
+            288.12217 * (t - 60.0).powf(-0.07551485)
+        };
+        let b = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.51773 * (t - 10.0).ln() - 305.0448
+        };
+        Self::rgb(
+            (r / 255.0).clamp(0.0, 1.0),
+            (g / 255.0).clamp(0.0, 1.0),
+            (b / 255.0).clamp(0.0, 1.0),
+        )
+    }
+
+    /// Parses a hex color such as `#ff8800`, `ff8800cc`, or `#f80`.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        // from_str_radix would accept a sign.
+        if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+        match hex.len() {
+            3 | 4 => {
+                let a = if hex.len() == 4 { digit(3)? * 17 } else { 255 };
+                Some(Self::from_rgba8(
+                    digit(0)? * 17,
+                    digit(1)? * 17,
+                    digit(2)? * 17,
+                    a,
+                ))
+            }
+            6 | 8 => {
+                let a = if hex.len() == 8 { byte(6)? } else { 255 };
+                Some(Self::from_rgba8(byte(0)?, byte(2)?, byte(4)?, a))
+            }
+            _ => None,
+        }
+    }
+
+    /// Creates a color from a packed `0xRRGGBBAA` value.
+    pub fn from_hex_u32(rgba: u32) -> Self {
+        let [r, g, b, a] = rgba.to_be_bytes();
+        Self::from_rgba8(r, g, b, a)
+    }
+
+    /// Converts to 8-bit RGBA components.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let quantize = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [
+            quantize(self.r),
+            quantize(self.g),
+            quantize(self.b),
+            quantize(self.a),
+        ]
+    }
+
+    /// Converts to a `#rrggbbaa` hex string.
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+
+    /// Converts an sRGB color to linear space. Alpha is left untouched.
+    pub fn to_linear(&self) -> Self {
+        Self::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        )
+    }
+
+    /// Converts a linear-space color back to sRGB. Alpha is left untouched.
+    pub fn from_linear(linear: Color) -> Self {
+        Self::new(
+            linear_to_srgb(linear.r),
+            linear_to_srgb(linear.g),
+            linear_to_srgb(linear.b),
+            linear.a,
+        )
+    }
+
+    /// Converts to linear space and returns the components as an array, ready
+    /// for vertex data or uniforms.
+    pub fn to_linear_array(&self) -> [f32; 4] {
+        self.to_linear().to_array()
+    }
+
+    /// Interpolates between two colors in linear space, which avoids the dark
+    /// band in the middle of a plain sRGB blend.
+    pub fn lerp_linear(&self, other: Color, t: f32) -> Self {
+        Self::from_linear(self.to_linear().lerp(other.to_linear(), t))
+    }
+
+    /// Returns the relative luminance, from 0 for black to 1 for white.
+    pub fn luminance(&self) -> f32 {
+        let linear = self.to_linear();
+        0.2126 * linear.r + 0.7152 * linear.g + 0.0722 * linear.b
+    }
+
+    /// Returns the same color with a different alpha.
+    pub fn with_alpha(&self, a: f32) -> Self {
+        Self { a, ..*self }
+    }
+
+    /// Linearly interpolates between two colors component-wise, in whatever
+    /// space they are in.
+    pub fn lerp(&self, other: Color, t: f32) -> Self {
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Returns the components as an array.
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(c: [f32; 4]) -> Self {
+        Self::new(c[0], c[1], c[2], c[3])
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(c: Color) -> Self {
+        c.to_array()
+    }
+}
+
+impl From<Vector4<f32>> for Color {
+    fn from(v: Vector4<f32>) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Color> for Vector4<f32> {
+    fn from(c: Color) -> Self {
+        Vector4::new(c.r, c.g, c.b, c.a)
+    }
+}
+
+/// Converts a single sRGB-encoded channel to linear.
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a single linear channel to sRGB encoding.
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_parse_in_every_length() {
+        assert_eq!(
+            Color::from_hex("#ff8800"),
+            Some(Color::from_rgba8(255, 136, 0, 255))
+        );
+        assert_eq!(
+            Color::from_hex("ff880080"),
+            Some(Color::from_rgba8(255, 136, 0, 128))
+        );
+        assert_eq!(
+            Color::from_hex("#f80"),
+            Some(Color::from_rgba8(255, 136, 0, 255))
+        );
+        assert_eq!(
+            Color::from_hex("f808"),
+            Some(Color::from_rgba8(255, 136, 0, 136))
+        );
+    }
+
+    #[test]
+    fn hex_colors_reject_signs_and_other_characters() {
+        assert_eq!(Color::from_hex("#+f80"), None);
+        assert_eq!(Color::from_hex("+ff880"), None);
+        assert_eq!(Color::from_hex("-ff880"), None);
+        assert_eq!(Color::from_hex("#ff 880"), None);
+        assert_eq!(Color::from_hex("#ff88000"), None);
+        assert_eq!(Color::from_hex("#ffé0"), None);
+    }
+}
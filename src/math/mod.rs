@@ -0,0 +1,40 @@
+pub mod aabb;
+pub mod color;
+pub mod noise;
+pub mod plane;
+pub mod polygon;
+pub mod projection;
+pub mod quat;
+pub mod random;
+pub mod ray;
+pub mod rect;
+pub mod spline;
+pub mod trs;
+
+pub use aabb::Aabb;
+pub use color::Color;
+pub use noise::{Fbm, Noise};
+pub use plane::Plane;
+pub use random::{Random, Rng, StableHasher, StableState};
+pub use ray::Ray;
+pub use rect::{Anchor, Rect};
+pub use spline::{Spline, SplineKind};
+
+pub use cgmath::prelude::*;
+pub use cgmath::{
+    point2, point3, vec2, vec3, vec4, Deg, Euler, Matrix2, Matrix3, Matrix4, Point2, Point3,
+    Quaternion, Rad, Vector2, Vector3, Vector4,
+};
+
+/// 2D vector of `f32`.
+pub type Vec2 = Vector2<f32>;
+/// 3D vector of `f32`.
+pub type Vec3 = Vector3<f32>;
+/// 4D vector of `f32`.
+pub type Vec4 = Vector4<f32>;
+/// 3x3 matrix of `f32`.
+pub type Mat3 = Matrix3<f32>;
+/// 4x4 matrix of `f32`.
+pub type Mat4 = Matrix4<f32>;
+/// Rotation quaternion of `f32`.
+pub type Quat = Quaternion<f32>;
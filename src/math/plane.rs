@@ -0,0 +1,86 @@
+use cgmath::*;
+
+/// # Plane
+///
+/// An infinite plane satisfying `normal · p + distance = 0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a plane from a normal and signed distance from the origin.
+    pub fn new(normal: Vector3<f32>, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    /// Creates a plane passing through a point with the given normal.
+    pub fn from_point_normal(point: Vector3<f32>, normal: Vector3<f32>) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, -normal.dot(point))
+    }
+
+    /// Creates a plane through three points, wound counter-clockwise around the normal.
+    pub fn from_points(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Self {
+        Self::from_point_normal(a, (b - a).cross(c - a))
+    }
+
+    /// Creates a plane from packed `(a, b, c, d)` coefficients, normalizing them.
+    pub fn from_coefficients(coefficients: Vector4<f32>) -> Self {
+        Self::new(coefficients.truncate(), coefficients.w).normalized()
+    }
+
+    /// Returns the plane with a unit-length normal.
+    pub fn normalized(&self) -> Self {
+        let length = self.normal.magnitude();
+        Self::new(self.normal / length, self.distance / length)
+    }
+
+    /// Returns the signed distance from the plane to a point; positive on the normal's side.
+    pub fn signed_distance(&self, point: Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+
+    /// Projects a point onto the plane.
+    pub fn project(&self, point: Vector3<f32>) -> Vector3<f32> {
+        point - self.normal * self.signed_distance(point)
+    }
+
+    /// Returns the plane as packed `(a, b, c, d)` coefficients.
+    pub fn to_vector4(&self) -> Vector4<f32> {
+        self.normal.extend(self.distance)
+    }
+
+    /// Mirrors a point across the plane.
+    pub fn reflect_point(&self, point: Vector3<f32>) -> Vector3<f32> {
+        point - self.normal * (2.0 * self.signed_distance(point))
+    }
+
+    /// Mirrors a direction across the plane.
+    pub fn reflect_vector(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        vector - self.normal * (2.0 * self.normal.dot(vector))
+    }
+
+    /// Returns the matrix that mirrors points across the plane. The normal must be unit length.
+    #[rustfmt::skip]
+    pub fn reflection_matrix(&self) -> Matrix4<f32> {
+        let Vector3 { x, y, z } = self.normal;
+        let d = self.distance;
+        Matrix4::new(
+            1.0 - 2.0 * x * x, -2.0 * x * y, -2.0 * x * z, 0.0,
+            -2.0 * x * y, 1.0 - 2.0 * y * y, -2.0 * y * z, 0.0,
+            -2.0 * x * z, -2.0 * y * z, 1.0 - 2.0 * z * z, 0.0,
+            -2.0 * x * d, -2.0 * y * d, -2.0 * z * d, 1.0,
+        )
+    }
+
+    /// Transforms the plane by a matrix that maps points from its space into another.
+    pub fn transformed(&self, matrix: &Matrix4<f32>) -> Self {
+        let inverse_transpose = matrix
+            .invert()
+            .expect("Plane transform is not invertible")
+            .transpose();
+        Self::from_coefficients(inverse_transpose * self.to_vector4())
+    }
+}
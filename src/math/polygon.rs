@@ -0,0 +1,96 @@
+use super::Vec2;
+
+/// Returns twice the signed area of a polygon; positive when counter-clockwise
+/// in a Y-up coordinate system (clockwise on screen, where Y points down).
+pub fn signed_area(points: &[Vec2]) -> f32 {
+    let mut area = 0.0;
+    for (index, a) in points.iter().enumerate() {
+        let b = points[(index + 1) % points.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    area
+}
+
+/// Checks if a point lies inside a simple polygon, using the even-odd rule.
+pub fn contains_point(points: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut previous = match points.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    for current in points {
+        if (current.y > point.y) != (previous.y > point.y)
+            && point.x
+                < (previous.x - current.x) * (point.y - current.y) / (previous.y - current.y)
+                    + current.x
+        {
+            inside = !inside;
+        }
+        previous = *current;
+    }
+    inside
+}
+
+/// Triangulates a simple polygon (no self-intersections or holes) by ear
+/// clipping, returning indices into `points` in groups of three. Works with
+/// either winding and keeps it in the output.
+pub fn triangulate(points: &[Vec2]) -> Vec<u32> {
+    let count = points.len();
+    if count < 3 {
+        return Vec::new();
+    }
+
+    let winding = signed_area(points).signum();
+    let mut remaining: Vec<usize> = (0..count).collect();
+    let mut triangles = Vec::with_capacity((count - 2) * 3);
+
+    let cross = |a: Vec2, b: Vec2, c: Vec2| (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+    let in_triangle = |p: Vec2, a: Vec2, b: Vec2, c: Vec2| {
+        let d1 = cross(a, b, p) * winding;
+        let d2 = cross(b, c, p) * winding;
+        let d3 = cross(c, a, p) * winding;
+        d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0
+    };
+
+    let mut guard = 0;
+    while remaining.len() > 3 && guard < count * count {
+        guard += 1;
+        let length = remaining.len();
+        let mut clipped = false;
+        for i in 0..length {
+            let (previous, current, next) = (
+                remaining[(i + length - 1) % length],
+                remaining[i],
+                remaining[(i + 1) % length],
+            );
+            let (a, b, c) = (points[previous], points[current], points[next]);
+            if cross(a, b, c) * winding <= 0.0 {
+                continue;
+            }
+            let blocked = remaining.iter().any(|&other| {
+                other != previous
+                    && other != current
+                    && other != next
+                    && in_triangle(points[other], a, b, c)
+            });
+            if blocked {
+                continue;
+            }
+
+            triangles.extend_from_slice(&[previous as u32, current as u32, next as u32]);
+            remaining.remove(i);
+            clipped = true;
+            break;
+        }
+
+        // Degenerate input (collinear or self-touching); drop a vertex and carry on.
+        if !clipped {
+            remaining.remove(0);
+        }
+    }
+
+    if remaining.len() == 3 {
+        triangles.extend(remaining.iter().map(|&index| index as u32));
+    }
+    triangles
+}
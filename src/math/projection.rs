@@ -0,0 +1,69 @@
+use super::{vec4, Deg, Mat4, Rad, Vec4};
+use cgmath::prelude::*;
+
+/// Creates a right-handed perspective projection with OpenGL's `-1..1` depth range.
+pub fn perspective<A: Into<Rad<f32>>>(fovy: A, aspect: f32, near: f32, far: f32) -> Mat4 {
+    cgmath::perspective(fovy, aspect, near, far)
+}
+
+/// Creates a right-handed perspective projection with no far plane, for OpenGL's
+/// `-1..1` depth range.
+#[rustfmt::skip]
+pub fn perspective_infinite<A: Into<Rad<f32>>>(fovy: A, aspect: f32, near: f32) -> Mat4 {
+    let f = 1.0 / (fovy.into().0 * 0.5).tan();
+    Mat4::new(
+        f / aspect, 0.0, 0.0, 0.0,
+        0.0, f, 0.0, 0.0,
+        0.0, 0.0, -1.0, -1.0,
+        0.0, 0.0, -2.0 * near, 0.0,
+    )
+}
+
+/// Creates a right-handed, reversed-Z perspective projection with no far plane.
+///
+/// Depth is 1 at the near plane and approaches 0 at infinity, which spreads
+/// float precision evenly across the view distance. It expects a `0..1` depth
+/// range, so pair it with `glClipControl(GL_LOWER_LEFT, GL_ZERO_TO_ONE)`, a depth
+/// clear value of 0, and `glDepthFunc(GL_GREATER)`.
+#[rustfmt::skip]
+pub fn perspective_infinite_reverse<A: Into<Rad<f32>>>(fovy: A, aspect: f32, near: f32) -> Mat4 {
+    let f = 1.0 / (fovy.into().0 * 0.5).tan();
+    Mat4::new(
+        f / aspect, 0.0, 0.0, 0.0,
+        0.0, f, 0.0, 0.0,
+        0.0, 0.0, 0.0, -1.0,
+        0.0, 0.0, near, 0.0,
+    )
+}
+
+/// Creates a right-handed orthographic projection with OpenGL's `-1..1` depth range.
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    cgmath::ortho(left, right, bottom, top, near, far)
+}
+
+/// Converts a horizontal field of view to a vertical one for the given aspect ratio.
+pub fn horizontal_to_vertical_fov(horizontal: Deg<f32>, aspect: f32) -> Deg<f32> {
+    let half = Rad::from(horizontal).0 * 0.5;
+    Deg::from(Rad(2.0 * (half.tan() / aspect).atan()))
+}
+
+/// Replaces the near plane of an OpenGL perspective projection with an arbitrary
+/// clip plane given in view space, so geometry behind that plane is clipped for
+/// free. The camera must be on the plane's negative side.
+///
+/// Depth precision degrades the more the plane tilts away from the view
+/// direction; this is Lengyel's oblique frustum technique.
+pub fn oblique_near_plane(projection: Mat4, clip_plane: Vec4) -> Mat4 {
+    let Some(inverse) = projection.invert() else {
+        return projection;
+    };
+    let corner = inverse * vec4(clip_plane.x.signum(), clip_plane.y.signum(), 1.0, 1.0);
+    let scaled = clip_plane * (2.0 / clip_plane.dot(corner));
+
+    let mut result = projection;
+    result.x.z = scaled.x - result.x.w;
+    result.y.z = scaled.y - result.y.w;
+    result.z.z = scaled.z - result.z.w;
+    result.w.z = scaled.w - result.w.w;
+    result
+}
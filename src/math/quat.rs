@@ -0,0 +1,57 @@
+use super::{InnerSpace, Mat3, Quat, Rad, Rotation, Vec3};
+
+/// Returns a rotation whose forward (-Z) axis points along `forward`.
+pub fn look_rotation(forward: Vec3, up: Vec3) -> Quat {
+    let back = -forward.normalize();
+    let right = up.cross(back).normalize();
+    let up = back.cross(right);
+    Quat::from(Mat3::from_cols(right, up, back)).normalize()
+}
+
+/// Returns the shortest rotation turning `from` into `to`.
+pub fn from_to_rotation(from: Vec3, to: Vec3) -> Quat {
+    Quat::from_arc(from.normalize(), to.normalize(), None)
+}
+
+/// Splits a rotation into a unit axis and an angle.
+pub fn to_axis_angle(rotation: Quat) -> (Vec3, Rad<f32>) {
+    let rotation = rotation.normalize();
+    let angle = 2.0 * rotation.s.clamp(-1.0, 1.0).acos();
+    let sin_half = (1.0 - rotation.s * rotation.s).max(0.0).sqrt();
+    if sin_half < 1e-6 {
+        (Vec3::unit_x(), Rad(0.0))
+    } else {
+        (rotation.v / sin_half, Rad(angle))
+    }
+}
+
+/// Returns the angle between two rotations.
+pub fn angle_between(a: Quat, b: Quat) -> Rad<f32> {
+    let dot = a.normalize().dot(b.normalize()).abs().min(1.0);
+    Rad(2.0 * dot.acos())
+}
+
+/// Rotates `from` towards `to` by at most `max_angle`.
+pub fn rotate_towards(from: Quat, to: Quat, max_angle: Rad<f32>) -> Quat {
+    let angle = angle_between(from, to);
+    if angle.0 <= max_angle.0 || angle.0 == 0.0 {
+        to
+    } else {
+        from.slerp(to, max_angle.0 / angle.0)
+    }
+}
+
+/// Returns the direction the rotation's forward (-Z) axis points.
+pub fn forward(rotation: Quat) -> Vec3 {
+    rotation.rotate_vector(-Vec3::unit_z())
+}
+
+/// Returns the direction the rotation's right (+X) axis points.
+pub fn right(rotation: Quat) -> Vec3 {
+    rotation.rotate_vector(Vec3::unit_x())
+}
+
+/// Returns the direction the rotation's up (+Y) axis points.
+pub fn up(rotation: Quat) -> Vec3 {
+    rotation.rotate_vector(Vec3::unit_y())
+}
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::ops::{Range, RangeInclusive};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{vec2, vec3, InnerSpace, Quat, Vec2, Vec3};
+
+/// Expands a 64-bit seed into well-mixed state words.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A range that [`Rng::range`] can sample from.
+pub trait SampleRange<T> {
+    /// Draws a value from the range. Empty ranges return their start.
+    fn sample(self, rng: &mut Rng) -> T;
+}
+
+impl SampleRange<f32> for Range<f32> {
+    fn sample(self, rng: &mut Rng) -> f32 {
+        self.start + (self.end - self.start) * rng.f32()
+    }
+}
+
+impl SampleRange<f64> for Range<f64> {
+    fn sample(self, rng: &mut Rng) -> f64 {
+        self.start + (self.end - self.start) * rng.f64()
+    }
+}
+
+macro_rules! impl_integer_range {
+    ($($t:ty),*) => {$(
+        impl SampleRange<$t> for Range<$t> {
+            fn sample(self, rng: &mut Rng) -> $t {
+                if self.end <= self.start {
+                    return self.start;
+                }
+                let span = self.end.abs_diff(self.start) as u64;
+                self.start.wrapping_add(rng.below(span) as $t)
+            }
+        }
+
+        impl SampleRange<$t> for RangeInclusive<$t> {
+            fn sample(self, rng: &mut Rng) -> $t {
+                let (start, end) = self.into_inner();
+                if end < start {
+                    return start;
+                }
+                let span = end.abs_diff(start) as u64;
+                if span == u64::MAX {
+                    return rng.next_u64() as $t;
+                }
+                start.wrapping_add(rng.below(span + 1) as $t)
+            }
+        }
+    )*};
+}
+
+impl_integer_range!(i32, i64, u32, u64, usize);
+
+/// # Rng
+///
+/// A small, fast, seedable pseudo-random generator (xoshiro256**). The same
+/// seed always produces the same sequence on every platform, which is what
+/// procedural generation and deterministic replays need. Not suitable for
+/// cryptography.
+///
+/// ## Example
+/// ```ignore
+/// let mut rng = Rng::new(1234);
+/// let damage = rng.range(8..=12);
+/// let spawn = center + rng.in_sphere() * 5.0;
+/// let loot = rng.weighted_choice(&[("coin", 10.0), ("gem", 1.0)]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        let mut mix = seed;
+        Self {
+            state: [
+                splitmix64(&mut mix),
+                splitmix64(&mut mix),
+                splitmix64(&mut mix),
+                splitmix64(&mut mix),
+            ],
+        }
+    }
+
+    /// Creates a generator seeded from the system clock.
+    pub fn from_entropy() -> Self {
+        Self::new(entropy_seed())
+    }
+
+    /// Creates an independent generator seeded from this one.
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// Returns the next 32 random bits.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a uniform value in `0..bound` without modulo bias.
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = self.next_u64();
+            let product = value as u128 * bound as u128;
+            if (product as u64) >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+
+    /// Returns a uniform value in `[0, 1)`.
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 * (1.0 / (1u64 << 24) as f32)
+    }
+
+    /// Returns a uniform value in `[0, 1)` with double precision.
+    pub fn f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Returns `true` with the given probability.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.f32() < probability
+    }
+
+    /// Returns a uniform value from a range, such as `0..10`, `1..=6` or `-1.0..1.0`.
+    pub fn range<T, R: SampleRange<T>>(&mut self, range: R) -> T {
+        range.sample(self)
+    }
+
+    /// Returns a normally distributed value.
+    pub fn normal(&mut self, mean: f32, standard_deviation: f32) -> f32 {
+        let u1 = 1.0 - self.f32();
+        let u2 = self.f32();
+        mean + standard_deviation * (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+    }
+
+    /// Returns a random angle in radians, in `[0, 2π)`.
+    pub fn angle(&mut self) -> f32 {
+        self.f32() * TAU
+    }
+
+    /// Returns a uniformly distributed 2D direction.
+    pub fn unit_vector_2d(&mut self) -> Vec2 {
+        let angle = self.angle();
+        vec2(angle.cos(), angle.sin())
+    }
+
+    /// Returns a uniformly distributed point inside the unit circle.
+    pub fn in_circle(&mut self) -> Vec2 {
+        self.unit_vector_2d() * self.f32().sqrt()
+    }
+
+    /// Returns a uniformly distributed 3D direction.
+    pub fn unit_vector(&mut self) -> Vec3 {
+        let z = self.f32() * 2.0 - 1.0;
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+        let angle = self.angle();
+        vec3(radius * angle.cos(), radius * angle.sin(), z)
+    }
+
+    /// Returns a uniformly distributed point inside the unit sphere.
+    pub fn in_sphere(&mut self) -> Vec3 {
+        self.unit_vector() * self.f32().cbrt()
+    }
+
+    /// Returns a uniformly distributed direction within `angle` radians of `axis`.
+    pub fn in_cone(&mut self, axis: Vec3, angle: f32) -> Vec3 {
+        let axis = axis.normalize();
+        let z = 1.0 - self.f32() * (1.0 - angle.cos());
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+        let phi = self.angle();
+        let tangent = if axis.x.abs() < 0.9 {
+            axis.cross(Vec3::unit_x()).normalize()
+        } else {
+            axis.cross(Vec3::unit_y()).normalize()
+        };
+        let bitangent = axis.cross(tangent);
+        axis * z + (tangent * phi.cos() + bitangent * phi.sin()) * radius
+    }
+
+    /// Returns a uniformly distributed rotation.
+    pub fn rotation(&mut self) -> Quat {
+        let (u1, u2, u3) = (self.f32(), self.angle(), self.angle());
+        let (a, b) = ((1.0 - u1).sqrt(), u1.sqrt());
+        Quat::new(b * u3.cos(), a * u2.sin(), a * u2.cos(), b * u3.sin())
+    }
+
+    /// Returns a random element, or `None` if the slice is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.range(0..items.len()))
+        }
+    }
+
+    /// Returns an index chosen with probability proportional to its weight.
+    /// Negative and NaN weights count as zero; returns `None` if all are zero.
+    pub fn weighted_index(&mut self, weights: &[f32]) -> Option<usize> {
+        let weight = |w: &f32| if *w > 0.0 { *w } else { 0.0 };
+        let total: f32 = weights.iter().map(weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = self.f32() * total;
+        let mut last = None;
+        for (index, w) in weights.iter().map(weight).enumerate() {
+            if w <= 0.0 {
+                continue;
+            }
+            if target < w {
+                return Some(index);
+            }
+            target -= w;
+            last = Some(index);
+        }
+        last
+    }
+
+    /// Returns an item chosen with probability proportional to its weight.
+    pub fn weighted_choice<'a, T>(&mut self, items: &'a [(T, f32)]) -> Option<&'a T> {
+        let weights: Vec<f32> = items.iter().map(|(_, weight)| *weight).collect();
+        self.weighted_index(&weights).map(|index| &items[index].0)
+    }
+
+    /// Shuffles a slice in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..=i));
+        }
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+fn entropy_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64);
+    let local = 0u8;
+    nanos ^ (&local as *const u8 as u64).rotate_left(32)
+}
+
+/// # Random
+///
+/// The engine's source of randomness: a seed, a main generator, and named
+/// streams. Each stream is seeded from the world seed and its name only, so a
+/// system drawing more or fewer numbers never shifts the sequence another
+/// system sees, and systems running in parallel don't contend for one
+/// generator. Reseeding makes a whole run reproducible.
+///
+/// A [`World`](crate::ecs::World) owns one, reachable through
+/// [`World::random`](crate::ecs::World::random).
+///
+/// ## Example
+/// ```ignore
+/// world.random_mut().reseed(replay.seed);
+///
+/// // In a system:
+/// let offset = world.random().with_stream("particles", |rng| rng.in_sphere());
+/// ```
+#[derive(Debug)]
+pub struct Random {
+    seed: u64,
+    main: Rng,
+    streams: Mutex<HashMap<String, Rng>>,
+}
+
+impl Random {
+    /// Creates a source from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            main: Rng::new(seed),
+            streams: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a source seeded from the system clock.
+    pub fn from_entropy() -> Self {
+        Self::new(entropy_seed())
+    }
+
+    /// Returns the seed, e.g. to store with a replay or a bug report.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the main generator and every stream from a new seed.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// Returns the main generator.
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.main
+    }
+
+    /// Returns a generator seeded from the world seed and a name, without
+    /// touching any state. Useful for content keyed by name or coordinates.
+    pub fn derive(&self, name: &str) -> Rng {
+        let mut hasher = StableHasher::with_seed(self.seed);
+        name.hash(&mut hasher);
+        Rng::new(hasher.finish())
+    }
+
+    /// Runs `f` with a named stream, creating it on first use.
+    pub fn with_stream<R>(&self, name: &str, f: impl FnOnce(&mut Rng) -> R) -> R {
+        let mut streams = self.streams.lock().expect("Random streams poisoned");
+        if !streams.contains_key(name) {
+            streams.insert(name.to_string(), self.derive(name));
+        }
+        f(streams.get_mut(name).unwrap())
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+/// # Stable Hasher
+///
+/// FNV-1a, which unlike the standard hasher gives the same hashes on every
+/// run and platform: integers are hashed as little-endian bytes and there is
+/// no random key. Hash maps built with [`StableState`] iterate in the same
+/// order whenever the same operations were applied, and
+/// [`World::checksum`](crate::ecs::World::checksum) uses it to compare
+/// simulation state between machines.
+///
+/// ## Example
+/// ```ignore
+/// let mut hasher = StableHasher::new();
+/// "level_3".hash(&mut hasher);
+/// hasher.write_f32(position.x);
+/// let checksum = hasher.finish();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+    /// Creates a hasher with the standard FNV offset.
+    pub fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    /// Creates a hasher whose output also depends on a seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self(seed ^ Self::OFFSET)
+    }
+
+    /// Hashes a float by its bits, so `0.0` and `-0.0` differ.
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    /// Hashes a double by its bits.
+    pub fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        // Hashed as 64 bits so 32-bit platforms agree with 64-bit ones.
+        self.write_u64(value as u64);
+    }
+
+    fn write_i16(&mut self, value: i16) {
+        self.write_u16(value as u16);
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.write_u64(value as i64 as u64);
+    }
+}
+
+/// Builds [`StableHasher`]s for hash maps whose iteration order must not
+/// change between runs.
+pub type StableState = BuildHasherDefault<StableHasher>;
@@ -0,0 +1,72 @@
+use cgmath::*;
+
+use super::aabb::Aabb;
+use super::plane::Plane;
+
+/// # Ray
+///
+/// A half-line starting at `origin` and extending along a unit `direction`.
+/// Intersection methods return the distance along the ray to the hit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// Creates a ray, normalizing the direction.
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Returns the point at distance `t` along the ray.
+    pub fn at(&self, t: f32) -> Vector3<f32> {
+        self.origin + self.direction * t
+    }
+
+    /// Intersects the ray with a box using the slab method.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let mut t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let mut t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    /// Intersects the ray with a plane, ignoring hits behind the origin.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let t = -plane.signed_distance(self.origin) / denominator;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// Intersects the ray with a sphere, returning the nearest hit in front of the origin.
+    pub fn intersect_sphere(&self, center: Vector3<f32>, radius: f32) -> Option<f32> {
+        let offset = self.origin - center;
+        let b = offset.dot(self.direction);
+        let c = offset.magnitude2() - radius * radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [-b - root, -b + root].into_iter().find(|t| *t >= 0.0)
+    }
+}
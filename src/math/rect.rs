@@ -0,0 +1,164 @@
+use cgmath::Vector2;
+
+/// # Anchor
+///
+/// A reference point on a rectangle, used to pin UI elements to edges and
+/// corners. Names assume a top-left origin with Y pointing down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Returns the anchor's position as a fraction of the rectangle's size.
+    pub fn fraction(&self) -> Vector2<f32> {
+        let (x, y) = match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        };
+        Vector2::new(x, y)
+    }
+}
+
+/// # Rect
+///
+/// An axis-aligned 2D rectangle given by its minimum corner and size. Used for
+/// viewports, UI layout, and sprite regions.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// Creates a rectangle from its minimum corner and size.
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a rectangle spanning two corners.
+    pub fn from_min_max(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Self::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// Creates a rectangle of the given size centered on a point.
+    pub fn from_center(center: Vector2<f32>, size: Vector2<f32>) -> Self {
+        Self::new(
+            center.x - size.x * 0.5,
+            center.y - size.y * 0.5,
+            size.x,
+            size.y,
+        )
+    }
+
+    /// Returns the minimum corner.
+    pub fn min(&self) -> Vector2<f32> {
+        Vector2::new(self.x, self.y)
+    }
+
+    /// Returns the maximum corner.
+    pub fn max(&self) -> Vector2<f32> {
+        Vector2::new(self.x + self.width, self.y + self.height)
+    }
+
+    /// Returns the size.
+    pub fn size(&self) -> Vector2<f32> {
+        Vector2::new(self.width, self.height)
+    }
+
+    /// Returns the center point.
+    pub fn center(&self) -> Vector2<f32> {
+        Vector2::new(self.x + self.width * 0.5, self.y + self.height * 0.5)
+    }
+
+    /// Returns the area.
+    pub fn area(&self) -> f32 {
+        self.width * self.height
+    }
+
+    /// Returns width divided by height.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width / self.height
+    }
+
+    /// Checks if a point lies inside the rectangle.
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        point.x >= self.x
+            && point.y >= self.y
+            && point.x <= self.x + self.width
+            && point.y <= self.y + self.height
+    }
+
+    /// Checks if two rectangles overlap.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    /// Returns the overlapping region of two rectangles.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let min = Vector2::new(self.x.max(other.x), self.y.max(other.y));
+        let max = Vector2::new(
+            self.max().x.min(other.max().x),
+            self.max().y.min(other.max().y),
+        );
+        Some(Rect::from_min_max(min, max))
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let min = Vector2::new(self.x.min(other.x), self.y.min(other.y));
+        let max = Vector2::new(
+            self.max().x.max(other.max().x),
+            self.max().y.max(other.max().y),
+        );
+        Rect::from_min_max(min, max)
+    }
+
+    /// Returns the position of an anchor point on the rectangle.
+    pub fn anchor_point(&self, anchor: Anchor) -> Vector2<f32> {
+        let fraction = anchor.fraction();
+        Vector2::new(
+            self.x + self.width * fraction.x,
+            self.y + self.height * fraction.y,
+        )
+    }
+
+    /// Grows the rectangle by `amount` on every side.
+    pub fn expand(&self, amount: f32) -> Rect {
+        Rect::new(
+            self.x - amount,
+            self.y - amount,
+            self.width + amount * 2.0,
+            self.height + amount * 2.0,
+        )
+    }
+}
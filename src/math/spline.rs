@@ -0,0 +1,348 @@
+use super::{InnerSpace, Ray, Vec3, Zero};
+
+/// Arc length samples taken per segment.
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+/// # Spline Kind
+///
+/// How a [`Spline`]'s points shape the curve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplineKind {
+    /// Passes through every point, with tangents taken from the neighbours.
+    /// Easy to author by just placing points.
+    CatmullRom,
+    /// Cubic Bézier segments laid out as `anchor, control, control, anchor,
+    /// control, control, anchor, ...`. Passes through anchors only.
+    Bezier,
+}
+
+/// # Spline
+///
+/// A smooth curve through 3D points, for moving platforms, camera rails, and
+/// racing lines. Positions can be looked up either by curve parameter, where
+/// `t` runs from `0` to [`Spline::segment_count`], or by distance along the
+/// curve, which moves at constant speed no matter how unevenly points are
+/// spaced. 2D curves simply keep `z` at zero.
+///
+/// For editor gizmos, [`Spline::polyline`] gives points for a
+/// [`LineRenderer`](crate::graphics::lines::LineRenderer), [`Spline::pick`]
+/// finds the handle under the cursor, and [`Spline::set_point`] drags it.
+///
+/// ## Example
+/// ```ignore
+/// let rail = Spline::catmull_rom(vec![
+///     vec3(0.0, 2.0, 0.0),
+///     vec3(10.0, 4.0, 5.0),
+///     vec3(20.0, 2.0, 0.0),
+/// ]);
+/// let halfway = rail.position_at_distance(rail.length() * 0.5);
+/// lines.polyline(&rail.polyline(8), LineStyle::new(Color::YELLOW, 2.0), rail.is_closed());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spline {
+    kind: SplineKind,
+    points: Vec<Vec3>,
+    closed: bool,
+    /// Cumulative length at evenly spaced parameters, starting with zero.
+    lengths: Vec<f32>,
+}
+
+impl Spline {
+    /// Creates a spline and measures it.
+    pub fn new(kind: SplineKind, points: Vec<Vec3>) -> Self {
+        let mut spline = Self {
+            kind,
+            points,
+            closed: false,
+            lengths: Vec::new(),
+        };
+        spline.measure();
+        spline
+    }
+
+    /// Creates a Catmull-Rom spline through `points`.
+    pub fn catmull_rom(points: Vec<Vec3>) -> Self {
+        Self::new(SplineKind::CatmullRom, points)
+    }
+
+    /// Creates a chain of cubic Bézier segments.
+    pub fn bezier(points: Vec<Vec3>) -> Self {
+        Self::new(SplineKind::Bezier, points)
+    }
+
+    /// Makes a Catmull-Rom spline loop back to its first point. Bézier splines
+    /// close by ending on their first anchor instead.
+    pub fn closed(mut self) -> Self {
+        self.closed = self.kind == SplineKind::CatmullRom;
+        self.measure();
+        self
+    }
+
+    /// Returns how the points shape the curve.
+    pub fn kind(&self) -> SplineKind {
+        self.kind
+    }
+
+    /// Checks if the curve loops.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Returns the points, including Bézier control points.
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    /// Moves a point and re-measures the curve.
+    pub fn set_point(&mut self, index: usize, position: Vec3) {
+        if let Some(point) = self.points.get_mut(index) {
+            *point = position;
+            self.measure();
+        }
+    }
+
+    /// Inserts a point and re-measures the curve.
+    pub fn insert_point(&mut self, index: usize, position: Vec3) {
+        self.points.insert(index.min(self.points.len()), position);
+        self.measure();
+    }
+
+    /// Removes a point and re-measures the curve.
+    pub fn remove_point(&mut self, index: usize) -> Option<Vec3> {
+        if index >= self.points.len() {
+            return None;
+        }
+        let point = self.points.remove(index);
+        self.measure();
+        Some(point)
+    }
+
+    /// Returns the number of curve segments.
+    pub fn segment_count(&self) -> usize {
+        let count = self.points.len();
+        match self.kind {
+            SplineKind::CatmullRom if count < 2 => 0,
+            SplineKind::CatmullRom if self.closed => count,
+            SplineKind::CatmullRom => count - 1,
+            SplineKind::Bezier => count.saturating_sub(1) / 3,
+        }
+    }
+
+    /// Returns the four points shaping a segment and the local parameter.
+    fn segment(&self, t: f32) -> Option<([Vec3; 4], f32)> {
+        let segments = self.segment_count();
+        if segments == 0 {
+            return None;
+        }
+        let t = t.clamp(0.0, segments as f32);
+        let index = (t.floor() as usize).min(segments - 1);
+        let local = t - index as f32;
+
+        let points = match self.kind {
+            SplineKind::CatmullRom => {
+                let count = self.points.len() as isize;
+                let point = |offset: isize| {
+                    let i = index as isize + offset;
+                    let i = if self.closed {
+                        i.rem_euclid(count)
+                    } else {
+                        i.clamp(0, count - 1)
+                    };
+                    self.points[i as usize]
+                };
+                [point(-1), point(0), point(1), point(2)]
+            }
+            SplineKind::Bezier => {
+                let start = index * 3;
+                [
+                    self.points[start],
+                    self.points[start + 1],
+                    self.points[start + 2],
+                    self.points[start + 3],
+                ]
+            }
+        };
+        Some((points, local))
+    }
+
+    /// Returns the position at a curve parameter.
+    pub fn position(&self, t: f32) -> Vec3 {
+        let Some(([p0, p1, p2, p3], t)) = self.segment(t) else {
+            return self.points.first().copied().unwrap_or(Vec3::zero());
+        };
+        let (t2, t3) = (t * t, t * t * t);
+        match self.kind {
+            SplineKind::CatmullRom => {
+                (p1 * 2.0
+                    + (p2 - p0) * t
+                    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+                    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+                    * 0.5
+            }
+            SplineKind::Bezier => {
+                let u = 1.0 - t;
+                p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t2) + p3 * t3
+            }
+        }
+    }
+
+    /// Returns the derivative at a curve parameter, pointing along the curve.
+    pub fn derivative(&self, t: f32) -> Vec3 {
+        let Some(([p0, p1, p2, p3], t)) = self.segment(t) else {
+            return Vec3::zero();
+        };
+        match self.kind {
+            SplineKind::CatmullRom => {
+                ((p2 - p0)
+                    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (2.0 * t)
+                    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * (3.0 * t * t))
+                    * 0.5
+            }
+            SplineKind::Bezier => {
+                let u = 1.0 - t;
+                (p1 - p0) * (3.0 * u * u) + (p2 - p1) * (6.0 * u * t) + (p3 - p2) * (3.0 * t * t)
+            }
+        }
+    }
+
+    /// Returns the unit direction at a curve parameter, or zero on a degenerate curve.
+    pub fn tangent(&self, t: f32) -> Vec3 {
+        let derivative = self.derivative(t);
+        if derivative.magnitude2() > 1e-12 {
+            derivative.normalize()
+        } else {
+            Vec3::zero()
+        }
+    }
+
+    /// Rebuilds the arc length table.
+    fn measure(&mut self) {
+        let samples = self.segment_count() * SAMPLES_PER_SEGMENT;
+        self.lengths.clear();
+        self.lengths.push(0.0);
+        let mut previous = self.position(0.0);
+        let mut total = 0.0;
+        for index in 1..=samples {
+            let point = self.position(index as f32 / SAMPLES_PER_SEGMENT as f32);
+            total += (point - previous).magnitude();
+            self.lengths.push(total);
+            previous = point;
+        }
+    }
+
+    /// Returns the length of the curve.
+    pub fn length(&self) -> f32 {
+        self.lengths.last().copied().unwrap_or(0.0)
+    }
+
+    /// Converts a distance along the curve to a curve parameter.
+    pub fn parameter_at_distance(&self, distance: f32) -> f32 {
+        let length = self.length();
+        if length <= 0.0 {
+            return 0.0;
+        }
+        let distance = if self.closed {
+            distance.rem_euclid(length)
+        } else {
+            distance.clamp(0.0, length)
+        };
+        let upper = self
+            .lengths
+            .partition_point(|&sample| sample < distance)
+            .clamp(1, self.lengths.len() - 1);
+        let (start, end) = (self.lengths[upper - 1], self.lengths[upper]);
+        let fraction = if end > start {
+            (distance - start) / (end - start)
+        } else {
+            0.0
+        };
+        (upper - 1) as f32 / SAMPLES_PER_SEGMENT as f32 + fraction / SAMPLES_PER_SEGMENT as f32
+    }
+
+    /// Converts a curve parameter to a distance along the curve.
+    pub fn distance_at_parameter(&self, t: f32) -> f32 {
+        if self.lengths.len() < 2 {
+            return 0.0;
+        }
+        let sample = (t.max(0.0) * SAMPLES_PER_SEGMENT as f32).min((self.lengths.len() - 1) as f32);
+        let index = (sample.floor() as usize).min(self.lengths.len() - 2);
+        let fraction = sample - index as f32;
+        self.lengths[index] + (self.lengths[index + 1] - self.lengths[index]) * fraction
+    }
+
+    /// Returns the position a distance along the curve. Closed curves wrap.
+    pub fn position_at_distance(&self, distance: f32) -> Vec3 {
+        self.position(self.parameter_at_distance(distance))
+    }
+
+    /// Returns the unit direction a distance along the curve.
+    pub fn tangent_at_distance(&self, distance: f32) -> Vec3 {
+        self.tangent(self.parameter_at_distance(distance))
+    }
+
+    /// Returns the distance along the curve of the point closest to `point`,
+    /// e.g. to measure race progress or snap onto a rail.
+    pub fn closest_distance(&self, point: Vec3) -> f32 {
+        let samples = self.lengths.len();
+        if samples < 2 {
+            return 0.0;
+        }
+        let step = 1.0 / SAMPLES_PER_SEGMENT as f32;
+        let distance2 = |t: f32| (self.position(t) - point).magnitude2();
+        let nearest = (0..samples)
+            .map(|index| index as f32 * step)
+            .min_by(|a, b| distance2(*a).total_cmp(&distance2(*b)))
+            .unwrap_or(0.0);
+
+        let (mut low, mut high) = ((nearest - step).max(0.0), nearest + step);
+        high = high.min((samples - 1) as f32 * step);
+        for _ in 0..16 {
+            let third = (high - low) / 3.0;
+            if distance2(low + third) < distance2(high - third) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        self.distance_at_parameter((low + high) * 0.5)
+    }
+
+    /// Returns points along the curve for drawing, with `per_segment` lines
+    /// per segment.
+    pub fn polyline(&self, per_segment: usize) -> Vec<Vec3> {
+        let per_segment = per_segment.max(1);
+        let steps = self.segment_count() * per_segment;
+        let end = if self.closed { steps } else { steps + 1 };
+        (0..end)
+            .map(|index| self.position(index as f32 / per_segment as f32))
+            .collect()
+    }
+
+    /// Returns the index of the point nearest to a ray within `radius`, for
+    /// picking handles with the mouse.
+    pub fn pick(&self, ray: &Ray, radius: f32) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, point)| Some((index, ray.intersect_sphere(*point, radius)?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the lines from Bézier anchors to their control points, for
+    /// drawing tangent handles. Empty for Catmull-Rom splines.
+    pub fn handle_lines(&self) -> Vec<(Vec3, Vec3)> {
+        if self.kind != SplineKind::Bezier {
+            return Vec::new();
+        }
+        (0..self.segment_count())
+            .flat_map(|segment| {
+                let start = segment * 3;
+                [
+                    (self.points[start], self.points[start + 1]),
+                    (self.points[start + 3], self.points[start + 2]),
+                ]
+            })
+            .collect()
+    }
+}
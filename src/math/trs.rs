@@ -0,0 +1,28 @@
+use super::{InnerSpace, Mat3, Mat4, Quat, SquareMatrix, Vec3};
+
+/// Builds a matrix that scales, then rotates, then translates.
+pub fn compose_trs(translation: Vec3, rotation: Quat, scale: Vec3) -> Mat4 {
+    Mat4::from_translation(translation)
+        * Mat4::from(rotation)
+        * Mat4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+}
+
+/// Splits an affine matrix into translation, rotation, and scale.
+///
+/// Shear can't be represented and is lost. A mirrored matrix is reported as a
+/// negative X scale.
+pub fn decompose_trs(matrix: &Mat4) -> (Vec3, Quat, Vec3) {
+    let translation = matrix.w.truncate();
+    let x = matrix.x.truncate();
+    let y = matrix.y.truncate();
+    let z = matrix.z.truncate();
+
+    let mut scale = Vec3::new(x.magnitude(), y.magnitude(), z.magnitude());
+    if Mat3::from_cols(x, y, z).determinant() < 0.0 {
+        scale.x = -scale.x;
+    }
+
+    let rotation_matrix = Mat3::from_cols(x / scale.x, y / scale.y, z / scale.z);
+    let rotation = Quat::from(rotation_matrix).normalize();
+    (translation, rotation, scale)
+}
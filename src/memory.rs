@@ -0,0 +1,358 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Mutex;
+
+static USAGE: Mutex<Vec<(MemoryCategory, MemoryUsage)>> = Mutex::new(Vec::new());
+
+/// What memory is used for. The engine tracks textures and buffers on the
+/// GPU and sounds on the CPU; games name their own CPU categories, such as
+/// `Custom("levels")`, and track them with [`Tracked`] or [`allocate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MemoryCategory {
+    Textures,
+    Buffers,
+    Audio,
+    Custom(&'static str),
+}
+
+impl MemoryCategory {
+    /// Checks if the memory is on the GPU rather than the CPU.
+    pub fn is_gpu(self) -> bool {
+        matches!(self, MemoryCategory::Textures | MemoryCategory::Buffers)
+    }
+
+    /// Returns a human-readable name for HUDs and logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            MemoryCategory::Textures => "Textures",
+            MemoryCategory::Buffers => "Buffers",
+            MemoryCategory::Audio => "Audio",
+            MemoryCategory::Custom(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for MemoryCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Estimated memory of one category.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub bytes: usize,
+    /// Allocations alive, such as textures or sounds.
+    pub count: usize,
+    /// Most bytes used at once since startup.
+    pub peak_bytes: usize,
+}
+
+/// # Memory Stats
+///
+/// A snapshot of estimated memory use per category, for HUDs, logs and
+/// telemetry. GPU figures are estimates from sizes and formats; drivers add
+/// padding and keep copies of their own.
+///
+/// ## Example
+/// ```ignore
+/// let stats = memory::stats();
+/// log::info!("GPU {} / CPU {}", format_bytes(stats.gpu_bytes()), format_bytes(stats.cpu_bytes()));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Every category that was ever used, in category order.
+    pub categories: Vec<(MemoryCategory, MemoryUsage)>,
+}
+
+impl MemoryStats {
+    /// Returns the usage of one category.
+    pub fn get(&self, category: MemoryCategory) -> MemoryUsage {
+        self.categories
+            .iter()
+            .find(|(existing, _)| *existing == category)
+            .map_or_else(MemoryUsage::default, |(_, usage)| *usage)
+    }
+
+    /// Returns the estimated bytes on the GPU.
+    pub fn gpu_bytes(&self) -> usize {
+        self.sum(true)
+    }
+
+    /// Returns the tracked bytes on the CPU.
+    pub fn cpu_bytes(&self) -> usize {
+        self.sum(false)
+    }
+
+    fn sum(&self, gpu: bool) -> usize {
+        self.categories
+            .iter()
+            .filter(|(category, _)| category.is_gpu() == gpu)
+            .map(|(_, usage)| usage.bytes)
+            .sum()
+    }
+}
+
+/// Records an allocation of `bytes` in a category. Safe to call from any
+/// thread.
+pub fn allocate(category: MemoryCategory, bytes: usize) {
+    update(category, |usage| {
+        usage.bytes += bytes;
+        usage.count += 1;
+    });
+}
+
+/// Records that an allocation of `bytes` was freed.
+pub fn free(category: MemoryCategory, bytes: usize) {
+    update(category, |usage| {
+        usage.bytes = usage.bytes.saturating_sub(bytes);
+        usage.count = usage.count.saturating_sub(1);
+    });
+}
+
+/// Records that an allocation changed size.
+pub fn resize(category: MemoryCategory, old_bytes: usize, new_bytes: usize) {
+    if old_bytes != new_bytes {
+        update(category, |usage| {
+            usage.bytes = (usage.bytes + new_bytes).saturating_sub(old_bytes);
+        });
+    }
+}
+
+/// Returns the usage of one category.
+pub fn usage(category: MemoryCategory) -> MemoryUsage {
+    stats().get(category)
+}
+
+/// Returns the usage of every category.
+pub fn stats() -> MemoryStats {
+    let mut categories = lock().clone();
+    categories.sort_by_key(|(category, _)| *category);
+    MemoryStats { categories }
+}
+
+/// Formats a byte count for people, such as `12.5 MB`.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn update(category: MemoryCategory, change: impl FnOnce(&mut MemoryUsage)) {
+    let mut categories = lock();
+    let index = match categories
+        .iter()
+        .position(|(existing, _)| *existing == category)
+    {
+        Some(index) => index,
+        None => {
+            categories.push((category, MemoryUsage::default()));
+            categories.len() - 1
+        }
+    };
+    let usage = &mut categories[index].1;
+    change(usage);
+    usage.peak_bytes = usage.peak_bytes.max(usage.bytes);
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<(MemoryCategory, MemoryUsage)>> {
+    // A panic while holding the lock leaves the counts intact; keep going.
+    USAGE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// # Tracked
+///
+/// A value whose memory counts towards a category for as long as it lives,
+/// such as the decoded data of an asset. Derefs to the value.
+///
+/// ## Example
+/// ```ignore
+/// let bytes = level.tiles.len() * std::mem::size_of::<Tile>();
+/// let level = Arc::new(Tracked::new(level, MemoryCategory::Custom("levels"), bytes));
+/// ```
+pub struct Tracked<T> {
+    value: T,
+    category: MemoryCategory,
+    bytes: usize,
+}
+
+impl<T> Tracked<T> {
+    /// Starts tracking a value taking `bytes` in a category.
+    pub fn new(value: T, category: MemoryCategory, bytes: usize) -> Self {
+        allocate(category, bytes);
+        Self {
+            value,
+            category,
+            bytes,
+        }
+    }
+
+    /// Returns the tracked bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the category.
+    pub fn category(&self) -> MemoryCategory {
+        self.category
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        free(self.category, self.bytes);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Tracked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+/// Called when a category is over budget with the bytes over, returning the
+/// bytes it freed.
+pub type EvictionCallback = dyn FnMut(MemoryCategory, usize) -> usize + Send;
+
+/// A category found over its budget by [`MemoryBudgets::update`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub category: MemoryCategory,
+    pub bytes: usize,
+    pub budget: usize,
+    /// Bytes the eviction callbacks reported freeing.
+    pub freed: usize,
+}
+
+/// # Memory Budgets
+///
+/// Limits on the memory of categories. Each update, categories over budget
+/// are logged once until they fall back under, and their eviction callbacks
+/// are asked to free the difference, e.g. by unloading assets nothing uses.
+///
+/// ## Example
+/// ```ignore
+/// let mut budgets = MemoryBudgets::new()
+///     .with_budget(MemoryCategory::Textures, 512 * 1024 * 1024)
+///     .with_budget(MemoryCategory::Audio, 64 * 1024 * 1024);
+/// budgets.on_over_budget(MemoryCategory::Audio, move |_, excess| sound_cache.evict_unused(excess));
+///
+/// // every frame
+/// budgets.update();
+/// ```
+#[derive(Default)]
+pub struct MemoryBudgets {
+    budgets: HashMap<MemoryCategory, usize>,
+    callbacks: Vec<(MemoryCategory, Box<EvictionCallback>)>,
+    exceeded: HashSet<MemoryCategory>,
+}
+
+impl MemoryBudgets {
+    /// Creates budgets without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the budget of a category in bytes.
+    pub fn with_budget(mut self, category: MemoryCategory, bytes: usize) -> Self {
+        self.set_budget(category, bytes);
+        self
+    }
+
+    /// Sets the budget of a category in bytes.
+    pub fn set_budget(&mut self, category: MemoryCategory, bytes: usize) {
+        self.budgets.insert(category, bytes);
+    }
+
+    /// Removes the budget of a category.
+    pub fn remove_budget(&mut self, category: MemoryCategory) {
+        self.budgets.remove(&category);
+        self.exceeded.remove(&category);
+    }
+
+    /// Returns the budget of a category.
+    pub fn budget(&self, category: MemoryCategory) -> Option<usize> {
+        self.budgets.get(&category).copied()
+    }
+
+    /// Adds a callback asked to free memory when a category is over budget.
+    /// Callbacks run in the order they were added until enough was freed.
+    pub fn on_over_budget<F>(&mut self, category: MemoryCategory, callback: F)
+    where
+        F: FnMut(MemoryCategory, usize) -> usize + Send + 'static,
+    {
+        self.callbacks.push((category, Box::new(callback)));
+    }
+
+    /// Checks every budgeted category, evicting from those over budget, and
+    /// returns them.
+    pub fn update(&mut self) -> Vec<BudgetExceeded> {
+        let stats = stats();
+        let mut budgets: Vec<_> = self
+            .budgets
+            .iter()
+            .map(|(category, budget)| (*category, *budget))
+            .collect();
+        budgets.sort();
+
+        let mut exceeded = Vec::new();
+        for (category, budget) in budgets {
+            let bytes = stats.get(category).bytes;
+            if bytes <= budget {
+                self.exceeded.remove(&category);
+                continue;
+            }
+            if self.exceeded.insert(category) {
+                log::warn!(
+                    "{} memory over budget: {} of {}",
+                    category,
+                    format_bytes(bytes),
+                    format_bytes(budget)
+                );
+            }
+            let mut freed = 0;
+            for (_, callback) in self
+                .callbacks
+                .iter_mut()
+                .filter(|(callback_category, _)| *callback_category == category)
+            {
+                if freed >= bytes - budget {
+                    break;
+                }
+                freed += callback(category, bytes - budget - freed);
+            }
+            exceeded.push(BudgetExceeded {
+                category,
+                bytes,
+                budget,
+                freed,
+            });
+        }
+        exceeded
+    }
+}
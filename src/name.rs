@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+
+static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+
+/// Every string interned so far, looked up both ways.
+struct Interner {
+    ids: HashMap<&'static str, u32>,
+    names: Vec<&'static str>,
+}
+
+fn interner() -> &'static RwLock<Interner> {
+    INTERNER.get_or_init(|| {
+        RwLock::new(Interner {
+            ids: HashMap::from([("", 0)]),
+            names: vec![""],
+        })
+    })
+}
+
+/// # Name
+///
+/// An interned string: a number standing for a string, so comparing, copying
+/// and hashing it never touches the text. Used for uniform names, asset paths,
+/// atlas regions and entity names, anywhere the same strings are looked up
+/// every frame. Interning the same string twice gives the same name.
+///
+/// Interned strings are kept for the rest of the program, so names are meant
+/// for identifiers, not arbitrary text such as chat messages.
+///
+/// Names order by their text, so sorted output doesn't depend on the order
+/// strings were first interned in.
+///
+/// ## Example
+/// ```ignore
+/// let u_model = Name::new("u_model");
+///
+/// // every draw, without hashing the string
+/// shader.set_matrix4fv_uniform(u_model, &model);
+/// shader.set_1f_uniform(name!("u_time"), time);
+///
+/// world.insert(player, Name::new("player"));
+/// let player = world.find_named("player");
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Name(u32);
+
+impl Name {
+    /// Interns a string.
+    pub fn new(text: &str) -> Self {
+        if let Some(name) = Self::get(text) {
+            return name;
+        }
+        let mut interner = interner()
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Another thread may have interned it since the read.
+        if let Some(&id) = interner.ids.get(text) {
+            return Name(id);
+        }
+        let text: &'static str = Box::leak(text.into());
+        let id = interner.names.len() as u32;
+        interner.names.push(text);
+        interner.ids.insert(text, id);
+        Name(id)
+    }
+
+    /// Returns the name of a string if it was interned, without interning it.
+    pub fn get(text: &str) -> Option<Self> {
+        interner()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .ids
+            .get(text)
+            .map(|&id| Name(id))
+    }
+
+    /// Returns the interned string.
+    pub fn as_str(self) -> &'static str {
+        interner()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .names[self.0 as usize]
+    }
+
+    /// Checks if this is the empty string, which is also the default.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl From<&str> for Name {
+    fn from(text: &str) -> Self {
+        Name::new(text)
+    }
+}
+
+impl From<&String> for Name {
+    fn from(text: &String) -> Self {
+        Name::new(text)
+    }
+}
+
+impl From<String> for Name {
+    fn from(text: String) -> Self {
+        Name::new(&text)
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.0 == other.0 {
+            Ordering::Equal
+        } else {
+            self.as_str().cmp(other.as_str())
+        }
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Interns a string once, the first time this line runs, and returns the
+/// same [`Name`] every time after without hashing the string.
+///
+/// ```ignore
+/// shader.set_1f_uniform(name!("u_time"), time);
+/// ```
+#[macro_export]
+macro_rules! name {
+    ($text:expr) => {{
+        static NAME: ::std::sync::OnceLock<$crate::name::Name> = ::std::sync::OnceLock::new();
+        *NAME.get_or_init(|| $crate::name::Name::new($text))
+    }};
+}
+
+/// # Asset Path
+///
+/// An interned asset path in one canonical spelling, so `assets\ui\icon.png`,
+/// `./assets/ui/icon.png` and `assets//ui/icon.png` are the same key.
+/// Separators become `/`, and empty and `.` segments are dropped.
+///
+/// ## Example
+/// ```ignore
+/// let path = AssetPath::new("./assets\\textures/brick.png");
+/// assert_eq!(path.as_str(), "assets/textures/brick.png");
+/// assert_eq!(path.extension(), Some("png"));
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetPath(Name);
+
+impl AssetPath {
+    /// Interns a path in its canonical spelling.
+    pub fn new(path: &str) -> Self {
+        let absolute = path.starts_with(['/', '\\']);
+        let canonical = !path.contains('\\')
+            && path
+                .split('/')
+                .skip(absolute as usize)
+                .all(|segment| !segment.is_empty() && segment != ".");
+        if canonical {
+            return AssetPath(Name::new(path));
+        }
+        let segments: Vec<&str> = path
+            .split(['/', '\\'])
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect();
+        let canonical = segments.join("/");
+        if absolute {
+            AssetPath(Name::new(&format!("/{}", canonical)))
+        } else {
+            AssetPath(Name::new(&canonical))
+        }
+    }
+
+    /// Returns the interned path.
+    pub fn name(self) -> Name {
+        self.0
+    }
+
+    /// Returns the path as a string.
+    pub fn as_str(self) -> &'static str {
+        self.0.as_str()
+    }
+
+    /// Returns the last segment, such as `brick.png`.
+    pub fn file_name(self) -> &'static str {
+        let path = self.as_str();
+        path.rsplit_once('/')
+            .map_or(path, |(_, file_name)| file_name)
+    }
+
+    /// Returns the extension without the dot, such as `png`.
+    pub fn extension(self) -> Option<&'static str> {
+        let (stem, extension) = self.file_name().rsplit_once('.')?;
+        (!stem.is_empty()).then_some(extension)
+    }
+}
+
+impl From<&str> for AssetPath {
+    fn from(path: &str) -> Self {
+        AssetPath::new(path)
+    }
+}
+
+impl From<&String> for AssetPath {
+    fn from(path: &String) -> Self {
+        AssetPath::new(path)
+    }
+}
+
+impl From<String> for AssetPath {
+    fn from(path: String) -> Self {
+        AssetPath::new(&path)
+    }
+}
+
+impl fmt::Debug for AssetPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for AssetPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_macro_interns_once_per_call_site() {
+        let names: Vec<Name> = (0..3).map(|_| name!("u_macro_test")).collect();
+        assert!(names.iter().all(|&name| name == Name::new("u_macro_test")));
+        assert_eq!(names[0].as_str(), "u_macro_test");
+    }
+}
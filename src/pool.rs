@@ -0,0 +1,117 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hash};
+
+/// # Recycle
+///
+/// Resets a value to be reused like a new one while keeping its allocations,
+/// for example clearing a `Vec` but not shrinking it.
+pub trait Recycle {
+    /// Resets the value for reuse.
+    fn recycle(&mut self);
+}
+
+impl<T> Recycle for Vec<T> {
+    fn recycle(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Recycle for VecDeque<T> {
+    fn recycle(&mut self) {
+        self.clear();
+    }
+}
+
+impl Recycle for String {
+    fn recycle(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Recycle for HashMap<K, V, S> {
+    fn recycle(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher> Recycle for HashSet<T, S> {
+    fn recycle(&mut self) {
+        self.clear();
+    }
+}
+
+/// # Pool
+///
+/// Keeps values that were given back so later takes reuse them, and their
+/// allocations, instead of creating new ones. Suited to objects created and
+/// dropped every frame, such as particles, batches and scratch buffers.
+///
+/// ## Example
+/// ```ignore
+/// let mut buffers: Pool<Vec<f32>> = Pool::new();
+///
+/// let mut vertices = buffers.take();
+/// vertices.extend_from_slice(&quad);
+/// upload(&vertices);
+/// buffers.give(vertices);
+/// ```
+pub struct Pool<T> {
+    /// Values kept at most; more given back are dropped.
+    pub max_free: usize,
+    free: Vec<T>,
+    created: usize,
+}
+
+impl<T: Default + Recycle> Pool<T> {
+    /// Creates an empty pool keeping up to 64 values.
+    pub fn new() -> Self {
+        Self {
+            max_free: 64,
+            free: Vec::new(),
+            created: 0,
+        }
+    }
+
+    /// Sets the most values kept.
+    pub fn with_max_free(mut self, max_free: usize) -> Self {
+        self.max_free = max_free;
+        self
+    }
+
+    /// Returns a value given back earlier, or a new one if there is none.
+    pub fn take(&mut self) -> T {
+        self.free.pop().unwrap_or_else(|| {
+            self.created += 1;
+            T::default()
+        })
+    }
+
+    /// Recycles a value and keeps it for a later take.
+    pub fn give(&mut self, mut value: T) {
+        if self.free.len() < self.max_free {
+            value.recycle();
+            self.free.push(value);
+        }
+    }
+
+    /// Returns the number of values waiting to be taken.
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Returns the number of values ever created because the pool was empty.
+    pub fn created(&self) -> usize {
+        self.created
+    }
+
+    /// Drops every kept value.
+    pub fn clear(&mut self) {
+        self.free.clear();
+    }
+}
+
+impl<T: Default + Recycle> Default for Pool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
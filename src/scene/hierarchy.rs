@@ -0,0 +1,157 @@
+use super::transform::Transform;
+use crate::ecs::{system_ticks, Access, Entity, FunctionSystem, Relation, System, Tick, World};
+use crate::math::*;
+
+/// # Parent
+///
+/// Makes an entity's [`Transform`] relative to another entity's, so moving
+/// the parent moves the child along with it. Parent is a relation, registered
+/// by [`transform_propagation_system`] or `world.register_relation::<Parent>()`:
+/// `world.related::<Parent>(parent)` lists the children, and despawning a
+/// parent detaches them, making them roots that keep their local transform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+impl Relation for Parent {
+    fn target(&self) -> Entity {
+        self.0
+    }
+
+    fn on_detach(world: &mut World, child: Entity) {
+        world.set_changed::<Transform>(child);
+    }
+}
+
+/// # Global Transform
+///
+/// An entity's world matrix: its [`Transform`] combined with every parent's.
+/// Written by [`propagate_transforms`] only when the transform or one of its
+/// parents changed, so renderers and bounds can filter on
+/// `Changed<GlobalTransform>` to redo work only for entities that moved.
+///
+/// ## Example
+/// ```ignore
+/// let globals = world.query_filtered::<GlobalTransform, Changed<GlobalTransform>>();
+/// for (entity, global) in globals.iter() {
+///     instances.upload(entity, &global.0);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlobalTransform(pub Mat4);
+
+impl GlobalTransform {
+    /// Returns the world-space position.
+    pub fn translation(&self) -> Vec3 {
+        self.0.w.truncate()
+    }
+
+    /// Transforms a point from the entity's local space to world space.
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        (self.0 * point.extend(1.0)).truncate()
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self(Mat4::identity())
+    }
+}
+
+/// An entity's bounds in its local space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocalBounds(pub Aabb);
+
+/// An entity's bounds in world space, kept up to date from its
+/// [`LocalBounds`] and [`GlobalTransform`] by [`update_world_bounds`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldBounds(pub Aabb);
+
+/// Returns a system that keeps every [`GlobalTransform`] up to date.
+pub fn transform_propagation_system() -> impl System {
+    FunctionSystem::new(
+        "transform_propagation",
+        Access::new()
+            .read::<Transform>()
+            .relation::<Parent>()
+            .write::<GlobalTransform>(),
+        propagate_transforms,
+    )
+}
+
+/// Recomputes the [`GlobalTransform`] of every entity whose [`Transform`] or
+/// [`Parent`] changed since the system last ran, and of their descendants,
+/// adding one to entities that have a transform but no global transform yet.
+/// Untouched entities keep their global transform and aren't marked changed.
+///
+/// Entities whose parent has no transform are treated as roots. Children of
+/// a despawned parent are detached and recomputed as roots; removing a
+/// [`Parent`] by hand isn't noticed by itself, so mark the child's transform
+/// changed too.
+pub fn propagate_transforms(world: &World) {
+    let last_run = system_ticks().map_or(Tick::default(), |ticks| ticks.last_run);
+    let transforms = world.read::<Transform>();
+    let parents = world.read::<Parent>();
+    let mut globals = world.write::<GlobalTransform>();
+
+    let mut stack: Vec<(Entity, Mat4, bool)> = transforms
+        .entities()
+        .filter(|entity| {
+            parents
+                .get(*entity)
+                .is_none_or(|parent| !transforms.contains(parent.0))
+        })
+        .map(|root| (root, Mat4::identity(), false))
+        .collect();
+
+    while let Some((entity, parent_matrix, parent_dirty)) = stack.pop() {
+        let Some(transform) = transforms.get(entity) else {
+            continue;
+        };
+        let dirty = parent_dirty
+            || transforms.is_changed(entity, last_run)
+            || parents.is_changed(entity, last_run)
+            || !globals.contains(entity);
+        let matrix = if dirty {
+            let matrix = parent_matrix * transform.matrix();
+            globals.insert(entity, GlobalTransform(matrix));
+            matrix
+        } else {
+            globals.get(entity).expect("Checked above").0
+        };
+        let children = world.related::<Parent>(entity);
+        stack.extend(children.iter().map(|&child| (child, matrix, dirty)));
+    }
+}
+
+/// Returns a system that keeps every [`WorldBounds`] up to date.
+pub fn world_bounds_system() -> impl System {
+    FunctionSystem::new(
+        "world_bounds",
+        Access::new()
+            .read::<LocalBounds>()
+            .read::<GlobalTransform>()
+            .write::<WorldBounds>(),
+        update_world_bounds,
+    )
+}
+
+/// Recomputes the [`WorldBounds`] of every entity whose [`LocalBounds`] or
+/// [`GlobalTransform`] changed since the system last ran.
+pub fn update_world_bounds(world: &World) {
+    let last_run = system_ticks().map_or(Tick::default(), |ticks| ticks.last_run);
+    let local_bounds = world.read::<LocalBounds>();
+    let globals = world.read::<GlobalTransform>();
+    let mut world_bounds = world.write::<WorldBounds>();
+
+    for (entity, local) in local_bounds.iter() {
+        let Some(global) = globals.get(*entity) else {
+            continue;
+        };
+        let dirty = local_bounds.is_changed(*entity, last_run)
+            || globals.is_changed(*entity, last_run)
+            || !world_bounds.contains(*entity);
+        if dirty {
+            world_bounds.insert(*entity, WorldBounds(local.0.transformed(&global.0)));
+        }
+    }
+}
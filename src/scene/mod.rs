@@ -0,0 +1,12 @@
+pub mod hierarchy;
+pub mod path_follower;
+pub mod streaming;
+pub mod transform;
+
+pub use hierarchy::{
+    propagate_transforms, transform_propagation_system, update_world_bounds, world_bounds_system,
+    GlobalTransform, LocalBounds, Parent, WorldBounds,
+};
+pub use path_follower::{path_follower_system, PathFollower, PathMode, PathOrientation};
+pub use streaming::{CellCoord, LoadingProgress, LoadingScreen, SceneStreamer, StreamingEvent};
+pub use transform::Transform;
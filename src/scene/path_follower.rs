@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use super::Transform;
+use crate::ecs::{Access, FunctionSystem, System, World};
+use crate::math::*;
+
+/// What a [`PathFollower`] does when it reaches the end of an open path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathMode {
+    /// Stops at the end.
+    #[default]
+    Once,
+    /// Jumps back to the start. Closed paths always loop smoothly.
+    Loop,
+    /// Turns around and heads back, like a moving platform.
+    PingPong,
+}
+
+/// How a [`PathFollower`] rotates its entity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathOrientation {
+    /// Leaves the rotation alone.
+    #[default]
+    Fixed,
+    /// Faces along the path with `-Z`, keeping `+Y` up, for vehicles and camera rails.
+    Forward,
+    /// Turns around `Z` so `+X` points along the path, for 2D sprites.
+    Forward2d,
+}
+
+/// # Path Follower
+///
+/// A component that moves its entity along a [`Spline`] at a constant speed,
+/// measured in world units per second along the curve.
+///
+/// ## Example
+/// ```ignore
+/// let rail = Arc::new(Spline::catmull_rom(points));
+/// world.insert(platform, PathFollower::new(rail, 2.0).with_mode(PathMode::PingPong));
+/// schedule.add_system(path_follower_system());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathFollower {
+    pub path: Arc<Spline>,
+    /// Units per second along the path.
+    pub speed: f32,
+    pub mode: PathMode,
+    pub orientation: PathOrientation,
+    /// Added to the path position, e.g. to ride above a racing line.
+    pub offset: Vec3,
+    pub paused: bool,
+    distance: f32,
+    direction: f32,
+}
+
+impl PathFollower {
+    /// Creates a follower at the start of a path.
+    pub fn new(path: Arc<Spline>, speed: f32) -> Self {
+        Self {
+            path,
+            speed,
+            mode: PathMode::Once,
+            orientation: PathOrientation::Fixed,
+            offset: Vec3::zero(),
+            paused: false,
+            distance: 0.0,
+            direction: 1.0,
+        }
+    }
+
+    /// Sets what happens at the end of the path.
+    pub fn with_mode(mut self, mode: PathMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets how the entity is rotated.
+    pub fn with_orientation(mut self, orientation: PathOrientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Sets the offset from the path.
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Starts at a distance along the path instead of its start.
+    pub fn starting_at(mut self, distance: f32) -> Self {
+        self.distance = distance.clamp(0.0, self.path.length());
+        self
+    }
+
+    /// Returns the distance travelled along the path.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Jumps to a distance along the path.
+    pub fn set_distance(&mut self, distance: f32) {
+        self.distance = distance.clamp(0.0, self.path.length());
+    }
+
+    /// Returns how far along the path the follower is, from zero to one.
+    pub fn progress(&self) -> f32 {
+        let length = self.path.length();
+        if length > 0.0 {
+            self.distance / length
+        } else {
+            0.0
+        }
+    }
+
+    /// Checks if the follower stopped at the end of a [`PathMode::Once`] path.
+    pub fn is_finished(&self) -> bool {
+        self.mode == PathMode::Once && !self.path.is_closed() && self.distance >= self.path.length()
+    }
+
+    /// Checks if the follower is heading back towards the start.
+    pub fn is_reversed(&self) -> bool {
+        self.direction < 0.0
+    }
+
+    /// Moves along the path.
+    pub fn advance(&mut self, delta_time: f32) {
+        let length = self.path.length();
+        if self.paused || length <= 0.0 {
+            return;
+        }
+        let mut distance = self.distance + self.speed * self.direction * delta_time;
+        if self.path.is_closed() {
+            distance = distance.rem_euclid(length);
+        } else {
+            match self.mode {
+                PathMode::Once => distance = distance.clamp(0.0, length),
+                PathMode::Loop => distance = distance.rem_euclid(length),
+                PathMode::PingPong => {
+                    if distance > length {
+                        distance = (2.0 * length - distance).max(0.0);
+                        self.direction = -self.direction;
+                    } else if distance < 0.0 {
+                        distance = (-distance).min(length);
+                        self.direction = -self.direction;
+                    }
+                }
+            }
+        }
+        self.distance = distance;
+    }
+
+    /// Places a transform at the follower's point on the path.
+    pub fn apply(&self, transform: &mut Transform) {
+        let t = self.path.parameter_at_distance(self.distance);
+        transform.translation = self.path.position(t) + self.offset;
+
+        let mut tangent = self.path.tangent(t);
+        if tangent.magnitude2() <= 0.0 {
+            return;
+        }
+        if self.direction < 0.0 {
+            tangent = -tangent;
+        }
+        match self.orientation {
+            PathOrientation::Fixed => {}
+            PathOrientation::Forward => {
+                if tangent.cross(Vec3::unit_y()).magnitude2() > 1e-6 {
+                    transform.rotation = quat::look_rotation(tangent, Vec3::unit_y());
+                }
+            }
+            PathOrientation::Forward2d => {
+                let angle = Rad(tangent.y.atan2(tangent.x));
+                transform.rotation = Quat::from_angle_z(angle);
+            }
+        }
+    }
+}
+
+/// Returns a system that moves every [`PathFollower`] along its path.
+pub fn path_follower_system() -> impl System {
+    FunctionSystem::new(
+        "path_followers",
+        Access::new().write::<PathFollower>().write::<Transform>(),
+        update_path_followers,
+    )
+}
+
+/// Advances path followers and updates their transforms.
+pub fn update_path_followers(world: &World) {
+    let delta_time = world.time().delta();
+    let mut transforms = world.write::<Transform>();
+    for (entity, follower) in world.write::<PathFollower>().iter_mut() {
+        follower.advance(delta_time);
+        if let Some(transform) = transforms.get_mut(*entity) {
+            follower.apply(transform);
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::ecs::{Entity, World};
+use crate::jobs::{JobHandle, JobSystem};
+use crate::math::*;
+
+/// Grid coordinates of a streaming cell on the XZ plane; cell `(1, 0)` covers
+/// `cell_size..2 * cell_size` along X.
+pub type CellCoord = (i32, i32);
+
+/// Reads a cell's data off the main thread, e.g. from a file.
+pub type CellLoader<D> = dyn Fn(CellCoord) -> Result<D, String> + Send + Sync;
+
+/// Turns a loaded cell's data into entities on the main thread and returns
+/// them, so they can be despawned when the cell unloads.
+pub type CellSpawner<D> = dyn FnMut(&mut World, CellCoord, D) -> Vec<Entity>;
+
+/// Something that happened to a cell during [`SceneStreamer::update`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamingEvent {
+    /// The cell's entities were spawned.
+    Loaded(CellCoord),
+    /// The cell's entities were despawned.
+    Unloaded(CellCoord),
+    /// The loader returned an error; the cell is retried after
+    /// [`SceneStreamer::retry_delay`], doubling with every failure in a row.
+    Failed(CellCoord, String),
+}
+
+/// How far the cells needed around the focus are from being ready.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadingProgress {
+    /// Required cells that are spawned.
+    pub ready: usize,
+    /// Cells within the required radius of the focus.
+    pub required: usize,
+    /// Cells being read in the background, required or not.
+    pub in_flight: usize,
+}
+
+impl LoadingProgress {
+    /// Returns the fraction of required cells that are ready, 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.required == 0 {
+            1.0
+        } else {
+            self.ready as f32 / self.required as f32
+        }
+    }
+
+    /// Checks if every required cell is ready.
+    pub fn is_complete(&self) -> bool {
+        self.ready >= self.required
+    }
+}
+
+/// # Loading Screen
+///
+/// Hooks called by a [`SceneStreamer`] when the cells around the focus aren't
+/// ready, such as at the start of a level or after teleporting, and the game
+/// should hide the world until they are.
+///
+/// ## Example
+/// ```ignore
+/// struct Curtain(Arc<Mutex<Option<f32>>>);
+///
+/// impl LoadingScreen for Curtain {
+///     fn show(&mut self) {
+///         *self.0.lock().unwrap() = Some(0.0);
+///     }
+///     fn progress(&mut self, progress: LoadingProgress) {
+///         *self.0.lock().unwrap() = Some(progress.fraction());
+///     }
+///     fn hide(&mut self) {
+///         *self.0.lock().unwrap() = None;
+///     }
+/// }
+/// ```
+pub trait LoadingScreen {
+    /// Called when required cells start missing.
+    fn show(&mut self) {}
+
+    /// Called every update while shown.
+    fn progress(&mut self, _progress: LoadingProgress) {}
+
+    /// Called once every required cell is ready.
+    fn hide(&mut self) {}
+}
+
+enum CellState<D> {
+    /// Being read on the job system, after failing this many times in a row.
+    Loading(JobHandle<Result<D, String>>, u32),
+    /// Read, waiting for a spawn slot.
+    Ready(D),
+    Loaded(Vec<Entity>),
+    /// Failed this many times in a row, retried at the instant.
+    Failed(u32, Instant),
+}
+
+/// Most times the retry delay doubles for a cell that keeps failing.
+const MAX_RETRY_DOUBLINGS: u32 = 5;
+
+/// # Scene Streamer
+///
+/// Splits a large world into square cells on the XZ plane and keeps the ones
+/// around a focus point, usually the camera or player, loaded. Cell data is
+/// read on the engine's [IO pool](JobSystem::io), closest cells first, then
+/// spawned on the main thread a few cells per frame so arriving data never
+/// causes a hitch. Cells that fail to read are retried with a growing delay.
+/// Cells
+/// beyond the unload radius are despawned; the gap between the two radii
+/// keeps cells at the border from loading and unloading repeatedly.
+///
+/// ## Example
+/// ```ignore
+/// let mut streamer = SceneStreamer::new(
+///     64.0,
+///     |cell| LevelCell::read(&format!("levels/forest/{}_{}.cell", cell.0, cell.1)),
+///     |world, _cell, data: LevelCell| data.spawn(world),
+/// )
+/// .with_load_radius(3.0)
+/// .with_loading_screen(curtain);
+///
+/// // Every frame:
+/// for event in streamer.update(&mut world, camera_transform.translation) {
+///     log::debug!("{:?}", event);
+/// }
+/// ```
+pub struct SceneStreamer<D> {
+    pub cell_size: f32,
+    /// Cells whose center is within this many cells of the focus load.
+    pub load_radius: f32,
+    /// Cells further than this many cells unload; at least the load radius.
+    pub unload_radius: f32,
+    /// Cells that must be spawned before the loading screen hides, in cells.
+    /// Zero means just the focus cell.
+    pub required_radius: f32,
+    /// Most cells read in the background at once.
+    pub max_in_flight: usize,
+    /// Most cells spawned per update.
+    pub spawns_per_update: usize,
+    /// How long a cell that failed to read waits before it is read again.
+    pub retry_delay: Duration,
+    loader: Arc<CellLoader<D>>,
+    spawner: Box<CellSpawner<D>>,
+    jobs: Arc<JobSystem>,
+    cells: HashMap<CellCoord, CellState<D>>,
+    /// Reads of cells that unloaded before they finished, counted as in
+    /// flight until their jobs end.
+    abandoned: Vec<JobHandle<Result<D, String>>>,
+    pinned: HashSet<CellCoord>,
+    loading_screen: Option<Box<dyn LoadingScreen>>,
+    loading_screen_shown: bool,
+    progress: LoadingProgress,
+}
+
+impl<D: Send + 'static> SceneStreamer<D> {
+    /// Creates a streamer of cells `cell_size` units wide, loading cells
+    /// within two cells of the focus on the IO pool.
+    pub fn new<L, S>(cell_size: f32, loader: L, spawner: S) -> Self
+    where
+        L: Fn(CellCoord) -> Result<D, String> + Send + Sync + 'static,
+        S: FnMut(&mut World, CellCoord, D) -> Vec<Entity> + 'static,
+    {
+        Self {
+            cell_size,
+            load_radius: 2.0,
+            unload_radius: 3.0,
+            required_radius: 0.0,
+            max_in_flight: 4,
+            spawns_per_update: 1,
+            retry_delay: Duration::from_secs(1),
+            loader: Arc::new(loader),
+            spawner: Box::new(spawner),
+            jobs: JobSystem::io(),
+            cells: HashMap::new(),
+            abandoned: Vec::new(),
+            pinned: HashSet::new(),
+            loading_screen: None,
+            loading_screen_shown: false,
+            progress: LoadingProgress::default(),
+        }
+    }
+
+    /// Sets the load radius in cells, raising the unload radius to at least
+    /// one cell more.
+    pub fn with_load_radius(mut self, load_radius: f32) -> Self {
+        self.load_radius = load_radius;
+        self.unload_radius = self.unload_radius.max(load_radius + 1.0);
+        self
+    }
+
+    /// Sets the unload radius in cells.
+    pub fn with_unload_radius(mut self, unload_radius: f32) -> Self {
+        self.unload_radius = unload_radius;
+        self
+    }
+
+    /// Sets the radius in cells that must be ready before the loading screen
+    /// hides.
+    pub fn with_required_radius(mut self, required_radius: f32) -> Self {
+        self.required_radius = required_radius;
+        self
+    }
+
+    /// Sets the most cells read at once.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Sets the most cells spawned per update.
+    pub fn with_spawns_per_update(mut self, spawns_per_update: usize) -> Self {
+        self.spawns_per_update = spawns_per_update.max(1);
+        self
+    }
+
+    /// Sets how long a failed cell waits before it is read again.
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Sets the job system cells are read on.
+    pub fn with_job_system(mut self, jobs: Arc<JobSystem>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Sets the hooks shown while required cells load.
+    pub fn with_loading_screen(mut self, loading_screen: impl LoadingScreen + 'static) -> Self {
+        self.loading_screen = Some(Box::new(loading_screen));
+        self
+    }
+
+    /// Returns the cell containing a world position.
+    pub fn cell_at(&self, position: Vec3) -> CellCoord {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Returns the world position of a cell's center, at height zero.
+    pub fn cell_center(&self, cell: CellCoord) -> Vec3 {
+        vec3(
+            (cell.0 as f32 + 0.5) * self.cell_size,
+            0.0,
+            (cell.1 as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    /// Checks if a cell's entities are spawned.
+    pub fn is_loaded(&self, cell: CellCoord) -> bool {
+        matches!(self.cells.get(&cell), Some(CellState::Loaded(_)))
+    }
+
+    /// Returns the loaded cells, in no particular order.
+    pub fn loaded_cells(&self) -> impl Iterator<Item = CellCoord> + '_ {
+        self.cells.iter().filter_map(|(cell, state)| match state {
+            CellState::Loaded(_) => Some(*cell),
+            _ => None,
+        })
+    }
+
+    /// Returns the entities spawned for a cell.
+    pub fn cell_entities(&self, cell: CellCoord) -> &[Entity] {
+        match self.cells.get(&cell) {
+            Some(CellState::Loaded(entities)) => entities,
+            _ => &[],
+        }
+    }
+
+    /// Returns the progress of the required cells as of the last update.
+    pub fn progress(&self) -> LoadingProgress {
+        self.progress
+    }
+
+    /// Keeps a cell loaded regardless of distance, loading it before any
+    /// other, e.g. a teleport destination or a boss arena.
+    pub fn pin(&mut self, cell: CellCoord) {
+        self.pinned.insert(cell);
+    }
+
+    /// Lets a pinned cell unload again once it is out of range.
+    pub fn unpin(&mut self, cell: CellCoord) {
+        self.pinned.remove(&cell);
+    }
+
+    /// Loads and unloads cells around `focus`, spawns cells whose data
+    /// arrived and drives the loading screen. Call once per frame.
+    pub fn update(&mut self, world: &mut World, focus: Vec3) -> Vec<StreamingEvent> {
+        let mut events = Vec::new();
+        let center = self.cell_at(focus);
+        let distance = |cell: CellCoord| {
+            let (dx, dz) = ((cell.0 - center.0) as f32, (cell.1 - center.1) as f32);
+            (dx * dx + dz * dz).sqrt()
+        };
+        // Pinned cells come first, then the closest.
+        let priority = |cell: CellCoord, pinned: &HashSet<CellCoord>| {
+            if pinned.contains(&cell) {
+                -1.0
+            } else {
+                distance(cell)
+            }
+        };
+
+        // Unload what went out of range. Reads in flight keep counting against
+        // the limit until their jobs finish, then the result is discarded.
+        self.abandoned.retain(|handle| !handle.is_finished());
+        let unload: Vec<CellCoord> = self
+            .cells
+            .keys()
+            .copied()
+            .filter(|cell| !self.pinned.contains(cell) && distance(*cell) > self.unload_radius)
+            .collect();
+        for cell in unload {
+            match self.cells.remove(&cell) {
+                Some(CellState::Loaded(entities)) => {
+                    for entity in entities {
+                        world.despawn(entity);
+                    }
+                    events.push(StreamingEvent::Unloaded(cell));
+                }
+                Some(CellState::Loading(handle, _)) => self.abandoned.push(handle),
+                _ => {}
+            }
+        }
+
+        // Collect finished reads.
+        let now = Instant::now();
+        for (cell, state) in self.cells.iter_mut() {
+            if let CellState::Loading(handle, failures) = state {
+                match handle.try_take() {
+                    Some(Ok(data)) => *state = CellState::Ready(data),
+                    Some(Err(message)) => {
+                        log::warn!("Failed to load cell {:?}: {}", cell, message);
+                        events.push(StreamingEvent::Failed(*cell, message));
+                        let delay = self.retry_delay * (1 << (*failures).min(MAX_RETRY_DOUBLINGS));
+                        *state = CellState::Failed(*failures + 1, now + delay);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        // Spawn the most important cells whose data is ready.
+        let mut ready: Vec<CellCoord> = self
+            .cells
+            .iter()
+            .filter(|(_, state)| matches!(state, CellState::Ready(_)))
+            .map(|(cell, _)| *cell)
+            .collect();
+        ready.sort_by(|a, b| {
+            priority(*a, &self.pinned)
+                .total_cmp(&priority(*b, &self.pinned))
+                .then(a.cmp(b))
+        });
+        for cell in ready.into_iter().take(self.spawns_per_update) {
+            if let Some(CellState::Ready(data)) = self.cells.remove(&cell) {
+                let entities = (self.spawner)(world, cell, data);
+                self.cells.insert(cell, CellState::Loaded(entities));
+                events.push(StreamingEvent::Loaded(cell));
+            }
+        }
+
+        // Start reading missing cells and failed cells due for a retry, most
+        // important first.
+        let reach = self.load_radius.max(self.required_radius).ceil() as i32;
+        let wanted: HashSet<CellCoord> = (-reach..=reach)
+            .flat_map(|dx| (-reach..=reach).map(move |dz| (center.0 + dx, center.1 + dz)))
+            .filter(|cell| distance(*cell) <= self.load_radius.max(self.required_radius))
+            .chain(self.pinned.iter().copied())
+            .filter(|cell| match self.cells.get(cell) {
+                None => true,
+                Some(CellState::Failed(_, retry_at)) => *retry_at <= now,
+                Some(_) => false,
+            })
+            .collect();
+        let mut wanted: Vec<CellCoord> = wanted.into_iter().collect();
+        wanted.sort_by(|a, b| {
+            priority(*a, &self.pinned)
+                .total_cmp(&priority(*b, &self.pinned))
+                .then(a.cmp(b))
+        });
+        let in_flight = self.in_flight();
+        for cell in wanted
+            .into_iter()
+            .take(self.max_in_flight.saturating_sub(in_flight))
+        {
+            let failures = match self.cells.get(&cell) {
+                Some(CellState::Failed(failures, _)) => *failures,
+                _ => 0,
+            };
+            let loader = Arc::clone(&self.loader);
+            let handle = self.jobs.spawn(move || loader(cell));
+            self.cells
+                .insert(cell, CellState::Loading(handle, failures));
+        }
+
+        self.update_loading_screen(center, &distance);
+        events
+    }
+
+    fn update_loading_screen(&mut self, center: CellCoord, distance: &dyn Fn(CellCoord) -> f32) {
+        let reach = self.required_radius.ceil() as i32;
+        let mut progress = LoadingProgress {
+            in_flight: self.in_flight(),
+            ..LoadingProgress::default()
+        };
+        for dx in -reach..=reach {
+            for dz in -reach..=reach {
+                let cell = (center.0 + dx, center.1 + dz);
+                if distance(cell) <= self.required_radius {
+                    progress.required += 1;
+                    // A failed cell may never load, so it doesn't hold the
+                    // loading screen up while it waits for a retry.
+                    if matches!(
+                        self.cells.get(&cell),
+                        Some(CellState::Loaded(_) | CellState::Failed(..))
+                    ) {
+                        progress.ready += 1;
+                    }
+                }
+            }
+        }
+        self.progress = progress;
+
+        let Some(screen) = self.loading_screen.as_mut() else {
+            return;
+        };
+        if !progress.is_complete() {
+            if !self.loading_screen_shown {
+                self.loading_screen_shown = true;
+                screen.show();
+            }
+            screen.progress(progress);
+        } else if self.loading_screen_shown {
+            self.loading_screen_shown = false;
+            screen.progress(progress);
+            screen.hide();
+        }
+    }
+
+    /// Despawns every loaded cell and forgets pending reads, e.g. when
+    /// leaving the level.
+    pub fn unload_all(&mut self, world: &mut World) {
+        for (_, state) in self.cells.drain() {
+            match state {
+                CellState::Loaded(entities) => {
+                    for entity in entities {
+                        world.despawn(entity);
+                    }
+                }
+                CellState::Loading(handle, _) => self.abandoned.push(handle),
+                _ => {}
+            }
+        }
+        self.progress = LoadingProgress::default();
+    }
+
+    /// Counts the reads still running, including those of unloaded cells.
+    fn in_flight(&self) -> usize {
+        let loading = self
+            .cells
+            .values()
+            .filter(|state| matches!(state, CellState::Loading(..)))
+            .count();
+        let abandoned = self
+            .abandoned
+            .iter()
+            .filter(|handle| !handle.is_finished())
+            .count();
+        loading + abandoned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    /// Updates until `done` holds, failing the test after a few seconds.
+    fn update_until(
+        streamer: &mut SceneStreamer<()>,
+        world: &mut World,
+        mut done: impl FnMut(&SceneStreamer<()>, &[StreamingEvent]) -> bool,
+    ) {
+        let start = Instant::now();
+        loop {
+            let events = streamer.update(world, Vec3::zero());
+            if done(streamer, &events) {
+                return;
+            }
+            assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn failed_cells_are_retried() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&attempts);
+        let mut streamer = SceneStreamer::new(
+            10.0,
+            move |_| match counted.fetch_add(1, Ordering::SeqCst) {
+                0 => Err("disk busy".to_string()),
+                _ => Ok(()),
+            },
+            |world, _, ()| vec![world.spawn()],
+        )
+        .with_load_radius(0.0)
+        .with_retry_delay(Duration::ZERO);
+        let mut world = World::new();
+
+        let mut failed = false;
+        update_until(&mut streamer, &mut world, |streamer, events| {
+            failed |= events
+                .iter()
+                .any(|event| matches!(event, StreamingEvent::Failed((0, 0), _)));
+            streamer.is_loaded((0, 0))
+        });
+        assert!(failed);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unloaded_reads_count_as_in_flight_until_they_finish() {
+        let release = Arc::new(AtomicBool::new(false));
+        let released = Arc::clone(&release);
+        let mut streamer = SceneStreamer::new(
+            10.0,
+            move |_| {
+                while !released.load(Ordering::SeqCst) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Ok(())
+            },
+            |_, _, ()| Vec::new(),
+        )
+        .with_load_radius(0.0)
+        .with_job_system(Arc::new(JobSystem::with_threads(1)));
+        let mut world = World::new();
+
+        streamer.update(&mut world, Vec3::zero());
+        streamer.unload_all(&mut world);
+        streamer.update(&mut world, vec3(100.0, 0.0, 0.0));
+        assert_eq!(streamer.progress().in_flight, 2);
+
+        release.store(true, Ordering::SeqCst);
+        update_until(&mut streamer, &mut world, |streamer, _| {
+            streamer.progress().in_flight == 0
+        });
+    }
+}
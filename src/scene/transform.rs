@@ -0,0 +1,203 @@
+use crate::math::*;
+
+/// # Transform
+///
+/// Position, rotation, and scale of an entity. Rotation is stored as a unit
+/// quaternion, which avoids gimbal lock and interpolates smoothly; Euler angle
+/// and axis-angle helpers are provided for authoring and UI.
+///
+/// ## Example
+/// ```ignore
+/// let mut transform = Transform::from_translation(vec3(0.0, 1.0, 5.0));
+/// transform.set_euler_degrees(-15.0, 45.0, 0.0);
+/// transform.rotate_axis(Vec3::unit_y(), Deg(90.0) * dt);
+///
+/// shader.set_matrix4fv_uniform(name!("model"), &transform.matrix());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    /// Creates an identity transform.
+    pub fn new() -> Self {
+        Self {
+            translation: Vec3::zero(),
+            rotation: Quat::one(),
+            scale: vec3(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Creates a transform at the given position.
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a transform with the given rotation.
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a transform with the given scale.
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a transform from translation, rotation, and scale.
+    pub fn from_trs(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Creates a transform from an affine matrix, discarding any shear.
+    pub fn from_matrix(matrix: &Mat4) -> Self {
+        let (translation, rotation, scale) = trs::decompose_trs(matrix);
+        Self::from_trs(translation, rotation, scale)
+    }
+
+    /// Returns the transform moved to a new position.
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// Returns the transform with a new rotation.
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Returns the transform with a new scale.
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns the model matrix (scale, then rotate, then translate).
+    pub fn matrix(&self) -> Mat4 {
+        trs::compose_trs(self.translation, self.rotation, self.scale)
+    }
+
+    /// Returns the rotation as Euler angles in radians (pitch around X, yaw around Y, roll around Z).
+    pub fn euler(&self) -> Euler<Rad<f32>> {
+        Euler::from(self.rotation)
+    }
+
+    /// Sets the rotation from Euler angles.
+    pub fn set_euler<A: Into<Rad<f32>>>(&mut self, x: A, y: A, z: A) {
+        self.rotation = Quat::from(Euler::new(x.into(), y.into(), z.into()));
+    }
+
+    /// Sets the rotation from Euler angles in degrees.
+    pub fn set_euler_degrees(&mut self, x: f32, y: f32, z: f32) {
+        self.set_euler(Deg(x), Deg(y), Deg(z));
+    }
+
+    /// Returns the rotation as a unit axis and an angle.
+    pub fn axis_angle(&self) -> (Vec3, Rad<f32>) {
+        quat::to_axis_angle(self.rotation)
+    }
+
+    /// Sets the rotation from an axis and an angle.
+    pub fn set_axis_angle<A: Into<Rad<f32>>>(&mut self, axis: Vec3, angle: A) {
+        self.rotation = Quat::from_axis_angle(axis.normalize(), angle);
+    }
+
+    /// Applies an extra rotation in world space.
+    pub fn rotate(&mut self, rotation: Quat) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    /// Applies an extra rotation in the transform's local space.
+    pub fn rotate_local(&mut self, rotation: Quat) {
+        self.rotation = (self.rotation * rotation).normalize();
+    }
+
+    /// Rotates around a world-space axis.
+    pub fn rotate_axis<A: Into<Rad<f32>>>(&mut self, axis: Vec3, angle: A) {
+        self.rotate(Quat::from_axis_angle(axis.normalize(), angle));
+    }
+
+    /// Rotates so the forward (-Z) axis points at a target.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        self.rotation = quat::look_rotation(target - self.translation, up);
+    }
+
+    /// Returns the direction the transform is facing (-Z).
+    pub fn forward(&self) -> Vec3 {
+        quat::forward(self.rotation)
+    }
+
+    /// Returns the transform's right direction (+X).
+    pub fn right(&self) -> Vec3 {
+        quat::right(self.rotation)
+    }
+
+    /// Returns the transform's up direction (+Y).
+    pub fn up(&self) -> Vec3 {
+        quat::up(self.rotation)
+    }
+
+    /// Transforms a point from local space to parent space.
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.translation
+            + self
+                .rotation
+                .rotate_vector(self.scale.mul_element_wise(point))
+    }
+
+    /// Transforms a direction from local space to parent space, ignoring translation.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation
+            .rotate_vector(self.scale.mul_element_wise(vector))
+    }
+
+    /// Combines this (parent) transform with a child transform.
+    pub fn mul_transform(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_point(child.translation),
+            rotation: (self.rotation * child.rotation).normalize(),
+            scale: self.scale.mul_element_wise(child.scale),
+        }
+    }
+
+    /// Returns the inverse transform. Exact only for uniform scale.
+    pub fn inverse(&self) -> Transform {
+        let rotation = self.rotation.invert();
+        let scale = vec3(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        let translation = rotation
+            .rotate_vector(-self.translation)
+            .mul_element_wise(scale);
+        Transform::from_trs(translation, rotation, scale)
+    }
+
+    /// Interpolates towards another transform, slerping the rotation.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
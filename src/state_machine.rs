@@ -0,0 +1,259 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::ecs::{Access, Entity, FunctionSystem, System, World};
+
+/// Types usable as states of a [`StateMachine`], usually a fieldless enum.
+pub trait State: Copy + Eq + Hash + Debug + Send + Sync + 'static {}
+
+impl<T: Copy + Eq + Hash + Debug + Send + Sync + 'static> State for T {}
+
+/// Everything hooks and guards can see while a state machine updates.
+pub struct StateContext<'a> {
+    pub world: &'a World,
+    /// The entity the state machine belongs to.
+    pub entity: Entity,
+    pub delta_time: f32,
+    /// Seconds spent in the current state.
+    pub time_in_state: f32,
+}
+
+type Hook = Box<dyn FnMut(&StateContext) + Send + Sync>;
+type Guard = Box<dyn Fn(&StateContext) -> bool + Send + Sync>;
+
+/// A guarded transition. `from` is `None` for transitions out of any state.
+struct Transition<S> {
+    from: Option<S>,
+    to: S,
+    guard: Guard,
+}
+
+/// Hooks registered for one state.
+struct StateHooks<S> {
+    state: S,
+    enter: Vec<Hook>,
+    exit: Vec<Hook>,
+    update: Vec<Hook>,
+}
+
+/// # State Machine
+///
+/// A component for logic that is always in exactly one of a few states: enemy
+/// AI, doors, menus or the overall game flow. Each update the machine checks
+/// the transitions out of its current state in the order they were added and
+/// follows the first whose guard passes, running exit and enter hooks, then runs
+/// the update hooks of the state it ends up in.
+///
+/// ## Example
+/// ```ignore
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum Door { Closed, Opening, Open, Closing }
+///
+/// let door = StateMachine::new(Door::Closed)
+///     .with_transition(Door::Closed, Door::Opening, |ctx| player_near(ctx))
+///     .with_transition(Door::Opening, Door::Open, |ctx| ctx.time_in_state >= 0.5)
+///     .with_transition(Door::Open, Door::Closing, |ctx| !player_near(ctx))
+///     .with_transition(Door::Closing, Door::Closed, |ctx| ctx.time_in_state >= 0.5)
+///     .on_enter(Door::Opening, |ctx| play_sound(ctx, "door_open"));
+///
+/// world.insert(entity, door);
+/// schedule.add_system(state_machine_system::<Door>(Access::new().read::<Transform>()));
+/// ```
+pub struct StateMachine<S: State> {
+    state: S,
+    previous: Option<S>,
+    pending: Option<S>,
+    entered: bool,
+    changed: bool,
+    time_in_state: f32,
+    transitions: Vec<Transition<S>>,
+    hooks: Vec<StateHooks<S>>,
+}
+
+impl<S: State> StateMachine<S> {
+    /// Creates a machine starting in `initial`. Its enter hooks run on the first update.
+    pub fn new(initial: S) -> Self {
+        Self {
+            state: initial,
+            previous: None,
+            pending: None,
+            entered: false,
+            changed: false,
+            time_in_state: 0.0,
+            transitions: Vec::new(),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Adds a transition from one state to another, taken when `guard` passes.
+    pub fn with_transition<F>(mut self, from: S, to: S, guard: F) -> Self
+    where
+        F: Fn(&StateContext) -> bool + Send + Sync + 'static,
+    {
+        self.transitions.push(Transition {
+            from: Some(from),
+            to,
+            guard: Box::new(guard),
+        });
+        self
+    }
+
+    /// Adds a transition from every other state, e.g. to a death or pause state.
+    pub fn with_any_transition<F>(mut self, to: S, guard: F) -> Self
+    where
+        F: Fn(&StateContext) -> bool + Send + Sync + 'static,
+    {
+        self.transitions.push(Transition {
+            from: None,
+            to,
+            guard: Box::new(guard),
+        });
+        self
+    }
+
+    fn hooks_mut(&mut self, state: S) -> &mut StateHooks<S> {
+        let index = match self.hooks.iter().position(|hooks| hooks.state == state) {
+            Some(index) => index,
+            None => {
+                self.hooks.push(StateHooks {
+                    state,
+                    enter: Vec::new(),
+                    exit: Vec::new(),
+                    update: Vec::new(),
+                });
+                self.hooks.len() - 1
+            }
+        };
+        &mut self.hooks[index]
+    }
+
+    /// Adds a hook run when the machine enters `state`.
+    pub fn on_enter<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: FnMut(&StateContext) + Send + Sync + 'static,
+    {
+        self.hooks_mut(state).enter.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a hook run when the machine leaves `state`.
+    pub fn on_exit<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: FnMut(&StateContext) + Send + Sync + 'static,
+    {
+        self.hooks_mut(state).exit.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a hook run every update while the machine is in `state`.
+    pub fn on_update<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: FnMut(&StateContext) + Send + Sync + 'static,
+    {
+        self.hooks_mut(state).update.push(Box::new(hook));
+        self
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Returns the state before the last transition.
+    pub fn previous(&self) -> Option<S> {
+        self.previous
+    }
+
+    /// Checks if the machine is in `state`.
+    pub fn is(&self, state: S) -> bool {
+        self.state == state
+    }
+
+    /// Returns the seconds spent in the current state.
+    pub fn time_in_state(&self) -> f32 {
+        self.time_in_state
+    }
+
+    /// Checks if the last update changed state.
+    pub fn just_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Forces a transition on the next update, ignoring guards. Useful for
+    /// events such as taking damage.
+    pub fn request(&mut self, state: S) {
+        self.pending = Some(state);
+    }
+
+    fn run_hooks(
+        &mut self,
+        state: S,
+        context: &StateContext,
+        select: fn(&mut StateHooks<S>) -> &mut Vec<Hook>,
+    ) {
+        if let Some(hooks) = self.hooks.iter_mut().find(|hooks| hooks.state == state) {
+            for hook in select(hooks) {
+                hook(context);
+            }
+        }
+    }
+
+    /// Follows at most one transition and runs the current state's update hooks.
+    pub fn update(&mut self, world: &World, entity: Entity, delta_time: f32) {
+        let mut context = StateContext {
+            world,
+            entity,
+            delta_time,
+            time_in_state: self.time_in_state,
+        };
+        self.changed = false;
+        if !self.entered {
+            self.entered = true;
+            self.changed = true;
+            self.run_hooks(self.state, &context, |hooks| &mut hooks.enter);
+        }
+
+        self.time_in_state += delta_time;
+        context.time_in_state = self.time_in_state;
+
+        let next = self.pending.take().or_else(|| {
+            self.transitions
+                .iter()
+                .filter(|transition| transition.from.is_none_or(|from| from == self.state))
+                .filter(|transition| transition.from.is_some() || transition.to != self.state)
+                .find(|transition| (transition.guard)(&context))
+                .map(|transition| transition.to)
+        });
+
+        if let Some(next) = next {
+            log::debug!("State machine {:?}: {:?} -> {:?}", entity, self.state, next);
+            self.run_hooks(self.state, &context, |hooks| &mut hooks.exit);
+            self.previous = Some(self.state);
+            self.state = next;
+            self.time_in_state = 0.0;
+            self.changed = true;
+            context.time_in_state = 0.0;
+            self.run_hooks(next, &context, |hooks| &mut hooks.enter);
+        }
+
+        self.run_hooks(self.state, &context, |hooks| &mut hooks.update);
+    }
+}
+
+/// Returns a system that updates every `StateMachine<S>`. `access` should
+/// declare the components the machines' hooks and guards read and write.
+pub fn state_machine_system<S: State>(access: Access) -> impl System {
+    FunctionSystem::new(
+        &format!("state_machine<{}>", std::any::type_name::<S>()),
+        access.write::<StateMachine<S>>(),
+        update_state_machines::<S>,
+    )
+}
+
+/// Updates every `StateMachine<S>` once.
+pub fn update_state_machines<S: State>(world: &World) {
+    let delta_time = world.time().delta();
+    for (entity, machine) in world.write::<StateMachine<S>>().iter_mut() {
+        machine.update(world, *entity, delta_time);
+    }
+}
@@ -0,0 +1,557 @@
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::custom_errors::Errors;
+
+/// A value attached to a [`TelemetryEvent`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum TelemetryValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl From<bool> for TelemetryValue {
+    fn from(value: bool) -> Self {
+        TelemetryValue::Bool(value)
+    }
+}
+
+impl From<i32> for TelemetryValue {
+    fn from(value: i32) -> Self {
+        TelemetryValue::Int(value as i64)
+    }
+}
+
+impl From<i64> for TelemetryValue {
+    fn from(value: i64) -> Self {
+        TelemetryValue::Int(value)
+    }
+}
+
+impl From<u32> for TelemetryValue {
+    fn from(value: u32) -> Self {
+        TelemetryValue::Int(value as i64)
+    }
+}
+
+impl From<f32> for TelemetryValue {
+    fn from(value: f32) -> Self {
+        TelemetryValue::Float(value as f64)
+    }
+}
+
+impl From<f64> for TelemetryValue {
+    fn from(value: f64) -> Self {
+        TelemetryValue::Float(value)
+    }
+}
+
+impl From<&str> for TelemetryValue {
+    fn from(value: &str) -> Self {
+        TelemetryValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for TelemetryValue {
+    fn from(value: String) -> Self {
+        TelemetryValue::Text(value)
+    }
+}
+
+/// # Telemetry Event
+///
+/// Something that happened during play, such as a level starting or the
+/// player dying, with named fields. Events are written as JSON objects.
+///
+/// ## Example
+/// ```ignore
+/// telemetry.record(
+///     TelemetryEvent::new("death")
+///         .with("level", "forest_2")
+///         .with("cause", "spikes")
+///         .with("x", position.x),
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TelemetryEvent {
+    pub name: String,
+    /// Seconds since the session started, filled in when recorded.
+    pub time: f64,
+    pub fields: Vec<(String, TelemetryValue)>,
+}
+
+impl TelemetryEvent {
+    /// Creates an event with no fields.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            time: 0.0,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field.
+    pub fn with(mut self, key: &str, value: impl Into<TelemetryValue>) -> Self {
+        self.fields.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Returns a field by key.
+    pub fn field(&self, key: &str) -> Option<&TelemetryValue> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Serializes the event as a single-line JSON object tagged with a session.
+    pub fn to_json(&self, session: &str) -> String {
+        let mut json = String::from("{");
+        let _ = write!(
+            json,
+            "\"event\":{},\"session\":{},\"time\":{}",
+            json_string(&self.name),
+            json_string(session),
+            json_number(self.time)
+        );
+        for (key, value) in &self.fields {
+            let value = match value {
+                TelemetryValue::Bool(value) => value.to_string(),
+                TelemetryValue::Int(value) => value.to_string(),
+                TelemetryValue::Float(value) => json_number(*value),
+                TelemetryValue::Text(value) => json_string(value),
+            };
+            let _ = write!(json, ",{}:{}", json_string(key), value);
+        }
+        json.push('}');
+        json
+    }
+}
+
+pub(crate) fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", character as u32);
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub(crate) fn json_number(value: f64) -> String {
+    // JSON has no infinity or NaN.
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// # Telemetry Sink
+///
+/// Where batches of telemetry go. Sinks run on a background thread, so they
+/// may block on disk or network without stalling the game.
+pub trait TelemetrySink: Send {
+    /// Writes a batch of events from a session.
+    fn write(&mut self, session: &str, events: &[TelemetryEvent]) -> Result<(), Errors>;
+}
+
+/// Appends events to a file, one JSON object per line.
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    /// Opens a file for appending, creating it if needed.
+    pub fn new(path: &str) -> Result<Self, Errors> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|error| Errors::Telemetry(format!("{}: {}", path, error)))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+impl TelemetrySink for FileSink {
+    fn write(&mut self, session: &str, events: &[TelemetryEvent]) -> Result<(), Errors> {
+        for event in events {
+            writeln!(self.writer, "{}", event.to_json(session))
+                .map_err(|error| Errors::Telemetry(error.to_string()))?;
+        }
+        self.writer
+            .flush()
+            .map_err(|error| Errors::Telemetry(error.to_string()))
+    }
+}
+
+/// Posts batches as newline-delimited JSON to a plain `http://` endpoint, such
+/// as a collection server on the playtest network. HTTPS isn't supported.
+/// Connecting, sending and waiting for the answer each give up after a
+/// timeout, 5 seconds by default.
+pub struct HttpSink {
+    host: String,
+    address: String,
+    path: String,
+    timeout: Duration,
+}
+
+impl HttpSink {
+    /// Creates a sink posting to a URL like `http://192.168.1.20:8080/events`.
+    pub fn new(url: &str) -> Result<Self, Errors> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            Errors::Telemetry(format!("{}: only http:// URLs are supported", url))
+        })?;
+        let (host, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(Errors::Telemetry(format!("{}: missing host", url)));
+        }
+        let address = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+        Ok(Self {
+            host: host.to_string(),
+            address,
+            path: path.to_string(),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// Sets how long in seconds connecting, sending and waiting for the
+    /// answer may each take.
+    pub fn with_timeout(mut self, seconds: f32) -> Self {
+        self.timeout = Duration::from_secs_f32(seconds.max(0.001));
+        self
+    }
+
+    fn connect(&self) -> Result<TcpStream, String> {
+        let addresses = self
+            .address
+            .to_socket_addrs()
+            .map_err(|io| io.to_string())?;
+        let mut last_error = "no address".to_string();
+        for address in addresses {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(io) => last_error = io.to_string(),
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl TelemetrySink for HttpSink {
+    fn write(&mut self, session: &str, events: &[TelemetryEvent]) -> Result<(), Errors> {
+        let error = |message: String| Errors::Telemetry(format!("{}: {}", self.address, message));
+        let mut body = String::new();
+        for event in events {
+            body.push_str(&event.to_json(session));
+            body.push('\n');
+        }
+
+        let mut stream = self.connect().map_err(error)?;
+        let _ = stream.set_read_timeout(Some(self.timeout));
+        let _ = stream.set_write_timeout(Some(self.timeout));
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|io| error(io.to_string()))?;
+
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| error("no HTTP response".to_string()))?;
+        if !(200..300).contains(&status) {
+            return Err(error(format!("server answered {}", status)));
+        }
+        Ok(())
+    }
+}
+
+/// # Telemetry
+///
+/// Opt-in recording of gameplay events for playtest analysis. Nothing is
+/// recorded until the player agrees and [`set_enabled`](Self::set_enabled) is
+/// called. Events are batched and handed to a [`TelemetrySink`] on a
+/// background thread when the batch fills up, every flush interval, and when
+/// the telemetry is dropped. Dropping it waits for the last batch for at most
+/// the shutdown timeout, 3 seconds by default, so a slow sink can't hold up
+/// quitting. Every session gets a random id so events from one run can be
+/// grouped.
+///
+/// ## Example
+/// ```ignore
+/// let mut telemetry = Telemetry::new(FileSink::new("playtest.jsonl")?);
+/// telemetry.set_enabled(settings.share_analytics);
+///
+/// telemetry.record(TelemetryEvent::new("level_start").with("level", "forest_1"));
+///
+/// // each frame:
+/// telemetry.update(time.unscaled_delta());
+/// ```
+pub struct Telemetry {
+    enabled: bool,
+    session: String,
+    started: Instant,
+    batch: Vec<TelemetryEvent>,
+    batch_size: usize,
+    flush_interval: f32,
+    since_flush: f32,
+    fps_interval: f32,
+    fps_frames: Vec<f32>,
+    fps_elapsed: f32,
+    sender: Option<Sender<Vec<TelemetryEvent>>>,
+    worker: Option<JoinHandle<()>>,
+    // Disconnects when the worker has finished.
+    finished: Receiver<()>,
+    shutdown_timeout: Duration,
+}
+
+impl Telemetry {
+    /// Creates disabled telemetry writing to a sink.
+    pub fn new(sink: impl TelemetrySink + 'static) -> Self {
+        let session = session_id();
+        let (sender, receiver) = mpsc::channel::<Vec<TelemetryEvent>>();
+        let (finished_sender, finished) = mpsc::channel::<()>();
+        let worker_session = session.clone();
+        let mut sink = sink;
+        let worker = thread::Builder::new()
+            .name("nyanko-telemetry".to_string())
+            .spawn(move || {
+                let _finished = finished_sender;
+                for batch in receiver {
+                    if let Err(error) = sink.write(&worker_session, &batch) {
+                        log::warn!("Dropped {} telemetry events: {}", batch.len(), error);
+                    }
+                }
+            })
+            .map_err(|error| log::error!("Failed to start telemetry thread: {}", error))
+            .ok();
+        Self {
+            enabled: false,
+            session,
+            started: Instant::now(),
+            batch: Vec::new(),
+            batch_size: 64,
+            flush_interval: 30.0,
+            since_flush: 0.0,
+            fps_interval: 10.0,
+            fps_frames: Vec::new(),
+            fps_elapsed: 0.0,
+            sender: worker.as_ref().map(|_| sender),
+            worker,
+            finished,
+            shutdown_timeout: Duration::from_secs(3),
+        }
+    }
+
+    /// Sets how many events are collected before they are sent.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the longest time in seconds events wait before they are sent.
+    pub fn with_flush_interval(mut self, flush_interval: f32) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Sets the longest time in seconds dropping the telemetry waits for the
+    /// last batch to be written.
+    pub fn with_shutdown_timeout(mut self, seconds: f32) -> Self {
+        self.shutdown_timeout = Duration::from_secs_f32(seconds.max(0.0));
+        self
+    }
+
+    /// Sets how often in seconds an `fps` event is recorded from the frame
+    /// times given to [`update`](Self::update), or turns it off with 0.
+    pub fn with_fps_interval(mut self, fps_interval: f32) -> Self {
+        self.fps_interval = fps_interval;
+        self
+    }
+
+    /// Checks if events are recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns recording on or off, for example from a consent prompt. Turning
+    /// it off throws away anything not yet sent.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.batch.clear();
+            self.fps_frames.clear();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Returns this session's id.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Records an event, if telemetry is enabled.
+    pub fn record(&mut self, mut event: TelemetryEvent) {
+        if !self.enabled {
+            return;
+        }
+        event.time = self.started.elapsed().as_secs_f64();
+        self.batch.push(event);
+        if self.batch.len() >= self.batch_size {
+            self.flush();
+        }
+    }
+
+    /// Samples the frame rate and sends events that have waited too long.
+    /// Call once per frame with the real frame time.
+    pub fn update(&mut self, delta_time: f32) {
+        if !self.enabled {
+            return;
+        }
+        if self.fps_interval > 0.0 && delta_time > 0.0 {
+            self.fps_frames.push(delta_time);
+            self.fps_elapsed += delta_time;
+            if self.fps_elapsed >= self.fps_interval {
+                self.record_fps();
+            }
+        }
+        self.since_flush += delta_time;
+        if self.since_flush >= self.flush_interval {
+            self.flush();
+        }
+    }
+
+    fn record_fps(&mut self) {
+        let mut frames = std::mem::take(&mut self.fps_frames);
+        let elapsed = std::mem::take(&mut self.fps_elapsed);
+        frames.sort_by(|a, b| b.total_cmp(a));
+        // The 1% slowest frames show stutter an average hides.
+        let slow = &frames[..(frames.len() / 100).max(1)];
+        let slow_average = slow.iter().sum::<f32>() / slow.len() as f32;
+        self.record(
+            TelemetryEvent::new("fps")
+                .with("average", frames.len() as f32 / elapsed)
+                .with("low_1_percent", 1.0 / slow_average)
+                .with("worst_frame_ms", frames[0] * 1000.0),
+        );
+    }
+
+    /// Sends every waiting event to the sink now.
+    pub fn flush(&mut self) {
+        self.since_flush = 0.0;
+        if self.batch.is_empty() {
+            return;
+        }
+        let batch = std::mem::take(&mut self.batch);
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(batch);
+        }
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        self.flush();
+        // Closing the channel lets the worker finish the last batch and exit.
+        self.sender = None;
+        let Some(worker) = self.worker.take() else {
+            return;
+        };
+        match self.finished.recv_timeout(self.shutdown_timeout) {
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // The thread is left to finish or die with the process.
+                log::warn!("Telemetry still sending at shutdown, giving up on it");
+            }
+            _ => {
+                let _ = worker.join();
+            }
+        }
+    }
+}
+
+/// Returns a random-enough id for a session without a random number crate.
+fn session_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut hash = nanos ^ ((std::process::id() as u64) << 32);
+    // SplitMix64 finalizer, to spread the bits of the clock.
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    struct StuckSink;
+
+    impl TelemetrySink for StuckSink {
+        fn write(&mut self, _: &str, _: &[TelemetryEvent]) -> Result<(), Errors> {
+            thread::sleep(Duration::from_secs(60));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dropping_does_not_wait_for_a_stuck_sink() {
+        let mut telemetry = Telemetry::new(StuckSink).with_shutdown_timeout(0.1);
+        telemetry.set_enabled(true);
+        telemetry.record(TelemetryEvent::new("quit"));
+
+        let started = Instant::now();
+        drop(telemetry);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn http_sink_gives_up_on_a_silent_server() {
+        // The listener accepts connections into its backlog but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let mut sink = HttpSink::new(&url).unwrap().with_timeout(0.1);
+
+        let started = Instant::now();
+        let result = sink.write("session", &[TelemetryEvent::new("ping")]);
+        assert!(matches!(result, Err(Errors::Telemetry(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
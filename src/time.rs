@@ -0,0 +1,273 @@
+use std::time::{Duration, Instant};
+
+use crate::cvars::CVars;
+
+/// # Time
+///
+/// The engine clock. Every frame it measures real elapsed time and derives the
+/// game delta from it, applying `time_scale`, pause and single-frame stepping,
+/// so slow motion and frame-by-frame debugging work everywhere that takes its
+/// delta from [`Time::delta`]. UI, input and recording should use
+/// [`Time::unscaled_delta`] to keep running while the game is paused.
+///
+/// A [`World`](crate::ecs::World) owns one, reachable from systems through
+/// [`World::time`](crate::ecs::World::time).
+///
+/// ## Example
+/// ```ignore
+/// loop {
+///     world.time_mut().update();
+///     if input.key_pressed(Key::P) {
+///         world.time_mut().toggle_pause();
+///     }
+///     if input.key_pressed(Key::Period) {
+///         world.time_mut().step_frame();
+///     }
+///
+///     let delta = world.time().delta();
+///     decals.update(delta);
+///     day_night.update(delta);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Time {
+    time_scale: f32,
+    /// Longest real frame time accepted, so breakpoints and hitches don't
+    /// produce huge simulation steps.
+    pub max_delta: f32,
+    /// Real time simulated by [`Time::step_frame`] while paused.
+    pub step_duration: f32,
+    fixed_step: Option<f32>,
+    paused: bool,
+    pending_steps: u32,
+    last_update: Option<Instant>,
+    delta: f32,
+    unscaled_delta: f32,
+    elapsed: f64,
+    unscaled_elapsed: f64,
+    frame_count: u64,
+    cvar_scale: Option<f64>,
+    cvar_max_delta: Option<f64>,
+}
+
+impl Time {
+    /// Creates a running clock at normal speed.
+    pub fn new() -> Self {
+        Self {
+            time_scale: 1.0,
+            max_delta: 0.25,
+            step_duration: 1.0 / 60.0,
+            fixed_step: None,
+            paused: false,
+            pending_steps: 0,
+            last_update: None,
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            elapsed: 0.0,
+            unscaled_elapsed: 0.0,
+            frame_count: 0,
+            cvar_scale: None,
+            cvar_max_delta: None,
+        }
+    }
+
+    /// Starts a new frame, measuring the real time since the previous call.
+    /// The first call reports a delta of zero. With a fixed step, every call
+    /// advances by exactly that step instead.
+    pub fn update(&mut self) {
+        if let Some(step) = self.fixed_step {
+            self.advance(step);
+            return;
+        }
+        let now = Instant::now();
+        let real = self
+            .last_update
+            .map_or(Duration::ZERO, |last| now.duration_since(last));
+        self.last_update = Some(now);
+        self.advance(real.as_secs_f32());
+    }
+
+    /// Starts a new frame with an explicit real delta in seconds, for fixed-rate
+    /// loops, tests and replays.
+    pub fn advance(&mut self, real_delta: f32) {
+        let real_delta = real_delta.clamp(0.0, self.max_delta);
+        self.unscaled_delta = real_delta;
+        self.unscaled_elapsed += real_delta as f64;
+        self.frame_count += 1;
+        crate::crash::record_frame(self.frame_count, real_delta);
+
+        let game_delta = if !self.paused {
+            real_delta
+        } else if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            self.step_duration
+        } else {
+            0.0
+        };
+        self.delta = game_delta * self.time_scale;
+        self.elapsed += self.delta as f64;
+    }
+
+    /// Returns the step [`Time::update`] advances by instead of reading the
+    /// wall clock, if any.
+    pub fn fixed_step(&self) -> Option<f32> {
+        self.fixed_step
+    }
+
+    /// Makes [`Time::update`] advance by exactly `step` seconds every frame,
+    /// for deterministic simulations, or measure real time again with `None`.
+    pub fn set_fixed_step(&mut self, step: Option<f32>) {
+        self.fixed_step = step;
+        self.last_update = None;
+    }
+
+    /// Returns the scaled game time of this frame in seconds; zero while paused.
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    /// Returns the real time of this frame in seconds, ignoring scale and pause.
+    pub fn unscaled_delta(&self) -> f32 {
+        self.unscaled_delta
+    }
+
+    /// Returns the total scaled game time in seconds.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Returns the total real time in seconds.
+    pub fn unscaled_elapsed(&self) -> f64 {
+        self.unscaled_elapsed
+    }
+
+    /// Returns the number of frames started.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Returns the game speed multiplier.
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Sets the game speed multiplier: 0.5 is half speed, 2 is double. Negative
+    /// values are treated as zero.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /// Applies `time.scale` and `time.max_delta` when they change, so values
+    /// set in code stay until the variables are set again. Call once per frame.
+    pub fn apply_cvars(&mut self, cvars: &CVars) {
+        if let Some(scale) = cvars.float("time.scale") {
+            if self.cvar_scale != Some(scale) {
+                self.cvar_scale = Some(scale);
+                self.set_time_scale(scale as f32);
+            }
+        }
+        if let Some(max_delta) = cvars.float("time.max_delta") {
+            if self.cvar_max_delta != Some(max_delta) {
+                self.cvar_max_delta = Some(max_delta);
+                self.max_delta = max_delta as f32;
+            }
+        }
+    }
+
+    /// Checks if game time is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Freezes game time.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes game time and drops any pending steps.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.pending_steps = 0;
+    }
+
+    /// Pauses if running, resumes if paused.
+    pub fn toggle_pause(&mut self) {
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Advances a paused clock by one frame of `step_duration` on the next
+    /// update. Pauses first if running.
+    pub fn step_frame(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Fixed Timestep
+///
+/// Turns variable frame times into a whole number of equal simulation steps,
+/// carrying the remainder to the next frame. Physics and replays need this to
+/// behave identically regardless of frame rate.
+///
+/// ## Example
+/// ```ignore
+/// let mut fixed = FixedTimestep::new(1.0 / 60.0);
+///
+/// for _ in 0..fixed.advance(world.time().delta()) {
+///     replay.step(window.input_mut());
+///     simulate(&mut world, fixed.step());
+/// }
+/// let blend = fixed.alpha();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedTimestep {
+    step: f32,
+    /// Most steps run in one frame; the rest of the backlog is dropped so a
+    /// slow frame can't spiral into ever slower ones.
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Creates a timestep of `step` seconds.
+    pub fn new(step: f32) -> Self {
+        Self {
+            step,
+            max_steps: 8,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Returns the step length in seconds.
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Adds a frame's delta and returns how many steps to run.
+    pub fn advance(&mut self, delta: f32) -> u32 {
+        self.accumulator += delta.max(0.0);
+        let steps = (self.accumulator / self.step).floor() as u32;
+        self.accumulator -= steps as f32 * self.step;
+        if steps > self.max_steps {
+            self.accumulator = 0.0;
+            return self.max_steps;
+        }
+        steps
+    }
+
+    /// Returns how far between the last and next step the frame is, 0 to 1, for
+    /// interpolating rendered positions.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use super::palette::{BlockId, AIR};
+use crate::math::*;
+
+/// Blocks along each side of a chunk.
+pub const CHUNK_SIZE: i32 = 16;
+
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// Integer block coordinates. Block `(x, y, z)` fills the unit cube from
+/// `(x, y, z)` to `(x + 1, y + 1, z + 1)` in world space.
+pub type BlockPos = (i32, i32, i32);
+
+/// Integer chunk coordinates; chunk `(1, 0, 0)` holds blocks 16 to 31 along X.
+pub type ChunkPos = (i32, i32, i32);
+
+/// Splits a block position into its chunk and its position inside the chunk.
+pub fn split_block_pos((x, y, z): BlockPos) -> (ChunkPos, BlockPos) {
+    (
+        (
+            x.div_euclid(CHUNK_SIZE),
+            y.div_euclid(CHUNK_SIZE),
+            z.div_euclid(CHUNK_SIZE),
+        ),
+        (
+            x.rem_euclid(CHUNK_SIZE),
+            y.rem_euclid(CHUNK_SIZE),
+            z.rem_euclid(CHUNK_SIZE),
+        ),
+    )
+}
+
+/// Returns the world position of a chunk's lowest corner.
+pub fn chunk_origin((x, y, z): ChunkPos) -> Vec3 {
+    vec3(x as f32, y as f32, z as f32) * CHUNK_SIZE as f32
+}
+
+/// Returns the block containing a world position.
+pub fn block_at(position: Vec3) -> BlockPos {
+    (
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    )
+}
+
+/// # Chunk
+///
+/// A cube of [`CHUNK_SIZE`] blocks per side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    blocks: Vec<BlockId>,
+    filled: usize,
+    dirty: bool,
+}
+
+impl Chunk {
+    /// Creates a chunk full of air.
+    pub fn new() -> Self {
+        Self {
+            blocks: vec![AIR; CHUNK_VOLUME],
+            filled: 0,
+            dirty: true,
+        }
+    }
+
+    fn index((x, y, z): BlockPos) -> Option<usize> {
+        let range = 0..CHUNK_SIZE;
+        (range.contains(&x) && range.contains(&y) && range.contains(&z))
+            .then(|| (x + CHUNK_SIZE * (z + CHUNK_SIZE * y)) as usize)
+    }
+
+    /// Returns the block at a position inside the chunk, or air outside it.
+    pub fn get(&self, local: BlockPos) -> BlockId {
+        Self::index(local).map_or(AIR, |index| self.blocks[index])
+    }
+
+    /// Sets the block at a position inside the chunk and returns the old one.
+    pub fn set(&mut self, local: BlockPos, block: BlockId) -> BlockId {
+        let Some(index) = Self::index(local) else {
+            return AIR;
+        };
+        let previous = std::mem::replace(&mut self.blocks[index], block);
+        if previous != block {
+            self.filled = self.filled + (block != AIR) as usize - (previous != AIR) as usize;
+            self.dirty = true;
+        }
+        previous
+    }
+
+    /// Fills the whole chunk with one block.
+    pub fn fill(&mut self, block: BlockId) {
+        self.blocks.fill(block);
+        self.filled = if block == AIR { 0 } else { CHUNK_VOLUME };
+        self.dirty = true;
+    }
+
+    /// Checks if the chunk holds only air.
+    pub fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Checks if the chunk changed since its mesh was last built.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marks the chunk's mesh as rebuilt.
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Marks the chunk's mesh as out of date.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Voxel World
+///
+/// An unbounded grid of blocks stored in chunks that are created on demand.
+/// Editing a block marks its chunk dirty, along with any neighbouring chunk
+/// whose faces it touches, so only changed chunks need remeshing.
+///
+/// ## Example
+/// ```ignore
+/// let mut voxels = VoxelWorld::new();
+/// for x in 0..64 {
+///     for z in 0..64 {
+///         voxels.set_block((x, 0, z), stone);
+///     }
+/// }
+///
+/// for chunk in voxels.take_dirty() {
+///     let mesh = mesh_chunk(&voxels, &palette, chunk);
+///     // Upload and place at chunk_origin(chunk).
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VoxelWorld {
+    chunks: HashMap<ChunkPos, Chunk>,
+}
+
+impl VoxelWorld {
+    /// Creates an empty world.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the block at a position.
+    pub fn get_block(&self, position: BlockPos) -> BlockId {
+        let (chunk, local) = split_block_pos(position);
+        self.chunks
+            .get(&chunk)
+            .map_or(AIR, |chunk| chunk.get(local))
+    }
+
+    /// Sets the block at a position and returns the old one.
+    pub fn set_block(&mut self, position: BlockPos, block: BlockId) -> BlockId {
+        let (chunk_pos, local) = split_block_pos(position);
+        if block == AIR && !self.chunks.contains_key(&chunk_pos) {
+            return AIR;
+        }
+        let previous = self.chunks.entry(chunk_pos).or_default().set(local, block);
+        if previous == block {
+            return previous;
+        }
+
+        let (cx, cy, cz) = chunk_pos;
+        let (lx, ly, lz) = local;
+        let last = CHUNK_SIZE - 1;
+        let neighbours = [
+            (lx == 0, (cx - 1, cy, cz)),
+            (lx == last, (cx + 1, cy, cz)),
+            (ly == 0, (cx, cy - 1, cz)),
+            (ly == last, (cx, cy + 1, cz)),
+            (lz == 0, (cx, cy, cz - 1)),
+            (lz == last, (cx, cy, cz + 1)),
+        ];
+        for (_, neighbour) in neighbours.iter().filter(|(touches, _)| *touches) {
+            if let Some(chunk) = self.chunks.get_mut(neighbour) {
+                chunk.mark_dirty();
+            }
+        }
+        previous
+    }
+
+    /// Returns a chunk.
+    pub fn chunk(&self, position: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&position)
+    }
+
+    /// Returns a chunk, creating it if needed.
+    pub fn chunk_mut(&mut self, position: ChunkPos) -> &mut Chunk {
+        self.chunks.entry(position).or_default()
+    }
+
+    /// Removes a chunk, e.g. when it goes out of view distance.
+    pub fn remove_chunk(&mut self, position: ChunkPos) -> Option<Chunk> {
+        self.chunks.remove(&position)
+    }
+
+    /// Iterates over every loaded chunk.
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkPos, &Chunk)> {
+        self.chunks
+            .iter()
+            .map(|(position, chunk)| (*position, chunk))
+    }
+
+    /// Returns every dirty chunk and marks them clean, for remeshing.
+    pub fn take_dirty(&mut self) -> Vec<ChunkPos> {
+        self.chunks
+            .iter_mut()
+            .filter(|(_, chunk)| chunk.is_dirty())
+            .map(|(position, chunk)| {
+                chunk.mark_clean();
+                *position
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,290 @@
+use super::chunk::{ChunkPos, VoxelWorld, CHUNK_SIZE};
+use super::palette::{BlockId, BlockPalette, Face, AIR};
+use crate::graphics::mesh::{MeshBuilder, MeshVertex};
+use crate::math::*;
+
+/// Vertex shader for chunk meshes built by [`mesh_chunk`].
+pub const VOXEL_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 a_position;
+layout (location = 1) in vec3 a_normal;
+layout (location = 2) in vec2 a_uv;
+layout (location = 3) in vec4 a_tile;
+
+uniform mat4 u_model;
+uniform mat4 u_view_projection;
+
+out vec3 v_normal;
+out vec2 v_uv;
+out vec4 v_tile;
+
+void main() {
+    v_normal = a_normal;
+    v_uv = a_uv;
+    v_tile = a_tile;
+    gl_Position = u_view_projection * u_model * vec4(a_position, 1.0);
+}
+"#;
+
+/// Fragment shader for chunk meshes, repeating each face's atlas tile across
+/// merged quads and shading faces by a directional light.
+pub const VOXEL_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec3 v_normal;
+in vec2 v_uv;
+in vec4 v_tile;
+
+uniform sampler2D u_atlas;
+uniform vec3 u_light_direction;
+
+out vec4 out_color;
+
+void main() {
+    vec4 color = texture(u_atlas, v_tile.xy + fract(v_uv) * v_tile.zw);
+    if (color.a < 0.5) {
+        discard;
+    }
+    float light = 0.55 + 0.45 * max(dot(normalize(v_normal), -u_light_direction), 0.0);
+    out_color = vec4(color.rgb * light, color.a);
+}
+"#;
+
+/// Returns a face's texture coordinates at a position, in blocks. Side faces
+/// keep the texture upright and nothing is mirrored when seen from outside.
+fn face_uv(face: Face, position: Vec3) -> Vec2 {
+    match face {
+        Face::PosX => vec2(-position.z, -position.y),
+        Face::NegX => vec2(position.z, -position.y),
+        Face::PosZ => vec2(position.x, -position.y),
+        Face::NegZ => vec2(-position.x, -position.y),
+        Face::PosY => vec2(position.x, position.z),
+        Face::NegY => vec2(position.x, -position.z),
+    }
+}
+
+/// Checks if the face of `block` towards `neighbour` should be drawn.
+fn face_visible(palette: &BlockPalette, block: BlockId, neighbour: BlockId) -> bool {
+    block != AIR && !palette.is_opaque(neighbour) && block != neighbour
+}
+
+/// Builds a chunk's mesh with greedy meshing, merging neighbouring faces of
+/// the same block into large quads so flat terrain costs a handful of
+/// triangles instead of two per block face.
+///
+/// Positions are relative to the chunk, so place the mesh at
+/// [`chunk_origin`](super::chunk::chunk_origin). Faces between a block and an
+/// opaque neighbour are skipped, including across chunk borders. Texture
+/// coordinates count blocks and each vertex color holds the face's atlas tile
+/// as `(x, y, width, height)`; draw with [`VOXEL_VERTEX_SHADER`] and
+/// [`VOXEL_FRAGMENT_SHADER`], which repeat the tile across merged faces.
+pub fn mesh_chunk(world: &VoxelWorld, palette: &BlockPalette, chunk: ChunkPos) -> MeshBuilder {
+    let mut builder = MeshBuilder::new();
+    match world.chunk(chunk) {
+        Some(contents) if !contents.is_empty() => {}
+        _ => return builder,
+    }
+
+    let size = CHUNK_SIZE;
+    let base = (chunk.0 * size, chunk.1 * size, chunk.2 * size);
+    let block = |p: [i32; 3]| world.get_block((base.0 + p[0], base.1 + p[1], base.2 + p[2]));
+    let mut mask: Vec<BlockId> = vec![AIR; (size * size) as usize];
+
+    for axis in 0..3 {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        for positive in [true, false] {
+            let face = Face::from_axis(axis, positive);
+            let step = if positive { 1 } else { -1 };
+
+            for slice in 0..size {
+                // Which blocks show this face in this slice.
+                for j in 0..size {
+                    for i in 0..size {
+                        let mut p = [0; 3];
+                        p[axis] = slice;
+                        p[u] = i;
+                        p[v] = j;
+                        let id = block(p);
+                        p[axis] += step;
+                        let visible = face_visible(palette, id, block(p));
+                        mask[(i + j * size) as usize] = if visible { id } else { AIR };
+                    }
+                }
+
+                // Merge runs of the same block into rectangles.
+                for j in 0..size {
+                    let mut i = 0;
+                    while i < size {
+                        let id = mask[(i + j * size) as usize];
+                        if id == AIR {
+                            i += 1;
+                            continue;
+                        }
+                        let mut width = 1;
+                        while i + width < size && mask[(i + width + j * size) as usize] == id {
+                            width += 1;
+                        }
+                        let mut height = 1;
+                        'grow: while j + height < size {
+                            for k in 0..width {
+                                if mask[(i + k + (j + height) * size) as usize] != id {
+                                    break 'grow;
+                                }
+                            }
+                            height += 1;
+                        }
+                        for y in j..j + height {
+                            for x in i..i + width {
+                                mask[(x + y * size) as usize] = AIR;
+                            }
+                        }
+
+                        let mut origin = Vec3::zero();
+                        origin[axis] = (slice + positive as i32) as f32;
+                        origin[u] = i as f32;
+                        origin[v] = j as f32;
+                        let mut du = Vec3::zero();
+                        du[u] = width as f32;
+                        let mut dv = Vec3::zero();
+                        dv[v] = height as f32;
+                        push_face(
+                            &mut builder,
+                            palette,
+                            id,
+                            face,
+                            [origin, origin + du, origin + du + dv, origin + dv],
+                        );
+                        i += width;
+                    }
+                }
+            }
+        }
+    }
+    builder
+}
+
+/// Adds one quad, wound to face outward.
+fn push_face(
+    builder: &mut MeshBuilder,
+    palette: &BlockPalette,
+    id: BlockId,
+    face: Face,
+    corners: [Vec3; 4],
+) {
+    let tile = palette.face_tile(id, face);
+    let (x, y, z) = face.normal();
+    let normal = vec3(x as f32, y as f32, z as f32);
+    let tile = Color::new(tile.x, tile.y, tile.width, tile.height);
+    let [a, b, c, d] = corners.map(|corner| {
+        builder.push_vertex(
+            MeshVertex::new(corner)
+                .with_normal(normal)
+                .with_uv(face_uv(face, corner))
+                .with_color(tile),
+        )
+    });
+    match face {
+        Face::PosX | Face::PosY | Face::PosZ => builder.push_quad(a, b, c, d),
+        _ => builder.push_quad(a, d, c, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::palette::{BlockDefinition, TextureAtlas};
+
+    fn palette() -> (BlockPalette, BlockId, BlockId, BlockId) {
+        let mut palette = BlockPalette::new(TextureAtlas::new(4, 4));
+        let stone = palette.register(BlockDefinition::new("stone", 1));
+        let dirt = palette.register(BlockDefinition::new("dirt", 2));
+        let glass = palette.register(BlockDefinition::new("glass", 3).transparent());
+        (palette, stone, dirt, glass)
+    }
+
+    /// Returns the number of quads in a mesh, checking that every triangle
+    /// is wound counter-clockwise seen from the side its normal points to.
+    fn quads(mesh: &MeshBuilder) -> usize {
+        for triangle in mesh.indices().chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices()[triangle[i] as usize]);
+            let facing = (b.position - a.position).cross(c.position - a.position);
+            assert!(facing.dot(a.normal) > 0.0, "{:?}", triangle);
+        }
+        assert_eq!(mesh.vertex_count(), mesh.triangle_count() * 2);
+        mesh.triangle_count() / 2
+    }
+
+    #[test]
+    fn single_block_has_six_outward_faces() {
+        let (palette, stone, _, _) = palette();
+        let mut world = VoxelWorld::new();
+        world.set_block((3, 4, 5), stone);
+        let mesh = mesh_chunk(&world, &palette, (0, 0, 0));
+        assert_eq!(quads(&mesh), 6);
+        let min = vec3(3.0, 4.0, 5.0);
+        for vertex in mesh.vertices() {
+            let offset = vertex.position - min;
+            assert!([offset.x, offset.y, offset.z]
+                .iter()
+                .all(|&axis| axis == 0.0 || axis == 1.0));
+        }
+    }
+
+    #[test]
+    fn flat_layer_merges_into_one_quad_per_side() {
+        let (palette, stone, _, _) = palette();
+        let mut world = VoxelWorld::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                world.set_block((x, 0, z), stone);
+            }
+        }
+        let mesh = mesh_chunk(&world, &palette, (0, 0, 0));
+        assert_eq!(quads(&mesh), 6);
+        let top: Vec<&MeshVertex> = mesh
+            .vertices()
+            .iter()
+            .filter(|vertex| vertex.normal == vec3(0.0, 1.0, 0.0))
+            .collect();
+        assert_eq!(top.len(), 4);
+        assert!(top.iter().all(|vertex| vertex.position.y == 1.0));
+        assert!(top
+            .iter()
+            .any(|vertex| vertex.position == vec3(16.0, 1.0, 16.0)));
+    }
+
+    #[test]
+    fn different_blocks_do_not_merge_and_hide_shared_faces() {
+        let (palette, stone, dirt, _) = palette();
+        let mut world = VoxelWorld::new();
+        world.set_block((0, 0, 0), stone);
+        world.set_block((1, 0, 0), stone);
+        assert_eq!(quads(&mesh_chunk(&world, &palette, (0, 0, 0))), 6);
+
+        world.set_block((1, 0, 0), dirt);
+        // Five faces each; the shared one is hidden by both.
+        assert_eq!(quads(&mesh_chunk(&world, &palette, (0, 0, 0))), 10);
+    }
+
+    #[test]
+    fn transparent_blocks_show_the_faces_behind_them() {
+        let (palette, stone, _, glass) = palette();
+        let mut world = VoxelWorld::new();
+        world.set_block((0, 0, 0), stone);
+        world.set_block((1, 0, 0), glass);
+        world.set_block((2, 0, 0), glass);
+        // Stone keeps all six faces; the glass pair merges into five quads,
+        // without the faces between its blocks or against the stone.
+        assert_eq!(quads(&mesh_chunk(&world, &palette, (0, 0, 0))), 11);
+    }
+
+    #[test]
+    fn faces_against_the_next_chunk_are_hidden() {
+        let (palette, stone, _, _) = palette();
+        let mut world = VoxelWorld::new();
+        world.set_block((CHUNK_SIZE - 1, 0, 0), stone);
+        world.set_block((CHUNK_SIZE, 0, 0), stone);
+        assert_eq!(quads(&mesh_chunk(&world, &palette, (0, 0, 0))), 5);
+        assert_eq!(quads(&mesh_chunk(&world, &palette, (1, 0, 0))), 5);
+        assert!(mesh_chunk(&world, &palette, (2, 0, 0)).is_empty());
+    }
+}
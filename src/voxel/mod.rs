@@ -0,0 +1,9 @@
+pub mod chunk;
+pub mod meshing;
+pub mod palette;
+pub mod raycast;
+
+pub use chunk::{block_at, chunk_origin, BlockPos, Chunk, ChunkPos, VoxelWorld, CHUNK_SIZE};
+pub use meshing::mesh_chunk;
+pub use palette::{BlockDefinition, BlockId, BlockPalette, Face, TextureAtlas, AIR};
+pub use raycast::{raycast, VoxelHit};
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use crate::math::Rect;
+
+/// Index of a block kind in a [`BlockPalette`].
+pub type BlockId = u16;
+
+/// The empty block. Always registered as id zero.
+pub const AIR: BlockId = 0;
+
+/// One of the six sides of a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Face {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl Face {
+    /// Every face, in index order.
+    pub const ALL: [Face; 6] = [
+        Face::PosX,
+        Face::NegX,
+        Face::PosY,
+        Face::NegY,
+        Face::PosZ,
+        Face::NegZ,
+    ];
+
+    /// Returns the face's position in [`Face::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the outward direction as a block offset.
+    pub fn normal(self) -> (i32, i32, i32) {
+        match self {
+            Face::PosX => (1, 0, 0),
+            Face::NegX => (-1, 0, 0),
+            Face::PosY => (0, 1, 0),
+            Face::NegY => (0, -1, 0),
+            Face::PosZ => (0, 0, 1),
+            Face::NegZ => (0, 0, -1),
+        }
+    }
+
+    /// Returns the face pointing along an axis (`0` for X, `1` for Y, `2` for Z).
+    pub fn from_axis(axis: usize, positive: bool) -> Self {
+        match (axis, positive) {
+            (0, true) => Face::PosX,
+            (0, false) => Face::NegX,
+            (1, true) => Face::PosY,
+            (1, false) => Face::NegY,
+            (2, true) => Face::PosZ,
+            _ => Face::NegZ,
+        }
+    }
+}
+
+/// # Texture Atlas
+///
+/// A texture split into a grid of equally sized tiles, numbered left to right
+/// and top to bottom.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureAtlas {
+    pub columns: u32,
+    pub rows: u32,
+    /// UV inset on each side of a tile, so filtering doesn't bleed in from
+    /// neighbouring tiles.
+    pub inset: f32,
+}
+
+impl TextureAtlas {
+    /// Creates an atlas with a number of columns and rows.
+    pub fn new(columns: u32, rows: u32) -> Self {
+        Self {
+            columns: columns.max(1),
+            rows: rows.max(1),
+            inset: 0.0,
+        }
+    }
+
+    /// Sets the UV inset on each side of a tile.
+    pub fn with_inset(mut self, inset: f32) -> Self {
+        self.inset = inset;
+        self
+    }
+
+    /// Returns a tile's rectangle in UV space.
+    pub fn tile(&self, index: u32) -> Rect {
+        let (width, height) = (1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let (column, row) = (index % self.columns, index / self.columns);
+        Rect::new(
+            column as f32 * width + self.inset,
+            row as f32 * height + self.inset,
+            width - self.inset * 2.0,
+            height - self.inset * 2.0,
+        )
+    }
+}
+
+/// Everything the voxel code knows about a kind of block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockDefinition {
+    pub name: String,
+    /// Atlas tile for each face, indexed by [`Face::index`].
+    pub faces: [u32; 6],
+    /// Whether raycasts hit the block.
+    pub solid: bool,
+    /// Whether the block hides the faces of its neighbours. Glass and leaves
+    /// are solid but not opaque.
+    pub opaque: bool,
+}
+
+impl BlockDefinition {
+    /// Creates a solid, opaque block with the same tile on every face.
+    pub fn new(name: &str, tile: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            faces: [tile; 6],
+            solid: true,
+            opaque: true,
+        }
+    }
+
+    /// Sets the tile of one face.
+    pub fn with_face(mut self, face: Face, tile: u32) -> Self {
+        self.faces[face.index()] = tile;
+        self
+    }
+
+    /// Sets the top tile, like grass on a dirt block.
+    pub fn with_top(self, tile: u32) -> Self {
+        self.with_face(Face::PosY, tile)
+    }
+
+    /// Sets the bottom tile.
+    pub fn with_bottom(self, tile: u32) -> Self {
+        self.with_face(Face::NegY, tile)
+    }
+
+    /// Sets the four side tiles.
+    pub fn with_sides(self, tile: u32) -> Self {
+        self.with_face(Face::PosX, tile)
+            .with_face(Face::NegX, tile)
+            .with_face(Face::PosZ, tile)
+            .with_face(Face::NegZ, tile)
+    }
+
+    /// Lets neighbouring faces show through the block.
+    pub fn transparent(mut self) -> Self {
+        self.opaque = false;
+        self
+    }
+
+    /// Makes raycasts pass through the block, e.g. for tall grass.
+    pub fn non_solid(mut self) -> Self {
+        self.solid = false;
+        self
+    }
+}
+
+/// # Block Palette
+///
+/// The kinds of block a voxel world can contain, with the atlas their faces
+/// are drawn from. Chunks store only [`BlockId`]s into the palette.
+///
+/// ## Example
+/// ```ignore
+/// let mut palette = BlockPalette::new(TextureAtlas::new(16, 16));
+/// let stone = palette.register(BlockDefinition::new("stone", 1));
+/// let grass = palette.register(BlockDefinition::new("grass", 3).with_top(0).with_bottom(2));
+/// let glass = palette.register(BlockDefinition::new("glass", 49).transparent());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockPalette {
+    pub atlas: TextureAtlas,
+    blocks: Vec<BlockDefinition>,
+    names: HashMap<String, BlockId>,
+}
+
+impl BlockPalette {
+    /// Creates a palette holding only air.
+    pub fn new(atlas: TextureAtlas) -> Self {
+        let air = BlockDefinition {
+            name: "air".to_string(),
+            faces: [0; 6],
+            solid: false,
+            opaque: false,
+        };
+        Self {
+            atlas,
+            names: HashMap::from([(air.name.clone(), AIR)]),
+            blocks: vec![air],
+        }
+    }
+
+    /// Adds a block kind and returns its id. Registering a name again replaces
+    /// the definition and keeps the id.
+    pub fn register(&mut self, block: BlockDefinition) -> BlockId {
+        if let Some(&id) = self.names.get(&block.name) {
+            self.blocks[id as usize] = block;
+            return id;
+        }
+        let id = self.blocks.len() as BlockId;
+        self.names.insert(block.name.clone(), id);
+        self.blocks.push(block);
+        id
+    }
+
+    /// Returns a block kind.
+    pub fn get(&self, id: BlockId) -> Option<&BlockDefinition> {
+        self.blocks.get(id as usize)
+    }
+
+    /// Returns the id of a named block kind.
+    pub fn id(&self, name: &str) -> Option<BlockId> {
+        self.names.get(name).copied()
+    }
+
+    /// Checks if a block hides its neighbours' faces. Unknown ids don't.
+    pub fn is_opaque(&self, id: BlockId) -> bool {
+        self.get(id).is_some_and(|block| block.opaque)
+    }
+
+    /// Checks if raycasts hit a block. Unknown ids are not solid.
+    pub fn is_solid(&self, id: BlockId) -> bool {
+        self.get(id).is_some_and(|block| block.solid)
+    }
+
+    /// Returns the atlas rectangle for a face of a block.
+    pub fn face_tile(&self, id: BlockId, face: Face) -> Rect {
+        let tile = self.get(id).map_or(0, |block| block.faces[face.index()]);
+        self.atlas.tile(tile)
+    }
+
+    /// Returns the number of block kinds, including air.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Always `false`, since air is always registered.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
@@ -0,0 +1,192 @@
+use super::chunk::{block_at, BlockPos, VoxelWorld};
+use super::palette::{BlockId, BlockPalette, Face, AIR};
+use crate::math::*;
+
+/// A block found by [`raycast`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoxelHit {
+    pub block: BlockPos,
+    pub id: BlockId,
+    /// The face the ray entered through.
+    pub face: Face,
+    /// Distance along the ray to the hit.
+    pub distance: f32,
+    pub point: Vec3,
+}
+
+impl VoxelHit {
+    /// Returns the block in front of the hit face, where a new block would be placed.
+    pub fn adjacent(&self) -> BlockPos {
+        let (x, y, z) = self.block;
+        let (dx, dy, dz) = self.face.normal();
+        (x + dx, y + dy, z + dz)
+    }
+}
+
+/// Walks a ray through the grid block by block and returns the first solid
+/// block within `max_distance`. A ray starting inside a solid block hits it
+/// with the face it would leave through.
+pub fn raycast(
+    world: &VoxelWorld,
+    palette: &BlockPalette,
+    ray: &Ray,
+    max_distance: f32,
+) -> Option<VoxelHit> {
+    let mut block = block_at(ray.origin);
+    let direction = ray.direction;
+    let mut current = [block.0, block.1, block.2];
+    let mut step = [0; 3];
+    let mut next = [f32::INFINITY; 3];
+    let mut delta = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        if direction[axis] > 0.0 {
+            step[axis] = 1;
+            delta[axis] = 1.0 / direction[axis];
+            next[axis] = (current[axis] as f32 + 1.0 - ray.origin[axis]) * delta[axis];
+        } else if direction[axis] < 0.0 {
+            step[axis] = -1;
+            delta[axis] = -1.0 / direction[axis];
+            next[axis] = (ray.origin[axis] - current[axis] as f32) * delta[axis];
+        }
+    }
+
+    // Before the first step, report the face the ray is heading out of.
+    let first_axis = (0..3)
+        .min_by(|a, b| next[*a].total_cmp(&next[*b]))
+        .unwrap_or(0);
+    let mut face = Face::from_axis(first_axis, step[first_axis] > 0);
+    let mut distance = 0.0;
+
+    while distance <= max_distance {
+        let id = world.get_block(block);
+        if id != AIR && palette.is_solid(id) {
+            return Some(VoxelHit {
+                block,
+                id,
+                face,
+                distance,
+                point: ray.at(distance),
+            });
+        }
+
+        let axis = (0..3)
+            .min_by(|a, b| next[*a].total_cmp(&next[*b]))
+            .unwrap_or(0);
+        if step[axis] == 0 {
+            return None;
+        }
+        distance = next[axis];
+        next[axis] += delta[axis];
+        current[axis] += step[axis];
+        block = (current[0], current[1], current[2]);
+        face = Face::from_axis(axis, step[axis] < 0);
+    }
+    None
+}
+
+impl VoxelWorld {
+    /// Places a block against the face a ray hit. Returns `false` if something
+    /// solid is already there.
+    pub fn place_block(&mut self, palette: &BlockPalette, hit: &VoxelHit, block: BlockId) -> bool {
+        let position = hit.adjacent();
+        if palette.is_solid(self.get_block(position)) {
+            return false;
+        }
+        self.set_block(position, block);
+        true
+    }
+
+    /// Replaces the hit block with air and returns what was there.
+    pub fn break_block(&mut self, hit: &VoxelHit) -> BlockId {
+        self.set_block(hit.block, AIR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::palette::{BlockDefinition, TextureAtlas};
+
+    fn setup() -> (VoxelWorld, BlockPalette, BlockId, BlockId) {
+        let mut palette = BlockPalette::new(TextureAtlas::new(4, 4));
+        let stone = palette.register(BlockDefinition::new("stone", 1));
+        let water = palette.register(BlockDefinition::new("water", 2).non_solid());
+        (VoxelWorld::new(), palette, stone, water)
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn ray_hits_the_face_it_enters_through() {
+        let (mut world, palette, stone, _) = setup();
+        world.set_block((5, 0, 0), stone);
+        let ray = Ray::new(vec3(0.5, 0.5, 0.5), vec3(1.0, 0.0, 0.0));
+        let hit = raycast(&world, &palette, &ray, 10.0).unwrap();
+        assert_eq!(hit.block, (5, 0, 0));
+        assert_eq!(hit.id, stone);
+        assert_eq!(hit.face, Face::NegX);
+        assert_close(hit.distance, 4.5);
+        assert_eq!(hit.adjacent(), (4, 0, 0));
+        assert_eq!(raycast(&world, &palette, &ray, 4.0), None);
+    }
+
+    #[test]
+    fn ray_walks_negative_and_diagonal_directions() {
+        let (mut world, palette, stone, _) = setup();
+        world.set_block((-4, 0, 0), stone);
+        let ray = Ray::new(vec3(-0.5, 0.5, 0.5), vec3(-1.0, 0.0, 0.0));
+        let hit = raycast(&world, &palette, &ray, 10.0).unwrap();
+        assert_eq!((hit.block, hit.face), ((-4, 0, 0), Face::PosX));
+        assert_close(hit.distance, 2.5);
+
+        // Down onto the top of a block three across and three over.
+        world.set_block((3, 0, 3), stone);
+        let ray = Ray::new(vec3(0.5, 3.5, 0.5), vec3(1.0, -0.8, 1.0));
+        let hit = raycast(&world, &palette, &ray, 20.0).unwrap();
+        assert_eq!((hit.block, hit.face), ((3, 0, 3), Face::PosY));
+        assert_close(hit.point.y, 1.0);
+        assert!(hit.point.x >= 3.0 && hit.point.x <= 4.0);
+        assert!(hit.point.z >= 3.0 && hit.point.z <= 4.0);
+    }
+
+    #[test]
+    fn ray_passes_through_non_solid_blocks() {
+        let (mut world, palette, stone, water) = setup();
+        for y in 1..4 {
+            world.set_block((0, y, 0), water);
+        }
+        world.set_block((0, 0, 0), stone);
+        let ray = Ray::new(vec3(0.5, 5.5, 0.5), vec3(0.0, -1.0, 0.0));
+        let hit = raycast(&world, &palette, &ray, 10.0).unwrap();
+        assert_eq!((hit.block, hit.face), ((0, 0, 0), Face::PosY));
+        assert_close(hit.distance, 4.5);
+    }
+
+    #[test]
+    fn ray_starting_inside_a_block_hits_it() {
+        let (mut world, palette, stone, _) = setup();
+        world.set_block((0, 0, 0), stone);
+        let ray = Ray::new(vec3(0.5, 0.2, 0.5), vec3(0.0, 1.0, 0.0));
+        let hit = raycast(&world, &palette, &ray, 10.0).unwrap();
+        assert_eq!((hit.block, hit.face), ((0, 0, 0), Face::PosY));
+        assert_eq!(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn blocks_are_placed_against_and_broken_at_hits() {
+        let (mut world, palette, stone, _) = setup();
+        world.set_block((0, 0, 0), stone);
+        let ray = Ray::new(vec3(0.5, 5.5, 0.5), vec3(0.0, -1.0, 0.0));
+        let hit = raycast(&world, &palette, &ray, 10.0).unwrap();
+        assert!(world.place_block(&palette, &hit, stone));
+        assert_eq!(world.get_block((0, 1, 0)), stone);
+        assert!(!world.place_block(&palette, &hit, stone));
+
+        let hit = raycast(&world, &palette, &ray, 10.0).unwrap();
+        assert_eq!(hit.block, (0, 1, 0));
+        assert_eq!(world.break_block(&hit), stone);
+        assert_eq!(world.get_block((0, 1, 0)), AIR);
+    }
+}